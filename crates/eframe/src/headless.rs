@@ -0,0 +1,1058 @@
+//! Run an [`App`] without a window or graphics context.
+//!
+//! See [`HeadlessRunner`].
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use egui::{
+    DeferredViewportUiCallback, FullOutput, ImmediateViewport, Pos2, RawInput, Rect, Vec2,
+    ViewportCommand, ViewportEvent, ViewportId, ViewportIdMap, ViewportInfo, ViewportOutput,
+    ViewportSimulator, epaint::textures::TexturesDelta,
+};
+
+use crate::{App, AppCreator, CreationContext, Frame, Storage};
+
+/// Same key as the native integration, so the two can share a storage.
+#[cfg(feature = "persistence")]
+const STORAGE_EGUI_MEMORY_KEY: &str = "egui";
+
+/// How time advances in a [`HeadlessRunner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeadlessClock {
+    /// Each frame advances the time by the given duration.
+    FixedTimestep(Duration),
+
+    /// The time only advances when calling [`HeadlessRunner::advance_time`].
+    Manual,
+}
+
+impl Default for HeadlessClock {
+    /// A fixed timestep of 60 frames per second.
+    fn default() -> Self {
+        Self::FixedTimestep(Duration::from_secs(1) / 60)
+    }
+}
+
+/// Options controlling the behavior of a [`HeadlessRunner`].
+#[derive(Clone, Debug)]
+pub struct HeadlessOptions {
+    /// The initial size of the root viewport, in points.
+    ///
+    /// Default: `800x600`.
+    pub inner_size: Vec2,
+
+    /// The native pixels per point of the simulated screen.
+    ///
+    /// Default: `1.0`.
+    pub pixels_per_point: f32,
+
+    /// How time advances from frame to frame.
+    ///
+    /// Default: a fixed timestep of 60 frames per second.
+    pub clock: HeadlessClock,
+
+    /// If `true`, child viewports are embedded in the root viewport (see [`egui::Context::embed_viewports`]).
+    ///
+    /// If `false`, each child viewport runs as if it were a separate window.
+    ///
+    /// Default: `true`.
+    pub embed_viewports: bool,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            inner_size: Vec2::new(800.0, 600.0),
+            pixels_per_point: 1.0,
+            clock: HeadlessClock::default(),
+            embed_viewports: true,
+        }
+    }
+}
+
+/// A [`Storage`] that keeps everything in memory.
+///
+/// Useful for giving each headless run its own, isolated storage.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    values: std::collections::HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_owned(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+/// A child viewport, simulated as if it were a separate window.
+struct ChildViewport {
+    viewport_ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    info: ViewportInfo,
+
+    /// The events for the next pass.
+    events: Vec<egui::Event>,
+
+    /// The output of the last pass, `None` until the viewport has run.
+    output: Option<FullOutput>,
+}
+
+/// The immediate viewports that ran during a pass.
+type RanImmediateViewports = Rc<RefCell<Vec<(ViewportId, FullOutput)>>>;
+
+/// See [`HeadlessRunner::set_screenshot_renderer`].
+type ScreenshotRenderer<'app> = Box<
+    dyn 'app + FnMut(&egui::Context, &FullOutput, ViewportId) -> Result<egui::ColorImage, String>,
+>;
+
+/// Runs an [`App`] without any windowing system or graphics context.
+///
+/// This is meant for whole-application integration tests, screenshot jobs and servers
+/// running in containers.
+/// Instead of waiting for window events, frames are run when you call [`Self::step`],
+/// with the time advancing according to [`HeadlessOptions::clock`].
+///
+/// The [`Frame`] given to [`App::update`] has no graphics context nor window handles,
+/// but [`Frame::storage`] works as usual.
+/// With the `persistence` feature, [`App::save`] is called on [`Self::shutdown`]
+/// and every [`App::auto_save_interval`] of simulated time,
+/// and the egui memory is persisted (see [`App::persist_egui_memory`]).
+///
+/// Nothing is rendered: use [`Self::tessellate`] and [`Self::take_textures_delta`]
+/// to paint the output with your renderer of choice.
+/// For the same reason, [`ViewportCommand::Screenshot`] is answered with an empty image,
+/// unless you render it with [`Self::set_screenshot_renderer`].
+///
+/// ```
+/// # struct MyApp;
+/// # impl eframe::App for MyApp {
+/// #     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+/// # }
+/// let mut runner = eframe::HeadlessRunner::new(
+///     eframe::HeadlessOptions::default(),
+///     Some(Box::new(eframe::MemoryStorage::default())),
+///     Box::new(|_cc| Ok(Box::new(MyApp))),
+/// )?;
+/// runner.run_frames(10);
+/// let storage = runner.shutdown();
+/// # Ok::<(), eframe::Error>(())
+/// ```
+pub struct HeadlessRunner<'app> {
+    app: Box<dyn 'app + App>,
+    frame: Frame,
+    egui_ctx: egui::Context,
+    options: HeadlessOptions,
+
+    /// The simulated time, in seconds.
+    time: f64,
+
+    /// The time of the last frame, `None` before the first frame.
+    last_frame_time: Option<f64>,
+    last_auto_save: f64,
+
+    /// The input for the next pass of the root viewport.
+    input: RawInput,
+    root_info: ViewportInfo,
+    output: FullOutput,
+    textures_delta: TexturesDelta,
+    viewports: ViewportIdMap<ChildViewport>,
+
+    /// The immediate viewports that ran during the last pass.
+    ran_immediate: RanImmediateViewports,
+
+    /// The [`ViewportCommand::Screenshot`]s sent during the last frame.
+    screenshot_requests: Vec<(ViewportId, egui::UserData)>,
+    screenshot_renderer: Option<ScreenshotRenderer<'app>>,
+
+    /// When set, the app wants to close.
+    close: bool,
+}
+
+impl<'app> HeadlessRunner<'app> {
+    /// Create the app, without running any frame.
+    ///
+    /// The `storage` is given to the app through [`CreationContext::storage`] and [`Frame::storage`].
+    ///
+    /// # Errors
+    /// Returns [`crate::Error::AppCreation`] if the app creator fails.
+    pub fn new(
+        options: HeadlessOptions,
+        storage: Option<Box<dyn Storage>>,
+        app_creator: AppCreator<'app>,
+    ) -> crate::Result<Self> {
+        let egui_ctx = egui::Context::default();
+        egui_ctx.set_embed_viewports(options.embed_viewports);
+
+        #[cfg(feature = "persistence")]
+        if let Some(memory) = storage
+            .as_deref()
+            .and_then(|storage| crate::get_value(storage, STORAGE_EGUI_MEMORY_KEY))
+        {
+            egui_ctx.memory_mut(|mem| *mem = memory);
+        }
+
+        let app = {
+            let cc = CreationContext {
+                storage: storage.as_deref(),
+                ..CreationContext::_new_kittest(egui_ctx.clone())
+            };
+            app_creator(&cc).map_err(crate::Error::AppCreation)?
+        };
+
+        let rect = Rect::from_min_size(Pos2::ZERO, options.inner_size);
+        let root_info = ViewportInfo {
+            native_pixels_per_point: Some(options.pixels_per_point),
+            inner_rect: Some(rect),
+            outer_rect: Some(rect),
+            focused: Some(true),
+            ..Default::default()
+        };
+
+        Ok(Self {
+            app,
+            frame: Frame {
+                storage,
+                ..Frame::_new_kittest()
+            },
+            egui_ctx,
+            options,
+            time: 0.0,
+            last_frame_time: None,
+            last_auto_save: 0.0,
+            input: RawInput::default(),
+            root_info,
+            output: FullOutput::default(),
+            textures_delta: TexturesDelta::default(),
+            viewports: ViewportIdMap::default(),
+            ran_immediate: RanImmediateViewports::default(),
+            screenshot_requests: Vec::new(),
+            screenshot_renderer: None,
+            close: false,
+        })
+    }
+
+    /// The egui context of the app.
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.egui_ctx
+    }
+
+    /// The app being run.
+    pub fn app(&self) -> &dyn App {
+        &*self.app
+    }
+
+    /// The app being run.
+    pub fn app_mut(&mut self) -> &mut dyn App {
+        &mut *self.app
+    }
+
+    /// The [`Frame`] passed to [`App::update`], e.g. to access its [`Frame::storage`].
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// The [`Frame`] passed to [`App::update`], e.g. to access its [`Frame::storage_mut`].
+    pub fn frame_mut(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    /// The simulated time, in seconds.
+    ///
+    /// This is what the app sees in [`egui::InputState::time`].
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Advance the simulated time.
+    ///
+    /// This is the only way time advances with [`HeadlessClock::Manual`].
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.time += duration.as_secs_f64();
+    }
+
+    /// The input for the next frame of the root viewport.
+    ///
+    /// Use this to simulate user input, e.g. to add [`egui::Event`]s or set the modifiers.
+    pub fn input_mut(&mut self) -> &mut RawInput {
+        &mut self.input
+    }
+
+    /// Add an event to the input of the next frame of the root viewport.
+    pub fn push_event(&mut self, event: egui::Event) {
+        self.input.events.push(event);
+    }
+
+    /// Render the screenshots requested with [`ViewportCommand::Screenshot`],
+    /// e.g. with the same renderer you use to paint the output of [`Self::tessellate`].
+    ///
+    /// The renderer gets the output of the viewport that requested the screenshot,
+    /// and the image is sent back in an [`egui::Event::Screenshot`] in the next frame.
+    /// Without a renderer, or if it fails, the image is empty.
+    pub fn set_screenshot_renderer(
+        &mut self,
+        renderer: impl 'app
+        + FnMut(
+            &egui::Context,
+            &FullOutput,
+            ViewportId,
+        ) -> Result<egui::ColorImage, String>,
+    ) {
+        self.screenshot_renderer = Some(Box::new(renderer));
+    }
+
+    /// Resize the root viewport, as if the user resized the window.
+    pub fn set_inner_size(&mut self, size: Vec2) {
+        self.root_info.set_simulated_inner_size(size);
+    }
+
+    /// Request the viewport to close, as if the user clicked the close button of the window.
+    ///
+    /// The app sees this in [`ViewportInfo::close_requested`] in the next frame,
+    /// and can cancel it with [`ViewportCommand::CancelClose`].
+    pub fn close_viewport(&mut self, viewport_id: ViewportId) {
+        if let Some(info) = self.viewport_info_mut(viewport_id) {
+            info.events.push(ViewportEvent::Close);
+        }
+    }
+
+    /// `true` once the root viewport was closed without the app canceling it.
+    ///
+    /// This is when a native app would exit.
+    pub fn should_close(&self) -> bool {
+        self.close
+    }
+
+    /// The output of the last frame of the root viewport.
+    pub fn output(&self) -> &FullOutput {
+        &self.output
+    }
+
+    /// The ids of all viewports, starting with [`ViewportId::ROOT`].
+    ///
+    /// Child viewports are only listed if [`HeadlessOptions::embed_viewports`] is `false`,
+    /// once they have run.
+    pub fn viewport_ids(&self) -> Vec<ViewportId> {
+        std::iter::once(ViewportId::ROOT)
+            .chain(
+                self.viewports
+                    .iter()
+                    .filter(|(_, viewport)| viewport.output.is_some())
+                    .map(|(id, _)| *id),
+            )
+            .collect()
+    }
+
+    /// The output of the last pass of the viewport.
+    pub fn viewport_output(&self, viewport_id: ViewportId) -> Option<&FullOutput> {
+        if viewport_id == ViewportId::ROOT {
+            Some(&self.output)
+        } else {
+            self.viewports.get(&viewport_id)?.output.as_ref()
+        }
+    }
+
+    /// The [`ViewportInfo`] that will be passed to the next pass of the viewport,
+    /// e.g. its size and title.
+    pub fn viewport_info(&self, viewport_id: ViewportId) -> Option<&ViewportInfo> {
+        if viewport_id == ViewportId::ROOT {
+            Some(&self.root_info)
+        } else {
+            Some(&self.viewports.get(&viewport_id)?.info)
+        }
+    }
+
+    /// Take all texture changes since the last call.
+    ///
+    /// Apply these to your renderer before painting the result of [`Self::tessellate`].
+    pub fn take_textures_delta(&mut self) -> TexturesDelta {
+        std::mem::take(&mut self.textures_delta)
+    }
+
+    /// Tessellate the shapes of the last frame of the root viewport.
+    pub fn tessellate(&self) -> Vec<egui::ClippedPrimitive> {
+        self.egui_ctx
+            .tessellate(self.output.shapes.clone(), self.output.pixels_per_point)
+    }
+
+    /// Run a single frame of the app, including all deferred child viewports.
+    ///
+    /// With [`HeadlessClock::FixedTimestep`], the time advances by the timestep before each frame
+    /// (except the first one).
+    pub fn step(&mut self) -> &FullOutput {
+        profiling::function_scope!();
+
+        if let (Some(_), HeadlessClock::FixedTimestep(dt)) =
+            (self.last_frame_time, self.options.clock)
+        {
+            self.advance_time(dt);
+        }
+
+        let mut raw_input = self.take_viewport_input(ViewportId::ROOT);
+        let close_requested = raw_input.viewport().close_requested();
+        self.app.raw_input_hook(&self.egui_ctx, &mut raw_input);
+
+        self.set_immediate_viewport_renderer();
+        let start = Instant::now();
+        let mut output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            profiling::scope!("App::update");
+            self.app.update(egui_ctx, &mut self.frame);
+        });
+        self.frame.info.cpu_usage = Some(start.elapsed().as_secs_f32());
+        self.last_frame_time = Some(self.time);
+
+        if close_requested {
+            let canceled = output
+                .viewport_output
+                .get(&ViewportId::ROOT)
+                .is_some_and(|output| output.commands.contains(&ViewportCommand::CancelClose));
+            if canceled {
+                log::debug!("Closing of root viewport canceled with ViewportCommand::CancelClose");
+            } else {
+                self.close = true;
+            }
+        }
+
+        self.textures_delta
+            .append(std::mem::take(&mut output.textures_delta));
+        let viewport_output = std::mem::take(&mut output.viewport_output);
+        self.handle_viewport_output(&viewport_output);
+        self.handle_immediate_viewports();
+        output.viewport_output = viewport_output;
+        self.output = output;
+
+        self.step_deferred_viewports();
+        self.answer_screenshot_requests();
+        self.maybe_autosave();
+
+        &self.output
+    }
+
+    /// Run the given number of frames.
+    pub fn run_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Run frames until the app no longer requests a repaint, or wants to close.
+    ///
+    /// Returns the number of frames that were run, at most `max_frames`.
+    pub fn run_until_idle(&mut self, max_frames: usize) -> usize {
+        for frame in 1..=max_frames {
+            self.step();
+            if self.close || !self.needs_repaint() {
+                return frame;
+            }
+        }
+        max_frames
+    }
+
+    /// Does any viewport want to be repainted right away?
+    fn needs_repaint(&self) -> bool {
+        std::iter::once(&self.output)
+            .chain(
+                self.viewports
+                    .values()
+                    .filter_map(|viewport| viewport.output.as_ref()),
+            )
+            .flat_map(|output| output.viewport_output.values())
+            .any(|output| output.repaint_delay.is_zero())
+    }
+
+    /// Call [`App::save`] and persist the egui memory, then flush the storage.
+    ///
+    /// Does nothing without a storage or without the `persistence` feature.
+    #[allow(
+        clippy::unused_self,
+        clippy::needless_pass_by_ref_mut,
+        clippy::allow_attributes
+    )]
+    pub fn save(&mut self) {
+        #[cfg(feature = "persistence")]
+        if let Some(storage) = self.frame.storage_mut() {
+            profiling::function_scope!();
+
+            if self.app.persist_egui_memory() {
+                self.egui_ctx
+                    .memory(|mem| crate::set_value(storage, STORAGE_EGUI_MEMORY_KEY, mem));
+            }
+            self.app.save(storage);
+            storage.flush();
+        }
+    }
+
+    /// Save and call [`App::on_exit`], like a native app does when it exits.
+    ///
+    /// Returns the storage, e.g. to restart the app with it.
+    pub fn shutdown(mut self) -> Option<Box<dyn Storage>> {
+        self.save();
+
+        #[cfg(feature = "glow")]
+        self.app.on_exit(None);
+        #[cfg(not(feature = "glow"))]
+        self.app.on_exit();
+
+        self.frame.storage.take()
+    }
+
+    fn maybe_autosave(&mut self) {
+        if self.time - self.last_auto_save >= self.app.auto_save_interval().as_secs_f64() {
+            self.save();
+            self.last_auto_save = self.time;
+        }
+    }
+
+    fn viewport_info_mut(&mut self, viewport_id: ViewportId) -> Option<&mut ViewportInfo> {
+        if viewport_id == ViewportId::ROOT {
+            Some(&mut self.root_info)
+        } else {
+            Some(&mut self.viewports.get_mut(&viewport_id)?.info)
+        }
+    }
+
+    /// The [`ViewportInfo`] of all viewports, without their events.
+    fn viewport_infos(&self) -> ViewportIdMap<ViewportInfo> {
+        std::iter::once((ViewportId::ROOT, &self.root_info))
+            .chain(
+                self.viewports
+                    .iter()
+                    .map(|(id, viewport)| (*id, &viewport.info)),
+            )
+            .map(|(id, info)| {
+                let mut info = info.clone();
+                info.events.clear();
+                (id, info)
+            })
+            .collect()
+    }
+
+    fn predicted_dt(&self) -> f32 {
+        match self.options.clock {
+            HeadlessClock::FixedTimestep(dt) => dt.as_secs_f32(),
+            HeadlessClock::Manual => self
+                .last_frame_time
+                .map(|last| (self.time - last) as f32)
+                .filter(|dt| *dt > 0.0)
+                .unwrap_or(1.0 / 60.0),
+        }
+    }
+
+    /// Take the input for the next pass of the given viewport.
+    fn take_viewport_input(&mut self, viewport_id: ViewportId) -> RawInput {
+        let mut viewports = self.viewport_infos();
+        let Some(info) = self.viewport_info_mut(viewport_id) else {
+            return RawInput::default();
+        };
+        let events = std::mem::take(&mut info.events);
+        viewports.entry(viewport_id).or_default().events = events;
+
+        let mut input = if viewport_id == ViewportId::ROOT {
+            std::mem::take(&mut self.input)
+        } else {
+            RawInput {
+                events: self
+                    .viewports
+                    .get_mut(&viewport_id)
+                    .map(|viewport| std::mem::take(&mut viewport.events))
+                    .unwrap_or_default(),
+                ..Default::default()
+            }
+        };
+        let info = &viewports[&viewport_id];
+        input.viewport_id = viewport_id;
+        input.screen_rect = info
+            .inner_rect
+            .map(|rect| Rect::from_min_size(Pos2::ZERO, rect.size()));
+        input.focused = info.focused.unwrap_or(true);
+        input.time = Some(self.time);
+        input.predicted_dt = self.predicted_dt();
+        input.viewports = viewports;
+        input
+    }
+
+    /// Install the callback running the immediate viewports shown in the next pass.
+    ///
+    /// The callback is per thread, so this needs to be called before every pass.
+    fn set_immediate_viewport_renderer(&mut self) {
+        self.ran_immediate = RanImmediateViewports::default();
+        if self.egui_ctx.embed_viewports() {
+            return;
+        }
+
+        let mut viewports = self.viewport_infos();
+        let mut events = ViewportIdMap::default();
+        for (id, viewport) in &mut self.viewports {
+            if viewport.viewport_ui_cb.is_none() {
+                viewports.insert(*id, viewport.info.clone());
+                events.insert(*id, std::mem::take(&mut viewport.events));
+            }
+        }
+        let events = RefCell::new(events);
+        let template = RawInput {
+            viewports,
+            time: Some(self.time),
+            predicted_dt: self.predicted_dt(),
+            ..Default::default()
+        };
+        let pixels_per_point = self.options.pixels_per_point;
+        let ran_immediate = Rc::downgrade(&self.ran_immediate);
+        egui::Context::set_immediate_viewport_renderer(move |ctx, viewport| {
+            let ImmediateViewport {
+                ids,
+                builder,
+                mut viewport_ui_cb,
+            } = viewport;
+
+            let mut input = template.clone();
+            for (id, info) in &mut input.viewports {
+                if *id != ids.this {
+                    info.events.clear();
+                }
+            }
+            let info = input.viewports.entry(ids.this).or_insert_with(|| {
+                ViewportInfo::simulated(ids.parent, &builder, Some(pixels_per_point))
+            });
+            input.viewport_id = ids.this;
+            input.screen_rect = info
+                .inner_rect
+                .map(|rect| Rect::from_min_size(Pos2::ZERO, rect.size()));
+            input.events = events.borrow_mut().remove(&ids.this).unwrap_or_default();
+
+            let output = ctx.run(input, |ctx| viewport_ui_cb(ctx));
+            if let Some(ran) = ran_immediate.upgrade() {
+                ran.borrow_mut().push((ids.this, output));
+            }
+        });
+    }
+
+    /// Send the screenshots requested during the last frame to the viewports that requested them.
+    fn answer_screenshot_requests(&mut self) {
+        for (viewport_id, user_data) in std::mem::take(&mut self.screenshot_requests) {
+            let output = if viewport_id == ViewportId::ROOT {
+                Some(&self.output)
+            } else {
+                self.viewports
+                    .get(&viewport_id)
+                    .and_then(|viewport| viewport.output.as_ref())
+            };
+            let image = if let (Some(renderer), Some(output)) =
+                (&mut self.screenshot_renderer, output)
+            {
+                renderer(&self.egui_ctx, output, viewport_id).unwrap_or_else(|err| {
+                    log::warn!("Failed to render the screenshot of {viewport_id:?}: {err}");
+                    Default::default()
+                })
+            } else {
+                log::warn!(
+                    "No screenshot renderer set, answering the screenshot of {viewport_id:?} with an empty image"
+                );
+                Default::default()
+            };
+            let event = egui::Event::Screenshot {
+                viewport_id,
+                user_data,
+                image: Arc::new(image),
+            };
+            if viewport_id == ViewportId::ROOT {
+                self.input.events.push(event);
+            } else if let Some(viewport) = self.viewports.get_mut(&viewport_id) {
+                viewport.events.push(event);
+            }
+        }
+    }
+
+    /// Store the outputs of the immediate viewports that ran during the last pass.
+    ///
+    /// Must be called after [`Self::handle_viewport_output`], which adds new viewports.
+    fn handle_immediate_viewports(&mut self) {
+        let ran = std::mem::take(&mut *self.ran_immediate.borrow_mut());
+        for (id, mut output) in ran {
+            self.textures_delta
+                .append(std::mem::take(&mut output.textures_delta));
+            if let Some(viewport) = self.viewports.get_mut(&id) {
+                viewport.info.events.clear();
+                viewport.output = Some(output);
+            }
+        }
+    }
+}
+
+impl ViewportSimulator for HeadlessRunner<'_> {
+    fn deferred_viewports(&self) -> Vec<(ViewportId, Arc<DeferredViewportUiCallback>)> {
+        self.viewports
+            .iter()
+            .filter_map(|(id, viewport)| Some((*id, viewport.viewport_ui_cb.clone()?)))
+            .collect()
+    }
+
+    fn contains_viewport(&self, viewport_id: ViewportId) -> bool {
+        self.viewports.contains_key(&viewport_id)
+    }
+
+    fn run_viewport(
+        &mut self,
+        viewport_id: ViewportId,
+        viewport_ui_cb: &DeferredViewportUiCallback,
+    ) -> FullOutput {
+        let mut raw_input = self.take_viewport_input(viewport_id);
+        self.app.raw_input_hook(&self.egui_ctx, &mut raw_input);
+
+        self.set_immediate_viewport_renderer();
+        let mut output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            profiling::scope!("viewport_callback");
+            viewport_ui_cb(egui_ctx);
+        });
+
+        self.textures_delta
+            .append(std::mem::take(&mut output.textures_delta));
+        output
+    }
+
+    fn handle_viewport_output(&mut self, viewport_output: &ViewportIdMap<ViewportOutput>) {
+        for (id, output) in viewport_output {
+            for command in &output.commands {
+                if let ViewportCommand::Screenshot(user_data) = command {
+                    self.screenshot_requests.push((*id, user_data.clone()));
+                }
+            }
+            if *id == ViewportId::ROOT {
+                for command in &output.commands {
+                    self.root_info.apply_simulated_command(command);
+                }
+                continue;
+            }
+            if self.egui_ctx.embed_viewports() {
+                continue;
+            }
+
+            let pixels_per_point = self.options.pixels_per_point;
+            let viewport = self.viewports.entry(*id).or_insert_with(|| ChildViewport {
+                viewport_ui_cb: None,
+                info: ViewportInfo::simulated(
+                    output.parent,
+                    &output.builder,
+                    Some(pixels_per_point),
+                ),
+                events: Vec::new(),
+                output: None,
+            });
+            viewport.viewport_ui_cb.clone_from(&output.viewport_ui_cb);
+            for command in &output.commands {
+                viewport.info.apply_simulated_command(command);
+            }
+        }
+
+        self.viewports
+            .retain(|id, _| viewport_output.contains_key(id));
+    }
+
+    fn set_viewport_output(&mut self, viewport_id: ViewportId, output: FullOutput) {
+        // Must come after `handle_viewport_output`, which adds the new immediate viewports.
+        self.handle_immediate_viewports();
+        if let Some(viewport) = self.viewports.get_mut(&viewport_id) {
+            viewport.output = Some(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Records the time of each frame and closes the root viewport when asked to.
+    #[derive(Default)]
+    struct TestApp {
+        times: Rc<RefCell<Vec<f64>>>,
+        cancel_close: bool,
+        send_close: bool,
+    }
+
+    impl App for TestApp {
+        fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+            self.times.borrow_mut().push(ctx.input(|i| i.time));
+            if self.cancel_close && ctx.input(|i| i.viewport().close_requested()) {
+                ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            }
+            if std::mem::take(&mut self.send_close) {
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+        }
+    }
+
+    fn runner(options: HeadlessOptions, app: TestApp) -> HeadlessRunner<'static> {
+        HeadlessRunner::new(options, None, Box::new(|_cc| Ok(Box::new(app)))).unwrap()
+    }
+
+    #[test]
+    fn fixed_timestep() {
+        let times = Rc::new(RefCell::new(Vec::new()));
+        let options = HeadlessOptions {
+            clock: HeadlessClock::FixedTimestep(Duration::from_millis(250)),
+            ..Default::default()
+        };
+        let mut runner = runner(
+            options,
+            TestApp {
+                times: times.clone(),
+                ..Default::default()
+            },
+        );
+
+        runner.run_frames(3);
+        assert_eq!(*times.borrow(), [0.0, 0.25, 0.5]);
+        assert_eq!(runner.time(), 0.5);
+    }
+
+    #[test]
+    fn manual_clock() {
+        let times = Rc::new(RefCell::new(Vec::new()));
+        let options = HeadlessOptions {
+            clock: HeadlessClock::Manual,
+            ..Default::default()
+        };
+        let mut runner = runner(
+            options,
+            TestApp {
+                times: times.clone(),
+                ..Default::default()
+            },
+        );
+
+        runner.step();
+        runner.step();
+        runner.advance_time(Duration::from_secs(2));
+        runner.step();
+        assert_eq!(*times.borrow(), [0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn close() {
+        let mut runner = runner(HeadlessOptions::default(), TestApp::default());
+        runner.step();
+        assert!(!runner.should_close());
+
+        runner.close_viewport(ViewportId::ROOT);
+        runner.step();
+        assert!(runner.should_close());
+
+        let mut runner = runner_that_cancels_close();
+        runner.close_viewport(ViewportId::ROOT);
+        runner.step();
+        assert!(!runner.should_close());
+    }
+
+    fn runner_that_cancels_close() -> HeadlessRunner<'static> {
+        runner(
+            HeadlessOptions::default(),
+            TestApp {
+                cancel_close: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn close_command() {
+        let mut runner = runner(
+            HeadlessOptions::default(),
+            TestApp {
+                send_close: true,
+                ..Default::default()
+            },
+        );
+        runner.step();
+        assert!(
+            !runner.should_close(),
+            "The close is handled in the next frame"
+        );
+        runner.step();
+        assert!(runner.should_close());
+    }
+
+    #[test]
+    fn screen_size() {
+        let options = HeadlessOptions {
+            inner_size: Vec2::new(300.0, 200.0),
+            pixels_per_point: 2.0,
+            ..Default::default()
+        };
+        let mut runner = runner(options, TestApp::default());
+        runner.step();
+        let ctx = runner.egui_ctx().clone();
+        assert_eq!(ctx.screen_rect().size(), Vec2::new(300.0, 200.0));
+        assert_eq!(ctx.pixels_per_point(), 2.0);
+        assert_eq!(runner.output().pixels_per_point, 2.0);
+
+        runner.set_inner_size(Vec2::new(100.0, 50.0));
+        runner.step();
+        assert_eq!(ctx.screen_rect().size(), Vec2::new(100.0, 50.0));
+        assert!(
+            !runner.take_textures_delta().set.is_empty(),
+            "The font atlas"
+        );
+    }
+
+    #[test]
+    fn deferred_viewports() {
+        struct ViewportApp {
+            child_frames: Arc<AtomicUsize>,
+        }
+
+        impl App for ViewportApp {
+            fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+                let child_frames = self.child_frames.clone();
+                ctx.show_viewport_deferred(
+                    egui::ViewportId::from_hash_of("child"),
+                    egui::ViewportBuilder::default()
+                        .with_title("Child")
+                        .with_inner_size([200.0, 100.0]),
+                    move |ctx, _class| {
+                        child_frames.fetch_add(1, Ordering::Relaxed);
+                        assert_eq!(ctx.screen_rect().size(), Vec2::new(200.0, 100.0));
+                    },
+                );
+            }
+        }
+
+        let child_frames = Arc::new(AtomicUsize::new(0));
+        let options = HeadlessOptions {
+            embed_viewports: false,
+            ..Default::default()
+        };
+        let app = ViewportApp {
+            child_frames: child_frames.clone(),
+        };
+        let mut runner =
+            HeadlessRunner::new(options, None, Box::new(|_cc| Ok(Box::new(app)))).unwrap();
+        runner.run_frames(2);
+
+        let child = egui::ViewportId::from_hash_of("child");
+        assert_eq!(child_frames.load(Ordering::Relaxed), 2);
+        assert_eq!(runner.viewport_ids(), [ViewportId::ROOT, child]);
+        assert_eq!(
+            runner.viewport_info(child).unwrap().title.as_deref(),
+            Some("Child")
+        );
+        assert!(runner.viewport_output(child).is_some());
+    }
+
+    #[test]
+    fn screenshots_are_answered() {
+        /// Takes a screenshot in every frame, and records the size of the images it gets back.
+        struct ScreenshotApp {
+            sizes: Rc<RefCell<Vec<[usize; 2]>>>,
+        }
+
+        impl App for ScreenshotApp {
+            fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+                ctx.input(|i| {
+                    for event in &i.raw.events {
+                        if let egui::Event::Screenshot { image, .. } = event {
+                            self.sizes.borrow_mut().push(image.size);
+                        }
+                    }
+                });
+                ctx.send_viewport_cmd(ViewportCommand::Screenshot(Default::default()));
+            }
+        }
+
+        let sizes = Rc::new(RefCell::new(Vec::new()));
+        let app = ScreenshotApp {
+            sizes: sizes.clone(),
+        };
+        let mut runner = HeadlessRunner::new(
+            HeadlessOptions::default(),
+            None,
+            Box::new(|_cc| Ok(Box::new(app))),
+        )
+        .unwrap();
+        runner.run_frames(2);
+        assert_eq!(*sizes.borrow(), [[0, 0]], "Nothing to render with");
+
+        runner.set_screenshot_renderer(|_ctx, output, viewport_id| {
+            assert_eq!(viewport_id, ViewportId::ROOT);
+            let size = output.shapes.len();
+            Ok(egui::ColorImage::filled([size, 1], egui::Color32::RED))
+        });
+        runner.step();
+        let shapes = runner.output().shapes.len();
+        runner.step();
+        assert_eq!(*sizes.borrow(), [[0, 0], [0, 0], [shapes, 1]]);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn persistence() {
+        struct CounterApp {
+            count: u32,
+        }
+
+        impl App for CounterApp {
+            fn update(&mut self, _ctx: &egui::Context, _frame: &mut Frame) {
+                self.count += 1;
+            }
+
+            fn save(&mut self, storage: &mut dyn Storage) {
+                crate::set_value(storage, crate::APP_KEY, &self.count);
+            }
+
+            fn auto_save_interval(&self) -> Duration {
+                Duration::from_secs(1)
+            }
+        }
+
+        fn counter_runner(storage: Option<Box<dyn Storage>>) -> HeadlessRunner<'static> {
+            let options = HeadlessOptions {
+                clock: HeadlessClock::FixedTimestep(Duration::from_millis(250)),
+                ..Default::default()
+            };
+            HeadlessRunner::new(
+                options,
+                storage,
+                Box::new(|cc| {
+                    let count = cc
+                        .storage
+                        .and_then(|storage| crate::get_value(storage, crate::APP_KEY))
+                        .unwrap_or_default();
+                    Ok(Box::new(CounterApp { count }))
+                }),
+            )
+            .unwrap()
+        }
+
+        let mut runner = counter_runner(Some(Box::new(MemoryStorage::default())));
+        runner.run_frames(4);
+        let stored = |runner: &HeadlessRunner<'_>| -> Option<u32> {
+            crate::get_value(runner.frame().storage()?, crate::APP_KEY)
+        };
+        assert_eq!(stored(&runner), None, "Not auto-saved yet");
+        runner.step();
+        assert_eq!(stored(&runner), Some(5), "Auto-saved after a second");
+
+        runner
+            .egui_ctx()
+            .memory_mut(|mem| mem.data.insert_persisted(egui::Id::new("key"), 42_u32));
+        runner.run_frames(2);
+        let storage = runner.shutdown();
+
+        let mut runner = counter_runner(storage);
+        runner.step();
+        assert_eq!(stored(&runner), Some(7));
+        assert_eq!(
+            runner
+                .egui_ctx()
+                .memory_mut(|mem| mem.data.get_persisted::<u32>(egui::Id::new("key"))),
+            Some(42)
+        );
+    }
+}
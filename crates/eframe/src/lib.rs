@@ -207,6 +207,12 @@ pub use native::file_storage::storage_dir;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod icon_data;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::{HeadlessClock, HeadlessOptions, HeadlessRunner, MemoryStorage};
+
 /// This is how you start a native (desktop) app.
 ///
 /// The first argument is name of your app, which is an identifier
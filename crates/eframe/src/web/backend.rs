@@ -14,6 +14,11 @@ pub(crate) struct WebInput {
     /// Required because we don't get a position on touched
     pub primary_touch: Option<egui::TouchId>,
 
+    /// Was the eraser of the pen used in the last pen event?
+    ///
+    /// Remembered because `buttons` is always zero when the pen is lifted.
+    pub pen_eraser: bool,
+
     /// The raw input to `egui`.
     pub raw: egui::RawInput,
 }
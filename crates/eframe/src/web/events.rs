@@ -3,8 +3,8 @@ use crate::web::string_from_js_value;
 use super::{
     AppRunner, Closure, DEBUG_RESIZE, JsCast as _, JsValue, WebRunner, button_from_mouse_event,
     location_hash, modifiers_from_kb_event, modifiers_from_mouse_event, modifiers_from_wheel_event,
-    native_pixels_per_point, pos_from_mouse_event, prefers_color_scheme, primary_touch_pos,
-    push_touches, text_from_keyboard_event, translate_key,
+    native_pixels_per_point, pen_event_from_pointer_event, pos_from_mouse_event,
+    prefers_color_scheme, primary_touch_pos, push_touches, text_from_keyboard_event, translate_key,
 };
 
 use web_sys::{Document, EventTarget, ShadowRoot};
@@ -91,6 +91,8 @@ pub(crate) fn install_event_handlers(runner_ref: &WebRunner) -> Result<(), JsVal
     install_mousemove(runner_ref, &document)?;
     install_pointerup(runner_ref, &document)?;
     install_pointerdown(runner_ref, &canvas)?;
+    install_pointermove(runner_ref, &document)?;
+    install_pointercancel(runner_ref, &document)?;
     install_mouseleave(runner_ref, &canvas)?;
 
     install_touchstart(runner_ref, &canvas)?;
@@ -512,6 +514,7 @@ fn install_pointerdown(runner_ref: &WebRunner, target: &EventTarget) -> Result<(
         |event: web_sys::PointerEvent, runner: &mut AppRunner| {
             let modifiers = modifiers_from_mouse_event(&event);
             runner.input.raw.modifiers = modifiers;
+            push_pen_event(runner, &event, egui::TouchPhase::Start);
             let mut should_stop_propagation = true;
             if let Some(button) = button_from_mouse_event(&event) {
                 let pos = pos_from_mouse_event(runner.canvas(), &event, runner.egui_ctx());
@@ -556,6 +559,8 @@ fn install_pointerup(runner_ref: &WebRunner, target: &EventTarget) -> Result<(),
                 runner,
                 egui::pos2(event.client_x() as f32, event.client_y() as f32),
             ) {
+                push_pen_event(runner, &event, egui::TouchPhase::End);
+
                 if let Some(button) = button_from_mouse_event(&event) {
                     let modifiers = runner.input.raw.modifiers;
                     let egui_event = egui::Event::PointerButton {
@@ -600,6 +605,67 @@ fn install_pointerup(runner_ref: &WebRunner, target: &EventTarget) -> Result<(),
     )
 }
 
+/// Only used for pens: mouse movements are handled by `mousemove`.
+fn install_pointermove(runner_ref: &WebRunner, target: &EventTarget) -> Result<(), JsValue> {
+    runner_ref.add_event_listener(
+        target,
+        "pointermove",
+        |event: web_sys::PointerEvent, runner| {
+            if !is_interested_in_pointer_event(
+                runner,
+                egui::pos2(event.client_x() as f32, event.client_y() as f32),
+            ) {
+                return;
+            }
+
+            if push_pen_event(runner, &event, egui::TouchPhase::Move) {
+                runner.needs_repaint.repaint_asap();
+            }
+        },
+    )
+}
+
+/// A pen gesture can be cancelled by the browser, e.g. when it starts scrolling instead.
+fn install_pointercancel(runner_ref: &WebRunner, target: &EventTarget) -> Result<(), JsValue> {
+    runner_ref.add_event_listener(
+        target,
+        "pointercancel",
+        |event: web_sys::PointerEvent, runner| {
+            if push_pen_event(runner, &event, egui::TouchPhase::Cancel) {
+                runner.needs_repaint.repaint_asap();
+            }
+        },
+    )
+}
+
+/// Push an [`egui::Event::Pen`] if the event is from a pen.
+///
+/// Returns `false` for other pointers.
+fn push_pen_event(
+    runner: &mut AppRunner,
+    event: &web_sys::PointerEvent,
+    phase: egui::TouchPhase,
+) -> bool {
+    let Some(mut pen_event) =
+        pen_event_from_pointer_event(runner.canvas(), event, runner.egui_ctx(), phase)
+    else {
+        return false;
+    };
+    if let egui::Event::Pen { state, .. } = &mut pen_event {
+        match phase {
+            // No buttons are pressed once the pen is lifted, so keep the eraser of the stroke:
+            egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                state.eraser = runner.input.pen_eraser;
+            }
+            egui::TouchPhase::Start | egui::TouchPhase::Move => {
+                runner.input.pen_eraser = state.eraser;
+            }
+        }
+    }
+    runner.input.raw.events.push(pen_event);
+    true
+}
+
 /// Returns true if the cursor is above the canvas, or if we're dragging something.
 /// Pass in the position in browser viewport coordinates (usually event.clientX/Y).
 fn is_interested_in_pointer_event(runner: &AppRunner, pos: egui::Pos2) -> bool {
@@ -24,6 +24,40 @@ pub fn button_from_mouse_event(event: &web_sys::MouseEvent) -> Option<egui::Poin
     }
 }
 
+/// Translate a pointer event into an [`egui::Event::Pen`], if it comes from a pen (stylus).
+pub fn pen_event_from_pointer_event(
+    canvas: &web_sys::HtmlCanvasElement,
+    event: &web_sys::PointerEvent,
+    ctx: &egui::Context,
+    phase: egui::TouchPhase,
+) -> Option<egui::Event> {
+    if event.pointer_type() != "pen" {
+        return None;
+    }
+
+    // See https://www.w3.org/TR/pointerevents/#the-buttons-property
+    const BARREL_BUTTON: u16 = 2;
+    const ERASER_BUTTON: u16 = 32;
+
+    let buttons = event.buttons();
+    Some(egui::Event::Pen {
+        device_id: egui::TouchDeviceId(0),
+        phase,
+        pos: pos_from_mouse_event(canvas, event, ctx),
+        state: egui::PenState {
+            pressure: event.pressure(),
+            tilt: Some(egui::vec2(
+                (event.tilt_x() as f32).to_radians(),
+                (event.tilt_y() as f32).to_radians(),
+            )),
+            altitude: None,
+            twist: Some((event.twist() as f32).to_radians()),
+            barrel_button: buttons & BARREL_BUTTON != 0,
+            eraser: buttons & ERASER_BUTTON != 0,
+        },
+    })
+}
+
 /// A single touch is translated to a pointer movement. When a second touch is added, the pointer
 /// should not jump to a different position. Therefore, we do not calculate the average position
 /// of all touches, but we keep using the same touch as long as it is available.
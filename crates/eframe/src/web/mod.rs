@@ -46,8 +46,8 @@ use web_sys::{Document, MediaQueryList, Node};
 
 use input::{
     button_from_mouse_event, modifiers_from_kb_event, modifiers_from_mouse_event,
-    modifiers_from_wheel_event, pos_from_mouse_event, primary_touch_pos, push_touches,
-    text_from_keyboard_event, translate_key,
+    modifiers_from_wheel_event, pen_event_from_pointer_event, pos_from_mouse_event,
+    primary_touch_pos, push_touches, text_from_keyboard_event, translate_key,
 };
 
 // ----------------------------------------------------------------------------
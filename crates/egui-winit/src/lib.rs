@@ -627,20 +627,45 @@ impl State {
     fn on_touch(&mut self, window: &Window, touch: &winit::event::Touch) {
         let pixels_per_point = pixels_per_point(&self.egui_ctx, window);
 
+        let device_id = egui::TouchDeviceId(egui::epaint::util::hash(touch.device_id));
+        let phase = match touch.phase {
+            winit::event::TouchPhase::Started => egui::TouchPhase::Start,
+            winit::event::TouchPhase::Moved => egui::TouchPhase::Move,
+            winit::event::TouchPhase::Ended => egui::TouchPhase::End,
+            winit::event::TouchPhase::Cancelled => egui::TouchPhase::Cancel,
+        };
+        let pos = egui::pos2(
+            touch.location.x as f32 / pixels_per_point,
+            touch.location.y as f32 / pixels_per_point,
+        );
+
+        // Only a stylus (e.g. the Apple Pencil) reports its altitude angle.
+        // winit only reports that on iOS, and has no other tablet events,
+        // so this is the only place where egui-winit emits `Event::Pen`:
+        if let Some(winit::event::Force::Calibrated {
+            force,
+            max_possible_force,
+            altitude_angle: Some(altitude_angle),
+        }) = touch.force
+        {
+            self.egui_input.events.push(egui::Event::Pen {
+                device_id,
+                phase,
+                pos,
+                state: egui::PenState {
+                    pressure: (force / max_possible_force) as f32,
+                    altitude: Some(altitude_angle as f32),
+                    ..Default::default()
+                },
+            });
+        }
+
         // Emit touch event
         self.egui_input.events.push(egui::Event::Touch {
-            device_id: egui::TouchDeviceId(egui::epaint::util::hash(touch.device_id)),
+            device_id,
             id: egui::TouchId::from(touch.id),
-            phase: match touch.phase {
-                winit::event::TouchPhase::Started => egui::TouchPhase::Start,
-                winit::event::TouchPhase::Moved => egui::TouchPhase::Move,
-                winit::event::TouchPhase::Ended => egui::TouchPhase::End,
-                winit::event::TouchPhase::Cancelled => egui::TouchPhase::Cancel,
-            },
-            pos: egui::pos2(
-                touch.location.x as f32 / pixels_per_point,
-                touch.location.y as f32 / pixels_per_point,
-            ),
+            phase,
+            pos,
             force: match touch.force {
                 Some(winit::event::Force::Normalized(force)) => Some(force as f32),
                 Some(winit::event::Force::Calibrated {
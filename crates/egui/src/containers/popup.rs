@@ -53,7 +53,7 @@ impl From<&Response> for PopupAnchor {
     fn from(response: &Response) -> Self {
         // We use interact_rect so we don't show the popup relative to some clipped point
         let mut widget_rect = response.interact_rect;
-        if let Some(to_global) = response.ctx.layer_transform_to_global(response.layer_id) {
+        if let Some(to_global) = response.ctx.layer_affine_to_global(response.layer_id) {
             widget_rect = to_global * widget_rect;
         }
         Self::ParentRect(widget_rect)
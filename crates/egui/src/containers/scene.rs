@@ -4,7 +4,7 @@ use emath::{GuiRounding as _, Pos2};
 
 use crate::{
     InnerResponse, LayerId, PointerButton, Rangef, Rect, Response, Sense, Ui, UiBuilder, Vec2,
-    emath::TSTransform,
+    emath::{Affine2, TSTransform},
 };
 
 /// Creates a transformation that fits a given scene rectangle into the available screen size.
@@ -41,6 +41,8 @@ fn fit_to_rect_in_scene(
 /// * Supports zooming
 /// * Has no scroll bars
 /// * Has no limits on the scrolling
+///
+/// The view can also be rotated, see [`Self::rotation`].
 #[derive(Clone, Debug)]
 #[must_use = "You should call .show()"]
 pub struct Scene {
@@ -48,6 +50,7 @@ pub struct Scene {
     sense: Sense,
     max_inner_size: Vec2,
     drag_pan_buttons: DragPanButtons,
+    rotation: f32,
 }
 
 /// Specifies which pointer buttons can be used to pan the scene by dragging.
@@ -80,6 +83,7 @@ impl Default for Scene {
             sense: Sense::click_and_drag(),
             max_inner_size: Vec2::splat(1000.0),
             drag_pan_buttons: DragPanButtons::all(),
+            rotation: 0.0,
         }
     }
 }
@@ -130,6 +134,17 @@ impl Scene {
         self
     }
 
+    /// Rotate the view clockwise by this many radians, around the center of the scene.
+    ///
+    /// The `scene_rect` passed to [`Self::show`] still describes the view before rotation.
+    ///
+    /// The default is `0.0`.
+    #[inline]
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
     /// `scene_rect` contains the view bounds of the inner [`Ui`].
     ///
     /// `scene_rect` will be mutated by any panning/zooming done by the user.
@@ -202,22 +217,30 @@ impl Scene {
 
         let mut pan_response = local_ui.response();
 
+        // The rotation is applied after `to_global`, around the center of the scene:
+        let rotation = Affine2::from_angle(self.rotation).around(outer_rect.center());
+
         // Update the `to_global` transform based on use interaction:
-        self.register_pan_and_zoom(&local_ui, &mut pan_response, to_global);
+        self.pan_and_zoom(&local_ui, &mut pan_response, to_global, rotation);
 
-        // Set a correct global clip rect:
-        local_ui.set_clip_rect(to_global.inverse() * outer_rect);
+        let layer_to_global = rotation * *to_global;
+
+        // Clip to the scene in global space, which is exact also when rotated.
+        // The local clip rect is then just the part of the layer that can end up inside it:
+        let global_clip_rect = outer_rect.intersect(parent_ui.clip_rect());
+        let local_clip_rect = layer_to_global.inverse() * global_clip_rect;
+        local_ui.set_clip_rect(local_clip_rect);
 
         // Tell egui to apply the transform on the layer:
-        local_ui
-            .ctx()
-            .set_transform_layer(scene_layer_id, *to_global);
+        let ctx = local_ui.ctx();
+        ctx.set_transform_layer(scene_layer_id, layer_to_global);
+        ctx.set_transform_layer_clip_rect(scene_layer_id, global_clip_rect);
 
         // Add the actual contents to the area:
         let ret = add_contents(&mut local_ui);
 
         // This ensures we catch clicks/drags/pans anywhere on the background.
-        local_ui.force_set_min_rect((to_global.inverse() * outer_rect).round_ui());
+        local_ui.force_set_min_rect(local_clip_rect.round_ui());
 
         InnerResponse {
             response: pan_response,
@@ -226,7 +249,22 @@ impl Scene {
     }
 
     /// Helper function to handle pan and zoom interactions on a response.
+    ///
+    /// Like in [`Self::show`], the view is rotated by [`Self::rotation`] around the center of the scene,
+    /// after `to_global`. The scene is `resp.rect`, so `resp` should cover the whole scene.
     pub fn register_pan_and_zoom(&self, ui: &Ui, resp: &mut Response, to_global: &mut TSTransform) {
+        let rotation = Affine2::from_angle(self.rotation).around(resp.rect.center());
+        self.pan_and_zoom(ui, resp, to_global, rotation);
+    }
+
+    /// `rotation` is applied after `to_global` to get to the global coordinate system.
+    fn pan_and_zoom(
+        &self,
+        ui: &Ui,
+        resp: &mut Response,
+        to_global: &mut TSTransform,
+        rotation: Affine2,
+    ) {
         let dragged = self.drag_pan_buttons.iter().any(|button| match button {
             DragPanButtons::PRIMARY => resp.dragged_by(PointerButton::Primary),
             DragPanButtons::SECONDARY => resp.dragged_by(PointerButton::Secondary),
@@ -242,9 +280,10 @@ impl Scene {
 
         if let Some(mouse_pos) = ui.input(|i| i.pointer.latest_pos()) {
             if resp.contains_pointer() {
-                let pointer_in_scene = to_global.inverse() * mouse_pos;
+                let unrotate = rotation.inverse();
+                let pointer_in_scene = to_global.inverse() * (unrotate * mouse_pos);
                 let zoom_delta = ui.ctx().input(|i| i.zoom_delta());
-                let pan_delta = ui.ctx().input(|i| i.smooth_scroll_delta);
+                let pan_delta = unrotate.mul_vec(ui.ctx().input(|i| i.smooth_scroll_delta));
 
                 // Most of the time we can return early. This is also important to
                 // avoid `ui_from_scene` to change slightly due to floating point errors.
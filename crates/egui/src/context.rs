@@ -6,7 +6,7 @@ use emath::{GuiRounding as _, OrderedFloat};
 use epaint::{
     ClippedPrimitive, ClippedShape, Color32, ImageData, ImageDelta, Pos2, Rect, StrokeKind,
     TessellationOptions, TextureAtlas, TextureId, Vec2,
-    emath::{self, TSTransform},
+    emath::{self, Affine2, TSTransform},
     mutex::RwLock,
     stats::PaintStats,
     tessellator,
@@ -514,6 +514,10 @@ impl ContextImpl {
             layers.sort_by(|&a, &b| self.memory.areas().compare_order(a, b));
 
             viewport.hits = if let Some(pos) = viewport.input.pointer.interact_pos() {
+                // A layer can only be interacted with inside its global clip rect:
+                let clip_rects = &self.memory.to_global_clip_rects;
+                layers.retain(|layer_id| clip_rects.get(layer_id).is_none_or(|r| r.contains(pos)));
+
                 let interact_radius = self.memory.options.style().interaction.interact_radius;
 
                 crate::hit_test::hit_test(
@@ -777,6 +781,7 @@ impl Default for Context {
         // Register built-in plugins:
         crate::debug_text::register(&ctx);
         crate::text_selection::LabelSelectionState::register(&ctx);
+        crate::text_selection::FindInPageState::register(&ctx);
         crate::DragAndDrop::register(&ctx);
 
         ctx
@@ -1166,6 +1171,20 @@ impl Context {
             show_error(prev_rect, format!("First use of {what} ID {id_str}"));
             show_error(new_rect, format!("Second use of {what} ID {id_str}"));
         }
+
+        self.pass_state_mut(|state| {
+            state
+                .id_clashes
+                .push(format!("{what} ID {id_str} at {prev_rect} and {new_rect}"));
+        });
+    }
+
+    /// The [`Id`] clashes found by [`Self::check_for_id_clash`] during the previous pass.
+    ///
+    /// These are only collected if [`crate::Options::warn_on_id_clash`] is set.
+    /// Useful in tests, e.g. to fail on clashes instead of only painting a warning.
+    pub fn id_clashes(&self) -> Vec<String> {
+        self.prev_pass_state(|state| state.id_clashes.clone())
     }
 
     // ---------------------------------------------------------------------
@@ -2488,9 +2507,11 @@ impl ContextImpl {
             }
         }
 
-        let shapes = viewport
-            .graphics
-            .drain(self.memory.areas().order(), &self.memory.to_global);
+        let shapes = viewport.graphics.drain(
+            self.memory.areas().order(),
+            &self.memory.to_global,
+            &self.memory.to_global_clip_rects,
+        );
 
         let mut repaint_needed = false;
 
@@ -2819,13 +2840,18 @@ impl Context {
     ///
     /// This is a sticky setting, remembered from one frame to the next.
     ///
-    /// Can be used to implement pan and zoom (see relevant demo).
+    /// Can be used to implement pan and zoom (see relevant demo),
+    /// using a [`TSTransform`], or to rotate and skew the layer, using an [`Affine2`].
+    ///
+    /// Both the graphics and the hit testing of rotated or skewed layers are exact:
+    /// the clip rectangles of the layer are rotated/skewed along with the contents.
     ///
     /// For a temporary transform, use [`Self::transform_layer_shapes`] or
     /// [`Ui::with_visual_transform`].
-    pub fn set_transform_layer(&self, layer_id: LayerId, transform: TSTransform) {
+    pub fn set_transform_layer(&self, layer_id: LayerId, transform: impl Into<Affine2>) {
+        let transform = transform.into();
         self.memory_mut(|m| {
-            if transform == TSTransform::IDENTITY {
+            if transform == Affine2::IDENTITY {
                 m.to_global.remove(&layer_id)
             } else {
                 m.to_global.insert(layer_id, transform)
@@ -2833,19 +2859,61 @@ impl Context {
         });
     }
 
+    /// Clip the graphics of the given layer to a rectangle in the global coordinate system.
+    ///
+    /// The clip rectangles inside a layer are given in the local coordinate system of the layer,
+    /// so they are rotated along with it (see [`Self::set_transform_layer`]).
+    /// Use this to keep a rotated layer inside e.g. the clip rectangle of its parent [`crate::Ui`].
+    /// The layer can then only be interacted with inside this rectangle.
+    ///
+    /// This is a sticky setting, remembered from one frame to the next.
+    /// Use [`Rect::EVERYTHING`] to remove it.
+    pub fn set_transform_layer_clip_rect(&self, layer_id: LayerId, clip_rect: Rect) {
+        self.memory_mut(|m| {
+            if clip_rect == Rect::EVERYTHING {
+                m.to_global_clip_rects.remove(&layer_id)
+            } else {
+                m.to_global_clip_rects.insert(layer_id, clip_rect)
+            }
+        });
+    }
+
     /// Return how to transform the graphics of the given layer into the global coordinate system.
     ///
-    /// Set this with [`Self::layer_transform_to_global`].
+    /// Set this with [`Self::set_transform_layer`].
+    ///
+    /// Returns `None` if the layer has no transform, and ALSO if it rotates or skews the layer,
+    /// so code using this will silently treat a rotated layer as untransformed.
+    /// Use [`Self::layer_affine_to_global`] instead, which returns every transform.
+    #[deprecated = "Returns `None` for rotated or skewed layers. Use `layer_affine_to_global` instead"]
     pub fn layer_transform_to_global(&self, layer_id: LayerId) -> Option<TSTransform> {
-        self.memory(|m| m.to_global.get(&layer_id).copied())
+        self.layer_affine_to_global(layer_id)
+            .and_then(|t| t.as_ts_transform())
     }
 
     /// Return how to transform the graphics of the global coordinate system into the local coordinate system of the given layer.
     ///
-    /// This returns the inverse of [`Self::layer_transform_to_global`].
+    /// This returns the inverse of [`Self::layer_transform_to_global`],
+    /// so it is also `None` for rotated or skewed layers.
+    #[deprecated = "Returns `None` for rotated or skewed layers. Use `layer_affine_from_global` instead"]
     pub fn layer_transform_from_global(&self, layer_id: LayerId) -> Option<TSTransform> {
-        self.layer_transform_to_global(layer_id)
-            .map(|t| t.inverse())
+        self.layer_affine_from_global(layer_id)
+            .and_then(|t| t.as_ts_transform())
+    }
+
+    /// Return how to transform the graphics of the given layer into the global coordinate system,
+    /// including any rotation or skew.
+    ///
+    /// Set this with [`Self::set_transform_layer`].
+    pub fn layer_affine_to_global(&self, layer_id: LayerId) -> Option<Affine2> {
+        self.memory(|m| m.to_global.get(&layer_id).copied())
+    }
+
+    /// Return how to transform the graphics of the global coordinate system into the local coordinate system of the given layer.
+    ///
+    /// This returns the inverse of [`Self::layer_affine_to_global`].
+    pub fn layer_affine_from_global(&self, layer_id: LayerId) -> Option<Affine2> {
+        self.layer_affine_to_global(layer_id).map(|t| t.inverse())
     }
 
     /// Transform all the graphics at the given layer.
@@ -2897,11 +2965,6 @@ impl Context {
     ///
     /// See also [`Response::contains_pointer`].
     pub fn rect_contains_pointer(&self, layer_id: LayerId, rect: Rect) -> bool {
-        let rect = if let Some(to_global) = self.layer_transform_to_global(layer_id) {
-            to_global * rect
-        } else {
-            rect
-        };
         if !rect.is_positive() {
             return false;
         }
@@ -2911,7 +2974,12 @@ impl Context {
             return false;
         };
 
-        if !rect.contains(pointer_pos) {
+        // Test in the local space of the layer, so that rotated layers are handled correctly:
+        let pointer_pos_in_layer = self
+            .layer_affine_from_global(layer_id)
+            .map_or(pointer_pos, |from_global| from_global * pointer_pos);
+
+        if !rect.contains(pointer_pos_in_layer) {
             return false;
         }
 
@@ -4,7 +4,7 @@ use epaint::ColorImage;
 
 use crate::{
     Key, Theme, ViewportId, ViewportIdMap,
-    emath::{Pos2, Rect, Vec2},
+    emath::{Pos2, Rect, Vec2, vec2},
 };
 
 /// What the integrations provides to egui at the start of each frame.
@@ -276,6 +276,72 @@ impl ViewportInfo {
         }
     }
 
+    /// The info of the window of a new child viewport,
+    /// for integrations that simulate windows, like `eframe::HeadlessRunner` and `egui_kittest`.
+    ///
+    /// The window is focused, and `800x600` points if the builder doesn't set
+    /// [`crate::ViewportBuilder::inner_size`].
+    pub fn simulated(
+        parent: ViewportId,
+        builder: &crate::ViewportBuilder,
+        native_pixels_per_point: Option<f32>,
+    ) -> Self {
+        let size = builder.inner_size.unwrap_or(vec2(800.0, 600.0));
+        let rect = Rect::from_min_size(builder.position.unwrap_or_default(), size);
+        Self {
+            parent: Some(parent),
+            title: builder.title.clone(),
+            native_pixels_per_point,
+            inner_rect: Some(rect),
+            outer_rect: Some(rect),
+            maximized: builder.maximized,
+            fullscreen: builder.fullscreen,
+            focused: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Resize a simulated window (see [`Self::simulated`]), keeping its position.
+    pub fn set_simulated_inner_size(&mut self, size: Vec2) {
+        let size = size.max(Vec2::splat(1.0));
+        let min = self.inner_rect.map_or(Pos2::ZERO, |rect| rect.min);
+        self.inner_rect = Some(Rect::from_min_size(min, size));
+        self.outer_rect = self.inner_rect;
+    }
+
+    /// Do what a native window would do with the command, for a simulated window (see [`Self::simulated`]).
+    ///
+    /// Commands that don't change the [`ViewportInfo`] are ignored.
+    pub fn apply_simulated_command(&mut self, command: &crate::ViewportCommand) {
+        use crate::ViewportCommand;
+
+        match command {
+            ViewportCommand::Close => {
+                self.events.push(ViewportEvent::Close);
+            }
+            ViewportCommand::InnerSize(size) => {
+                self.set_simulated_inner_size(*size);
+            }
+            ViewportCommand::OuterPosition(pos) => {
+                self.inner_rect = self.inner_rect.map(|rect| rect.translate(*pos - rect.min));
+                self.outer_rect = self.inner_rect;
+            }
+            ViewportCommand::Title(title) => {
+                self.title = Some(title.clone());
+            }
+            ViewportCommand::Minimized(minimized) => {
+                self.minimized = Some(*minimized);
+            }
+            ViewportCommand::Maximized(maximized) => {
+                self.maximized = Some(*maximized);
+            }
+            ViewportCommand::Fullscreen(fullscreen) => {
+                self.fullscreen = Some(*fullscreen);
+            }
+            _ => {}
+        }
+    }
+
     pub fn ui(&self, ui: &mut crate::Ui) {
         let Self {
             parent,
@@ -505,6 +571,35 @@ pub enum Event {
         force: Option<f32>,
     },
 
+    /// A pen (stylus) on a tablet or touch screen.
+    ///
+    /// Report this *in addition to* [`Self::PointerMoved`], [`Self::PointerButton`], [`Self::PointerGone`],
+    /// which the integration should emit as if the pen was a mouse.
+    /// If the platform also reports the pen as a touch, put this before the [`Self::Touch`] event.
+    ///
+    /// The phase is [`TouchPhase::Start`] when the pen touches the surface,
+    /// [`TouchPhase::Move`] when it moves or any of its [`PenState`] changes (also while hovering above the surface),
+    /// and [`TouchPhase::End`] when it is lifted.
+    ///
+    /// Which integrations report this:
+    /// * `eframe` on the web: from pointer events with `pointerType == "pen"`, with pressure, tilt, twist and eraser.
+    /// * `egui-winit` on iOS: from touches with an altitude angle (e.g. the Apple Pencil), with pressure and altitude.
+    /// * `egui-winit` on other platforms: not at all, since `winit` has no tablet events there.
+    ///   A pen is then reported as a mouse or as a touch.
+    Pen {
+        /// Hashed device identifier (if available; may be zero).
+        device_id: TouchDeviceId,
+
+        /// One of: start move end cancel.
+        phase: TouchPhase,
+
+        /// Position of the pen tip.
+        pos: Pos2,
+
+        /// Pressure, tilt, buttons, …
+        state: PenState,
+    },
+
     /// A raw mouse wheel event as sent by the backend.
     ///
     /// Used for scrolling.
@@ -1174,6 +1269,57 @@ pub struct TouchDeviceId(pub u64);
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct TouchId(pub u64);
 
+/// The state of a pen (stylus), reported with [`Event::Pen`].
+///
+/// Any of these may be unavailable on a given platform or device.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PenState {
+    /// How hard the pen is pressed against the surface,
+    /// in the range from 0.0 (no pressure, or hovering) to 1.0 (maximum pressure).
+    pub pressure: f32,
+
+    /// The tilt of the pen in radians, as the plane angles between the Y-Z plane and the X-Z plane
+    /// and the plane containing both the pen and the respective axis.
+    ///
+    /// `x` is positive when the pen leans towards the right (+X),
+    /// `y` is positive when the pen leans towards the bottom (+Y),
+    /// and both are zero when the pen is perpendicular to the surface.
+    pub tilt: Option<Vec2>,
+
+    /// The angle in radians between the pen and the surface,
+    /// from 0 (lying flat on the surface) to 𝞃/4 (perpendicular to the surface).
+    ///
+    /// Some platforms only report this and not [`Self::tilt`].
+    pub altitude: Option<f32>,
+
+    /// Clockwise rotation of the pen around its own axis, in radians.
+    pub twist: Option<f32>,
+
+    /// Is the barrel (side) button of the pen pressed?
+    pub barrel_button: bool,
+
+    /// Is the eraser end of the pen being used (or the pen is in eraser mode)?
+    pub eraser: bool,
+}
+
+impl PenState {
+    /// The altitude of the pen, as reported by the platform, or computed from [`Self::tilt`].
+    ///
+    /// ```
+    /// # use egui::{PenState, vec2};
+    /// let pen = PenState { tilt: Some(vec2(0.0, 0.0)), ..Default::default() };
+    /// assert_eq!(pen.altitude(), Some(std::f32::consts::FRAC_PI_2));
+    /// ```
+    pub fn altitude(&self) -> Option<f32> {
+        self.altitude.or_else(|| {
+            let tilt = self.tilt?;
+            let horizontal = vec2(tilt.x.tan(), tilt.y.tan()).length();
+            Some(1.0_f32.atan2(horizontal))
+        })
+    }
+}
+
 /// In what phase a touch event is in.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -1,6 +1,6 @@
 use ahash::HashMap;
 
-use emath::TSTransform;
+use emath::Affine2;
 
 use crate::{LayerId, Pos2, Rect, Sense, WidgetRect, WidgetRects, ahash, emath, id::IdSet};
 
@@ -42,7 +42,7 @@ pub struct WidgetHits {
 pub fn hit_test(
     widgets: &WidgetRects,
     layer_order: &[LayerId],
-    layer_to_global: &HashMap<LayerId, TSTransform>,
+    layer_to_global: &HashMap<LayerId, Affine2>,
     pos: Pos2,
     search_radius: f32,
 ) -> WidgetHits {
@@ -114,10 +114,38 @@ pub fn hit_test(
     // but if the pointer is at the edge of a layer, we might include widgets in
     // a layer behind it.
 
+    // Layers that are rotated or skewed need special care,
+    // since the global bounding box of a widget is larger than the widget itself.
+    // For those we do the tests in the local space of the layer instead.
+    let rotated_layers: HashMap<LayerId, Affine2> = layer_to_global
+        .iter()
+        .filter(|(_, to_global)| !to_global.is_translate_scale())
+        .map(|(layer_id, to_global)| (*layer_id, *to_global))
+        .collect();
+
+    // The (untransformed) interact rect of a widget in a rotated layer,
+    // together with the pointer position in that same layer.
+    let in_rotated_layer = |widget: &WidgetRect| -> Option<(Affine2, Rect, Pos2)> {
+        let to_global = *rotated_layers.get(&widget.layer_id)?;
+        let local_rect = widgets
+            .get(widget.id)
+            .map_or(widget.interact_rect, |w| w.interact_rect);
+        Some((to_global, local_rect, to_global.inverse() * pos))
+    };
+
     let mut included_layers: ahash::HashSet<LayerId> = Default::default();
     for hit in close.iter().rev() {
         included_layers.insert(hit.layer_id);
-        let hit_covers_search_area = contains_circle(hit.interact_rect, pos, search_radius);
+        let hit_covers_search_area =
+            if let Some((to_global, local_rect, pos_in_layer)) = in_rotated_layer(hit) {
+                contains_circle(
+                    local_rect,
+                    pos_in_layer,
+                    search_radius / to_global.scale_factor(),
+                )
+            } else {
+                contains_circle(hit.interact_rect, pos, search_radius)
+            };
         if hit_covers_search_area {
             break; // nothing behind this layer could ever be interacted with
         }
@@ -152,11 +180,30 @@ pub fn hit_test(
 
     close.retain(|c| !hidden.contains(&c.id));
 
-    let mut hits = hit_test_on_close(&close, pos);
+    let distance_sq = |widget: &WidgetRect| -> f32 {
+        if let Some((to_global, local_rect, pos_in_layer)) = in_rotated_layer(widget) {
+            if local_rect.contains(pos_in_layer) {
+                0.0
+            } else {
+                // Find the closest point in local space, then measure the distance in global space:
+                (to_global * local_rect.clamp(pos_in_layer)).distance_sq(pos)
+            }
+        } else {
+            widget.interact_rect.distance_sq_to_pos(pos)
+        }
+    };
+
+    let mut hits = hit_test_on_close(&close, &distance_sq);
 
     hits.contains_pointer = close
         .iter()
-        .filter(|widget| widget.interact_rect.contains(pos))
+        .filter(|widget| {
+            if let Some((_, local_rect, pos_in_layer)) = in_rotated_layer(widget) {
+                local_rect.contains(pos_in_layer)
+            } else {
+                widget.interact_rect.contains(pos)
+            }
+        })
         .copied()
         .collect();
 
@@ -200,18 +247,19 @@ fn contains_circle(interact_rect: emath::Rect, pos: Pos2, radius: f32) -> bool {
     interact_rect.shrink(radius).contains(pos)
 }
 
-fn hit_test_on_close(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
+/// `distance_sq` is the squared distance from the pointer to the given widget.
+fn hit_test_on_close(close: &[WidgetRect], distance_sq: &dyn Fn(&WidgetRect) -> f32) -> WidgetHits {
     #![allow(clippy::collapsible_else_if)]
 
     // First find the best direct hits:
     let hit_click = find_closest_within(
         close.iter().copied().filter(|w| w.sense.senses_click()),
-        pos,
+        distance_sq,
         0.0,
     );
     let hit_drag = find_closest_within(
         close.iter().copied().filter(|w| w.sense.senses_drag()),
-        pos,
+        distance_sq,
         0.0,
     );
 
@@ -224,7 +272,7 @@ fn hit_test_on_close(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
                     .iter()
                     .copied()
                     .filter(|w| w.sense.senses_click() || w.sense.senses_drag()),
-                pos,
+                distance_sq,
             );
 
             if let Some(closest) = closest {
@@ -253,7 +301,7 @@ fn hit_test_on_close(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
 
             let closest_click = find_closest(
                 close.iter().copied().filter(|w| w.sense.senses_click()),
-                pos,
+                distance_sq,
             );
             if let Some(closest_click) = closest_click {
                 if closest_click.sense.senses_drag() {
@@ -314,7 +362,7 @@ fn hit_test_on_close(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
                         .iter()
                         .copied()
                         .filter(|w| w.sense.senses_drag() && w.id != hit_drag.id),
-                    pos,
+                    distance_sq,
                 );
 
                 if let Some(closest_drag) = closest_drag {
@@ -407,13 +455,16 @@ fn hit_test_on_close(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
     }
 }
 
-fn find_closest(widgets: impl Iterator<Item = WidgetRect>, pos: Pos2) -> Option<WidgetRect> {
-    find_closest_within(widgets, pos, f32::INFINITY)
+fn find_closest(
+    widgets: impl Iterator<Item = WidgetRect>,
+    distance_sq: &dyn Fn(&WidgetRect) -> f32,
+) -> Option<WidgetRect> {
+    find_closest_within(widgets, distance_sq, f32::INFINITY)
 }
 
 fn find_closest_within(
     widgets: impl Iterator<Item = WidgetRect>,
-    pos: Pos2,
+    distance_sq: &dyn Fn(&WidgetRect) -> f32,
     max_dist: f32,
 ) -> Option<WidgetRect> {
     let mut closest: Option<WidgetRect> = None;
@@ -423,7 +474,7 @@ fn find_closest_within(
             continue;
         }
 
-        let dist_sq = widget.interact_rect.distance_sq_to_pos(pos);
+        let dist_sq = distance_sq(&widget);
 
         if let Some(closest) = closest {
             if dist_sq == closest_dist_sq {
@@ -474,6 +525,10 @@ mod tests {
 
     use super::*;
 
+    fn hit_test_on_close_at(close: &[WidgetRect], pos: Pos2) -> WidgetHits {
+        hit_test_on_close(close, &|w| w.interact_rect.distance_sq_to_pos(pos))
+    }
+
     fn wr(id: Id, sense: Sense, rect: Rect) -> WidgetRect {
         WidgetRect {
             id,
@@ -506,22 +561,22 @@ mod tests {
         ];
 
         // Perfect hit:
-        let hits = hit_test_on_close(&widgets, pos2(15.0, 15.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(15.0, 15.0));
         assert_eq!(hits.click.unwrap().id, Id::new("click"));
         assert_eq!(hits.drag.unwrap().id, Id::new("bg-area"));
 
         // Close hit:
-        let hits = hit_test_on_close(&widgets, pos2(5.0, 5.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(5.0, 5.0));
         assert_eq!(hits.click.unwrap().id, Id::new("click"));
         assert_eq!(hits.drag.unwrap().id, Id::new("bg-area"));
 
         // Perfect hit:
-        let hits = hit_test_on_close(&widgets, pos2(105.0, 15.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(105.0, 15.0));
         assert_eq!(hits.click.unwrap().id, Id::new("click-and-drag"));
         assert_eq!(hits.drag.unwrap().id, Id::new("click-and-drag"));
 
         // Close hit - should still ignore the drag-background so as not to confuse the user:
-        let hits = hit_test_on_close(&widgets, pos2(105.0, 5.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(105.0, 5.0));
         assert_eq!(hits.click.unwrap().id, Id::new("click-and-drag"));
         assert_eq!(hits.drag.unwrap().id, Id::new("click-and-drag"));
     }
@@ -556,23 +611,59 @@ mod tests {
         }
 
         // In the middle of the bg-left-label:
-        let hits = hit_test_on_close(&widgets, pos2(25.0, 50.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(25.0, 50.0));
         assert_eq!(hits.click.unwrap().id, Id::new("bg-left-label"));
         assert_eq!(hits.drag.unwrap().id, Id::new("bg-left-label"));
 
         // On both the left click-and-drag and thin handle, but the thin handle is on top and should win:
-        let hits = hit_test_on_close(&widgets, pos2(35.0, 50.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(35.0, 50.0));
         assert_eq!(hits.click, None);
         assert_eq!(hits.drag.unwrap().id, Id::new("thin-drag-handle"));
 
         // Only on the thin-drag-handle:
-        let hits = hit_test_on_close(&widgets, pos2(50.0, 50.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(50.0, 50.0));
         assert_eq!(hits.click, None);
         assert_eq!(hits.drag.unwrap().id, Id::new("thin-drag-handle"));
 
         // On both the thin handle and right label. The label is on top and should win
-        let hits = hit_test_on_close(&widgets, pos2(65.0, 50.0));
+        let hits = hit_test_on_close_at(&widgets, pos2(65.0, 50.0));
         assert_eq!(hits.click.unwrap().id, Id::new("fg-right-label"));
         assert_eq!(hits.drag.unwrap().id, Id::new("fg-right-label"));
     }
+
+    #[test]
+    fn rotated_layer() {
+        let layer_id = LayerId::new(crate::Order::Middle, Id::new("rotated"));
+        let button = WidgetRect {
+            layer_id,
+            ..wr(
+                Id::new("button"),
+                Sense::click(),
+                Rect::from_min_size(pos2(0.0, 0.0), vec2(100.0, 10.0)),
+            )
+        };
+
+        let mut widgets = WidgetRects::default();
+        widgets.insert(layer_id, button);
+
+        let layer_to_global: HashMap<LayerId, Affine2> =
+            std::iter::once((layer_id, Affine2::from_angle(std::f32::consts::TAU / 8.0))).collect();
+
+        // On the rotated button:
+        let pos = layer_to_global[&layer_id] * pos2(50.0, 5.0);
+        let hits = hit_test(&widgets, &[layer_id], &layer_to_global, pos, 5.0);
+        assert_eq!(hits.click.unwrap().id, Id::new("button"));
+        assert_eq!(hits.contains_pointer.len(), 1);
+
+        // Inside the bounding box of the rotated button, but far from the button itself:
+        let hits = hit_test(
+            &widgets,
+            &[layer_id],
+            &layer_to_global,
+            pos2(60.0, 10.0),
+            5.0,
+        );
+        assert_eq!(hits.click, None);
+        assert!(hits.contains_pointer.is_empty());
+    }
 }
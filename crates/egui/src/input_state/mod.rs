@@ -1,8 +1,8 @@
 mod touch_state;
 
 use crate::data::input::{
-    Event, EventFilter, KeyboardShortcut, Modifiers, MouseWheelUnit, NUM_POINTER_BUTTONS,
-    PointerButton, RawInput, TouchDeviceId, ViewportInfo,
+    Event, EventFilter, KeyboardShortcut, Modifiers, MouseWheelUnit, NUM_POINTER_BUTTONS, PenState,
+    PointerButton, RawInput, TouchDeviceId, TouchPhase, ViewportInfo,
 };
 use crate::{
     emath::{NumExt as _, Pos2, Rect, Vec2, vec2},
@@ -922,7 +922,24 @@ impl PointerEvent {
     }
 }
 
-/// Mouse or touch state.
+/// What kind of device is controlling the pointer.
+///
+/// See [`PointerState::kind`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum PointerKind {
+    /// A mouse, trackpad, or similar.
+    #[default]
+    Mouse,
+
+    /// A finger on a touch screen.
+    Touch,
+
+    /// A pen (stylus) on a tablet or touch screen.
+    Pen,
+}
+
+/// Mouse, touch or pen state.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct PointerState {
@@ -996,6 +1013,18 @@ pub struct PointerState {
     /// All button events that occurred this frame
     pub(crate) pointer_events: Vec<PointerEvent>,
 
+    /// What kind of device last controlled the pointer.
+    kind: PointerKind,
+
+    /// Latest state of the pen, if [`Self::kind`] is [`PointerKind::Pen`].
+    pen: Option<PenState>,
+
+    /// Is the pen touching the surface?
+    pen_in_contact: bool,
+
+    /// Latest force of a touch, if [`Self::kind`] is [`PointerKind::Touch`].
+    touch_force: Option<f32>,
+
     /// Input state management configuration.
     ///
     /// This gets copied from `egui::Options` at the start of each frame for convenience.
@@ -1022,6 +1051,10 @@ impl Default for PointerState {
             last_last_click_time: f64::NEG_INFINITY,
             last_move_time: f64::NEG_INFINITY,
             pointer_events: vec![],
+            kind: PointerKind::Mouse,
+            pen: None,
+            pen_in_contact: false,
+            touch_force: None,
             options: Default::default(),
         }
     }
@@ -1043,12 +1076,33 @@ impl PointerState {
             self.motion = Some(Vec2::ZERO);
         }
 
+        // A pen may also be reported as a touch, and touches also produce pointer events,
+        // so the most specific kind of device wins.
+        let mut new_kind = None;
+
         let mut clear_history_after_velocity_calculation = false;
         for event in &new.events {
             match event {
+                Event::Pen { phase, state, .. } => {
+                    new_kind = Some(PointerKind::Pen);
+                    self.pen = Some(*state);
+                    match phase {
+                        TouchPhase::Start => self.pen_in_contact = true,
+                        TouchPhase::Move => {}
+                        TouchPhase::End | TouchPhase::Cancel => self.pen_in_contact = false,
+                    }
+                }
+                Event::Touch { force, .. } => {
+                    if new_kind != Some(PointerKind::Pen) {
+                        new_kind = Some(PointerKind::Touch);
+                        self.touch_force = *force;
+                    }
+                }
                 Event::PointerMoved(pos) => {
                     let pos = *pos;
 
+                    new_kind.get_or_insert(PointerKind::Mouse);
+
                     self.latest_pos = Some(pos);
                     self.interact_pos = Some(pos);
 
@@ -1071,6 +1125,8 @@ impl PointerState {
                     let pressed = *pressed;
                     let modifiers = *modifiers;
 
+                    new_kind.get_or_insert(PointerKind::Mouse);
+
                     self.latest_pos = Some(pos);
                     self.interact_pos = Some(pos);
 
@@ -1141,6 +1197,17 @@ impl PointerState {
             }
         }
 
+        if let Some(new_kind) = new_kind {
+            self.kind = new_kind;
+        }
+        if self.kind != PointerKind::Pen {
+            self.pen = None;
+            self.pen_in_contact = false;
+        }
+        if self.kind != PointerKind::Touch {
+            self.touch_force = None;
+        }
+
         self.delta = if let (Some(old_pos), Some(new_pos)) = (old_pos, self.latest_pos) {
             new_pos - old_pos
         } else {
@@ -1248,6 +1315,49 @@ impl PointerState {
         self.interact_pos
     }
 
+    /// What kind of device is controlling the pointer: a mouse, a finger or a pen?
+    ///
+    /// This is the kind of device that last moved or pressed the pointer.
+    #[inline(always)]
+    pub fn kind(&self) -> PointerKind {
+        self.kind
+    }
+
+    /// How hard is the pointer pressed, from 0.0 (not at all) to 1.0 (maximum pressure)?
+    ///
+    /// This is the pressure of the pen for [`PointerKind::Pen`], and the force of the touch for [`PointerKind::Touch`].
+    ///
+    /// Returns `None` for a mouse, or if the device or platform doesn't report pressure.
+    #[inline]
+    pub fn pressure(&self) -> Option<f32> {
+        match self.kind {
+            PointerKind::Mouse => None,
+            PointerKind::Touch => self.touch_force,
+            PointerKind::Pen => self.pen.map(|pen| pen.pressure),
+        }
+    }
+
+    /// The latest state (pressure, tilt, buttons, …) of the pen,
+    /// if the pointer is controlled by a pen.
+    #[inline(always)]
+    pub fn pen(&self) -> Option<PenState> {
+        self.pen
+    }
+
+    /// Is a pen touching the surface?
+    ///
+    /// A pen can also hover above the surface, in which case this is `false`.
+    #[inline(always)]
+    pub fn pen_in_contact(&self) -> bool {
+        self.pen_in_contact
+    }
+
+    /// Is the pointer controlled by the eraser end of a pen?
+    #[inline]
+    pub fn is_eraser(&self) -> bool {
+        self.pen.is_some_and(|pen| pen.eraser)
+    }
+
     /// Do we have a pointer?
     ///
     /// `false` if the mouse is not over the egui area, or if no touches are down on touch screens.
@@ -1585,6 +1695,10 @@ impl PointerState {
             last_last_click_time,
             pointer_events,
             last_move_time,
+            kind,
+            pen,
+            pen_in_contact,
+            touch_force,
             options: _,
         } = self;
 
@@ -1610,5 +1724,80 @@ impl PointerState {
         ui.label(format!("last_last_click_time: {last_last_click_time:#?}"));
         ui.label(format!("last_move_time: {last_move_time:#?}"));
         ui.label(format!("pointer_events: {pointer_events:?}"));
+        ui.label(format!("kind: {kind:?}"));
+        ui.label(format!("pen: {pen:#?}"));
+        ui.label(format!("pen_in_contact: {pen_in_contact}"));
+        ui.label(format!("touch_force: {touch_force:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TouchDeviceId, TouchId, pos2};
+
+    fn next_pass(input: InputState, events: Vec<Event>) -> InputState {
+        let raw = RawInput {
+            events,
+            ..Default::default()
+        };
+        input.begin_pass(raw, false, 1.0, InputOptions::default())
+    }
+
+    #[test]
+    fn pointer_kind() {
+        let pos = pos2(10.0, 20.0);
+        let pen = PenState {
+            pressure: 0.5,
+            ..Default::default()
+        };
+
+        let input = next_pass(InputState::default(), vec![Event::PointerMoved(pos)]);
+        assert_eq!(input.pointer.kind(), PointerKind::Mouse);
+        assert_eq!(input.pointer.pressure(), None);
+
+        // The platform reports the pen as a touch too:
+        let input = next_pass(
+            input,
+            vec![
+                Event::Pen {
+                    device_id: TouchDeviceId(0),
+                    phase: TouchPhase::Start,
+                    pos,
+                    state: pen,
+                },
+                Event::Touch {
+                    device_id: TouchDeviceId(0),
+                    id: TouchId(0),
+                    phase: TouchPhase::Start,
+                    pos,
+                    force: Some(0.25),
+                },
+                Event::PointerMoved(pos),
+            ],
+        );
+        assert_eq!(input.pointer.kind(), PointerKind::Pen);
+        assert_eq!(input.pointer.pressure(), Some(0.5));
+        assert!(input.pointer.pen_in_contact());
+        assert!(!input.pointer.is_eraser());
+
+        // No new pointer events, so we remember the pen:
+        let input = next_pass(input, vec![]);
+        assert_eq!(input.pointer.kind(), PointerKind::Pen);
+
+        let input = next_pass(
+            input,
+            vec![Event::Touch {
+                device_id: TouchDeviceId(1),
+                id: TouchId(1),
+                phase: TouchPhase::Start,
+                pos,
+                force: Some(0.25),
+            }],
+        );
+        assert_eq!(input.pointer.kind(), PointerKind::Touch);
+        assert_eq!(input.pointer.pressure(), Some(0.25));
+        assert_eq!(input.pointer.pen(), None);
+        assert!(!input.pointer.pen_in_contact());
     }
 }
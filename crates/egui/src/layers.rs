@@ -2,7 +2,10 @@
 //! are sometimes painted behind or in front of other things.
 
 use crate::{Id, IdMap, Rect, ahash, epaint};
-use epaint::{ClippedShape, Shape, emath::TSTransform};
+use epaint::{
+    ClippedShape, Shape,
+    emath::{Affine2, TSTransform},
+};
 
 /// Different layer categories
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
@@ -132,7 +135,7 @@ impl PaintList {
     #[inline(always)]
     pub fn add(&mut self, clip_rect: Rect, shape: Shape) -> ShapeIdx {
         let idx = self.next_idx();
-        self.0.push(ClippedShape { clip_rect, shape });
+        self.0.push(ClippedShape::new(clip_rect, shape));
         idx
     }
 
@@ -140,7 +143,7 @@ impl PaintList {
         self.0.extend(
             shapes
                 .into_iter()
-                .map(|shape| ClippedShape { clip_rect, shape }),
+                .map(|shape| ClippedShape::new(clip_rect, shape)),
         );
     }
 
@@ -159,7 +162,7 @@ impl PaintList {
             return;
         }
 
-        self.0[idx.0] = ClippedShape { clip_rect, shape };
+        self.0[idx.0] = ClippedShape::new(clip_rect, shape);
     }
 
     /// Set the given shape to be empty (a `Shape::Noop`).
@@ -175,17 +178,15 @@ impl PaintList {
 
     /// Transform each [`Shape`] and clip rectangle by this much, in-place
     pub fn transform(&mut self, transform: TSTransform) {
-        for ClippedShape { clip_rect, shape } in &mut self.0 {
-            *clip_rect = transform.mul_rect(*clip_rect);
-            shape.transform(transform);
+        for clipped_shape in &mut self.0 {
+            clipped_shape.transform_to_global(transform.into());
         }
     }
 
     /// Transform each [`Shape`] and clip rectangle in range by this much, in-place
     pub fn transform_range(&mut self, start: ShapeIdx, end: ShapeIdx, transform: TSTransform) {
-        for ClippedShape { clip_rect, shape } in &mut self.0[start.0..end.0] {
-            *clip_rect = transform.mul_rect(*clip_rect);
-            shape.transform(transform);
+        for clipped_shape in &mut self.0[start.0..end.0] {
+            clipped_shape.transform_to_global(transform.into());
         }
     }
 
@@ -220,7 +221,8 @@ impl GraphicLayers {
     pub fn drain(
         &mut self,
         area_order: &[LayerId],
-        to_global: &ahash::HashMap<LayerId, TSTransform>,
+        to_global: &ahash::HashMap<LayerId, Affine2>,
+        to_global_clip_rects: &ahash::HashMap<LayerId, Rect>,
     ) -> Vec<ClippedShape> {
         profiling::function_scope!();
 
@@ -238,12 +240,11 @@ impl GraphicLayers {
             for layer_id in area_order {
                 if layer_id.order == order {
                     if let Some(list) = order_map.get_mut(&layer_id.id) {
-                        if let Some(to_global) = to_global.get(layer_id) {
-                            for clipped_shape in &mut list.0 {
-                                clipped_shape.clip_rect = *to_global * clipped_shape.clip_rect;
-                                clipped_shape.shape.transform(*to_global);
-                            }
-                        }
+                        transform_to_global(
+                            &mut list.0,
+                            to_global.get(layer_id),
+                            to_global_clip_rects.get(layer_id),
+                        );
                         all_shapes.append(&mut list.0);
                     }
                 }
@@ -253,12 +254,11 @@ impl GraphicLayers {
             for (id, list) in order_map {
                 let layer_id = LayerId::new(order, *id);
 
-                if let Some(to_global) = to_global.get(&layer_id) {
-                    for clipped_shape in &mut list.0 {
-                        clipped_shape.clip_rect = *to_global * clipped_shape.clip_rect;
-                        clipped_shape.shape.transform(*to_global);
-                    }
-                }
+                transform_to_global(
+                    &mut list.0,
+                    to_global.get(&layer_id),
+                    to_global_clip_rects.get(&layer_id),
+                );
 
                 all_shapes.append(&mut list.0);
             }
@@ -267,3 +267,21 @@ impl GraphicLayers {
         all_shapes
     }
 }
+
+/// Apply the transform and global clip rect of a layer to its shapes.
+fn transform_to_global(
+    shapes: &mut [ClippedShape],
+    to_global: Option<&Affine2>,
+    global_clip_rect: Option<&Rect>,
+) {
+    if let Some(to_global) = to_global {
+        for clipped_shape in &mut *shapes {
+            clipped_shape.transform_to_global(*to_global);
+        }
+    }
+    if let Some(global_clip_rect) = global_clip_rect {
+        for clipped_shape in shapes {
+            clipped_shape.clip_to_global_rect(*global_clip_rect);
+        }
+    }
+}
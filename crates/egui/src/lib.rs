@@ -475,7 +475,7 @@ pub mod text {
     pub use crate::text_selection::CCursorRange;
     pub use epaint::text::{
         FontData, FontDefinitions, FontFamily, Fonts, Galley, LayoutJob, LayoutSection, TAB_SIZE,
-        TextFormat, TextWrapping, cursor::CCursor,
+        TabAlign, TabStop, TextFormat, TextWrapping, cursor::CCursor,
     };
 }
 
@@ -495,7 +495,7 @@ pub use self::{
     epaint::text::TextWrapMode,
     grid::Grid,
     id::{Id, IdMap},
-    input_state::{InputOptions, InputState, MultiTouchInfo, PointerState},
+    input_state::{InputOptions, InputState, MultiTouchInfo, PointerKind, PointerState},
     layers::{LayerId, Order},
     layout::*,
     load::SizeHint,
@@ -3,7 +3,7 @@
 use std::num::NonZeroUsize;
 
 use ahash::{HashMap, HashSet};
-use epaint::emath::TSTransform;
+use epaint::emath::{Affine2, TSTransform};
 
 use crate::{
     EventFilter, Id, IdMap, LayerId, Order, Pos2, Rangef, RawInput, Rect, Style, Vec2, ViewportId,
@@ -94,9 +94,14 @@ pub struct Memory {
     ///
     /// Instead of using this directly, use:
     /// * [`crate::Context::set_transform_layer`]
-    /// * [`crate::Context::layer_transform_to_global`]
-    /// * [`crate::Context::layer_transform_from_global`]
-    pub to_global: HashMap<LayerId, TSTransform>,
+    /// * [`crate::Context::layer_affine_to_global`]
+    /// * [`crate::Context::layer_affine_from_global`]
+    pub to_global: HashMap<LayerId, Affine2>,
+
+    /// Clip rectangles in the global coordinate system, for layers in [`Self::to_global`].
+    ///
+    /// Set with [`crate::Context::set_transform_layer_clip_rect`].
+    pub(crate) to_global_clip_rects: HashMap<LayerId, Rect>,
 
     // -------------------------------------------------
     // Per-viewport:
@@ -130,6 +135,7 @@ impl Default for Memory {
             viewport_id: Default::default(),
             areas: Default::default(),
             to_global: Default::default(),
+            to_global_clip_rects: Default::default(),
             popups: Default::default(),
             everything_is_visible: Default::default(),
             add_fonts: Default::default(),
@@ -233,6 +239,15 @@ pub struct Options {
     #[cfg_attr(feature = "serde", serde(skip))]
     pub zoom_with_keyboard: bool,
 
+    /// If `true`, Cmd+F (Ctrl+F on Windows and Linux) opens a find bar
+    /// that searches all labels and read-only text edits,
+    /// unless a widget has already consumed the shortcut.
+    ///
+    /// See [`crate::text_selection::FindInPageState`].
+    ///
+    /// This is `false` by default, so that the shortcut is left to the app.
+    pub find_in_page_with_keyboard: bool,
+
     /// Controls the tessellator.
     pub tessellation_options: epaint::TessellationOptions,
 
@@ -311,6 +326,7 @@ impl Default for Options {
             system_theme: None,
             zoom_factor: 1.0,
             zoom_with_keyboard: true,
+            find_in_page_with_keyboard: false,
             tessellation_options: Default::default(),
             repaint_on_widget_change: false,
             max_passes: NonZeroUsize::new(2).unwrap(),
@@ -367,6 +383,7 @@ impl Options {
             system_theme: _,
             zoom_factor: _, // TODO(emilk)
             zoom_with_keyboard,
+            find_in_page_with_keyboard,
             tessellation_options,
             repaint_on_widget_change,
             max_passes,
@@ -398,6 +415,11 @@ impl Options {
                     "Zoom with keyboard (Cmd +, Cmd -, Cmd 0)",
                 );
 
+                ui.checkbox(
+                    find_in_page_with_keyboard,
+                    "Find in page with keyboard (Cmd F)",
+                );
+
                 ui.checkbox(warn_on_id_clash, "Warn if two widgets have the same Id");
 
                 ui.checkbox(reduce_texture_memory, "Reduce texture memory");
@@ -812,9 +834,11 @@ impl Memory {
     }
 
     /// The currently set transform of a layer.
-    #[deprecated = "Use `Context::layer_transform_to_global` instead"]
+    #[deprecated = "Use `Context::layer_affine_to_global` instead"]
     pub fn layer_transforms(&self, layer_id: LayerId) -> Option<TSTransform> {
-        self.to_global.get(&layer_id).copied()
+        self.to_global
+            .get(&layer_id)
+            .and_then(|transform| transform.as_ts_transform())
     }
 
     /// An iterator over all layers. Back-to-front, top is last.
@@ -1197,18 +1221,18 @@ impl Areas {
     pub fn layer_id_at(
         &self,
         pos: Pos2,
-        layer_to_global: &HashMap<LayerId, TSTransform>,
+        layer_to_global: &HashMap<LayerId, Affine2>,
     ) -> Option<LayerId> {
         for layer in self.order.iter().rev() {
             if self.is_visible(layer) {
                 if let Some(state) = self.areas.get(&layer.id) {
-                    let mut rect = state.rect();
                     if state.interactable {
-                        if let Some(to_global) = layer_to_global.get(layer) {
-                            rect = *to_global * rect;
-                        }
+                        // Test in the local space of the layer, so that rotated layers are handled correctly:
+                        let pos_in_layer = layer_to_global
+                            .get(layer)
+                            .map_or(pos, |to_global| to_global.inverse() * pos);
 
-                        if rect.contains(pos) {
+                        if state.rect().contains(pos_in_layer) {
                             return Some(*layer);
                         }
                     }
@@ -412,7 +412,7 @@ impl MenuRoot {
                 }
             }
 
-            if let Some(to_global) = button.ctx.layer_transform_to_global(button.layer_id) {
+            if let Some(to_global) = button.ctx.layer_affine_to_global(button.layer_id) {
                 pos = to_global * pos;
             }
 
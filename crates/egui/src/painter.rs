@@ -361,6 +361,21 @@ impl Painter {
         self.add(Shape::line(points, stroke))
     }
 
+    /// Paints a line connecting the points, with a different width at each point.
+    ///
+    /// Useful for pen strokes, where the width can follow [`crate::PointerState::pressure`], and for tapered lines.
+    /// The width of the `stroke` is only used for points without a width.
+    /// For rounded caps and joins, give the `stroke` a [`epaint::PathStroke::style`].
+    /// NOTE: all coordinates are screen coordinates!
+    pub fn variable_width_line(
+        &self,
+        points: Vec<Pos2>,
+        widths: Vec<f32>,
+        stroke: impl Into<PathStroke>,
+    ) -> ShapeIdx {
+        self.add(Shape::variable_width_line(points, widths, stroke))
+    }
+
     /// Paints a horizontal line.
     pub fn hline(&self, x: impl Into<Rangef>, y: f32, stroke: impl Into<Stroke>) -> ShapeIdx {
         self.add(Shape::hline(x, y, stroke))
@@ -190,6 +190,9 @@ pub struct PassState {
     /// All [`Id`]s that were used this pass.
     pub used_ids: IdMap<Rect>,
 
+    /// The [`Id`] clashes found this pass, see [`crate::Context::id_clashes`].
+    pub id_clashes: Vec<String>,
+
     /// All widgets produced this pass.
     pub widgets: WidgetRects,
 
@@ -239,6 +242,7 @@ impl Default for PassState {
     fn default() -> Self {
         Self {
             used_ids: Default::default(),
+            id_clashes: Default::default(),
             widgets: Default::default(),
             layers: Default::default(),
             tooltips: Default::default(),
@@ -262,6 +266,7 @@ impl PassState {
         profiling::function_scope!();
         let Self {
             used_ids,
+            id_clashes,
             widgets,
             tooltips,
             layers,
@@ -279,6 +284,7 @@ impl PassState {
         } = self;
 
         used_ids.clear();
+        id_clashes.clear();
         widgets.clear();
         tooltips.clear();
         layers.clear();
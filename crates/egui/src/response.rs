@@ -401,8 +401,8 @@ impl Response {
     pub fn drag_delta(&self) -> Vec2 {
         if self.dragged() {
             let mut delta = self.ctx.input(|i| i.pointer.delta());
-            if let Some(from_global) = self.ctx.layer_transform_from_global(self.layer_id) {
-                delta *= from_global.scaling;
+            if let Some(from_global) = self.ctx.layer_affine_from_global(self.layer_id) {
+                delta = from_global.mul_vec(delta);
             }
             delta
         } else {
@@ -484,7 +484,7 @@ impl Response {
     pub fn hover_pos(&self) -> Option<Pos2> {
         if self.hovered() {
             let mut pos = self.ctx.input(|i| i.pointer.hover_pos())?;
-            if let Some(from_global) = self.ctx.layer_transform_from_global(self.layer_id) {
+            if let Some(from_global) = self.ctx.layer_affine_from_global(self.layer_id) {
                 pos = from_global * pos;
             }
             Some(pos)
@@ -1,4 +1,4 @@
-use emath::TSTransform;
+use emath::Affine2;
 
 use crate::{Context, Galley, Id};
 
@@ -10,7 +10,7 @@ pub fn update_accesskit_for_text_widget(
     widget_id: Id,
     cursor_range: Option<CCursorRange>,
     role: accesskit::Role,
-    global_from_galley: TSTransform,
+    global_from_galley: Affine2,
     galley: &Galley,
 ) {
     let parent_id = ctx.accesskit_node_builder(widget_id, |builder| {
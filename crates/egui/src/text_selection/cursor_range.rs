@@ -118,58 +118,25 @@ impl CCursorRange {
         modifiers: &Modifiers,
         key: Key,
     ) -> bool {
-        match key {
-            Key::A if modifiers.command => {
-                *self = Self::select_all(galley);
-                true
+        let Some(action) = CursorKeyAction::from_key(os, modifiers, key, !self.is_empty()) else {
+            return false;
+        };
+        match action {
+            CursorKeyAction::SelectAll => *self = Self::select_all(galley),
+            CursorKeyAction::Deselect { keep_end } => {
+                *self = Self::one(self.sorted_cursors()[keep_end as usize]);
             }
-
-            Key::ArrowLeft | Key::ArrowRight if modifiers.is_none() && !self.is_empty() => {
-                if key == Key::ArrowLeft {
-                    *self = Self::one(self.sorted_cursors()[0]);
-                } else {
-                    *self = Self::one(self.sorted_cursors()[1]);
+            CursorKeyAction::Move { movement, select } => {
+                if let Some(movement) = movement {
+                    (self.primary, self.h_pos) =
+                        movement.move_in_galley(galley, &self.primary, self.h_pos);
                 }
-                true
-            }
-
-            Key::ArrowLeft
-            | Key::ArrowRight
-            | Key::ArrowUp
-            | Key::ArrowDown
-            | Key::Home
-            | Key::End => {
-                move_single_cursor(
-                    os,
-                    &mut self.primary,
-                    &mut self.h_pos,
-                    galley,
-                    key,
-                    modifiers,
-                );
-                if !modifiers.shift {
+                if !select {
                     self.secondary = self.primary;
                 }
-                true
             }
-
-            Key::P | Key::N | Key::B | Key::F | Key::A | Key::E
-                if os == OperatingSystem::Mac && modifiers.ctrl && !modifiers.shift =>
-            {
-                move_single_cursor(
-                    os,
-                    &mut self.primary,
-                    &mut self.h_pos,
-                    galley,
-                    key,
-                    modifiers,
-                );
-                self.secondary = self.primary;
-                true
-            }
-
-            _ => false,
         }
+        true
     }
 
     /// Check for events that modify the cursor range.
@@ -244,86 +211,172 @@ fn ccursor_from_accesskit_text_position(
 
 // ----------------------------------------------------------------------------
 
-/// Move a text cursor based on keyboard
-fn move_single_cursor(
-    os: OperatingSystem,
-    cursor: &mut CCursor,
-    h_pos: &mut Option<f32>,
-    galley: &Galley,
-    key: Key,
-    modifiers: &Modifiers,
-) {
-    let (new_cursor, new_h_pos) =
+/// What a key press does to a [`CCursorRange`].
+///
+/// Shared by all text widgets, so that the keyboard works the same everywhere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CursorKeyAction {
+    SelectAll,
+
+    /// Collapse the selection to its start, or its end if `keep_end`.
+    Deselect {
+        keep_end: bool,
+    },
+
+    /// Move the primary cursor (if `movement` is `Some`),
+    /// and the secondary cursor too unless `select` is set.
+    Move {
+        movement: Option<CursorMovement>,
+        select: bool,
+    },
+}
+
+impl CursorKeyAction {
+    pub(crate) fn from_key(
+        os: OperatingSystem,
+        modifiers: &Modifiers,
+        key: Key,
+        has_selection: bool,
+    ) -> Option<Self> {
+        match key {
+            Key::A if modifiers.command => Some(Self::SelectAll),
+
+            Key::ArrowLeft | Key::ArrowRight if modifiers.is_none() && has_selection => {
+                Some(Self::Deselect {
+                    keep_end: key == Key::ArrowRight,
+                })
+            }
+
+            Key::ArrowLeft
+            | Key::ArrowRight
+            | Key::ArrowUp
+            | Key::ArrowDown
+            | Key::Home
+            | Key::End => Some(Self::Move {
+                movement: CursorMovement::from_key(os, modifiers, key),
+                select: modifiers.shift,
+            }),
+
+            Key::P | Key::N | Key::B | Key::F | Key::A | Key::E
+                if os == OperatingSystem::Mac && modifiers.ctrl && !modifiers.shift =>
+            {
+                Some(Self::Move {
+                    movement: CursorMovement::from_key(os, modifiers, key),
+                    select: false,
+                })
+            }
+
+            _ => None,
+        }
+    }
+}
+
+/// Where a key press moves a text cursor to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CursorMovement {
+    PreviousChar,
+    NextChar,
+    PreviousWord,
+    NextWord,
+    RowBegin,
+    RowEnd,
+    RowUp,
+    RowDown,
+    TextBegin,
+    TextEnd,
+}
+
+impl CursorMovement {
+    fn from_key(os: OperatingSystem, modifiers: &Modifiers, key: Key) -> Option<Self> {
         if os == OperatingSystem::Mac && modifiers.ctrl && !modifiers.shift {
             match key {
-                Key::A => (galley.cursor_begin_of_row(cursor), None),
-                Key::E => (galley.cursor_end_of_row(cursor), None),
-                Key::P => galley.cursor_up_one_row(cursor, *h_pos),
-                Key::N => galley.cursor_down_one_row(cursor, *h_pos),
-                Key::B => (galley.cursor_left_one_character(cursor), None),
-                Key::F => (galley.cursor_right_one_character(cursor), None),
-                _ => return,
+                Key::A => Some(Self::RowBegin),
+                Key::E => Some(Self::RowEnd),
+                Key::P => Some(Self::RowUp),
+                Key::N => Some(Self::RowDown),
+                Key::B => Some(Self::PreviousChar),
+                Key::F => Some(Self::NextChar),
+                _ => None,
             }
         } else {
             match key {
                 Key::ArrowLeft => {
                     if modifiers.alt || modifiers.ctrl {
                         // alt on mac, ctrl on windows
-                        (ccursor_previous_word(galley, *cursor), None)
+                        Some(Self::PreviousWord)
                     } else if modifiers.mac_cmd {
-                        (galley.cursor_begin_of_row(cursor), None)
+                        Some(Self::RowBegin)
                     } else {
-                        (galley.cursor_left_one_character(cursor), None)
+                        Some(Self::PreviousChar)
                     }
                 }
                 Key::ArrowRight => {
                     if modifiers.alt || modifiers.ctrl {
                         // alt on mac, ctrl on windows
-                        (ccursor_next_word(galley, *cursor), None)
+                        Some(Self::NextWord)
                     } else if modifiers.mac_cmd {
-                        (galley.cursor_end_of_row(cursor), None)
+                        Some(Self::RowEnd)
                     } else {
-                        (galley.cursor_right_one_character(cursor), None)
+                        Some(Self::NextChar)
                     }
                 }
                 Key::ArrowUp => {
                     if modifiers.command {
                         // mac and windows behavior
-                        (galley.begin(), None)
+                        Some(Self::TextBegin)
                     } else {
-                        galley.cursor_up_one_row(cursor, *h_pos)
+                        Some(Self::RowUp)
                     }
                 }
                 Key::ArrowDown => {
                     if modifiers.command {
                         // mac and windows behavior
-                        (galley.end(), None)
+                        Some(Self::TextEnd)
                     } else {
-                        galley.cursor_down_one_row(cursor, *h_pos)
+                        Some(Self::RowDown)
                     }
                 }
 
                 Key::Home => {
                     if modifiers.ctrl {
                         // windows behavior
-                        (galley.begin(), None)
+                        Some(Self::TextBegin)
                     } else {
-                        (galley.cursor_begin_of_row(cursor), None)
+                        Some(Self::RowBegin)
                     }
                 }
                 Key::End => {
                     if modifiers.ctrl {
                         // windows behavior
-                        (galley.end(), None)
+                        Some(Self::TextEnd)
                     } else {
-                        (galley.cursor_end_of_row(cursor), None)
+                        Some(Self::RowEnd)
                     }
                 }
 
-                _ => unreachable!(),
+                _ => None,
             }
-        };
+        }
+    }
 
-    *cursor = new_cursor;
-    *h_pos = new_h_pos;
+    /// Move a cursor in the galley, returning the new cursor and the saved horizontal position.
+    fn move_in_galley(
+        self,
+        galley: &Galley,
+        cursor: &CCursor,
+        h_pos: Option<f32>,
+    ) -> (CCursor, Option<f32>) {
+        match self {
+            Self::PreviousChar => (galley.cursor_left_one_character(cursor), None),
+            Self::NextChar => (galley.cursor_right_one_character(cursor), None),
+            Self::PreviousWord => (ccursor_previous_word(galley, *cursor), None),
+            Self::NextWord => (ccursor_next_word(galley, *cursor), None),
+            Self::RowBegin => (galley.cursor_begin_of_row(cursor), None),
+            Self::RowEnd => (galley.cursor_end_of_row(cursor), None),
+            Self::RowUp => galley.cursor_up_one_row(cursor, h_pos),
+            Self::RowDown => galley.cursor_down_one_row(cursor, h_pos),
+            Self::TextBegin => (galley.begin(), None),
+            Self::TextEnd => (galley.end(), None),
+        }
+    }
 }
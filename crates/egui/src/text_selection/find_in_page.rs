@@ -0,0 +1,573 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    Align, Align2, Area, Context, Frame, Galley, Id, Key, LayerId, LineBuffer, Modifiers, Order,
+    Painter, Pos2, Rect, Response, RichText, TextEdit, Ui,
+    cache::{ComputerMut, FrameCache},
+    pos2,
+    text::CCursor,
+    vec2,
+};
+
+/// A find bar that searches the text of all [`crate::Label`]s, read-only [`crate::TextEdit`]s
+/// and [`crate::TextEdit::virtualized`] in the current viewport,
+/// like the find-in-page feature of a browser.
+///
+/// If [`crate::Options::find_in_page_with_keyboard`] is turned on,
+/// press Cmd+F (Ctrl+F on Windows and Linux) to open it,
+/// unless a widget consumed the shortcut first.
+/// You can also open it with [`Self::open`].
+/// All matches are highlighted, and the current match is scrolled into view.
+///
+/// Matches are numbered in the order the widgets are shown,
+/// so widgets that are not shown at all (e.g. collapsed or skipped by [`crate::ScrollArea::show_rows`])
+/// are not searched.
+/// A virtualized [`crate::TextEdit`] is searched as a whole, including the lines that are not laid out.
+///
+/// One state per viewport.
+#[derive(Clone, Debug, Default)]
+pub struct FindInPageState {
+    is_open: bool,
+    query: String,
+    match_case: bool,
+
+    /// Index of the current match, counting all matches in the order they were shown.
+    current: usize,
+
+    /// Scroll to the current match this pass.
+    scroll_to_current: bool,
+
+    /// Focus the query field this pass.
+    request_focus: bool,
+
+    /// Number of matches last pass.
+    num_matches: usize,
+
+    /// Number of matches so far this pass.
+    num_matches_this_pass: usize,
+}
+
+impl FindInPageState {
+    pub(crate) fn register(ctx: &Context) {
+        ctx.on_begin_pass("FindInPageState", std::sync::Arc::new(Self::begin_pass));
+        ctx.on_end_pass("FindInPageState", std::sync::Arc::new(Self::end_pass));
+    }
+
+    /// The state of the find bar in the current viewport.
+    pub fn load(ctx: &Context) -> Self {
+        let id = Id::new(ctx.viewport_id());
+        ctx.data(|data| data.get_temp::<Self>(id))
+            .unwrap_or_default()
+    }
+
+    /// Apply changes made to a state from [`Self::load`], e.g. with [`Self::open`] or [`Self::set_query`].
+    pub fn store(self, ctx: &Context) {
+        let id = Id::new(ctx.viewport_id());
+        ctx.data_mut(|data| {
+            data.insert_temp(Self::is_searching_id(id), self.is_searching());
+            data.insert_temp(id, self);
+        });
+    }
+
+    /// Stored next to the state, so every widget can check it without cloning the state.
+    fn is_searching_id(id: Id) -> Id {
+        id.with("is_searching")
+    }
+
+    fn is_searching(&self) -> bool {
+        self.is_open && !self.query.is_empty()
+    }
+
+    fn begin_pass(ctx: &Context) {
+        let id = Id::new(ctx.viewport_id());
+        ctx.data_mut(|data| {
+            data.get_temp_mut_or_default::<Self>(id)
+                .num_matches_this_pass = 0;
+        });
+    }
+
+    fn end_pass(ctx: &Context) {
+        let id = Id::new(ctx.viewport_id());
+        let is_open = ctx.data_mut(|data| data.get_temp_mut_or_default::<Self>(id).is_open);
+        let open_shortcut = ctx.options(|o| o.find_in_page_with_keyboard)
+            && ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::F));
+        if !is_open && !open_shortcut {
+            return; // Nothing to do, so don't clone the state
+        }
+
+        let mut state = Self::load(ctx);
+        state.scroll_to_current = false;
+
+        if open_shortcut {
+            state.open();
+        }
+
+        state.num_matches = state.num_matches_this_pass;
+        if state.num_matches == 0 {
+            state.current = 0;
+        } else {
+            state.current = state.current.min(state.num_matches - 1);
+        }
+
+        if state.is_open {
+            state.bar_ui(ctx);
+        }
+
+        state.store(ctx);
+    }
+
+    /// Is the find bar open?
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Open the find bar, and focus its query field.
+    pub fn open(&mut self) {
+        self.is_open = true;
+        self.request_focus = true;
+        self.scroll_to_current = true;
+    }
+
+    /// Close the find bar, removing all highlights.
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.current = 0;
+        self.num_matches = 0;
+    }
+
+    /// What we are searching for.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Set what to search for, and go to the first match.
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.current = 0;
+        self.scroll_to_current = true;
+    }
+
+    /// The number of matches found last pass.
+    pub fn num_matches(&self) -> usize {
+        self.num_matches
+    }
+
+    /// The index of the current match, if there are any matches.
+    pub fn current_match(&self) -> Option<usize> {
+        (0 < self.num_matches).then_some(self.current)
+    }
+
+    /// Go to the next match, wrapping around at the end.
+    pub fn next_match(&mut self) {
+        if 0 < self.num_matches {
+            self.current = (self.current + 1) % self.num_matches;
+            self.scroll_to_current = true;
+        }
+    }
+
+    /// Go to the previous match, wrapping around at the start.
+    pub fn previous_match(&mut self) {
+        if 0 < self.num_matches {
+            self.current = (self.current + self.num_matches - 1) % self.num_matches;
+            self.scroll_to_current = true;
+        }
+    }
+
+    fn bar_layer_id() -> LayerId {
+        LayerId::new(Order::Foreground, Id::new("find_in_page_bar"))
+    }
+
+    fn bar_ui(&mut self, ctx: &Context) {
+        Area::new(Self::bar_layer_id().id)
+            .order(Self::bar_layer_id().order)
+            .anchor(Align2::RIGHT_TOP, vec2(-8.0, 8.0))
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            TextEdit::singleline(&mut self.query)
+                                .id(Id::new("find_in_page_query"))
+                                .hint_text("Find in page")
+                                .desired_width(160.0),
+                        );
+                        if std::mem::take(&mut self.request_focus) {
+                            response.request_focus();
+                        }
+                        if response.changed() {
+                            self.current = 0;
+                            self.scroll_to_current = true;
+                        }
+                        if response.lost_focus() {
+                            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                                if ui.input(|i| i.modifiers.shift) {
+                                    self.previous_match();
+                                } else {
+                                    self.next_match();
+                                }
+                                response.request_focus();
+                            } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                                self.close();
+                            }
+                        }
+
+                        if ui
+                            .toggle_value(&mut self.match_case, "Aa")
+                            .on_hover_text("Match case")
+                            .changed()
+                        {
+                            self.current = 0;
+                            self.scroll_to_current = true;
+                        }
+
+                        if let Some(current) = self.current_match() {
+                            ui.label(format!("{} of {}", current + 1, self.num_matches));
+                        } else if !self.query.is_empty() {
+                            ui.label(
+                                RichText::new("No results").color(ui.visuals().weak_text_color()),
+                            );
+                        }
+
+                        if ui
+                            .small_button("⬆")
+                            .on_hover_text("Previous match")
+                            .clicked()
+                        {
+                            self.previous_match();
+                        }
+                        if ui.small_button("⬇").on_hover_text("Next match").clicked() {
+                            self.next_match();
+                        }
+                        if ui.small_button("Close").clicked() {
+                            self.close();
+                        }
+                    });
+                });
+            });
+    }
+
+    /// Highlight the matches in a galley of a label or similar read-only widget.
+    ///
+    /// Call this before painting the galley, so the highlights end up behind the text.
+    pub(crate) fn on_galley(
+        ui: &Ui,
+        painter: &Painter,
+        response: &Response,
+        galley_pos: Pos2,
+        galley: &Galley,
+    ) {
+        let Some(found) = Self::count_matches(ui, response, |query, match_case| {
+            find_matches(galley.text(), query, match_case).into()
+        }) else {
+            return;
+        };
+
+        for (i, range) in found.matches.iter().enumerate() {
+            found.paint(
+                ui,
+                painter,
+                found.first_index + i,
+                galley_pos,
+                galley,
+                range.clone(),
+            );
+        }
+    }
+
+    /// Like [`Self::on_galley`], for a [`TextEdit::virtualized`] that has only laid out some of its lines.
+    ///
+    /// All of the `text` is searched, so that matches outside of the laid out lines
+    /// are counted too, and can be scrolled to.
+    /// The matches are cached by [`LineBuffer::generation`], so this is only slow when the text changes.
+    pub(crate) fn on_lines(
+        ui: &Ui,
+        painter: &Painter,
+        response: &Response,
+        text: &dyn LineBuffer,
+        lines: &LaidOutLines<'_>,
+    ) {
+        type MatchCache = FrameCache<Arc<[Range<usize>]>, MatchFinder>;
+
+        let Some(found) = Self::count_matches(ui, response, |query, match_case| {
+            ui.memory_mut(|mem| {
+                mem.caches
+                    .cache::<MatchCache>()
+                    .get((Document::new(text), query, match_case))
+            })
+        }) else {
+            return;
+        };
+
+        let LaidOutLines {
+            text_pos,
+            row_height,
+            galley_pos,
+            galley,
+            char_offset,
+        } = *lines;
+
+        let galley_end = char_offset + galley.end().index;
+        let is_laid_out =
+            |range: &Range<usize>| char_offset < range.end && range.start < galley_end;
+
+        if found.scroll_to_current {
+            // Scroll the current match into view, so that it is laid out next pass:
+            if let Some(range) = found.current_range().filter(|range| !is_laid_out(range)) {
+                let line = text.line_from_char_index(range.start);
+                let top = text_pos.y + (line as f64 * row_height as f64) as f32;
+                let rect = Rect::from_min_max(
+                    pos2(text_pos.x, top),
+                    pos2(galley_pos.x + galley.size().x, top + row_height),
+                );
+                ui.scroll_to_rect(rect, Some(Align::Center));
+            }
+        }
+
+        // Only the matches in the laid out lines can be painted:
+        let first = found
+            .matches
+            .partition_point(|range| range.end <= char_offset);
+        for (i, range) in found.matches.iter().enumerate().skip(first) {
+            if !is_laid_out(range) {
+                break;
+            }
+            let local =
+                range.start.max(char_offset) - char_offset..range.end.min(galley_end) - char_offset;
+            found.paint(
+                ui,
+                painter,
+                found.first_index + i,
+                galley_pos,
+                galley,
+                local,
+            );
+        }
+    }
+
+    /// Count the matches of a widget, if the find bar is open.
+    fn count_matches(
+        ui: &Ui,
+        response: &Response,
+        find: impl FnOnce(&str, bool) -> Arc<[Range<usize>]>,
+    ) -> Option<Matches> {
+        if response.layer_id == Self::bar_layer_id() {
+            return None; // Don't search the find bar itself
+        }
+
+        let id = Id::new(ui.ctx().viewport_id());
+        let is_searching = ui
+            .ctx()
+            .data(|data| data.get_temp::<bool>(Self::is_searching_id(id)));
+        if is_searching != Some(true) {
+            return None;
+        }
+        let (query, match_case) = ui.ctx().data_mut(|data| {
+            let state = data.get_temp_mut_or_default::<Self>(id);
+            (state.query.clone(), state.match_case)
+        });
+        let matches = find(&query, match_case);
+
+        ui.ctx().data_mut(|data| {
+            let state = data.get_temp_mut_or_default::<Self>(id);
+            let first_index = state.num_matches_this_pass;
+            state.num_matches_this_pass += matches.len();
+
+            let has_current = (first_index..state.num_matches_this_pass).contains(&state.current);
+            let scroll_to_current = has_current && std::mem::take(&mut state.scroll_to_current);
+            Some(Matches {
+                matches,
+                first_index,
+                current: state.current,
+                scroll_to_current,
+            })
+        })
+    }
+}
+
+/// The laid out lines of a [`TextEdit::virtualized`], see [`FindInPageState::on_lines`].
+#[derive(Clone, Copy)]
+pub(crate) struct LaidOutLines<'a> {
+    /// Where the first line of the text would be.
+    pub text_pos: Pos2,
+    pub row_height: f32,
+    pub galley_pos: Pos2,
+
+    /// The laid out lines.
+    pub galley: &'a Galley,
+
+    /// The character index of the start of the [`Self::galley`].
+    pub char_offset: usize,
+}
+
+/// The matches in one widget.
+struct Matches {
+    /// Character ranges in the text of the widget.
+    matches: Arc<[Range<usize>]>,
+
+    /// The index of the first of the [`Self::matches`] among all matches.
+    first_index: usize,
+
+    /// The index of the current match among all matches.
+    current: usize,
+
+    /// Scroll to the current match, which is one of ours.
+    scroll_to_current: bool,
+}
+
+impl Matches {
+    fn current_range(&self) -> Option<&Range<usize>> {
+        self.matches
+            .get(self.current.checked_sub(self.first_index)?)
+    }
+
+    /// Highlight a match, and scroll to it if it is the current one.
+    fn paint(
+        &self,
+        ui: &Ui,
+        painter: &Painter,
+        index: usize,
+        galley_pos: Pos2,
+        galley: &Galley,
+        range: Range<usize>,
+    ) {
+        let visuals = ui.visuals();
+        let is_current = index == self.current;
+        let color = if is_current {
+            visuals.warn_fg_color.gamma_multiply(0.6)
+        } else {
+            visuals.warn_fg_color.gamma_multiply(0.3)
+        };
+        let rects = match_rects(galley, range);
+        if is_current && self.scroll_to_current {
+            let rect = rects
+                .iter()
+                .fold(Rect::NOTHING, |acc, rect| acc.union(*rect))
+                .translate(galley_pos.to_vec2());
+            ui.scroll_to_rect(rect, Some(Align::Center));
+        }
+        for rect in rects {
+            painter.rect_filled(rect.translate(galley_pos.to_vec2()), 2.0, color);
+        }
+    }
+}
+
+/// The text of a [`LineBuffer`], hashed by its [`LineBuffer::generation`] instead of its contents.
+#[derive(Clone, Copy)]
+struct Document<'a> {
+    generation: u64,
+    text: &'a str,
+}
+
+impl<'a> Document<'a> {
+    fn new(text: &'a dyn LineBuffer) -> Self {
+        Self {
+            generation: text.generation(),
+            text: text.as_str(),
+        }
+    }
+}
+
+impl std::hash::Hash for Document<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.generation.hash(state);
+    }
+}
+
+/// Searches a whole document, see [`FindInPageState::on_lines`].
+#[derive(Default)]
+struct MatchFinder;
+
+impl ComputerMut<(Document<'_>, &str, bool), Arc<[Range<usize>]>> for MatchFinder {
+    fn compute(
+        &mut self,
+        (document, query, match_case): (Document<'_>, &str, bool),
+    ) -> Arc<[Range<usize>]> {
+        find_matches_in_lines(document.text, query, match_case).into()
+    }
+}
+
+/// Non-overlapping matches of `needle` in `haystack`, as character ranges.
+fn find_matches(haystack: &str, needle: &str, match_case: bool) -> Vec<Range<usize>> {
+    let fold = |c: char| {
+        if match_case {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let needle: Vec<char> = needle.chars().map(fold).collect();
+    let mut matches = Vec::new();
+    if needle.is_empty() {
+        return matches;
+    }
+
+    let haystack: Vec<char> = haystack.chars().map(fold).collect();
+    let mut start = 0;
+    while start + needle.len() <= haystack.len() {
+        if haystack[start..start + needle.len()] == needle[..] {
+            matches.push(start..start + needle.len());
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+/// Like [`find_matches`], but one line at a time, so that we don't copy all of a large document.
+///
+/// Matches never span lines.
+fn find_matches_in_lines(haystack: &str, needle: &str, match_case: bool) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    let mut line_start = 0;
+    for line in haystack.split('\n') {
+        matches.extend(
+            find_matches(line, needle, match_case)
+                .into_iter()
+                .map(|range| line_start + range.start..line_start + range.end),
+        );
+        line_start += line.chars().count() + 1;
+    }
+    matches
+}
+
+/// The rectangles covering a character range of the galley, one per row.
+fn match_rects(galley: &Galley, range: Range<usize>) -> Vec<Rect> {
+    let min = galley.layout_from_cursor(CCursor::new(range.start));
+    let max = galley.layout_from_cursor(CCursor::new(range.end));
+
+    (min.row..=max.row)
+        .filter_map(|ri| {
+            let placed_row = galley.rows.get(ri)?;
+            let row = &placed_row.row;
+            let left = if ri == min.row {
+                row.x_offset(min.column)
+            } else {
+                0.0
+            };
+            let right = if ri == max.row {
+                row.x_offset(max.column)
+            } else {
+                row.size.x
+            };
+            let rect = Rect::from_min_max(pos2(left, 0.0), pos2(right, row.size.y));
+            Some(rect.translate(placed_row.pos.to_vec2()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(find_matches("Ärger ärger", "är", false), vec![0..2, 6..8]);
+        assert_eq!(find_matches("Ärger ärger", "är", true), vec![6..8]);
+        assert_eq!(find_matches("aaaa", "aa", true), vec![0..2, 2..4]);
+        assert_eq!(find_matches("abc", "", false), vec![]);
+        assert_eq!(
+            find_matches_in_lines("äb\nab\nb", "b", true),
+            vec![1..2, 4..5, 6..7]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use emath::TSTransform;
+use emath::Affine2;
 
 use crate::{
     Context, CursorIcon, Event, Galley, Id, LayerId, Pos2, Rect, Response, Ui, layers::ShapeIdx,
@@ -30,7 +30,7 @@ impl WidgetTextCursor {
     fn new(
         widget_id: Id,
         cursor: impl Into<CCursor>,
-        global_from_galley: TSTransform,
+        global_from_galley: Affine2,
         galley: &Galley,
     ) -> Self {
         let ccursor = cursor.into();
@@ -317,7 +317,7 @@ impl LabelSelectionState {
         &mut self,
         ui: &Ui,
         response: &Response,
-        global_from_galley: TSTransform,
+        global_from_galley: Affine2,
         galley: &Galley,
     ) -> TextCursorState {
         let Some(selection) = &mut self.selection else {
@@ -511,9 +511,9 @@ impl LabelSelectionState {
 
         let global_from_layer = ui
             .ctx()
-            .layer_transform_to_global(ui.layer_id())
+            .layer_affine_to_global(ui.layer_id())
             .unwrap_or_default();
-        let layer_from_galley = TSTransform::from_translation(galley_pos_in_layer.to_vec2());
+        let layer_from_galley = Affine2::from_translation(galley_pos_in_layer.to_vec2());
         let galley_from_layer = layer_from_galley.inverse();
         let layer_from_global = global_from_layer.inverse();
         let galley_from_global = galley_from_layer * layer_from_global;
@@ -4,10 +4,14 @@
 pub mod accesskit_text;
 
 mod cursor_range;
+mod find_in_page;
 mod label_text_selection;
 pub mod text_cursor_state;
 pub mod visuals;
 
 pub use cursor_range::CCursorRange;
+pub(crate) use cursor_range::{CursorKeyAction, CursorMovement};
+pub use find_in_page::FindInPageState;
+pub(crate) use find_in_page::LaidOutLines;
 pub use label_text_selection::LabelSelectionState;
 pub use text_cursor_state::TextCursorState;
@@ -1224,3 +1224,48 @@ pub struct ImmediateViewport<'a> {
     /// The user-code that shows the GUI.
     pub viewport_ui_cb: Box<dyn FnMut(&Context) + 'a>,
 }
+
+// ----------------------------------------------------------------------------
+
+/// Runs child viewports as simulated windows, without a windowing system.
+///
+/// Shared by `eframe::HeadlessRunner` and `egui_kittest::Harness`, so that both run
+/// deferred viewports the same way.
+/// The windows themselves are simulated with [`crate::ViewportInfo::simulated`]
+/// and [`crate::ViewportInfo::apply_simulated_command`].
+#[doc(hidden)]
+pub trait ViewportSimulator {
+    /// The deferred child viewports, in the order they should run.
+    fn deferred_viewports(&self) -> Vec<(ViewportId, Arc<DeferredViewportUiCallback>)>;
+
+    /// Is the child viewport still open?
+    fn contains_viewport(&self, viewport_id: ViewportId) -> bool;
+
+    /// Run a pass of the viewport with its input, including the immediate viewports it shows.
+    fn run_viewport(
+        &mut self,
+        viewport_id: ViewportId,
+        viewport_ui_cb: &DeferredViewportUiCallback,
+    ) -> crate::FullOutput;
+
+    /// Add new child viewports, simulate the viewport commands and remove closed viewports.
+    fn handle_viewport_output(&mut self, viewport_output: &ViewportIdMap<ViewportOutput>);
+
+    /// Store the output of the last pass of the child viewport.
+    fn set_viewport_output(&mut self, viewport_id: ViewportId, output: crate::FullOutput);
+
+    /// Run all deferred child viewports once, like a native integration does
+    /// after each pass of the root viewport.
+    fn step_deferred_viewports(&mut self) {
+        for (id, viewport_ui_cb) in self.deferred_viewports() {
+            if !self.contains_viewport(id) {
+                continue; // Closed by a viewport that ran before
+            }
+            let mut output = self.run_viewport(id, &*viewport_ui_cb);
+            let viewport_output = std::mem::take(&mut output.viewport_output);
+            self.handle_viewport_output(&viewport_output);
+            output.viewport_output = viewport_output;
+            self.set_viewport_output(id, output);
+        }
+    }
+}
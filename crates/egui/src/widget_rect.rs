@@ -43,7 +43,11 @@ pub struct WidgetRect {
 }
 
 impl WidgetRect {
-    pub fn transform(self, transform: emath::TSTransform) -> Self {
+    /// Transform the rectangles.
+    ///
+    /// For transforms with rotation or skew, the rectangles become the bounding boxes of the transformed rectangles.
+    pub fn transform(self, transform: impl Into<emath::Affine2>) -> Self {
+        let transform = transform.into();
         let Self {
             id,
             layer_id,
@@ -453,6 +453,7 @@ impl RichText {
                 background: background_color,
                 italics,
                 underline,
+                wavy_underline: crate::Stroke::NONE,
                 strikethrough,
                 valign,
                 expand_bg,
@@ -2,7 +2,8 @@ use std::sync::Arc;
 
 use crate::{
     Align, Direction, FontSelection, Galley, Pos2, Response, Sense, Stroke, TextWrapMode, Ui,
-    Widget, WidgetInfo, WidgetText, WidgetType, epaint, pos2, text_selection::LabelSelectionState,
+    Widget, WidgetInfo, WidgetText, WidgetType, epaint, pos2,
+    text_selection::{FindInPageState, LabelSelectionState},
 };
 
 /// Static text.
@@ -279,6 +280,8 @@ impl Widget for Label {
         response
             .widget_info(|| WidgetInfo::labeled(WidgetType::Label, ui.is_enabled(), galley.text()));
 
+        FindInPageState::on_galley(ui, ui.painter(), &response, galley_pos, &galley);
+
         if ui.is_rect_visible(response.rect) {
             if show_tooltip_when_elided && galley.elided {
                 // Show the full (non-elided) text on hover:
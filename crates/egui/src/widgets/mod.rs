@@ -40,7 +40,10 @@ pub use self::{
     separator::Separator,
     slider::{Slider, SliderClamping, SliderOrientation},
     spinner::Spinner,
-    text_edit::{TextBuffer, TextEdit},
+    text_edit::{
+        Autocomplete, InputMask, LineBuffer, LineIndexedString, RichTextDocument, SpellChecker,
+        SuggestionSource, TextAttributes, TextBuffer, TextEdit, TextValidator,
+    },
 };
 
 // ----------------------------------------------------------------------------
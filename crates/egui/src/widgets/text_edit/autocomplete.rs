@@ -0,0 +1,429 @@
+use std::ops::Range;
+
+use crate::{
+    EventFilter, Id, Key, Modifiers, Popup, PopupCloseBehavior, Rect, RectAlign, TextBuffer, Ui,
+    text::{CCursor, CCursorRange},
+};
+
+use super::{TextEdit, TextEditOutput, TextEditState};
+
+/// Where an [`Autocomplete`] gets its suggestions from.
+///
+/// This is implemented for lists of strings (`Vec<String>`, `[&str; N]`, …),
+/// which are filtered by what the user has typed,
+/// and for closures `Fn(&str) -> Option<Vec<String>>`.
+///
+/// The source is asked every frame while the user is typing.
+/// If the suggestions take long to compute (e.g. they come from a server),
+/// start fetching them in the background, return `None` until they are ready,
+/// and call [`crate::Context::request_repaint`] when they arrive.
+pub trait SuggestionSource {
+    /// Suggestions for what the user has typed so far, best first.
+    ///
+    /// Returns `None` while the suggestions are still loading.
+    fn suggestions(&self, query: &str) -> Option<Vec<String>>;
+}
+
+impl<F> SuggestionSource for F
+where
+    F: Fn(&str) -> Option<Vec<String>>,
+{
+    fn suggestions(&self, query: &str) -> Option<Vec<String>> {
+        self(query)
+    }
+}
+
+impl<S: AsRef<str>> SuggestionSource for Vec<S> {
+    fn suggestions(&self, query: &str) -> Option<Vec<String>> {
+        Some(filter_suggestions(self, query))
+    }
+}
+
+impl<S: AsRef<str>, const N: usize> SuggestionSource for [S; N] {
+    fn suggestions(&self, query: &str) -> Option<Vec<String>> {
+        Some(filter_suggestions(self, query))
+    }
+}
+
+/// The candidates that contain the query, ignoring case.
+///
+/// Candidates that start with the query come first.
+pub fn filter_suggestions(candidates: &[impl AsRef<str>], query: &str) -> Vec<String> {
+    let query = query.to_lowercase();
+    let mut starts_with = Vec::new();
+    let mut contains = Vec::new();
+    for candidate in candidates {
+        let candidate = candidate.as_ref();
+        let lowercase = candidate.to_lowercase();
+        if lowercase.starts_with(&query) {
+            starts_with.push(candidate.to_owned());
+        } else if lowercase.contains(&query) {
+            contains.push(candidate.to_owned());
+        }
+    }
+    starts_with.extend(contains);
+    starts_with
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Default)]
+struct AutocompleteState {
+    /// Was the popup shown last frame?
+    is_open: bool,
+
+    /// The suggestions shown last frame.
+    suggestions: Vec<String>,
+
+    /// Index into [`Self::suggestions`].
+    selected: usize,
+
+    /// The character range of the query the suggestions are for.
+    query_range: Range<usize>,
+
+    /// The user closed the popup (or accepted a suggestion) for this query,
+    /// so don't show it again until the query changes.
+    dismissed_query: Option<String>,
+
+    /// Where the popup was shown last frame.
+    popup_rect: Option<Rect>,
+}
+
+/// Shows a dropdown with suggestions below the text cursor of a [`TextEdit`].
+///
+/// The suggestions are filtered as the user types.
+/// Use the arrow keys to select a suggestion, and Enter or Tab to accept it.
+/// Escape closes the dropdown.
+///
+/// By default the whole line before the cursor is used as the query, which is good for
+/// single-line fields (like a combo box you can type into).
+/// Use [`Self::trigger`] to instead only complete words after e.g. an `@` (for mentions).
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// # let mut city = String::new();
+/// let cities = vec!["Berlin", "Stockholm", "Stuttgart"];
+/// egui::Autocomplete::new("city", &cities).show(ui, &mut city, |text| {
+///     egui::TextEdit::singleline(text).hint_text("City")
+/// });
+///
+/// # let mut message = String::new();
+/// let users = vec!["alice", "bob"];
+/// egui::Autocomplete::new("message", &users)
+///     .trigger('@')
+///     .show(ui, &mut message, |text| egui::TextEdit::multiline(text));
+/// # });
+/// ```
+#[must_use = "You should call .show()"]
+pub struct Autocomplete<'a> {
+    id_salt: Id,
+    source: &'a dyn SuggestionSource,
+    trigger: Option<char>,
+    max_suggestions: usize,
+}
+
+impl<'a> Autocomplete<'a> {
+    /// Suggest completions from `source`, e.g. a `Vec<&str>` or your own [`SuggestionSource`].
+    ///
+    /// The `id_salt` must be unique within the parent [`Ui`],
+    /// and is also used for the [`TextEdit`].
+    pub fn new(id_salt: impl std::hash::Hash, source: &'a dyn SuggestionSource) -> Self {
+        Self {
+            id_salt: Id::new(id_salt),
+            source,
+            trigger: None,
+            max_suggestions: 10,
+        }
+    }
+
+    /// Only suggest completions for the word after this character, e.g. `'@'` for mentions.
+    ///
+    /// The character must be at the start of the text or after whitespace.
+    #[inline]
+    pub fn trigger(mut self, trigger: char) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    /// Show at most this many suggestions. Default: 10.
+    #[inline]
+    pub fn max_suggestions(mut self, max_suggestions: usize) -> Self {
+        self.max_suggestions = max_suggestions;
+        self
+    }
+
+    /// Show the [`TextEdit`] created by `text_edit`, and the suggestions below its cursor.
+    ///
+    /// The [`TextEdit`] will get its [`Id`] from the `id_salt` of the [`Autocomplete`].
+    /// Accepting a suggestion marks the response as changed.
+    pub fn show(
+        self,
+        ui: &mut Ui,
+        text: &mut dyn TextBuffer,
+        text_edit: impl FnOnce(&mut dyn TextBuffer) -> TextEdit<'_>,
+    ) -> TextEditOutput {
+        let Self {
+            id_salt,
+            source,
+            trigger,
+            max_suggestions,
+        } = self;
+
+        let id = ui.make_persistent_id(id_salt);
+        let state_id = id.with("autocomplete");
+        let mut state: AutocompleteState = ui
+            .ctx()
+            .data(|data| data.get_temp(state_id))
+            .unwrap_or_default();
+
+        // Handle keyboard input before the `TextEdit` sees it:
+        let mut accepted = None;
+        if state.is_open && ui.memory(|mem| mem.has_focus(id)) {
+            let num_suggestions = state.suggestions.len();
+            ui.input_mut(|i| {
+                if i.consume_key(Modifiers::NONE, Key::ArrowDown) && 0 < num_suggestions {
+                    state.selected = (state.selected + 1) % num_suggestions;
+                }
+                if i.consume_key(Modifiers::NONE, Key::ArrowUp) && 0 < num_suggestions {
+                    state.selected = (state.selected + num_suggestions - 1) % num_suggestions;
+                }
+                // Leave Enter and Tab to the `TextEdit` while the suggestions are loading:
+                if let Some(suggestion) = state.suggestions.get(state.selected) {
+                    if i.consume_key(Modifiers::NONE, Key::Enter)
+                        || i.consume_key(Modifiers::NONE, Key::Tab)
+                    {
+                        accepted = Some(suggestion.clone());
+                    }
+                }
+                if i.consume_key(Modifiers::NONE, Key::Escape) {
+                    state.dismissed_query = Some(char_range(text.as_str(), &state.query_range));
+                }
+            });
+        }
+        let mut changed = false;
+        if let Some(suggestion) = accepted {
+            let range = state.query_range.clone();
+            if accept(ui, id, text, range, &suggestion, &mut state) {
+                changed = true;
+            }
+        }
+
+        let mut output = text_edit(text).id(id).show(ui);
+
+        let has_focus = output.response.has_focus();
+        let is_interacting_with_popup = state.popup_rect.is_some_and(|rect| {
+            ui.input(|i| {
+                (i.pointer.any_down() || i.pointer.any_released())
+                    && i.pointer
+                        .interact_pos()
+                        .is_some_and(|pos| rect.contains(pos))
+            })
+        });
+
+        let cursor = output
+            .state
+            .cursor
+            .char_range()
+            .filter(|cursor_range| cursor_range.is_empty())
+            .map(|cursor_range| cursor_range.primary);
+        let query_range =
+            cursor.and_then(|cursor| query_range(text.as_str(), cursor.index, trigger));
+
+        let mut suggestions = None;
+        if let Some(query_range) = &query_range {
+            let query = char_range(text.as_str(), query_range);
+            let is_dismissed = state.dismissed_query.as_ref() == Some(&query);
+            let is_empty = query.is_empty() && trigger.is_none();
+            if (has_focus || is_interacting_with_popup) && !is_dismissed && !is_empty {
+                suggestions = Some(source.suggestions(&query).map(|mut suggestions| {
+                    suggestions.truncate(max_suggestions);
+                    suggestions
+                }));
+            }
+            if !is_dismissed {
+                state.dismissed_query = None;
+            }
+        }
+
+        let is_open = match &suggestions {
+            Some(Some(suggestions)) => !suggestions.is_empty(),
+            Some(None) => true, // Loading
+            None => false,
+        };
+
+        let new_query_range = query_range.unwrap_or_default();
+        if new_query_range != state.query_range {
+            state.selected = 0;
+        }
+        state.query_range = new_query_range;
+        state.suggestions = suggestions.clone().flatten().unwrap_or_default();
+        state.selected = state
+            .selected
+            .min(state.suggestions.len().saturating_sub(1));
+        state.is_open = is_open;
+        state.popup_rect = None;
+
+        if is_open {
+            // Keep Tab and Escape from moving focus away from the `TextEdit`:
+            ui.memory_mut(|mem| {
+                mem.set_focus_lock_filter(
+                    id,
+                    EventFilter {
+                        tab: true,
+                        horizontal_arrows: true,
+                        vertical_arrows: true,
+                        escape: true,
+                    },
+                );
+            });
+
+            let cursor_rect = cursor.map_or(output.response.rect, |cursor| {
+                output
+                    .galley
+                    .pos_from_cursor(cursor)
+                    .translate(output.galley_pos.to_vec2())
+                    .intersect(output.text_clip_rect)
+            });
+            let mut clicked = None;
+            let popup = Popup::new(
+                state_id,
+                ui.ctx().clone(),
+                cursor_rect,
+                output.response.layer_id,
+            )
+            .align(RectAlign::BOTTOM_START)
+            .close_behavior(PopupCloseBehavior::IgnoreClicks)
+            .show(|ui| {
+                ui.set_min_width(120.0);
+                match suggestions.flatten() {
+                    Some(suggestions) => {
+                        for (i, suggestion) in suggestions.iter().enumerate() {
+                            if ui
+                                .selectable_label(i == state.selected, suggestion)
+                                .clicked()
+                            {
+                                clicked = Some(suggestion.clone());
+                            }
+                        }
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.weak("Loading…");
+                        });
+                    }
+                }
+            });
+            state.popup_rect = popup.map(|popup| popup.response.rect);
+
+            if let Some(suggestion) = clicked {
+                let range = state.query_range.clone();
+                if accept(ui, id, text, range, &suggestion, &mut state) {
+                    changed = true;
+                }
+                ui.memory_mut(|mem| mem.request_focus(id));
+            }
+        }
+
+        if changed {
+            output.response.mark_changed();
+            if let Some(text_edit_state) = TextEditState::load(ui.ctx(), id) {
+                output.state = text_edit_state;
+            }
+        }
+        ui.data_mut(|data| data.insert_temp(state_id, state));
+
+        output
+    }
+}
+
+/// Replace the query with the suggestion, and move the cursor after it.
+///
+/// Returns `false` if the query is no longer in the text.
+fn accept(
+    ui: &Ui,
+    id: Id,
+    text: &mut dyn TextBuffer,
+    query_range: Range<usize>,
+    suggestion: &str,
+    state: &mut AutocompleteState,
+) -> bool {
+    if text.as_str().chars().count() < query_range.end {
+        return false;
+    }
+
+    text.delete_char_range(query_range.clone());
+    let len = text.insert_text(suggestion, query_range.start);
+    let cursor = CCursor::new(query_range.start + len);
+
+    let mut text_edit_state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
+    text_edit_state
+        .cursor
+        .set_char_range(Some(CCursorRange::one(cursor)));
+    text_edit_state.store(ui.ctx(), id);
+
+    state.dismissed_query = Some(suggestion.to_owned());
+    state.is_open = false;
+    ui.ctx().request_repaint();
+    true
+}
+
+/// The characters in the given range.
+fn char_range(text: &str, range: &Range<usize>) -> String {
+    text.chars().skip(range.start).take(range.len()).collect()
+}
+
+/// The character range of what the user is typing at the cursor.
+fn query_range(text: &str, cursor: usize, trigger: Option<char>) -> Option<Range<usize>> {
+    let before_cursor: Vec<char> = text.chars().take(cursor).collect();
+    if before_cursor.len() < cursor {
+        return None;
+    }
+
+    let Some(trigger) = trigger else {
+        let line_start = before_cursor
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |i| i + 1);
+        return Some(line_start..cursor);
+    };
+
+    for (i, c) in before_cursor.iter().enumerate().rev() {
+        if *c == trigger {
+            let is_word_start = i == 0 || before_cursor[i - 1].is_whitespace();
+            return is_word_start.then_some(i + 1..cursor);
+        }
+        if c.is_whitespace() {
+            return None;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_range() {
+        assert_eq!(query_range("New Y", 5, None), Some(0..5));
+        assert_eq!(query_range("a\nbc", 4, None), Some(2..4));
+        assert_eq!(query_range("hi @al", 6, Some('@')), Some(4..6));
+        assert_eq!(query_range("hi @", 4, Some('@')), Some(4..4));
+        assert_eq!(query_range("a@b", 3, Some('@')), None);
+        assert_eq!(query_range("@al ice", 7, Some('@')), None);
+    }
+
+    #[test]
+    fn test_filter_suggestions() {
+        let cities = ["Berlin", "Stockholm", "Stuttgart", "Gothenburg"];
+        assert_eq!(
+            filter_suggestions(&cities, "st"),
+            vec!["Stockholm", "Stuttgart"]
+        );
+        assert_eq!(
+            filter_suggestions(&cities, "G"),
+            vec!["Gothenburg", "Stuttgart"]
+        );
+    }
+}
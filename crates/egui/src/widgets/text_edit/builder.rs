@@ -3,23 +3,31 @@ use std::sync::Arc;
 use emath::{Rect, TSTransform};
 use epaint::{
     StrokeKind,
-    text::{Galley, LayoutJob, cursor::CCursor},
+    text::{Galley, LayoutJob},
 };
 
 use crate::{
-    Align, Align2, Color32, Context, CursorIcon, Event, EventFilter, FontSelection, Id, ImeEvent,
-    Key, KeyboardShortcut, Margin, Modifiers, NumExt as _, Response, Sense, Shape, TextBuffer,
-    TextStyle, TextWrapMode, Ui, Vec2, Widget, WidgetInfo, WidgetText, WidgetWithState, epaint,
-    os::OperatingSystem,
+    Align, Align2, Color32, Context, CursorIcon, Event, EventFilter, FontSelection, Id, Key,
+    KeyboardShortcut, Margin, Modifiers, NumExt as _, Response, Sense, Shape, Stroke, TextBuffer,
+    TextStyle, TextWrapMode, Tooltip, Ui, Vec2, Widget, WidgetInfo, WidgetText, WidgetWithState,
+    epaint,
     output::OutputEvent,
     response, text_selection,
-    text_selection::{CCursorRange, text_cursor_state::cursor_rect, visuals::paint_text_selection},
+    text_selection::{
+        CCursorRange, FindInPageState,
+        text_cursor_state::{ccursor_next_word, ccursor_previous_word, cursor_rect},
+        visuals::paint_text_selection,
+    },
     vec2,
 };
 
-use super::{TextEditOutput, TextEditState};
+use super::{
+    InputMask, SpellChecker, TextEditOutput, TextEditState, TextValidator,
+    editing::{self, Boundary},
+    rich_text_document, spell_checker,
+};
 
-type LayouterFn<'t> = &'t mut dyn FnMut(&Ui, &dyn TextBuffer, f32) -> Arc<Galley>;
+pub(super) type LayouterFn<'t> = &'t mut dyn FnMut(&Ui, &dyn TextBuffer, f32) -> Arc<Galley>;
 
 /// A text region that the user can edit the contents of.
 ///
@@ -66,29 +74,34 @@ type LayouterFn<'t> = &'t mut dyn FnMut(&Ui, &dyn TextBuffer, f32) -> Arc<Galley
 /// The background color of a [`crate::TextEdit`] is [`crate::Visuals::text_edit_bg_color`] or can be set with [`crate::TextEdit::background_color`].
 #[must_use = "You should put this widget in a ui with `ui.add(widget);`"]
 pub struct TextEdit<'t> {
-    text: &'t mut dyn TextBuffer,
-    hint_text: WidgetText,
-    hint_text_font: Option<FontSelection>,
-    id: Option<Id>,
-    id_salt: Option<Id>,
-    font_selection: FontSelection,
-    text_color: Option<Color32>,
-    layouter: Option<LayouterFn<'t>>,
-    password: bool,
-    frame: bool,
-    margin: Margin,
-    multiline: bool,
-    interactive: bool,
-    desired_width: Option<f32>,
-    desired_height_rows: usize,
-    event_filter: EventFilter,
-    cursor_at_end: bool,
-    min_size: Vec2,
-    align: Align2,
-    clip_text: bool,
-    char_limit: usize,
-    return_key: Option<KeyboardShortcut>,
-    background_color: Option<Color32>,
+    pub(super) text: &'t mut dyn TextBuffer,
+    pub(super) hint_text: WidgetText,
+    pub(super) hint_text_font: Option<FontSelection>,
+    pub(super) id: Option<Id>,
+    pub(super) id_salt: Option<Id>,
+    pub(super) font_selection: FontSelection,
+    pub(super) text_color: Option<Color32>,
+    pub(super) layouter: Option<LayouterFn<'t>>,
+    pub(super) password: bool,
+    pub(super) frame: bool,
+    pub(super) margin: Margin,
+    pub(super) multiline: bool,
+    pub(super) interactive: bool,
+    pub(super) desired_width: Option<f32>,
+    pub(super) desired_height_rows: usize,
+    pub(super) event_filter: EventFilter,
+    pub(super) cursor_at_end: bool,
+    pub(super) min_size: Vec2,
+    pub(super) align: Align2,
+    pub(super) clip_text: bool,
+    pub(super) char_limit: usize,
+    pub(super) return_key: Option<KeyboardShortcut>,
+    pub(super) background_color: Option<Color32>,
+    pub(super) virtualized: bool,
+    pub(super) spell_checker: Option<&'t dyn SpellChecker>,
+    pub(super) mask: Option<InputMask>,
+    pub(super) char_filter: Option<&'t dyn Fn(char) -> bool>,
+    pub(super) validator: Option<&'t dyn TextValidator>,
 }
 
 impl WidgetWithState for TextEdit<'_> {
@@ -148,6 +161,50 @@ impl<'t> TextEdit<'t> {
             char_limit: usize::MAX,
             return_key: Some(KeyboardShortcut::new(Modifiers::NONE, Key::Enter)),
             background_color: None,
+            virtualized: false,
+            spell_checker: None,
+            mask: None,
+            char_filter: None,
+            validator: None,
+        }
+    }
+
+    /// A multiline [`TextEdit`] that only lays out and paints the lines that are visible,
+    /// e.g. in a [`crate::ScrollArea`].
+    ///
+    /// Use this for very large documents, like log files of tens of megabytes.
+    /// The text needs to be a [`LineBuffer`](super::LineBuffer), e.g. a [`LineIndexedString`](super::LineIndexedString).
+    /// Other buffers are shown like [`Self::multiline`].
+    ///
+    /// Lines are never wrapped, and each line must be laid out as a single row of the font's row height.
+    /// A custom [`Self::layouter`] is only given the visible lines.
+    /// Undo and redo are not supported, since they would need copies of the whole document.
+    ///
+    /// Typing, pasting, copying and IME input work like in a normal [`TextEdit`],
+    /// but these options are ignored:
+    /// * [`Self::horizontal_align`], [`Self::vertical_align`] and [`Self::clip_text`]: the text is always left-top aligned and clipped.
+    /// * [`Self::spell_checker`]: only the visible lines are laid out, so there is nothing to check the rest with.
+    /// * [`Self::mask`]: formatting would rewrite the whole document on every keystroke.
+    /// * The wrap width given to a custom [`Self::layouter`], which is always infinite.
+    ///
+    /// [`TextEditOutput::galley`] only contains the visible lines,
+    /// while [`TextEditOutput::cursor_range`] is for the whole text.
+    /// Setting the cursor range of the [`TextEditState`] from outside, e.g. to show a search result,
+    /// scrolls it into view.
+    ///
+    /// ```
+    /// # egui::__run_test_ui(|ui| {
+    /// # let log_file = String::new();
+    /// let mut log = egui::LineIndexedString::new(log_file);
+    /// egui::ScrollArea::both().show(ui, |ui| {
+    ///     ui.add(egui::TextEdit::virtualized(&mut log).code_editor());
+    /// });
+    /// # });
+    /// ```
+    pub fn virtualized(text: &'t mut dyn TextBuffer) -> Self {
+        Self {
+            virtualized: true,
+            ..Self::multiline(text)
         }
     }
 
@@ -281,6 +338,38 @@ impl<'t> TextEdit<'t> {
         self
     }
 
+    /// Underline misspelled words with a wavy line,
+    /// and suggest replacements when right-clicking them.
+    ///
+    /// This works together with a custom [`Self::layouter`],
+    /// but is ignored for [`Self::password`] fields and in [`Self::virtualized`] mode.
+    ///
+    /// ```
+    /// # egui::__run_test_ui(|ui| {
+    /// # let mut my_string = String::new();
+    /// struct NoDigits;
+    ///
+    /// impl egui::SpellChecker for NoDigits {
+    ///     fn misspelled(&self, text: &str) -> Vec<std::ops::Range<usize>> {
+    ///         text.match_indices(|c: char| c.is_ascii_digit())
+    ///             .map(|(i, digit)| i..i + digit.len())
+    ///             .collect()
+    ///     }
+    ///
+    ///     fn suggestions(&self, _word: &str) -> Vec<String> {
+    ///         vec![]
+    ///     }
+    /// }
+    ///
+    /// ui.add(egui::TextEdit::multiline(&mut my_string).spell_checker(&NoDigits));
+    /// # });
+    /// ```
+    #[inline]
+    pub fn spell_checker(mut self, spell_checker: &'t dyn SpellChecker) -> Self {
+        self.spell_checker = Some(spell_checker);
+        self
+    }
+
     /// Default is `true`. If set to `false` then you cannot interact with the text (neither edit or select it).
     ///
     /// Consider using [`Ui::add_enabled`] instead to also give the [`TextEdit`] a greyed out look.
@@ -364,6 +453,49 @@ impl<'t> TextEdit<'t> {
         self
     }
 
+    /// Format the text into a pattern while the user types, e.g. `"(999) 999-9999"`.
+    ///
+    /// If no [`Self::hint_text`] is set, the pattern is shown as the hint text.
+    ///
+    /// See [`InputMask`] for the syntax.
+    ///
+    /// Not supported by [`Self::virtualized`] and [`crate::RichTextDocument`],
+    /// since formatting rewrites the whole text. The mask is ignored there.
+    #[inline]
+    pub fn mask(mut self, mask: impl Into<InputMask>) -> Self {
+        if self.virtualized || self.text.as_rich_text().is_some() {
+            #[cfg(feature = "log")]
+            log::warn!("TextEdit::mask is ignored for virtualized and rich text");
+        } else {
+            self.mask = Some(mask.into());
+        }
+        self
+    }
+
+    /// Only allow typing and pasting characters for which this returns `true`.
+    ///
+    /// ```
+    /// # egui::__run_test_ui(|ui| {
+    /// # let mut amount = String::new();
+    /// let is_currency_char = |c: char| c.is_ascii_digit() || c == '.';
+    /// ui.add(egui::TextEdit::singleline(&mut amount).char_filter(&is_currency_char));
+    /// # });
+    /// ```
+    #[inline]
+    pub fn char_filter(mut self, char_filter: &'t dyn Fn(char) -> bool) -> Self {
+        self.char_filter = Some(char_filter);
+        self
+    }
+
+    /// Check the text every frame, and show the error in a tooltip of the [`TextEdit`] if it is invalid.
+    ///
+    /// See [`TextValidator`].
+    #[inline]
+    pub fn validator(mut self, validator: &'t dyn TextValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
     /// Set the horizontal align of the inner text.
     #[inline]
     pub fn horizontal_align(mut self, align: Align) -> Self {
@@ -434,7 +566,18 @@ impl TextEdit<'_> {
         if frame {
             let visuals = ui.style().interact(&output.response);
             let frame_rect = output.response.rect.expand(visuals.expansion);
-            let shape = if is_mutable {
+            let shape = if output.validation_error.is_some() {
+                epaint::RectShape::new(
+                    frame_rect,
+                    visuals.corner_radius,
+                    background_color,
+                    Stroke::new(
+                        visuals.bg_stroke.width.at_least(1.0),
+                        ui.visuals().error_fg_color,
+                    ),
+                    StrokeKind::Inside,
+                )
+            } else if is_mutable {
                 if output.response.has_focus() {
                     epaint::RectShape::new(
                         frame_rect,
@@ -465,10 +608,29 @@ impl TextEdit<'_> {
             ui.painter().set(where_to_put_background, shape);
         }
 
+        if let Some(error) = &output.validation_error {
+            // Show the error in a tooltip, so that it doesn't change the layout of the `Ui`:
+            let response = &output.response;
+            if response.hovered() || response.has_focus() {
+                let error_color = ui.visuals().error_fg_color;
+                Tooltip::for_widget(response).show(|ui| ui.colored_label(error_color, error));
+            }
+        }
+
         output
     }
 
-    fn show_content(self, ui: &mut Ui) -> TextEditOutput {
+    fn show_content(mut self, ui: &mut Ui) -> TextEditOutput {
+        if self.hint_text.is_empty() {
+            if let Some(mask) = &self.mask {
+                self.hint_text = mask.placeholder().into();
+            }
+        }
+
+        if self.virtualized && self.text.as_line_buffer_mut().is_some() {
+            return super::virtualized::show(ui, self);
+        }
+
         let TextEdit {
             text,
             hint_text,
@@ -493,6 +655,11 @@ impl TextEdit<'_> {
             char_limit,
             return_key,
             background_color: _,
+            virtualized: _,
+            spell_checker,
+            mask,
+            char_filter,
+            validator,
         } = self;
 
         let text_color = text_color
@@ -514,18 +681,42 @@ impl TextEdit<'_> {
             desired_width.min(available_width)
         };
 
+        let spell_checker = spell_checker.filter(|_| !password);
+
         let font_id_clone = font_id.clone();
         let mut default_layouter = move |ui: &Ui, text: &dyn TextBuffer, wrap_width: f32| {
-            let text = mask_if_password(password, text.as_str());
-            let layout_job = if multiline {
-                LayoutJob::simple(text, font_id_clone.clone(), text_color, wrap_width)
+            let mut layout_job = if let Some(document) = text.as_rich_text().filter(|_| !password) {
+                let wrap_width = if multiline { wrap_width } else { f32::INFINITY };
+                let mut layout_job =
+                    document.layout_job(ui.style(), font_id_clone.clone(), text_color, wrap_width);
+                layout_job.break_on_newline = multiline;
+                layout_job
             } else {
-                LayoutJob::simple_singleline(text, font_id_clone.clone(), text_color)
+                let text = mask_if_password(password, text.as_str());
+                if multiline {
+                    LayoutJob::simple(text, font_id_clone.clone(), text_color, wrap_width)
+                } else {
+                    LayoutJob::simple_singleline(text, font_id_clone.clone(), text_color)
+                }
             };
+            if let Some(spell_checker) = spell_checker {
+                spell_checker::underline_misspelled_in_job(ui, spell_checker, &mut layout_job);
+            }
             ui.fonts(|f| f.layout_job(layout_job))
         };
 
-        let layouter = layouter.unwrap_or(&mut default_layouter);
+        let mut spell_checked_layouter;
+        let layouter: LayouterFn<'_> = match (layouter, spell_checker) {
+            (Some(layouter), Some(spell_checker)) => {
+                spell_checked_layouter = |ui: &Ui, text: &dyn TextBuffer, wrap_width: f32| {
+                    let galley = layouter(ui, text, wrap_width);
+                    spell_checker::underline_misspelled(ui, spell_checker, galley)
+                };
+                &mut spell_checked_layouter
+            }
+            (Some(layouter), None) => layouter,
+            (None, _) => &mut default_layouter,
+        };
 
         let mut galley = layouter(ui, text, wrap_width);
 
@@ -604,6 +795,20 @@ impl TextEdit<'_> {
                     is_being_dragged,
                 );
 
+                if let Some(url) = text
+                    .as_rich_text()
+                    .and_then(|document| document.link_at(cursor_at_pointer.index))
+                {
+                    if ui.input(|i| i.modifiers.command) {
+                        if response.hovered() {
+                            ui.ctx().set_cursor_icon(CursorIcon::PointingHand);
+                        }
+                        if response.clicked() {
+                            ui.ctx().open_url(crate::OpenUrl::new_tab(url));
+                        }
+                    }
+                }
+
                 if did_interact || response.clicked() {
                     ui.memory_mut(|mem| mem.request_focus(response.id));
 
@@ -639,6 +844,8 @@ impl TextEdit<'_> {
                 password,
                 default_cursor_range,
                 char_limit,
+                mask.as_ref(),
+                char_filter,
                 event_filter,
                 return_key,
             );
@@ -691,6 +898,10 @@ impl TextEdit<'_> {
             false
         };
 
+        if !text.is_mutable() {
+            FindInPageState::on_galley(ui, &painter, &response, galley_pos, &galley);
+        }
+
         if ui.is_rect_visible(rect) {
             if text.as_str().is_empty() && !hint_text.is_empty() {
                 let hint_text_color = ui.visuals().weak_text_color();
@@ -775,7 +986,7 @@ impl TextEdit<'_> {
                         // Set IME output (in screen coords) when text is editable and visible
                         let to_global = ui
                             .ctx()
-                            .layer_transform_to_global(ui.layer_id())
+                            .layer_affine_to_global(ui.layer_id())
                             .unwrap_or_default();
 
                         ui.ctx().output_mut(|o| {
@@ -789,6 +1000,38 @@ impl TextEdit<'_> {
             }
         }
 
+        if let Some(spell_checker) = spell_checker {
+            if text.is_mutable() && interactive {
+                let replacement = spell_checker::context_menu(
+                    ui,
+                    &response,
+                    spell_checker,
+                    text,
+                    &galley,
+                    galley_pos,
+                );
+                if let Some((selection, suggestion)) = replacement {
+                    let filter = editing::InsertFilter {
+                        password,
+                        char_limit,
+                        char_filter,
+                    };
+                    let old_cursor_range = cursor_range.unwrap_or(selection);
+                    feed_undoer(ui, &state, text, old_cursor_range);
+                    if let Some(ccursor) = filter.replace_selection(text, &selection, &suggestion) {
+                        let mut new_cursor_range = CCursorRange::one(ccursor);
+                        if let Some(mask) = &mask {
+                            new_cursor_range = mask.apply(text, new_cursor_range);
+                        }
+                        state.cursor.set_char_range(Some(new_cursor_range));
+                        feed_undoer(ui, &state, text, new_cursor_range);
+                        cursor_range = Some(new_cursor_range);
+                        response.mark_changed();
+                    }
+                }
+            }
+        }
+
         // Ensures correct IME behavior when the text input area gains or loses focus.
         if state.ime_enabled && (response.gained_focus() || response.lost_focus()) {
             state.ime_enabled = false;
@@ -845,11 +1088,14 @@ impl TextEdit<'_> {
                 id,
                 cursor_range,
                 role,
-                TSTransform::from_translation(galley_pos.to_vec2()),
+                emath::Affine2::from_translation(galley_pos.to_vec2()),
                 &galley,
             );
         }
 
+        let validation_error =
+            validator.and_then(|validator| validator.validate(text.as_str()).err());
+
         TextEditOutput {
             response,
             galley,
@@ -857,6 +1103,7 @@ impl TextEdit<'_> {
             text_clip_rect,
             state,
             cursor_range,
+            validation_error,
         }
     }
 }
@@ -893,6 +1140,8 @@ fn events(
     password: bool,
     default_cursor_range: CCursorRange,
     char_limit: usize,
+    mask: Option<&InputMask>,
+    char_filter: Option<&dyn Fn(char) -> bool>,
     event_filter: EventFilter,
     return_key: Option<KeyboardShortcut>,
 ) -> (bool, CCursorRange) {
@@ -902,15 +1151,12 @@ fn events(
 
     // We feed state to the undoer both before and after handling input
     // so that the undoer creates automatic saves even when there are no events for a while.
-    state.undoer.lock().feed_state(
-        ui.input(|i| i.time),
-        &(cursor_range, text.as_str().to_owned()),
-    );
-
-    let copy_if_not_password = |ui: &Ui, text: String| {
-        if !password {
-            ui.ctx().copy_text(text);
-        }
+    feed_undoer(ui, state, text, cursor_range);
+
+    let filter = editing::InsertFilter {
+        password,
+        char_limit,
+        char_filter,
     };
 
     let mut any_change = false;
@@ -928,59 +1174,26 @@ fn events(
             // First handle events that only changes the selection cursor, not the text:
             event if cursor_range.on_event(os, event, galley, id) => None,
 
-            Event::Copy => {
-                if cursor_range.is_empty() {
-                    None
-                } else {
-                    copy_if_not_password(ui, cursor_range.slice_str(text.as_str()).to_owned());
-                    None
-                }
-            }
-            Event::Cut => {
-                if cursor_range.is_empty() {
-                    None
-                } else {
-                    copy_if_not_password(ui, cursor_range.slice_str(text.as_str()).to_owned());
-                    Some(CCursorRange::one(text.delete_selected(&cursor_range)))
-                }
-            }
-            Event::Paste(text_to_insert) => {
-                if !text_to_insert.is_empty() {
-                    let mut ccursor = text.delete_selected(&cursor_range);
-
-                    text.insert_text_at(&mut ccursor, text_to_insert, char_limit);
-
-                    Some(CCursorRange::one(ccursor))
-                } else {
-                    None
-                }
+            Event::Copy | Event::Cut | Event::Paste(_) | Event::Text(_) | Event::Ime(_) => {
+                editing::on_event(ui, state, text, &cursor_range, event, filter)
             }
-            Event::Text(text_to_insert) => {
-                // Newlines are handled by `Key::Enter`.
-                if !text_to_insert.is_empty() && text_to_insert != "\n" && text_to_insert != "\r" {
-                    let mut ccursor = text.delete_selected(&cursor_range);
 
-                    text.insert_text_at(&mut ccursor, text_to_insert, char_limit);
-
-                    Some(CCursorRange::one(ccursor))
-                } else {
-                    None
-                }
-            }
             Event::Key {
                 key: Key::Tab,
                 pressed: true,
                 modifiers,
                 ..
             } if multiline => {
-                let mut ccursor = text.delete_selected(&cursor_range);
                 if modifiers.shift {
+                    let mut ccursor = text.delete_selected(&cursor_range);
                     // TODO(emilk): support removing indentation over a selection?
                     text.decrease_indentation(&mut ccursor);
+                    Some(CCursorRange::one(ccursor))
                 } else {
-                    text.insert_text_at(&mut ccursor, "\t", char_limit);
+                    filter
+                        .replace_selection(text, &cursor_range, "\t")
+                        .map(CCursorRange::one)
                 }
-                Some(CCursorRange::one(ccursor))
             }
             Event::Key {
                 key,
@@ -992,16 +1205,33 @@ fn events(
             }) =>
             {
                 if multiline {
-                    let mut ccursor = text.delete_selected(&cursor_range);
-                    text.insert_text_at(&mut ccursor, "\n", char_limit);
                     // TODO(emilk): if code editor, auto-indent by same leading tabs, + one if the lines end on an opening bracket
-                    Some(CCursorRange::one(ccursor))
+                    filter
+                        .replace_selection(text, &cursor_range, "\n")
+                        .map(CCursorRange::one)
                 } else {
                     ui.memory_mut(|mem| mem.surrender_focus(id)); // End input with enter
                     break;
                 }
             }
 
+            Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } if modifiers.matches_logically(Modifiers::COMMAND)
+                && rich_text_document::is_shortcut(*key)
+                && text.as_rich_text().is_some()
+                && !password =>
+            {
+                if let Some(document) = text.as_rich_text_mut() {
+                    rich_text_document::on_shortcut(document, *key, &cursor_range);
+                }
+                // The text is the same, but it needs to be laid out again:
+                Some(cursor_range)
+            }
+
             Event::Key {
                 key,
                 pressed: true,
@@ -1011,16 +1241,7 @@ fn events(
                 || (modifiers.matches_logically(Modifiers::SHIFT | Modifiers::COMMAND)
                     && *key == Key::Z) =>
             {
-                if let Some((redo_ccursor_range, redo_txt)) = state
-                    .undoer
-                    .lock()
-                    .redo(&(cursor_range, text.as_str().to_owned()))
-                {
-                    text.replace_with(redo_txt);
-                    Some(*redo_ccursor_range)
-                } else {
-                    None
-                }
+                undo_or_redo(state, text, cursor_range, true)
             }
 
             Event::Key {
@@ -1029,16 +1250,7 @@ fn events(
                 modifiers,
                 ..
             } if modifiers.matches_logically(Modifiers::COMMAND) => {
-                if let Some((undo_ccursor_range, undo_txt)) = state
-                    .undoer
-                    .lock()
-                    .undo(&(cursor_range, text.as_str().to_owned()))
-                {
-                    text.replace_with(undo_txt);
-                    Some(*undo_ccursor_range)
-                } else {
-                    None
-                }
+                undo_or_redo(state, text, cursor_range, false)
             }
 
             Event::Key {
@@ -1046,60 +1258,31 @@ fn events(
                 key,
                 pressed: true,
                 ..
-            } => check_for_mutating_key_press(os, &cursor_range, text, galley, modifiers, *key),
-
-            Event::Ime(ime_event) => match ime_event {
-                ImeEvent::Enabled => {
-                    state.ime_enabled = true;
-                    state.ime_cursor_range = cursor_range;
-                    None
-                }
-                ImeEvent::Preedit(text_mark) => {
-                    if text_mark == "\n" || text_mark == "\r" {
-                        None
-                    } else {
-                        // Empty prediction can be produced when user press backspace
-                        // or escape during IME, so we clear current text.
-                        let mut ccursor = text.delete_selected(&cursor_range);
-                        let start_cursor = ccursor;
-                        if !text_mark.is_empty() {
-                            text.insert_text_at(&mut ccursor, text_mark, char_limit);
-                        }
-                        state.ime_cursor_range = cursor_range;
-                        Some(CCursorRange::two(start_cursor, ccursor))
-                    }
-                }
-                ImeEvent::Commit(prediction) => {
-                    if prediction == "\n" || prediction == "\r" {
-                        None
-                    } else {
-                        state.ime_enabled = false;
-
-                        if !prediction.is_empty()
-                            && cursor_range.secondary.index
-                                == state.ime_cursor_range.secondary.index
-                        {
-                            let mut ccursor = text.delete_selected(&cursor_range);
-                            text.insert_text_at(&mut ccursor, prediction, char_limit);
-                            Some(CCursorRange::one(ccursor))
-                        } else {
-                            let ccursor = cursor_range.primary;
-                            Some(CCursorRange::one(ccursor))
-                        }
-                    }
-                }
-                ImeEvent::Disabled => {
-                    state.ime_enabled = false;
-                    None
-                }
-            },
+            } => editing::on_deleting_key_press(
+                os,
+                &cursor_range,
+                text,
+                |text, boundary, ccursor| match boundary {
+                    Boundary::PreviousWord => ccursor_previous_word(text.as_str(), ccursor),
+                    Boundary::NextWord => ccursor_next_word(text.as_str(), ccursor),
+                    Boundary::ParagraphBegin => galley.cursor_begin_of_paragraph(&ccursor),
+                    Boundary::ParagraphEnd => galley.cursor_end_of_paragraph(&ccursor),
+                },
+                modifiers,
+                *key,
+            ),
 
             _ => None,
         };
 
-        if let Some(new_ccursor_range) = did_mutate_text {
+        if let Some(mut new_ccursor_range) = did_mutate_text {
             any_change = true;
 
+            // Don't format the text under an IME preedit, only once it is committed:
+            if let Some(mask) = mask.filter(|_| !state.ime_enabled) {
+                new_ccursor_range = mask.apply(text, new_ccursor_range);
+            }
+
             // Layout again to avoid frame delay, and to keep `text` and `galley` in sync.
             *galley = layouter(ui, text, wrap_width);
 
@@ -1110,17 +1293,62 @@ fn events(
 
     state.cursor.set_char_range(Some(cursor_range));
 
-    state.undoer.lock().feed_state(
-        ui.input(|i| i.time),
-        &(cursor_range, text.as_str().to_owned()),
-    );
+    feed_undoer(ui, state, text, cursor_range);
 
     (any_change, cursor_range)
 }
 
+/// Let the undoer know about the current state.
+///
+/// A [`crate::RichTextDocument`] is remembered with its formatting, so that formatting changes can be undone.
+fn feed_undoer(ui: &Ui, state: &TextEditState, text: &dyn TextBuffer, cursor_range: CCursorRange) {
+    let time = ui.input(|i| i.time);
+    if let Some(document) = text.as_rich_text() {
+        state
+            .rich_text_undoer
+            .lock()
+            .feed_state(time, &(cursor_range, document.undo_snapshot()));
+    } else {
+        state
+            .undoer
+            .lock()
+            .feed_state(time, &(cursor_range, text.as_str().to_owned()));
+    }
+}
+
+/// Go back to the previous state (or forward again if `redo`), returning its cursor range.
+fn undo_or_redo(
+    state: &TextEditState,
+    text: &mut dyn TextBuffer,
+    cursor_range: CCursorRange,
+    redo: bool,
+) -> Option<CCursorRange> {
+    if let Some(document) = text.as_rich_text_mut() {
+        let current = (cursor_range, document.undo_snapshot());
+        let mut undoer = state.rich_text_undoer.lock();
+        let (cursor_range, restored) = if redo {
+            undoer.redo(&current)
+        } else {
+            undoer.undo(&current)
+        }?;
+        *document = restored.clone();
+        Some(*cursor_range)
+    } else {
+        let current = (cursor_range, text.as_str().to_owned());
+        let mut undoer = state.undoer.lock();
+        let (cursor_range, restored) = if redo {
+            undoer.redo(&current)
+        } else {
+            undoer.undo(&current)
+        }?;
+        text.replace_with(restored);
+        Some(*cursor_range)
+    }
+}
+
 // ----------------------------------------------------------------------------
 
-fn remove_ime_incompatible_events(events: &mut Vec<Event>) {
+pub(super) fn remove_ime_incompatible_events(events: &mut Vec<Event>) {
     // Remove key events which cause problems while 'IME' is being used.
     // See https://github.com/emilk/egui/pull/4509
     events.retain(|event| {
@@ -1138,79 +1366,3 @@ fn remove_ime_incompatible_events(events: &mut Vec<Event>) {
         )
     });
 }
-
-// ----------------------------------------------------------------------------
-
-/// Returns `Some(new_cursor)` if we did mutate `text`.
-fn check_for_mutating_key_press(
-    os: OperatingSystem,
-    cursor_range: &CCursorRange,
-    text: &mut dyn TextBuffer,
-    galley: &Galley,
-    modifiers: &Modifiers,
-    key: Key,
-) -> Option<CCursorRange> {
-    match key {
-        Key::Backspace => {
-            let ccursor = if modifiers.mac_cmd {
-                text.delete_paragraph_before_cursor(galley, cursor_range)
-            } else if let Some(cursor) = cursor_range.single() {
-                if modifiers.alt || modifiers.ctrl {
-                    // alt on mac, ctrl on windows
-                    text.delete_previous_word(cursor)
-                } else {
-                    text.delete_previous_char(cursor)
-                }
-            } else {
-                text.delete_selected(cursor_range)
-            };
-            Some(CCursorRange::one(ccursor))
-        }
-
-        Key::Delete if !modifiers.shift || os != OperatingSystem::Windows => {
-            let ccursor = if modifiers.mac_cmd {
-                text.delete_paragraph_after_cursor(galley, cursor_range)
-            } else if let Some(cursor) = cursor_range.single() {
-                if modifiers.alt || modifiers.ctrl {
-                    // alt on mac, ctrl on windows
-                    text.delete_next_word(cursor)
-                } else {
-                    text.delete_next_char(cursor)
-                }
-            } else {
-                text.delete_selected(cursor_range)
-            };
-            let ccursor = CCursor {
-                prefer_next_row: true,
-                ..ccursor
-            };
-            Some(CCursorRange::one(ccursor))
-        }
-
-        Key::H if modifiers.ctrl => {
-            let ccursor = text.delete_previous_char(cursor_range.primary);
-            Some(CCursorRange::one(ccursor))
-        }
-
-        Key::K if modifiers.ctrl => {
-            let ccursor = text.delete_paragraph_after_cursor(galley, cursor_range);
-            Some(CCursorRange::one(ccursor))
-        }
-
-        Key::U if modifiers.ctrl => {
-            let ccursor = text.delete_paragraph_before_cursor(galley, cursor_range);
-            Some(CCursorRange::one(ccursor))
-        }
-
-        Key::W if modifiers.ctrl => {
-            let ccursor = if let Some(cursor) = cursor_range.single() {
-                text.delete_previous_word(cursor)
-            } else {
-                text.delete_selected(cursor_range)
-            };
-            Some(CCursorRange::one(ccursor))
-        }
-
-        _ => None,
-    }
-}
@@ -0,0 +1,280 @@
+//! Text editing shared by the normal and the virtualized [`super::TextEdit`].
+
+use epaint::text::cursor::CCursor;
+
+use crate::{
+    Event, ImeEvent, Key, Modifiers, Ui, os::OperatingSystem, text_selection::CCursorRange,
+};
+
+use super::{TextBuffer, TextEditState, rich_text_document};
+
+/// What text may be typed or pasted into a [`super::TextEdit`].
+#[derive(Clone, Copy)]
+pub(super) struct InsertFilter<'a> {
+    /// Don't copy anything to the clipboard.
+    pub password: bool,
+
+    /// The maximum number of characters in the text.
+    pub char_limit: usize,
+
+    /// Drop the characters this returns `false` for.
+    pub char_filter: Option<&'a dyn Fn(char) -> bool>,
+}
+
+impl InsertFilter<'_> {
+    fn filter_chars(&self, text: &str) -> String {
+        match self.char_filter {
+            Some(char_filter) => text.chars().filter(|c| char_filter(*c)).collect(),
+            None => text.to_owned(),
+        }
+    }
+
+    /// Replace the selection with the characters of `text_to_insert` that pass the filter.
+    ///
+    /// Returns the new cursor, or `None` if none of the characters passed the filter.
+    pub fn replace_selection<T: TextBuffer + ?Sized>(
+        &self,
+        text: &mut T,
+        cursor_range: &CCursorRange,
+        text_to_insert: &str,
+    ) -> Option<CCursor> {
+        let text_to_insert = self.filter_chars(text_to_insert);
+        if text_to_insert.is_empty() {
+            return None;
+        }
+        let mut ccursor = text.delete_selected(cursor_range);
+        text.insert_text_at(&mut ccursor, &text_to_insert, self.char_limit);
+        Some(ccursor)
+    }
+
+    fn copy<T: TextBuffer + ?Sized>(&self, ui: &Ui, text: &T, cursor_range: &CCursorRange) {
+        if !self.password {
+            // `char_range` is fast for a `LineBuffer`:
+            let selected = text.char_range(cursor_range.as_sorted_char_range());
+            ui.ctx().copy_text(selected.to_owned());
+            if let Some(document) = text.as_rich_text() {
+                rich_text_document::copy(ui.ctx(), document, cursor_range);
+            }
+        }
+    }
+}
+
+/// Handle the events that edit the text the same way in every mode of a [`super::TextEdit`]:
+/// [`Event::Copy`], [`Event::Cut`], [`Event::Paste`], [`Event::Text`] and [`Event::Ime`].
+///
+/// Returns the new cursor range if the text was changed.
+/// Other events are ignored.
+pub(super) fn on_event<T: TextBuffer + ?Sized>(
+    ui: &Ui,
+    state: &mut TextEditState,
+    text: &mut T,
+    cursor_range: &CCursorRange,
+    event: &Event,
+    filter: InsertFilter<'_>,
+) -> Option<CCursorRange> {
+    let InsertFilter {
+        password,
+        char_limit,
+        char_filter,
+    } = filter;
+
+    match event {
+        Event::Copy => {
+            if !cursor_range.is_empty() {
+                filter.copy(ui, text, cursor_range);
+            }
+            None
+        }
+        Event::Cut => {
+            if cursor_range.is_empty() {
+                None
+            } else {
+                filter.copy(ui, text, cursor_range);
+                Some(CCursorRange::one(text.delete_selected(cursor_range)))
+            }
+        }
+        Event::Paste(text_to_insert) => {
+            // Keep the formatting of text copied from a rich text document:
+            let pasted_document = text
+                .as_rich_text()
+                .filter(|_| !password && char_filter.is_none() && char_limit == usize::MAX)
+                .and_then(|_| rich_text_document::pasted_document(ui.ctx(), text_to_insert));
+            if let Some(pasted_document) = pasted_document {
+                let ccursor = text.delete_selected(cursor_range);
+                let num_chars = text.as_rich_text_mut().map_or(0, |document| {
+                    document.insert_document(&pasted_document, ccursor.index)
+                });
+                Some(CCursorRange::one(CCursor::new(ccursor.index + num_chars)))
+            } else {
+                filter
+                    .replace_selection(text, cursor_range, text_to_insert)
+                    .map(CCursorRange::one)
+            }
+        }
+        Event::Text(text_to_insert) => {
+            // Newlines are handled by `Key::Enter`.
+            if text_to_insert != "\n" && text_to_insert != "\r" {
+                filter
+                    .replace_selection(text, cursor_range, text_to_insert)
+                    .map(CCursorRange::one)
+            } else {
+                None
+            }
+        }
+        Event::Ime(ime_event) => match ime_event {
+            ImeEvent::Enabled => {
+                state.ime_enabled = true;
+                state.ime_cursor_range = *cursor_range;
+                None
+            }
+            ImeEvent::Preedit(text_mark) => {
+                if text_mark == "\n" || text_mark == "\r" {
+                    None
+                } else {
+                    // Empty prediction can be produced when user press backspace
+                    // or escape during IME, so we clear current text.
+                    let text_mark = filter.filter_chars(text_mark);
+                    let mut ccursor = text.delete_selected(cursor_range);
+                    let start_cursor = ccursor;
+                    if !text_mark.is_empty() {
+                        text.insert_text_at(&mut ccursor, &text_mark, char_limit);
+                    }
+                    state.ime_cursor_range = *cursor_range;
+                    Some(CCursorRange::two(start_cursor, ccursor))
+                }
+            }
+            ImeEvent::Commit(prediction) => {
+                if prediction == "\n" || prediction == "\r" {
+                    None
+                } else {
+                    state.ime_enabled = false;
+
+                    if !prediction.is_empty()
+                        && cursor_range.secondary.index == state.ime_cursor_range.secondary.index
+                    {
+                        let mut ccursor = text.delete_selected(cursor_range);
+                        text.insert_text_at(
+                            &mut ccursor,
+                            &filter.filter_chars(prediction),
+                            char_limit,
+                        );
+                        Some(CCursorRange::one(ccursor))
+                    } else {
+                        let ccursor = cursor_range.primary;
+                        Some(CCursorRange::one(ccursor))
+                    }
+                }
+            }
+            ImeEvent::Disabled => {
+                state.ime_enabled = false;
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Where the words and paragraphs around a cursor begin and end,
+/// for the key presses in [`on_deleting_key_press`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Boundary {
+    PreviousWord,
+    NextWord,
+    ParagraphBegin,
+    ParagraphEnd,
+}
+
+/// Handle the key presses that delete text, like backspace, the same way in every mode of a [`super::TextEdit`].
+///
+/// `find` finds a [`Boundary`] from a cursor.
+/// Returns the new cursor range if the key press was handled.
+pub(super) fn on_deleting_key_press<T: TextBuffer + ?Sized>(
+    os: OperatingSystem,
+    cursor_range: &CCursorRange,
+    text: &mut T,
+    find: impl Fn(&T, Boundary, CCursor) -> CCursor,
+    modifiers: &Modifiers,
+    key: Key,
+) -> Option<CCursorRange> {
+    let delete_paragraph_before_cursor = |text: &mut T| {
+        let [min, max] = cursor_range.sorted_cursors();
+        let min = find(text, Boundary::ParagraphBegin, min);
+        if min == max {
+            text.delete_previous_char(min)
+        } else {
+            text.delete_selected(&CCursorRange::two(min, max))
+        }
+    };
+    let delete_paragraph_after_cursor = |text: &mut T| {
+        let [min, max] = cursor_range.sorted_cursors();
+        let max = find(text, Boundary::ParagraphEnd, max);
+        if min == max {
+            text.delete_next_char(min)
+        } else {
+            text.delete_selected(&CCursorRange::two(min, max))
+        }
+    };
+    let delete_previous_word = |text: &mut T, max: CCursor| {
+        let min = find(text, Boundary::PreviousWord, max);
+        text.delete_selected_ccursor_range([min, max])
+    };
+
+    match key {
+        Key::Backspace => {
+            let ccursor = if modifiers.mac_cmd {
+                delete_paragraph_before_cursor(text)
+            } else if let Some(cursor) = cursor_range.single() {
+                if modifiers.alt || modifiers.ctrl {
+                    // alt on mac, ctrl on windows
+                    delete_previous_word(text, cursor)
+                } else {
+                    text.delete_previous_char(cursor)
+                }
+            } else {
+                text.delete_selected(cursor_range)
+            };
+            Some(CCursorRange::one(ccursor))
+        }
+
+        Key::Delete if !modifiers.shift || os != OperatingSystem::Windows => {
+            let ccursor = if modifiers.mac_cmd {
+                delete_paragraph_after_cursor(text)
+            } else if let Some(cursor) = cursor_range.single() {
+                if modifiers.alt || modifiers.ctrl {
+                    // alt on mac, ctrl on windows
+                    let max = find(text, Boundary::NextWord, cursor);
+                    text.delete_selected_ccursor_range([cursor, max])
+                } else {
+                    text.delete_next_char(cursor)
+                }
+            } else {
+                text.delete_selected(cursor_range)
+            };
+            let ccursor = CCursor {
+                prefer_next_row: true,
+                ..ccursor
+            };
+            Some(CCursorRange::one(ccursor))
+        }
+
+        Key::H if modifiers.ctrl => {
+            let ccursor = text.delete_previous_char(cursor_range.primary);
+            Some(CCursorRange::one(ccursor))
+        }
+
+        Key::K if modifiers.ctrl => Some(CCursorRange::one(delete_paragraph_after_cursor(text))),
+
+        Key::U if modifiers.ctrl => Some(CCursorRange::one(delete_paragraph_before_cursor(text))),
+
+        Key::W if modifiers.ctrl => {
+            let ccursor = if let Some(cursor) = cursor_range.single() {
+                delete_previous_word(text, cursor)
+            } else {
+                text.delete_selected(cursor_range)
+            };
+            Some(CCursorRange::one(ccursor))
+        }
+
+        _ => None,
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    TextBuffer,
+    text::{CCursor, CCursorRange},
+};
+
+/// What a single slot in an [`InputMask`] accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Slot {
+    /// `9`
+    Digit,
+
+    /// `a`
+    Letter,
+
+    /// `*`
+    Alphanumeric,
+}
+
+impl Slot {
+    fn accepts(self, c: char) -> bool {
+        match self {
+            Self::Digit => c.is_ascii_digit(),
+            Self::Letter => c.is_alphabetic(),
+            Self::Alphanumeric => c.is_alphanumeric(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(char),
+    Slot(Slot),
+}
+
+/// A pattern that the text of a [`crate::TextEdit`] is formatted into while the user types,
+/// e.g. `"(999) 999-9999"` for a phone number.
+///
+/// In the pattern, `9` is a digit, `a` is a letter and `*` is a letter or a digit.
+/// Any other character is a literal that is inserted automatically, and `\` escapes the next character.
+///
+/// Typed and pasted characters that don't fit the next slot are dropped,
+/// and literals are only shown up to the last filled slot.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// # let mut phone_number = String::new();
+/// ui.add(egui::TextEdit::singleline(&mut phone_number).mask(egui::InputMask::new("(999) 999-9999")));
+/// # });
+/// ```
+///
+/// See also [`crate::TextEdit::validator`] to check that the mask has been filled in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputMask {
+    parts: Vec<Part>,
+}
+
+impl InputMask {
+    pub fn new(pattern: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            parts.push(match c {
+                '9' => Part::Slot(Slot::Digit),
+                'a' => Part::Slot(Slot::Letter),
+                '*' => Part::Slot(Slot::Alphanumeric),
+                '\\' => Part::Literal(chars.next().unwrap_or('\\')),
+                c => Part::Literal(c),
+            });
+        }
+        Self { parts }
+    }
+
+    /// The number of characters the user can enter.
+    pub fn num_slots(&self) -> usize {
+        self.parts
+            .iter()
+            .filter(|part| matches!(part, Part::Slot(_)))
+            .count()
+    }
+
+    /// The pattern with each slot shown as `_`, e.g. `"(___) ___-____"`.
+    ///
+    /// Used as the hint text of a [`crate::TextEdit`] that has no other hint text.
+    pub fn placeholder(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(c) => *c,
+                Part::Slot(_) => '_',
+            })
+            .collect()
+    }
+
+    /// Format the given text into the mask.
+    ///
+    /// `cursor` is a character index into `text`, and the returned cursor is the
+    /// matching index into the formatted text, placed after the same number of entered characters.
+    pub fn format(&self, text: &str, cursor: usize) -> (String, usize) {
+        let mut formatted = String::new();
+        let mut formatted_len = 0; // in characters
+
+        // Literals after the last filled slot. They are only shown once the next slot is filled.
+        let mut pending = String::new();
+
+        // Character index in `formatted` after each filled slot.
+        let mut slot_ends = Vec::new();
+        let mut slots_before_cursor = None;
+
+        let mut part_index = 0;
+        for (char_index, c) in text.chars().enumerate() {
+            if char_index == cursor {
+                slots_before_cursor = Some(slot_ends.len());
+            }
+
+            while let Some(part) = self.parts.get(part_index) {
+                match *part {
+                    Part::Literal(literal) => {
+                        pending.push(literal);
+                        part_index += 1;
+                        if c == literal {
+                            break; // The user entered the literal
+                        }
+                    }
+                    Part::Slot(slot) => {
+                        if slot.accepts(c) {
+                            formatted_len += pending.chars().count() + 1;
+                            formatted.push_str(&pending);
+                            formatted.push(c);
+                            pending.clear();
+                            slot_ends.push(formatted_len);
+                            part_index += 1;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        let slots_before_cursor = slots_before_cursor.unwrap_or(slot_ends.len());
+        let cursor = slots_before_cursor
+            .checked_sub(1)
+            .map_or(0, |slot| slot_ends[slot]);
+        (formatted, cursor)
+    }
+
+    /// The characters the user entered, without the literals, e.g. `"5551234567"`.
+    pub fn unmasked(&self, text: &str) -> String {
+        let (formatted, _) = self.format(text, 0);
+        let mut chars = formatted.chars();
+        self.parts
+            .iter()
+            .filter_map(|part| {
+                let c = chars.next()?;
+                matches!(part, Part::Slot(_)).then_some(c)
+            })
+            .collect()
+    }
+
+    /// Have all slots been filled?
+    pub fn is_complete(&self, text: &str) -> bool {
+        self.unmasked(text).chars().count() == self.num_slots()
+    }
+
+    /// Format the text after an edit, returning the new cursor range.
+    pub(super) fn apply<T: TextBuffer + ?Sized>(
+        &self,
+        text: &mut T,
+        cursor_range: CCursorRange,
+    ) -> CCursorRange {
+        let (formatted, cursor) = self.format(text.as_str(), cursor_range.primary.index);
+        if formatted == text.as_str() {
+            cursor_range
+        } else {
+            text.replace_with(&formatted);
+            CCursorRange::one(CCursor::new(cursor))
+        }
+    }
+}
+
+impl From<&str> for InputMask {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phone_number() {
+        let mask = InputMask::new("(999) 999-9999");
+        assert_eq!(mask.placeholder(), "(___) ___-____");
+        assert_eq!(mask.format("", 0), (String::new(), 0));
+        assert_eq!(mask.format("5", 1), ("(5".to_owned(), 2));
+        assert_eq!(mask.format("(5551", 5), ("(555) 1".to_owned(), 7));
+        assert_eq!(
+            mask.format("555x1234567890", 3),
+            ("(555) 123-4567".to_owned(), 4)
+        );
+
+        // Deleting a literal moves the cursor to before it:
+        assert_eq!(mask.format("(555 12", 4), ("(555) 12".to_owned(), 4));
+
+        // Inserting in the middle:
+        assert_eq!(mask.format("(525) 1", 3), ("(525) 1".to_owned(), 3));
+
+        assert_eq!(mask.unmasked("(555) 123-45"), "55512345");
+        assert!(!mask.is_complete("(555) 123-45"));
+        assert!(mask.is_complete("5551234567"));
+    }
+
+    #[test]
+    fn escaped_literals() {
+        let mask = InputMask::new("\\9a-*");
+        assert_eq!(mask.format("x7", 2), ("9x-7".to_owned(), 4));
+    }
+}
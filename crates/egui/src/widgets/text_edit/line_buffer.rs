@@ -0,0 +1,480 @@
+use std::ops::Range;
+
+use epaint::text::cursor::CCursor;
+
+use crate::text_selection::text_cursor_state::byte_index_from_char_index;
+
+use super::TextBuffer;
+
+/// A [`TextBuffer`] that keeps an index of its lines.
+///
+/// This is what [`crate::TextEdit::virtualized`] needs to only lay out and paint the visible lines,
+/// so that it stays fast even for documents with millions of lines.
+///
+/// All indices are *character* indices, like in [`TextBuffer`].
+/// Lines are separated by `\n`, and a trailing newline starts a last, empty line.
+///
+/// Implementors should also implement [`TextBuffer::as_line_buffer_mut`] to return `Some(self)`.
+///
+/// See [`LineIndexedString`] for an implementation.
+pub trait LineBuffer: TextBuffer {
+    /// The number of lines. Always at least one.
+    fn num_lines(&self) -> usize;
+
+    /// The total number of characters.
+    fn num_chars(&self) -> usize;
+
+    /// The character index of the start of the given line.
+    ///
+    /// Returns [`Self::num_chars`] for lines past the end.
+    fn line_start_char_index(&self, line: usize) -> usize;
+
+    /// The line containing the given character index.
+    fn line_from_char_index(&self, char_index: usize) -> usize;
+
+    /// The character index of the end of the given line, before its newline.
+    fn line_end_char_index(&self, line: usize) -> usize {
+        if line + 1 < self.num_lines() {
+            self.line_start_char_index(line + 1) - 1
+        } else {
+            self.num_chars()
+        }
+    }
+
+    /// The text of the given lines, without the newline of the last one.
+    fn lines(&self, lines: Range<usize>) -> &str {
+        if lines.is_empty() {
+            return "";
+        }
+        let start = self.line_start_char_index(lines.start);
+        let end = self.line_end_char_index(lines.end - 1);
+        self.char_range(start..end)
+    }
+
+    /// The text of the given line, without its newline.
+    fn line(&self, line: usize) -> &str {
+        self.lines(line..line + 1)
+    }
+
+    /// Changes whenever the text changes, and is never the same for two different texts.
+    ///
+    /// Used to cache work on the whole text, e.g. when searching it.
+    fn generation(&self) -> u64;
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct LineStart {
+    byte: usize,
+    char: usize,
+}
+
+impl LineStart {
+    /// Move by `shift`, using wrapping arithmetic so that it can also move backwards.
+    #[inline]
+    fn shifted(self, shift: Self) -> Self {
+        Self {
+            byte: self.byte.wrapping_add(shift.byte),
+            char: self.char.wrapping_add(shift.char),
+        }
+    }
+
+    /// The shift that moves backwards by `self`.
+    #[inline]
+    fn negated(self) -> Self {
+        Self {
+            byte: self.byte.wrapping_neg(),
+            char: self.char.wrapping_neg(),
+        }
+    }
+}
+
+/// A [`String`] with an index of where each line starts.
+///
+/// Edits update the index incrementally: the lines after an edit are moved lazily,
+/// so typing in one place only costs time proportional to the length of the line.
+/// Converting between character and byte indices only needs to look at a single line.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// let mut log = egui::LineIndexedString::new("first line\nsecond line");
+/// egui::ScrollArea::vertical().show(ui, |ui| {
+///     ui.add(egui::TextEdit::virtualized(&mut log));
+/// });
+/// # });
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndexedString {
+    text: String,
+
+    /// Where each line starts. The first one is always at zero.
+    ///
+    /// The lines from [`Self::shift_from`] on still need to be moved by [`Self::shift`].
+    line_starts: Vec<LineStart>,
+
+    /// The first line that [`Self::shift`] applies to.
+    shift_from: usize,
+
+    /// How far the lines from [`Self::shift_from`] on have moved since they were stored,
+    /// see [`LineStart::shifted`].
+    shift: LineStart,
+
+    num_chars: usize,
+
+    /// See [`LineBuffer::generation`].
+    generation: u64,
+}
+
+/// A [`LineBuffer::generation`] that no text has had so far.
+fn next_generation() -> u64 {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+impl PartialEq for LineIndexedString {
+    fn eq(&self, other: &Self) -> bool {
+        // The index only depends on the text.
+        self.text == other.text
+    }
+}
+
+impl Eq for LineIndexedString {}
+
+impl Default for LineIndexedString {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl From<String> for LineIndexedString {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&str> for LineIndexedString {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<LineIndexedString> for String {
+    fn from(text: LineIndexedString) -> Self {
+        text.text
+    }
+}
+
+impl LineIndexedString {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let mut line_starts = vec![LineStart::default()];
+        let num_chars = index_lines(&text, LineStart::default(), &mut line_starts);
+        Self {
+            text,
+            line_starts,
+            shift_from: 0,
+            shift: LineStart::default(),
+            num_chars,
+            generation: next_generation(),
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Append text at the end, e.g. new lines of a log.
+    pub fn push_str(&mut self, text: &str) {
+        self.insert_text(text, self.num_chars);
+    }
+
+    fn line_start(&self, line: usize) -> LineStart {
+        match self.line_starts.get(line) {
+            Some(&start) if line < self.shift_from => start,
+            Some(&start) => start.shifted(self.shift),
+            None => LineStart {
+                byte: self.text.len(),
+                char: self.num_chars,
+            },
+        }
+    }
+
+    /// The number of lines whose start fulfills `is_before`, like [`slice::partition_point`].
+    fn partition_point(&self, is_before: impl Fn(LineStart) -> bool) -> usize {
+        let (stored, shifted) = self
+            .line_starts
+            .split_at(self.shift_from.min(self.line_starts.len()));
+        let num_stored = stored.partition_point(|start| is_before(*start));
+        if num_stored < stored.len() {
+            num_stored
+        } else {
+            stored.len() + shifted.partition_point(|start| is_before(start.shifted(self.shift)))
+        }
+    }
+
+    /// Move all the lines from `line` on by `shift`.
+    ///
+    /// Only the lines between `line` and [`Self::shift_from`] are touched,
+    /// so edits close to each other are cheap.
+    fn shift_lines_from(&mut self, line: usize, shift: LineStart) {
+        if self.shift_from < line {
+            // These lines are no longer moved lazily:
+            for start in &mut self.line_starts[self.shift_from..line] {
+                *start = start.shifted(self.shift);
+            }
+        } else {
+            // These lines are now moved lazily, including by the shift the later ones had so far:
+            let undo = self.shift.negated();
+            for start in &mut self.line_starts[line..self.shift_from] {
+                *start = start.shifted(undo);
+            }
+        }
+        self.shift_from = line;
+        self.shift = self.shift.shifted(shift);
+    }
+}
+
+/// Push the start of every line after a newline in `text`, which itself starts at `start`.
+///
+/// Returns the number of characters in `text`.
+fn index_lines(text: &str, start: LineStart, line_starts: &mut Vec<LineStart>) -> usize {
+    let mut num_chars = 0;
+    for (byte, c) in text.char_indices() {
+        num_chars += 1;
+        if c == '\n' {
+            line_starts.push(LineStart {
+                byte: start.byte + byte + 1,
+                char: start.char + num_chars,
+            });
+        }
+    }
+    num_chars
+}
+
+impl TextBuffer for LineIndexedString {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        let char_index = char_index.min(self.num_chars);
+        let line = self.line_from_char_index(char_index);
+        let start = LineStart {
+            byte: self.byte_index_from_char_index(char_index),
+            char: char_index,
+        };
+        self.text.insert_str(start.byte, text);
+
+        let mut new_starts = Vec::new();
+        let num_chars = index_lines(text, start, &mut new_starts);
+        self.shift_lines_from(
+            line + 1,
+            LineStart {
+                byte: text.len(),
+                char: num_chars,
+            },
+        );
+        // The new lines are after `shift_from`, so store them without the shift:
+        let undo = self.shift.negated();
+        self.line_starts.splice(
+            line + 1..line + 1,
+            new_starts.into_iter().map(|start| start.shifted(undo)),
+        );
+        self.num_chars += num_chars;
+        self.generation = next_generation();
+
+        num_chars
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        assert!(
+            char_range.start <= char_range.end,
+            "start must be <= end, but got {char_range:?}"
+        );
+        let start = char_range.start.min(self.num_chars);
+        let end = char_range.end.min(self.num_chars);
+
+        let first_line = self.line_from_char_index(start);
+        let last_line = self.line_from_char_index(end);
+        let byte_start = self.byte_index_from_char_index(start);
+        let byte_end = self.byte_index_from_char_index(end);
+
+        self.text.drain(byte_start..byte_end);
+        self.shift_lines_from(
+            last_line + 1,
+            LineStart {
+                byte: byte_end - byte_start,
+                char: end - start,
+            }
+            .negated(),
+        );
+        // The lines after the removed ones keep their shift:
+        self.line_starts.drain(first_line + 1..=last_line);
+        self.shift_from = first_line + 1;
+        self.num_chars -= end - start;
+        self.generation = next_generation();
+    }
+
+    fn char_range(&self, char_range: Range<usize>) -> &str {
+        let start = self.byte_index_from_char_index(char_range.start);
+        let end = self.byte_index_from_char_index(char_range.end);
+        &self.text[start..end]
+    }
+
+    fn byte_index_from_char_index(&self, char_index: usize) -> usize {
+        let line_start = self.line_start(self.line_from_char_index(char_index));
+        line_start.byte
+            + byte_index_from_char_index(
+                &self.text[line_start.byte..],
+                char_index - line_start.char,
+            )
+    }
+
+    fn char_index_from_byte_index(&self, byte_index: usize) -> usize {
+        let line = self.partition_point(|line_start| line_start.byte <= byte_index) - 1;
+        let line_start = self.line_start(line);
+        let byte_index = byte_index.min(self.text.len());
+        line_start.char + self.text[line_start.byte..byte_index].chars().count()
+    }
+
+    fn insert_text_at(&mut self, ccursor: &mut CCursor, text_to_insert: &str, char_limit: usize) {
+        // Like the default, but without counting all the characters of the document:
+        let room = char_limit.saturating_sub(self.num_chars);
+        let text_to_insert = match text_to_insert.char_indices().nth(room) {
+            None => text_to_insert,
+            Some((idx, _)) => &text_to_insert[..idx],
+        };
+        ccursor.index += self.insert_text(text_to_insert, ccursor.index);
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn replace_with(&mut self, text: &str) {
+        *self = Self::new(text);
+    }
+
+    fn take(&mut self) -> String {
+        std::mem::take(self).text
+    }
+
+    fn as_line_buffer_mut(&mut self) -> Option<&mut dyn LineBuffer> {
+        Some(self)
+    }
+
+    fn type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<Self>()
+    }
+}
+
+impl LineBuffer for LineIndexedString {
+    fn num_lines(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    fn num_chars(&self) -> usize {
+        self.num_chars
+    }
+
+    fn line_start_char_index(&self, line: usize) -> usize {
+        self.line_start(line).char
+    }
+
+    fn line_from_char_index(&self, char_index: usize) -> usize {
+        self.partition_point(|line_start| line_start.char <= char_index) - 1
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_index_is_up_to_date(text: &LineIndexedString) {
+        let fresh = LineIndexedString::new(text.as_str());
+        assert_eq!(text.num_lines(), fresh.num_lines(), "{:?}", text.as_str());
+        for line in 0..=fresh.num_lines() {
+            assert_eq!(
+                text.line_start(line),
+                fresh.line_start(line),
+                "{:?}",
+                text.as_str()
+            );
+        }
+        assert_eq!(text.num_chars, fresh.num_chars);
+    }
+
+    #[test]
+    fn edits_update_the_line_index() {
+        let mut text = LineIndexedString::new("héllo\nwörld\n");
+        assert_eq!(text.num_lines(), 3);
+        assert_eq!(text.line(1), "wörld");
+        assert_eq!(text.line(2), "");
+        assert_eq!(text.lines(0..2), "héllo\nwörld");
+        assert_eq!(text.line_from_char_index(6), 1);
+        assert_eq!(text.line_end_char_index(0), 5);
+
+        text.insert_text("a\nb", 3);
+        assert_eq!(text.as_str(), "héla\nblo\nwörld\n");
+        assert_index_is_up_to_date(&text);
+
+        text.delete_char_range(2..12);
+        assert_eq!(text.as_str(), "héld\n");
+        assert_index_is_up_to_date(&text);
+
+        text.push_str("ä\nö");
+        assert_eq!(text.line(2), "ö");
+        assert_index_is_up_to_date(&text);
+
+        assert_eq!(text.byte_index_from_char_index(7), "héld\nä\n".len());
+        assert_eq!(text.char_index_from_byte_index("héld\nä\n".len()), 7);
+        assert_eq!(text.char_range(1..6), "éld\nä");
+    }
+
+    #[test]
+    fn edits_before_and_after_each_other() {
+        let mut text = LineIndexedString::new("a\nb\nc\nd\ne\n");
+        for (char_index, insert) in [(6, "x\ny"), (2, "ü"), (12, "\n"), (0, "z\n"), (9, "w")] {
+            text.insert_text(insert, char_index);
+            assert_index_is_up_to_date(&text);
+        }
+        for range in [10..13, 1..4, 5..6, 0..text.num_chars()] {
+            text.delete_char_range(range);
+            assert_index_is_up_to_date(&text);
+        }
+        assert_eq!(text, LineIndexedString::default());
+    }
+    #[test]
+    fn generation_changes_with_the_text() {
+        let mut text = LineIndexedString::new("a\nb");
+        let mut seen = vec![text.generation()];
+        text.push_str("c");
+        seen.push(text.generation());
+        text.delete_char_range(0..1);
+        seen.push(text.generation());
+        text.replace_with("a\nb");
+        seen.push(text.generation());
+        seen.push(LineIndexedString::new("a\nb").generation());
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+
+        let clone = text.clone();
+        assert_eq!(clone.generation(), text.generation());
+    }
+}
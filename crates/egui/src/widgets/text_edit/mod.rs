@@ -1,9 +1,26 @@
+mod autocomplete;
 mod builder;
+mod editing;
+mod input_mask;
+mod line_buffer;
 mod output;
+mod rich_text_document;
+mod spell_checker;
 mod state;
 mod text_buffer;
+mod validator;
+mod virtualized;
 
 pub use {
-    crate::text_selection::TextCursorState, builder::TextEdit, output::TextEditOutput,
-    state::TextEditState, text_buffer::TextBuffer,
+    crate::text_selection::TextCursorState,
+    autocomplete::{Autocomplete, SuggestionSource, filter_suggestions},
+    builder::TextEdit,
+    input_mask::InputMask,
+    line_buffer::{LineBuffer, LineIndexedString},
+    output::TextEditOutput,
+    rich_text_document::{RichTextDocument, TextAttributes},
+    spell_checker::SpellChecker,
+    state::TextEditState,
+    text_buffer::TextBuffer,
+    validator::TextValidator,
 };
@@ -21,6 +21,9 @@ pub struct TextEditOutput {
 
     /// Where the text cursor is.
     pub cursor_range: Option<CCursorRange>,
+
+    /// The error from the [`crate::TextEdit::validator`], if the text is invalid.
+    pub validation_error: Option<String>,
 }
 
 // TODO(emilk): add `output.paint` and `output.store` and split out that code from `TextEdit::show`.
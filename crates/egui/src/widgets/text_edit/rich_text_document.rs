@@ -0,0 +1,672 @@
+use std::ops::Range;
+
+use epaint::text::{LayoutJob, TextFormat};
+
+use crate::{
+    Color32, Context, FontId, Id, Key, Stroke, Style, TextBuffer,
+    text::CCursorRange,
+    text_selection::text_cursor_state::{byte_index_from_char_index, slice_char_range},
+};
+
+/// Formatting of a range of text in a [`RichTextDocument`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TextAttributes {
+    /// Shown in [`crate::Visuals::strong_text_color`], like [`crate::RichText::strong`].
+    pub bold: bool,
+
+    /// Shown in italics, like [`crate::RichText::italics`].
+    pub italic: bool,
+
+    /// Underlined in the text color.
+    pub underline: bool,
+
+    /// Struck through in the text color.
+    pub strikethrough: bool,
+
+    /// Overrides the text color.
+    pub color: Option<Color32>,
+
+    /// The text is a link to this url.
+    ///
+    /// Links are shown in [`crate::Visuals::hyperlink_color`],
+    /// and are opened by clicking them while holding down the command key.
+    pub link: Option<String>,
+}
+
+impl TextAttributes {
+    /// The [`TextFormat`] for text with these attributes.
+    pub fn text_format(
+        &self,
+        style: &Style,
+        font_id: FontId,
+        default_color: Color32,
+    ) -> TextFormat {
+        let visuals = &style.visuals;
+        let color = if let Some(color) = self.color {
+            color
+        } else if self.link.is_some() {
+            visuals.hyperlink_color
+        } else if self.bold {
+            visuals.strong_text_color()
+        } else {
+            default_color
+        };
+        let line = |enabled: bool| {
+            if enabled {
+                Stroke::new(1.0, color)
+            } else {
+                Stroke::NONE
+            }
+        };
+        TextFormat {
+            font_id,
+            color,
+            italics: self.italic,
+            underline: line(self.underline || self.link.is_some()),
+            strikethrough: line(self.strikethrough),
+            ..Default::default()
+        }
+    }
+}
+
+/// Text where ranges of characters have [`TextAttributes`] like bold, italic and links.
+///
+/// This implements [`TextBuffer`], so it can be edited with a [`crate::TextEdit`],
+/// which then shows the formatting and adds these keyboard shortcuts:
+///
+/// * <kbd>Cmd/Ctrl</kbd> + <kbd>B</kbd>: toggle bold
+/// * <kbd>Cmd/Ctrl</kbd> + <kbd>I</kbd>: toggle italic
+/// * <kbd>Cmd/Ctrl</kbd> + <kbd>U</kbd>: toggle underline
+///
+/// The formatting survives edits: typed text gets the formatting of the text before it,
+/// and formatting changes can be undone like edits.
+///
+/// Text copied from one [`RichTextDocument`] keeps its formatting when pasted into another,
+/// but only within the same app:
+/// the system clipboard only gets the plain text,
+/// and the formatted copy is kept in the temporary data of the [`Context`]
+/// (see [`crate::Memory::data`]) under `Id::new("rich_text_clipboard")`.
+/// It is used when the pasted text is the same as the copied text.
+///
+/// Internally the document is a list of spans, like the sections of a [`LayoutJob`].
+/// Use [`Self::layout_job`] to show the document elsewhere, e.g. in a [`crate::Label`].
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// let mut document = egui::RichTextDocument::new("Hello world!");
+/// document.toggle_bold(6..11);
+/// ui.add(egui::TextEdit::multiline(&mut document));
+/// # });
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SerializedDocument"))]
+pub struct RichTextDocument {
+    text: String,
+
+    /// The byte length and attributes of each span, covering all of [`Self::text`].
+    ///
+    /// There are no empty spans, and neighboring spans have different attributes.
+    spans: Vec<(usize, TextAttributes)>,
+
+    /// Attributes for text typed at this character index,
+    /// set when toggling formatting without a selection.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    typing_attributes: Option<(usize, TextAttributes)>,
+}
+
+impl RichTextDocument {
+    /// A document with unformatted text.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let spans = if text.is_empty() {
+            vec![]
+        } else {
+            vec![(text.len(), TextAttributes::default())]
+        };
+        Self {
+            text,
+            spans,
+            typing_attributes: None,
+        }
+    }
+
+    /// The plain text, without formatting.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Append text with the given attributes.
+    pub fn append(&mut self, text: &str, attributes: TextAttributes) {
+        self.text.push_str(text);
+        self.spans.push((text.len(), attributes));
+        self.normalize();
+    }
+
+    /// The byte ranges of the text with the same attributes, in order.
+    pub fn spans(&self) -> impl Iterator<Item = (Range<usize>, &TextAttributes)> + '_ {
+        let mut start = 0;
+        self.spans.iter().map(move |(len, attributes)| {
+            let range = start..start + len;
+            start += len;
+            (range, attributes)
+        })
+    }
+
+    /// The attributes of the character at this index.
+    pub fn attributes_at(&self, char_index: usize) -> Option<&TextAttributes> {
+        let byte_index = byte_index_from_char_index(&self.text, char_index);
+        self.spans()
+            .find(|(range, _)| range.contains(&byte_index))
+            .map(|(_, attributes)| attributes)
+    }
+
+    /// The url of the link at this character index, if any.
+    pub fn link_at(&self, char_index: usize) -> Option<&str> {
+        self.attributes_at(char_index)?.link.as_deref()
+    }
+
+    /// Change the attributes of all characters in the range.
+    ///
+    /// An empty range instead changes the attributes of text typed at that position.
+    pub fn format(
+        &mut self,
+        char_range: Range<usize>,
+        mut change: impl FnMut(&mut TextAttributes),
+    ) {
+        if char_range.is_empty() {
+            let mut attributes = self.attributes_for_insertion(char_range.start);
+            change(&mut attributes);
+            self.typing_attributes = Some((char_range.start, attributes));
+            return;
+        }
+
+        let byte_range = self.byte_range(char_range);
+        let first = self.split_at(byte_range.start);
+        let end = self.split_at(byte_range.end);
+        for (_, attributes) in &mut self.spans[first..end] {
+            change(attributes);
+        }
+        self.normalize();
+    }
+
+    /// Make the range bold, or not bold if all of it already is.
+    pub fn toggle_bold(&mut self, char_range: Range<usize>) {
+        self.toggle(char_range, |attributes| &mut attributes.bold);
+    }
+
+    /// Make the range italic, or not italic if all of it already is.
+    pub fn toggle_italic(&mut self, char_range: Range<usize>) {
+        self.toggle(char_range, |attributes| &mut attributes.italic);
+    }
+
+    /// Underline the range, or remove the underline if all of it already is underlined.
+    pub fn toggle_underline(&mut self, char_range: Range<usize>) {
+        self.toggle(char_range, |attributes| &mut attributes.underline);
+    }
+
+    /// Strike through the range, or stop striking it through if all of it already is.
+    pub fn toggle_strikethrough(&mut self, char_range: Range<usize>) {
+        self.toggle(char_range, |attributes| &mut attributes.strikethrough);
+    }
+
+    /// Set or clear the color of the range.
+    pub fn set_color(&mut self, char_range: Range<usize>, color: Option<Color32>) {
+        self.format(char_range, |attributes| attributes.color = color);
+    }
+
+    /// Turn the range into a link, or remove the link with `None`.
+    pub fn set_link(&mut self, char_range: Range<usize>, url: Option<&str>) {
+        self.format(char_range, |attributes| {
+            attributes.link = url.map(ToOwned::to_owned);
+        });
+    }
+
+    fn toggle(&mut self, char_range: Range<usize>, flag: fn(&mut TextAttributes) -> &mut bool) {
+        let is_set = if char_range.is_empty() {
+            let mut attributes = self.attributes_for_insertion(char_range.start);
+            *flag(&mut attributes)
+        } else {
+            let byte_range = self.byte_range(char_range.clone());
+            self.spans()
+                .filter(|(range, _)| range.start < byte_range.end && byte_range.start < range.end)
+                .all(|(_, attributes)| *flag(&mut attributes.clone()))
+        };
+        self.format(char_range, |attributes| *flag(attributes) = !is_set);
+    }
+
+    /// A copy of the document for undo and redo, which forgets the attributes for typed text.
+    pub(super) fn undo_snapshot(&self) -> Self {
+        Self {
+            text: self.text.clone(),
+            spans: self.spans.clone(),
+            typing_attributes: None,
+        }
+    }
+
+    /// A copy of the given range of the document.
+    pub fn slice(&self, char_range: Range<usize>) -> Self {
+        let byte_range = self.byte_range(char_range);
+        let mut slice = Self::default();
+        for (range, attributes) in self.spans() {
+            let start = range.start.max(byte_range.start);
+            let end = range.end.min(byte_range.end);
+            if start < end {
+                slice.append(&self.text[start..end], attributes.clone());
+            }
+        }
+        slice
+    }
+
+    /// Insert another document, keeping its formatting.
+    ///
+    /// Returns how many characters were inserted.
+    pub fn insert_document(&mut self, document: &Self, char_index: usize) -> usize {
+        let byte_index = byte_index_from_char_index(&self.text, char_index);
+        self.text.insert_str(byte_index, &document.text);
+        let index = self.split_at(byte_index);
+        self.spans
+            .splice(index..index, document.spans.iter().cloned());
+        self.normalize();
+        self.typing_attributes = None;
+        document.text.chars().count()
+    }
+
+    /// The document as a [`LayoutJob`], with one section per span.
+    ///
+    /// `default_color` is used for text without a color.
+    pub fn layout_job(
+        &self,
+        style: &Style,
+        font_id: FontId,
+        default_color: Color32,
+        wrap_width: f32,
+    ) -> LayoutJob {
+        let mut job = LayoutJob {
+            text: self.text.clone(),
+            ..Default::default()
+        };
+        job.wrap.max_width = wrap_width;
+        for (byte_range, attributes) in self.spans() {
+            job.sections.push(epaint::text::LayoutSection {
+                leading_space: 0.0,
+                byte_range,
+                format: attributes.text_format(style, font_id.clone(), default_color),
+            });
+        }
+        if job.sections.is_empty() {
+            // An empty job still needs a font for the row height:
+            job.sections.push(epaint::text::LayoutSection {
+                leading_space: 0.0,
+                byte_range: 0..0,
+                format: TextAttributes::default().text_format(style, font_id, default_color),
+            });
+        }
+        job
+    }
+
+    /// The attributes for text inserted at this character index.
+    fn attributes_for_insertion(&self, char_index: usize) -> TextAttributes {
+        if let Some((typing_index, attributes)) = &self.typing_attributes {
+            if *typing_index == char_index {
+                return attributes.clone();
+            }
+        }
+
+        let before = char_index
+            .checked_sub(1)
+            .and_then(|index| self.attributes_at(index));
+        let Some(before) = before.or_else(|| self.attributes_at(char_index)) else {
+            return TextAttributes::default();
+        };
+        let mut attributes = before.clone();
+        if attributes.link.is_some() && self.attributes_at(char_index) != Some(before) {
+            // Don't extend links when typing after them.
+            attributes.link = None;
+        }
+        attributes
+    }
+
+    fn byte_range(&self, char_range: Range<usize>) -> Range<usize> {
+        byte_index_from_char_index(&self.text, char_range.start)
+            ..byte_index_from_char_index(&self.text, char_range.end)
+    }
+
+    /// Make sure a span starts at the given byte index, and return its index.
+    fn split_at(&mut self, byte_index: usize) -> usize {
+        let mut start = 0;
+        for i in 0..self.spans.len() {
+            if start == byte_index {
+                return i;
+            }
+            let (len, attributes) = &self.spans[i];
+            let end = start + len;
+            if byte_index < end {
+                let attributes = attributes.clone();
+                self.spans[i].0 = byte_index - start;
+                self.spans.insert(i + 1, (end - byte_index, attributes));
+                return i + 1;
+            }
+            start = end;
+        }
+        self.spans.len()
+    }
+
+    /// Remove empty spans and merge neighbors with the same attributes.
+    fn normalize(&mut self) {
+        let mut spans: Vec<(usize, TextAttributes)> = Vec::with_capacity(self.spans.len());
+        for (len, attributes) in self.spans.drain(..) {
+            if len == 0 {
+                continue;
+            }
+            match spans.last_mut() {
+                Some((last_len, last_attributes)) if *last_attributes == attributes => {
+                    *last_len += len;
+                }
+                _ => spans.push((len, attributes)),
+            }
+        }
+        self.spans = spans;
+    }
+}
+
+/// What a [`RichTextDocument`] is deserialized from, before its spans are checked.
+#[cfg(feature = "serde")]
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct SerializedDocument {
+    text: String,
+    spans: Vec<(usize, TextAttributes)>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedDocument> for RichTextDocument {
+    type Error = String;
+
+    fn try_from(SerializedDocument { text, spans }: SerializedDocument) -> Result<Self, String> {
+        if spans.is_empty() {
+            // Plain text:
+            return Ok(Self::new(text));
+        }
+
+        let mut end = 0_usize;
+        for (len, _) in &spans {
+            end = end.saturating_add(*len);
+            if end < text.len() && !text.is_char_boundary(end) {
+                return Err(format!(
+                    "A span ends at byte {end}, which is inside a character"
+                ));
+            }
+        }
+        if end != text.len() {
+            return Err(format!(
+                "The spans cover {end} bytes, but the text is {} bytes long",
+                text.len()
+            ));
+        }
+
+        let mut document = Self {
+            text,
+            spans,
+            typing_attributes: None,
+        };
+        document.normalize();
+        Ok(document)
+    }
+}
+
+impl From<&str> for RichTextDocument {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for RichTextDocument {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl TextBuffer for RichTextDocument {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+
+        let is_typing = matches!(self.typing_attributes, Some((index, _)) if index == char_index);
+        let attributes = self.attributes_for_insertion(char_index);
+        let byte_index = byte_index_from_char_index(&self.text, char_index);
+        self.text.insert_str(byte_index, text);
+        let index = self.split_at(byte_index);
+        self.spans.insert(index, (text.len(), attributes.clone()));
+        self.normalize();
+
+        let num_chars = text.chars().count();
+        self.typing_attributes = is_typing.then(|| {
+            // Keep typing with the same attributes:
+            (char_index + num_chars, attributes)
+        });
+        num_chars
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        assert!(char_range.start <= char_range.end, "start must be <= end");
+        let byte_range = self.byte_range(char_range);
+        self.text.drain(byte_range.clone());
+        let first = self.split_at(byte_range.start);
+        let end = self.split_at(byte_range.end);
+        self.spans.drain(first..end);
+        self.normalize();
+        self.typing_attributes = None;
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.spans.clear();
+        self.typing_attributes = None;
+    }
+
+    /// Only replaces the part of the text that changed, so that the rest keeps its formatting.
+    fn replace_with(&mut self, text: &str) {
+        let common_prefix = self
+            .text
+            .chars()
+            .zip(text.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_len = self.text.chars().count();
+        let new_len = text.chars().count();
+        let max_suffix = old_len.min(new_len) - common_prefix;
+        let common_suffix = self
+            .text
+            .chars()
+            .rev()
+            .zip(text.chars().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        self.delete_char_range(common_prefix..old_len - common_suffix);
+        self.insert_text(
+            slice_char_range(text, common_prefix..new_len - common_suffix),
+            common_prefix,
+        );
+    }
+
+    fn as_rich_text(&self) -> Option<&RichTextDocument> {
+        Some(self)
+    }
+
+    fn as_rich_text_mut(&mut self) -> Option<&mut RichTextDocument> {
+        Some(self)
+    }
+
+    fn type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<Self>()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Used by `TextEdit`:
+
+/// The formatted version of the text last copied from a [`RichTextDocument`].
+fn clipboard_id() -> Id {
+    Id::new("rich_text_clipboard")
+}
+
+/// Remember the formatting of the copied text, for pasting it into a [`RichTextDocument`].
+pub(super) fn copy(ctx: &Context, document: &RichTextDocument, cursor_range: &CCursorRange) {
+    let slice = document.slice(cursor_range.as_sorted_char_range());
+    ctx.data_mut(|data| data.insert_temp(clipboard_id(), slice));
+}
+
+/// The formatted version of the pasted text, if it was copied from a [`RichTextDocument`].
+pub(super) fn pasted_document(ctx: &Context, pasted: &str) -> Option<RichTextDocument> {
+    ctx.data(|data| data.get_temp::<RichTextDocument>(clipboard_id()))
+        .filter(|document| document.text == pasted)
+}
+
+/// Is this key (together with the command key) a formatting shortcut?
+pub(super) fn is_shortcut(key: Key) -> bool {
+    matches!(key, Key::B | Key::I | Key::U)
+}
+
+/// Toggle the formatting of the selection for a shortcut, see [`is_shortcut`].
+pub(super) fn on_shortcut(document: &mut RichTextDocument, key: Key, cursor_range: &CCursorRange) {
+    let char_range = cursor_range.as_sorted_char_range();
+    match key {
+        Key::B => document.toggle_bold(char_range),
+        Key::I => document.toggle_italic(char_range),
+        Key::U => document.toggle_underline(char_range),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bold_text(document: &RichTextDocument) -> Vec<&str> {
+        document
+            .spans()
+            .filter(|(_, attributes)| attributes.bold)
+            .map(|(range, _)| &document.text[range])
+            .collect()
+    }
+
+    #[test]
+    fn formatting_survives_edits() {
+        let mut document = RichTextDocument::new("Hello world");
+        document.toggle_bold(6..11);
+        assert_eq!(bold_text(&document), vec!["world"]);
+
+        // Typed text gets the formatting of the text before it:
+        document.insert_text("!", 11);
+        document.insert_text("big ", 6);
+        assert_eq!(bold_text(&document), vec!["world!"]);
+
+        document.delete_char_range(6..10);
+        document.insert_text("e", 9);
+        assert_eq!(bold_text(&document), vec!["woreld!"]);
+        document.delete_char_range(9..10);
+
+        // Toggling a partly bold range makes all of it bold, and then none of it:
+        document.toggle_bold(0..8);
+        assert_eq!(bold_text(&document), vec!["Hello world!"]);
+        document.toggle_bold(0..6);
+        assert_eq!(bold_text(&document), vec!["world!"]);
+        assert_eq!(document.spans().count(), 2);
+    }
+
+    #[test]
+    fn typing_attributes() {
+        let mut document = RichTextDocument::new("ab");
+        document.toggle_italic(1..1);
+        document.insert_text("x", 1);
+        document.insert_text("y", 2);
+        document.insert_text("z", 0);
+        let italic: Vec<_> = document
+            .spans()
+            .filter(|(_, attributes)| attributes.italic)
+            .map(|(range, _)| &document.text[range])
+            .collect();
+        assert_eq!(document.as_str(), "zaxyb");
+        assert_eq!(italic, vec!["xy"]);
+    }
+
+    #[test]
+    fn links_are_not_extended() {
+        let mut document = RichTextDocument::new("see egui");
+        document.set_link(4..8, Some("https://github.com/emilk/egui"));
+        document.insert_text("!", 8);
+        assert_eq!(document.link_at(7), Some("https://github.com/emilk/egui"));
+        assert_eq!(document.link_at(8), None);
+    }
+
+    #[test]
+    fn slice_and_insert_document() {
+        let mut document = RichTextDocument::new("one two");
+        document.toggle_bold(4..7);
+        let slice = document.slice(2..6);
+        assert_eq!(slice.as_str(), "e tw");
+        assert_eq!(bold_text(&slice), vec!["tw"]);
+
+        let mut other = RichTextDocument::new("[]");
+        other.insert_document(&slice, 1);
+        assert_eq!(other.as_str(), "[e tw]");
+        assert_eq!(bold_text(&other), vec!["tw"]);
+    }
+
+    #[test]
+    fn replace_with_keeps_formatting() {
+        let mut document = RichTextDocument::new("Hello world");
+        document.toggle_bold(0..5);
+        document.replace_with("Hello there world");
+        assert_eq!(document.as_str(), "Hello there world");
+        assert_eq!(bold_text(&document), vec!["Hello"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_checks_spans() {
+        let bold = TextAttributes {
+            bold: true,
+            ..Default::default()
+        };
+        let document = |text: &str, spans: Vec<(usize, TextAttributes)>| {
+            RichTextDocument::try_from(SerializedDocument {
+                text: text.to_owned(),
+                spans,
+            })
+        };
+
+        let plain = document("plain", vec![]).unwrap();
+        assert_eq!(plain, RichTextDocument::new("plain"));
+
+        let merged = document(
+            "one two",
+            vec![
+                (0, TextAttributes::default()),
+                (4, bold.clone()),
+                (3, bold.clone()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(merged.spans().count(), 1);
+        assert_eq!(bold_text(&merged), vec!["one two"]);
+
+        assert!(document("too short", vec![(3, bold.clone())]).is_err());
+        assert!(document("too long", vec![(30, bold.clone())]).is_err());
+        assert!(document("åäö", vec![(1, bold.clone()), (5, bold)]).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use std::{ops::Range, sync::Arc};
+
+use epaint::text::{Galley, LayoutJob, LayoutSection};
+
+use crate::{
+    Id, Popup, Pos2, Response, Stroke, TextBuffer, Ui,
+    cache::FramePublisher,
+    text::{CCursor, CCursorRange},
+};
+
+/// Checks the spelling of the text in a [`crate::TextEdit`].
+///
+/// Misspelled words get a wavy underline, and right-clicking one of them
+/// shows the suggested replacements in a context menu.
+///
+/// The results are cached for as long as the text and [`Self::generation`] stay the same.
+///
+/// See [`crate::TextEdit::spell_checker`].
+/// `egui_extras::DictionarySpellChecker` is an implementation that uses a list of words.
+pub trait SpellChecker {
+    /// The byte ranges of all misspelled words in the given text.
+    fn misspelled(&self, text: &str) -> Vec<Range<usize>>;
+
+    /// Replacements for a misspelled word, best first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+
+    /// Change this whenever [`Self::misspelled`] may give a different answer for the same text,
+    /// e.g. when a word is added to the dictionary.
+    fn generation(&self) -> u64 {
+        0
+    }
+}
+
+/// Add a wavy underline under each misspelled word of a job that is about to be laid out.
+pub(super) fn underline_misspelled_in_job(
+    ui: &Ui,
+    spell_checker: &dyn SpellChecker,
+    job: &mut LayoutJob,
+) {
+    let misspelled = cached_misspelled(ui, spell_checker, &job.text);
+    let stroke = Stroke::new(1.0, ui.visuals().error_fg_color);
+    add_wavy_underlines(job, &misspelled, stroke);
+}
+
+/// Lay out a galley from a custom layouter again, with a wavy underline under each misspelled word.
+///
+/// The result is reused for as long as the galley and the misspelled words stay the same.
+pub(super) fn underline_misspelled(
+    ui: &Ui,
+    spell_checker: &dyn SpellChecker,
+    galley: Arc<Galley>,
+) -> Arc<Galley> {
+    /// From the galley and a hash of what to underline, to the galley and the underlined galley.
+    type UnderlinedGalleys = FramePublisher<Id, (Arc<Galley>, Arc<Galley>)>;
+
+    let misspelled = cached_misspelled(ui, spell_checker, &galley.job.text);
+    if misspelled.is_empty() {
+        return galley;
+    }
+
+    let stroke = Stroke::new(1.0, ui.visuals().error_fg_color);
+    // Keeping the galley alive in the cache means that no other galley can get the same address:
+    let key = Id::new((Arc::as_ptr(&galley), &*misspelled, stroke));
+    let cached = ui.memory_mut(|mem| {
+        mem.caches
+            .cache::<UnderlinedGalleys>()
+            .get(&key)
+            .filter(|(original, _)| Arc::ptr_eq(original, &galley))
+            .map(|(_, underlined)| underlined.clone())
+    });
+    let underlined = cached.unwrap_or_else(|| {
+        let mut job = (*galley.job).clone();
+        add_wavy_underlines(&mut job, &misspelled, stroke);
+        ui.fonts(|f| f.layout_job(job))
+    });
+    ui.memory_mut(|mem| {
+        mem.caches
+            .cache::<UnderlinedGalleys>()
+            .set(key, (galley, underlined.clone()));
+    });
+    underlined
+}
+
+/// [`misspelled`], reused for as long as the text and the spell checker stay the same.
+fn cached_misspelled(
+    ui: &Ui,
+    spell_checker: &dyn SpellChecker,
+    text: &str,
+) -> Arc<Vec<Range<usize>>> {
+    /// From a hash of the text and the spell checker, to the misspelled ranges.
+    type MisspelledRanges = FramePublisher<Id, Arc<Vec<Range<usize>>>>;
+
+    let checker_ptr = std::ptr::from_ref(spell_checker).cast::<()>();
+    let key = Id::new((text, checker_ptr, spell_checker.generation()));
+    let cached = ui.memory_mut(|mem| mem.caches.cache::<MisspelledRanges>().get(&key).cloned());
+    let ranges = cached.unwrap_or_else(|| Arc::new(misspelled(spell_checker, text)));
+    ui.memory_mut(|mem| {
+        mem.caches
+            .cache::<MisspelledRanges>()
+            .set(key, ranges.clone());
+    });
+    ranges
+}
+
+/// The ranges from [`SpellChecker::misspelled`], sorted, with overlapping ranges merged.
+///
+/// Empty ranges and ranges that are not on character boundaries are ignored,
+/// so that a buggy spell checker can't make us panic.
+fn misspelled(spell_checker: &dyn SpellChecker, text: &str) -> Vec<Range<usize>> {
+    let mut ranges = spell_checker.misspelled(text);
+    ranges.retain(|range| {
+        range.start < range.end
+            && text.is_char_boundary(range.start)
+            && text.is_char_boundary(range.end)
+    });
+    ranges.sort_by_key(|range| range.start);
+    ranges.dedup_by(|next, prev| {
+        let overlaps = next.start < prev.end;
+        if overlaps {
+            prev.end = prev.end.max(next.end);
+        }
+        overlaps
+    });
+    ranges
+}
+
+/// Split the sections of the job so that the given byte ranges get a wavy underline.
+///
+/// The ranges must be sorted and must not overlap, like the ones from [`misspelled`].
+fn add_wavy_underlines(job: &mut LayoutJob, byte_ranges: &[Range<usize>], stroke: Stroke) {
+    if byte_ranges.is_empty() {
+        return;
+    }
+
+    let mut ranges = byte_ranges.iter().peekable();
+    let mut sections = Vec::with_capacity(job.sections.len() + 2 * byte_ranges.len());
+    for section in job.sections.drain(..) {
+        let Range { mut start, end } = section.byte_range;
+        while ranges.next_if(|range| range.end <= start).is_some() {}
+        if start == end || ranges.peek().is_none_or(|range| end <= range.start) {
+            sections.push(section);
+            continue;
+        }
+
+        // Only the first part keeps the leading space:
+        let mut leading_space = section.leading_space;
+        while start < end {
+            let (split_end, underlined) = match ranges.peek() {
+                Some(range) if range.start <= start => (range.end.min(end), true),
+                Some(range) if range.start < end => (range.start, false),
+                _ => (end, false),
+            };
+            let mut format = section.format.clone();
+            if underlined {
+                format.wavy_underline = stroke;
+            }
+            sections.push(LayoutSection {
+                leading_space,
+                byte_range: start..split_end,
+                format,
+            });
+            leading_space = 0.0;
+            start = split_end;
+            while ranges.next_if(|range| range.end <= start).is_some() {}
+        }
+    }
+    job.sections = sections;
+}
+
+/// The misspelled word that the context menu was opened for.
+#[derive(Clone)]
+struct ContextMenuWord {
+    char_range: Range<usize>,
+    word: String,
+}
+
+/// Show suggestions for the misspelled word under the pointer when right-clicking.
+///
+/// Returns the selection to replace and the chosen suggestion, if one was clicked.
+/// The menu is closed if the text changes while it is open.
+pub(super) fn context_menu(
+    ui: &Ui,
+    response: &Response,
+    spell_checker: &dyn SpellChecker,
+    text: &dyn TextBuffer,
+    galley: &Galley,
+    galley_pos: Pos2,
+) -> Option<(CCursorRange, String)> {
+    let menu_id = response.id.with("spell_checker");
+
+    if response.secondary_clicked() {
+        let word = response
+            .interact_pointer_pos()
+            .and_then(|pos| misspelled_word_at(spell_checker, galley, galley_pos, pos));
+        ui.data_mut(|data| match word {
+            Some(word) => data.insert_temp(menu_id, word),
+            None => data.remove::<ContextMenuWord>(menu_id),
+        });
+    }
+
+    let ContextMenuWord { char_range, word } = ui
+        .ctx()
+        .data(|data| data.get_temp::<ContextMenuWord>(menu_id))?;
+
+    // The range is only valid for the text it was found in:
+    if response.changed() || text.char_range(char_range.clone()) != word {
+        ui.data_mut(|data| data.remove::<ContextMenuWord>(menu_id));
+        Popup::close_id(ui.ctx(), Popup::default_response_id(response));
+        return None;
+    }
+
+    let mut replacement = None;
+    let menu = response.context_menu(|ui| {
+        let suggestions = spell_checker.suggestions(&word);
+        if suggestions.is_empty() {
+            ui.weak("No suggestions");
+        }
+        for suggestion in suggestions {
+            if ui.button(&suggestion).clicked() {
+                let selection =
+                    CCursorRange::two(CCursor::new(char_range.start), CCursor::new(char_range.end));
+                replacement = Some((selection, suggestion));
+            }
+        }
+    });
+    if menu.is_none() || replacement.is_some() {
+        ui.data_mut(|data| data.remove::<ContextMenuWord>(menu_id));
+    }
+    replacement
+}
+
+fn misspelled_word_at(
+    spell_checker: &dyn SpellChecker,
+    galley: &Galley,
+    galley_pos: Pos2,
+    pointer_pos: Pos2,
+) -> Option<ContextMenuWord> {
+    let text = galley.text();
+    let char_index = galley.cursor_from_pos(pointer_pos - galley_pos).index;
+    let byte_index = text
+        .char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(byte_index, _)| byte_index);
+
+    let range = misspelled(spell_checker, text)
+        .into_iter()
+        .find(|range| range.start <= byte_index && byte_index <= range.end)?;
+    let char_start = text[..range.start].chars().count();
+    let word = text[range].to_owned();
+    Some(ContextMenuWord {
+        char_range: char_start..char_start + word.chars().count(),
+        word,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use epaint::text::TextFormat;
+
+    #[test]
+    fn wavy_underline_splits_sections() {
+        let mut job = LayoutJob::default();
+        job.append("helo ", 0.0, TextFormat::default());
+        job.append("wrld!", 0.0, TextFormat::default());
+        let stroke = Stroke::new(1.0, crate::Color32::RED);
+        add_wavy_underlines(&mut job, &[0..4, 5..9], stroke);
+
+        let sections: Vec<_> = job
+            .sections
+            .iter()
+            .map(|s| (s.byte_range.clone(), s.format.wavy_underline == stroke))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![(0..4, true), (4..5, false), (5..9, true), (9..10, false)]
+        );
+    }
+
+    #[test]
+    fn wavy_underline_across_sections() {
+        let mut job = LayoutJob::default();
+        job.append("he", 0.0, TextFormat::default());
+        job.append("lo wrld", 4.0, TextFormat::default());
+        let stroke = Stroke::new(1.0, crate::Color32::RED);
+        add_wavy_underlines(&mut job, &[1..4, 5..7], stroke);
+
+        let sections: Vec<_> = job
+            .sections
+            .iter()
+            .map(|s| {
+                (
+                    s.byte_range.clone(),
+                    s.leading_space,
+                    s.format.wavy_underline == stroke,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                (0..1, 0.0, false),
+                (1..2, 0.0, true),
+                (2..4, 4.0, true),
+                (4..5, 0.0, false),
+                (5..7, 0.0, true),
+                (7..9, 0.0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_ranges_are_ignored() {
+        struct Buggy;
+
+        impl SpellChecker for Buggy {
+            fn misspelled(&self, _text: &str) -> Vec<Range<usize>> {
+                vec![0..1, 1..2, 3..3, 4..100, 3..6]
+            }
+
+            fn suggestions(&self, _word: &str) -> Vec<String> {
+                vec![]
+            }
+        }
+
+        // "ä" is two bytes long:
+        assert_eq!(misspelled(&Buggy, "äb cde"), vec![3..6]);
+    }
+
+    #[test]
+    fn misspelled_ranges_are_sorted_and_merged() {
+        struct Unsorted;
+
+        impl SpellChecker for Unsorted {
+            fn misspelled(&self, _text: &str) -> Vec<Range<usize>> {
+                vec![6..9, 0..2, 1..4, 7..8]
+            }
+
+            fn suggestions(&self, _word: &str) -> Vec<String> {
+                vec![]
+            }
+        }
+
+        assert_eq!(misspelled(&Unsorted, "abcd efghij"), vec![0..4, 6..9]);
+    }
+}
@@ -3,12 +3,15 @@ use std::sync::Arc;
 use crate::mutex::Mutex;
 
 use crate::{
-    Context, Id,
+    Context, Id, RichTextDocument,
     text_selection::{CCursorRange, TextCursorState},
 };
 
 pub type TextEditUndoer = crate::util::undoer::Undoer<(CCursorRange, String)>;
 
+/// Like [`TextEditUndoer`], but for a [`RichTextDocument`], so that formatting can be undone too.
+pub(crate) type RichTextUndoer = crate::util::undoer::Undoer<(CCursorRange, RichTextDocument)>;
+
 /// The text edit state stored between frames.
 ///
 /// Attention: You also need to `store` the updated state.
@@ -41,6 +44,10 @@ pub struct TextEditState {
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) undoer: Arc<Mutex<TextEditUndoer>>,
 
+    /// Used instead of [`Self::undoer`] when editing a [`RichTextDocument`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) rich_text_undoer: Arc<Mutex<RichTextUndoer>>,
+
     // If IME candidate window is shown on this text edit.
     #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) ime_enabled: bool,
@@ -79,5 +86,6 @@ impl TextEditState {
 
     pub fn clear_undoer(&mut self) {
         self.set_undoer(TextEditUndoer::default());
+        *self.rich_text_undoer.lock() = RichTextUndoer::default();
     }
 }
@@ -5,6 +5,7 @@ use epaint::{
     text::{TAB_SIZE, cursor::CCursor},
 };
 
+use super::{LineBuffer, RichTextDocument};
 use crate::{
     text::CCursorRange,
     text_selection::text_cursor_state::{
@@ -87,29 +88,23 @@ pub trait TextBuffer {
         }
     }
 
+    /// Remove one level of indentation from the line of the cursor.
+    ///
+    /// Only looks at that line if this is a [`LineBuffer`].
     fn decrease_indentation(&mut self, ccursor: &mut CCursor) {
-        let line_start = find_line_start(self.as_str(), *ccursor);
-
-        let remove_len = if self.as_str().chars().nth(line_start.index) == Some('\t') {
-            Some(1)
-        } else if self
-            .as_str()
-            .chars()
-            .skip(line_start.index)
-            .take(TAB_SIZE)
-            .all(|c| c == ' ')
-        {
-            Some(TAB_SIZE)
+        let (line_start, remove_len) = if let Some(lines) = self.as_line_buffer_mut() {
+            let line = lines.line_from_char_index(ccursor.index);
+            let line_start = lines.line_start_char_index(line);
+            (line_start, indentation_to_remove(lines.line(line)))
         } else {
-            None
+            let text = self.as_str();
+            let line_start = find_line_start(text, *ccursor).index;
+            let line = &text[byte_index_from_char_index(text, line_start)..];
+            (line_start, indentation_to_remove(line))
         };
 
-        if let Some(len) = remove_len {
-            self.delete_char_range(line_start.index..(line_start.index + len));
-            if *ccursor != line_start {
-                *ccursor -= len;
-            }
-        }
+        self.delete_char_range(line_start..line_start + remove_len);
+        ccursor.index = ccursor.index.saturating_sub(remove_len).max(line_start);
     }
 
     fn delete_selected(&mut self, cursor_range: &CCursorRange) -> CCursor {
@@ -177,6 +172,26 @@ pub trait TextBuffer {
         }
     }
 
+    /// Line-indexed access to this buffer, if it keeps an index of its lines.
+    ///
+    /// This is what [`crate::TextEdit::virtualized`] uses to only lay out the visible lines.
+    /// Implementors of [`LineBuffer`] should return `Some(self)`.
+    fn as_line_buffer_mut(&mut self) -> Option<&mut dyn LineBuffer> {
+        None
+    }
+
+    /// The formatted document, if this buffer is a [`RichTextDocument`].
+    ///
+    /// [`crate::TextEdit`] uses this to show the formatting and to handle formatting shortcuts.
+    fn as_rich_text(&self) -> Option<&RichTextDocument> {
+        None
+    }
+
+    /// See [`Self::as_rich_text`].
+    fn as_rich_text_mut(&mut self) -> Option<&mut RichTextDocument> {
+        None
+    }
+
     /// Returns a unique identifier for the implementing type.
     ///
     /// This is useful for downcasting from this trait to the implementing type.
@@ -211,6 +226,21 @@ pub trait TextBuffer {
     fn type_id(&self) -> std::any::TypeId;
 }
 
+/// How many characters of indentation [`TextBuffer::decrease_indentation`] removes from the start of the line.
+fn indentation_to_remove(line: &str) -> usize {
+    if line.starts_with('\t') {
+        1
+    } else if line
+        .as_bytes()
+        .get(..TAB_SIZE)
+        .is_some_and(|indentation| indentation.iter().all(|&b| b == b' '))
+    {
+        TAB_SIZE
+    } else {
+        0
+    }
+}
+
 impl TextBuffer for String {
     fn is_mutable(&self) -> bool {
         true
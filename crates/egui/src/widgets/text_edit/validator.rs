@@ -0,0 +1,48 @@
+use super::InputMask;
+
+/// Checks the text of a [`crate::TextEdit`].
+///
+/// If the text is invalid, the [`crate::TextEdit`] gets a red frame and the error is shown in a tooltip
+/// while it is hovered or focused.
+/// The error is also available in [`crate::text_edit::TextEditOutput::validation_error`].
+///
+/// This is implemented for closures:
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// # let mut ip = String::new();
+/// let is_ip = |text: &str| -> Result<(), String> {
+///     text.parse::<std::net::IpAddr>()
+///         .map(|_| ())
+///         .map_err(|_| "Expected an IP address".to_owned())
+/// };
+/// ui.add(egui::TextEdit::singleline(&mut ip).validator(&is_ip));
+/// # });
+/// ```
+pub trait TextValidator {
+    /// Check the text.
+    ///
+    /// # Errors
+    /// A short, human readable message if the text is invalid.
+    fn validate(&self, text: &str) -> Result<(), String>;
+}
+
+impl<F> TextValidator for F
+where
+    F: Fn(&str) -> Result<(), String>,
+{
+    fn validate(&self, text: &str) -> Result<(), String> {
+        self(text)
+    }
+}
+
+/// Accepts empty text and text that fills all slots of the mask.
+impl TextValidator for InputMask {
+    fn validate(&self, text: &str) -> Result<(), String> {
+        if text.is_empty() || self.is_complete(text) {
+            Ok(())
+        } else {
+            Err(format!("Expected {}", self.placeholder()))
+        }
+    }
+}
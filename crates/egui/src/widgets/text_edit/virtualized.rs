@@ -0,0 +1,852 @@
+//! [`TextEdit::virtualized`](super::TextEdit::virtualized): only lay out and paint the visible lines.
+
+use std::{ops::Range, sync::Arc};
+
+use emath::Rect;
+use epaint::text::{Galley, LayoutJob, cursor::CCursor};
+
+use crate::{
+    Color32, CursorIcon, Event, EventFilter, FontId, Key, KeyboardShortcut, Modifiers, NumExt as _,
+    Response, Sense, TextWrapMode, Ui, Vec2, WidgetInfo,
+    os::OperatingSystem,
+    response, text_selection,
+    text_selection::{
+        CCursorRange, CursorKeyAction, CursorMovement, FindInPageState, LaidOutLines,
+        TextCursorState,
+        text_cursor_state::{
+            ccursor_next_word, ccursor_previous_word, cursor_rect, select_word_at,
+        },
+        visuals::paint_text_selection,
+    },
+    vec2,
+};
+
+use super::{
+    LineBuffer, TextEdit, TextEditOutput, TextEditState,
+    builder::LayouterFn,
+    editing::{self, Boundary},
+};
+
+/// The cursor range we last scrolled to, so that we can scroll to it when it is changed from outside.
+#[derive(Clone, Copy)]
+struct ShownCursorRange(CCursorRange);
+
+/// Lays out lines of the text, never wrapping them.
+struct LineLayouter<'t> {
+    layouter: Option<LayouterFn<'t>>,
+    font_id: FontId,
+    text_color: Color32,
+    password: bool,
+}
+
+impl LineLayouter<'_> {
+    fn layout(&mut self, ui: &Ui, text: &dyn LineBuffer, lines: Range<usize>) -> Arc<Galley> {
+        let masked;
+        let mut text = text.lines(lines);
+        if self.password {
+            masked = text
+                .chars()
+                .map(|c| {
+                    if c == '\n' {
+                        c
+                    } else {
+                        epaint::text::PASSWORD_REPLACEMENT_CHAR
+                    }
+                })
+                .collect::<String>();
+            text = &masked;
+        }
+
+        if let Some(layouter) = &mut self.layouter {
+            layouter(ui, &text, f32::INFINITY)
+        } else {
+            let layout_job = LayoutJob::simple(
+                text.to_owned(),
+                self.font_id.clone(),
+                self.text_color,
+                f32::INFINITY,
+            );
+            ui.fonts(|f| f.layout_job(layout_job))
+        }
+    }
+}
+
+/// The lines that are laid out, and how to find the others.
+struct View<'t> {
+    layouter: LineLayouter<'t>,
+    row_height: f32,
+
+    /// The laid out lines.
+    lines: Range<usize>,
+
+    /// The character index of the first laid out line.
+    char_offset: usize,
+
+    /// The laid out lines, with the first one at the top.
+    galley: Arc<Galley>,
+}
+
+impl<'t> View<'t> {
+    fn new(
+        ui: &Ui,
+        text: &dyn LineBuffer,
+        mut layouter: LineLayouter<'t>,
+        row_height: f32,
+        lines: Range<usize>,
+    ) -> Self {
+        Self {
+            char_offset: text.line_start_char_index(lines.start),
+            galley: layouter.layout(ui, text, lines.clone()),
+            lines,
+            layouter,
+            row_height,
+        }
+    }
+
+    fn layout(&mut self, ui: &Ui, text: &dyn LineBuffer, lines: Range<usize>) {
+        self.char_offset = text.line_start_char_index(lines.start);
+        self.galley = self.layouter.layout(ui, text, lines.clone());
+        self.lines = lines;
+    }
+
+    /// Lay out the same lines again, e.g. after the text changed.
+    fn relayout(&mut self, ui: &Ui, text: &dyn LineBuffer) {
+        let end = self.lines.end.min(text.num_lines());
+        self.layout(ui, text, self.lines.start.min(end)..end);
+    }
+
+    /// A galley containing the line, the character index where the galley starts,
+    /// and how far down in the galley the line is.
+    fn line_galley(
+        &mut self,
+        ui: &Ui,
+        text: &dyn LineBuffer,
+        line: usize,
+    ) -> (Arc<Galley>, usize, f32) {
+        if self.lines.contains(&line) {
+            let y = lines_height(line - self.lines.start, self.row_height);
+            (self.galley.clone(), self.char_offset, y)
+        } else {
+            let galley = self.layouter.layout(ui, text, line..line + 1);
+            (galley, text.line_start_char_index(line), 0.0)
+        }
+    }
+
+    /// The cursor on the given line that is closest to `x`.
+    fn cursor_at(&mut self, ui: &Ui, text: &dyn LineBuffer, line: usize, x: f32) -> CCursor {
+        let (galley, offset, y) = self.line_galley(ui, text, line);
+        let cursor = galley.cursor_from_pos(vec2(x, y + 0.5 * self.row_height));
+        CCursor {
+            index: offset + cursor.index,
+            prefer_next_row: cursor.prefer_next_row,
+        }
+    }
+
+    /// The rectangle of the cursor, relative to the top left of the text.
+    fn cursor_rect(&mut self, ui: &Ui, text: &dyn LineBuffer, cursor: CCursor) -> Rect {
+        let line = text.line_from_char_index(cursor.index);
+        let (galley, offset, y) = self.line_galley(ui, text, line);
+        let local = CCursor::new(cursor.index - offset);
+        cursor_rect(&galley, &local, self.row_height)
+            .translate(vec2(0.0, lines_height(line, self.row_height) - y))
+    }
+
+    /// Move the cursor, returning the new cursor and the saved horizontal position.
+    ///
+    /// [`CursorMovement::RowUp`] and [`CursorMovement::RowDown`] move by `num_lines`.
+    fn move_cursor(
+        &mut self,
+        ui: &Ui,
+        text: &dyn LineBuffer,
+        cursor: CCursor,
+        h_pos: Option<f32>,
+        movement: CursorMovement,
+        num_lines: usize,
+    ) -> (CCursor, Option<f32>) {
+        let index = cursor.index;
+        let line = text.line_from_char_index(index);
+        let new_index = match movement {
+            CursorMovement::PreviousChar => index.saturating_sub(1),
+            CursorMovement::NextChar => (index + 1).min(text.num_chars()),
+            CursorMovement::PreviousWord => {
+                find_boundary(text, Boundary::PreviousWord, cursor).index
+            }
+            CursorMovement::NextWord => find_boundary(text, Boundary::NextWord, cursor).index,
+            CursorMovement::RowBegin => text.line_start_char_index(line),
+            CursorMovement::RowEnd => text.line_end_char_index(line),
+            CursorMovement::TextBegin => 0,
+            CursorMovement::TextEnd => text.num_chars(),
+
+            CursorMovement::RowUp | CursorMovement::RowDown => {
+                let last_line = text.num_lines() - 1;
+                let target = if movement == CursorMovement::RowUp {
+                    line.checked_sub(1)
+                        .map(|line| line.saturating_sub(num_lines - 1))
+                } else {
+                    (line < last_line).then(|| (line + num_lines).min(last_line))
+                };
+                if let Some(target) = target {
+                    // Keep the same x coordinate:
+                    let h_pos =
+                        h_pos.unwrap_or_else(|| self.cursor_rect(ui, text, cursor).center().x);
+                    return (self.cursor_at(ui, text, target, h_pos), Some(h_pos));
+                } else if movement == CursorMovement::RowUp {
+                    0
+                } else {
+                    text.num_chars()
+                }
+            }
+        };
+        (CCursor::new(new_index), None)
+    }
+
+    /// The part of the range that is in the laid out lines, relative to [`Self::galley`].
+    fn galley_range(&self, range: &CCursorRange) -> Option<CCursorRange> {
+        let [min, max] = range.sorted_cursors();
+        let end = self.char_offset + self.galley.end().index;
+        if max.index < self.char_offset || end < min.index {
+            return None;
+        }
+        let local = |cursor: CCursor| {
+            CCursor::new(cursor.index.clamp(self.char_offset, end) - self.char_offset)
+        };
+        Some(CCursorRange {
+            primary: local(range.primary),
+            secondary: local(range.secondary),
+            h_pos: None,
+        })
+    }
+}
+
+/// The height of the given number of lines.
+///
+/// Computed in `f64`, so that it stays exact for documents with millions of lines.
+fn lines_height(num_lines: usize, row_height: f32) -> f32 {
+    (num_lines as f64 * row_height as f64) as f32
+}
+
+/// The lines that are (at least partially) inside the clip rectangle.
+fn visible_lines(ui: &Ui, rect: Rect, row_height: f32, num_lines: usize) -> Range<usize> {
+    let clip_rect = ui.clip_rect();
+    let first = ((clip_rect.top() - rect.top()) / row_height)
+        .floor()
+        .max(0.0) as usize;
+    let last = ((clip_rect.bottom() - rect.top()) / row_height)
+        .ceil()
+        .max(0.0) as usize;
+    let last = last.min(num_lines);
+    first.min(last)..last
+}
+
+/// Show a [`TextEdit`] whose text is a [`LineBuffer`].
+pub(super) fn show(ui: &mut Ui, edit: TextEdit<'_>) -> TextEditOutput {
+    let TextEdit {
+        text,
+        hint_text,
+        hint_text_font,
+        id,
+        id_salt,
+        font_selection,
+        text_color,
+        layouter,
+        password,
+        frame: _,
+        margin,
+        multiline: _,
+        interactive,
+        desired_width,
+        desired_height_rows,
+        event_filter,
+        cursor_at_end,
+        min_size,
+        align: _,
+        clip_text: _,
+        char_limit,
+        return_key,
+        background_color: _,
+        virtualized: _,
+        spell_checker: _,
+        mask: _,
+        char_filter,
+        validator,
+    } = edit;
+    let Some(text) = text.as_line_buffer_mut() else {
+        unreachable!("`TextEdit::show` only gets here for a `LineBuffer`");
+    };
+
+    let hint_text_str = hint_text.text().to_owned();
+    let text_color = text_color
+        .or(ui.visuals().override_text_color)
+        .unwrap_or_else(|| ui.visuals().widgets.inactive.text_color());
+
+    let font_id = font_selection.resolve(ui.style());
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    const MIN_WIDTH: f32 = 24.0; // Never make a [`TextEdit`] more narrow than this.
+    let available_width = (ui.available_width() - margin.sum().x).at_least(MIN_WIDTH);
+    let desired_width = desired_width.unwrap_or_else(|| ui.spacing().text_edit_width);
+    let inner_width = if ui.layout().horizontal_justify() {
+        available_width
+    } else {
+        desired_width.min(available_width)
+    };
+
+    let content_height = lines_height(text.num_lines(), row_height);
+    let desired_height = (desired_height_rows.at_least(1) as f32) * row_height;
+    let desired_inner_size = vec2(inner_width, content_height.max(desired_height));
+    let desired_outer_size = (desired_inner_size + margin.sum()).at_least(min_size);
+    let (auto_id, outer_rect) = ui.allocate_space(desired_outer_size);
+    let rect = outer_rect - margin; // inner rect (excluding frame/margin).
+
+    let id = id.unwrap_or_else(|| {
+        if let Some(id_salt) = id_salt {
+            ui.make_persistent_id(id_salt)
+        } else {
+            auto_id
+        }
+    });
+    let mut state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
+
+    let layouter = LineLayouter {
+        layouter,
+        font_id: font_id.clone(),
+        text_color,
+        password,
+    };
+    let lines = visible_lines(ui, rect, row_height, text.num_lines());
+    let mut view = View::new(ui, text, layouter, row_height, lines);
+    let prev_visible_text = text.lines(view.lines.clone()).to_owned();
+
+    let allow_drag_to_select =
+        ui.input(|i| !i.has_touch_screen()) || ui.memory(|mem| mem.has_focus(id));
+    let sense = if interactive {
+        if allow_drag_to_select {
+            Sense::click_and_drag()
+        } else {
+            Sense::click()
+        }
+    } else {
+        Sense::hover()
+    };
+    let mut response = ui.interact(outer_rect, id, sense);
+    response.intrinsic_size = Some(Vec2::new(desired_width, desired_outer_size.y));
+
+    // Don't sent `OutputEvent::Clicked` when a user presses the space bar
+    response.flags -= response::Flags::FAKE_PRIMARY_CLICKED;
+    let painter = ui.painter_at(rect.expand(1.0)); // expand to avoid clipping cursor
+
+    if interactive {
+        if let Some(pointer_pos) = response.interact_pointer_pos() {
+            if response.hovered() && text.is_mutable() {
+                ui.output_mut(|o| o.mutable_text_under_cursor = true);
+            }
+
+            let line = ((pointer_pos.y - rect.top()) / row_height).floor().max(0.0) as usize;
+            let line = line.min(text.num_lines() - 1);
+            let cursor_at_pointer = view.cursor_at(ui, text, line, pointer_pos.x - rect.left());
+
+            if ui.visuals().text_cursor.preview
+                && response.hovered()
+                && ui.input(|i| i.pointer.is_moving())
+            {
+                // text cursor preview:
+                let cursor_rect = view
+                    .cursor_rect(ui, text, cursor_at_pointer)
+                    .translate(rect.min.to_vec2());
+                text_selection::visuals::paint_cursor_end(&painter, ui.visuals(), cursor_rect);
+            }
+
+            let is_being_dragged = ui.ctx().is_being_dragged(response.id);
+            let did_interact = pointer_interaction(
+                ui,
+                &response,
+                &mut state.cursor,
+                text,
+                cursor_at_pointer,
+                is_being_dragged,
+            );
+
+            if did_interact || response.clicked() {
+                ui.memory_mut(|mem| mem.request_focus(response.id));
+
+                state.last_interaction_time = ui.ctx().input(|i| i.time);
+            }
+        }
+    }
+
+    if interactive && response.hovered() {
+        ui.ctx().set_cursor_icon(CursorIcon::Text);
+    }
+
+    let clamp = |range: CCursorRange, num_chars: usize| CCursorRange {
+        primary: CCursor::new(range.primary.index.min(num_chars)),
+        secondary: CCursor::new(range.secondary.index.min(num_chars)),
+        h_pos: range.h_pos,
+    };
+
+    let mut cursor_range = None;
+    let prev_cursor_range = state
+        .cursor
+        .char_range()
+        .map(|range| clamp(range, text.num_chars()));
+    if interactive && ui.memory(|mem| mem.has_focus(id)) {
+        ui.memory_mut(|mem| mem.set_focus_lock_filter(id, event_filter));
+
+        let default_cursor_range = if cursor_at_end {
+            CCursorRange::one(CCursor::new(text.num_chars()))
+        } else {
+            CCursorRange::default()
+        };
+        let rows_per_page = (ui.clip_rect().intersect(rect).height() / row_height)
+            .floor()
+            .at_least(1.0) as usize;
+
+        let (changed, new_cursor_range) = events(
+            ui,
+            &mut state,
+            text,
+            &mut view,
+            prev_cursor_range.unwrap_or(default_cursor_range),
+            rows_per_page,
+            editing::InsertFilter {
+                password,
+                char_limit,
+                char_filter,
+            },
+            event_filter,
+            return_key,
+        );
+
+        if changed {
+            response.mark_changed();
+        }
+        cursor_range = Some(new_cursor_range);
+    }
+
+    // The text may have changed, so lay out what is visible now:
+    view.layout(
+        ui,
+        text,
+        visible_lines(ui, rect, row_height, text.num_lines()),
+    );
+    let galley_pos = rect.min + vec2(0.0, lines_height(view.lines.start, row_height));
+
+    FindInPageState::on_lines(
+        ui,
+        &painter,
+        &response,
+        text,
+        &LaidOutLines {
+            text_pos: rect.min,
+            row_height,
+            galley_pos,
+            galley: &view.galley,
+            char_offset: view.char_offset,
+        },
+    );
+
+    let selection_changed =
+        if let (Some(cursor_range), Some(prev_cursor_range)) = (cursor_range, prev_cursor_range) {
+            prev_cursor_range != cursor_range
+        } else {
+            false
+        };
+
+    let mut galley = view.galley.clone();
+    if ui.is_rect_visible(rect) {
+        if text.num_chars() == 0 && !hint_text.is_empty() {
+            let hint_text_color = ui.visuals().weak_text_color();
+            let hint_text_font_id = hint_text_font.unwrap_or(font_id.into());
+            let galley = hint_text.into_galley(
+                ui,
+                Some(TextWrapMode::Wrap),
+                desired_inner_size.x,
+                hint_text_font_id,
+            );
+            painter.galley(rect.min, galley, hint_text_color);
+        }
+
+        let has_focus = ui.memory(|mem| mem.has_focus(id));
+
+        if has_focus {
+            if let Some(range) = state.cursor.char_range() {
+                if let Some(galley_range) = view.galley_range(&range) {
+                    // Add text selection rectangles to the galley:
+                    paint_text_selection(&mut galley, ui.visuals(), &galley_range, None);
+                }
+            }
+        }
+
+        // Allocate additional space if the text grew, so that an enclosing `ScrollArea` can scroll to it.
+        let content_size = vec2(galley.size().x, lines_height(text.num_lines(), row_height));
+        let extra_size = content_size - rect.size();
+        if extra_size.x > 0.0 || extra_size.y > 0.0 {
+            ui.allocate_rect(
+                Rect::from_min_size(outer_rect.max, extra_size.max(Vec2::ZERO)),
+                Sense::hover(),
+            );
+        }
+
+        painter.galley(galley_pos, galley.clone(), text_color);
+    }
+
+    if let Some(range) = state.cursor.char_range() {
+        let range = clamp(range, text.num_chars());
+        let primary_cursor_rect = view
+            .cursor_rect(ui, text, range.primary)
+            .translate(rect.min.to_vec2());
+
+        // Scroll to keep the primary cursor in view, also when the cursor was moved
+        // from outside, e.g. to show a search result:
+        let shown = ui.data(|d| d.get_temp::<ShownCursorRange>(id));
+        if response.changed()
+            || selection_changed
+            || shown.is_some_and(|ShownCursorRange(shown)| shown != range)
+        {
+            ui.scroll_to_rect(primary_cursor_rect + margin, None);
+        }
+        ui.data_mut(|d| d.insert_temp(id, ShownCursorRange(range)));
+
+        let has_focus = ui.memory(|mem| mem.has_focus(id));
+        if has_focus && text.is_mutable() && interactive && ui.is_rect_visible(rect) {
+            let now = ui.ctx().input(|i| i.time);
+            if response.changed() || selection_changed {
+                state.last_interaction_time = now;
+            }
+
+            // Only show (and blink) cursor if the egui viewport has focus.
+            let viewport_has_focus = ui.ctx().input(|i| i.focused);
+            if viewport_has_focus {
+                text_selection::visuals::paint_text_cursor(
+                    ui,
+                    &painter,
+                    primary_cursor_rect,
+                    now - state.last_interaction_time,
+                );
+            }
+
+            // Set IME output (in screen coords) when text is editable and visible
+            let to_global = ui
+                .ctx()
+                .layer_affine_to_global(ui.layer_id())
+                .unwrap_or_default();
+
+            ui.ctx().output_mut(|o| {
+                o.ime = Some(crate::output::IMEOutput {
+                    rect: to_global * rect,
+                    cursor_rect: to_global * primary_cursor_rect,
+                });
+            });
+        }
+    }
+
+    // Ensures correct IME behavior when the text input area gains or loses focus.
+    if state.ime_enabled && (response.gained_focus() || response.lost_focus()) {
+        state.ime_enabled = false;
+        if let Some(mut ccursor_range) = state.cursor.char_range() {
+            ccursor_range.secondary.index = ccursor_range.primary.index;
+            state.cursor.set_char_range(Some(ccursor_range));
+        }
+        ui.input_mut(|i| i.events.retain(|e| !matches!(e, Event::Ime(_))));
+    }
+
+    state.clone().store(ui.ctx(), id);
+
+    // Only the visible text is reported, since the whole document could be huge:
+    let mask = |text: &str| {
+        if password {
+            std::iter::repeat_n(
+                epaint::text::PASSWORD_REPLACEMENT_CHAR,
+                text.chars().count(),
+            )
+            .collect::<String>()
+        } else {
+            text.to_owned()
+        }
+    };
+    let visible_text = text.lines(view.lines.clone());
+    response.widget_info(|| {
+        WidgetInfo::text_edit(
+            ui.is_enabled(),
+            mask(&prev_visible_text),
+            mask(visible_text),
+            hint_text_str.as_str(),
+        )
+    });
+
+    #[cfg(feature = "accesskit")]
+    {
+        let role = if password {
+            accesskit::Role::PasswordInput
+        } else {
+            accesskit::Role::MultilineTextInput
+        };
+
+        crate::text_selection::accesskit_text::update_accesskit_for_text_widget(
+            ui.ctx(),
+            id,
+            cursor_range.and_then(|range| view.galley_range(&range)),
+            role,
+            emath::Affine2::from_translation(galley_pos.to_vec2()),
+            &galley,
+        );
+    }
+
+    let validation_error = validator.and_then(|validator| validator.validate(text.as_str()).err());
+
+    TextEditOutput {
+        response,
+        galley,
+        galley_pos,
+        text_clip_rect: rect,
+        state,
+        cursor_range,
+        validation_error,
+    }
+}
+
+/// Handle clicking and/or dragging text, like [`TextCursorState::pointer_interaction`],
+/// but for the whole text rather than a galley.
+///
+/// Returns `true` if there was interaction.
+fn pointer_interaction(
+    ui: &Ui,
+    response: &Response,
+    cursor: &mut TextCursorState,
+    text: &dyn LineBuffer,
+    cursor_at_pointer: CCursor,
+    is_being_dragged: bool,
+) -> bool {
+    let line = text.line_from_char_index(cursor_at_pointer.index);
+    let line_start = text.line_start_char_index(line);
+
+    if response.double_clicked() {
+        // Select word:
+        let word = select_word_at(text.line(line), cursor_at_pointer - line_start);
+        cursor.set_char_range(Some(CCursorRange::two(
+            word.secondary + line_start,
+            word.primary + line_start,
+        )));
+        true
+    } else if response.triple_clicked() {
+        // Select line:
+        cursor.set_char_range(Some(CCursorRange::two(
+            CCursor::new(line_start),
+            CCursor::new(text.line_end_char_index(line)),
+        )));
+        true
+    } else if response.sense.senses_drag() {
+        if response.hovered() && ui.input(|i| i.pointer.any_pressed()) {
+            // The start of a drag (or a click).
+            let range = match cursor.char_range() {
+                Some(mut range) if ui.input(|i| i.modifiers.shift) => {
+                    range.primary = cursor_at_pointer;
+                    range
+                }
+                _ => CCursorRange::one(cursor_at_pointer),
+            };
+            cursor.set_char_range(Some(range));
+            true
+        } else if is_being_dragged {
+            // Drag to select text:
+            if let Some(mut range) = cursor.char_range() {
+                range.primary = cursor_at_pointer;
+                cursor.set_char_range(Some(range));
+            }
+            true
+        } else {
+            false
+        }
+    } else {
+        false
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Check for (keyboard) events to edit the cursor and/or text.
+///
+/// Like the events of a normal [`super::TextEdit`], except that undo and redo are not supported.
+#[expect(clippy::too_many_arguments)]
+fn events(
+    ui: &Ui,
+    state: &mut TextEditState,
+    text: &mut dyn LineBuffer,
+    view: &mut View<'_>,
+    mut cursor_range: CCursorRange,
+    rows_per_page: usize,
+    filter: editing::InsertFilter<'_>,
+    event_filter: EventFilter,
+    return_key: Option<KeyboardShortcut>,
+) -> (bool, CCursorRange) {
+    let os = ui.ctx().os();
+
+    let mut any_change = false;
+
+    let mut events = ui.input(|i| i.filtered_events(&event_filter));
+
+    if state.ime_enabled {
+        super::builder::remove_ime_incompatible_events(&mut events);
+        // Process IME events first:
+        events.sort_by_key(|e| !matches!(e, Event::Ime(_)));
+    }
+
+    for event in &events {
+        let did_mutate_text = match event {
+            // First handle events that only changes the selection cursor, not the text:
+            Event::Key {
+                modifiers,
+                key,
+                pressed: true,
+                ..
+            } if move_cursor(
+                ui,
+                text,
+                view,
+                &mut cursor_range,
+                os,
+                modifiers,
+                *key,
+                rows_per_page,
+            ) =>
+            {
+                None
+            }
+
+            Event::Copy | Event::Cut | Event::Paste(_) | Event::Text(_) | Event::Ime(_) => {
+                editing::on_event(ui, state, text, &cursor_range, event, filter)
+            }
+
+            Event::Key {
+                key: Key::Tab,
+                pressed: true,
+                modifiers,
+                ..
+            } => {
+                if modifiers.shift {
+                    let mut ccursor = text.delete_selected(&cursor_range);
+                    text.decrease_indentation(&mut ccursor);
+                    Some(CCursorRange::one(ccursor))
+                } else {
+                    filter
+                        .replace_selection(text, &cursor_range, "\t")
+                        .map(CCursorRange::one)
+                }
+            }
+            Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } if return_key.is_some_and(|return_key| {
+                *key == return_key.logical_key && modifiers.matches_logically(return_key.modifiers)
+            }) =>
+            {
+                filter
+                    .replace_selection(text, &cursor_range, "\n")
+                    .map(CCursorRange::one)
+            }
+
+            Event::Key {
+                modifiers,
+                key,
+                pressed: true,
+                ..
+            } => editing::on_deleting_key_press(
+                os,
+                &cursor_range,
+                text,
+                find_boundary,
+                modifiers,
+                *key,
+            ),
+
+            _ => None,
+        };
+
+        if let Some(new_ccursor_range) = did_mutate_text {
+            any_change = true;
+
+            // Layout again, so that moving the cursor uses the new text:
+            view.relayout(ui, text);
+
+            cursor_range = new_ccursor_range;
+        }
+    }
+
+    state.cursor.set_char_range(Some(cursor_range));
+
+    (any_change, cursor_range)
+}
+
+/// Find a word or paragraph boundary in the line of the cursor.
+///
+/// Lines are never wrapped, so a paragraph is a line.
+fn find_boundary(text: &(dyn LineBuffer + '_), boundary: Boundary, ccursor: CCursor) -> CCursor {
+    let index = ccursor.index;
+    let line = text.line_from_char_index(index);
+    let line_start = text.line_start_char_index(line);
+    let line_end = text.line_end_char_index(line);
+    let local = CCursor::new(index - line_start);
+    CCursor::new(match boundary {
+        Boundary::ParagraphBegin => line_start,
+        Boundary::ParagraphEnd => line_end,
+
+        // Step over the newline to the previous or next line:
+        Boundary::PreviousWord if index == line_start => index.saturating_sub(1),
+        Boundary::NextWord if index == line_end => (index + 1).min(text.num_chars()),
+
+        Boundary::PreviousWord => line_start + ccursor_previous_word(text.line(line), local).index,
+        Boundary::NextWord => line_start + ccursor_next_word(text.line(line), local).index,
+    })
+}
+
+/// Check for key presses that are moving the cursor, see [`CursorKeyAction`].
+///
+/// Returns `true` if the key was handled.
+#[expect(clippy::too_many_arguments)]
+fn move_cursor(
+    ui: &Ui,
+    text: &dyn LineBuffer,
+    view: &mut View<'_>,
+    cursor_range: &mut CCursorRange,
+    os: OperatingSystem,
+    modifiers: &Modifiers,
+    key: Key,
+    rows_per_page: usize,
+) -> bool {
+    let (movement, num_lines, select) = match key {
+        Key::PageUp => (Some(CursorMovement::RowUp), rows_per_page, modifiers.shift),
+        Key::PageDown => (
+            Some(CursorMovement::RowDown),
+            rows_per_page,
+            modifiers.shift,
+        ),
+        _ => match CursorKeyAction::from_key(os, modifiers, key, !cursor_range.is_empty()) {
+            None => return false,
+            Some(CursorKeyAction::SelectAll) => {
+                *cursor_range = CCursorRange::two(CCursor::new(0), CCursor::new(text.num_chars()));
+                return true;
+            }
+            Some(CursorKeyAction::Deselect { keep_end }) => {
+                *cursor_range = CCursorRange::one(cursor_range.sorted_cursors()[keep_end as usize]);
+                return true;
+            }
+            Some(CursorKeyAction::Move { movement, select }) => (movement, 1, select),
+        },
+    };
+
+    if let Some(movement) = movement {
+        (cursor_range.primary, cursor_range.h_pos) = view.move_cursor(
+            ui,
+            text,
+            cursor_range.primary,
+            cursor_range.h_pos,
+            movement,
+            num_lines,
+        );
+    }
+    if !select {
+        cursor_range.secondary = cursor_range.primary;
+    }
+    true
+}
@@ -51,6 +51,14 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         });
     }
 
+    {
+        let mut demo_windows = egui_demo_lib::DemoWindows::default();
+        let mut harness = egui_kittest::Harness::new(|ctx| demo_windows.ui(ctx));
+        c.bench_function("demo_harness", |b| {
+            b.iter_custom(|iters| harness.benchmark(iters as usize).total_time());
+        });
+    }
+
     if false {
         let ctx = egui::Context::default();
         ctx.memory_mut(|m| m.set_everything_is_visible(true)); // give us everything
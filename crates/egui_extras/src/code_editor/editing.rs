@@ -0,0 +1,289 @@
+//! Auto-indentation and auto-closing of brackets and quotes.
+
+use egui::{Event, Key};
+
+use super::Selection;
+
+/// Opening and closing characters that are inserted in pairs.
+const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')];
+
+fn closing_char(open: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(o, _)| *o == open)
+        .map(|(_, close)| *close)
+}
+
+/// Handle an input event that needs more than what [`egui::TextEdit`] does for code.
+///
+/// `selection` is in bytes of `text`.
+/// Returns `true` if the event was handled and should not be passed on to the [`egui::TextEdit`].
+pub(crate) fn handle_event(
+    text: &mut String,
+    selection: &mut Selection,
+    event: &Event,
+    auto_indent: bool,
+    auto_close: bool,
+) -> bool {
+    match event {
+        Event::Text(typed) if auto_close => {
+            let mut chars = typed.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => type_char(text, selection, c),
+                _ => false,
+            }
+        }
+        Event::Key {
+            key: Key::Enter,
+            pressed: true,
+            modifiers,
+            ..
+        } if auto_indent && modifiers.is_none() => {
+            newline(text, selection);
+            true
+        }
+        Event::Key {
+            key: Key::Tab,
+            pressed: true,
+            modifiers,
+            ..
+        } if auto_indent && (modifiers.is_none() || modifiers.shift_only()) => {
+            indent(text, selection, modifiers.shift);
+            true
+        }
+        Event::Key {
+            key: Key::Backspace,
+            pressed: true,
+            modifiers,
+            ..
+        } if auto_close && modifiers.is_none() && selection.is_empty() => {
+            delete_empty_pair(text, selection)
+        }
+        _ => false,
+    }
+}
+
+/// Replace the selection, and place the cursor after the inserted text.
+fn replace_selection(text: &mut String, selection: &mut Selection, insert: &str) {
+    let range = selection.range();
+    text.replace_range(range.clone(), insert);
+    *selection = Selection::one(range.start + insert.len());
+}
+
+fn type_char(text: &mut String, selection: &mut Selection, c: char) -> bool {
+    let range = selection.range();
+
+    if !selection.is_empty() {
+        // Surround the selection:
+        let Some(close) = closing_char(c) else {
+            return false;
+        };
+        text.insert(range.end, close);
+        text.insert(range.start, c);
+        let len = c.len_utf8();
+        *selection = Selection {
+            anchor: selection.anchor + len,
+            cursor: selection.cursor + len,
+        };
+        return true;
+    }
+
+    let next = text[range.end..].chars().next();
+    let prev = text[..range.start].chars().next_back();
+
+    // Type over a closing character instead of adding another one:
+    if next == Some(c) && PAIRS.iter().any(|(_, close)| *close == c) {
+        *selection = Selection::one(range.end + c.len_utf8());
+        return true;
+    }
+
+    let Some(close) = closing_char(c) else {
+        return false;
+    };
+    let before_ok =
+        c != '"' || !prev.is_some_and(|prev| prev.is_alphanumeric() || prev == '"' || prev == '\\');
+    let after_ok = next.is_none_or(|next| next.is_whitespace() || ")]};,".contains(next));
+    if !before_ok || !after_ok {
+        return false;
+    }
+
+    text.insert(range.start, close);
+    text.insert(range.start, c);
+    *selection = Selection::one(range.start + c.len_utf8());
+    true
+}
+
+/// The indentation of a single level: a tab if the code is indented with tabs, otherwise four spaces.
+fn indent_unit(text: &str) -> &'static str {
+    let uses_tabs = text
+        .lines()
+        .find(|line| line.starts_with([' ', '\t']))
+        .is_some_and(|line| line.starts_with('\t'));
+    if uses_tabs { "\t" } else { "    " }
+}
+
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Insert a newline with the same indentation as the current line,
+/// and one more level after an opening bracket.
+fn newline(text: &mut String, selection: &mut Selection) {
+    let range = selection.range();
+    let start = line_start(text, range.start);
+    let indentation: String = text[start..range.start]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+
+    let prev = text[start..range.start].trim_end().chars().next_back();
+    let next = text[range.end..].chars().next();
+
+    let opens = prev.and_then(super::folding::closing_bracket);
+    if let Some(close) = opens {
+        let unit = indent_unit(text);
+        let mut insert = format!("\n{indentation}{unit}");
+        let cursor = range.start + insert.len();
+        if next == Some(close) {
+            insert += &format!("\n{indentation}");
+        }
+        text.replace_range(range, &insert);
+        *selection = Selection::one(cursor);
+    } else {
+        replace_selection(text, selection, &format!("\n{indentation}"));
+    }
+}
+
+/// Insert one level of indentation, or indent/dedent all selected lines.
+fn indent(text: &mut String, selection: &mut Selection, dedent: bool) {
+    let unit = indent_unit(text);
+    let range = selection.range();
+    let multiline = text[range.clone()].contains('\n');
+
+    if !dedent && !multiline {
+        replace_selection(text, selection, unit);
+        return;
+    }
+
+    let first_line = line_start(text, range.start);
+    let mut last_line = line_start(text, range.end);
+    if multiline && last_line == range.end {
+        // The selection ends at the start of a line, so that line is not included.
+        last_line = line_start(text, range.end - 1);
+    }
+
+    let mut line_starts: Vec<usize> = std::iter::once(first_line)
+        .chain(
+            text[first_line..last_line]
+                .match_indices('\n')
+                .map(|(i, _)| first_line + i + 1),
+        )
+        .collect();
+
+    let mut end = text[last_line..]
+        .find('\n')
+        .map_or(text.len(), |i| last_line + i);
+    let end_before = end;
+
+    // Go backwards so the earlier positions stay valid:
+    line_starts.reverse();
+    for start in line_starts {
+        if dedent {
+            let line = &text[start..];
+            let remove = if line.starts_with('\t') {
+                1
+            } else {
+                line.chars()
+                    .take(unit.len())
+                    .take_while(|c| *c == ' ')
+                    .count()
+            };
+            text.replace_range(start..start + remove, "");
+            end -= remove;
+        } else {
+            text.insert_str(start, unit);
+            end += unit.len();
+        }
+    }
+
+    *selection = if multiline {
+        Selection {
+            anchor: first_line,
+            cursor: end,
+        }
+    } else {
+        // Only dedented a single line:
+        let removed = (end_before - end).min(range.start - first_line);
+        Selection::one(range.start - removed)
+    };
+}
+
+/// Backspace between an empty pair of brackets or quotes deletes both.
+fn delete_empty_pair(text: &mut String, selection: &mut Selection) -> bool {
+    let pos = selection.cursor;
+    let prev = text[..pos].chars().next_back();
+    let next = text[pos..].chars().next();
+    match (prev, next) {
+        (Some(prev), Some(next)) if closing_char(prev) == Some(next) => {
+            let start = pos - prev.len_utf8();
+            text.replace_range(start..pos + next.len_utf8(), "");
+            *selection = Selection::one(start);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply the event to a text where `|` marks the cursor.
+    fn apply(text: &str, event: &Event) -> String {
+        let cursor = text.find('|').unwrap();
+        let mut text = text.replace('|', "");
+        let mut selection = Selection::one(cursor);
+        handle_event(&mut text, &mut selection, event, true, true);
+        text.insert(selection.cursor, '|');
+        text
+    }
+
+    fn key(key: Key) -> Event {
+        Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn auto_close() {
+        let typed = |c: &str| Event::Text(c.to_owned());
+        assert_eq!(apply("f|", &typed("(")), "f(|)");
+        assert_eq!(apply("f(|)", &typed(")")), "f()|");
+        assert_eq!(apply("f|x", &typed("(")), "f|x", "left to the TextEdit");
+        assert_eq!(apply("don|", &typed("\"")), "don|", "left to the TextEdit");
+        assert_eq!(apply("f(|)", &key(Key::Backspace)), "f|");
+    }
+
+    #[test]
+    fn auto_indent() {
+        assert_eq!(
+            apply("    fn f() {|}", &key(Key::Enter)),
+            "    fn f() {\n        |\n    }"
+        );
+        assert_eq!(apply("\tx|", &key(Key::Enter)), "\tx\n\t|");
+        assert_eq!(apply("x|", &key(Key::Tab)), "x    |");
+
+        let shift_tab = Event::Key {
+            key: Key::Tab,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::SHIFT,
+        };
+        assert_eq!(apply("        x|y", &shift_tab), "    x|y");
+    }
+}
@@ -0,0 +1,396 @@
+//! Bracket matching, fold regions, and the mapping between the code and the folded text.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+/// The closing bracket for an opening bracket.
+pub(crate) fn closing_bracket(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+/// All matching bracket pairs, as byte offsets of the opening and closing bracket.
+///
+/// Brackets in string literals and in `//` and `/* */` comments are ignored.
+/// The result is sorted by the opening bracket.
+pub(crate) fn bracket_pairs(code: &str) -> Vec<(usize, usize)> {
+    profiling::function_scope!();
+
+    let mut pairs = Vec::new();
+    let mut stack: Vec<(char, usize)> = Vec::new();
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek().is_some_and(|&(_, c)| c == '/') => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '/' if chars.peek().is_some_and(|&(_, c)| c == '*') => {
+                chars.next();
+                let mut prev = ' ';
+                for (_, c) in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '(' | '[' | '{' => stack.push((c, i)),
+            ')' | ']' | '}' => {
+                if let Some(&(open, start)) = stack.last() {
+                    if closing_bracket(open) == Some(c) {
+                        stack.pop();
+                        pairs.push((start, i));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+/// The bracket pair with a bracket just after or just before the cursor.
+pub(crate) fn pair_at_cursor(
+    pairs: &[(usize, usize)],
+    code: &str,
+    cursor: usize,
+) -> Option<(usize, usize)> {
+    let before = code
+        .get(..cursor)
+        .and_then(|s| s.chars().next_back())
+        .map(|c| cursor - c.len_utf8());
+
+    [Some(cursor), before]
+        .into_iter()
+        .flatten()
+        .find_map(|pos| {
+            pairs
+                .iter()
+                .find(|(open, close)| *open == pos || *close == pos)
+        })
+        .copied()
+}
+
+// ----------------------------------------------------------------------------
+
+/// Byte offsets of the start of each line.
+pub(crate) struct LineIndex {
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            starts,
+            len: text.len(),
+        }
+    }
+
+    /// The number of lines, including an empty last line after a trailing newline.
+    pub fn num_lines(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The line containing the given byte offset.
+    pub fn line_of(&self, pos: usize) -> usize {
+        self.starts.partition_point(|&start| start <= pos) - 1
+    }
+
+    pub fn line_start(&self, line: usize) -> usize {
+        self.starts.get(line).copied().unwrap_or(self.len)
+    }
+
+    /// The byte range of the line, including its newline.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        self.line_start(line)..self.line_start(line + 1)
+    }
+}
+
+/// A range of lines that can be folded so that only its first and last line are visible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FoldRegion {
+    /// The line with the opening bracket.
+    pub header: usize,
+
+    /// The line with the closing bracket.
+    pub end: usize,
+}
+
+impl FoldRegion {
+    /// The lines that are hidden when folded.
+    pub fn hidden_lines(&self) -> Range<usize> {
+        self.header + 1..self.end
+    }
+}
+
+/// Every bracket pair that spans at least three lines can be folded.
+///
+/// If several pairs start on the same line, the longest one is used.
+pub(crate) fn fold_regions(lines: &LineIndex, pairs: &[(usize, usize)]) -> Vec<FoldRegion> {
+    let mut regions = BTreeMap::new();
+    for &(open, close) in pairs {
+        let header = lines.line_of(open);
+        let end = lines.line_of(close);
+        if header + 2 <= end {
+            let region_end = regions.entry(header).or_insert(end);
+            *region_end = end.max(*region_end);
+        }
+    }
+    regions
+        .into_iter()
+        .map(|(header, end)| FoldRegion { header, end })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+
+/// The text shown in the editor: the code without the folded lines.
+pub(crate) struct DisplayText {
+    pub text: String,
+
+    /// For each line of [`Self::text`], the corresponding line of the code.
+    pub line_map: Vec<usize>,
+
+    /// Byte ranges of the code that are hidden. Sorted and non-overlapping.
+    hidden: Vec<Range<usize>>,
+}
+
+impl DisplayText {
+    pub fn new(
+        code: &str,
+        lines: &LineIndex,
+        regions: &[FoldRegion],
+        folded: &BTreeSet<usize>,
+    ) -> Self {
+        profiling::function_scope!();
+
+        let mut visible = vec![true; lines.num_lines()];
+        for region in regions {
+            if folded.contains(&region.header) {
+                visible[region.hidden_lines()].fill(false);
+            }
+        }
+
+        let mut text = String::with_capacity(code.len());
+        let mut line_map = Vec::new();
+        let mut hidden: Vec<Range<usize>> = Vec::new();
+        for (line, &is_visible) in visible.iter().enumerate() {
+            let range = lines.line_range(line);
+            if is_visible {
+                text.push_str(&code[range]);
+                line_map.push(line);
+            } else if let Some(last) = hidden.last_mut().filter(|last| last.end == range.start) {
+                last.end = range.end;
+            } else {
+                hidden.push(range);
+            }
+        }
+
+        Self {
+            text,
+            line_map,
+            hidden,
+        }
+    }
+
+    /// Map a byte offset in the display text to the code.
+    ///
+    /// A position right where lines are hidden maps to after the hidden lines.
+    pub fn to_code(&self, pos: usize) -> usize {
+        let mut code_pos = pos;
+        for hidden in &self.hidden {
+            if hidden.start <= code_pos {
+                code_pos += hidden.len();
+            } else {
+                break;
+            }
+        }
+        code_pos
+    }
+
+    /// Like [`Self::to_code`], but a position right where lines are hidden maps to before the hidden lines.
+    pub fn to_code_before(&self, pos: usize) -> usize {
+        let mut code_pos = pos;
+        for hidden in &self.hidden {
+            if hidden.start < code_pos {
+                code_pos += hidden.len();
+            } else {
+                break;
+            }
+        }
+        code_pos
+    }
+
+    /// Map a byte offset in the code to the display text.
+    ///
+    /// A position in hidden lines maps to where the lines are hidden.
+    pub fn to_display(&self, pos: usize) -> usize {
+        let mut removed = 0;
+        for hidden in &self.hidden {
+            if hidden.end <= pos {
+                removed += hidden.len();
+            } else {
+                if hidden.start < pos {
+                    removed += pos - hidden.start;
+                }
+                break;
+            }
+        }
+        pos - removed
+    }
+}
+
+/// The part of `old` that was replaced to get `new`, and what it was replaced with.
+///
+/// Returns `None` if the texts are the same.
+pub(crate) fn text_diff(old: &str, new: &str) -> Option<(Range<usize>, Range<usize>)> {
+    if old == new {
+        return None;
+    }
+
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(old.len().min(new.len()), |((i, _), _)| i);
+
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+
+    Some((prefix..old.len() - suffix, prefix..new.len() - suffix))
+}
+
+/// Update the folded regions after `removed` bytes of the code was replaced with `inserted`.
+///
+/// Folds whose hidden lines were edited are unfolded, and folds after the edit are moved.
+pub(crate) fn update_folds(
+    folded: &BTreeSet<usize>,
+    regions: &[FoldRegion],
+    lines: &LineIndex,
+    removed: Range<usize>,
+    removed_text: &str,
+    inserted: &str,
+) -> BTreeSet<usize> {
+    let line_delta =
+        inserted.matches('\n').count() as isize - removed_text.matches('\n').count() as isize;
+
+    regions
+        .iter()
+        .filter(|region| folded.contains(&region.header))
+        .filter_map(|region| {
+            let hidden = lines.line_start(region.header + 1)..lines.line_start(region.end);
+            let touches_hidden = if removed.is_empty() {
+                hidden.start < removed.start && removed.start < hidden.end
+            } else {
+                removed.start < hidden.end && hidden.start < removed.end
+            };
+            if touches_hidden {
+                None
+            } else if removed.start <= lines.line_start(region.header) {
+                region.header.checked_add_signed(line_delta)
+            } else {
+                Some(region.header)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "fn main() {\n    let x = [\n        1,\n    ];\n}\n";
+
+    #[test]
+    fn brackets_in_strings_and_comments_are_ignored() {
+        let code = r#"f("(", x) // )
+{ /* } */ "\"}" }"#;
+        let pairs = bracket_pairs(code);
+        assert_eq!(pairs, vec![(1, 8), (15, 31)]);
+        assert_eq!(pair_at_cursor(&pairs, code, 9), Some((1, 8)));
+        assert_eq!(pair_at_cursor(&pairs, code, 15), Some((15, 31)));
+        assert_eq!(pair_at_cursor(&pairs, code, 5), None);
+    }
+
+    #[test]
+    fn folding_maps_between_code_and_display() {
+        let lines = LineIndex::new(CODE);
+        let regions = fold_regions(&lines, &bracket_pairs(CODE));
+        assert_eq!(
+            regions,
+            vec![
+                FoldRegion { header: 0, end: 4 },
+                FoldRegion { header: 1, end: 3 }
+            ]
+        );
+
+        let folded = BTreeSet::from([1]);
+        let display = DisplayText::new(CODE, &lines, &regions, &folded);
+        assert_eq!(display.text, "fn main() {\n    let x = [\n    ];\n}\n");
+        assert_eq!(display.line_map, vec![0, 1, 3, 4, 5]);
+
+        let hidden_start = CODE.find("        1").unwrap();
+        let closing = CODE.find("    ];").unwrap();
+        let display_closing = display.text.find("    ];").unwrap();
+        assert_eq!(display.to_code(display_closing), closing);
+        assert_eq!(display.to_code_before(display_closing), hidden_start);
+        assert_eq!(display.to_display(hidden_start + 3), display_closing);
+        assert_eq!(display.to_display(closing + 2), display_closing + 2);
+    }
+
+    #[test]
+    fn edits_move_or_unfold_folds() {
+        let lines = LineIndex::new(CODE);
+        let regions = fold_regions(&lines, &bracket_pairs(CODE));
+        let folded = BTreeSet::from([1]);
+
+        // A new line before the fold moves it down:
+        let folds = update_folds(&folded, &regions, &lines, 0..0, "", "\n");
+        assert_eq!(folds, BTreeSet::from([2]));
+
+        // Typing at the end of the header keeps it:
+        let pos = CODE.find('[').unwrap() + 1;
+        let folds = update_folds(&folded, &regions, &lines, pos..pos, "", "\n");
+        assert_eq!(folds, BTreeSet::from([1]));
+
+        // Deleting into the hidden lines unfolds it:
+        let end = CODE.find("1,").unwrap();
+        let folds = update_folds(&folded, &regions, &lines, pos..end, &CODE[pos..end], "");
+        assert!(folds.is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        assert_eq!(text_diff("abc", "abc"), None);
+        assert_eq!(text_diff("abc", "abxc"), Some((2..2, 2..3)));
+        assert_eq!(text_diff("aa", "a"), Some((1..2, 1..1)));
+        assert_eq!(text_diff("héllo", "hallo"), Some((1..3, 1..2)));
+    }
+}
@@ -0,0 +1,1639 @@
+//! A code editor with a line-number gutter, folding, bracket matching,
+//! search & replace and diagnostics.
+//!
+//! Requires the `code_editor` feature.
+
+mod editing;
+mod folding;
+mod search;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+use egui::{
+    Align, Align2, Color32, Frame, Galley, Id, Key, Modifiers, PopupAnchor, Pos2, Rect, Response,
+    Sense, Shape, Stroke, StrokeKind, TextBuffer, TextEdit, Tooltip, Ui, Visuals,
+    layers::ShapeIdx,
+    mutex::Mutex,
+    pos2,
+    text::{CCursor, CCursorRange, LayoutJob, LayoutSection},
+    text_edit::{TextEditOutput, TextEditState},
+    util::undoer::Undoer,
+    vec2,
+};
+
+use crate::syntax_highlighting::{CodeTheme, IncrementalHighlighter, code_font_id};
+
+use self::folding::{DisplayText, FoldRegion, LineIndex};
+use self::search::{GoToLineAction, GoToLineState, SearchAction, SearchState};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self, visuals: &Visuals) -> Color32 {
+        match self {
+            Self::Hint => visuals.weak_text_color(),
+            Self::Info => visuals.hyperlink_color,
+            Self::Warning => visuals.warn_fg_color,
+            Self::Error => visuals.error_fg_color,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Hint => "hint",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A message about a part of the code, e.g. from a compiler or linter.
+///
+/// Shown as a wavy underline, with the message in a tooltip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Byte range in the code.
+    ///
+    /// An empty range is underlined as the character following it.
+    pub range: Range<usize>,
+
+    pub severity: Severity,
+
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(range: Range<usize>, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            range,
+            severity,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(range: Range<usize>, message: impl Into<String>) -> Self {
+        Self::new(range, Severity::Error, message)
+    }
+
+    pub fn warning(range: Range<usize>, message: impl Into<String>) -> Self {
+        Self::new(range, Severity::Warning, message)
+    }
+}
+
+/// The result of showing a [`CodeEditor`].
+pub struct CodeEditorOutput {
+    /// The response of the underlying [`TextEdit`].
+    ///
+    /// [`Response::changed`] is true if the code changed,
+    /// including by undo/redo and search & replace.
+    pub response: Response,
+
+    /// The selected byte range of the code, if the editor has been focused.
+    pub selection: Option<Range<usize>>,
+
+    /// The output of the underlying [`TextEdit`].
+    ///
+    /// Note that its text is the code with the folded lines removed.
+    pub text_edit: TextEditOutput,
+}
+
+/// A code editor, built on a [`TextEdit`].
+///
+/// On top of [`TextEdit::code_editor`] this has:
+/// * Syntax highlighting, updated incrementally with an [`IncrementalHighlighter`].
+/// * A gutter with line numbers and markers for folding `{…}`, `[…]` and `(…)` blocks.
+/// * Highlighting of the current line and of the bracket matching the one at the cursor.
+/// * Auto-indentation, and auto-closing of brackets and quotes.
+/// * Search & replace with regular expressions (`Ctrl+F` / `Ctrl+H`) and go-to-line (`Ctrl+G`).
+/// * Wavy underlines for [`Diagnostic`]s, with their message shown on hover.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// # let mut code = String::new();
+/// use egui_extras::code_editor::{CodeEditor, Diagnostic};
+///
+/// let diagnostics = [Diagnostic::error(0..2, "expected item")];
+/// CodeEditor::new(&mut code, "rs")
+///     .diagnostics(&diagnostics)
+///     .show(ui);
+/// # });
+/// ```
+#[must_use = "You should call .show()"]
+pub struct CodeEditor<'a> {
+    code: &'a mut String,
+    language: String,
+    id_salt: Id,
+    theme: Option<CodeTheme>,
+    diagnostics: &'a [Diagnostic],
+    line_numbers: bool,
+    folding: bool,
+    auto_indent: bool,
+    auto_close: bool,
+    desired_rows: usize,
+}
+
+impl<'a> CodeEditor<'a> {
+    /// Edit code in the given `language`, e.g. `"rs"` or `"python"`.
+    pub fn new(code: &'a mut String, language: impl Into<String>) -> Self {
+        Self {
+            code,
+            language: language.into(),
+            id_salt: Id::new("code_editor"),
+            theme: None,
+            diagnostics: &[],
+            line_numbers: true,
+            folding: true,
+            auto_indent: true,
+            auto_close: true,
+            desired_rows: 10,
+        }
+    }
+
+    /// Must be set if multiple editors are in the same [`Ui`].
+    #[inline]
+    pub fn id_salt(mut self, id_salt: impl std::hash::Hash) -> Self {
+        self.id_salt = Id::new(id_salt);
+        self
+    }
+
+    /// The theme for the syntax highlighting.
+    ///
+    /// Default: [`CodeTheme::from_memory`].
+    #[inline]
+    pub fn theme(mut self, theme: CodeTheme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Messages to show as wavy underlines.
+    ///
+    /// These should be for the code as it was passed to [`Self::new`].
+    #[inline]
+    pub fn diagnostics(mut self, diagnostics: &'a [Diagnostic]) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Show line numbers in the gutter. Default: `true`.
+    #[inline]
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Allow folding blocks that span at least three lines. Default: `true`.
+    #[inline]
+    pub fn folding(mut self, folding: bool) -> Self {
+        self.folding = folding;
+        self
+    }
+
+    /// Keep the indentation on new lines, and indent after an opening bracket. Default: `true`.
+    ///
+    /// This also makes `Tab` and `Shift+Tab` indent and dedent the selected lines.
+    #[inline]
+    pub fn auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// Insert the closing bracket or quote when typing an opening one. Default: `true`.
+    #[inline]
+    pub fn auto_close(mut self, auto_close: bool) -> Self {
+        self.auto_close = auto_close;
+        self
+    }
+
+    /// The minimum height of the editor, in rows. Default: `10`.
+    #[inline]
+    pub fn desired_rows(mut self, desired_rows: usize) -> Self {
+        self.desired_rows = desired_rows;
+        self
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// A selection in byte offsets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Selection {
+    /// Where the selection started.
+    anchor: usize,
+
+    /// Where the cursor is.
+    cursor: usize,
+}
+
+impl Selection {
+    fn one(pos: usize) -> Self {
+        Self {
+            anchor: pos,
+            cursor: pos,
+        }
+    }
+
+    fn range(&self) -> Range<usize> {
+        self.anchor.min(self.cursor)..self.anchor.max(self.cursor)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.anchor == self.cursor
+    }
+
+    fn map(self, f: impl Fn(usize) -> usize) -> Self {
+        Self {
+            anchor: f(self.anchor),
+            cursor: f(self.cursor),
+        }
+    }
+}
+
+/// Converts between byte and char offsets of a text.
+struct CharIndex {
+    /// The byte offset of each char, followed by the length of the text.
+    byte_offsets: Vec<usize>,
+}
+
+impl CharIndex {
+    fn new(text: &str) -> Self {
+        Self {
+            byte_offsets: text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(text.len()))
+                .collect(),
+        }
+    }
+
+    fn to_char(&self, byte: usize) -> usize {
+        self.byte_offsets.partition_point(|&b| b < byte)
+    }
+
+    fn to_byte(&self, char: usize) -> usize {
+        let last = self.byte_offsets.len() - 1;
+        self.byte_offsets[char.min(last)]
+    }
+}
+
+fn floor_char_boundary(text: &str, mut pos: usize) -> usize {
+    pos = pos.min(text.len());
+    while !text.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    pos
+}
+
+type HighlighterKey = (CodeTheme, String, egui::FontId);
+
+/// The lines, brackets and fold regions of the code, which only change when the code does.
+struct Analysis {
+    /// Hash of the code and whether folding is enabled.
+    key: u64,
+    lines: LineIndex,
+    pairs: Vec<(usize, usize)>,
+    regions: Vec<FoldRegion>,
+}
+
+impl Analysis {
+    fn new(code: &str, folding: bool, key: u64) -> Self {
+        let lines = LineIndex::new(code);
+        let pairs = folding::bracket_pairs(code);
+        let regions = if folding {
+            folding::fold_regions(&lines, &pairs)
+        } else {
+            Vec::new()
+        };
+        Self {
+            key,
+            lines,
+            pairs,
+            regions,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct State {
+    /// The header lines of the folded regions.
+    folded: BTreeSet<usize>,
+
+    /// In bytes of the code, so that it stays valid when folding changes.
+    selection: Option<Selection>,
+
+    /// Scroll the cursor into view, e.g. after jumping to a search result.
+    scroll_to_cursor: bool,
+
+    /// [`TextEdit`]s own undo works on the text with the folded lines removed,
+    /// so we keep our own for the code.
+    undoer: Arc<Mutex<Undoer<(Selection, String)>>>,
+
+    highlighter: Option<(HighlighterKey, Arc<Mutex<IncrementalHighlighter>>)>,
+
+    /// See [`Self::analyze`].
+    analysis: Option<Arc<Analysis>>,
+
+    /// The [`Analysis::key`] of the code, until it changes. Cleared every frame,
+    /// since the code may also be changed outside of the editor.
+    code_key: Option<u64>,
+
+    /// See [`Self::display_text`]. Keyed on [`Analysis::key`] and the folds.
+    display: Option<(u64, BTreeSet<usize>, Arc<DisplayText>)>,
+
+    /// The text for the [`TextEdit`] from the last frame, if it is still the same as this [`DisplayText`],
+    /// so that we don't need to copy it every frame.
+    edit_buffer: Option<(Arc<DisplayText>, String)>,
+
+    /// See [`CachedLayoutJob`].
+    layout_job: Option<CachedLayoutJob>,
+
+    search: SearchState,
+
+    go_to_line: GoToLineState,
+}
+
+impl State {
+    /// Call this whenever the code is changed.
+    fn code_changed(&mut self) {
+        self.code_key = None;
+    }
+
+    /// Analyze the code, or reuse the analysis from an earlier frame if the code is the same.
+    ///
+    /// Also forgets the folds that no longer exist.
+    fn analyze(&mut self, code: &str, folding: bool) -> Arc<Analysis> {
+        let key = *self
+            .code_key
+            .get_or_insert_with(|| egui::util::hash((code, folding)));
+        let analysis = match &self.analysis {
+            Some(analysis) if analysis.key == key => analysis.clone(),
+            _ => {
+                let analysis = Arc::new(Analysis::new(code, folding, key));
+                self.analysis = Some(analysis.clone());
+                analysis
+            }
+        };
+        self.folded.retain(|header| {
+            analysis
+                .regions
+                .iter()
+                .any(|region| region.header == *header)
+        });
+        analysis
+    }
+
+    /// The code without the folded lines, reused from an earlier frame if nothing changed.
+    fn display_text(&mut self, code: &str, analysis: &Analysis) -> Arc<DisplayText> {
+        match &self.display {
+            Some((key, folded, display)) if *key == analysis.key && *folded == self.folded => {
+                display.clone()
+            }
+            _ => {
+                let display = Arc::new(DisplayText::new(
+                    code,
+                    &analysis.lines,
+                    &analysis.regions,
+                    &self.folded,
+                ));
+                self.display = Some((analysis.key, self.folded.clone(), display.clone()));
+                display
+            }
+        }
+    }
+
+    /// Unfold any folds that hide the selection.
+    fn reveal_selection(&mut self, regions: &[FoldRegion], lines: &LineIndex) {
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let first = lines.line_of(selection.range().start);
+        let last = lines.line_of(selection.range().end);
+        for region in regions {
+            let hidden = region.hidden_lines();
+            if hidden.start <= last && first < hidden.end {
+                self.folded.remove(&region.header);
+            }
+        }
+        self.scroll_to_cursor = true;
+    }
+
+    fn toggle_fold(&mut self, region: &FoldRegion, lines: &LineIndex) {
+        if self.folded.remove(&region.header) {
+            return;
+        }
+        self.folded.insert(region.header);
+
+        // Move the cursor out of the hidden lines:
+        if let Some(selection) = self.selection {
+            let hidden = lines.line_start(region.header + 1)..lines.line_start(region.end);
+            if hidden.contains(&selection.anchor) || hidden.contains(&selection.cursor) {
+                self.selection = Some(Selection::one(hidden.start - 1));
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+impl CodeEditor<'_> {
+    pub fn show(self, ui: &mut Ui) -> CodeEditorOutput {
+        profiling::function_scope!();
+
+        let Self {
+            code,
+            language,
+            id_salt,
+            theme,
+            diagnostics,
+            line_numbers,
+            folding,
+            auto_indent,
+            auto_close,
+            desired_rows,
+        } = self;
+
+        let id = ui.make_persistent_id(id_salt);
+        let text_edit_id = id.with("text_edit");
+        let mut state: State = ui.data_mut(|d| d.remove_temp(id)).unwrap_or_else(|| {
+            let state = State::default();
+            state
+                .undoer
+                .lock()
+                .add_undo(&(Selection::default(), code.clone()));
+            state
+        });
+        state.code_changed();
+        let theme = theme.unwrap_or_else(|| CodeTheme::from_memory(ui.ctx(), ui.style()));
+        let font_id = code_font_id(ui.style());
+        let mut changed = false;
+
+        if let Some(selection) = &mut state.selection {
+            *selection = selection.map(|pos| floor_char_boundary(code, pos));
+        }
+
+        // Keyboard shortcuts:
+        let editor_focused = ui.memory(|mem| mem.has_focus(text_edit_id));
+        let bar_focused = ui.memory(|mem| mem.focused()).is_some_and(|focused| {
+            [
+                id.with("query"),
+                id.with("replacement"),
+                id.with("go_to_line"),
+            ]
+            .contains(&focused)
+        });
+        if editor_focused || bar_focused {
+            let (find, replace, go_to_line) = ui.input_mut(|i| {
+                (
+                    i.consume_key(Modifiers::COMMAND, Key::F),
+                    i.consume_key(Modifiers::COMMAND, Key::H),
+                    i.consume_key(Modifiers::COMMAND, Key::G),
+                )
+            });
+            if find || replace {
+                state.search.open = true;
+                state.search.show_replace |= replace;
+                state.search.request_focus = true;
+                if let Some(selection) = state.selection {
+                    let selected = &code[selection.range()];
+                    if !selected.is_empty() && !selected.contains('\n') {
+                        state.search.query = selected.to_owned();
+                    }
+                }
+            }
+            if go_to_line {
+                state.go_to_line.open = true;
+                state.go_to_line.request_focus = true;
+            }
+        }
+
+        let mut reveal_selection = false;
+
+        if state.search.open {
+            let matches = state.search.matches(code);
+            let current = state.selection.and_then(|selection| {
+                let selected = selection.range();
+                matches.as_ref().ok()?.iter().position(|m| *m == selected)
+            });
+            let action = search::search_bar(ui, &mut state.search, id, &matches, current);
+            if let Some(action) = action {
+                let matches = state.search.matches(code).unwrap_or_default();
+                let selected = state.selection.unwrap_or_default().range();
+                match action {
+                    SearchAction::Next | SearchAction::Previous => {
+                        let forward = matches!(action, SearchAction::Next);
+                        if let Some(found) = find_match(&matches, &selected, forward) {
+                            state.selection = Some(Selection {
+                                anchor: found.start,
+                                cursor: found.end,
+                            });
+                            reveal_selection = true;
+                        }
+                    }
+                    SearchAction::Replace => {
+                        let mut from = selected.clone();
+                        if matches.contains(&selected) {
+                            state
+                                .undoer
+                                .lock()
+                                .add_undo(&(state.selection.unwrap_or_default(), code.clone()));
+                            from = state.search.replace(code, selected);
+                            state.code_changed();
+                            changed = true;
+                        }
+                        let matches = state.search.matches(code).unwrap_or_default();
+                        let next = find_match(&matches, &(from.end..from.end), true);
+                        state.selection =
+                            Some(next.map_or(Selection::one(from.end), |found| Selection {
+                                anchor: found.start,
+                                cursor: found.end,
+                            }));
+                        reveal_selection = true;
+                    }
+                    SearchAction::ReplaceAll => {
+                        state
+                            .undoer
+                            .lock()
+                            .add_undo(&(state.selection.unwrap_or_default(), code.clone()));
+                        if state.search.replace_all(code) > 0 {
+                            state.code_changed();
+                            changed = true;
+                            state.selection = state
+                                .selection
+                                .map(|selection| Selection::one(selection.range().start))
+                                .map(|selection| {
+                                    selection.map(|pos| floor_char_boundary(code, pos))
+                                });
+                        }
+                    }
+                    SearchAction::Close => {
+                        state.search.open = false;
+                        ui.memory_mut(|mem| mem.request_focus(text_edit_id));
+                    }
+                }
+            }
+        }
+
+        if state.go_to_line.open {
+            let analysis = state.analyze(code, folding);
+            let lines = &analysis.lines;
+            match search::go_to_line_bar(ui, &mut state.go_to_line, id, lines.num_lines()) {
+                Some(GoToLineAction::Go { line, column }) => {
+                    let line = line.min(lines.num_lines() - 1);
+                    let range = lines.line_range(line);
+                    let text = code[range.clone()].trim_end_matches('\n');
+                    let column = text
+                        .char_indices()
+                        .nth(column)
+                        .map_or(text.len(), |(i, _)| i);
+                    state.selection = Some(Selection::one(range.start + column));
+                    state.go_to_line = Default::default();
+                    reveal_selection = true;
+                    ui.memory_mut(|mem| mem.request_focus(text_edit_id));
+                }
+                Some(GoToLineAction::Close) => {
+                    state.go_to_line = Default::default();
+                    ui.memory_mut(|mem| mem.request_focus(text_edit_id));
+                }
+                None => {}
+            }
+        }
+
+        if editor_focused {
+            let (redo, undo) = ui.input_mut(|i| {
+                let redo = i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                    || i.consume_key(Modifiers::COMMAND, Key::Y);
+                let undo = !redo && i.consume_key(Modifiers::COMMAND, Key::Z);
+                (redo, undo)
+            });
+            if undo || redo {
+                let current = (state.selection.unwrap_or_default(), code.clone());
+                let mut undoer = state.undoer.lock();
+                let restored = if undo {
+                    undoer.undo(&current)
+                } else {
+                    undoer.redo(&current)
+                };
+                if let Some((selection, text)) = restored.cloned() {
+                    drop(undoer);
+                    *code = text;
+                    state.selection = Some(selection);
+                    state.code_changed();
+                    changed = true;
+                    reveal_selection = true;
+                }
+            }
+        }
+
+        let highlighter_key = (theme, language, font_id.clone());
+        let highlighter = match &state.highlighter {
+            Some((key, highlighter)) if *key == highlighter_key => highlighter.clone(),
+            _ => {
+                let (theme, language, _) = &highlighter_key;
+                let highlighter = Arc::new(Mutex::new(IncrementalHighlighter::new(
+                    ui.ctx(),
+                    ui.style(),
+                    theme,
+                    language,
+                )));
+                state.highlighter = Some((highlighter_key, highlighter.clone()));
+                highlighter
+            }
+        };
+        highlighter.lock().update(code);
+
+        let analysis = state.analyze(code, folding);
+        if reveal_selection {
+            state.reveal_selection(&analysis.regions, &analysis.lines);
+        }
+        let display = state.display_text(code, &analysis);
+
+        // Hand over the selection to the `TextEdit`:
+        let mut text_edit_state = TextEditState::load(ui.ctx(), text_edit_id).unwrap_or_default();
+        let mut display_text = match state.edit_buffer.take() {
+            Some((buffered, text)) if Arc::ptr_eq(&buffered, &display) => text,
+            _ => display.text.clone(),
+        };
+        {
+            let chars = CharIndex::new(&display_text);
+            if let Some(selection) = state.selection {
+                let selection = selection.map(|pos| chars.to_char(display.to_display(pos)));
+                let current = text_edit_state.cursor.char_range();
+                let is_same = current.is_some_and(|range| {
+                    range.secondary.index == selection.anchor
+                        && range.primary.index == selection.cursor
+                });
+                if !is_same {
+                    text_edit_state
+                        .cursor
+                        .set_char_range(Some(CCursorRange::two(
+                            CCursor::new(selection.anchor),
+                            CCursor::new(selection.cursor),
+                        )));
+                }
+            }
+
+            if editor_focused && (auto_indent || auto_close) {
+                if let Some(range) = text_edit_state.cursor.char_range() {
+                    let mut selection = Selection {
+                        anchor: chars.to_byte(range.secondary.index),
+                        cursor: chars.to_byte(range.primary.index),
+                    };
+                    let mut handled_any = false;
+                    let mut pass_through = false;
+                    ui.input_mut(|i| {
+                        i.events.retain(|event| {
+                            if pass_through {
+                                return true;
+                            }
+                            let handled = editing::handle_event(
+                                &mut display_text,
+                                &mut selection,
+                                event,
+                                auto_indent,
+                                auto_close,
+                            );
+                            handled_any |= handled;
+                            // Keep the order of the edits:
+                            pass_through = !handled
+                                && matches!(
+                                    event,
+                                    egui::Event::Text(_)
+                                        | egui::Event::Paste(_)
+                                        | egui::Event::Key { pressed: true, .. }
+                                );
+                            !handled
+                        });
+                    });
+                    if handled_any {
+                        let chars = CharIndex::new(&display_text);
+                        text_edit_state
+                            .cursor
+                            .set_char_range(Some(CCursorRange::two(
+                                CCursor::new(chars.to_char(selection.anchor)),
+                                CCursor::new(chars.to_char(selection.cursor)),
+                            )));
+                    }
+                }
+            }
+        }
+        text_edit_state.store(ui.ctx(), text_edit_id);
+
+        let gutter = Gutter::new(
+            ui,
+            &font_id,
+            analysis.lines.num_lines(),
+            line_numbers,
+            folding,
+        );
+        let visuals = ui.visuals().clone();
+        let frame_stroke = if editor_focused {
+            visuals.selection.stroke
+        } else {
+            visuals.widgets.inactive.bg_stroke
+        };
+        let frame = Frame::new()
+            .fill(visuals.text_edit_bg_color())
+            .stroke(frame_stroke)
+            .corner_radius(visuals.widgets.inactive.corner_radius)
+            .inner_margin(2.0);
+
+        let mut output = frame
+            .show(ui, |ui| {
+                egui::ScrollArea::both()
+                    .id_salt(id.with("scroll"))
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        let (output, background) = ui
+                            .horizontal_top(|ui| {
+                                ui.spacing_mut().item_spacing.x = 0.0;
+                                ui.add_space(gutter.width);
+                                let background = ui.painter().add(Shape::Noop);
+
+                                let underlines = diagnostic_underlines(
+                                    code,
+                                    &display,
+                                    diagnostics,
+                                    ui.visuals(),
+                                );
+                                let layout_job_cache = &mut state.layout_job;
+                                let mut layouter =
+                                    |ui: &Ui, buf: &dyn TextBuffer, _wrap_width: f32| {
+                                        let highlighter = highlighter.lock();
+                                        let job = if buf.as_str() == display.text {
+                                            CachedLayoutJob::get(
+                                                layout_job_cache,
+                                                &highlighter,
+                                                &display,
+                                                &underlines,
+                                            )
+                                        } else {
+                                            // Edited this frame, so this is only shown once:
+                                            Arc::new(layout_job(
+                                                &highlighter,
+                                                &display,
+                                                &underlines,
+                                                buf.as_str(),
+                                            ))
+                                        };
+                                        ui.fonts(|f| f.layout_job((*job).clone()))
+                                    };
+                                let output = TextEdit::multiline(&mut display_text)
+                                    .id(text_edit_id)
+                                    .code_editor()
+                                    .frame(false)
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(desired_rows)
+                                    .layouter(&mut layouter)
+                                    .show(ui);
+                                (output, background)
+                            })
+                            .inner;
+
+                        let edited = apply_edit(
+                            code,
+                            &mut state,
+                            &display,
+                            &analysis.lines,
+                            &analysis.regions,
+                            &display_text,
+                            output.cursor_range,
+                        );
+                        if edited {
+                            state.code_changed();
+                            changed = true;
+                        } else {
+                            state.edit_buffer =
+                                Some((display.clone(), std::mem::take(&mut display_text)));
+                        }
+
+                        // Between changes we only need to feed the undoer until it has settled:
+                        {
+                            let mut undoer = state.undoer.lock();
+                            if changed || undoer.is_in_flux() {
+                                undoer.feed_state(
+                                    ui.input(|i| i.time),
+                                    &(state.selection.unwrap_or_default(), code.clone()),
+                                );
+                            }
+                        }
+
+                        let options = PaintOptions {
+                            id,
+                            diagnostics,
+                            line_numbers,
+                            font_id: &font_id,
+                            gutter,
+                            background,
+                        };
+                        // The code and the folds may have changed, so we need to look at them again:
+                        let analysis = state.analyze(code, folding);
+                        let display = state.display_text(code, &analysis);
+                        paint(ui, code, &mut state, &analysis, &display, &output, &options);
+                        output
+                    })
+                    .inner
+            })
+            .inner;
+
+        if changed {
+            output.response.mark_changed();
+        }
+
+        let selection = state.selection.map(|selection| selection.range());
+        ui.data_mut(|d| d.insert_temp(id, state));
+
+        CodeEditorOutput {
+            response: output.response.clone(),
+            selection,
+            text_edit: output,
+        }
+    }
+}
+
+/// Apply the edit of the display text to the code, and update the selection.
+///
+/// Returns `true` if the code changed.
+fn apply_edit(
+    code: &mut String,
+    state: &mut State,
+    display: &DisplayText,
+    lines: &LineIndex,
+    regions: &[FoldRegion],
+    edited_text: &str,
+    cursor_range: Option<CCursorRange>,
+) -> bool {
+    let edit = folding::text_diff(&display.text, edited_text).map(|(old_range, new_range)| {
+        // An insertion right where lines are hidden goes after them,
+        // and hidden lines inside a removed range are removed too.
+        let start = display.to_code(old_range.start);
+        let end = if old_range.is_empty() {
+            start
+        } else {
+            display.to_code_before(old_range.end).max(start)
+        };
+        (old_range, new_range, start..end)
+    });
+
+    if let Some((_, new_range, removed)) = &edit {
+        let inserted = &edited_text[new_range.clone()];
+        state.folded = folding::update_folds(
+            &state.folded,
+            regions,
+            lines,
+            removed.clone(),
+            &code[removed.clone()],
+            inserted,
+        );
+        code.replace_range(removed.clone(), inserted);
+    }
+
+    if let Some(range) = cursor_range {
+        let chars = CharIndex::new(edited_text);
+        let to_code = |cursor: CCursor| {
+            let pos = chars.to_byte(cursor.index);
+            match &edit {
+                Some((old_range, new_range, removed)) if new_range.start <= pos => {
+                    if pos <= new_range.end {
+                        removed.start + pos - new_range.start
+                    } else {
+                        let old_pos = display.to_code(pos - new_range.end + old_range.end);
+                        old_pos - removed.end + removed.start + new_range.len()
+                    }
+                }
+                _ => display.to_code(pos),
+            }
+        };
+        state.selection = Some(Selection {
+            anchor: to_code(range.secondary),
+            cursor: to_code(range.primary),
+        });
+    }
+
+    edit.is_some()
+}
+
+/// The sizes of the parts of the gutter.
+#[derive(Clone, Copy)]
+struct Gutter {
+    width: f32,
+    numbers_width: f32,
+    fold_width: f32,
+    digit_width: f32,
+    row_height: f32,
+}
+
+impl Gutter {
+    fn new(
+        ui: &Ui,
+        font_id: &egui::FontId,
+        num_lines: usize,
+        line_numbers: bool,
+        folding: bool,
+    ) -> Self {
+        let (digit_width, row_height) =
+            ui.fonts(|f| (f.glyph_width(font_id, '0'), f.row_height(font_id)));
+        let num_digits = num_lines.to_string().len().max(2);
+        let numbers_width = if line_numbers {
+            (num_digits + 1) as f32 * digit_width
+        } else {
+            0.0
+        };
+        let fold_width = if folding { row_height } else { 0.0 };
+        Self {
+            width: numbers_width + fold_width + digit_width,
+            numbers_width,
+            fold_width,
+            digit_width,
+            row_height,
+        }
+    }
+}
+
+struct PaintOptions<'a> {
+    id: Id,
+    diagnostics: &'a [Diagnostic],
+    line_numbers: bool,
+    font_id: &'a egui::FontId,
+    gutter: Gutter,
+
+    /// Where to put the current line highlight, behind the text.
+    background: ShapeIdx,
+}
+
+/// Paint the gutter, highlights and diagnostics, and handle clicks on the fold markers.
+fn paint(
+    ui: &Ui,
+    code: &str,
+    state: &mut State,
+    analysis: &Analysis,
+    display: &DisplayText,
+    output: &TextEditOutput,
+    options: &PaintOptions<'_>,
+) {
+    let PaintOptions {
+        id,
+        diagnostics,
+        line_numbers,
+        font_id,
+        gutter,
+        background,
+    } = *options;
+
+    let Analysis {
+        lines,
+        pairs,
+        regions,
+        ..
+    } = analysis;
+    if display.text != output.galley.text() {
+        // The galley is out of date, so wait for the next frame.
+        ui.ctx().request_repaint();
+        return;
+    }
+
+    let layout = TextLayout::new(&output.galley, output.galley_pos, display);
+    let painter = ui.painter();
+    let visuals = ui.visuals();
+    let viewport = ui.clip_rect();
+    let cursor = state.selection.filter(|_| output.response.has_focus());
+    let cursor_line = cursor.map(|cursor| lines.line_of(cursor.cursor));
+
+    // Current line:
+    if let Some(line) = cursor_line.filter(|_| cursor.is_some_and(|c| c.is_empty())) {
+        if let Some(rect) = layout.line_rect(line) {
+            painter.set(
+                background,
+                Shape::rect_filled(
+                    Rect::from_x_y_ranges(viewport.x_range(), rect.y_range()),
+                    0.0,
+                    visuals.code_bg_color.gamma_multiply(0.5),
+                ),
+            );
+        }
+    }
+
+    // Search matches:
+    if state.search.open {
+        let color = visuals.warn_fg_color.gamma_multiply(0.3);
+        let shapes: Vec<Shape> = state
+            .search
+            .matches(code)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|range| layout.rects(range))
+            .filter(|rect| viewport.intersects(*rect))
+            .map(|rect| Shape::rect_filled(rect, 2.0, color))
+            .collect();
+        painter.add(shapes);
+    }
+
+    // Matching brackets:
+    if let Some(cursor) = cursor.filter(|cursor| cursor.is_empty()) {
+        if let Some(pair) = folding::pair_at_cursor(pairs, code, cursor.cursor) {
+            for pos in <[usize; 2]>::from(pair) {
+                for rect in layout.rects(pos..pos + 1) {
+                    painter.rect_stroke(
+                        rect,
+                        2.0,
+                        Stroke::new(1.0, visuals.weak_text_color()),
+                        StrokeKind::Inside,
+                    );
+                }
+            }
+        }
+    }
+
+    // Diagnostics:
+    let hover_pos = output.response.hover_pos();
+    let mut hovered = Vec::new();
+    let mut line_severity: BTreeMap<usize, Severity> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let Some(range) = diagnostic_range(code, &diagnostic.range) else {
+            continue;
+        };
+        let severity = line_severity
+            .entry(lines.line_of(range.start))
+            .or_insert(diagnostic.severity);
+        *severity = diagnostic.severity.max(*severity);
+
+        // The wavy underline is part of the text, see `diagnostic_underlines`.
+        for rect in layout.rects(range) {
+            if hover_pos.is_some_and(|pos| rect.contains(pos)) {
+                hovered.push(diagnostic);
+            }
+        }
+    }
+    if !hovered.is_empty() {
+        hovered.dedup();
+        Tooltip::always_open(
+            ui.ctx().clone(),
+            ui.layer_id(),
+            id.with("diagnostic"),
+            PopupAnchor::Pointer,
+        )
+        .gap(12.0)
+        .show(|ui| {
+            for diagnostic in hovered {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(
+                        diagnostic.severity.color(ui.visuals()),
+                        diagnostic.severity.label(),
+                    );
+                    ui.label(&diagnostic.message);
+                });
+            }
+        });
+    }
+
+    // The gutter is painted on top of the text, so that it stays in place when scrolling horizontally:
+    let gutter_rect = Rect::from_x_y_ranges(
+        viewport.left()..=viewport.left() + gutter.width,
+        viewport
+            .y_range()
+            .intersection(output.response.rect.y_range()),
+    );
+    painter.rect_filled(gutter_rect, 0.0, visuals.text_edit_bg_color());
+    let show_fold_markers = ui.rect_contains_pointer(gutter_rect);
+
+    for (display_line, &line) in display.line_map.iter().enumerate() {
+        let Some(rect) = layout.display_line_rect(display_line) else {
+            continue;
+        };
+        if !viewport.y_range().intersects(rect.y_range()) {
+            continue;
+        }
+
+        if line_numbers {
+            let color = match line_severity.get(&line) {
+                Some(severity) if Severity::Warning <= *severity => severity.color(visuals),
+                _ if cursor_line == Some(line) => visuals.strong_text_color(),
+                _ => visuals.weak_text_color(),
+            };
+            painter.text(
+                pos2(gutter_rect.left() + gutter.numbers_width, rect.center().y),
+                Align2::RIGHT_CENTER,
+                (line + 1).to_string(),
+                font_id.clone(),
+                color,
+            );
+        }
+
+        let Some(region) = regions.iter().find(|region| region.header == line) else {
+            continue;
+        };
+        let is_folded = state.folded.contains(&line);
+        let marker_rect = Rect::from_center_size(
+            pos2(
+                gutter_rect.left() + gutter.numbers_width + 0.5 * gutter.fold_width,
+                rect.center().y,
+            ),
+            vec2(gutter.fold_width, gutter.row_height),
+        );
+        let marker = ui.interact(marker_rect, id.with(("fold_marker", line)), Sense::click());
+        if is_folded || show_fold_markers {
+            let color = if marker.hovered() {
+                visuals.strong_text_color()
+            } else {
+                visuals.weak_text_color()
+            };
+            painter.add(fold_marker(marker_rect, is_folded, color));
+        }
+        let mut clicked = marker.clicked();
+
+        if is_folded {
+            // A placeholder for the hidden lines, after the header:
+            let pill_rect = Rect::from_min_size(
+                pos2(rect.right() + gutter.digit_width, rect.top() + 1.0),
+                vec2(3.0 * gutter.digit_width, rect.height() - 2.0),
+            );
+            let pill = ui.interact(pill_rect, id.with(("fold_pill", line)), Sense::click());
+            let fill = if pill.hovered() {
+                visuals.widgets.hovered.weak_bg_fill
+            } else {
+                visuals.widgets.inactive.weak_bg_fill
+            };
+            painter.rect_filled(pill_rect, 3.0, fill);
+            painter.text(
+                pill_rect.center(),
+                Align2::CENTER_CENTER,
+                "…",
+                font_id.clone(),
+                visuals.weak_text_color(),
+            );
+            clicked |= pill.clicked();
+        }
+
+        if clicked {
+            state.toggle_fold(region, lines);
+            if output.response.has_focus() || output.response.lost_focus() {
+                ui.memory_mut(|mem| mem.request_focus(output.response.id));
+            }
+            ui.ctx().request_repaint();
+        }
+    }
+
+    if std::mem::take(&mut state.scroll_to_cursor) {
+        if let Some(selection) = state.selection {
+            let pos = layout.char_at(selection.cursor);
+            let rect = output
+                .galley
+                .pos_from_cursor(CCursor::new(pos))
+                .translate(output.galley_pos.to_vec2());
+            ui.scroll_to_rect(rect.expand(gutter.row_height), Some(Align::Center));
+        }
+    }
+}
+
+/// The next or previous match after the selection, wrapping around.
+fn find_match(
+    matches: &[Range<usize>],
+    selected: &Range<usize>,
+    forward: bool,
+) -> Option<Range<usize>> {
+    if forward {
+        matches
+            .iter()
+            .find(|m| selected.end <= m.start && *m != selected)
+            .or_else(|| matches.first())
+            .cloned()
+    } else {
+        matches
+            .iter()
+            .rev()
+            .find(|m| m.end <= selected.start && *m != selected)
+            .or_else(|| matches.last())
+            .cloned()
+    }
+}
+
+/// The range to underline, or `None` if it is outside the code.
+fn diagnostic_range(code: &str, range: &Range<usize>) -> Option<Range<usize>> {
+    if code.len() < range.end || range.end < range.start {
+        return None;
+    }
+    if range.is_empty() {
+        let next = code
+            .get(range.start..)?
+            .chars()
+            .next()
+            .map_or(0, char::len_utf8);
+        Some(range.start..range.start + next.max(1)).filter(|range| range.end <= code.len())
+    } else {
+        Some(range.clone())
+    }
+}
+
+/// The wavy underlines of the diagnostics, as byte ranges of the display text.
+fn diagnostic_underlines(
+    code: &str,
+    display: &DisplayText,
+    diagnostics: &[Diagnostic],
+    visuals: &Visuals,
+) -> Vec<(Range<usize>, Stroke)> {
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let range = diagnostic_range(code, &diagnostic.range).filter(|range| {
+                code.is_char_boundary(range.start) && code.is_char_boundary(range.end)
+            })?;
+            let range = display.to_display(range.start)..display.to_display(range.end);
+            let stroke = Stroke::new(1.0, diagnostic.severity.color(visuals));
+            (!range.is_empty()).then_some((range, stroke))
+        })
+        .collect()
+}
+
+/// Give the parts of the `sections` within `range` a wavy underline.
+fn underline_sections(sections: &mut Vec<LayoutSection>, range: &Range<usize>, stroke: Stroke) {
+    let mut underlined = Vec::with_capacity(sections.len() + 2);
+    for section in sections.drain(..) {
+        let whole = section.byte_range.clone();
+        let start = range.start.clamp(whole.start, whole.end);
+        let end = range.end.clamp(whole.start, whole.end);
+        if start == end {
+            underlined.push(section);
+            continue;
+        }
+        for (byte_range, is_underlined) in [
+            (whole.start..start, false),
+            (start..end, true),
+            (end..whole.end, false),
+        ] {
+            if !byte_range.is_empty() {
+                let mut part = LayoutSection {
+                    leading_space: if byte_range.start == whole.start {
+                        section.leading_space
+                    } else {
+                        0.0
+                    },
+                    byte_range,
+                    format: section.format.clone(),
+                };
+                if is_underlined {
+                    part.format.wavy_underline = stroke;
+                }
+                underlined.push(part);
+            }
+        }
+    }
+    *sections = underlined;
+}
+
+/// Lay out the display text, highlighted like the corresponding lines of the code,
+/// with the `underlines` from [`diagnostic_underlines`].
+///
+/// `text` is different from the display text if it was edited this frame.
+/// The changed lines are then shown without highlighting until the next frame.
+fn layout_job(
+    highlighter: &IncrementalHighlighter,
+    display: &DisplayText,
+    underlines: &[(Range<usize>, Stroke)],
+    text: &str,
+) -> LayoutJob {
+    profiling::function_scope!();
+
+    let old_lines: Vec<&str> = display.text.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let mut job = LayoutJob {
+        text: text.to_owned(),
+        ..Default::default()
+    };
+
+    let mut offset = 0;
+    let mut line_sections = Vec::new();
+    for (i, line) in new_lines.iter().enumerate() {
+        // The line and its start in the display text:
+        let old_line = if i < prefix {
+            Some((i, offset))
+        } else if new_lines.len() - suffix <= i {
+            Some((
+                i + old_lines.len() - new_lines.len(),
+                offset + display.text.len() - text.len(),
+            ))
+        } else {
+            None
+        };
+        let sections = old_line
+            .and_then(|(old_line, _)| display.line_map.get(old_line))
+            .map_or(&[][..], |&line| highlighter.line_sections(line));
+
+        if sections.is_empty() {
+            line_sections.push(LayoutSection {
+                leading_space: 0.0,
+                byte_range: offset..offset + line.len(),
+                format: highlighter.fallback_format(),
+            });
+        } else {
+            line_sections.extend(sections.iter().map(|section| LayoutSection {
+                byte_range: offset + section.byte_range.start..offset + section.byte_range.end,
+                ..section.clone()
+            }));
+        }
+
+        if let Some((_, old_start)) = old_line {
+            let old_end = old_start + line.len();
+            for (range, stroke) in underlines {
+                if range.start < old_end && old_start < range.end {
+                    let start = range.start.max(old_start) - old_start + offset;
+                    let end = range.end.min(old_end) - old_start + offset;
+                    underline_sections(&mut line_sections, &(start..end), *stroke);
+                }
+            }
+        }
+
+        job.sections.append(&mut line_sections);
+        offset += line.len();
+    }
+
+    job
+}
+
+/// The [`LayoutJob`] of the [`DisplayText`],
+/// until the highlighting, the display text or the diagnostic underlines change.
+///
+/// Building it touches every line, so we don't want to do that every frame.
+#[derive(Clone)]
+struct CachedLayoutJob {
+    highlighter_generation: u64,
+    display: Arc<DisplayText>,
+    underlines: Vec<(Range<usize>, Stroke)>,
+    job: Arc<LayoutJob>,
+}
+
+impl CachedLayoutJob {
+    fn get(
+        cache: &mut Option<Self>,
+        highlighter: &IncrementalHighlighter,
+        display: &Arc<DisplayText>,
+        underlines: &[(Range<usize>, Stroke)],
+    ) -> Arc<LayoutJob> {
+        let is_up_to_date = cache.as_ref().is_some_and(|cached| {
+            cached.highlighter_generation == highlighter.generation()
+                && Arc::ptr_eq(&cached.display, display)
+                && cached.underlines == underlines
+        });
+        if !is_up_to_date {
+            *cache = None;
+        }
+        let cached = cache.get_or_insert_with(|| Self {
+            highlighter_generation: highlighter.generation(),
+            display: display.clone(),
+            underlines: underlines.to_vec(),
+            job: Arc::new(layout_job(highlighter, display, underlines, &display.text)),
+        });
+        cached.job.clone()
+    }
+}
+
+/// Positions of the laid out display text.
+struct TextLayout<'a> {
+    galley: &'a Galley,
+    galley_pos: Pos2,
+    display: &'a DisplayText,
+    chars: CharIndex,
+
+    /// The char index at the start of each row.
+    row_starts: Vec<usize>,
+
+    /// The first row of each line of the display text.
+    line_rows: Vec<usize>,
+}
+
+impl<'a> TextLayout<'a> {
+    fn new(galley: &'a Galley, galley_pos: Pos2, display: &'a DisplayText) -> Self {
+        let mut row_starts = Vec::with_capacity(galley.rows.len());
+        let mut line_rows = vec![0];
+        let mut index = 0;
+        for (i, row) in galley.rows.iter().enumerate() {
+            row_starts.push(index);
+            index += row.char_count_including_newline();
+            if row.ends_with_newline {
+                line_rows.push(i + 1);
+            }
+        }
+
+        Self {
+            galley,
+            galley_pos,
+            display,
+            chars: CharIndex::new(galley.text()),
+            row_starts,
+            line_rows,
+        }
+    }
+
+    fn char_at(&self, pos: usize) -> usize {
+        self.chars.to_char(self.display.to_display(pos))
+    }
+
+    /// Screen rectangles covering a byte range of the code, one per row.
+    ///
+    /// Parts in folded lines are skipped.
+    fn rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let (start, end) = (self.char_at(range.start), self.char_at(range.end));
+        if end <= start {
+            return Vec::new();
+        }
+
+        let first_row = self
+            .row_starts
+            .partition_point(|&s| s <= start)
+            .saturating_sub(1);
+        let mut rects = Vec::new();
+        for (row, &row_start) in self
+            .galley
+            .rows
+            .iter()
+            .zip(&self.row_starts)
+            .skip(first_row)
+        {
+            if end <= row_start {
+                break;
+            }
+            let row_end = row_start + row.char_count_excluding_newline();
+            let (from, to) = (start.max(row_start), end.min(row_end));
+            if to <= from {
+                continue;
+            }
+            let x = self.galley_pos.x + row.pos.x;
+            let y = self.galley_pos.y;
+            rects.push(Rect::from_x_y_ranges(
+                x + row.x_offset(from - row_start)..=x + row.x_offset(to - row_start),
+                y + row.min_y()..=y + row.max_y(),
+            ));
+        }
+        rects
+    }
+
+    /// The rectangle of the text on a line of the display text.
+    fn display_line_rect(&self, display_line: usize) -> Option<Rect> {
+        let first = *self.line_rows.get(display_line)?;
+        let last = self
+            .line_rows
+            .get(display_line + 1)
+            .map_or(self.galley.rows.len(), |next| *next)
+            .checked_sub(1)?;
+        let rect = self
+            .galley
+            .rows
+            .get(first)?
+            .rect()
+            .union(self.galley.rows.get(last)?.rect());
+        Some(rect.translate(self.galley_pos.to_vec2()))
+    }
+
+    /// The rectangle of the text on a line of the code, if it is visible.
+    fn line_rect(&self, line: usize) -> Option<Rect> {
+        let display_line = self.display.line_map.binary_search(&line).ok()?;
+        self.display_line_rect(display_line)
+    }
+}
+
+/// A triangle pointing right when folded, and down when not.
+fn fold_marker(rect: Rect, is_folded: bool, color: Color32) -> Shape {
+    let rect = Rect::from_center_size(rect.center(), vec2(rect.height(), rect.height()) * 0.35);
+    let points = if is_folded {
+        vec![rect.left_top(), rect.right_center(), rect.left_bottom()]
+    } else {
+        vec![rect.left_top(), rect.right_top(), rect.center_bottom()]
+    };
+    Shape::convex_polygon(points, color, Stroke::NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_next_and_previous_match() {
+        let matches = [2..4, 6..8, 10..12];
+        assert_eq!(find_match(&matches, &(0..0), true), Some(2..4));
+        assert_eq!(find_match(&matches, &(2..4), true), Some(6..8));
+        assert_eq!(find_match(&matches, &(10..12), true), Some(2..4));
+        assert_eq!(find_match(&matches, &(6..8), false), Some(2..4));
+        assert_eq!(find_match(&matches, &(0..0), false), Some(10..12));
+    }
+
+    #[test]
+    fn diagnostics_are_wavy_underlined() {
+        let ctx = egui::Context::default();
+        let code = "let x = 1;\nlet y = 2;\n";
+        let mut highlighter =
+            IncrementalHighlighter::new(&ctx, &ctx.style(), &CodeTheme::dark(12.0), "rs");
+        highlighter.update(code);
+        let display = DisplayText::new(code, &LineIndex::new(code), &[], &BTreeSet::new());
+        let stroke = Stroke::new(1.0, Color32::RED);
+        let y = code.find('y').unwrap();
+
+        let underlined = |text: &str| {
+            let job = layout_job(&highlighter, &display, &[(y..y + 1, stroke)], text);
+            job.sections
+                .iter()
+                .filter(|section| section.format.wavy_underline == stroke)
+                .map(|section| (section.byte_range.start, section.byte_range.end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(underlined(code), [(y, y + 1)]);
+
+        // Unchanged lines keep their underlines while the code is edited:
+        let edited = format!("// new\n{code}");
+        assert_eq!(underlined(&edited), [(y + 7, y + 8)]);
+    }
+
+    #[test]
+    fn layout_job_is_cached() {
+        let ctx = egui::Context::default();
+        let mut code = "let x = 1;\n".to_owned();
+        let mut highlighter =
+            IncrementalHighlighter::new(&ctx, &ctx.style(), &CodeTheme::dark(12.0), "rs");
+        highlighter.update(&code);
+        let display = Arc::new(DisplayText::new(
+            &code,
+            &LineIndex::new(&code),
+            &[],
+            &BTreeSet::new(),
+        ));
+
+        let mut cache = None;
+        let first = CachedLayoutJob::get(&mut cache, &highlighter, &display, &[]);
+        let second = CachedLayoutJob::get(&mut cache, &highlighter, &display, &[]);
+        assert!(Arc::ptr_eq(&first, &second), "Nothing changed");
+
+        let stroke = Stroke::new(1.0, Color32::RED);
+        let underlined =
+            CachedLayoutJob::get(&mut cache, &highlighter, &display, &[(0..3, stroke)]);
+        assert!(!Arc::ptr_eq(&second, &underlined), "The underlines changed");
+
+        code.push_str("let y = 2;\n");
+        highlighter.update(&code);
+        let display = Arc::new(DisplayText::new(
+            &code,
+            &LineIndex::new(&code),
+            &[],
+            &BTreeSet::new(),
+        ));
+        let edited = CachedLayoutJob::get(&mut cache, &highlighter, &display, &[(0..3, stroke)]);
+        assert_eq!(edited.text, code, "The code changed");
+    }
+
+    #[test]
+    fn edit_folded_code() {
+        let ctx = egui::Context::default();
+        let mut code = "fn f() {\n    a;\n    b;\n}\n".to_owned();
+        let closing = code.find('}').unwrap();
+
+        let run = |code: &mut String, events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                events,
+                ..Default::default()
+            };
+            let mut shown = String::new();
+            let _ = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let id = ui.make_persistent_id(Id::new("editor"));
+                    if ui.data(|d| d.get_temp::<State>(id)).is_none() {
+                        let state = State {
+                            folded: BTreeSet::from([0]),
+                            selection: Some(Selection::one(closing)),
+                            ..Default::default()
+                        };
+                        ui.data_mut(|d| d.insert_temp(id, state));
+                        ui.memory_mut(|mem| mem.request_focus(id.with("text_edit")));
+                    }
+                    let output = CodeEditor::new(code, "rs").id_salt("editor").show(ui);
+                    shown = output.text_edit.galley.text().to_owned();
+                });
+            });
+            shown
+        };
+
+        run(&mut code, vec![]);
+        assert_eq!(run(&mut code, vec![]), "fn f() {\n}\n");
+
+        // The closing line comes right after the hidden lines, which are kept:
+        run(&mut code, vec![egui::Event::Text("x".to_owned())]);
+        assert_eq!(code, "fn f() {\n    a;\n    b;\nx}\n");
+        assert_eq!(run(&mut code, vec![]), "fn f() {\nx}\n");
+    }
+}
@@ -0,0 +1,356 @@
+//! The search & replace bar and the go-to-line bar.
+
+use std::ops::Range;
+
+use egui::{Key, RichText, TextEdit, Ui};
+use regex::{NoExpand, Regex, RegexBuilder};
+
+#[derive(Clone, Default)]
+pub(crate) struct SearchState {
+    pub open: bool,
+    pub show_replace: bool,
+    pub query: String,
+    pub replacement: String,
+    pub use_regex: bool,
+    pub match_case: bool,
+
+    /// Focus the query field on the next frame.
+    pub request_focus: bool,
+
+    /// The last compiled query, with the settings it was compiled with.
+    compiled: Option<(String, bool, bool, Result<Regex, String>)>,
+
+    /// The matches of the compiled query, with the hash of the code they were found in.
+    matches: Option<(u64, Result<Vec<Range<usize>>, String>)>,
+}
+
+pub(crate) enum SearchAction {
+    Next,
+    Previous,
+    Replace,
+    ReplaceAll,
+    Close,
+}
+
+impl SearchState {
+    fn regex(&mut self) -> Option<&Result<Regex, String>> {
+        if self.query.is_empty() {
+            return None;
+        }
+
+        let is_cached = self
+            .compiled
+            .as_ref()
+            .is_some_and(|(query, regex, case, _)| {
+                *query == self.query && *regex == self.use_regex && *case == self.match_case
+            });
+        if !is_cached {
+            let pattern = if self.use_regex {
+                self.query.clone()
+            } else {
+                regex::escape(&self.query)
+            };
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(!self.match_case)
+                .multi_line(true)
+                .build()
+                .map_err(|err| err.to_string());
+            self.compiled = Some((self.query.clone(), self.use_regex, self.match_case, regex));
+            self.matches = None;
+        }
+
+        self.compiled.as_ref().map(|(_, _, _, regex)| regex)
+    }
+
+    /// All non-empty matches in the code, as byte ranges.
+    ///
+    /// The matches are cached until the code or the query changes.
+    pub fn matches(&mut self, code: &str) -> Result<Vec<Range<usize>>, String> {
+        // Compiling the query forgets the matches of the previous one:
+        if self.regex().is_none() {
+            return Ok(Vec::new());
+        }
+
+        let code_hash = egui::util::hash(code);
+        if let Some((hash, matches)) = &self.matches {
+            if *hash == code_hash {
+                return matches.clone();
+            }
+        }
+
+        profiling::function_scope!();
+        let matches = match self.regex() {
+            Some(Ok(regex)) => Ok(regex
+                .find_iter(code)
+                .map(|m| m.range())
+                .filter(|range| !range.is_empty())
+                .collect()),
+            Some(Err(err)) => Err(err.clone()),
+            None => Ok(Vec::new()),
+        };
+        self.matches = Some((code_hash, matches.clone()));
+        matches
+    }
+
+    /// Replace the match at `range`. Returns the byte range of the replacement.
+    pub fn replace(&mut self, code: &mut String, range: Range<usize>) -> Range<usize> {
+        let use_regex = self.use_regex;
+        let replacement = self.replacement.clone();
+        let mut new_text = replacement.clone();
+        if use_regex {
+            if let Some(Ok(regex)) = self.regex() {
+                if let Some(captures) = regex.captures_at(code, range.start) {
+                    new_text.clear();
+                    captures.expand(&replacement, &mut new_text);
+                }
+            }
+        }
+        code.replace_range(range.clone(), &new_text);
+        range.start..range.start + new_text.len()
+    }
+
+    /// Replace all matches. Returns the number of replaced matches.
+    pub fn replace_all(&mut self, code: &mut String) -> usize {
+        let use_regex = self.use_regex;
+        let replacement = self.replacement.clone();
+        let Some(Ok(regex)) = self.regex() else {
+            return 0;
+        };
+        let count = regex.find_iter(code).filter(|m| !m.is_empty()).count();
+        let replaced = if use_regex {
+            regex.replace_all(code, replacement.as_str())
+        } else {
+            regex.replace_all(code, NoExpand(&replacement))
+        };
+        *code = replaced.into_owned();
+        count
+    }
+}
+
+/// Returns the action the user requested, if any.
+pub(crate) fn search_bar(
+    ui: &mut Ui,
+    state: &mut SearchState,
+    id: egui::Id,
+    matches: &Result<Vec<Range<usize>>, String>,
+    current: Option<usize>,
+) -> Option<SearchAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            TextEdit::singleline(&mut state.query)
+                .id(id.with("query"))
+                .hint_text("Find")
+                .desired_width(200.0),
+        );
+        if std::mem::take(&mut state.request_focus) {
+            response.request_focus();
+        }
+        if response.lost_focus() {
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                action = Some(if ui.input(|i| i.modifiers.shift) {
+                    SearchAction::Previous
+                } else {
+                    SearchAction::Next
+                });
+                response.request_focus();
+            } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                action = Some(SearchAction::Close);
+            }
+        }
+
+        ui.toggle_value(&mut state.match_case, "Aa")
+            .on_hover_text("Match case");
+        ui.toggle_value(&mut state.use_regex, ".*")
+            .on_hover_text("Use regular expression");
+
+        match matches {
+            Ok(matches) if matches.is_empty() => {
+                if !state.query.is_empty() {
+                    ui.weak("No results");
+                }
+            }
+            Ok(matches) => {
+                if let Some(current) = current {
+                    ui.label(format!("{} of {}", current + 1, matches.len()));
+                } else {
+                    ui.label(format!("{} matches", matches.len()));
+                }
+            }
+            Err(err) => {
+                ui.label(RichText::new("Invalid regex").color(ui.visuals().error_fg_color))
+                    .on_hover_text(err);
+            }
+        }
+
+        if ui
+            .small_button("⬆")
+            .on_hover_text("Previous match")
+            .clicked()
+        {
+            action = Some(SearchAction::Previous);
+        }
+        if ui.small_button("⬇").on_hover_text("Next match").clicked() {
+            action = Some(SearchAction::Next);
+        }
+        if ui
+            .small_button(if state.show_replace { "⏶" } else { "⏷" })
+            .on_hover_text("Toggle replace")
+            .clicked()
+        {
+            state.show_replace = !state.show_replace;
+        }
+        if ui.small_button("Close").clicked() {
+            action = Some(SearchAction::Close);
+        }
+    });
+
+    if state.show_replace {
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut state.replacement)
+                    .id(id.with("replacement"))
+                    .hint_text("Replace")
+                    .desired_width(200.0),
+            );
+            if response.lost_focus() {
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    action = Some(SearchAction::Replace);
+                    response.request_focus();
+                } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                    action = Some(SearchAction::Close);
+                }
+            }
+            if ui.button("Replace").clicked() {
+                action = Some(SearchAction::Replace);
+            }
+            if ui.button("Replace all").clicked() {
+                action = Some(SearchAction::ReplaceAll);
+            }
+        });
+    }
+
+    action
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Clone, Default)]
+pub(crate) struct GoToLineState {
+    pub open: bool,
+    pub input: String,
+
+    /// Focus the input field on the next frame.
+    pub request_focus: bool,
+}
+
+pub(crate) enum GoToLineAction {
+    /// Zero-based line and column.
+    Go {
+        line: usize,
+        column: usize,
+    },
+    Close,
+}
+
+/// Parse `line` or `line:column`, both one-based.
+fn parse_line_column(input: &str) -> Option<(usize, usize)> {
+    let (line, column) = match input.trim().split_once(':') {
+        Some((line, column)) => (line, Some(column)),
+        None => (input.trim(), None),
+    };
+    let line = line.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    let column = match column {
+        Some(column) => column.trim().parse::<usize>().ok()?.saturating_sub(1),
+        None => 0,
+    };
+    Some((line, column))
+}
+
+/// Returns the action the user requested, if any.
+pub(crate) fn go_to_line_bar(
+    ui: &mut Ui,
+    state: &mut GoToLineState,
+    id: egui::Id,
+    num_lines: usize,
+) -> Option<GoToLineAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        ui.label("Go to line:");
+        let response = ui.add(
+            TextEdit::singleline(&mut state.input)
+                .id(id.with("go_to_line"))
+                .hint_text(format!("1 – {num_lines}"))
+                .desired_width(80.0),
+        );
+        if std::mem::take(&mut state.request_focus) {
+            response.request_focus();
+        }
+
+        let parsed = parse_line_column(&state.input);
+        if response.lost_focus() {
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                action = parsed
+                    .map(|(line, column)| GoToLineAction::Go { line, column })
+                    .or(Some(GoToLineAction::Close));
+            } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                action = Some(GoToLineAction::Close);
+            }
+        }
+        if !state.input.is_empty() && parsed.is_none() {
+            ui.label(RichText::new("Expected a line number").color(ui.visuals().error_fg_color));
+        }
+
+        if ui.small_button("Close").clicked() {
+            action = Some(GoToLineAction::Close);
+        }
+    });
+
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_and_replace() {
+        let mut search = SearchState {
+            query: "(\\w+)_id".to_owned(),
+            replacement: "${1}Id".to_owned(),
+            use_regex: true,
+            ..Default::default()
+        };
+        let mut code = "user_id = group_ID; x_id".to_owned();
+        assert_eq!(search.matches(&code), Ok(vec![0..7, 10..18, 20..24]));
+
+        search.match_case = true;
+        assert_eq!(search.matches(&code), Ok(vec![0..7, 20..24]));
+
+        assert_eq!(search.replace(&mut code, 0..7), 0..6);
+        assert_eq!(code, "userId = group_ID; x_id");
+
+        search.match_case = false;
+        search.use_regex = false;
+        search.query = "_ID".to_owned();
+        search.replacement = "$x".to_owned();
+        assert_eq!(search.matches(&code), Ok(vec![14..17, 20..23]));
+        assert_eq!(search.replace_all(&mut code), 2);
+        assert_eq!(code, "userId = group$x; x$x");
+        assert_eq!(search.matches(&code), Ok(vec![]));
+
+        search.use_regex = true;
+        search.query = "(".to_owned();
+        assert!(search.matches(&code).is_err());
+    }
+
+    #[test]
+    fn line_column() {
+        assert_eq!(parse_line_column("12"), Some((11, 0)));
+        assert_eq!(parse_line_column(" 3:7 "), Some((2, 6)));
+        assert_eq!(parse_line_column("0"), None);
+        assert_eq!(parse_line_column("x"), None);
+    }
+}
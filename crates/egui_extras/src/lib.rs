@@ -9,9 +9,16 @@
 #![allow(clippy::float_cmp)]
 #![allow(clippy::manual_range_contains)]
 
+#[cfg(feature = "code_editor")]
+pub mod code_editor;
+
 #[cfg(feature = "chrono")]
 mod datepicker;
 
+#[cfg(feature = "markdown")]
+pub mod markdown;
+
+pub mod spell_checker;
 pub mod syntax_highlighting;
 
 #[doc(hidden)]
@@ -22,11 +29,18 @@ mod sizing;
 mod strip;
 mod table;
 
+#[cfg(feature = "code_editor")]
+pub use crate::code_editor::CodeEditor;
+
 #[cfg(feature = "chrono")]
 pub use crate::datepicker::DatePickerButton;
 
+#[cfg(feature = "markdown")]
+pub use crate::markdown::CommonMarkViewer;
+
 pub(crate) use crate::layout::StripLayout;
 pub use crate::sizing::Size;
+pub use crate::spell_checker::DictionarySpellChecker;
 pub use crate::strip::*;
 pub use crate::table::*;
 
@@ -0,0 +1,983 @@
+//! Render [CommonMark](https://commonmark.org/) markdown, including the GitHub extensions
+//! for tables, task lists, strikethrough and footnotes.
+//!
+//! Requires the `markdown` feature.
+
+use std::sync::Arc;
+
+use egui::{
+    Align, Align2, Checkbox, FontId, FontSelection, Frame, Hyperlink, Id, Label, Layout, OpenUrl,
+    Response, RichText, Sense, Stroke, TextStyle, Ui, text::LayoutJob, vec2,
+};
+use pulldown_cmark::{Alignment, BlockQuoteKind, CodeBlockKind, Event, HeadingLevel, Tag, TagEnd};
+
+use crate::syntax_highlighting::{self, CodeTheme};
+
+/// Shows markdown text.
+///
+/// Supports [CommonMark](https://spec.commonmark.org/) with the
+/// [GitHub extensions](https://github.github.com/gfm/) for tables, task lists,
+/// strikethrough, footnotes and alerts (`> [!NOTE]`).
+///
+/// * Code blocks are highlighted with [`syntax_highlighting::highlight`].
+/// * Images are loaded with [`egui::Image`], so you need to [install image loaders](crate::install_image_loaders).
+/// * Clicking a link will emit [`egui::OutputCommand::OpenUrl`].
+/// * All text is selectable, and the selection can span several paragraphs.
+///
+/// Parsing is cached, so you can call this every frame without performance penalty.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// egui_extras::CommonMarkViewer::new().show(ui, "# Hello\n\nThis is **markdown**!");
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct CommonMarkViewer {
+    id_salt: Id,
+    max_image_width: Option<f32>,
+    code_theme: Option<CodeTheme>,
+    selectable: bool,
+}
+
+impl Default for CommonMarkViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommonMarkViewer {
+    pub fn new() -> Self {
+        Self {
+            id_salt: Id::new("common_mark_viewer"),
+            max_image_width: None,
+            code_theme: None,
+            selectable: true,
+        }
+    }
+
+    /// Must be set if multiple viewers are in the same [`Ui`].
+    #[inline]
+    pub fn id_salt(mut self, id_salt: impl std::hash::Hash) -> Self {
+        self.id_salt = Id::new(id_salt);
+        self
+    }
+
+    /// Images are never wider than this. (Default: the available width)
+    #[inline]
+    pub fn max_image_width(mut self, max_image_width: f32) -> Self {
+        self.max_image_width = Some(max_image_width);
+        self
+    }
+
+    /// The theme used for code blocks.
+    ///
+    /// Default: [`CodeTheme::from_memory`].
+    #[inline]
+    pub fn code_theme(mut self, code_theme: CodeTheme) -> Self {
+        self.code_theme = Some(code_theme);
+        self
+    }
+
+    /// Can the user select the text? (Default: true)
+    #[inline]
+    pub fn selectable(mut self, selectable: bool) -> Self {
+        self.selectable = selectable;
+        self
+    }
+
+    /// Show the given markdown.
+    pub fn show(self, ui: &mut Ui, markdown: &str) -> Response {
+        let document = parse_cached(ui.ctx(), markdown);
+
+        ui.vertical(|ui| {
+            ui.style_mut().interaction.selectable_labels = self.selectable;
+
+            let theme = self
+                .code_theme
+                .clone()
+                .unwrap_or_else(|| CodeTheme::from_memory(ui.ctx(), ui.style()));
+            let mut renderer = Renderer {
+                viewer: &self,
+                document: &document,
+                theme,
+                id: ui.make_persistent_id(self.id_salt),
+                next_table: 0,
+                list_depth: 0,
+            };
+
+            renderer.blocks(ui, &document.blocks);
+            renderer.footnotes(ui);
+        })
+        .response
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Default, PartialEq)]
+struct Document {
+    blocks: Vec<Block>,
+
+    /// In order of first reference.
+    footnotes: Vec<Vec<Block>>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Paragraph(Vec<Inline>),
+    Heading {
+        level: HeadingLevel,
+        inlines: Vec<Inline>,
+    },
+    Quote {
+        kind: Option<BlockQuoteKind>,
+        blocks: Vec<Block>,
+    },
+    Code {
+        language: String,
+        code: String,
+    },
+    List {
+        /// The number of the first item, for numbered lists.
+        start: Option<u64>,
+        items: Vec<ListItem>,
+    },
+    Table {
+        alignments: Vec<Align>,
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+    Html(String),
+    Rule,
+}
+
+#[derive(Debug, PartialEq)]
+struct ListItem {
+    /// Set for task list items.
+    checked: Option<bool>,
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Inline {
+    Text(String, InlineStyle),
+    Image {
+        url: String,
+        alt: String,
+        title: String,
+        link: Option<Arc<str>>,
+    },
+    /// The 1-based footnote number.
+    FootnoteReference(usize),
+    Checkbox(bool),
+    LineBreak,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct InlineStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+    superscript: bool,
+    subscript: bool,
+    link: Option<Arc<str>>,
+}
+
+fn parse_cached(ctx: &egui::Context, markdown: &str) -> Arc<Document> {
+    #[derive(Default)]
+    struct Parser;
+
+    impl egui::cache::ComputerMut<&str, Arc<Document>> for Parser {
+        fn compute(&mut self, markdown: &str) -> Arc<Document> {
+            Arc::new(parse(markdown))
+        }
+    }
+
+    type ParseCache = egui::cache::FrameCache<Arc<Document>, Parser>;
+
+    ctx.memory_mut(|mem| mem.caches.cache::<ParseCache>().get(markdown))
+}
+
+fn parse(markdown: &str) -> Document {
+    profiling::function_scope!();
+
+    let options = pulldown_cmark::Options::ENABLE_TABLES
+        | pulldown_cmark::Options::ENABLE_FOOTNOTES
+        | pulldown_cmark::Options::ENABLE_STRIKETHROUGH
+        | pulldown_cmark::Options::ENABLE_TASKLISTS
+        | pulldown_cmark::Options::ENABLE_GFM;
+
+    let mut builder = DocumentBuilder {
+        events: pulldown_cmark::Parser::new_ext(markdown, options),
+        strong: 0,
+        emphasis: 0,
+        strikethrough: 0,
+        superscript: 0,
+        subscript: 0,
+        link: None,
+        image: None,
+        footnote_labels: Vec::new(),
+        footnote_definitions: Default::default(),
+    };
+
+    let blocks = builder.blocks();
+    let mut definitions = builder.footnote_definitions;
+    let footnotes = builder
+        .footnote_labels
+        .iter()
+        .map(|label| definitions.remove(label).unwrap_or_default())
+        .collect();
+
+    Document { blocks, footnotes }
+}
+
+/// Turns the flat stream of [`Event`]s into a tree of [`Block`]s.
+struct DocumentBuilder<'a> {
+    events: pulldown_cmark::Parser<'a>,
+
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    superscript: usize,
+    subscript: usize,
+    link: Option<Arc<str>>,
+
+    /// The image whose alt-text we are currently reading.
+    image: Option<Inline>,
+
+    footnote_labels: Vec<String>,
+    footnote_definitions: ahash::HashMap<String, Vec<Block>>,
+}
+
+impl DocumentBuilder<'_> {
+    /// Read blocks until the end of the enclosing block.
+    fn blocks(&mut self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut inlines = Vec::new();
+
+        while let Some(event) = self.events.next() {
+            if self.inline(&event, &mut inlines) {
+                // Tight list items contain inlines without a wrapping paragraph.
+                continue;
+            }
+
+            if !inlines.is_empty() {
+                blocks.push(Block::Paragraph(std::mem::take(&mut inlines)));
+            }
+
+            match event {
+                Event::Start(tag) => blocks.extend(self.block(tag)),
+                Event::End(_) => break,
+                Event::Rule => blocks.push(Block::Rule),
+                Event::Html(html) => blocks.push(Block::Html(html.to_string())),
+                _ => {}
+            }
+        }
+
+        if !inlines.is_empty() {
+            blocks.push(Block::Paragraph(inlines));
+        }
+
+        blocks
+    }
+
+    /// Read the rest of the block started by `tag`.
+    fn block(&mut self, tag: Tag<'_>) -> Option<Block> {
+        match tag {
+            Tag::Paragraph => Some(Block::Paragraph(self.inlines())),
+            Tag::Heading { level, .. } => Some(Block::Heading {
+                level,
+                inlines: self.inlines(),
+            }),
+            Tag::BlockQuote(kind) => Some(Block::Quote {
+                kind,
+                blocks: self.blocks(),
+            }),
+            Tag::CodeBlock(kind) => {
+                let language = match kind {
+                    CodeBlockKind::Indented => String::new(),
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .unwrap_or_default()
+                        .to_owned(),
+                };
+                let mut code = self.raw_text();
+                if code.ends_with('\n') {
+                    code.pop();
+                }
+                Some(Block::Code { language, code })
+            }
+            Tag::HtmlBlock => Some(Block::Html(self.raw_text())),
+            Tag::List(start) => {
+                let mut items = Vec::new();
+                while self.events.next() == Some(Event::Start(Tag::Item)) {
+                    items.push(self.list_item());
+                }
+                Some(Block::List { start, items })
+            }
+            Tag::FootnoteDefinition(label) => {
+                let blocks = self.blocks();
+                self.footnote_definitions.insert(label.to_string(), blocks);
+                None
+            }
+            Tag::Table(alignments) => {
+                let alignments = alignments
+                    .into_iter()
+                    .map(|alignment| match alignment {
+                        Alignment::None | Alignment::Left => Align::Min,
+                        Alignment::Center => Align::Center,
+                        Alignment::Right => Align::Max,
+                    })
+                    .collect();
+                let mut header = Vec::new();
+                let mut rows = Vec::new();
+                loop {
+                    match self.events.next() {
+                        Some(Event::Start(Tag::TableHead)) => header = self.table_row(),
+                        Some(Event::Start(Tag::TableRow)) => rows.push(self.table_row()),
+                        _ => break,
+                    }
+                }
+                Some(Block::Table {
+                    alignments,
+                    header,
+                    rows,
+                })
+            }
+            _ => {
+                // Not enabled by our parser options - skip it:
+                self.blocks();
+                None
+            }
+        }
+    }
+
+    fn list_item(&mut self) -> ListItem {
+        let mut blocks = self.blocks();
+        let mut checked = None;
+        if let Some(Block::Paragraph(inlines)) = blocks.first_mut() {
+            if let Some(&Inline::Checkbox(is_checked)) = inlines.first() {
+                checked = Some(is_checked);
+                inlines.remove(0);
+            }
+        }
+        ListItem { checked, blocks }
+    }
+
+    fn table_row(&mut self) -> Vec<Vec<Inline>> {
+        let mut cells = Vec::new();
+        while self.events.next() == Some(Event::Start(Tag::TableCell)) {
+            cells.push(self.inlines());
+        }
+        cells
+    }
+
+    /// Read inlines until the end of the enclosing block.
+    fn inlines(&mut self) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        while let Some(event) = self.events.next() {
+            if !self.inline(&event, &mut inlines) && matches!(event, Event::End(_)) {
+                break;
+            }
+        }
+        inlines
+    }
+
+    /// Read the contents of a code or html block.
+    fn raw_text(&mut self) -> String {
+        let mut text = String::new();
+        for event in self.events.by_ref() {
+            match event {
+                Event::Text(s) | Event::Html(s) => text.push_str(&s),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        text
+    }
+
+    /// Returns `false` if this is not an inline event.
+    fn inline(&mut self, event: &Event<'_>, out: &mut Vec<Inline>) -> bool {
+        match event {
+            Event::Text(text) => self.text(text, false, out),
+            Event::Code(text) | Event::InlineMath(text) => self.text(text, true, out),
+            Event::InlineHtml(html) => {
+                if matches!(html.trim(), "<br>" | "<br/>" | "<br />") {
+                    out.push(Inline::LineBreak);
+                } else {
+                    self.text(html, true, out);
+                }
+            }
+            Event::SoftBreak => self.text(" ", false, out),
+            Event::HardBreak => {
+                if self.image.is_some() {
+                    self.text(" ", false, out);
+                } else {
+                    out.push(Inline::LineBreak);
+                }
+            }
+            Event::FootnoteReference(label) => {
+                let number = self.footnote_number(label);
+                out.push(Inline::FootnoteReference(number));
+            }
+            Event::TaskListMarker(checked) => out.push(Inline::Checkbox(*checked)),
+
+            Event::Start(Tag::Strong) => self.strong += 1,
+            Event::Start(Tag::Emphasis) => self.emphasis += 1,
+            Event::Start(Tag::Strikethrough) => self.strikethrough += 1,
+            Event::Start(Tag::Superscript) => self.superscript += 1,
+            Event::Start(Tag::Subscript) => self.subscript += 1,
+            Event::Start(Tag::Link { dest_url, .. }) => self.link = Some(dest_url.as_ref().into()),
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                self.image = Some(Inline::Image {
+                    url: dest_url.to_string(),
+                    alt: String::new(),
+                    title: title.to_string(),
+                    link: self.link.clone(),
+                });
+            }
+
+            Event::End(TagEnd::Strong) => self.strong = self.strong.saturating_sub(1),
+            Event::End(TagEnd::Emphasis) => self.emphasis = self.emphasis.saturating_sub(1),
+            Event::End(TagEnd::Strikethrough) => {
+                self.strikethrough = self.strikethrough.saturating_sub(1);
+            }
+            Event::End(TagEnd::Superscript) => {
+                self.superscript = self.superscript.saturating_sub(1);
+            }
+            Event::End(TagEnd::Subscript) => self.subscript = self.subscript.saturating_sub(1),
+            Event::End(TagEnd::Link) => self.link = None,
+            Event::End(TagEnd::Image) => out.extend(self.image.take()),
+
+            _ => return false,
+        }
+        true
+    }
+
+    fn text(&mut self, text: &str, code: bool, out: &mut Vec<Inline>) {
+        if let Some(Inline::Image { alt, .. }) = &mut self.image {
+            alt.push_str(text);
+            return;
+        }
+
+        let text = if matches!(out.last(), None | Some(Inline::LineBreak)) {
+            text.trim_start()
+        } else {
+            text
+        };
+        if text.is_empty() {
+            return;
+        }
+
+        let style = InlineStyle {
+            strong: self.strong > 0,
+            emphasis: self.emphasis > 0,
+            strikethrough: self.strikethrough > 0,
+            code,
+            superscript: self.superscript > 0,
+            subscript: self.subscript > 0,
+            link: self.link.clone(),
+        };
+
+        // The parser splits text at every special character, so merge it back:
+        if let Some(Inline::Text(previous, previous_style)) = out.last_mut() {
+            if *previous_style == style {
+                previous.push_str(text);
+                return;
+            }
+        }
+        out.push(Inline::Text(text.to_owned(), style));
+    }
+
+    fn footnote_number(&mut self, label: &str) -> usize {
+        let index = if let Some(index) = self.footnote_labels.iter().position(|l| l == label) {
+            index
+        } else {
+            self.footnote_labels.push(label.to_owned());
+            self.footnote_labels.len() - 1
+        };
+        index + 1
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// Text properties shared by a whole paragraph.
+#[derive(Clone, Copy, Default)]
+struct ParagraphStyle {
+    /// Overrides the body font size.
+    size: Option<f32>,
+    strong: bool,
+}
+
+struct Renderer<'a> {
+    viewer: &'a CommonMarkViewer,
+    document: &'a Document,
+    theme: CodeTheme,
+    id: Id,
+    next_table: usize,
+    list_depth: usize,
+}
+
+impl Renderer<'_> {
+    fn blocks(&mut self, ui: &mut Ui, blocks: &[Block]) {
+        let block_spacing = 0.5 * ui.text_style_height(&TextStyle::Body);
+        for (i, block) in blocks.iter().enumerate() {
+            if 0 < i {
+                ui.add_space(block_spacing);
+            }
+            self.block(ui, block);
+        }
+    }
+
+    fn block(&mut self, ui: &mut Ui, block: &Block) {
+        match block {
+            Block::Paragraph(inlines) => self.paragraph(ui, inlines, ParagraphStyle::default()),
+            Block::Heading { level, inlines } => {
+                let style = ParagraphStyle {
+                    size: Some(heading_size(ui.style(), *level)),
+                    strong: true,
+                };
+                self.paragraph(ui, inlines, style);
+                if *level <= HeadingLevel::H2 {
+                    ui.separator();
+                }
+            }
+            Block::Quote { kind, blocks } => self.block_quote(ui, *kind, blocks),
+            Block::Code { language, code } => self.code_block(ui, language, code),
+            Block::List { start, items } => self.list(ui, *start, items),
+            Block::Table {
+                alignments,
+                header,
+                rows,
+            } => self.table(ui, alignments, header, rows),
+            Block::Html(html) => {
+                ui.label(RichText::new(html.trim_end()).monospace().weak());
+            }
+            Block::Rule => {
+                ui.separator();
+            }
+        }
+    }
+
+    fn paragraph(&mut self, ui: &mut Ui, inlines: &[Inline], style: ParagraphStyle) {
+        let font_id = style
+            .size
+            .map_or_else(|| TextStyle::Body.resolve(ui.style()), FontId::proportional);
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
+        let layout = Layout::left_to_right(Align::BOTTOM).with_main_wrap(true);
+
+        ui.allocate_ui_with_layout(vec2(ui.available_width(), row_height), layout, |ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.set_row_height(row_height);
+            for inline in inlines {
+                self.inline(ui, inline, style, row_height);
+            }
+        });
+    }
+
+    fn inline(&mut self, ui: &mut Ui, inline: &Inline, style: ParagraphStyle, row_height: f32) {
+        match inline {
+            Inline::Text(text, inline_style) => {
+                let rich_text = rich_text(text, inline_style, style);
+                if let Some(url) = &inline_style.link {
+                    ui.add(Hyperlink::from_label_and_url(rich_text, url.as_ref()));
+                } else {
+                    ui.label(rich_text);
+                }
+            }
+            Inline::Image {
+                url,
+                alt,
+                title,
+                link,
+            } => {
+                let max_width = self
+                    .viewer
+                    .max_image_width
+                    .unwrap_or(f32::INFINITY)
+                    .min(ui.available_width());
+                let mut image = egui::Image::new(url.as_str())
+                    .alt_text(alt.as_str())
+                    .max_width(max_width);
+                if link.is_some() {
+                    image = image.sense(Sense::click());
+                }
+                let mut response = ui.add(image);
+                if let Some(link) = link {
+                    response = response.on_hover_cursor(egui::CursorIcon::PointingHand);
+                    if response.clicked_with_open_in_background() {
+                        ui.ctx().open_url(OpenUrl::new_tab(link.as_ref()));
+                    } else if response.clicked() {
+                        ui.ctx().open_url(OpenUrl::same_tab(link.as_ref()));
+                    }
+                }
+                if !title.is_empty() {
+                    response.on_hover_text(title);
+                }
+            }
+            Inline::FootnoteReference(number) => {
+                let text = RichText::new(format!("[{number}]"))
+                    .small_raised()
+                    .color(ui.visuals().hyperlink_color);
+                let response = ui.label(text);
+                if let Some(blocks) = self.document.footnotes.get(number - 1) {
+                    if !blocks.is_empty() {
+                        response.on_hover_ui(|ui| self.blocks(ui, blocks));
+                    }
+                }
+            }
+            Inline::Checkbox(checked) => {
+                let mut checked = *checked;
+                ui.add_enabled(false, Checkbox::without_text(&mut checked));
+            }
+            Inline::LineBreak => {
+                ui.allocate_exact_size(vec2(0.0, row_height), Sense::hover()); // make sure we take up some height
+                ui.end_row();
+                ui.set_row_height(row_height);
+            }
+        }
+    }
+
+    fn block_quote(&mut self, ui: &mut Ui, kind: Option<BlockQuoteKind>, blocks: &[Block]) {
+        let indent = ui.text_style_height(&TextStyle::Body);
+        let (title, color) = match kind {
+            None => (None, ui.visuals().weak_text_color()),
+            Some(BlockQuoteKind::Note) => (Some("Note"), ui.visuals().hyperlink_color),
+            Some(BlockQuoteKind::Tip) => (Some("Tip"), egui::Color32::from_rgb(0x3f, 0xb9, 0x50)),
+            Some(BlockQuoteKind::Important) => {
+                (Some("Important"), egui::Color32::from_rgb(0xab, 0x7d, 0xf8))
+            }
+            Some(BlockQuoteKind::Warning) => (Some("Warning"), ui.visuals().warn_fg_color),
+            Some(BlockQuoteKind::Caution) => (Some("Caution"), ui.visuals().error_fg_color),
+        };
+
+        let response = Frame::new()
+            .inner_margin(egui::Margin {
+                left: indent as i8,
+                ..Default::default()
+            })
+            .show(ui, |ui| {
+                if let Some(title) = title {
+                    ui.label(RichText::new(title).strong().color(color));
+                }
+                self.blocks(ui, blocks);
+            })
+            .response;
+
+        let rect = response.rect;
+        let x = rect.left() + 0.25 * indent;
+        ui.painter().vline(
+            x,
+            rect.y_range(),
+            Stroke::new(0.2 * indent, color.gamma_multiply(0.5)),
+        );
+    }
+
+    fn code_block(&self, ui: &mut Ui, language: &str, code: &str) {
+        let layout_job =
+            syntax_highlighting::highlight(ui.ctx(), ui.style(), &self.theme, code, language);
+        Frame::new()
+            .fill(ui.visuals().code_bg_color)
+            .corner_radius(ui.visuals().widgets.noninteractive.corner_radius)
+            .inner_margin(ui.spacing().menu_margin)
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.add(Label::new(layout_job));
+            });
+    }
+
+    fn list(&mut self, ui: &mut Ui, start: Option<u64>, items: &[ListItem]) {
+        let row_height = ui.text_style_height(&TextStyle::Body);
+        let one_indent = row_height / 2.0;
+
+        self.list_depth += 1;
+        for (i, item) in items.iter().enumerate() {
+            ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                if let Some(checked) = item.checked {
+                    let mut checked = checked;
+                    ui.add_enabled(false, Checkbox::without_text(&mut checked));
+                } else if let Some(start) = start {
+                    numbered_point(ui, 3.0 * one_indent, start + i as u64);
+                    ui.add_space(one_indent);
+                } else {
+                    ui.add_space(one_indent);
+                    bullet_point(ui, one_indent, self.list_depth);
+                    ui.add_space(one_indent);
+                }
+
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing.x = ui.style().spacing.item_spacing.x;
+                    self.blocks(ui, &item.blocks);
+                });
+            });
+        }
+        self.list_depth -= 1;
+    }
+
+    fn table(
+        &mut self,
+        ui: &mut Ui,
+        alignments: &[Align],
+        header: &[Vec<Inline>],
+        rows: &[Vec<Vec<Inline>>],
+    ) {
+        let id = self.id.with(("table", self.next_table));
+        self.next_table += 1;
+
+        let header_style = ParagraphStyle {
+            size: None,
+            strong: true,
+        };
+
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+
+            for (column, cell) in header.iter().enumerate() {
+                let align = alignments.get(column).copied().unwrap_or(Align::Min);
+                self.table_cell(ui, cell, align, header_style);
+            }
+            ui.end_row();
+
+            for row in rows {
+                for (column, cell) in row.iter().enumerate() {
+                    let align = alignments.get(column).copied().unwrap_or(Align::Min);
+                    self.table_cell(ui, cell, align, ParagraphStyle::default());
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    fn table_cell(&mut self, ui: &mut Ui, inlines: &[Inline], align: Align, style: ParagraphStyle) {
+        let is_plain_text = inlines.iter().all(
+            |inline| matches!(inline, Inline::Text(_, text_style) if text_style.link.is_none()),
+        );
+
+        if !is_plain_text {
+            let row_height = ui.text_style_height(&TextStyle::Body);
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                for inline in inlines {
+                    self.inline(ui, inline, style, row_height);
+                }
+            });
+        } else {
+            // A single label can be aligned within the column:
+            let mut layout_job = LayoutJob::default();
+            for inline in inlines {
+                if let Inline::Text(text, inline_style) = inline {
+                    rich_text(text, inline_style, style).append_to(
+                        &mut layout_job,
+                        ui.style(),
+                        FontSelection::Default,
+                        Align::Center,
+                    );
+                }
+            }
+            ui.with_layout(Layout::top_down(align), |ui| ui.label(layout_job));
+        }
+    }
+
+    fn footnotes(&mut self, ui: &mut Ui) {
+        let document = self.document;
+        if document.footnotes.is_empty() {
+            return;
+        }
+
+        ui.separator();
+
+        let one_indent = ui.text_style_height(&TextStyle::Body) / 2.0;
+        for (i, blocks) in document.footnotes.iter().enumerate() {
+            ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                numbered_point(ui, 3.0 * one_indent, i as u64 + 1);
+                ui.add_space(one_indent);
+                ui.vertical(|ui| self.blocks(ui, blocks));
+            });
+        }
+    }
+}
+
+fn heading_size(style: &egui::Style, level: HeadingLevel) -> f32 {
+    let body = TextStyle::Body.resolve(style).size;
+    let heading = TextStyle::Heading.resolve(style).size;
+    let t = (HeadingLevel::H6 as usize - level as usize) as f32 / 5.0;
+    egui::lerp(body..=heading, t)
+}
+
+fn rich_text(text: &str, inline_style: &InlineStyle, style: ParagraphStyle) -> RichText {
+    let InlineStyle {
+        strong,
+        emphasis,
+        strikethrough,
+        code,
+        superscript,
+        subscript,
+        link: _,
+    } = *inline_style;
+
+    let mut rich_text = RichText::new(text);
+    if let Some(size) = style.size {
+        rich_text = rich_text.size(size);
+    }
+    if code {
+        rich_text = rich_text.code();
+    }
+    if strong || style.strong {
+        rich_text = rich_text.strong();
+    }
+    if emphasis {
+        rich_text = rich_text.italics();
+    }
+    if strikethrough {
+        rich_text = rich_text.strikethrough();
+    }
+    if superscript {
+        rich_text = rich_text.small_raised();
+    } else if subscript {
+        rich_text = rich_text.small();
+    }
+    rich_text
+}
+
+fn bullet_point(ui: &mut Ui, width: f32, depth: usize) -> Response {
+    let row_height = ui.text_style_height(&TextStyle::Body);
+    let (rect, response) = ui.allocate_exact_size(vec2(width, row_height), Sense::hover());
+    let radius = rect.height() / 8.0;
+    let color = ui.visuals().strong_text_color();
+    if depth % 2 == 1 {
+        ui.painter().circle_filled(rect.center(), radius, color);
+    } else {
+        ui.painter()
+            .circle_stroke(rect.center(), radius, Stroke::new(1.0, color));
+    }
+    response
+}
+
+fn numbered_point(ui: &mut Ui, width: f32, number: u64) -> Response {
+    let font_id = TextStyle::Body.resolve(ui.style());
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let (rect, response) = ui.allocate_exact_size(vec2(width, row_height), Sense::hover());
+    let text = format!("{number}.");
+    let text_color = ui.visuals().strong_text_color();
+    ui.painter().text(
+        rect.right_center(),
+        Align2::RIGHT_CENTER,
+        text,
+        font_id,
+        text_color,
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.to_owned(), InlineStyle::default())
+    }
+
+    #[test]
+    fn tight_task_list() {
+        let document = parse("- [x] done\n- [ ] todo\n  - nested");
+        let Block::List { start: None, items } = &document.blocks[0] else {
+            panic!("Expected a list, got {:?}", document.blocks);
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].checked, Some(true));
+        assert_eq!(items[0].blocks, vec![Block::Paragraph(vec![text("done")])]);
+        assert_eq!(items[1].checked, Some(false));
+        assert_eq!(items[1].blocks.len(), 2, "paragraph and nested list");
+    }
+
+    #[test]
+    fn footnotes_are_numbered_by_first_reference() {
+        let document = parse("A[^b] B[^a] C[^b]\n\n[^a]: first\n\n[^b]: second\n");
+        assert_eq!(
+            document.blocks,
+            vec![Block::Paragraph(vec![
+                text("A"),
+                Inline::FootnoteReference(1),
+                text(" B"),
+                Inline::FootnoteReference(2),
+                text(" C"),
+                Inline::FootnoteReference(1),
+            ])]
+        );
+        assert_eq!(
+            document.footnotes,
+            vec![
+                vec![Block::Paragraph(vec![text("second")])],
+                vec![Block::Paragraph(vec![text("first")])],
+            ]
+        );
+    }
+
+    #[test]
+    fn table_and_code() {
+        let document =
+            parse("| a | b |\n|---|--:|\n| 1 | **2** |\n\n```rust,ignore\nfn main() {}\n```");
+        let strong = InlineStyle {
+            strong: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            document.blocks,
+            vec![
+                Block::Table {
+                    alignments: vec![Align::Min, Align::Max],
+                    header: vec![vec![text("a")], vec![text("b")]],
+                    rows: vec![vec![
+                        vec![text("1")],
+                        vec![Inline::Text("2".to_owned(), strong)]
+                    ]],
+                },
+                Block::Code {
+                    language: "rust".to_owned(),
+                    code: "fn main() {}".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn show_everything() {
+        let markdown = r#"
+# Heading
+
+Some *emphasis*, **strong**, ~~strikethrough~~, `code` and a [link](https://www.egui.rs).<br>
+A footnote[^1] and an ![image](https://example.com/image.png "title").
+
+> [!WARNING]
+> Quoted
+>
+> 1. numbered
+> 2. list
+
+- [x] task
+- bullet
+  - nested
+
+| left | center | right |
+|------|:------:|------:|
+| 1    | 2      | 3     |
+
+```rust
+fn main() {}
+```
+
+---
+
+[^1]: The footnote.
+"#;
+        egui::__run_test_ui(|ui| {
+            CommonMarkViewer::new().show(ui, markdown);
+        });
+    }
+}
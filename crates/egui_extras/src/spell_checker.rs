@@ -0,0 +1,230 @@
+//! A simple [`egui::SpellChecker`] based on a list of words.
+
+use std::{collections::BTreeSet, ops::Range, path::Path};
+
+/// A [`egui::SpellChecker`] that accepts every word in a dictionary.
+///
+/// The dictionary is a list of words, one per line, like `/usr/share/dict/words`.
+/// Words are compared case-insensitively, and suggestions are the dictionary words
+/// that are a single edit (insertion, deletion, replacement or swap of two letters) away.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// # let mut my_string = String::new();
+/// let spell_checker = egui_extras::DictionarySpellChecker::from_words("hello\nworld");
+/// ui.add(egui::TextEdit::multiline(&mut my_string).spell_checker(&spell_checker));
+/// # });
+/// ```
+#[derive(Clone, Debug)]
+pub struct DictionarySpellChecker {
+    /// All words, in lowercase.
+    words: ahash::HashSet<String>,
+
+    /// All letters used in the dictionary, for generating suggestions.
+    alphabet: BTreeSet<char>,
+
+    /// How many suggestions to return at most.
+    max_suggestions: usize,
+
+    /// Increased whenever a word is added.
+    generation: u64,
+}
+
+impl Default for DictionarySpellChecker {
+    fn default() -> Self {
+        Self {
+            words: Default::default(),
+            alphabet: Default::default(),
+            max_suggestions: 5,
+            generation: 0,
+        }
+    }
+}
+
+impl DictionarySpellChecker {
+    /// Parse a list of words, one per line.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn from_words(words: &str) -> Self {
+        let mut checker = Self::default();
+        for word in words.lines().map(str::trim) {
+            if !word.is_empty() && !word.starts_with('#') {
+                checker.add_word(word);
+            }
+        }
+        checker
+    }
+
+    /// Load a dictionary file with one word per line.
+    ///
+    /// # Errors
+    /// If the file could not be read.
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::from_words(&std::fs::read_to_string(path)?))
+    }
+
+    /// How many suggestions to return at most. Default: 5.
+    #[inline]
+    pub fn max_suggestions(mut self, max_suggestions: usize) -> Self {
+        self.max_suggestions = max_suggestions;
+        self
+    }
+
+    /// Accept this word from now on, e.g. for an "Add to dictionary" button.
+    pub fn add_word(&mut self, word: &str) {
+        let word = word.to_lowercase();
+        self.alphabet.extend(word.chars());
+        if self.words.insert(word) {
+            self.generation += 1;
+        }
+    }
+
+    /// Is this word in the dictionary?
+    pub fn contains(&self, word: &str) -> bool {
+        if !word.chars().any(char::is_uppercase) {
+            return self.words.contains(word);
+        }
+
+        // Lowercase short ASCII words on the stack, so that we don't allocate for every word:
+        let mut buffer = [0_u8; 64];
+        if word.is_ascii() && word.len() <= buffer.len() {
+            let lowercase = &mut buffer[..word.len()];
+            lowercase.copy_from_slice(word.as_bytes());
+            lowercase.make_ascii_lowercase();
+            return std::str::from_utf8(lowercase).is_ok_and(|word| self.words.contains(word));
+        }
+
+        self.words.contains(&word.to_lowercase())
+    }
+}
+
+impl egui::SpellChecker for DictionarySpellChecker {
+    fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        words(text)
+            .filter(|range| !self.contains(&text[range.clone()]))
+            .collect()
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let lowercase = word.to_lowercase();
+        let chars: Vec<char> = lowercase.chars().collect();
+
+        let mut candidates = Vec::new();
+        for i in 0..=chars.len() {
+            let (before, after) = chars.split_at(i);
+            if let Some((_, rest)) = after.split_first() {
+                // Deletion:
+                candidates.push(before.iter().chain(rest).collect::<String>());
+            }
+            if let [a, b, rest @ ..] = after {
+                // Swap:
+                candidates.push(before.iter().chain([b, a]).chain(rest).collect());
+            }
+            for c in &self.alphabet {
+                if let Some((_, rest)) = after.split_first() {
+                    // Replacement:
+                    candidates.push(before.iter().chain([c]).chain(rest).collect());
+                }
+                // Insertion:
+                candidates.push(before.iter().chain([c]).chain(after).collect());
+            }
+        }
+
+        let mut suggestions: Vec<String> = Vec::new();
+        for candidate in candidates {
+            if candidate != lowercase
+                && self.words.contains(&candidate)
+                && !suggestions.contains(&candidate)
+            {
+                suggestions.push(candidate);
+            }
+        }
+        suggestions.truncate(self.max_suggestions);
+
+        if word.chars().next().is_some_and(char::is_uppercase) {
+            for suggestion in &mut suggestions {
+                *suggestion = capitalize(suggestion);
+            }
+        }
+        suggestions
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// The byte ranges of all words in the text.
+///
+/// A word is a run of letters, possibly with apostrophes between them (like "don't").
+fn words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.find(|(_, c)| c.is_alphabetic())?;
+        let mut end = text.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_alphabetic() {
+                chars.next();
+            } else if c == '\'' || c == '’' {
+                let is_inside_word = text[i + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_alphabetic);
+                if !is_inside_word {
+                    end = i;
+                    break;
+                }
+                chars.next();
+            } else {
+                end = i;
+                break;
+            }
+        }
+        Some(start..end)
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::SpellChecker as _;
+
+    use super::*;
+
+    #[test]
+    fn misspelled_words_and_suggestions() {
+        let checker = DictionarySpellChecker::from_words(
+            "# A tiny dictionary\nhello\nworld\nword\nwords\ndon't\n",
+        );
+
+        let text = "Hello, wrold! Don't 42 wordz";
+        let misspelled: Vec<&str> = checker
+            .misspelled(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(misspelled, vec!["wrold", "wordz"]);
+
+        assert_eq!(checker.suggestions("wrold"), vec!["world"]);
+        assert_eq!(checker.suggestions("Wordz"), vec!["Word", "Words"]);
+        assert!(checker.suggestions("xyzzy").is_empty());
+    }
+
+    #[test]
+    fn adding_a_word_changes_the_generation() {
+        let mut checker = DictionarySpellChecker::from_words("hello");
+        let generation = checker.generation();
+        checker.add_word("HELLO");
+        assert_eq!(checker.generation(), generation);
+        checker.add_word("Wörld");
+        assert_ne!(checker.generation(), generation);
+        assert!(checker.contains("WÖRLD"));
+        assert!(checker.contains("Hello"));
+    }
+}
@@ -2,11 +2,13 @@
 //!
 //! Turn on the `syntect` feature for great syntax highlighting of any language.
 //! Otherwise, a very simple fallback will be used, that works okish for C, C++, Rust, and Python.
+//! The fallback continues string literals and `/* block comments */` onto the next lines until they are closed,
+//! both in [`highlight`] and in [`IncrementalHighlighter`].
 
 #![allow(clippy::mem_forget)] // False positive from enum_map macro
 
 use egui::TextStyle;
-use egui::text::LayoutJob;
+use egui::text::{LayoutJob, LayoutSection};
 
 /// View some code with syntax highlighting and selection.
 pub fn code_view_ui(
@@ -92,20 +94,7 @@ fn highlight_inner(
 
     type HighlightCache = egui::cache::FrameCache<LayoutJob, Highlighter>;
 
-    let font_id = style
-        .override_font_id
-        .clone()
-        .unwrap_or_else(|| TextStyle::Monospace.resolve(style));
-
-    // Private type, so that users can't interfere with it in the `IdTypeMap`
-    #[cfg(feature = "syntect")]
-    #[derive(Clone, Default)]
-    struct PrivateSettings(std::sync::Arc<SyntectSettings>);
-
-    // Dummy private settings, to minimize code changes without `syntect`
-    #[cfg(not(feature = "syntect"))]
-    #[derive(Clone, Default)]
-    struct PrivateSettings(std::sync::Arc<()>);
+    let font_id = code_font_id(style);
 
     ctx.memory_mut(|mem| {
         let settings = settings.unwrap_or_else(|| {
@@ -121,12 +110,451 @@ fn highlight_inner(
     })
 }
 
+// Private type, so that users can't interfere with it in the `IdTypeMap`
+#[cfg(feature = "syntect")]
+#[derive(Clone, Default)]
+struct PrivateSettings(std::sync::Arc<SyntectSettings>);
+
+// Dummy private settings, to minimize code changes without `syntect`
+#[cfg(not(feature = "syntect"))]
+#[derive(Clone, Default)]
+struct PrivateSettings(std::sync::Arc<()>);
+
+pub(crate) fn code_font_id(style: &egui::Style) -> egui::FontId {
+    style
+        .override_font_id
+        .clone()
+        .unwrap_or_else(|| TextStyle::Monospace.resolve(style))
+}
+
 fn monospace_font_size(style: &egui::Style) -> f32 {
     TextStyle::Monospace.resolve(style).size
 }
 
 // ----------------------------------------------------------------------------
 
+/// Syntax highlighting for large code buffers that are edited often.
+///
+/// [`highlight`] re-highlights all of the code whenever any of it changes.
+/// This instead remembers the parser state at the end of each line, so that after an edit
+/// only the changed lines are re-highlighted, plus any following lines whose starting
+/// state changed (e.g. because a string literal was opened).
+///
+/// Use [`BackgroundHighlighter`] to do the highlighting on another thread.
+///
+/// ```
+/// # egui::__run_test_ui(|ui| {
+/// use egui_extras::syntax_highlighting::{CodeTheme, IncrementalHighlighter};
+///
+/// # let mut code = String::new();
+/// let theme = CodeTheme::from_memory(ui.ctx(), ui.style());
+/// // Store this in your app state:
+/// let mut highlighter = IncrementalHighlighter::new(ui.ctx(), ui.style(), &theme, "rs");
+///
+/// let mut layouter = |ui: &egui::Ui, buf: &dyn egui::TextBuffer, wrap_width: f32| {
+///     highlighter.update(buf.as_str());
+///     let mut layout_job = (*highlighter.layout_job(buf.as_str())).clone();
+///     layout_job.wrap.max_width = wrap_width;
+///     ui.fonts(|f| f.layout_job(layout_job))
+/// };
+/// ui.add(egui::TextEdit::multiline(&mut code).code_editor().layouter(&mut layouter));
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct IncrementalHighlighter {
+    theme: CodeTheme,
+    language: String,
+    font_id: egui::FontId,
+    settings: PrivateSettings,
+
+    /// Cheap to clone, so we can hand the result over to another thread.
+    lines: Vec<std::sync::Arc<HighlightedLine>>,
+
+    /// The byte offset of each of the [`Self::lines`] in [`Self::job`].
+    line_starts: Vec<usize>,
+
+    /// All of the [`Self::lines`], ready to be laid out.
+    job: std::sync::Arc<LayoutJob>,
+
+    /// See [`Self::generation`].
+    generation: u64,
+}
+
+struct HighlightedLine {
+    /// The parser state at the end of the line, or `None` if the language is not supported.
+    end_state: Option<LineState>,
+
+    /// Byte ranges are relative to the start of the line.
+    sections: Vec<LayoutSection>,
+}
+
+/// The lines that differ between the last [`IncrementalHighlighter::update`] and some new code.
+struct ChangedLines {
+    /// Indices into [`IncrementalHighlighter::lines`].
+    lines: std::ops::Range<usize>,
+
+    /// Where those lines were in the old code.
+    old_bytes: std::ops::Range<usize>,
+
+    /// What replaced them in the new code.
+    new_bytes: std::ops::Range<usize>,
+}
+
+impl IncrementalHighlighter {
+    /// Highlight code in the given `language`, e.g. `"rs"` or `"python"`.
+    ///
+    /// Create a new highlighter if the theme, style or language changes.
+    pub fn new(
+        ctx: &egui::Context,
+        style: &egui::Style,
+        theme: &CodeTheme,
+        language: &str,
+    ) -> Self {
+        let settings = ctx.data_mut(|d| {
+            d.get_temp_mut_or_default::<PrivateSettings>(egui::Id::NULL)
+                .clone()
+        });
+        Self::new_impl(style, theme, language, settings)
+    }
+
+    /// Like [`Self::new`], but with custom `syntect` settings.
+    #[cfg(feature = "syntect")]
+    pub fn new_with(
+        style: &egui::Style,
+        theme: &CodeTheme,
+        language: &str,
+        settings: std::sync::Arc<SyntectSettings>,
+    ) -> Self {
+        Self::new_impl(style, theme, language, PrivateSettings(settings))
+    }
+
+    fn new_impl(
+        style: &egui::Style,
+        theme: &CodeTheme,
+        language: &str,
+        settings: PrivateSettings,
+    ) -> Self {
+        Self {
+            theme: theme.clone(),
+            language: language.to_owned(),
+            font_id: code_font_id(style),
+            settings,
+            lines: Vec::new(),
+            line_starts: Vec::new(),
+            job: Default::default(),
+            generation: 0,
+        }
+    }
+
+    /// Re-highlight the lines that changed since the last call.
+    ///
+    /// Returns the number of lines that were highlighted.
+    pub fn update(&mut self, code: &str) -> usize {
+        profiling::function_scope!();
+
+        if self.is_up_to_date(code) {
+            return 0;
+        }
+
+        let changed = self.changed_lines(code);
+        let old_len = self.job.text.len();
+        let old_start = |line: usize| self.line_starts.get(line).copied().unwrap_or(old_len);
+        let new_start = |line: usize| code.len() - (old_len - old_start(line));
+
+        let highlighter = LineHighlighter::new(
+            &self.theme,
+            &self.language,
+            HighlightSettings(&self.settings.0),
+        );
+        let initial_state = highlighter.as_ref().map(LineHighlighter::initial_state);
+        let state_before = |line: usize| {
+            if line == 0 {
+                &initial_state
+            } else {
+                &self.lines[line - 1].end_state
+            }
+        };
+
+        let mut state = state_before(changed.lines.start).clone();
+        let mut new_lines = Vec::new();
+        let mut new_starts = Vec::new();
+        let mut offset = changed.new_bytes.start;
+        for text in code[changed.new_bytes.clone()].split_inclusive('\n') {
+            new_lines.push(self.highlight_line(highlighter.as_ref(), &mut state, text));
+            new_starts.push(offset);
+            offset += text.len();
+        }
+
+        // The lines after the edit only need re-highlighting until the state is back in sync:
+        let mut end = changed.lines.end;
+        while end < self.lines.len() && state != *state_before(end) {
+            let text = &code[new_start(end)..new_start(end + 1)];
+            new_lines.push(self.highlight_line(highlighter.as_ref(), &mut state, text));
+            new_starts.push(new_start(end));
+            end += 1;
+        }
+
+        let num_highlighted = new_lines.len();
+        let old_bytes = changed.old_bytes.start..old_start(end);
+        let new_bytes = changed.new_bytes.start..new_start(end);
+        let new_sections: Vec<LayoutSection> = new_lines
+            .iter()
+            .zip(&new_starts)
+            .flat_map(|(line, &start)| offset_sections(&line.sections, start))
+            .collect();
+
+        for start in &mut self.line_starts[end..] {
+            *start = code.len() - (old_len - *start);
+        }
+        self.line_starts
+            .splice(changed.lines.start..end, new_starts);
+        self.lines.splice(
+            changed.lines.start..end,
+            new_lines.into_iter().map(std::sync::Arc::new),
+        );
+        splice_job(
+            std::sync::Arc::make_mut(&mut self.job),
+            old_bytes,
+            &code[new_bytes],
+            new_sections,
+        );
+        self.generation += 1;
+
+        num_highlighted
+    }
+
+    /// Changes every time [`Self::update`] changes the highlighting,
+    /// so you can tell when to rebuild anything that depends on it.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Compares `code` with the code of the last [`Self::update`].
+    fn changed_lines(&self, code: &str) -> ChangedLines {
+        let old = self.job.text.as_str();
+        let prefix = old
+            .bytes()
+            .zip(code.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old
+            .bytes()
+            .rev()
+            .zip(code.bytes().rev())
+            .take(old.len().min(code.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        // The first line that contains a difference…
+        let mut first = self
+            .line_starts
+            .partition_point(|&start| start <= prefix)
+            .saturating_sub(1);
+        if prefix == old.len() && old.ends_with('\n') {
+            first = self.lines.len(); // …or where text was appended.
+        }
+
+        // The first line after the difference:
+        let suffix_start = old.len() - suffix;
+        let mut end = self
+            .line_starts
+            .partition_point(|&start| start < suffix_start)
+            .max(first);
+        if self.line_starts.get(end) == Some(&suffix_start) {
+            // Is it still at the start of a line in the new code?
+            let new_start = code.len() - suffix;
+            if new_start != 0 && code.as_bytes()[new_start - 1] != b'\n' {
+                end += 1;
+            }
+        }
+
+        let old_start = |line: usize| self.line_starts.get(line).copied().unwrap_or(old.len());
+        let old_bytes = old_start(first)..old_start(end);
+        let new_bytes = old_bytes.start..code.len() - (old.len() - old_bytes.end);
+        ChangedLines {
+            lines: first..end,
+            old_bytes,
+            new_bytes,
+        }
+    }
+
+    fn highlight_line(
+        &self,
+        highlighter: Option<&LineHighlighter<'_>>,
+        state: &mut Option<LineState>,
+        text: &str,
+    ) -> HighlightedLine {
+        let mut job = LayoutJob::default();
+        if let (Some(highlighter), Some(line_state)) = (highlighter, state.as_mut()) {
+            if highlighter
+                .highlight_line(line_state, text, &mut job)
+                .is_none()
+            {
+                job = LayoutJob::default();
+            }
+        }
+        if job.sections.is_empty() {
+            job.append(text, 0.0, self.fallback_format());
+        }
+
+        HighlightedLine {
+            end_state: state.clone(),
+            sections: job.sections,
+        }
+    }
+
+    pub(crate) fn fallback_format(&self) -> egui::TextFormat {
+        egui::TextFormat::simple(self.font_id.clone(), fallback_color(&self.theme))
+    }
+
+    /// Does the last [`Self::update`] match this code?
+    pub fn is_up_to_date(&self, code: &str) -> bool {
+        self.job.text == code
+    }
+
+    /// Lay out `code` using the highlighting from the last [`Self::update`].
+    ///
+    /// Lines that have changed since then are shown without highlighting,
+    /// so this can show the previous result while a [`BackgroundHighlighter`] catches up.
+    ///
+    /// If `code` is up to date, this is just a reference count increment.
+    pub fn layout_job(&self, code: &str) -> std::sync::Arc<LayoutJob> {
+        profiling::function_scope!();
+
+        if self.is_up_to_date(code) {
+            return self.job.clone();
+        }
+
+        let changed = self.changed_lines(code);
+        let mut job = (*self.job).clone();
+        let unhighlighted = (!changed.new_bytes.is_empty()).then(|| LayoutSection {
+            leading_space: 0.0,
+            byte_range: changed.new_bytes.clone(),
+            format: self.fallback_format(),
+        });
+        splice_job(
+            &mut job,
+            changed.old_bytes,
+            &code[changed.new_bytes],
+            unhighlighted,
+        );
+        std::sync::Arc::new(job)
+    }
+
+    /// The number of lines, as of the last [`Self::update`].
+    pub fn num_lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The highlighting of one line, as of the last [`Self::update`].
+    ///
+    /// The byte ranges are relative to the start of the line.
+    pub fn line_sections(&self, line: usize) -> &[LayoutSection] {
+        self.lines
+            .get(line)
+            .map_or(&[], |line| line.sections.as_slice())
+    }
+}
+
+fn offset_sections(
+    sections: &[LayoutSection],
+    offset: usize,
+) -> impl Iterator<Item = LayoutSection> + '_ {
+    sections.iter().map(move |section| {
+        let mut section = section.clone();
+        section.byte_range = offset + section.byte_range.start..offset + section.byte_range.end;
+        section
+    })
+}
+
+/// Replace the `old_bytes` of `job` with `text`.
+///
+/// `old_bytes` must start and end on section boundaries,
+/// and `sections` must cover `text`, with byte ranges relative to the start of `job`.
+fn splice_job(
+    job: &mut LayoutJob,
+    old_bytes: std::ops::Range<usize>,
+    text: &str,
+    sections: impl IntoIterator<Item = LayoutSection>,
+) {
+    let first = job
+        .sections
+        .partition_point(|section| section.byte_range.start < old_bytes.start);
+    let end = job
+        .sections
+        .partition_point(|section| section.byte_range.start < old_bytes.end);
+    let new_end = old_bytes.start + text.len();
+    for section in &mut job.sections[end..] {
+        let range = &mut section.byte_range;
+        *range = range.start - old_bytes.end + new_end..range.end - old_bytes.end + new_end;
+    }
+    job.sections.splice(first..end, sections);
+    job.text.replace_range(old_bytes, text);
+}
+
+/// Runs an [`IncrementalHighlighter`] on a background thread.
+///
+/// [`Self::layout_job`] never waits for the highlighting. Until the thread has caught up,
+/// it shows the previous result, with the edited lines unhighlighted.
+/// A repaint is requested when a new result is ready.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct BackgroundHighlighter {
+    latest: std::sync::Arc<egui::mutex::Mutex<IncrementalHighlighter>>,
+    sender: std::sync::mpsc::Sender<String>,
+    last_sent: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl BackgroundHighlighter {
+    pub fn new(ctx: &egui::Context, highlighter: IncrementalHighlighter) -> Self {
+        let latest = std::sync::Arc::new(egui::mutex::Mutex::new(highlighter.clone()));
+        let (sender, receiver) = std::sync::mpsc::channel::<String>();
+
+        let ctx = ctx.clone();
+        let thread_latest = latest.clone();
+        std::thread::Builder::new()
+            .name("egui_extras::syntax_highlighting".to_owned())
+            .spawn(move || {
+                let mut highlighter = highlighter;
+                // Stops when `Self` is dropped:
+                while let Ok(mut code) = receiver.recv() {
+                    // Skip to the newest code:
+                    while let Ok(newer) = receiver.try_recv() {
+                        code = newer;
+                    }
+                    highlighter.update(&code);
+                    *thread_latest.lock() = highlighter.clone();
+                    ctx.request_repaint();
+                }
+            })
+            .expect("Failed to spawn syntax highlighting thread");
+
+        Self {
+            latest,
+            sender,
+            last_sent: None,
+        }
+    }
+
+    /// Lay out `code` using the latest available highlighting,
+    /// and start highlighting `code` if it has changed.
+    pub fn layout_job(&mut self, code: &str) -> std::sync::Arc<LayoutJob> {
+        if self.last_sent.as_deref() != Some(code) {
+            self.last_sent = Some(code.to_owned());
+            self.sender.send(code.to_owned()).ok();
+        }
+        self.latest.lock().layout_job(code)
+    }
+
+    /// Has the background thread finished highlighting this code?
+    pub fn is_up_to_date(&self, code: &str) -> bool {
+        self.latest.lock().is_up_to_date(code)
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 #[cfg(not(feature = "syntect"))]
 #[derive(Clone, Copy, PartialEq, enum_map::Enum)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -232,10 +660,7 @@ impl Default for CodeTheme {
 impl CodeTheme {
     /// Selects either dark or light theme based on the given style.
     pub fn from_style(style: &egui::Style) -> Self {
-        let font_id = style
-            .override_font_id
-            .clone()
-            .unwrap_or_else(|| TextStyle::Monospace.resolve(style));
+        let font_id = code_font_id(style);
 
         if style.visuals.dark_mode {
             Self::dark_with_font_id(font_id)
@@ -504,73 +929,122 @@ impl Highlighter {
     ) -> LayoutJob {
         Self::highlight_impl(theme, code, lang, settings).unwrap_or_else(|| {
             // Fallback:
-            LayoutJob::simple(
-                code.into(),
-                font_id,
-                if theme.dark_mode {
-                    egui::Color32::LIGHT_GRAY
-                } else {
-                    egui::Color32::DARK_GRAY
-                },
-                f32::INFINITY,
-            )
+            LayoutJob::simple(code.into(), font_id, fallback_color(theme), f32::INFINITY)
         })
     }
 
-    #[cfg(feature = "syntect")]
     fn highlight_impl(
         theme: &CodeTheme,
         text: &str,
         language: &str,
-        highlighter: HighlightSettings<'_>,
+        settings: HighlightSettings<'_>,
     ) -> Option<LayoutJob> {
         profiling::function_scope!();
-        use syntect::easy::HighlightLines;
-        use syntect::highlighting::FontStyle;
-        use syntect::util::LinesWithEndings;
 
-        let syntax = highlighter
-            .0
+        let highlighter = LineHighlighter::new(theme, language, settings)?;
+        let mut state = highlighter.initial_state();
+        let mut job = LayoutJob::default();
+        for line in text.split_inclusive('\n') {
+            highlighter.highlight_line(&mut state, line, &mut job)?;
+        }
+        Some(job)
+    }
+}
+
+/// The color of code we can't highlight.
+fn fallback_color(theme: &CodeTheme) -> egui::Color32 {
+    if theme.dark_mode {
+        egui::Color32::LIGHT_GRAY
+    } else {
+        egui::Color32::DARK_GRAY
+    }
+}
+
+/// Highlights code one line at a time.
+///
+/// The [`LineState`] at the end of a line is all that is needed to continue with the next one.
+#[cfg(feature = "syntect")]
+struct LineHighlighter<'a> {
+    theme: &'a CodeTheme,
+    settings: &'a SyntectSettings,
+    syntax: &'a syntect::parsing::SyntaxReference,
+    highlighter: syntect::highlighting::Highlighter<'a>,
+}
+
+#[cfg(feature = "syntect")]
+#[derive(Clone, PartialEq)]
+struct LineState {
+    parse: syntect::parsing::ParseState,
+    highlight: syntect::highlighting::HighlightState,
+}
+
+#[cfg(feature = "syntect")]
+impl<'a> LineHighlighter<'a> {
+    fn new(theme: &'a CodeTheme, language: &str, settings: HighlightSettings<'a>) -> Option<Self> {
+        let settings = settings.0;
+        let syntax = settings
             .ps
             .find_syntax_by_name(language)
-            .or_else(|| highlighter.0.ps.find_syntax_by_extension(language))?;
+            .or_else(|| settings.ps.find_syntax_by_extension(language))?;
 
         let syn_theme = theme.syntect_theme.syntect_key_name();
-        let mut h = HighlightLines::new(syntax, &highlighter.0.ts.themes[syn_theme]);
+        let highlighter = syntect::highlighting::Highlighter::new(&settings.ts.themes[syn_theme]);
 
-        use egui::text::{LayoutSection, TextFormat};
+        Some(Self {
+            theme,
+            settings,
+            syntax,
+            highlighter,
+        })
+    }
 
-        let mut job = LayoutJob {
-            text: text.into(),
-            ..Default::default()
-        };
+    fn initial_state(&self) -> LineState {
+        LineState {
+            parse: syntect::parsing::ParseState::new(self.syntax),
+            highlight: syntect::highlighting::HighlightState::new(
+                &self.highlighter,
+                syntect::parsing::ScopeStack::new(),
+            ),
+        }
+    }
 
-        for line in LinesWithEndings::from(text) {
-            for (style, range) in h.highlight_line(line, &highlighter.0.ps).ok()? {
-                let fg = style.foreground;
-                let text_color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
-                let italics = style.font_style.contains(FontStyle::ITALIC);
-                let underline = style.font_style.contains(FontStyle::ITALIC);
-                let underline = if underline {
-                    egui::Stroke::new(1.0, text_color)
-                } else {
-                    egui::Stroke::NONE
-                };
-                job.sections.push(LayoutSection {
-                    leading_space: 0.0,
-                    byte_range: as_byte_range(text, range),
-                    format: TextFormat {
-                        font_id: theme.font_id.clone(),
-                        color: text_color,
-                        italics,
-                        underline,
-                        ..Default::default()
-                    },
-                });
-            }
+    /// Append the highlighted `line` to `job`.
+    fn highlight_line(&self, state: &mut LineState, line: &str, job: &mut LayoutJob) -> Option<()> {
+        use egui::text::TextFormat;
+        use syntect::highlighting::{FontStyle, HighlightIterator};
+
+        let ops = state.parse.parse_line(line, &self.settings.ps).ok()?;
+
+        let offset = job.text.len();
+        job.text.push_str(line);
+
+        for (style, range) in
+            HighlightIterator::new(&mut state.highlight, &ops, line, &self.highlighter)
+        {
+            let fg = style.foreground;
+            let text_color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
+            let italics = style.font_style.contains(FontStyle::ITALIC);
+            let underline = style.font_style.contains(FontStyle::ITALIC);
+            let underline = if underline {
+                egui::Stroke::new(1.0, text_color)
+            } else {
+                egui::Stroke::NONE
+            };
+            let byte_range = as_byte_range(line, range);
+            job.sections.push(LayoutSection {
+                leading_space: 0.0,
+                byte_range: offset + byte_range.start..offset + byte_range.end,
+                format: TextFormat {
+                    font_id: self.theme.font_id.clone(),
+                    color: text_color,
+                    italics,
+                    underline,
+                    ..Default::default()
+                },
+            });
         }
 
-        Some(job)
+        Some(())
     }
 }
 
@@ -594,20 +1068,64 @@ fn as_byte_range(whole: &str, range: &str) -> std::ops::Range<usize> {
 // ----------------------------------------------------------------------------
 
 #[cfg(not(feature = "syntect"))]
-impl Highlighter {
-    fn highlight_impl(
-        theme: &CodeTheme,
-        mut text: &str,
-        language: &str,
-        _settings: HighlightSettings<'_>,
-    ) -> Option<LayoutJob> {
-        profiling::function_scope!();
+struct LineHighlighter<'a> {
+    theme: &'a CodeTheme,
+    language: Language,
+}
+
+/// What a line starts inside of, because an earlier line left it open.
+#[cfg(not(feature = "syntect"))]
+#[derive(Clone, Default, PartialEq)]
+enum LineState {
+    #[default]
+    Code,
 
-        let language = Language::new(language)?;
+    /// `"a string literal"`, until the closing quote.
+    String,
 
+    /// `/* a block comment */`
+    BlockComment,
+}
+
+#[cfg(not(feature = "syntect"))]
+impl<'a> LineHighlighter<'a> {
+    fn new(theme: &'a CodeTheme, language: &str, _settings: HighlightSettings<'a>) -> Option<Self> {
+        Some(Self {
+            theme,
+            language: Language::new(language)?,
+        })
+    }
+
+    // Same signature as the syntect version
+    #[expect(clippy::unused_self)]
+    fn initial_state(&self) -> LineState {
+        LineState::Code
+    }
+
+    /// Append the highlighted `line` to `job`.
+    // Same signature as the syntect version, which can fail
+    #[expect(clippy::unnecessary_wraps)]
+    fn highlight_line(
+        &self,
+        state: &mut LineState,
+        mut text: &str,
+        job: &mut LayoutJob,
+    ) -> Option<()> {
         // Extremely simple syntax highlighter for when we compile without syntect
 
-        let mut job = LayoutJob::default();
+        let Self { theme, language } = self;
+
+        // Finish what an earlier line left open:
+        let open = match state {
+            LineState::Code => None,
+            LineState::String => Some(("\"", TokenType::StringLiteral)),
+            LineState::BlockComment => Some(("*/", TokenType::Comment)),
+        };
+        if let Some((closing, tt)) = open {
+            if append_until(job, &mut text, 0, closing, &theme.formats[tt]) {
+                *state = LineState::Code;
+            }
+        }
 
         while !text.is_empty() {
             if language.double_slash_comments && text.starts_with("//")
@@ -616,18 +1134,16 @@ impl Highlighter {
                 let end = text.find('\n').unwrap_or(text.len());
                 job.append(&text[..end], 0.0, theme.formats[TokenType::Comment].clone());
                 text = &text[end..];
+            } else if language.double_slash_comments && text.starts_with("/*") {
+                let format = &theme.formats[TokenType::Comment];
+                if !append_until(job, &mut text, 2, "*/", format) {
+                    *state = LineState::BlockComment;
+                }
             } else if text.starts_with('"') {
-                let end = text[1..]
-                    .find('"')
-                    .map(|i| i + 2)
-                    .or_else(|| text.find('\n'))
-                    .unwrap_or(text.len());
-                job.append(
-                    &text[..end],
-                    0.0,
-                    theme.formats[TokenType::StringLiteral].clone(),
-                );
-                text = &text[end..];
+                let format = &theme.formats[TokenType::StringLiteral];
+                if !append_until(job, &mut text, 1, "\"", format) {
+                    *state = LineState::String;
+                }
             } else if text.starts_with(|c: char| c.is_ascii_alphanumeric()) {
                 let end = text[1..]
                     .find(|c: char| !c.is_ascii_alphanumeric())
@@ -663,8 +1179,29 @@ impl Highlighter {
             }
         }
 
-        Some(job)
+        Some(())
+    }
+}
+
+/// Append `text` up to and including the first `closing` after its first `skip` bytes,
+/// or all of it if it has no `closing`.
+///
+/// Returns `true` if `closing` was found.
+#[cfg(not(feature = "syntect"))]
+fn append_until(
+    job: &mut LayoutJob,
+    text: &mut &str,
+    skip: usize,
+    closing: &str,
+    format: &egui::TextFormat,
+) -> bool {
+    let end = text[skip..].find(closing).map(|i| skip + i + closing.len());
+    let (head, tail) = text.split_at(end.unwrap_or(text.len()));
+    if !head.is_empty() {
+        job.append(head, 0.0, format.clone());
     }
+    *text = tail;
+    end.is_some()
 }
 
 #[cfg(not(feature = "syntect"))]
@@ -842,3 +1379,181 @@ impl Language {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The color of each byte.
+    fn colors(job: &LayoutJob) -> Vec<egui::Color32> {
+        let mut colors = vec![egui::Color32::PLACEHOLDER; job.text.len()];
+        for section in &job.sections {
+            colors[section.byte_range.clone()].fill(section.format.color);
+        }
+        colors
+    }
+
+    fn highlight_all(theme: &CodeTheme, code: &str) -> LayoutJob {
+        let settings = PrivateSettings::default();
+        Highlighter::highlight(
+            egui::FontId::monospace(12.0),
+            theme,
+            code,
+            "rs",
+            HighlightSettings(&settings.0),
+        )
+    }
+
+    #[test]
+    fn incremental_highlighting() {
+        let ctx = egui::Context::default();
+        let theme = CodeTheme::dark(12.0);
+        let mut highlighter = IncrementalHighlighter::new(&ctx, &ctx.style(), &theme, "rs");
+
+        let mut lines = vec!["let a = 1; // a\n"; 100];
+        let code = lines.concat();
+        assert_eq!(highlighter.update(&code), 100);
+        assert_eq!(highlighter.update(&code), 0);
+        assert_eq!(highlighter.num_lines(), 100);
+
+        // Only the edited line needs highlighting:
+        lines[50] = "let b = 2; // b\n";
+        let code = lines.concat();
+        assert!(!highlighter.is_up_to_date(&code));
+        assert_eq!(highlighter.update(&code), 1);
+        assert!(highlighter.is_up_to_date(&code));
+        assert_eq!(
+            colors(&highlighter.layout_job(&code)),
+            colors(&highlight_all(&theme, &code))
+        );
+
+        // An unterminated string changes the state of all following lines…
+        lines[10] = "let s = \"\n";
+        let code = lines.concat();
+        assert_eq!(highlighter.update(&code), 90);
+        assert_eq!(
+            colors(&highlighter.layout_job(&code)),
+            colors(&highlight_all(&theme, &code))
+        );
+
+        // …until it is terminated:
+        lines[20] = "\";\n";
+        let code = lines.concat();
+        assert_eq!(highlighter.update(&code), 1 + 79);
+        lines[15] = "still a string\n";
+        let code = lines.concat();
+        assert_eq!(highlighter.update(&code), 1);
+        assert_eq!(
+            colors(&highlighter.layout_job(&code)),
+            colors(&highlight_all(&theme, &code))
+        );
+    }
+
+    #[test]
+    fn edits_match_full_highlighting() {
+        let ctx = egui::Context::default();
+        let theme = CodeTheme::dark(12.0);
+        let mut highlighter = IncrementalHighlighter::new(&ctx, &ctx.style(), &theme, "rs");
+
+        let mut code = String::new();
+        let edits: &[(usize, usize, &str)] = &[
+            (0, 0, "fn main() {\n    let x = 1;\n}\n"),
+            (0, 0, "// Hello\n"),
+            (9, 9, "/* still\n"),
+            (19, 19, "a comment */\n"),
+            (0, 9, ""),
+            (14, 15, "é"),
+            (14, 16, "\"ü\n"),
+            (4, 30, ""),
+            (0, 0, "\""),
+            (1, 1, "unterminated"),
+        ];
+        for &(start, end, text) in edits {
+            let mut edited = code.clone();
+            edited.replace_range(start.min(code.len())..end.min(code.len()), text);
+            let stale = highlighter.layout_job(&edited);
+            assert_eq!(stale.text, edited);
+
+            code = edited;
+            highlighter.update(&code);
+            assert!(highlighter.is_up_to_date(&code));
+            assert_eq!(highlighter.num_lines(), code.split_inclusive('\n').count());
+            assert_eq!(
+                colors(&highlighter.layout_job(&code)),
+                colors(&highlight_all(&theme, &code)),
+                "{code:?}"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "syntect"))]
+    #[test]
+    fn multiline_strings_and_comments() {
+        let theme = CodeTheme::dark(12.0);
+        let code = "let s = \"abc\ndef\"; /* x\ny */ let t;\n";
+        let colors = colors(&highlight_all(&theme, code));
+        let string_color = theme.formats[TokenType::StringLiteral].color;
+        let comment_color = theme.formats[TokenType::Comment].color;
+        let range = |token: &str| {
+            let start = code.find(token).unwrap();
+            start..start + token.len()
+        };
+        assert!(
+            colors[range("\"abc\ndef\"")]
+                .iter()
+                .all(|&c| c == string_color)
+        );
+        assert!(
+            colors[range("/* x\ny */")]
+                .iter()
+                .all(|&c| c == comment_color)
+        );
+        assert!(colors[range("let t")].iter().all(|&c| c != comment_color));
+    }
+
+    #[test]
+    fn stale_layout_job() {
+        let ctx = egui::Context::default();
+        let theme = CodeTheme::dark(12.0);
+        let mut highlighter = IncrementalHighlighter::new(&ctx, &ctx.style(), &theme, "rs");
+        highlighter.update("fn a() {}\nfn b() {}\n");
+
+        let code = "fn a() {}\nlet inserted = 0;\nfn b() {}\n";
+        let job = highlighter.layout_job(code);
+        assert_eq!(job.text, code);
+        let colors = colors(&job);
+        let fresh = super::tests::colors(&highlight_all(&theme, code));
+        let (first, rest) = code.split_once('\n').unwrap();
+        let inserted = first.len() + 1..first.len() + 1 + rest.find('\n').unwrap() + 1;
+        assert_eq!(colors[..inserted.start], fresh[..inserted.start]);
+        assert_eq!(colors[inserted.end..], fresh[inserted.end..]);
+        assert!(
+            colors[inserted]
+                .iter()
+                .all(|&c| c == fallback_color(&theme)),
+            "The new line is not highlighted yet"
+        );
+    }
+
+    #[test]
+    fn background_highlighting() {
+        let ctx = egui::Context::default();
+        let theme = CodeTheme::dark(12.0);
+        let mut highlighter = BackgroundHighlighter::new(
+            &ctx,
+            IncrementalHighlighter::new(&ctx, &ctx.style(), &theme, "rs"),
+        );
+
+        let code = "fn main() {\n    println!(\"Hello\");\n}\n";
+        highlighter.layout_job(code);
+        let start = std::time::Instant::now();
+        while !highlighter.is_up_to_date(code) {
+            assert!(start.elapsed().as_secs() < 60, "Timed out");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(
+            colors(&highlighter.layout_job(code)),
+            colors(&highlight_all(&theme, code))
+        );
+    }
+}
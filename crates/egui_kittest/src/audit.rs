@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use egui::accesskit::{Action, NodeId, Role};
+use egui::{Pos2, Rect, Vec2};
+use kittest::{AccessKitNode, NodeT as _};
+
+use crate::{Harness, Node};
+
+/// Options for [`Harness::accessibility_audit_options`] and [`Harness::contrast_audit`].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct AuditOptions {
+    /// The minimum size of clickable and focusable nodes.
+    ///
+    /// The default is `None`, meaning a square with the height of [`egui::style::Spacing::interact_size`].
+    pub min_target_size: Option<Vec2>,
+
+    /// The minimum contrast ratio between text and its background.
+    /// The default is `4.5`, as required by WCAG 2 level AA for normal text.
+    pub min_contrast_ratio: f32,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            min_target_size: None,
+            min_contrast_ratio: 4.5,
+        }
+    }
+}
+
+impl AuditOptions {
+    /// Create a new [`AuditOptions`] with the default values.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Change the minimum size of clickable and focusable nodes.
+    #[inline]
+    pub fn min_target_size(mut self, min_target_size: impl Into<Vec2>) -> Self {
+        self.min_target_size = Some(min_target_size.into());
+        self
+    }
+
+    /// Change the minimum contrast ratio between text and its background.
+    #[inline]
+    pub fn min_contrast_ratio(mut self, min_contrast_ratio: f32) -> Self {
+        self.min_contrast_ratio = min_contrast_ratio;
+        self
+    }
+}
+
+/// The kind of an [`AccessibilityViolation`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// An interactive node has no label, so screen readers can't tell what it does.
+    ///
+    /// Use [`egui::Response::labelled_by`] or a widget with text.
+    MissingLabel,
+
+    /// An image has no alternative text. See [`egui::Image::alt_text`].
+    MissingAltText,
+
+    /// An interactive node has the same label as an earlier sibling,
+    /// so screen reader users can't tell them apart.
+    DuplicateLabel,
+
+    /// A clickable or focusable node is smaller than [`AuditOptions::min_target_size`].
+    SmallTarget { size: Vec2, min_size: Vec2 },
+
+    /// The text of the node doesn't stand out enough from its background.
+    LowContrast { ratio: f32, min_ratio: f32 },
+
+    /// The node is clickable, but not focusable, so it can't be used with a keyboard.
+    NotFocusable,
+
+    /// The node is focusable, but pressing [`egui::Key::Tab`] never focuses it.
+    Unreachable,
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingLabel => write!(f, "Interactive node without label"),
+            Self::MissingAltText => write!(f, "Image without alt text"),
+            Self::DuplicateLabel => write!(f, "Duplicate label in the same container"),
+            Self::SmallTarget { size, min_size } => {
+                write!(f, "Target size {size} is smaller than {min_size}")
+            }
+            Self::LowContrast { ratio, min_ratio } => {
+                write!(f, "Contrast ratio {ratio:.2} is lower than {min_ratio:.2}")
+            }
+            Self::NotFocusable => write!(f, "Clickable node is not focusable"),
+            Self::Unreachable => write!(f, "Focusable node is unreachable with the keyboard"),
+        }
+    }
+}
+
+/// An accessibility problem found by an audit of the [`Harness`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibilityViolation {
+    /// What is wrong.
+    pub kind: ViolationKind,
+
+    /// The AccessKit node with the problem.
+    pub node_id: NodeId,
+
+    /// The role of the node, e.g. [`Role::Button`].
+    pub role: Role,
+
+    /// The label of the node, if it has one.
+    pub label: Option<String>,
+
+    /// The rect of the node, in points.
+    pub rect: Option<Rect>,
+}
+
+impl AccessibilityViolation {
+    fn new(kind: ViolationKind, node: &AccessKitNode<'_>) -> Self {
+        Self {
+            kind,
+            node_id: node.id(),
+            role: node.role(),
+            label: label(node),
+            rect: rect(node),
+        }
+    }
+}
+
+impl Display for AccessibilityViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:?}", self.kind, self.role)?;
+        if let Some(label) = &self.label {
+            write!(f, " {label:?}")?;
+        }
+        if let Some(rect) = &self.rect {
+            write!(f, " at {rect}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The text a screen reader would read for the node, if any.
+fn label(node: &AccessKitNode<'_>) -> Option<String> {
+    let label = if node.label_comes_from_value() {
+        node.value()
+    } else {
+        node.label()
+    };
+    label.filter(|label| !label.trim().is_empty())
+}
+
+fn rect(node: &AccessKitNode<'_>) -> Option<Rect> {
+    let rect = node.bounding_box()?;
+    Some(Rect::from_min_max(
+        Pos2::new(rect.x0 as f32, rect.y0 as f32),
+        Pos2::new(rect.x1 as f32, rect.y1 as f32),
+    ))
+}
+
+fn is_interactive(node: &AccessKitNode<'_>) -> bool {
+    // Labels are clickable for text selection, which doesn't make them controls.
+    node.supports_action(Action::Focus) || (node.is_clickable() && node.role() != Role::Label)
+}
+
+/// All nodes below `root` that a user could perceive.
+fn visible_nodes<'tree>(root: &Node<'tree>) -> impl Iterator<Item = AccessKitNode<'tree>> {
+    root.children_recursive()
+        .map(|node| node.accesskit_node())
+        .filter(|node| !node.is_hidden())
+}
+
+/// Run the checks that only need the AccessKit tree.
+fn audit_tree(root: &Node<'_>, min_target_size: Vec2) -> Vec<AccessibilityViolation> {
+    let mut violations = Vec::new();
+    let mut sibling_labels: HashMap<Option<NodeId>, HashSet<String>> = HashMap::new();
+
+    for node in visible_nodes(root) {
+        let label = label(&node);
+
+        if node.role() == Role::Image {
+            if label.is_none() {
+                violations.push(AccessibilityViolation::new(
+                    ViolationKind::MissingAltText,
+                    &node,
+                ));
+            }
+        } else if is_interactive(&node) && label.is_none() && node.placeholder().is_none() {
+            violations.push(AccessibilityViolation::new(
+                ViolationKind::MissingLabel,
+                &node,
+            ));
+        }
+
+        if !is_interactive(&node) || node.is_disabled() {
+            continue;
+        }
+
+        if let Some(label) = label {
+            let labels = sibling_labels.entry(node.parent_id()).or_default();
+            if !labels.insert(label) {
+                violations.push(AccessibilityViolation::new(
+                    ViolationKind::DuplicateLabel,
+                    &node,
+                ));
+            }
+        }
+
+        if let Some(size) = rect(&node).map(|rect| rect.size()) {
+            // Allow for rounding, e.g. of text heights:
+            if size.x < min_target_size.x - 0.5 || size.y < min_target_size.y - 0.5 {
+                violations.push(AccessibilityViolation::new(
+                    ViolationKind::SmallTarget {
+                        size,
+                        min_size: min_target_size,
+                    },
+                    &node,
+                ));
+            }
+        }
+
+        if !node.supports_action(Action::Focus) {
+            violations.push(AccessibilityViolation::new(
+                ViolationKind::NotFocusable,
+                &node,
+            ));
+        }
+    }
+
+    violations
+}
+
+/// ## Accessibility audits
+///
+/// The [`Harness`] can check the AccessKit tree of the root viewport for common accessibility problems,
+/// returning them as [`AccessibilityViolation`]s.
+///
+/// ```
+/// # use egui_kittest::{Harness, ViolationKind};
+/// let harness = Harness::new_ui(|ui| {
+///     ui.button("Save");
+///     ui.button("Save");
+/// });
+/// let violations = harness.accessibility_audit();
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].kind, ViolationKind::DuplicateLabel);
+/// ```
+impl<State> Harness<'_, State> {
+    /// Check for interactive nodes without labels, images without alt text,
+    /// duplicate labels in the same container, too small targets and clickable nodes that aren't focusable.
+    ///
+    /// See [`Self::keyboard_audit`] and [`Self::contrast_audit`] for the checks that need to
+    /// interact with or render the app.
+    pub fn accessibility_audit(&self) -> Vec<AccessibilityViolation> {
+        self.accessibility_audit_options(&AuditOptions::default())
+    }
+
+    /// Check for interactive nodes without labels, images without alt text,
+    /// duplicate labels in the same container, too small targets and clickable nodes that aren't focusable.
+    pub fn accessibility_audit_options(
+        &self,
+        options: &AuditOptions,
+    ) -> Vec<AccessibilityViolation> {
+        audit_tree(&self.root(), self.min_target_size(options))
+    }
+
+    /// Panic if [`Self::accessibility_audit`] finds any violations.
+    ///
+    /// # Panics
+    /// Panics with a list of all violations.
+    #[track_caller]
+    pub fn assert_accessible(&self) {
+        let violations = self.accessibility_audit();
+        if !violations.is_empty() {
+            let list: Vec<_> = violations
+                .iter()
+                .map(|violation| format!("  {violation}"))
+                .collect();
+            panic!("Accessibility violations:\n{}", list.join("\n"));
+        }
+    }
+
+    /// Press [`egui::Key::Tab`] until the focus wrapped around and report all focusable nodes
+    /// that were never focused.
+    ///
+    /// Note that this changes the focus and runs the app, just like a user tabbing through it.
+    pub fn keyboard_audit(&mut self) -> Vec<AccessibilityViolation> {
+        let focusable = visible_nodes(&self.root())
+            .filter(|node| node.supports_action(Action::Focus) && !node.is_disabled())
+            .count();
+
+        let mut reached = HashSet::new();
+        // One extra press, so the focus can wrap around once:
+        for _ in 0..=focusable {
+            self.key_press(egui::Key::Tab);
+            self.run();
+            if let Some(node) = visible_nodes(&self.root()).find(|node| node.is_focused()) {
+                reached.insert(node.id());
+            }
+        }
+
+        visible_nodes(&self.root())
+            .filter(|node| {
+                node.supports_action(Action::Focus)
+                    && !node.is_disabled()
+                    && !reached.contains(&node.id())
+            })
+            .map(|node| AccessibilityViolation::new(ViolationKind::Unreachable, &node))
+            .collect()
+    }
+
+    /// Render the app and report text that has too little contrast with its background.
+    ///
+    /// The background of a node is its most common color, and its text color is the one
+    /// with the highest contrast to that, so this may miss text in nodes containing other content.
+    ///
+    /// # Errors
+    /// Returns an error if the rendering fails.
+    #[cfg(any(feature = "wgpu", feature = "snapshot"))]
+    pub fn contrast_audit(
+        &mut self,
+        options: &AuditOptions,
+    ) -> Result<Vec<AccessibilityViolation>, String> {
+        let image = self.render()?;
+        let pixels_per_point = self.ctx.pixels_per_point();
+
+        Ok(visible_nodes(&self.root())
+            .filter(|node| {
+                // Text runs are checked as part of the widget containing them.
+                !matches!(node.role(), Role::Image | Role::TextRun)
+                    && node.children().all(|child| child.role() == Role::TextRun)
+                    && (label(node).is_some() || node.value().is_some_and(|v| !v.is_empty()))
+            })
+            .filter_map(|node| {
+                let ratio = contrast_ratio(&image, rect(&node)? * pixels_per_point)?;
+                (ratio < options.min_contrast_ratio).then(|| {
+                    AccessibilityViolation::new(
+                        ViolationKind::LowContrast {
+                            ratio,
+                            min_ratio: options.min_contrast_ratio,
+                        },
+                        &node,
+                    )
+                })
+            })
+            .collect())
+    }
+
+    fn min_target_size(&self, options: &AuditOptions) -> Vec2 {
+        options
+            .min_target_size
+            .unwrap_or_else(|| Vec2::splat(self.ctx.style().spacing.interact_size.y))
+    }
+}
+
+/// The contrast between the most common color in `rect` and the color contrasting most with it.
+///
+/// Returns `None` if `rect` is empty or has only one color.
+#[cfg(any(feature = "wgpu", feature = "snapshot"))]
+fn contrast_ratio(image: &image::RgbaImage, rect: Rect) -> Option<f32> {
+    let image_rect = Rect::from_min_size(
+        Pos2::ZERO,
+        Vec2::new(image.width() as f32, image.height() as f32),
+    );
+    let rect = rect.intersect(image_rect);
+    if !rect.is_positive() {
+        return None;
+    }
+
+    let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+    for y in rect.min.y.round() as u32..rect.max.y.round() as u32 {
+        for x in rect.min.x.round() as u32..rect.max.x.round() as u32 {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+            *counts.entry([r, g, b]).or_default() += 1;
+        }
+    }
+    if counts.len() < 2 {
+        return None;
+    }
+
+    let background = counts
+        .iter()
+        // Break ties by the color, so that the result doesn't depend on the order of the map:
+        .max_by_key(|(color, count)| (**count, **color))
+        .map(|(color, _)| relative_luminance(*color))?;
+    counts
+        .keys()
+        .map(|color| {
+            let foreground = relative_luminance(*color);
+            let (lighter, darker) = if foreground > background {
+                (foreground, background)
+            } else {
+                (background, foreground)
+            };
+            (lighter + 0.05) / (darker + 0.05)
+        })
+        .max_by(f32::total_cmp)
+}
+
+/// See <https://www.w3.org/TR/WCAG21/#dfn-relative-luminance>.
+#[cfg(any(feature = "wgpu", feature = "snapshot"))]
+fn relative_luminance([r, g, b]: [u8; 3]) -> f32 {
+    use egui::ecolor::linear_f32_from_gamma_u8;
+    0.2126 * linear_f32_from_gamma_u8(r)
+        + 0.7152 * linear_f32_from_gamma_u8(g)
+        + 0.0722 * linear_f32_from_gamma_u8(b)
+}
@@ -0,0 +1,474 @@
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+use egui::epaint::text::GalleyCacheStats;
+use egui::epaint::{Shape, stats::PaintStats};
+
+use crate::Harness;
+
+/// Statistics of a single frame run by [`Harness::benchmark`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// How long [`egui::Context::run`] took, i.e. running the app and laying out the ui.
+    pub layout: Duration,
+
+    /// How long [`egui::Context::tessellate`] took.
+    pub tessellation: Duration,
+
+    /// The number of shapes, including the ones nested in [`Shape::Vec`].
+    pub num_shapes: usize,
+
+    /// The number of clipped primitives after tessellation.
+    pub num_clipped_primitives: usize,
+
+    /// The number of vertices after tessellation.
+    pub num_vertices: usize,
+
+    /// The number of indices after tessellation.
+    pub num_indices: usize,
+
+    /// How often text layouts were found in the galley cache, summed over all passes of the frame.
+    pub galley_cache: GalleyCacheStats,
+
+    /// The number of galleys in the cache at the end of the frame.
+    pub num_galleys_in_cache: usize,
+}
+
+/// Minimum, median, mean and maximum of a timing over all frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    /// The fastest frame.
+    pub min: Duration,
+
+    /// The middle frame when sorted by time. Less noisy than [`Self::mean`].
+    pub median: Duration,
+
+    /// The average over all frames.
+    pub mean: Duration,
+
+    /// The slowest frame.
+    pub max: Duration,
+}
+
+impl TimingStats {
+    fn new(mut durations: Vec<Duration>) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+        durations.sort();
+        Self {
+            min: durations[0],
+            median: durations[durations.len() / 2],
+            mean: durations.iter().sum::<Duration>() / durations.len() as u32,
+            max: durations[durations.len() - 1],
+        }
+    }
+}
+
+impl Display for TimingStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "median {:.1?} (min {:.1?}, mean {:.1?}, max {:.1?})",
+            self.median, self.min, self.mean, self.max
+        )
+    }
+}
+
+/// The result of [`Harness::benchmark`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BenchmarkReport {
+    /// The statistics of each frame, in order.
+    pub frames: Vec<FrameStats>,
+}
+
+impl BenchmarkReport {
+    /// Timings of [`egui::Context::run`].
+    pub fn layout(&self) -> TimingStats {
+        TimingStats::new(self.frames.iter().map(|frame| frame.layout).collect())
+    }
+
+    /// Timings of [`egui::Context::tessellate`].
+    pub fn tessellation(&self) -> TimingStats {
+        TimingStats::new(self.frames.iter().map(|frame| frame.tessellation).collect())
+    }
+
+    /// The time spent in layout and tessellation of all frames.
+    ///
+    /// Use this with [`criterion`](https://docs.rs/criterion)'s `iter_custom`.
+    pub fn total_time(&self) -> Duration {
+        self.frames
+            .iter()
+            .map(|frame| frame.layout + frame.tessellation)
+            .sum()
+    }
+
+    /// The largest number of shapes in a frame.
+    pub fn max_shapes(&self) -> usize {
+        self.max_of(|frame| frame.num_shapes)
+    }
+
+    /// The largest number of vertices in a frame.
+    pub fn max_vertices(&self) -> usize {
+        self.max_of(|frame| frame.num_vertices)
+    }
+
+    /// The largest number of galleys in the cache at the end of a frame.
+    pub fn max_galleys_in_cache(&self) -> usize {
+        self.max_of(|frame| frame.num_galleys_in_cache)
+    }
+
+    /// The fraction of text layouts that were found in the galley cache, over all frames.
+    ///
+    /// This is `1.0` if no text was laid out.
+    pub fn galley_cache_hit_rate(&self) -> f32 {
+        let hits: usize = self
+            .frames
+            .iter()
+            .map(|frame| frame.galley_cache.hits)
+            .sum();
+        let misses: usize = self
+            .frames
+            .iter()
+            .map(|frame| frame.galley_cache.misses)
+            .sum();
+        if hits + misses == 0 {
+            1.0
+        } else {
+            hits as f32 / (hits + misses) as f32
+        }
+    }
+
+    fn max_of(&self, value: impl Fn(&FrameStats) -> usize) -> usize {
+        self.frames.iter().map(value).max().unwrap_or_default()
+    }
+
+    /// Compare the report to the thresholds.
+    pub fn regressions(&self, thresholds: &BenchmarkThresholds) -> Vec<BenchmarkRegression> {
+        let BenchmarkThresholds {
+            max_layout_time,
+            max_tessellation_time,
+            max_shapes,
+            max_vertices,
+            max_galleys_in_cache,
+            min_galley_cache_hit_rate,
+        } = *thresholds;
+
+        let mut regressions = Vec::new();
+        if let Some(max) = max_layout_time {
+            let median = self.layout().median;
+            if median > max {
+                regressions.push(BenchmarkRegression::LayoutTime { median, max });
+            }
+        }
+        if let Some(max) = max_tessellation_time {
+            let median = self.tessellation().median;
+            if median > max {
+                regressions.push(BenchmarkRegression::TessellationTime { median, max });
+            }
+        }
+        if let Some(max) = max_shapes {
+            let count = self.max_shapes();
+            if count > max {
+                regressions.push(BenchmarkRegression::Shapes { count, max });
+            }
+        }
+        if let Some(max) = max_vertices {
+            let count = self.max_vertices();
+            if count > max {
+                regressions.push(BenchmarkRegression::Vertices { count, max });
+            }
+        }
+        if let Some(max) = max_galleys_in_cache {
+            let count = self.max_galleys_in_cache();
+            if count > max {
+                regressions.push(BenchmarkRegression::GalleysInCache { count, max });
+            }
+        }
+        if let Some(min) = min_galley_cache_hit_rate {
+            let rate = self.galley_cache_hit_rate();
+            if rate < min {
+                regressions.push(BenchmarkRegression::GalleyCacheHitRate { rate, min });
+            }
+        }
+        regressions
+    }
+
+    /// Panic if the report exceeds any of the thresholds.
+    ///
+    /// # Panics
+    /// Panics with a list of the regressions, if there are any.
+    #[track_caller]
+    pub fn assert_within(&self, thresholds: &BenchmarkThresholds) {
+        let regressions = self.regressions(thresholds);
+        if !regressions.is_empty() {
+            let list: Vec<_> = regressions
+                .iter()
+                .map(|regression| format!("  {regression}"))
+                .collect();
+            panic!("Benchmark regressions:\n{}\n\n{self}", list.join("\n"));
+        }
+    }
+}
+
+impl Display for BenchmarkReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} frames", self.frames.len())?;
+        writeln!(f, "Layout:       {}", self.layout())?;
+        writeln!(f, "Tessellation: {}", self.tessellation())?;
+        writeln!(f, "Shapes:       {}", self.max_shapes())?;
+        writeln!(f, "Vertices:     {}", self.max_vertices())?;
+        writeln!(
+            f,
+            "Galley cache: {} galleys, {:.1}% hits",
+            self.max_galleys_in_cache(),
+            self.galley_cache_hit_rate() * 100.0
+        )
+    }
+}
+
+/// Limits for [`BenchmarkReport::regressions`]. All limits are optional.
+///
+/// Timings are compared to the median over all frames, counts to the maximum.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BenchmarkThresholds {
+    /// The maximum median time of [`egui::Context::run`].
+    pub max_layout_time: Option<Duration>,
+
+    /// The maximum median time of [`egui::Context::tessellate`].
+    pub max_tessellation_time: Option<Duration>,
+
+    /// The maximum number of shapes in a frame.
+    pub max_shapes: Option<usize>,
+
+    /// The maximum number of vertices in a frame.
+    pub max_vertices: Option<usize>,
+
+    /// The maximum number of galleys in the cache at the end of a frame.
+    pub max_galleys_in_cache: Option<usize>,
+
+    /// The minimum fraction of text layouts that should be found in the galley cache.
+    pub min_galley_cache_hit_rate: Option<f32>,
+}
+
+impl BenchmarkThresholds {
+    /// Create [`BenchmarkThresholds`] without any limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set [`Self::max_layout_time`].
+    #[inline]
+    pub fn max_layout_time(mut self, max_layout_time: Duration) -> Self {
+        self.max_layout_time = Some(max_layout_time);
+        self
+    }
+
+    /// Set [`Self::max_tessellation_time`].
+    #[inline]
+    pub fn max_tessellation_time(mut self, max_tessellation_time: Duration) -> Self {
+        self.max_tessellation_time = Some(max_tessellation_time);
+        self
+    }
+
+    /// Set [`Self::max_shapes`].
+    #[inline]
+    pub fn max_shapes(mut self, max_shapes: usize) -> Self {
+        self.max_shapes = Some(max_shapes);
+        self
+    }
+
+    /// Set [`Self::max_vertices`].
+    #[inline]
+    pub fn max_vertices(mut self, max_vertices: usize) -> Self {
+        self.max_vertices = Some(max_vertices);
+        self
+    }
+
+    /// Set [`Self::max_galleys_in_cache`].
+    #[inline]
+    pub fn max_galleys_in_cache(mut self, max_galleys_in_cache: usize) -> Self {
+        self.max_galleys_in_cache = Some(max_galleys_in_cache);
+        self
+    }
+
+    /// Set [`Self::min_galley_cache_hit_rate`].
+    #[inline]
+    pub fn min_galley_cache_hit_rate(mut self, min_galley_cache_hit_rate: f32) -> Self {
+        self.min_galley_cache_hit_rate = Some(min_galley_cache_hit_rate);
+        self
+    }
+}
+
+/// A value of a [`BenchmarkReport`] that exceeds its [`BenchmarkThresholds`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BenchmarkRegression {
+    /// See [`BenchmarkThresholds::max_layout_time`].
+    LayoutTime { median: Duration, max: Duration },
+
+    /// See [`BenchmarkThresholds::max_tessellation_time`].
+    TessellationTime { median: Duration, max: Duration },
+
+    /// See [`BenchmarkThresholds::max_shapes`].
+    Shapes { count: usize, max: usize },
+
+    /// See [`BenchmarkThresholds::max_vertices`].
+    Vertices { count: usize, max: usize },
+
+    /// See [`BenchmarkThresholds::max_galleys_in_cache`].
+    GalleysInCache { count: usize, max: usize },
+
+    /// See [`BenchmarkThresholds::min_galley_cache_hit_rate`].
+    GalleyCacheHitRate { rate: f32, min: f32 },
+}
+
+impl Display for BenchmarkRegression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LayoutTime { median, max } => {
+                write!(f, "Layout took {median:.1?}, more than {max:.1?}")
+            }
+            Self::TessellationTime { median, max } => {
+                write!(f, "Tessellation took {median:.1?}, more than {max:.1?}")
+            }
+            Self::Shapes { count, max } => write!(f, "{count} shapes, more than {max}"),
+            Self::Vertices { count, max } => write!(f, "{count} vertices, more than {max}"),
+            Self::GalleysInCache { count, max } => {
+                write!(f, "{count} galleys in the cache, more than {max}")
+            }
+            Self::GalleyCacheHitRate { rate, min } => write!(
+                f,
+                "Galley cache hit rate {:.1}% is lower than {:.1}%",
+                rate * 100.0,
+                min * 100.0
+            ),
+        }
+    }
+}
+
+/// The number of shapes, including the ones nested in [`Shape::Vec`].
+fn count_shapes<'a>(shapes: impl Iterator<Item = &'a Shape>) -> usize {
+    shapes
+        .map(|shape| match shape {
+            Shape::Vec(shapes) => 1 + count_shapes(shapes.iter()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// ## Benchmarking
+///
+/// [`Harness::benchmark`] runs the app for a number of frames and measures each of them.
+/// Check the results against [`BenchmarkThresholds`] in a test:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use egui_kittest::{BenchmarkThresholds, Harness};
+/// let mut harness = Harness::new_ui(|ui| {
+///     for i in 0..100 {
+///         ui.label(format!("Label {i}"));
+///     }
+/// });
+/// let report = harness.benchmark(10);
+/// report.assert_within(
+///     &BenchmarkThresholds::new()
+///         .max_layout_time(Duration::from_secs(1))
+///         .max_shapes(200)
+///         .min_galley_cache_hit_rate(0.9),
+/// );
+/// ```
+///
+/// Or measure the time with [`criterion`](https://docs.rs/criterion):
+///
+/// ```ignore
+/// c.bench_function("my_ui", |b| {
+///     b.iter_custom(|iters| harness.benchmark(iters as usize).total_time());
+/// });
+/// ```
+impl<State> Harness<'_, State> {
+    /// Run the app for `frames` frames and measure each of them.
+    ///
+    /// Each frame is a single [`Harness::step`] without new input; queue and handle any
+    /// input beforehand to benchmark a specific state of the app.
+    /// The frames are also tessellated, like an integration would do.
+    pub fn benchmark(&mut self, frames: usize) -> BenchmarkReport {
+        FrameGalleyCacheStats::register(&self.ctx);
+        let frames = (0..frames).map(|_| self.benchmark_frame()).collect();
+        BenchmarkReport { frames }
+    }
+
+    fn benchmark_frame(&mut self) -> FrameStats {
+        FrameGalleyCacheStats::reset(&self.ctx);
+        self._step(false);
+
+        let shapes = self.output.shapes.clone();
+        let num_shapes = count_shapes(shapes.iter().map(|clipped| &clipped.shape));
+        let start = Instant::now();
+        let primitives = self.ctx.tessellate(shapes, self.output.pixels_per_point);
+        let tessellation = start.elapsed();
+
+        let paint_stats = PaintStats::default().with_clipped_primitives(&primitives);
+        let galley_cache = FrameGalleyCacheStats::load(&self.ctx);
+        let num_galleys_in_cache = self.ctx.fonts(|fonts| fonts.num_galleys_in_cache());
+
+        FrameStats {
+            layout: self.last_run_duration,
+            tessellation,
+            num_shapes,
+            num_clipped_primitives: paint_stats.clipped_primitives.num_elements(),
+            num_vertices: paint_stats.vertices.num_elements(),
+            num_indices: paint_stats.indices.num_elements(),
+            galley_cache,
+            num_galleys_in_cache,
+        }
+    }
+}
+
+/// The galley cache stats of all passes of a frame.
+///
+/// [`egui::epaint::Fonts::galley_cache_stats`] is reset at the start of each pass,
+/// so we add it up at the end of each of them.
+#[derive(Clone, Copy, Default)]
+struct FrameGalleyCacheStats {
+    registered: bool,
+    stats: GalleyCacheStats,
+}
+
+impl FrameGalleyCacheStats {
+    fn id() -> egui::Id {
+        egui::Id::new("egui_kittest::FrameGalleyCacheStats")
+    }
+
+    /// Start adding up the stats of each pass, unless we already do.
+    fn register(ctx: &egui::Context) {
+        let registered = ctx.data_mut(|data| {
+            let state = data.get_temp_mut_or_default::<Self>(Self::id());
+            std::mem::replace(&mut state.registered, true)
+        });
+        if !registered {
+            ctx.on_end_pass("FrameGalleyCacheStats", std::sync::Arc::new(Self::end_pass));
+        }
+    }
+
+    fn end_pass(ctx: &egui::Context) {
+        let pass = ctx.fonts(|fonts| fonts.galley_cache_stats());
+        ctx.data_mut(|data| {
+            let stats = &mut data.get_temp_mut_or_default::<Self>(Self::id()).stats;
+            stats.hits += pass.hits;
+            stats.misses += pass.misses;
+        });
+    }
+
+    fn reset(ctx: &egui::Context) {
+        ctx.data_mut(|data| {
+            data.get_temp_mut_or_default::<Self>(Self::id()).stats = GalleyCacheStats::default();
+        });
+    }
+
+    fn load(ctx: &egui::Context) -> GalleyCacheStats {
+        ctx.data(|data| data.get_temp::<Self>(Self::id()))
+            .unwrap_or_default()
+            .stats
+    }
+}
@@ -13,6 +13,7 @@ pub struct HarnessBuilder<State = ()> {
     pub(crate) state: PhantomData<State>,
     pub(crate) renderer: Box<dyn TestRenderer>,
     pub(crate) wait_for_pending_images: bool,
+    pub(crate) embed_viewports: bool,
 }
 
 impl<State> Default for HarnessBuilder<State> {
@@ -26,6 +27,7 @@ impl<State> Default for HarnessBuilder<State> {
             max_steps: 4,
             step_dt: 1.0 / 4.0,
             wait_for_pending_images: true,
+            embed_viewports: true,
         }
     }
 }
@@ -87,6 +89,19 @@ impl<State> HarnessBuilder<State> {
         self
     }
 
+    /// Should child viewports be embedded in the root viewport?
+    ///
+    /// If `false`, viewports shown with [`egui::Context::show_viewport_immediate`] and
+    /// [`egui::Context::show_viewport_deferred`] are simulated as separate native windows.
+    /// Use e.g. [`Harness::viewport_ids`] and [`Harness::viewport_root`] to test them.
+    ///
+    /// Default: `true` (see [`egui::Context::embed_viewports`])
+    #[inline]
+    pub fn with_embed_viewports(mut self, embed_viewports: bool) -> Self {
+        self.embed_viewports = embed_viewports;
+        self
+    }
+
     /// Set the [`TestRenderer`] to use for rendering.
     ///
     /// By default, a [`LazyRenderer`] is used.
@@ -0,0 +1,658 @@
+use std::fmt::{Display, Formatter};
+use std::panic::AssertUnwindSafe;
+
+use egui::epaint::Shape;
+use egui::{Event, Key, Modifiers, MouseWheelUnit, PointerButton, Pos2, Rect, Vec2, pos2, vec2};
+use kittest::NodeT as _;
+
+use crate::Harness;
+
+/// Options for [`fuzz`].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct FuzzOptions {
+    /// The seed of the first run. Each following run uses the next seed.
+    /// The default is `0`.
+    pub seed: u64,
+
+    /// How many action sequences to try, each on a new [`Harness`].
+    /// The default is `10`.
+    pub runs: u64,
+
+    /// How many actions each run performs.
+    /// The default is `50`.
+    pub steps: usize,
+
+    /// Should a failing sequence be reduced to the actions that are needed to reproduce the issue?
+    /// The default is `true`.
+    pub shrink: bool,
+
+    /// Report interactive widgets on the screen that are entirely outside their clip rect,
+    /// so they can be neither seen nor clicked, e.g. because they overflow their panel or window.
+    ///
+    /// Widgets scrolled out of a [`egui::ScrollArea`] are outside their clip rect too,
+    /// so this is only useful for UIs without scroll areas.
+    /// The default is `false`.
+    pub check_outside_clip_rect: bool,
+
+    /// Report interactive widgets that are entirely outside the screen.
+    ///
+    /// This is useful for UIs that should adapt to any screen size, but widgets
+    /// in scroll areas or too small screens legitimately end up outside the screen.
+    /// The default is `false`.
+    pub check_outside_screen: bool,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            runs: 10,
+            steps: 50,
+            shrink: true,
+            check_outside_clip_rect: false,
+            check_outside_screen: false,
+        }
+    }
+}
+
+impl FuzzOptions {
+    /// Create a new [`FuzzOptions`] with the default values.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Change the seed of the first run.
+    #[inline]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Change how many action sequences are tried.
+    #[inline]
+    pub fn runs(mut self, runs: u64) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Change how many actions each run performs.
+    #[inline]
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Change whether failing sequences are shrunk.
+    #[inline]
+    pub fn shrink(mut self, shrink: bool) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    /// Change whether interactive widgets outside their clip rect are reported.
+    #[inline]
+    pub fn check_outside_clip_rect(mut self, check_outside_clip_rect: bool) -> Self {
+        self.check_outside_clip_rect = check_outside_clip_rect;
+        self
+    }
+
+    /// Change whether interactive widgets outside the screen are reported.
+    #[inline]
+    pub fn check_outside_screen(mut self, check_outside_screen: bool) -> Self {
+        self.check_outside_screen = check_outside_screen;
+        self
+    }
+}
+
+/// A single user action generated by [`fuzz`].
+///
+/// Each action is followed by [`Harness::step`]. Use [`FuzzAction::apply`] to replay a
+/// sequence from a [`FuzzFailure`] in a regular test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FuzzAction {
+    /// Move the pointer without pressing a button.
+    PointerMove(Pos2),
+
+    /// Press and release a button at the same position.
+    Click { pos: Pos2, button: PointerButton },
+
+    /// Press the primary button at `from`, move to `to` and release it there.
+    Drag { from: Pos2, to: Pos2 },
+
+    /// Move the pointer to `pos` and scroll by `delta` points.
+    Scroll { pos: Pos2, delta: Vec2 },
+
+    /// Press a key, see [`Harness::key_press_modifiers`].
+    Key { key: Key, modifiers: Modifiers },
+
+    /// Type some text.
+    Text(String),
+
+    /// Change the size of the screen, see [`Harness::set_size`].
+    Resize(Vec2),
+}
+
+impl FuzzAction {
+    /// Queue the events of this action on the harness. Call [`Harness::step`] afterwards.
+    pub fn apply<State>(&self, harness: &mut Harness<'_, State>) {
+        match self {
+            Self::PointerMove(pos) => harness.event(Event::PointerMoved(*pos)),
+            Self::Click { pos, button } => {
+                harness.event(Event::PointerMoved(*pos));
+                for pressed in [true, false] {
+                    harness.event(Event::PointerButton {
+                        pos: *pos,
+                        button: *button,
+                        pressed,
+                        modifiers: Modifiers::default(),
+                    });
+                }
+            }
+            Self::Drag { from, to } => {
+                harness.event(Event::PointerMoved(*from));
+                harness.event(Event::PointerButton {
+                    pos: *from,
+                    button: PointerButton::Primary,
+                    pressed: true,
+                    modifiers: Modifiers::default(),
+                });
+                harness.event(Event::PointerMoved(*to));
+                harness.event(Event::PointerButton {
+                    pos: *to,
+                    button: PointerButton::Primary,
+                    pressed: false,
+                    modifiers: Modifiers::default(),
+                });
+            }
+            Self::Scroll { pos, delta } => {
+                harness.event(Event::PointerMoved(*pos));
+                harness.event(Event::MouseWheel {
+                    unit: MouseWheelUnit::Point,
+                    delta: *delta,
+                    modifiers: Modifiers::default(),
+                });
+            }
+            Self::Key { key, modifiers } => harness.key_press_modifiers(*modifiers, *key),
+            Self::Text(text) => harness.event(Event::Text(text.clone())),
+            Self::Resize(size) => {
+                harness.set_size(*size);
+            }
+        }
+    }
+}
+
+/// A broken invariant found by [`fuzz`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FuzzIssue {
+    /// The app panicked, with this message.
+    Panic(String),
+
+    /// Two widgets used the same [`egui::Id`], as reported by [`egui::Context::id_clashes`].
+    IdClash(String),
+
+    /// A shape or node has a rect with NaN coordinates.
+    NanRect { what: String },
+
+    /// An interactive widget is entirely outside its clip rect.
+    /// See [`FuzzOptions::check_outside_clip_rect`].
+    OutsideClipRect { label: Option<String>, rect: Rect },
+
+    /// An interactive node is entirely outside the screen. See [`FuzzOptions::check_outside_screen`].
+    OutsideScreen { label: Option<String>, rect: Rect },
+}
+
+impl Display for FuzzIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Panic(message) => write!(f, "Panic: {message}"),
+            Self::IdClash(message) => write!(f, "Id clash: {message}"),
+            Self::NanRect { what } => write!(f, "NaN rect of {what}"),
+            Self::OutsideClipRect { label, rect } => {
+                write!(
+                    f,
+                    "Interactive widget {label:?} at {rect} is outside its clip rect"
+                )
+            }
+            Self::OutsideScreen { label, rect } => {
+                write!(
+                    f,
+                    "Interactive node {label:?} at {rect} is outside the screen"
+                )
+            }
+        }
+    }
+}
+
+/// A sequence of actions that breaks an invariant, returned by [`fuzz`].
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+    /// The seed of the run that found the issue.
+    pub seed: u64,
+
+    /// The actions that lead to the issue, shrunk if [`FuzzOptions::shrink`] is set.
+    pub actions: Vec<FuzzAction>,
+
+    /// The broken invariant.
+    pub issue: FuzzIssue,
+}
+
+impl Display for FuzzFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} (seed {})", self.issue, self.seed)?;
+        writeln!(f, "After {} action(s):", self.actions.len())?;
+        for action in &self.actions {
+            writeln!(f, "  {action:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FuzzFailure {}
+
+/// Feed random, but reproducible, sequences of user actions into an app and check for broken invariants.
+///
+/// After every [`Harness::step`], this checks for panics, [`egui::Id`] clashes and NaN rects.
+/// Interactive widgets outside their clip rect or outside the screen are reported
+/// if [`FuzzOptions::check_outside_clip_rect`] or [`FuzzOptions::check_outside_screen`] is set.
+/// Catching panics needs `panic = "unwind"`, as used by `cargo test`.
+///
+/// `make_harness` is called for every run (and for every attempt to shrink a failing sequence),
+/// so it should create the app from scratch.
+/// Clicks, drags and scrolls mostly target the interactive nodes of the AccessKit tree.
+///
+/// ```
+/// # use egui_kittest::{FuzzOptions, Harness, fuzz};
+/// let result = fuzz(&FuzzOptions::new().runs(2), || {
+///     Harness::new_ui_state(
+///         |ui, text: &mut String| {
+///             ui.text_edit_singleline(text);
+///             if ui.button("Clear").clicked() {
+///                 text.clear();
+///             }
+///         },
+///         String::new(),
+///     )
+/// });
+/// assert!(result.is_ok(), "{}", result.unwrap_err());
+/// ```
+///
+/// # Errors
+/// Returns the first issue found, with the actions that lead to it.
+pub fn fuzz<'a, State>(
+    options: &FuzzOptions,
+    mut make_harness: impl FnMut() -> Harness<'a, State>,
+) -> Result<(), FuzzFailure> {
+    for run in 0..options.runs {
+        let seed = options.seed.wrapping_add(run);
+        let mut rng = Rng::new(seed);
+        let mut harness = match new_harness(&mut make_harness, options) {
+            Ok(harness) => harness,
+            Err(issue) => {
+                return Err(FuzzFailure {
+                    seed,
+                    actions: Vec::new(),
+                    issue,
+                });
+            }
+        };
+        let mut actions = Vec::new();
+
+        let mut issue = None;
+        while issue.is_none() && actions.len() < options.steps {
+            let action = random_action(&mut rng, &harness);
+            issue = perform(&mut harness, &action, options);
+            actions.push(action);
+        }
+
+        if let Some(issue) = issue {
+            let (actions, issue) = if options.shrink {
+                shrink(&mut make_harness, options, actions, issue)
+            } else {
+                (actions, issue)
+            };
+            return Err(FuzzFailure {
+                seed,
+                actions,
+                issue,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Create a harness and run its first frame, returning the first broken invariant instead.
+fn new_harness<'a, State>(
+    make_harness: &mut impl FnMut() -> Harness<'a, State>,
+    options: &FuzzOptions,
+) -> Result<Harness<'a, State>, FuzzIssue> {
+    let harness = catch_panic(|| {
+        let mut harness = make_harness();
+        harness
+            .ctx
+            .options_mut(|options| options.warn_on_id_clash = true);
+        harness.step();
+        harness
+    })?;
+    match check_invariants(&harness, options) {
+        Some(issue) => Err(issue),
+        None => Ok(harness),
+    }
+}
+
+/// Apply the action and step the harness, returning the first broken invariant.
+fn perform<State>(
+    harness: &mut Harness<'_, State>,
+    action: &FuzzAction,
+    options: &FuzzOptions,
+) -> Option<FuzzIssue> {
+    match catch_panic(|| {
+        action.apply(harness);
+        harness.step();
+    }) {
+        Ok(()) => check_invariants(harness, options),
+        Err(issue) => Some(issue),
+    }
+}
+
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, FuzzIssue> {
+    #[expect(clippy::disallowed_methods)] // Tests are compiled with `panic = "unwind"`
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| (*message).to_owned())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Unknown panic payload".to_owned());
+        FuzzIssue::Panic(message)
+    })
+}
+
+/// Replay the actions on a new harness, returning the first broken invariant.
+fn replay<'a, State>(
+    make_harness: &mut impl FnMut() -> Harness<'a, State>,
+    options: &FuzzOptions,
+    actions: &[FuzzAction],
+) -> Option<FuzzIssue> {
+    match new_harness(make_harness, options) {
+        Ok(mut harness) => actions
+            .iter()
+            .find_map(|action| perform(&mut harness, action, options)),
+        Err(issue) => Some(issue),
+    }
+}
+
+/// Remove chunks of actions, from large to small, as long as the same kind of issue still occurs.
+fn shrink<'a, State>(
+    make_harness: &mut impl FnMut() -> Harness<'a, State>,
+    options: &FuzzOptions,
+    mut actions: Vec<FuzzAction>,
+    mut issue: FuzzIssue,
+) -> (Vec<FuzzAction>, FuzzIssue) {
+    let mut chunk_size = actions.len().div_ceil(2);
+    while chunk_size > 0 {
+        let mut start = 0;
+        while start < actions.len() {
+            let end = (start + chunk_size).min(actions.len());
+            let candidate: Vec<_> = actions[..start]
+                .iter()
+                .chain(&actions[end..])
+                .cloned()
+                .collect();
+            match replay(make_harness, options, &candidate) {
+                Some(new_issue)
+                    if std::mem::discriminant(&new_issue) == std::mem::discriminant(&issue) =>
+                {
+                    actions = candidate;
+                    issue = new_issue;
+                }
+                _ => start += chunk_size,
+            }
+        }
+        chunk_size /= 2;
+    }
+    (actions, issue)
+}
+
+fn check_invariants<State>(
+    harness: &Harness<'_, State>,
+    options: &FuzzOptions,
+) -> Option<FuzzIssue> {
+    if let Some(clash) = harness.ctx.id_clashes().into_iter().next() {
+        return Some(FuzzIssue::IdClash(clash));
+    }
+
+    for clipped in &harness.output().shapes {
+        if clipped.clip_rect.any_nan() {
+            return Some(FuzzIssue::NanRect {
+                what: "a clip rect".to_owned(),
+            });
+        }
+        if let Some(issue) = check_shape(&clipped.shape) {
+            return Some(issue);
+        }
+    }
+
+    if options.check_outside_clip_rect {
+        if let Some(issue) = check_clip_rects(harness) {
+            return Some(issue);
+        }
+    }
+
+    let screen_rect = harness.ctx.screen_rect();
+    for node in harness.root().children_recursive() {
+        let node = node.accesskit_node();
+        let Some(bounds) = node.bounding_box() else {
+            continue;
+        };
+        let rect = Rect::from_min_max(
+            pos2(bounds.x0 as f32, bounds.y0 as f32),
+            pos2(bounds.x1 as f32, bounds.y1 as f32),
+        );
+        let label = || node.label().or_else(|| node.value());
+        if rect.any_nan() {
+            return Some(FuzzIssue::NanRect {
+                what: format!("{:?} node {:?}", node.role(), label()),
+            });
+        }
+        if options.check_outside_screen
+            && is_interactive(&node)
+            && !node.is_hidden()
+            && !rect.intersects(screen_rect)
+        {
+            return Some(FuzzIssue::OutsideScreen {
+                label: label(),
+                rect,
+            });
+        }
+    }
+    None
+}
+
+/// Find an interactive widget of the last pass that is on screen, but was clipped away entirely.
+fn check_clip_rects<State>(harness: &Harness<'_, State>) -> Option<FuzzIssue> {
+    let screen_rect = harness.ctx.screen_rect();
+    let (id, rect) = harness.ctx.viewport(|viewport| {
+        viewport
+            .prev_pass
+            .widgets
+            .layers()
+            .flat_map(|(_, widgets)| widgets)
+            .find(|widget| {
+                (widget.sense.interactive() || widget.sense.is_focusable())
+                    && widget.enabled
+                    && widget.rect.is_positive()
+                    && widget.rect.intersects(screen_rect)
+                    // The interact rect is the part of the rect inside the clip rect:
+                    && !widget.interact_rect.is_positive()
+            })
+            .map(|widget| (widget.id, widget.rect))
+    })?;
+    let label = harness
+        .root()
+        .children_recursive()
+        .map(|node| node.accesskit_node())
+        .find(|node| node.id().0 == id.value())
+        .and_then(|node| node.label().or_else(|| node.value()));
+    Some(FuzzIssue::OutsideClipRect { label, rect })
+}
+
+fn check_shape(shape: &Shape) -> Option<FuzzIssue> {
+    let nan_rect = |what: String| Some(FuzzIssue::NanRect { what });
+    match shape {
+        Shape::Vec(shapes) => shapes.iter().find_map(check_shape),
+        Shape::Text(text) => {
+            let galley_text = text.galley.text();
+            if text.pos.any_nan() || text.galley.rect.any_nan() {
+                // Empty galleys have no mesh bounds, so don't use `visual_bounding_rect` here.
+                nan_rect(format!("the text {galley_text:?}"))
+            } else {
+                None
+            }
+        }
+        _ => {
+            let rect = shape.visual_bounding_rect();
+            if rect.any_nan() {
+                nan_rect(format!("a shape at {rect}"))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn is_interactive(node: &kittest::AccessKitNode<'_>) -> bool {
+    node.supports_action(egui::accesskit::Action::Focus)
+        || node.supports_action(egui::accesskit::Action::Click)
+}
+
+/// The rects of the interactive nodes that are at least partly on screen.
+fn targets<State>(harness: &Harness<'_, State>) -> Vec<Rect> {
+    let screen_rect = harness.ctx.screen_rect();
+    harness
+        .root()
+        .children_recursive()
+        .map(|node| node.accesskit_node())
+        .filter(|node| is_interactive(node) && !node.is_hidden() && !node.is_disabled())
+        .filter_map(|node| {
+            let bounds = node.bounding_box()?;
+            let rect = Rect::from_min_max(
+                pos2(bounds.x0 as f32, bounds.y0 as f32),
+                pos2(bounds.x1 as f32, bounds.y1 as f32),
+            );
+            let rect = rect.intersect(screen_rect);
+            rect.is_positive().then_some(rect)
+        })
+        .collect()
+}
+
+const KEYS: &[Key] = &[
+    Key::Tab,
+    Key::Enter,
+    Key::Escape,
+    Key::Space,
+    Key::Backspace,
+    Key::Delete,
+    Key::ArrowUp,
+    Key::ArrowDown,
+    Key::ArrowLeft,
+    Key::ArrowRight,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::A,
+    Key::Z,
+];
+
+const MODIFIERS: &[Modifiers] = &[
+    Modifiers::NONE,
+    Modifiers::NONE,
+    Modifiers::SHIFT,
+    Modifiers::COMMAND,
+    Modifiers::ALT,
+];
+
+const TEXT: &[&str] = &["a", "Z", "0", " ", "-", "é", "🦀", "\t", "\n", "ab c"];
+
+fn random_action<State>(rng: &mut Rng, harness: &Harness<'_, State>) -> FuzzAction {
+    let screen_rect = harness.ctx.screen_rect();
+    let targets = targets(harness);
+    let random_pos = |rng: &mut Rng| {
+        // Mostly aim for widgets, but sometimes hit the empty space between them:
+        let rect = if targets.is_empty() || rng.below(5) == 0 {
+            screen_rect
+        } else {
+            *rng.pick(&targets)
+        };
+        pos2(
+            rng.range(rect.min.x, rect.max.x),
+            rng.range(rect.min.y, rect.max.y),
+        )
+    };
+
+    match rng.below(20) {
+        0..=6 => FuzzAction::Click {
+            pos: random_pos(rng),
+            button: *rng.pick(&[
+                PointerButton::Primary,
+                PointerButton::Primary,
+                PointerButton::Primary,
+                PointerButton::Secondary,
+            ]),
+        },
+        7..=8 => FuzzAction::PointerMove(random_pos(rng)),
+        9..=10 => FuzzAction::Drag {
+            from: random_pos(rng),
+            to: random_pos(rng),
+        },
+        11..=12 => FuzzAction::Scroll {
+            pos: random_pos(rng),
+            delta: vec2(rng.range(-100.0, 100.0), rng.range(-200.0, 200.0)),
+        },
+        13..=15 => FuzzAction::Key {
+            key: *rng.pick(KEYS),
+            modifiers: *rng.pick(MODIFIERS),
+        },
+        16..=18 => {
+            let len = 1 + rng.below(4);
+            FuzzAction::Text((0..len).map(|_| *rng.pick(TEXT)).collect())
+        }
+        _ => FuzzAction::Resize(vec2(
+            rng.range(1.0, 1000.0).round(),
+            rng.range(1.0, 800.0).round(),
+        )),
+    }
+}
+
+/// A small deterministic random number generator ([SplitMix64](https://prng.di.unimi.it/splitmix64.c)),
+/// so the same seed always gives the same actions.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A random number in `min..max`.
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        let t = (self.next_u64() >> 40) as f32 / (1_u64 << 24) as f32;
+        min + t * (max - min)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
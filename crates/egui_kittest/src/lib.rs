@@ -6,30 +6,47 @@
 mod builder;
 #[cfg(feature = "snapshot")]
 mod snapshot;
+#[cfg(feature = "snapshot")]
+mod snapshot_comparison;
 
 #[cfg(feature = "snapshot")]
 pub use snapshot::*;
+#[cfg(feature = "snapshot")]
+pub use snapshot_comparison::{SnapshotComparison, SnapshotRegion};
+use std::cell::RefCell;
 use std::fmt::{Debug, Display, Formatter};
-use std::time::Duration;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 mod app_kind;
+mod audit;
+mod benchmark;
+mod fuzz;
 mod node;
+mod platform;
 mod renderer;
 #[cfg(feature = "wgpu")]
 mod texture_to_image;
+mod viewport;
 #[cfg(feature = "wgpu")]
 pub mod wgpu;
 
 pub use kittest;
 
 use crate::app_kind::AppKind;
+use crate::platform::PlatformState;
+use crate::viewport::{ChildViewport, ImmediateViewports};
 
+pub use audit::*;
+pub use benchmark::*;
 pub use builder::*;
+pub use fuzz::*;
 pub use node::*;
+pub use platform::PlatformRequest;
 pub use renderer::*;
 
 use egui::style::ScrollAnimation;
-use egui::{Key, Modifiers, Pos2, Rect, RepaintCause, Vec2, ViewportId};
+use egui::{Key, Modifiers, Pos2, Rect, RepaintCause, Vec2, ViewportId, ViewportSimulator as _};
 use kittest::Queryable;
 
 #[derive(Debug, Clone)]
@@ -73,6 +90,14 @@ pub struct Harness<'a, State = ()> {
     step_dt: f32,
     wait_for_pending_images: bool,
     queued_events: EventQueue,
+    platform: PlatformState,
+
+    /// How long the last [`egui::Context::run`] of the root viewport took.
+    last_run_duration: Duration,
+
+    /// The simulated child viewports, if [`HarnessBuilder::with_embed_viewports`] is `false`.
+    viewports: Vec<ChildViewport>,
+    immediate_viewports: Rc<RefCell<ImmediateViewports>>,
 }
 
 impl<State> Debug for Harness<'_, State> {
@@ -97,9 +122,11 @@ impl<'a, State> Harness<'a, State> {
             state: _,
             mut renderer,
             wait_for_pending_images,
+            embed_viewports,
         } = builder;
         let ctx = ctx.unwrap_or_default();
         ctx.set_theme(theme);
+        ctx.set_embed_viewports(embed_viewports);
         ctx.enable_accesskit();
         ctx.all_styles_mut(|style| {
             // Disable cursor blinking so it doesn't interfere with snapshots
@@ -116,6 +143,16 @@ impl<'a, State> Harness<'a, State> {
 
         let mut response = None;
 
+        let immediate_viewports = Rc::<RefCell<ImmediateViewports>>::default();
+        if !embed_viewports {
+            immediate_viewports.borrow_mut().new_viewport_input = egui::RawInput {
+                viewports: input.viewports.clone(),
+                predicted_dt: step_dt,
+                ..Default::default()
+            };
+            viewport::set_immediate_viewport_renderer(&immediate_viewports);
+        }
+
         // We need to run egui for a single frame so that the AccessKit state can be initialized
         // and users can immediately start querying for widgets.
         let mut output = ctx.run(input.clone(), |ctx| {
@@ -123,6 +160,8 @@ impl<'a, State> Harness<'a, State> {
         });
 
         renderer.handle_delta(&output.textures_delta);
+        let mut platform = PlatformState::default();
+        platform.record(ViewportId::ROOT, &output);
 
         let mut harness = Self {
             app,
@@ -143,7 +182,12 @@ impl<'a, State> Harness<'a, State> {
             step_dt,
             wait_for_pending_images,
             queued_events: Default::default(),
+            platform,
+            last_run_duration: Duration::ZERO,
+            viewports: Vec::new(),
+            immediate_viewports,
         };
+        harness.step_child_viewports();
         // Run the harness until it is stable, ensuring that all Areas are shown and animations are done
         harness.run_ok();
         harness
@@ -237,31 +281,52 @@ impl<'a, State> Harness<'a, State> {
     /// Run a frame for each queued event (or a single frame if there are no events).
     /// This will call the app closure with each queued event and
     /// update the Harness.
+    ///
+    /// Events queued for child viewports (see [`Harness::viewport_root`]) are handled after
+    /// the events of the root viewport.
     pub fn step(&mut self) {
-        let events = std::mem::take(&mut *self.queued_events.lock());
-        if events.is_empty() {
+        let viewport_events: Vec<_> = std::iter::once((ViewportId::ROOT, &self.queued_events))
+            .chain(
+                self.viewports
+                    .iter()
+                    .map(|viewport| (viewport.id, &viewport.queued_events)),
+            )
+            .map(|(id, queue)| (id, std::mem::take(&mut *queue.lock())))
+            .filter(|(_, events)| !events.is_empty())
+            .collect();
+        if viewport_events.is_empty() {
             self._step(false);
         }
-        for event in events {
-            match event {
-                EventType::Event(event) => {
-                    self.input.events.push(event);
-                }
-                EventType::Modifiers(modifiers) => {
-                    self.input.modifiers = modifiers;
+        for (viewport_id, events) in viewport_events {
+            let mut events = events.into_iter().peekable();
+            while let Some(event) = events.next() {
+                let event = self.platform.translate_clipboard_shortcut(event);
+                let Some(input) = self.viewport_input_mut(viewport_id) else {
+                    break; // The viewport was closed
+                };
+                // Files stop hovering in the same frame as they are dropped:
+                let same_frame_as_next = matches!(event, EventType::HoveredFiles(_))
+                    && matches!(events.peek(), Some(EventType::DroppedFiles(_)));
+                event.apply(input);
+                if !same_frame_as_next {
+                    self._step(false);
                 }
             }
-            self._step(false);
         }
     }
 
     /// Run a single step. This will not process any events.
     fn _step(&mut self, sizing_pass: bool) {
-        self.input.predicted_dt = self.step_dt;
+        let input = self.take_viewport_input(ViewportId::ROOT);
+        if !self.ctx.embed_viewports() {
+            self.prepare_immediate_viewports();
+        }
 
-        let mut output = self.ctx.run(self.input.take(), |ctx| {
+        let start = Instant::now();
+        let mut output = self.ctx.run(input, |ctx| {
             self.response = self.app.run(ctx, &mut self.state, sizing_pass);
         });
+        self.last_run_duration = start.elapsed();
         self.kittest.update(
             output
                 .platform_output
@@ -270,7 +335,22 @@ impl<'a, State> Harness<'a, State> {
                 .expect("AccessKit was disabled"),
         );
         self.renderer.handle_delta(&output.textures_delta);
+        self.platform.record(ViewportId::ROOT, &output);
         self.output = output;
+        self.step_child_viewports();
+    }
+
+    /// Handle the child viewports shown in the last pass of the root viewport
+    /// and run the deferred ones.
+    fn step_child_viewports(&mut self) {
+        if self.ctx.embed_viewports() {
+            return;
+        }
+        self.handle_immediate_viewports();
+        let viewport_output = std::mem::take(&mut self.output.viewport_output);
+        self.handle_viewport_output(&viewport_output);
+        self.output.viewport_output = viewport_output;
+        self.step_deferred_viewports();
     }
 
     /// Resize the test harness to fit the contents. This only works when creating the Harness via
@@ -319,7 +399,7 @@ impl<'a, State> Harness<'a, State> {
             let wait_for_images = self.wait_for_pending_images && self.ctx.has_pending_images();
 
             // We only care about immediate repaints
-            if self.root_viewport_output().repaint_delay != Duration::ZERO && !wait_for_images {
+            if !self.needs_repaint() && !wait_for_images {
                 break;
             } else if sleep || wait_for_images {
                 std::thread::sleep(Duration::from_secs_f32(self.step_dt));
@@ -556,6 +636,26 @@ impl<'a, State> Harness<'a, State> {
         self.key_combination_modifiers(modifiers, &[key]);
     }
 
+    /// Start (or stop, with an empty list) dragging files over the window.
+    ///
+    /// The files stay in [`egui::RawInput::hovered_files`] until changed.
+    /// See also [`Node::drop_files`] to drop files on a specific node.
+    pub fn hover_files(&self, files: Vec<egui::HoveredFile>) {
+        self.queued_events
+            .lock()
+            .push(EventType::HoveredFiles(files));
+    }
+
+    /// Drop files on the window at the current pointer position.
+    ///
+    /// This also stops any file hovering.
+    /// See also [`Node::drop_files`] to drop files on a specific node.
+    pub fn drop_files(&self, files: Vec<egui::DroppedFile>) {
+        let mut queue = self.queued_events.lock();
+        queue.push(EventType::HoveredFiles(Vec::new()));
+        queue.push(EventType::DroppedFiles(files));
+    }
+
     /// Render the last output to an image.
     ///
     /// # Errors
@@ -565,14 +665,6 @@ impl<'a, State> Harness<'a, State> {
         self.renderer.render(&self.ctx, &self.output)
     }
 
-    /// Get the root viewport output
-    fn root_viewport_output(&self) -> &egui::ViewportOutput {
-        self.output
-            .viewport_output
-            .get(&ViewportId::ROOT)
-            .expect("Missing root viewport")
-    }
-
     /// The root node of the test harness.
     pub fn root(&self) -> Node<'_> {
         Node {
@@ -1,12 +1,70 @@
 use egui::accesskit::ActionRequest;
 use egui::mutex::Mutex;
-use egui::{Modifiers, PointerButton, Pos2, accesskit};
+use egui::{
+    DroppedFile, HoveredFile, Modifiers, PointerButton, Pos2, TouchDeviceId, TouchId, TouchPhase,
+    Vec2, accesskit,
+};
 use kittest::{AccessKitNode, NodeT, debug_fmt_node};
 use std::fmt::{Debug, Formatter};
 
 pub(crate) enum EventType {
     Event(egui::Event),
+
+    /// Several events that happen in the same frame, e.g. the fingers of a multi-touch gesture.
+    Events(Vec<egui::Event>),
+
     Modifiers(Modifiers),
+
+    /// Files being dragged over the window, or an empty list when they leave.
+    HoveredFiles(Vec<HoveredFile>),
+
+    DroppedFiles(Vec<DroppedFile>),
+}
+
+impl EventType {
+    /// Add this to the input of the next frame.
+    pub(crate) fn apply(self, input: &mut egui::RawInput) {
+        match self {
+            Self::Event(event) => {
+                input.events.push(event);
+            }
+            Self::Events(events) => {
+                input.events.extend(events);
+            }
+            Self::Modifiers(modifiers) => {
+                input.modifiers = modifiers;
+            }
+            Self::HoveredFiles(files) => {
+                input.hovered_files = files;
+            }
+            Self::DroppedFiles(files) => {
+                input.dropped_files = files;
+            }
+        }
+    }
+}
+
+/// Options for [`Node::drag_to_with`] and [`Node::drag_by_with`].
+#[derive(Clone, Copy, Debug)]
+pub struct DragOptions {
+    /// The button to hold down while dragging. Default: [`PointerButton::Primary`].
+    pub button: PointerButton,
+
+    /// How many pointer moves (and thus frames) the drag is split into. Default: 10.
+    pub steps: usize,
+
+    /// Modifiers to hold down while dragging. Default: none.
+    pub modifiers: Modifiers,
+}
+
+impl Default for DragOptions {
+    fn default() -> Self {
+        Self {
+            button: PointerButton::Primary,
+            steps: 10,
+            modifiers: Modifiers::NONE,
+        }
+    }
 }
 
 pub(crate) type EventQueue = Mutex<Vec<EventType>>;
@@ -45,6 +103,10 @@ impl Node<'_> {
         self.queue.lock().push(EventType::Modifiers(modifiers));
     }
 
+    fn events(&self, events: Vec<egui::Event>) {
+        self.queue.lock().push(EventType::Events(events));
+    }
+
     pub fn hover(&self) {
         self.event(egui::Event::PointerMoved(self.rect().center()));
     }
@@ -107,6 +169,156 @@ impl Node<'_> {
         ));
     }
 
+    /// Drag from the node center to `target` with the primary button.
+    ///
+    /// Each step of the drag is a separate frame.
+    pub fn drag_to(&self, target: Pos2) {
+        self.drag_to_with(target, DragOptions::default());
+    }
+
+    /// Drag from the node center by `delta` with the primary button.
+    pub fn drag_by(&self, delta: Vec2) {
+        self.drag_by_with(delta, DragOptions::default());
+    }
+
+    /// Drag from the node center by `delta`.
+    pub fn drag_by_with(&self, delta: Vec2, options: DragOptions) {
+        self.drag_to_with(self.rect().center() + delta, options);
+    }
+
+    /// Drag from the node center to `target`.
+    ///
+    /// This moves the pointer to the node, presses the button, moves the pointer
+    /// to `target` in [`DragOptions::steps`] frames, and releases the button there.
+    pub fn drag_to_with(&self, target: Pos2, options: DragOptions) {
+        let DragOptions {
+            button,
+            steps,
+            modifiers,
+        } = options;
+        let start = self.rect().center();
+
+        self.modifiers(modifiers);
+        self.event(egui::Event::PointerMoved(start));
+        self.event(egui::Event::PointerButton {
+            pos: start,
+            button,
+            pressed: true,
+            modifiers,
+        });
+        let steps = steps.max(1);
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            self.event(egui::Event::PointerMoved(start.lerp(target, t)));
+        }
+        self.event(egui::Event::PointerButton {
+            pos: target,
+            button,
+            pressed: false,
+            modifiers,
+        });
+        self.modifiers(Modifiers::default());
+    }
+
+    /// Swipe with one finger from the node center by `delta`, in 10 steps.
+    ///
+    /// Like on a real touch screen, the finger also moves the pointer.
+    pub fn swipe_by(&self, delta: Vec2) {
+        let center = self.rect().center();
+        self.touch_gesture(&[(center, center + delta)], 10);
+    }
+
+    /// Pinch with two fingers around the node center, in 10 steps.
+    ///
+    /// A `zoom` above 1 moves the fingers apart (zooming in), and below 1 moves them together.
+    /// See [`egui::InputState::zoom_delta`].
+    pub fn pinch(&self, zoom: f32) {
+        let center = self.rect().center();
+        let offset = Vec2::X * (self.rect().size().min_elem() / 4.0).max(10.0);
+        self.touch_gesture(
+            &[
+                (center - offset, center - zoom * offset),
+                (center + offset, center + zoom * offset),
+            ],
+            10,
+        );
+    }
+
+    /// Put a finger down at the start of each `(start, end)` pair,
+    /// move all fingers to their ends in `steps` frames, and lift them.
+    ///
+    /// The fingers move together, so e.g. two fingers moving apart is a pinch-zoom,
+    /// and two fingers circling around their center is a rotation.
+    /// Like on a real touch screen, the first finger also moves the pointer.
+    pub fn touch_gesture(&self, fingers: &[(Pos2, Pos2)], steps: usize) {
+        let Some(&(first_start, first_end)) = fingers.first() else {
+            return;
+        };
+
+        let touch_events = |phase: TouchPhase, t: f32| {
+            fingers
+                .iter()
+                .enumerate()
+                .map(|(i, (start, end))| egui::Event::Touch {
+                    device_id: TouchDeviceId(0),
+                    id: TouchId(i as u64),
+                    phase,
+                    pos: start.lerp(*end, t),
+                    force: None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut events = touch_events(TouchPhase::Start, 0.0);
+        events.push(egui::Event::PointerMoved(first_start));
+        events.push(egui::Event::PointerButton {
+            pos: first_start,
+            button: PointerButton::Primary,
+            pressed: true,
+            modifiers: Modifiers::default(),
+        });
+        self.events(events);
+
+        let steps = steps.max(1);
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let mut events = touch_events(TouchPhase::Move, t);
+            events.push(egui::Event::PointerMoved(first_start.lerp(first_end, t)));
+            self.events(events);
+        }
+
+        let mut events = touch_events(TouchPhase::End, 1.0);
+        events.push(egui::Event::PointerButton {
+            pos: first_end,
+            button: PointerButton::Primary,
+            pressed: false,
+            modifiers: Modifiers::default(),
+        });
+        events.push(egui::Event::PointerGone);
+        self.events(events);
+    }
+
+    /// Drag files over the node and drop them there.
+    ///
+    /// The files show up in [`egui::RawInput::hovered_files`] for one frame,
+    /// and then in [`egui::RawInput::dropped_files`].
+    pub fn drop_files(&self, files: Vec<DroppedFile>) {
+        let hovered_files = files
+            .iter()
+            .map(|file| HoveredFile {
+                path: file.path.clone(),
+                mime: file.mime.clone(),
+            })
+            .collect();
+        self.hover();
+        self.queue
+            .lock()
+            .push(EventType::HoveredFiles(hovered_files));
+        let mut queue = self.queue.lock();
+        queue.push(EventType::HoveredFiles(Vec::new()));
+        queue.push(EventType::DroppedFiles(files));
+    }
+
     pub fn rect(&self) -> egui::Rect {
         let rect = self
             .accesskit_node
@@ -0,0 +1,238 @@
+use egui::output::IMEOutput;
+use egui::{
+    CursorIcon, FullOutput, Key, Modifiers, OutputCommand, ViewportCommand, ViewportId,
+    ViewportIdMap,
+};
+
+use crate::Harness;
+use crate::node::EventType;
+
+/// Something the app asked the platform to do, recorded by the [`Harness`].
+///
+/// See [`Harness::platform_requests`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlatformRequest {
+    /// An [`OutputCommand`], e.g. copying text or opening a url.
+    ///
+    /// The deprecated [`egui::PlatformOutput::copied_text`] and [`egui::PlatformOutput::open_url`]
+    /// are recorded as the equivalent commands.
+    Command(OutputCommand),
+
+    /// The cursor icon of the viewport changed.
+    CursorIcon {
+        viewport_id: ViewportId,
+        icon: CursorIcon,
+    },
+
+    /// The IME state of the viewport changed.
+    ///
+    /// `None` means the app no longer wants text input (see [`egui::PlatformOutput::ime`]).
+    Ime {
+        viewport_id: ViewportId,
+        ime: Option<IMEOutput>,
+    },
+
+    /// A [`ViewportCommand`] sent to a viewport, e.g. via [`egui::Context::send_viewport_cmd`].
+    ViewportCommand {
+        viewport_id: ViewportId,
+        command: ViewportCommand,
+    },
+}
+
+/// What the platform last showed for each viewport, so only changes are recorded.
+#[derive(Default)]
+pub(crate) struct PlatformState {
+    requests: Vec<PlatformRequest>,
+    clipboard: String,
+    cursor_icons: ViewportIdMap<CursorIcon>,
+    ime: ViewportIdMap<IMEOutput>,
+}
+
+impl PlatformState {
+    /// Record the platform output of a pass of the given viewport.
+    pub(crate) fn record(&mut self, viewport_id: ViewportId, output: &FullOutput) {
+        #![allow(deprecated)]
+
+        let platform_output = &output.platform_output;
+        let mut commands = platform_output.commands.clone();
+        if !platform_output.copied_text.is_empty() {
+            commands.push(OutputCommand::CopyText(platform_output.copied_text.clone()));
+        }
+        if let Some(open_url) = &platform_output.open_url {
+            commands.push(OutputCommand::OpenUrl(open_url.clone()));
+        }
+        for command in commands {
+            match &command {
+                OutputCommand::CopyText(text) => {
+                    self.clipboard.clone_from(text);
+                }
+                OutputCommand::CopyImage(_) => {
+                    // Only text is simulated, but a real clipboard would lose the text too.
+                    self.clipboard.clear();
+                }
+                OutputCommand::OpenUrl(_) => {}
+            }
+            self.requests.push(PlatformRequest::Command(command));
+        }
+
+        let icon = platform_output.cursor_icon;
+        let last_icon = self.cursor_icons.insert(viewport_id, icon);
+        if last_icon.unwrap_or_default() != icon {
+            self.requests
+                .push(PlatformRequest::CursorIcon { viewport_id, icon });
+        }
+
+        let ime = platform_output.ime;
+        let last_ime = if let Some(ime) = ime {
+            self.ime.insert(viewport_id, ime)
+        } else {
+            self.ime.remove(&viewport_id)
+        };
+        if last_ime != ime {
+            self.requests
+                .push(PlatformRequest::Ime { viewport_id, ime });
+        }
+
+        for (viewport_id, viewport_output) in &output.viewport_output {
+            for command in &viewport_output.commands {
+                self.requests.push(PlatformRequest::ViewportCommand {
+                    viewport_id: *viewport_id,
+                    command: command.clone(),
+                });
+            }
+        }
+    }
+
+    /// Add the events a native integration would send for cut, copy and paste shortcuts.
+    ///
+    /// Like `eframe` on the web, the key press is kept and followed by [`egui::Event::Cut`],
+    /// [`egui::Event::Copy`] or [`egui::Event::Paste`] in the same frame.
+    /// Nothing is pasted if the clipboard is empty.
+    pub(crate) fn translate_clipboard_shortcut(&self, event: EventType) -> EventType {
+        let translate = |event: egui::Event| {
+            let clipboard_event = match &event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => {
+                    if is_cut_command(*modifiers, *key) {
+                        Some(egui::Event::Cut)
+                    } else if is_copy_command(*modifiers, *key) {
+                        Some(egui::Event::Copy)
+                    } else if is_paste_command(*modifiers, *key) {
+                        (!self.clipboard.is_empty())
+                            .then(|| egui::Event::Paste(self.clipboard.clone()))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            std::iter::once(event).chain(clipboard_event)
+        };
+        match event {
+            EventType::Event(event) => {
+                let mut events: Vec<_> = translate(event).collect();
+                if events.len() == 1 {
+                    EventType::Event(events.remove(0))
+                } else {
+                    EventType::Events(events)
+                }
+            }
+            EventType::Events(events) => {
+                EventType::Events(events.into_iter().flat_map(translate).collect())
+            }
+            event => event,
+        }
+    }
+}
+
+fn is_cut_command(modifiers: Modifiers, key: Key) -> bool {
+    key == Key::Cut || (modifiers.command && key == Key::X)
+}
+
+fn is_copy_command(modifiers: Modifiers, key: Key) -> bool {
+    key == Key::Copy || (modifiers.command && key == Key::C)
+}
+
+fn is_paste_command(modifiers: Modifiers, key: Key) -> bool {
+    key == Key::Paste || (modifiers.command && key == Key::V)
+}
+
+/// ## Platform output
+///
+/// The [`Harness`] simulates the platform: everything the app asks it to do
+/// (see [`egui::PlatformOutput`] and [`ViewportCommand`]) is recorded as [`PlatformRequest`]s,
+/// and copied text ends up in a simulated clipboard.
+///
+/// Pressing [`Modifiers::COMMAND`] + [`Key::V`] (or [`Key::Paste`]) via the harness or a [`crate::Node`]
+/// pastes the clipboard, and [`Key::C`] / [`Key::X`] send [`egui::Event::Copy`] / [`egui::Event::Cut`].
+impl<State> Harness<'_, State> {
+    /// Everything the app asked the platform to do since the harness was created
+    /// (or since the last [`Self::take_platform_requests`]), in order.
+    pub fn platform_requests(&self) -> &[PlatformRequest] {
+        &self.platform.requests
+    }
+
+    /// Take the recorded [`PlatformRequest`]s, so following assertions only see new ones.
+    pub fn take_platform_requests(&mut self) -> Vec<PlatformRequest> {
+        std::mem::take(&mut self.platform.requests)
+    }
+
+    /// All texts the app copied to the clipboard, in order.
+    pub fn copied_texts(&self) -> impl Iterator<Item = &str> {
+        self.platform
+            .requests
+            .iter()
+            .filter_map(|request| match request {
+                PlatformRequest::Command(OutputCommand::CopyText(text)) => Some(text.as_str()),
+                _ => None,
+            })
+    }
+
+    /// All urls the app opened, in order.
+    pub fn opened_urls(&self) -> impl Iterator<Item = &egui::OpenUrl> {
+        self.platform
+            .requests
+            .iter()
+            .filter_map(|request| match request {
+                PlatformRequest::Command(OutputCommand::OpenUrl(open_url)) => Some(open_url),
+                _ => None,
+            })
+    }
+
+    /// All [`ViewportCommand`]s the app sent to the given viewport, in order.
+    pub fn viewport_commands(
+        &self,
+        viewport_id: ViewportId,
+    ) -> impl Iterator<Item = &ViewportCommand> {
+        self.platform
+            .requests
+            .iter()
+            .filter_map(move |request| match request {
+                PlatformRequest::ViewportCommand {
+                    viewport_id: id,
+                    command,
+                } if *id == viewport_id => Some(command),
+                _ => None,
+            })
+    }
+
+    /// The cursor icon the root viewport showed in the last pass.
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.output.platform_output.cursor_icon
+    }
+
+    /// The text in the simulated clipboard.
+    pub fn clipboard_text(&self) -> &str {
+        &self.platform.clipboard
+    }
+
+    /// Put text in the simulated clipboard, e.g. to paste it with [`Modifiers::COMMAND`] + [`Key::V`].
+    pub fn set_clipboard_text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.platform.clipboard = text.into();
+        self
+    }
+}
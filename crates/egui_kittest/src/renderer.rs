@@ -18,6 +18,26 @@ pub trait TestRenderer {
         ctx: &egui::Context,
         output: &egui::FullOutput,
     ) -> Result<image::RgbaImage, String>;
+
+    /// Render the last output of a viewport of the [`crate::Harness`] and return the resulting image.
+    ///
+    /// Only renderers overriding this support child viewports.
+    ///
+    /// # Errors
+    /// Returns an error if the rendering fails.
+    #[cfg(any(feature = "wgpu", feature = "snapshot"))]
+    fn render_viewport(
+        &mut self,
+        ctx: &egui::Context,
+        output: &egui::FullOutput,
+        viewport_id: egui::ViewportId,
+    ) -> Result<image::RgbaImage, String> {
+        if viewport_id == egui::ViewportId::ROOT {
+            self.render(ctx, output)
+        } else {
+            Err("This renderer does not support rendering child viewports".to_owned())
+        }
+    }
 }
 
 /// A lazy renderer that initializes the renderer on the first render call.
@@ -54,6 +74,31 @@ impl LazyRenderer {
     }
 }
 
+impl LazyRenderer {
+    /// Initialize the renderer, if it isn't yet.
+    #[cfg(any(feature = "wgpu", feature = "snapshot"))]
+    fn renderer(&mut self) -> Result<&mut Box<dyn TestRenderer>, String> {
+        if let Self::Uninitialized {
+            texture_ops,
+            builder: build,
+        } = self
+        {
+            let mut renderer = build.take().ok_or({
+                "No default renderer available. \
+                Enable the wgpu feature or set one via HarnessBuilder::renderer"
+            })?();
+            for delta in texture_ops.drain(..) {
+                renderer.handle_delta(&delta);
+            }
+            *self = Self::Initialized { renderer };
+        }
+        match self {
+            Self::Initialized { renderer } => Ok(renderer),
+            Self::Uninitialized { .. } => unreachable!("Initialized above"),
+        }
+    }
+}
+
 impl TestRenderer for LazyRenderer {
     fn handle_delta(&mut self, delta: &TexturesDelta) {
         match self {
@@ -68,23 +113,16 @@ impl TestRenderer for LazyRenderer {
         ctx: &egui::Context,
         output: &egui::FullOutput,
     ) -> Result<image::RgbaImage, String> {
-        match self {
-            Self::Uninitialized {
-                texture_ops,
-                builder: build,
-            } => {
-                let mut renderer = build.take().ok_or({
-                    "No default renderer available. \
-                    Enable the wgpu feature or set one via HarnessBuilder::renderer"
-                })?();
-                for delta in texture_ops.drain(..) {
-                    renderer.handle_delta(&delta);
-                }
-                let image = renderer.render(ctx, output)?;
-                *self = Self::Initialized { renderer };
-                Ok(image)
-            }
-            Self::Initialized { renderer } => renderer.render(ctx, output),
-        }
+        self.renderer()?.render(ctx, output)
+    }
+
+    #[cfg(any(feature = "wgpu", feature = "snapshot"))]
+    fn render_viewport(
+        &mut self,
+        ctx: &egui::Context,
+        output: &egui::FullOutput,
+        viewport_id: egui::ViewportId,
+    ) -> Result<image::RgbaImage, String> {
+        self.renderer()?.render_viewport(ctx, output, viewport_id)
     }
 }
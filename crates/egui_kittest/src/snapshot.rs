@@ -1,4 +1,6 @@
-use crate::Harness;
+use crate::snapshot_comparison::compare_images;
+use crate::{Harness, Node, SnapshotComparison, SnapshotRegion};
+use egui::Rect;
 use image::ImageError;
 use std::fmt::Display;
 use std::io::ErrorKind;
@@ -7,6 +9,7 @@ use std::path::PathBuf;
 pub type SnapshotResult = Result<(), SnapshotError>;
 
 #[non_exhaustive]
+#[derive(Clone, Debug)]
 pub struct SnapshotOptions {
     /// The threshold for the image comparison.
     /// The default is `0.6` (which is enough for most egui tests to pass across different
@@ -23,6 +26,22 @@ pub struct SnapshotOptions {
     /// The path where the snapshots will be saved.
     /// The default is `tests/snapshots`.
     pub output_path: PathBuf,
+
+    /// How the images are compared. The default is [`SnapshotComparison::Pixel`].
+    pub comparison: SnapshotComparison,
+
+    /// Regions (in points) that are ignored in the comparison, e.g. a clock or an animation.
+    /// They also apply within [`Self::regions`].
+    pub ignored_regions: Vec<Rect>,
+
+    /// Regions (in points) that are compared with their own thresholds.
+    pub regions: Vec<SnapshotRegion>,
+
+    /// The scale of the image, used to convert the regions to pixels.
+    ///
+    /// The [`Harness`] snapshot functions set this to the `pixels_per_point` of the harness.
+    /// The default is `1.0`.
+    pub pixels_per_point: f32,
 }
 
 /// Helper struct to define the number of pixels that can differ before the snapshot is considered a failure.
@@ -124,6 +143,10 @@ impl Default for SnapshotOptions {
             threshold: 0.6,
             output_path: PathBuf::from("tests/snapshots"),
             failed_pixel_count_threshold: 0, // Default is 0, meaning no pixels can differ
+            comparison: SnapshotComparison::Pixel,
+            ignored_regions: Vec::new(),
+            regions: Vec::new(),
+            pixels_per_point: 1.0,
         }
     }
 }
@@ -164,6 +187,53 @@ impl SnapshotOptions {
         self.failed_pixel_count_threshold = failed_pixel_count_threshold;
         self
     }
+
+    /// Change how the images are compared. See [`SnapshotComparison`].
+    #[inline]
+    pub fn comparison(mut self, comparison: SnapshotComparison) -> Self {
+        self.comparison = comparison;
+        self
+    }
+
+    /// Ignore a region (in points) in the comparison.
+    #[inline]
+    pub fn ignore_rect(mut self, rect: Rect) -> Self {
+        self.ignored_regions.push(rect);
+        self
+    }
+
+    /// Ignore the area covered by a node in the comparison.
+    #[inline]
+    pub fn ignore_node(self, node: &Node<'_>) -> Self {
+        self.ignore_rect(node.rect())
+    }
+
+    /// Compare a region (in points) with its own thresholds,
+    /// instead of [`Self::threshold`] and [`Self::failed_pixel_count_threshold`].
+    ///
+    /// If regions overlap, the overlapping pixels are checked against each of them.
+    #[inline]
+    pub fn region(
+        mut self,
+        rect: Rect,
+        threshold: impl Into<f32>,
+        failed_pixel_count_threshold: impl Into<OsThreshold<usize>>,
+    ) -> Self {
+        self.regions.push(SnapshotRegion {
+            rect,
+            threshold: threshold.into(),
+            failed_pixel_count_threshold: failed_pixel_count_threshold.into().threshold(),
+        });
+        self
+    }
+
+    /// Change the scale of the image, used to convert the regions to pixels.
+    /// The [`Harness`] snapshot functions set this for you.
+    #[inline]
+    pub fn pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+        self.pixels_per_point = pixels_per_point;
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -322,9 +392,13 @@ fn try_image_snapshot_options_impl(
     options: &SnapshotOptions,
 ) -> SnapshotResult {
     let SnapshotOptions {
-        threshold,
+        threshold: _,
         output_path,
-        failed_pixel_count_threshold,
+        failed_pixel_count_threshold: _,
+        comparison: _,
+        ignored_regions: _,
+        regions: _,
+        pixels_per_point: _,
     } = options;
 
     let parent_path = if let Some(parent) = PathBuf::from(&name).parent() {
@@ -401,11 +475,13 @@ fn try_image_snapshot_options_impl(
     }
 
     // Compare existing image to the new one:
-    let result =
-        dify::diff::get_results(previous, new.clone(), *threshold, true, None, &None, &None);
+    if let Some(diff) = compare_images(&previous, new, options) {
+        if diff.within_thresholds && !should_update_snapshots() {
+            // Only write a diff for snapshots that need to be looked at:
+            return Ok(());
+        }
 
-    if let Some((num_wrong_pixels, result_image)) = result {
-        result_image
+        diff.image
             .save(diff_path.clone())
             .map_err(|err| SnapshotError::WriteSnapshot {
                 path: diff_path.clone(),
@@ -415,13 +491,9 @@ fn try_image_snapshot_options_impl(
         if should_update_snapshots() {
             update_snapshot()
         } else {
-            if num_wrong_pixels as i64 <= *failed_pixel_count_threshold as i64 {
-                return Ok(());
-            }
-
             Err(SnapshotError::Diff {
                 name,
-                diff: num_wrong_pixels,
+                diff: diff.num_wrong_pixels,
                 diff_path,
             })
         }
@@ -524,7 +596,10 @@ impl<State> Harness<'_, State> {
         let image = self
             .render()
             .map_err(|err| SnapshotError::RenderError { err })?;
-        try_image_snapshot_options(&image, name.into(), options)
+        let options = options
+            .clone()
+            .pixels_per_point(self.ctx.pixels_per_point());
+        try_image_snapshot_options(&image, name.into(), &options)
     }
 
     /// Render an image using the setup [`crate::TestRenderer`] and compare it to the snapshot.
@@ -587,6 +662,66 @@ impl<State> Harness<'_, State> {
             }
         }
     }
+
+    /// Render an image of a viewport using the setup [`crate::TestRenderer`] and compare it to the snapshot
+    /// with custom options.
+    ///
+    /// See [`Harness::try_snapshot_options`] and [`Harness::viewport_ids`].
+    ///
+    /// # Errors
+    /// Returns a [`SnapshotError`] if the image does not match the snapshot, if there was an
+    /// error reading or writing the snapshot, if there is no such viewport, if the rendering fails
+    /// or if no default renderer is available.
+    pub fn try_viewport_snapshot_options(
+        &mut self,
+        viewport_id: egui::ViewportId,
+        name: impl Into<String>,
+        options: &SnapshotOptions,
+    ) -> SnapshotResult {
+        let image = self
+            .render_viewport(viewport_id)
+            .map_err(|err| SnapshotError::RenderError { err })?;
+        let options = options
+            .clone()
+            .pixels_per_point(self.ctx.input_for(viewport_id, |i| i.pixels_per_point()));
+        try_image_snapshot_options(&image, name.into(), &options)
+    }
+
+    /// Render an image of a viewport using the setup [`crate::TestRenderer`] and compare it to the snapshot.
+    ///
+    /// See [`Harness::try_snapshot`] and [`Harness::viewport_ids`].
+    ///
+    /// # Errors
+    /// Returns a [`SnapshotError`] if the image does not match the snapshot, if there was an
+    /// error reading or writing the snapshot, if there is no such viewport, if the rendering fails
+    /// or if no default renderer is available.
+    pub fn try_viewport_snapshot(
+        &mut self,
+        viewport_id: egui::ViewportId,
+        name: impl Into<String>,
+    ) -> SnapshotResult {
+        let image = self
+            .render_viewport(viewport_id)
+            .map_err(|err| SnapshotError::RenderError { err })?;
+        try_image_snapshot(&image, name)
+    }
+
+    /// Render an image of a viewport using the setup [`crate::TestRenderer`] and compare it to the snapshot.
+    ///
+    /// See [`Harness::snapshot`] and [`Harness::viewport_ids`].
+    ///
+    /// # Panics
+    /// Panics if the image does not match the snapshot, if there was an error reading or writing the
+    /// snapshot, if there is no such viewport, if the rendering fails or if no default renderer is available.
+    #[track_caller]
+    pub fn viewport_snapshot(&mut self, viewport_id: egui::ViewportId, name: impl Into<String>) {
+        match self.try_viewport_snapshot(viewport_id, name) {
+            Ok(_) => {}
+            Err(err) => {
+                panic!("{}", err);
+            }
+        }
+    }
 }
 
 // Deprecated wgpu_snapshot functions
@@ -657,6 +792,7 @@ impl<State> Harness<'_, State> {
 /// If you want to panic early, you can use [`SnapshotResults::unwrap`].
 #[derive(Debug, Default)]
 pub struct SnapshotResults {
+    checked: usize,
     errors: Vec<SnapshotError>,
 }
 
@@ -681,6 +817,7 @@ impl SnapshotResults {
 
     /// Check if the result is an error and add it to the list of errors.
     pub fn add(&mut self, result: SnapshotResult) {
+        self.checked += 1;
         if let Err(err) = result {
             self.errors.push(err);
         }
@@ -691,6 +828,36 @@ impl SnapshotResults {
         !self.errors.is_empty()
     }
 
+    /// A JSON summary of the results, e.g. for CI or a snapshot review tool:
+    ///
+    /// ```json
+    /// {
+    ///   "checked": 2,
+    ///   "errors": [
+    ///     {
+    ///       "diff": 42,
+    ///       "diff_path": "tests/snapshots/my_test.diff.png",
+    ///       "kind": "diff",
+    ///       "message": "…",
+    ///       "name": "my_test"
+    ///     }
+    ///   ],
+    ///   "failed": 1
+    /// }
+    /// ```
+    ///
+    /// Each error has a `kind` (`diff`, `open_snapshot`, `size_mismatch`, `write_snapshot` or `render_error`),
+    /// the fields of the corresponding [`SnapshotError`] and a human readable `message`.
+    pub fn to_json(&self) -> String {
+        let errors: Vec<_> = self.errors.iter().map(error_to_json).collect();
+        let json = serde_json::json!({
+            "checked": self.checked,
+            "failed": self.errors.len(),
+            "errors": errors,
+        });
+        format!("{json:#}")
+    }
+
     /// Convert this into a `Result<(), Self>`.
     #[expect(clippy::missing_errors_doc)]
     pub fn into_result(self) -> Result<(), Self> {
@@ -709,6 +876,42 @@ impl SnapshotResults {
     }
 }
 
+fn error_to_json(error: &SnapshotError) -> serde_json::Value {
+    use serde_json::json;
+
+    let mut json = match error {
+        SnapshotError::Diff {
+            name,
+            diff,
+            diff_path,
+        } => json!({
+            "kind": "diff",
+            "name": name,
+            "diff": diff,
+            "diff_path": diff_path.to_string_lossy(),
+        }),
+        SnapshotError::OpenSnapshot { path, .. } => {
+            json!({ "kind": "open_snapshot", "path": path.to_string_lossy() })
+        }
+        SnapshotError::SizeMismatch {
+            name,
+            expected,
+            actual,
+        } => json!({
+            "kind": "size_mismatch",
+            "name": name,
+            "expected": [expected.0, expected.1],
+            "actual": [actual.0, actual.1],
+        }),
+        SnapshotError::WriteSnapshot { path, .. } => {
+            json!({ "kind": "write_snapshot", "path": path.to_string_lossy() })
+        }
+        SnapshotError::RenderError { .. } => json!({ "kind": "render_error" }),
+    };
+    json["message"] = error.to_string().into();
+    json
+}
+
 impl From<SnapshotResults> for Vec<SnapshotError> {
     fn from(results: SnapshotResults) -> Self {
         results.into_inner()
@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+
+use egui::Rect;
+use image::{Rgba, RgbaImage};
+
+use crate::SnapshotOptions;
+
+const RED_PIXEL: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const YELLOW_PIXEL: Rgba<u8> = Rgba([255, 255, 0, 255]);
+
+/// The size of the blocks compared by [`SnapshotComparison::Ssim`].
+const SSIM_BLOCK_SIZE: u32 = 8;
+
+/// How a new image is compared to the snapshot. See [`SnapshotOptions::comparison`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SnapshotComparison {
+    /// Compare each pixel on its own: a pixel fails if the color difference is above
+    /// [`SnapshotOptions::threshold`], unless it looks like anti-aliasing.
+    #[default]
+    Pixel,
+
+    /// Compare the structure of the images in blocks of 8x8 pixels,
+    /// using the [structural similarity index](https://en.wikipedia.org/wiki/Structural_similarity).
+    ///
+    /// The pixels that differ by more than [`SnapshotOptions::threshold`] only fail
+    /// if their block has a similarity below `min_score` (where `1.0` means identical).
+    /// This ignores small changes in brightness or color that don't change the shapes.
+    Ssim { min_score: f32 },
+
+    /// Tolerate edges that moved by up to `max_shift` pixels, e.g. because of differences in font hinting.
+    ///
+    /// A pixel only fails if neither image has a pixel matching the other one
+    /// (within [`SnapshotOptions::threshold`]) in the surrounding `max_shift` pixels.
+    EdgeTolerant { max_shift: u32 },
+}
+
+/// A part of the image with its own thresholds. See [`SnapshotOptions::region`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotRegion {
+    /// The region, in points.
+    pub rect: Rect,
+
+    /// Replaces [`SnapshotOptions::threshold`] in this region.
+    pub threshold: f32,
+
+    /// Replaces [`SnapshotOptions::failed_pixel_count_threshold`] in this region.
+    pub failed_pixel_count_threshold: usize,
+}
+
+/// The differences between a snapshot and a new image.
+pub(crate) struct ImageDiff {
+    /// The number of pixels that failed, in all regions.
+    pub num_wrong_pixels: i32,
+
+    /// Are the failed pixels within [`SnapshotOptions::failed_pixel_count_threshold`]
+    /// and the thresholds of each [`SnapshotRegion`]?
+    pub within_thresholds: bool,
+
+    /// Failed pixels are red, tolerated differences yellow.
+    pub image: RgbaImage,
+}
+
+/// Compare the images according to the options.
+///
+/// Returns `None` if no pixel failed.
+pub(crate) fn compare_images(
+    previous: &RgbaImage,
+    new: &RgbaImage,
+    options: &SnapshotOptions,
+) -> Option<ImageDiff> {
+    let bounds = |rect: Rect| pixel_bounds(rect * options.pixels_per_point, new);
+
+    let pixels = |rect: Rect| {
+        let (x0, y0, x1, y1) = bounds(rect);
+        (y0..y1).flat_map(move |y| (x0..x1).map(move |x| (x, y)))
+    };
+    let ignored: HashSet<_> = options
+        .ignored_regions
+        .iter()
+        .copied()
+        .flat_map(pixels)
+        .collect();
+
+    // The regions are compared separately, with their own thresholds:
+    let mut masked = ignored.clone();
+    masked.extend(
+        options
+            .regions
+            .iter()
+            .flat_map(|region| pixels(region.rect)),
+    );
+
+    let mut diff_image = None;
+    let mut num_wrong_pixels = 0;
+    let mut within_thresholds = true;
+
+    if let Some((count, image)) = compare(
+        previous,
+        new,
+        options.comparison,
+        options.threshold,
+        &masked,
+    ) {
+        num_wrong_pixels += count;
+        within_thresholds &= count as usize <= options.failed_pixel_count_threshold;
+        diff_image = Some(image);
+    }
+
+    for region in &options.regions {
+        let (x0, y0, x1, y1) = bounds(region.rect);
+        let crop = |image: &RgbaImage| {
+            image::imageops::crop_imm(image, x0, y0, x1 - x0, y1 - y0).to_image()
+        };
+        // The ignored regions still apply within the region:
+        let region_ignored = pixels(region.rect)
+            .filter(|pixel| ignored.contains(pixel))
+            .map(|(x, y)| (x - x0, y - y0))
+            .collect();
+        if let Some((count, image)) = compare(
+            &crop(previous),
+            &crop(new),
+            options.comparison,
+            region.threshold,
+            &region_ignored,
+        ) {
+            num_wrong_pixels += count;
+            within_thresholds &= count as usize <= region.failed_pixel_count_threshold;
+            let diff_image =
+                diff_image.get_or_insert_with(|| RgbaImage::new(new.width(), new.height()));
+            image::imageops::overlay(diff_image, &image, x0.into(), y0.into());
+        }
+    }
+
+    Some(ImageDiff {
+        num_wrong_pixels,
+        within_thresholds,
+        image: diff_image?,
+    })
+}
+
+/// The pixels covered by `rect` (in pixels), clamped to the image, as `(x0, y0, x1, y1)`.
+fn pixel_bounds(rect: Rect, image: &RgbaImage) -> (u32, u32, u32, u32) {
+    let clamp = |value: f32, max: u32| (value.max(0.0) as u32).min(max);
+    let (width, height) = image.dimensions();
+    let x0 = clamp(rect.min.x.floor(), width);
+    let y0 = clamp(rect.min.y.floor(), height);
+    let x1 = clamp(rect.max.x.ceil(), width).max(x0);
+    let y1 = clamp(rect.max.y.ceil(), height).max(y0);
+    (x0, y0, x1, y1)
+}
+
+/// Compare two images of the same size, skipping the `masked` pixels.
+///
+/// Returns the number of failed pixels and the diff image, or `None` if no pixel failed.
+fn compare(
+    previous: &RgbaImage,
+    new: &RgbaImage,
+    comparison: SnapshotComparison,
+    threshold: f32,
+    masked: &HashSet<(u32, u32)>,
+) -> Option<(i32, RgbaImage)> {
+    let result = match comparison {
+        SnapshotComparison::Pixel => {
+            let block_out = (!masked.is_empty()).then(|| masked.clone());
+            return dify::diff::get_results(
+                previous.clone(),
+                new.clone(),
+                threshold,
+                true,
+                None,
+                &None,
+                &block_out,
+            );
+        }
+        SnapshotComparison::Ssim { min_score } => {
+            compare_ssim(previous, new, min_score, threshold, masked)
+        }
+        SnapshotComparison::EdgeTolerant { max_shift } => {
+            compare_edge_tolerant(previous, new, max_shift, threshold, masked)
+        }
+    };
+    (result.0 > 0).then_some(result)
+}
+
+fn compare_edge_tolerant(
+    previous: &RgbaImage,
+    new: &RgbaImage,
+    max_shift: u32,
+    threshold: f32,
+    masked: &HashSet<(u32, u32)>,
+) -> (i32, RgbaImage) {
+    let (width, height) = new.dimensions();
+    let mut diff = RgbaImage::new(width, height);
+    let mut failed = 0;
+
+    // Is there a pixel close to `(x, y)` in `image` that matches `color`?
+    let matches_nearby = |image: &RgbaImage, x: u32, y: u32, color: &Rgba<u8>| {
+        let xs = x.saturating_sub(max_shift)..=(x + max_shift).min(width - 1);
+        let ys = y.saturating_sub(max_shift)..=(y + max_shift).min(height - 1);
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .any(|(x, y)| color_distance(image.get_pixel(x, y), color) <= threshold)
+    };
+
+    for (x, y, new_pixel) in new.enumerate_pixels() {
+        let previous_pixel = previous.get_pixel(x, y);
+        if masked.contains(&(x, y)) || color_distance(previous_pixel, new_pixel) <= threshold {
+            continue;
+        }
+        if matches_nearby(previous, x, y, new_pixel) && matches_nearby(new, x, y, previous_pixel) {
+            diff.put_pixel(x, y, YELLOW_PIXEL);
+        } else {
+            diff.put_pixel(x, y, RED_PIXEL);
+            failed += 1;
+        }
+    }
+
+    (failed, diff)
+}
+
+fn compare_ssim(
+    previous: &RgbaImage,
+    new: &RgbaImage,
+    min_score: f32,
+    threshold: f32,
+    masked: &HashSet<(u32, u32)>,
+) -> (i32, RgbaImage) {
+    let (width, height) = new.dimensions();
+    let mut diff = RgbaImage::new(width, height);
+    let mut failed = 0;
+
+    for block_y in (0..height).step_by(SSIM_BLOCK_SIZE as usize) {
+        for block_x in (0..width).step_by(SSIM_BLOCK_SIZE as usize) {
+            let pixels: Vec<_> = (block_y..(block_y + SSIM_BLOCK_SIZE).min(height))
+                .flat_map(|y| {
+                    (block_x..(block_x + SSIM_BLOCK_SIZE).min(width)).map(move |x| (x, y))
+                })
+                .filter(|pixel| !masked.contains(pixel))
+                .collect();
+            if pixels.is_empty() {
+                continue;
+            }
+
+            let lumas: Vec<_> = pixels
+                .iter()
+                .map(|&(x, y)| (luma(previous.get_pixel(x, y)), luma(new.get_pixel(x, y))))
+                .collect();
+            let block_failed = ssim(&lumas) < min_score;
+
+            for &(x, y) in &pixels {
+                if color_distance(previous.get_pixel(x, y), new.get_pixel(x, y)) <= threshold {
+                    continue;
+                }
+                if block_failed {
+                    diff.put_pixel(x, y, RED_PIXEL);
+                    failed += 1;
+                } else {
+                    diff.put_pixel(x, y, YELLOW_PIXEL);
+                }
+            }
+        }
+    }
+
+    (failed, diff)
+}
+
+/// The structural similarity of the pairs of luma values, in `[-1, 1]`.
+fn ssim(lumas: &[(f32, f32)]) -> f32 {
+    const C1: f32 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f32 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let n = lumas.len() as f32;
+    let mean_a = lumas.iter().map(|(a, _)| a).sum::<f32>() / n;
+    let mean_b = lumas.iter().map(|(_, b)| b).sum::<f32>() / n;
+    let (mut variance_a, mut variance_b, mut covariance) = (0.0, 0.0, 0.0);
+    for (a, b) in lumas {
+        variance_a += (a - mean_a) * (a - mean_a) / n;
+        variance_b += (b - mean_b) * (b - mean_b) / n;
+        covariance += (a - mean_a) * (b - mean_b) / n;
+    }
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2))
+}
+
+/// The luma of the color blended over black, in `[0, 255]`.
+fn luma(color: &Rgba<u8>) -> f32 {
+    let [r, g, b, a] = color.0.map(f32::from);
+    (0.299 * r + 0.587 * g + 0.114 * b) * a / 255.0
+}
+
+/// The squared YIQ distance of the colors, on the same scale as [`SnapshotOptions::threshold`].
+fn color_distance(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let yiq = |color: &Rgba<u8>| {
+        let [r, g, b, _] = color.0.map(f32::from);
+        (
+            0.298_895_3 * r + 0.586_622_5 * g + 0.114_482_2 * b,
+            0.595_978 * r - 0.274_171_6 * g - 0.321_801_9 * b,
+            0.211_470_2 * r - 0.522_617_1 * g + 0.311_146_9 * b,
+        )
+    };
+    let (y_a, i_a, q_a) = yiq(a);
+    let (y_b, i_b, q_b) = yiq(b);
+    0.5053 * (y_a - y_b).powi(2) + 0.299 * (i_a - i_b).powi(2) + 0.1957 * (q_a - q_b).powi(2)
+}
@@ -0,0 +1,530 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use egui::{
+    DeferredViewportUiCallback, FullOutput, ImmediateViewport, Pos2, RawInput, Rect, Vec2,
+    ViewportBuilder, ViewportClass, ViewportCommand, ViewportEvent, ViewportId, ViewportIdMap,
+    ViewportIdPair, ViewportInfo, ViewportOutput, ViewportSimulator,
+};
+
+use crate::node::EventQueue;
+use crate::{Harness, Node};
+
+/// A simulated child viewport, i.e. a native window.
+///
+/// Only used if [`crate::HarnessBuilder::with_embed_viewports`] is `false`.
+pub(crate) struct ChildViewport {
+    pub(crate) id: ViewportId,
+    pub(crate) parent: ViewportId,
+    pub(crate) class: ViewportClass,
+    pub(crate) builder: ViewportBuilder,
+    pub(crate) viewport_ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+
+    /// The input for the next pass.
+    ///
+    /// `input.viewports` only contains the [`ViewportInfo`] of this viewport.
+    pub(crate) input: RawInput,
+
+    /// The output of the last pass, `None` until the viewport has run.
+    pub(crate) output: Option<FullOutput>,
+    pub(crate) kittest: Option<kittest::State>,
+    pub(crate) queued_events: EventQueue,
+}
+
+impl ChildViewport {
+    fn new(
+        ids: ViewportIdPair,
+        class: ViewportClass,
+        builder: ViewportBuilder,
+        native_pixels_per_point: Option<f32>,
+    ) -> Self {
+        Self {
+            id: ids.this,
+            parent: ids.parent,
+            class,
+            input: new_viewport_input(ids, &builder, native_pixels_per_point),
+            builder,
+            viewport_ui_cb: None,
+            output: None,
+            kittest: None,
+            queued_events: Default::default(),
+        }
+    }
+
+    pub(crate) fn info_mut(&mut self) -> &mut ViewportInfo {
+        self.input.viewports.entry(self.id).or_default()
+    }
+
+    pub(crate) fn set_size(&mut self, size: Vec2) {
+        self.info_mut().set_simulated_inner_size(size);
+        self.update_screen_rect();
+    }
+
+    /// Simulate what a native window would do with the command.
+    fn apply_command(&mut self, command: &ViewportCommand) {
+        self.info_mut().apply_simulated_command(command);
+        self.update_screen_rect();
+    }
+
+    /// Keep the screen rect of the input in sync with the size of the simulated window.
+    fn update_screen_rect(&mut self) {
+        let size = self.info_mut().inner_rect.map(|rect| rect.size());
+        self.input.screen_rect = size.map(|size| Rect::from_min_size(Pos2::ZERO, size));
+    }
+
+    fn update(&mut self, mut output: FullOutput) {
+        let update = output
+            .platform_output
+            .accesskit_update
+            .take()
+            .expect("AccessKit was disabled");
+        if let Some(kittest) = &mut self.kittest {
+            kittest.update(update);
+        } else {
+            self.kittest = Some(kittest::State::new(update));
+        }
+        self.output = Some(output);
+    }
+}
+
+/// The input for the first pass of a new child viewport.
+fn new_viewport_input(
+    ids: ViewportIdPair,
+    builder: &ViewportBuilder,
+    native_pixels_per_point: Option<f32>,
+) -> RawInput {
+    let info = ViewportInfo::simulated(ids.parent, builder, native_pixels_per_point);
+    RawInput {
+        viewport_id: ids.this,
+        screen_rect: info
+            .inner_rect
+            .map(|rect| Rect::from_min_size(Pos2::ZERO, rect.size())),
+        viewports: std::iter::once((ids.this, info)).collect(),
+        ..Default::default()
+    }
+}
+
+/// Shared with the [`egui::Context::set_immediate_viewport_renderer`] callback,
+/// which runs immediate viewports in the middle of the pass of their parent.
+#[derive(Default)]
+pub(crate) struct ImmediateViewports {
+    /// The input for the next pass of each known immediate viewport.
+    inputs: ViewportIdMap<RawInput>,
+
+    /// Used for the input of new immediate viewports.
+    pub(crate) new_viewport_input: RawInput,
+
+    /// The immediate viewports that ran since this was last drained.
+    ran: Vec<(ViewportIdPair, ViewportBuilder, FullOutput)>,
+}
+
+/// Run the immediate viewports shown on this thread using the inputs in `shared`.
+///
+/// The callback is per thread, so this needs to be called before every pass in case
+/// there are several harnesses.
+pub(crate) fn set_immediate_viewport_renderer(shared: &Rc<RefCell<ImmediateViewports>>) {
+    let shared = Rc::downgrade(shared);
+    egui::Context::set_immediate_viewport_renderer(move |ctx, mut viewport| {
+        if let Some(shared) = shared.upgrade() {
+            run_immediate_viewport(&shared, ctx, viewport);
+        } else {
+            // The harness is gone, so there is no one to simulate the viewport.
+            (viewport.viewport_ui_cb)(ctx);
+        }
+    });
+}
+
+fn run_immediate_viewport(
+    shared: &RefCell<ImmediateViewports>,
+    ctx: &egui::Context,
+    viewport: ImmediateViewport<'_>,
+) {
+    let ImmediateViewport {
+        ids,
+        builder,
+        mut viewport_ui_cb,
+    } = viewport;
+
+    let input = {
+        let mut shared = shared.borrow_mut();
+        if let Some(input) = shared.inputs.remove(&ids.this) {
+            input
+        } else {
+            let template = &shared.new_viewport_input;
+            let native_pixels_per_point = template
+                .viewports
+                .get(&ViewportId::ROOT)
+                .and_then(|info| info.native_pixels_per_point);
+            let mut input = new_viewport_input(ids, &builder, native_pixels_per_point);
+            input.predicted_dt = template.predicted_dt;
+            for (id, info) in &template.viewports {
+                input.viewports.entry(*id).or_insert_with(|| info.clone());
+            }
+            input
+        }
+    };
+
+    // No borrows may be held here, since this may re-entrantly run another immediate viewport.
+    let output = ctx.run(input, |ctx| viewport_ui_cb(ctx));
+
+    shared.borrow_mut().ran.push((ids, builder, output));
+}
+
+/// ## Viewports
+///
+/// By default, child viewports are embedded in the root viewport (see [`egui::Context::embed_viewports`]).
+/// If [`crate::HarnessBuilder::with_embed_viewports`] is set to `false`,
+/// they are instead simulated as separate native windows:
+/// each has its own input, size, [`ViewportInfo`] and AccessKit tree, and can be rendered on its own.
+///
+/// Immediate viewports run whenever their parent runs.
+/// Deferred viewports run once per [`Harness::step`], after the root viewport.
+/// [`ViewportCommand`]s sent to child viewports (e.g. [`ViewportCommand::InnerSize`]) are simulated.
+impl<State> Harness<'_, State> {
+    pub(crate) fn child_viewport(&self, viewport_id: ViewportId) -> Option<&ChildViewport> {
+        self.viewports
+            .iter()
+            .find(|viewport| viewport.id == viewport_id)
+    }
+
+    pub(crate) fn child_viewport_mut(
+        &mut self,
+        viewport_id: ViewportId,
+    ) -> Option<&mut ChildViewport> {
+        self.viewports
+            .iter_mut()
+            .find(|viewport| viewport.id == viewport_id)
+    }
+
+    /// The input for the next pass of the given viewport.
+    pub(crate) fn viewport_input_mut(&mut self, viewport_id: ViewportId) -> Option<&mut RawInput> {
+        if viewport_id == ViewportId::ROOT {
+            Some(&mut self.input)
+        } else {
+            self.child_viewport_mut(viewport_id)
+                .map(|viewport| &mut viewport.input)
+        }
+    }
+
+    /// The [`ViewportInfo`] of all viewports, without their events.
+    fn viewport_infos(&self) -> ViewportIdMap<ViewportInfo> {
+        std::iter::once(&self.input)
+            .chain(self.viewports.iter().map(|viewport| &viewport.input))
+            .filter_map(|input| {
+                let mut info = input.viewports.get(&input.viewport_id)?.clone();
+                info.events.clear();
+                Some((input.viewport_id, info))
+            })
+            .collect()
+    }
+
+    /// Take the input for the next pass of the given viewport,
+    /// including the [`ViewportInfo`] of all other viewports.
+    pub(crate) fn take_viewport_input(&mut self, viewport_id: ViewportId) -> RawInput {
+        let infos = if self.ctx.embed_viewports() {
+            ViewportIdMap::default()
+        } else {
+            self.viewport_infos()
+        };
+        let step_dt = self.step_dt;
+        let Some(input) = self.viewport_input_mut(viewport_id) else {
+            return RawInput::default();
+        };
+        input.predicted_dt = step_dt;
+        let mut input = input.take();
+        for (id, info) in infos {
+            input.viewports.entry(id).or_insert(info);
+        }
+        input
+    }
+
+    /// Install the callback running the immediate viewports shown in the next pass.
+    pub(crate) fn prepare_immediate_viewports(&mut self) {
+        let immediate_ids: Vec<_> = self
+            .viewports
+            .iter()
+            .filter(|viewport| viewport.class == ViewportClass::Immediate)
+            .map(|viewport| viewport.id)
+            .collect();
+        let inputs = immediate_ids
+            .into_iter()
+            .map(|id| (id, self.take_viewport_input(id)))
+            .collect();
+        let new_viewport_input = RawInput {
+            viewports: self.viewport_infos(),
+            predicted_dt: self.step_dt,
+            ..Default::default()
+        };
+        *self.immediate_viewports.borrow_mut() = ImmediateViewports {
+            inputs,
+            new_viewport_input,
+            ran: Vec::new(),
+        };
+        set_immediate_viewport_renderer(&self.immediate_viewports);
+    }
+
+    /// Store the outputs of the immediate viewports that ran during the last pass.
+    pub(crate) fn handle_immediate_viewports(&mut self) {
+        let ImmediateViewports { inputs, ran, .. } =
+            std::mem::take(&mut *self.immediate_viewports.borrow_mut());
+
+        // Keep the input of the viewports that didn't run, e.g. because their parent didn't run.
+        for (id, mut input) in inputs {
+            if let Some(viewport) = self.child_viewport_mut(id) {
+                input.viewports.retain(|viewport_id, _| *viewport_id == id);
+                viewport.input = input;
+            }
+        }
+
+        let native_pixels_per_point = self.native_pixels_per_point();
+        for (ids, builder, output) in ran {
+            self.renderer.handle_delta(&output.textures_delta);
+            self.platform.record(ids.this, &output);
+            let viewport = if let Some(viewport) = self.child_viewport_mut(ids.this) {
+                viewport
+            } else {
+                self.viewports.push(ChildViewport::new(
+                    ids,
+                    ViewportClass::Immediate,
+                    builder.clone(),
+                    native_pixels_per_point,
+                ));
+                self.viewports.last_mut().expect("We just pushed")
+            };
+            viewport.parent = ids.parent;
+            viewport.builder = builder;
+            viewport.update(output);
+        }
+    }
+
+    /// Does any viewport want to be repainted right away?
+    pub(crate) fn needs_repaint(&self) -> bool {
+        std::iter::once(&self.output)
+            .chain(
+                self.viewports
+                    .iter()
+                    .filter(|viewport| viewport.class == ViewportClass::Deferred)
+                    .filter_map(|viewport| viewport.output.as_ref()),
+            )
+            .flat_map(|output| output.viewport_output.values())
+            .any(|output| output.repaint_delay.is_zero())
+    }
+
+    fn native_pixels_per_point(&self) -> Option<f32> {
+        self.input
+            .viewports
+            .get(&ViewportId::ROOT)
+            .and_then(|info| info.native_pixels_per_point)
+    }
+
+    /// The ids of all viewports, starting with [`ViewportId::ROOT`].
+    ///
+    /// Child viewports are only listed once they have run.
+    pub fn viewport_ids(&self) -> Vec<ViewportId> {
+        std::iter::once(ViewportId::ROOT)
+            .chain(
+                self.viewports
+                    .iter()
+                    .filter(|viewport| viewport.output.is_some())
+                    .map(|viewport| viewport.id),
+            )
+            .collect()
+    }
+
+    /// The root node of the given viewport.
+    ///
+    /// Use this to query and interact with the widgets of a child viewport.
+    /// Events sent to its nodes go to that viewport.
+    ///
+    /// # Panics
+    /// Panics if there is no such viewport (see [`Self::viewport_ids`]).
+    #[track_caller]
+    pub fn viewport_root(&self, viewport_id: ViewportId) -> Node<'_> {
+        if viewport_id == ViewportId::ROOT {
+            return self.root();
+        }
+        let Some((viewport, kittest)) = self
+            .child_viewport(viewport_id)
+            .and_then(|viewport| Some((viewport, viewport.kittest.as_ref()?)))
+        else {
+            panic!("There is no viewport {viewport_id:?}");
+        };
+        Node {
+            accesskit_node: kittest.root(),
+            queue: &viewport.queued_events,
+        }
+    }
+
+    /// The [`ViewportInfo`] that will be passed to the next pass of the viewport,
+    /// e.g. its size, title and focus.
+    pub fn viewport_info(&self, viewport_id: ViewportId) -> Option<&ViewportInfo> {
+        if viewport_id == ViewportId::ROOT {
+            self.input.viewports.get(&viewport_id)
+        } else {
+            self.child_viewport(viewport_id)?
+                .input
+                .viewports
+                .get(&viewport_id)
+        }
+    }
+
+    /// The [`ViewportBuilder`] the app used in the last pass to show the child viewport.
+    pub fn viewport_builder(&self, viewport_id: ViewportId) -> Option<&ViewportBuilder> {
+        Some(&self.child_viewport(viewport_id)?.builder)
+    }
+
+    /// Access the [`egui::FullOutput`] for the last pass of the viewport.
+    pub fn viewport_output(&self, viewport_id: ViewportId) -> Option<&FullOutput> {
+        if viewport_id == ViewportId::ROOT {
+            Some(&self.output)
+        } else {
+            self.child_viewport(viewport_id)?.output.as_ref()
+        }
+    }
+
+    /// Resize the viewport, as if the user resized the window.
+    pub fn set_viewport_size(&mut self, viewport_id: ViewportId, size: Vec2) -> &mut Self {
+        if viewport_id == ViewportId::ROOT {
+            self.set_size(size);
+        } else if let Some(viewport) = self.child_viewport_mut(viewport_id) {
+            viewport.set_size(size);
+        }
+        self
+    }
+
+    /// Request the viewport to close, as if the user clicked the close button of the window.
+    ///
+    /// The app sees this in [`ViewportInfo::close_requested`] in the next pass.
+    /// A child viewport stays open until the app stops showing it.
+    pub fn close_viewport(&mut self, viewport_id: ViewportId) {
+        let parent = self
+            .child_viewport(viewport_id)
+            .map_or(ViewportId::ROOT, |viewport| viewport.parent);
+        if let Some(input) = self.viewport_input_mut(viewport_id) {
+            input
+                .viewports
+                .entry(viewport_id)
+                .or_default()
+                .events
+                .push(ViewportEvent::Close);
+
+            // Like eframe, repaint both the viewport and its parent, so the app can close it.
+            self.ctx.request_repaint_of(viewport_id);
+            self.ctx.request_repaint_of(parent);
+        }
+    }
+
+    /// Give the keyboard focus to the viewport, as if the user clicked on the window.
+    ///
+    /// Until this is called (or a [`ViewportCommand::Focus`] is sent), all viewports are considered focused.
+    pub fn focus_viewport(&mut self, viewport_id: ViewportId) {
+        let inputs = std::iter::once(&mut self.input).chain(
+            self.viewports
+                .iter_mut()
+                .map(|viewport| &mut viewport.input),
+        );
+        for input in inputs {
+            let focused = input.viewport_id == viewport_id;
+            input.focused = focused;
+            input
+                .viewports
+                .entry(input.viewport_id)
+                .or_default()
+                .focused = Some(focused);
+        }
+    }
+
+    /// Render the last output of the viewport to an image.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such viewport or if the rendering fails.
+    #[cfg(any(feature = "wgpu", feature = "snapshot"))]
+    pub fn render_viewport(&mut self, viewport_id: ViewportId) -> Result<image::RgbaImage, String> {
+        if viewport_id == ViewportId::ROOT {
+            return self.render();
+        }
+        let output = self
+            .viewports
+            .iter()
+            .find(|viewport| viewport.id == viewport_id)
+            .and_then(|viewport| viewport.output.as_ref())
+            .ok_or_else(|| format!("There is no viewport {viewport_id:?}"))?;
+        self.renderer
+            .render_viewport(&self.ctx, output, viewport_id)
+    }
+}
+
+impl<State> ViewportSimulator for Harness<'_, State> {
+    fn deferred_viewports(&self) -> Vec<(ViewportId, Arc<DeferredViewportUiCallback>)> {
+        self.viewports
+            .iter()
+            .filter_map(|viewport| Some((viewport.id, viewport.viewport_ui_cb.clone()?)))
+            .collect()
+    }
+
+    fn contains_viewport(&self, viewport_id: ViewportId) -> bool {
+        self.child_viewport(viewport_id).is_some()
+    }
+
+    fn run_viewport(
+        &mut self,
+        viewport_id: ViewportId,
+        viewport_ui_cb: &DeferredViewportUiCallback,
+    ) -> FullOutput {
+        self.prepare_immediate_viewports();
+        let input = self.take_viewport_input(viewport_id);
+        let output = self.ctx.run(input, |ctx| viewport_ui_cb(ctx));
+        self.handle_immediate_viewports();
+
+        self.renderer.handle_delta(&output.textures_delta);
+        self.platform.record(viewport_id, &output);
+        output
+    }
+
+    fn handle_viewport_output(&mut self, viewport_output: &ViewportIdMap<ViewportOutput>) {
+        let native_pixels_per_point = self.native_pixels_per_point();
+        for (id, output) in viewport_output {
+            let ids = ViewportIdPair::from_self_and_parent(*id, output.parent);
+            for command in &output.commands {
+                if *command == ViewportCommand::Focus {
+                    self.focus_viewport(*id);
+                }
+            }
+            if *id == ViewportId::ROOT {
+                continue;
+            }
+
+            let viewport = if let Some(viewport) = self.child_viewport_mut(*id) {
+                viewport
+            } else {
+                self.viewports.push(ChildViewport::new(
+                    ids,
+                    output.class,
+                    output.builder.clone(),
+                    native_pixels_per_point,
+                ));
+                self.viewports.last_mut().expect("We just pushed")
+            };
+            viewport.parent = output.parent;
+            // egui doesn't report the class of immediate viewports.
+            if viewport.class != ViewportClass::Immediate || output.viewport_ui_cb.is_some() {
+                viewport.class = output.class;
+            }
+            viewport.builder = output.builder.clone();
+            viewport.viewport_ui_cb.clone_from(&output.viewport_ui_cb);
+            for command in &output.commands {
+                viewport.apply_command(command);
+            }
+        }
+
+        self.viewports
+            .retain(|viewport| viewport_output.contains_key(&viewport.id));
+    }
+
+    fn set_viewport_output(&mut self, viewport_id: ViewportId, output: FullOutput) {
+        if let Some(viewport) = self.child_viewport_mut(viewport_id) {
+            viewport.update(output);
+        }
+    }
+}
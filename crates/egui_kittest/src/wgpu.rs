@@ -133,6 +133,15 @@ impl crate::TestRenderer for WgpuTestRenderer {
         &mut self,
         ctx: &egui::Context,
         output: &egui::FullOutput,
+    ) -> Result<RgbaImage, String> {
+        self.render_viewport(ctx, output, egui::ViewportId::ROOT)
+    }
+
+    fn render_viewport(
+        &mut self,
+        ctx: &egui::Context,
+        output: &egui::FullOutput,
+        viewport_id: egui::ViewportId,
     ) -> Result<RgbaImage, String> {
         let mut renderer = self.render_state.renderer.write();
 
@@ -143,13 +152,16 @@ impl crate::TestRenderer for WgpuTestRenderer {
                     label: Some("Egui Command Encoder"),
                 });
 
-        let size = ctx.screen_rect().size() * ctx.pixels_per_point();
+        let (screen_rect, pixels_per_point) = ctx.input_for(viewport_id, |input| {
+            (input.screen_rect(), input.pixels_per_point())
+        });
+        let size = screen_rect.size() * pixels_per_point;
         let screen = ScreenDescriptor {
-            pixels_per_point: ctx.pixels_per_point(),
+            pixels_per_point,
             size_in_pixels: [size.x.round() as u32, size.y.round() as u32],
         };
 
-        let tessellated = ctx.tessellate(output.shapes.clone(), ctx.pixels_per_point());
+        let tessellated = ctx.tessellate(output.shapes.clone(), pixels_per_point);
 
         let user_buffers = renderer.update_buffers(
             &self.render_state.device,
@@ -0,0 +1,106 @@
+use egui::load::SizedTexture;
+use egui::{Sense, TextureId, vec2};
+use egui_kittest::{AccessibilityViolation, AuditOptions, Harness, ViolationKind};
+
+fn kinds(violations: &[AccessibilityViolation]) -> Vec<(ViolationKind, Option<&str>)> {
+    violations
+        .iter()
+        .map(|violation| (violation.kind.clone(), violation.label.as_deref()))
+        .collect()
+}
+
+#[test]
+fn accessible_ui_has_no_violations() {
+    let harness = Harness::new_ui(|ui| {
+        ui.label("Settings");
+        ui.checkbox(&mut true, "Enabled");
+        ui.horizontal(|ui| {
+            let _ = ui.button("Save");
+            let _ = ui.button("Cancel");
+        });
+        ui.add(
+            egui::Image::new(SizedTexture::new(TextureId::default(), [32.0, 32.0]))
+                .alt_text("Logo"),
+        );
+    });
+    harness.assert_accessible();
+}
+
+#[test]
+fn tree_violations() {
+    let harness = Harness::new_ui(|ui| {
+        ui.add(egui::Image::new(SizedTexture::new(
+            TextureId::default(),
+            [32.0, 32.0],
+        )));
+        ui.add_sized([100.0, 30.0], egui::Button::new(""));
+        let _ = ui.button("Delete");
+        let _ = ui.button("Delete");
+        let (rect, _) = ui.allocate_exact_size(vec2(100.0, 8.0), Sense::hover());
+        ui.interact(rect, ui.id().with("strip"), Sense::CLICK)
+            .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, "Strip"));
+    });
+
+    let violations = harness.accessibility_audit();
+    assert_eq!(
+        kinds(&violations),
+        vec![
+            (ViolationKind::MissingAltText, None),
+            (ViolationKind::MissingLabel, None),
+            (ViolationKind::DuplicateLabel, Some("Delete")),
+            (
+                ViolationKind::SmallTarget {
+                    size: vec2(100.0, 8.0),
+                    min_size: vec2(18.0, 18.0),
+                },
+                Some("Strip")
+            ),
+            (ViolationKind::NotFocusable, Some("Strip")),
+        ]
+    );
+
+    let violations =
+        harness.accessibility_audit_options(&AuditOptions::new().min_target_size([120.0, 0.0]));
+    assert_eq!(
+        violations
+            .iter()
+            .filter(|violation| matches!(violation.kind, ViolationKind::SmallTarget { .. }))
+            .count(),
+        4
+    );
+}
+
+#[test]
+fn keyboard_audit() {
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut String| {
+            let _ = ui.button("Before");
+            ui.add(egui::TextEdit::multiline(text).lock_focus(true));
+            let _ = ui.button("After");
+        },
+        String::new(),
+    );
+
+    let violations = harness.keyboard_audit();
+    assert_eq!(
+        kinds(&violations),
+        vec![(ViolationKind::Unreachable, Some("After"))]
+    );
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn contrast_audit() {
+    let mut harness = Harness::new_ui(|ui| {
+        ui.label(egui::RichText::new("Readable").color(egui::Color32::WHITE));
+        ui.label(egui::RichText::new("Faint").color(egui::Color32::from_gray(40)));
+    });
+
+    let violations = harness.contrast_audit(&AuditOptions::default()).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].label.as_deref(), Some("Faint"));
+    assert!(matches!(
+        violations[0].kind,
+        ViolationKind::LowContrast { ratio, .. } if ratio < 2.0
+    ));
+}
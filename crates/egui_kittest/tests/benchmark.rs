@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use egui_kittest::{BenchmarkRegression, BenchmarkThresholds, Harness};
+
+fn labels(ui: &mut egui::Ui) {
+    for i in 0..20 {
+        ui.label(format!("Label {i}"));
+    }
+}
+
+#[test]
+fn benchmark_counts() {
+    let mut harness = Harness::new_ui(labels);
+    let report = harness.benchmark(5);
+
+    assert_eq!(report.frames.len(), 5);
+    for frame in &report.frames {
+        assert!(frame.num_shapes >= 20, "{frame:?}");
+        assert!(frame.num_vertices > 0);
+        assert!(frame.num_indices > 0);
+        assert!(frame.num_clipped_primitives > 0);
+        assert!(frame.num_galleys_in_cache >= 20);
+        assert_eq!(frame.galley_cache.misses, 0, "The labels don't change");
+    }
+    assert_eq!(report.galley_cache_hit_rate(), 1.0);
+    assert_eq!(report.total_time(), {
+        let layout: Duration = report.frames.iter().map(|frame| frame.layout).sum();
+        let tessellation: Duration = report.frames.iter().map(|frame| frame.tessellation).sum();
+        layout + tessellation
+    });
+}
+
+#[test]
+fn changing_text_misses_the_cache() {
+    let mut harness = Harness::new_ui_state(
+        |ui, frame: &mut u32| {
+            *frame += 1;
+            ui.label(format!("Frame {frame}"));
+            ui.label("Constant");
+        },
+        0,
+    );
+    let report = harness.benchmark(4);
+
+    assert!(
+        report
+            .frames
+            .iter()
+            .all(|frame| frame.galley_cache.misses >= 1)
+    );
+    assert!(report.galley_cache_hit_rate() < 1.0);
+
+    let regressions =
+        report.regressions(&BenchmarkThresholds::new().min_galley_cache_hit_rate(0.99));
+    assert!(matches!(
+        regressions[..],
+        [BenchmarkRegression::GalleyCacheHitRate { .. }]
+    ));
+}
+
+#[test]
+fn all_passes_are_counted() {
+    let mut harness = Harness::new_ui_state(
+        |ui, pass: &mut u32| {
+            *pass += 1;
+            ui.label(format!("Pass {pass}"));
+            if *pass % 2 == 1 {
+                ui.ctx().request_discard("test");
+            }
+        },
+        0,
+    );
+    let passes_before = *harness.state();
+    let report = harness.benchmark(3);
+
+    assert_eq!(*harness.state(), passes_before + 6, "Two passes per frame");
+    for frame in &report.frames {
+        assert_eq!(
+            frame.galley_cache.misses, 2,
+            "One new label per pass: {frame:?}"
+        );
+    }
+}
+
+#[test]
+fn thresholds() {
+    let mut harness = Harness::new_ui(labels);
+    let report = harness.benchmark(3);
+
+    report.assert_within(
+        &BenchmarkThresholds::new()
+            .max_layout_time(Duration::from_secs(10))
+            .max_tessellation_time(Duration::from_secs(10))
+            .max_shapes(1000),
+    );
+
+    let regressions =
+        report.regressions(&BenchmarkThresholds::new().max_shapes(10).max_vertices(10));
+    assert_eq!(regressions.len(), 2);
+    assert!(matches!(
+        regressions[0],
+        BenchmarkRegression::Shapes { max: 10, .. }
+    ));
+    assert!(matches!(
+        regressions[1],
+        BenchmarkRegression::Vertices { max: 10, .. }
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Benchmark regressions")]
+fn assert_within_panics() {
+    let mut harness = Harness::new_ui(labels);
+    harness
+        .benchmark(1)
+        .assert_within(&BenchmarkThresholds::new().max_galleys_in_cache(1));
+}
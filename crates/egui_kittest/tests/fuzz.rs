@@ -0,0 +1,136 @@
+use egui::{Id, Rect, Sense, pos2};
+use egui_kittest::{FuzzAction, FuzzIssue, FuzzOptions, Harness, fuzz};
+
+#[test]
+fn well_behaved_ui_passes() {
+    let result = fuzz(&FuzzOptions::new().runs(3).steps(30), || {
+        Harness::new_ui_state(
+            |ui, (text, checked): &mut (String, bool)| {
+                ui.text_edit_singleline(text);
+                ui.checkbox(checked, "Checked");
+                if ui.button("Clear").clicked() {
+                    text.clear();
+                }
+            },
+            (String::new(), false),
+        )
+    });
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+}
+
+fn counter_that_panics() -> Harness<'static, u32> {
+    Harness::new_ui_state(
+        |ui, count: &mut u32| {
+            ui.label("Some text");
+            if ui.button("Increment").clicked() {
+                *count += 1;
+            }
+            assert!(*count < 3, "Count is too high");
+        },
+        0,
+    )
+}
+
+#[test]
+fn panics_are_found_and_shrunk() {
+    let failure = fuzz(&FuzzOptions::new(), counter_that_panics).unwrap_err();
+
+    assert_eq!(
+        failure.issue,
+        FuzzIssue::Panic("Count is too high".to_owned())
+    );
+    assert_eq!(failure.actions.len(), 3);
+    assert!(
+        failure
+            .actions
+            .iter()
+            .all(|action| matches!(action, FuzzAction::Click { .. }))
+    );
+
+    // The failure is reproducible:
+    let again = fuzz(&FuzzOptions::new(), counter_that_panics).unwrap_err();
+    assert_eq!(failure.seed, again.seed);
+    assert_eq!(failure.actions, again.actions);
+
+    let mut harness = counter_that_panics();
+    for action in &failure.actions[..2] {
+        action.apply(&mut harness);
+        harness.step();
+    }
+    assert_eq!(*harness.state(), 2);
+}
+
+#[test]
+fn panics_in_the_first_frame_are_found() {
+    let failure = fuzz(&FuzzOptions::new(), || {
+        Harness::new_ui(|_ui| panic!("Broken from the start"))
+    })
+    .unwrap_err();
+
+    assert!(failure.actions.is_empty());
+    assert_eq!(
+        failure.issue,
+        FuzzIssue::Panic("Broken from the start".to_owned())
+    );
+}
+
+#[test]
+fn id_clashes_are_found() {
+    let failure = fuzz(&FuzzOptions::new().runs(1), || {
+        Harness::new_ui(|ui| {
+            for x in [10.0, 100.0] {
+                let rect = Rect::from_min_size(pos2(x, 10.0), egui::vec2(50.0, 20.0));
+                ui.interact(rect, Id::new("same"), Sense::click());
+            }
+        })
+    })
+    .unwrap_err();
+
+    assert!(failure.actions.is_empty());
+    assert!(matches!(failure.issue, FuzzIssue::IdClash(_)), "{failure}");
+}
+
+#[test]
+fn widgets_outside_their_clip_rect_are_found() {
+    let make_harness = || {
+        Harness::new_ui(|ui| {
+            ui.scope(|ui| {
+                ui.set_clip_rect(Rect::from_min_size(pos2(0.0, 0.0), egui::vec2(100.0, 20.0)));
+                let _ = ui.button("Visible");
+                let _ = ui.button("Clipped");
+            });
+        })
+    };
+
+    let options = FuzzOptions::new().runs(1).check_outside_clip_rect(true);
+    let failure = fuzz(&options, make_harness).unwrap_err();
+    assert!(failure.actions.is_empty());
+    assert!(
+        matches!(
+            &failure.issue,
+            FuzzIssue::OutsideClipRect { label, .. } if label.as_deref() == Some("Clipped")
+        ),
+        "{failure}"
+    );
+
+    assert!(fuzz(&FuzzOptions::new().runs(1), make_harness).is_ok());
+}
+
+#[test]
+fn widgets_outside_the_screen_are_found() {
+    let make_harness = || {
+        Harness::builder().with_size([200.0, 200.0]).build_ui(|ui| {
+            ui.add_space(300.0);
+            let _ = ui.button("Hidden");
+        })
+    };
+
+    let options = FuzzOptions::new().runs(1).check_outside_screen(true);
+    let failure = fuzz(&options, make_harness).unwrap_err();
+    assert!(matches!(
+        &failure.issue,
+        FuzzIssue::OutsideScreen { label, .. } if label.as_deref() == Some("Hidden")
+    ));
+
+    assert!(fuzz(&FuzzOptions::new().runs(1), make_harness).is_ok());
+}
@@ -0,0 +1,138 @@
+use egui::accesskit::Role;
+use egui::{CursorIcon, Key, Modifiers, OutputCommand, ViewportCommand, ViewportId};
+use egui_kittest::{Harness, PlatformRequest};
+use kittest::Queryable as _;
+
+#[test]
+fn copy_and_paste() {
+    let mut harness = Harness::new_ui_state(
+        |ui, texts: &mut [String; 2]| {
+            for text in texts {
+                ui.text_edit_singleline(text);
+            }
+        },
+        ["Hello".to_owned(), String::new()],
+    );
+
+    harness
+        .get_all_by_role(Role::TextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+    harness.key_press_modifiers(Modifiers::COMMAND, Key::A);
+    harness.key_press_modifiers(Modifiers::COMMAND, Key::C);
+    harness.run();
+    assert_eq!(harness.copied_texts().collect::<Vec<_>>(), vec!["Hello"]);
+    assert_eq!(harness.clipboard_text(), "Hello");
+
+    harness
+        .get_all_by_role(Role::TextInput)
+        .nth(1)
+        .unwrap()
+        .focus();
+    harness.run();
+    harness.key_press_modifiers(Modifiers::COMMAND, Key::V);
+    harness.run();
+    assert_eq!(harness.state()[1], "Hello");
+
+    harness.set_clipboard_text(", world");
+    harness.key_press_modifiers(Modifiers::COMMAND, Key::V);
+    harness.run();
+    assert_eq!(harness.state()[1], "Hello, world");
+}
+
+#[test]
+fn clipboard_shortcuts_keep_the_key_press() {
+    let mut harness = Harness::new_ui_state(
+        |ui, events: &mut Vec<egui::Event>| {
+            events.extend(ui.input(|i| i.events.clone()));
+        },
+        Vec::new(),
+    );
+
+    harness.key_press_modifiers(Modifiers::COMMAND, Key::C);
+    harness.run();
+    let events = harness.state();
+    let key_index = events.iter().position(|event| {
+        matches!(
+            event,
+            egui::Event::Key {
+                key: Key::C,
+                pressed: true,
+                ..
+            }
+        )
+    });
+    let copy_index = events
+        .iter()
+        .position(|event| matches!(event, egui::Event::Copy));
+    assert!(key_index.is_some(), "{events:?}");
+    assert_eq!(copy_index, key_index.map(|index| index + 1), "{events:?}");
+}
+
+#[test]
+fn open_url_and_viewport_commands() {
+    let mut harness = Harness::new_ui(|ui| {
+        ui.hyperlink_to("egui", "https://github.com/emilk/egui");
+        if ui.button("Rename").clicked() {
+            ui.ctx()
+                .send_viewport_cmd(ViewportCommand::Title("Renamed".to_owned()));
+        }
+    });
+    assert!(harness.platform_requests().is_empty());
+
+    harness.get_by_label("egui").click();
+    harness.run();
+    let urls: Vec<_> = harness.opened_urls().map(|url| url.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://github.com/emilk/egui"]);
+
+    harness.take_platform_requests();
+    harness.get_by_label("Rename").click();
+    harness.run();
+    assert_eq!(
+        harness
+            .viewport_commands(ViewportId::ROOT)
+            .collect::<Vec<_>>(),
+        vec![&ViewportCommand::Title("Renamed".to_owned())]
+    );
+    assert!(
+        !harness
+            .platform_requests()
+            .iter()
+            .any(|request| matches!(request, PlatformRequest::Command(OutputCommand::OpenUrl(_))))
+    );
+}
+
+#[test]
+fn cursor_icon_and_ime() {
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut String| {
+            ui.button("Button")
+                .on_hover_cursor(CursorIcon::PointingHand);
+            ui.text_edit_singleline(text);
+        },
+        String::new(),
+    );
+
+    harness.get_by_label("Button").hover();
+    harness.run();
+    assert_eq!(harness.cursor_icon(), CursorIcon::PointingHand);
+    assert!(
+        harness
+            .platform_requests()
+            .contains(&PlatformRequest::CursorIcon {
+                viewport_id: ViewportId::ROOT,
+                icon: CursorIcon::PointingHand,
+            })
+    );
+
+    harness.get_by_role(Role::TextInput).focus();
+    harness.run();
+    assert!(
+        harness
+            .platform_requests()
+            .iter()
+            .any(|request| matches!(request, PlatformRequest::Ime { ime: Some(_), .. }))
+    );
+}
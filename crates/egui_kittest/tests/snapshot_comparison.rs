@@ -0,0 +1,123 @@
+#![cfg(feature = "snapshot")]
+
+use std::path::PathBuf;
+
+use egui::{Rect, pos2};
+use egui_kittest::{SnapshotComparison, SnapshotOptions, try_image_snapshot_options};
+use image::{Rgba, RgbaImage};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Save `reference` as the snapshot `name` and compare `new` to it.
+fn compare(name: &str, reference: &RgbaImage, new: &RgbaImage, options: SnapshotOptions) -> bool {
+    let output_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("snapshot_comparison");
+    std::fs::create_dir_all(&output_path).unwrap();
+    reference
+        .save(output_path.join(format!("{name}.png")))
+        .unwrap();
+    try_image_snapshot_options(new, name, &options.output_path(output_path)).is_ok()
+}
+
+/// A white image with a black vertical line at `line_x`.
+fn line_image(line_x: u32) -> RgbaImage {
+    RgbaImage::from_fn(32, 32, |x, _| if x == line_x { BLACK } else { WHITE })
+}
+
+#[test]
+fn ignored_regions() {
+    let reference = line_image(4);
+    let mut new = reference.clone();
+    for (x, y) in [(20, 20), (21, 20), (20, 21)] {
+        new.put_pixel(x, y, BLACK);
+    }
+
+    assert!(!compare(
+        "ignored",
+        &reference,
+        &new,
+        SnapshotOptions::new()
+    ));
+    let rect = Rect::from_min_max(pos2(10.0, 10.0), pos2(15.0, 15.0));
+    let options = SnapshotOptions::new()
+        .ignore_rect(rect)
+        .pixels_per_point(2.0);
+    assert!(compare("ignored", &reference, &new, options));
+}
+
+#[test]
+fn region_thresholds() {
+    let reference = line_image(4);
+    let mut new = reference.clone();
+    for x in 20..24 {
+        new.put_pixel(x, 20, BLACK);
+    }
+    let rect = Rect::from_min_max(pos2(16.0, 16.0), pos2(32.0, 32.0));
+
+    let options = SnapshotOptions::new().region(rect, 0.6, 4);
+    assert!(compare("region", &reference, &new, options));
+    let options = SnapshotOptions::new().region(rect, 0.6, 3);
+    assert!(!compare("region", &reference, &new, options));
+
+    // Ignored regions take precedence over the region thresholds:
+    let ignored = Rect::from_min_max(pos2(20.0, 16.0), pos2(22.0, 24.0));
+    let options = SnapshotOptions::new()
+        .region(rect, 0.6, 2)
+        .ignore_rect(ignored);
+    assert!(compare("region", &reference, &new, options));
+
+    // The rest of the image still uses the global thresholds:
+    new.put_pixel(8, 8, BLACK);
+    let options = SnapshotOptions::new().region(rect, 0.6, 4);
+    assert!(!compare("region", &reference, &new, options));
+}
+
+#[test]
+fn edge_tolerant() {
+    let reference = line_image(4);
+    let shifted = line_image(5);
+
+    assert!(!compare(
+        "edge",
+        &reference,
+        &shifted,
+        SnapshotOptions::new()
+    ));
+    let options =
+        SnapshotOptions::new().comparison(SnapshotComparison::EdgeTolerant { max_shift: 1 });
+    assert!(compare("edge", &reference, &shifted, options));
+
+    let options =
+        SnapshotOptions::new().comparison(SnapshotComparison::EdgeTolerant { max_shift: 1 });
+    assert!(!compare("edge", &reference, &line_image(8), options));
+}
+
+#[test]
+fn ssim() {
+    let reference = RgbaImage::from_fn(32, 32, |x, y| {
+        let value = ((x * 8 + y * 4) % 200) as u8 + 20;
+        Rgba([value, value, value, 255])
+    });
+    let brighter = RgbaImage::from_fn(32, 32, |x, y| {
+        let Rgba([r, g, b, a]) = *reference.get_pixel(x, y);
+        Rgba([r + 3, g + 3, b + 3, a])
+    });
+
+    assert!(!compare(
+        "ssim",
+        &reference,
+        &brighter,
+        SnapshotOptions::new()
+    ));
+    let options = SnapshotOptions::new().comparison(SnapshotComparison::Ssim { min_score: 0.95 });
+    assert!(compare("ssim", &reference, &brighter, options));
+
+    let mut changed = reference.clone();
+    for y in 8..16 {
+        for x in 8..16 {
+            changed.put_pixel(x, y, BLACK);
+        }
+    }
+    let options = SnapshotOptions::new().comparison(SnapshotComparison::Ssim { min_score: 0.95 });
+    assert!(!compare("ssim", &reference, &changed, options));
+}
@@ -138,3 +138,95 @@ fn test_scroll_down() {
         "The button was not clicked after scrolling down. (Probably not scrolled enough / at all)"
     );
 }
+
+#[test]
+fn drag_pinch_and_drop_files() {
+    #[derive(Default)]
+    struct State {
+        value: f32,
+        zoom: f32,
+        hovered_files: usize,
+        dropped_files: Vec<String>,
+    }
+    let mut harness = Harness::new_ui_state(
+        |ui, state: &mut State| {
+            ui.spacing_mut().slider_width = 200.0;
+            ui.add(egui::Slider::new(&mut state.value, 0.0..=100.0));
+            let response = ui.label("Drop here");
+            if response.contains_pointer() {
+                state.zoom *= ui.input(|i| i.zoom_delta());
+            }
+            ui.input(|i| {
+                state.hovered_files = state.hovered_files.max(i.raw.hovered_files.len());
+                state
+                    .dropped_files
+                    .extend(i.raw.dropped_files.iter().map(|file| file.name.clone()));
+            });
+        },
+        State {
+            zoom: 1.0,
+            ..Default::default()
+        },
+    );
+
+    let slider = harness.get_by_role(egui::accesskit::Role::Slider);
+    let rect = slider.rect();
+    slider.drag_to(rect.right_center() + Vec2::X * 100.0);
+    harness.run();
+    assert_eq!(harness.state().value, 100.0);
+
+    harness
+        .get_by_role(egui::accesskit::Role::Slider)
+        .drag_by_with(
+            Vec2::X * -1000.0,
+            egui_kittest::DragOptions {
+                steps: 3,
+                ..Default::default()
+            },
+        );
+    harness.run();
+    assert_eq!(harness.state().value, 0.0);
+
+    harness.get_by_label("Drop here").pinch(2.0);
+    harness.run();
+    let zoom = harness.state().zoom;
+    assert!((zoom - 2.0).abs() < 0.01, "zoom: {zoom}");
+
+    harness
+        .get_by_label("Drop here")
+        .drop_files(vec![egui::DroppedFile {
+            name: "file.txt".to_owned(),
+            ..Default::default()
+        }]);
+    harness.run();
+    assert_eq!(harness.state().hovered_files, 1);
+    assert_eq!(harness.state().dropped_files, vec!["file.txt".to_owned()]);
+}
+
+#[test]
+fn snapshot_results_json() {
+    let mut results = SnapshotResults::new();
+    results.add(Ok(()));
+    results.add(Err(egui_kittest::SnapshotError::SizeMismatch {
+        name: "menu/\"opened\"".to_owned(),
+        expected: (100, 50),
+        actual: (120, 50),
+    }));
+
+    let json: serde_json::Value = serde_json::from_str(&results.to_json()).unwrap();
+    assert_eq!(json["checked"], 2);
+    assert_eq!(json["failed"], 1);
+    let error = &json["errors"][0];
+    assert_eq!(error["kind"], "size_mismatch");
+    assert_eq!(error["name"], "menu/\"opened\"");
+    assert_eq!(error["expected"], serde_json::json!([100, 50]));
+    assert_eq!(error["actual"], serde_json::json!([120, 50]));
+    assert!(error["message"].is_string());
+    results.into_inner();
+
+    let json: serde_json::Value = serde_json::from_str(&SnapshotResults::new().to_json()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "checked": 0, "failed": 0, "errors": [] })
+    );
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use egui::{CentralPanel, ViewportBuilder, ViewportClass, ViewportCommand, ViewportId};
+use egui_kittest::Harness;
+use egui_kittest::kittest::Queryable as _;
+
+#[derive(Default)]
+struct State {
+    show_immediate: bool,
+    immediate_clicks: usize,
+    immediate_close_requested: bool,
+    immediate_class: Option<ViewportClass>,
+}
+
+fn immediate_id() -> ViewportId {
+    ViewportId::from_hash_of("immediate")
+}
+
+fn deferred_id() -> ViewportId {
+    ViewportId::from_hash_of("deferred")
+}
+
+#[test]
+fn immediate_viewport() {
+    let mut harness = Harness::builder().with_embed_viewports(false).build_state(
+        |ctx, state: &mut State| {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.checkbox(&mut state.show_immediate, "Show viewport");
+            });
+            if state.show_immediate {
+                ctx.show_viewport_immediate(
+                    immediate_id(),
+                    ViewportBuilder::default()
+                        .with_title("Immediate")
+                        .with_inner_size([200.0, 100.0]),
+                    |ctx, class| {
+                        state.immediate_class = Some(class);
+                        CentralPanel::default().show(ctx, |ui| {
+                            if ui.button("Immediate button").clicked() {
+                                state.immediate_clicks += 1;
+                            }
+                        });
+                        if ctx.input(|i| i.viewport().close_requested()) {
+                            state.immediate_close_requested = true;
+                            state.show_immediate = false;
+                        }
+                    },
+                );
+            }
+        },
+        State::default(),
+    );
+
+    assert_eq!(harness.viewport_ids(), vec![ViewportId::ROOT]);
+
+    harness.get_by_label("Show viewport").click();
+    harness.run();
+    assert_eq!(
+        harness.viewport_ids(),
+        vec![ViewportId::ROOT, immediate_id()]
+    );
+    assert!(harness.state().immediate_class == Some(ViewportClass::Immediate));
+
+    // The widgets of the child viewport are in its own tree:
+    assert!(harness.query_by_label("Immediate button").is_none());
+    harness
+        .viewport_root(immediate_id())
+        .get_by_label("Immediate button")
+        .click();
+    harness.run();
+    assert_eq!(harness.state().immediate_clicks, 1);
+
+    let info = harness.viewport_info(immediate_id()).unwrap();
+    assert_eq!(info.title.as_deref(), Some("Immediate"));
+    assert_eq!(info.inner_rect.unwrap().size(), egui::vec2(200.0, 100.0));
+
+    harness.close_viewport(immediate_id());
+    harness.run();
+    assert!(harness.state().immediate_close_requested);
+    assert_eq!(harness.viewport_ids(), vec![ViewportId::ROOT]);
+}
+
+#[test]
+fn deferred_viewport() {
+    let clicks = Arc::new(AtomicUsize::new(0));
+    let mut harness = Harness::builder().with_embed_viewports(false).build({
+        let clicks = Arc::clone(&clicks);
+        move |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.label("Root");
+            });
+            let clicks = Arc::clone(&clicks);
+            ctx.show_viewport_deferred(
+                deferred_id(),
+                ViewportBuilder::default().with_title("Deferred"),
+                move |ctx, class| {
+                    assert!(class == ViewportClass::Deferred, "Should not be embedded");
+                    CentralPanel::default().show(ctx, |ui| {
+                        ui.label(format!("Size: {}", ctx.screen_rect().size()));
+                        if ui.button("Deferred button").clicked() {
+                            clicks.fetch_add(1, Ordering::Relaxed);
+                        }
+                        if ui.button("Shrink").clicked() {
+                            ctx.send_viewport_cmd(ViewportCommand::InnerSize(egui::vec2(
+                                300.0, 200.0,
+                            )));
+                        }
+                    });
+                },
+            );
+        }
+    });
+
+    assert_eq!(
+        harness.viewport_ids(),
+        vec![ViewportId::ROOT, deferred_id()]
+    );
+    let viewport = harness.viewport_root(deferred_id());
+    viewport.get_by_label("Size: [800 600]");
+    viewport.get_by_label("Deferred button").click();
+    harness.run();
+    assert_eq!(clicks.load(Ordering::Relaxed), 1);
+
+    harness
+        .viewport_root(deferred_id())
+        .get_by_label("Shrink")
+        .click();
+    harness.run();
+    harness
+        .viewport_root(deferred_id())
+        .get_by_label("Size: [300 200]");
+
+    harness.set_viewport_size(deferred_id(), egui::vec2(400.0, 300.0));
+    harness.run();
+    harness
+        .viewport_root(deferred_id())
+        .get_by_label("Size: [400 300]");
+
+    #[cfg(feature = "wgpu")]
+    {
+        let image = harness.render_viewport(deferred_id()).unwrap();
+        assert_eq!(image.dimensions(), (400, 300));
+        let image = harness.render_viewport(ViewportId::ROOT).unwrap();
+        assert_eq!(image.dimensions(), (800, 600));
+    }
+}
+
+#[test]
+fn viewport_focus() {
+    let mut harness = Harness::builder().with_embed_viewports(false).build(|ctx| {
+        CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Root focused: {}", ctx.input(|i| i.focused)));
+        });
+        ctx.show_viewport_immediate(immediate_id(), ViewportBuilder::default(), |ctx, _| {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.label(format!("Child focused: {}", ctx.input(|i| i.focused)));
+            });
+        });
+    });
+
+    harness.focus_viewport(immediate_id());
+    harness.run();
+    harness.get_by_label("Root focused: false");
+    harness
+        .viewport_root(immediate_id())
+        .get_by_label("Child focused: true");
+    assert_eq!(
+        harness.viewport_info(immediate_id()).unwrap().focused,
+        Some(true)
+    );
+
+    harness.focus_viewport(ViewportId::ROOT);
+    harness.run();
+    harness.get_by_label("Root focused: true");
+    harness
+        .viewport_root(immediate_id())
+        .get_by_label("Child focused: false");
+}
+
+#[test]
+fn embedded_by_default() {
+    let harness = Harness::new(|ctx| {
+        ctx.show_viewport_immediate(immediate_id(), ViewportBuilder::default(), |ctx, class| {
+            assert!(class == ViewportClass::Embedded, "Should be embedded");
+            egui::Window::new("Embedded").show(ctx, |ui| {
+                ui.label("Embedded label");
+            });
+        });
+    });
+    assert_eq!(harness.viewport_ids(), vec![ViewportId::ROOT]);
+    harness.get_by_label("Embedded label");
+}
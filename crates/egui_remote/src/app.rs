@@ -0,0 +1,109 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use egui::RawInput;
+
+use crate::{ClientMessage, PROTOCOL_VERSION, RemoteClient, ServerMessage};
+
+/// An [`eframe::App`] showing the UI of a remote server.
+///
+/// The input collected by `eframe` (e.g. by `eframe::web` in the browser) is sent to the server,
+/// and the frames received from the server are painted.
+///
+/// On the web, use [`crate::start_web`] to connect to a server over WebSocket.
+pub struct RemoteApp {
+    client: RemoteClient,
+    send: Box<dyn FnMut(ClientMessage) -> bool>,
+    incoming: Receiver<ServerMessage>,
+
+    /// The last input that was sent, without its events.
+    last_sent_input: Option<RawInput>,
+
+    error: Option<String>,
+}
+
+impl RemoteApp {
+    /// Create an app showing the frames received through `incoming`.
+    ///
+    /// `send` is called with the input for the server.
+    /// It should return `false` if the message could not be sent, e.g. because the connection isn't open yet.
+    ///
+    /// When the sender of `incoming` is dropped, the app shows that it is disconnected.
+    pub fn new(
+        send: impl FnMut(ClientMessage) -> bool + 'static,
+        incoming: Receiver<ServerMessage>,
+    ) -> Self {
+        Self {
+            client: RemoteClient::new(),
+            send: Box::new(send),
+            incoming,
+            last_sent_input: None,
+            error: None,
+        }
+    }
+
+    fn handle_message(&mut self, ctx: &egui::Context, message: ServerMessage) {
+        match message {
+            ServerMessage::Hello { protocol_version } => {
+                if protocol_version == PROTOCOL_VERSION {
+                    // A new connection: the server needs our input before it can show anything.
+                    self.client.reset(ctx);
+                    self.last_sent_input = None;
+                    self.error = None;
+                } else {
+                    self.error = Some(format!(
+                        "The server uses protocol version {protocol_version}, but this client uses version {PROTOCOL_VERSION}"
+                    ));
+                }
+            }
+            ServerMessage::Frame(frame) => {
+                if self.error.is_none() {
+                    self.client.apply(ctx, frame);
+                }
+            }
+        }
+    }
+}
+
+impl eframe::App for RemoteApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(message) => self.handle_message(ctx, message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.error
+                        .get_or_insert_with(|| "Disconnected from the server".to_owned());
+                    break;
+                }
+            }
+        }
+
+        if let Some(message) = self.client.resync_request() {
+            (self.send)(message);
+        }
+
+        if let Some(error) = &self.error {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| ui.label(error));
+            });
+        } else {
+            self.client.paint(ctx);
+        }
+    }
+
+    fn raw_input_hook(&mut self, _ctx: &egui::Context, raw_input: &mut RawInput) {
+        // Only send the input if something happened, so the server can stay idle.
+        let mut state = raw_input.clone();
+        state.events.clear();
+        state.time = None;
+        for info in state.viewports.values_mut() {
+            info.events.clear();
+        }
+        let changed = !raw_input.events.is_empty()
+            || raw_input.viewport().close_requested()
+            || self.last_sent_input.as_ref() != Some(&state);
+        if changed && (self.send)(ClientMessage::Input(raw_input.clone())) {
+            self.last_sent_input = Some(state);
+        }
+    }
+}
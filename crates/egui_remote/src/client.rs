@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use egui::{CursorIcon, LayerId, Mesh, OutputCommand, Rect, Shape, TextureId};
+
+use crate::{ClientMessage, FrameDelta, PrimitiveDelta};
+
+/// Shows the [`FrameDelta`]s created by a [`crate::FrameEncoder`] in a local [`egui::Context`].
+///
+/// This is the platform-independent part of [`crate::RemoteApp`].
+#[derive(Default)]
+pub struct RemoteClient {
+    /// Maps the texture ids of the server to our texture ids.
+    textures: HashMap<TextureId, TextureId>,
+
+    /// The meshes of the last frame, using our texture ids.
+    ///
+    /// Meshes we couldn't resolve are kept as empty placeholders,
+    /// so that the indices still match the ones of the server.
+    primitives: Vec<(Rect, Arc<Mesh>)>,
+
+    cursor_icon: CursorIcon,
+
+    /// Set when a frame referred to something we don't have.
+    needs_resync: bool,
+}
+
+impl RemoteClient {
+    /// A client that hasn't received any frame yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget everything about the previous server, e.g. when reconnecting.
+    pub fn reset(&mut self, ctx: &egui::Context) {
+        let tex_manager = ctx.tex_manager();
+        let mut tex_manager = tex_manager.write();
+        for (_, local_id) in self.textures.drain() {
+            tex_manager.free(local_id);
+        }
+        self.primitives.clear();
+        self.cursor_icon = CursorIcon::Default;
+        self.needs_resync = false;
+    }
+
+    /// The number of meshes in the last frame.
+    pub fn num_primitives(&self) -> usize {
+        self.primitives.len()
+    }
+
+    /// The message to send to the server if the frames got out of sync,
+    /// e.g. because a frame referred to a mesh or texture this client doesn't have.
+    ///
+    /// Until the server sends the next frame in full, the meshes that couldn't be resolved
+    /// are not painted.
+    /// Returns `None` if everything is fine, or if the resync was already requested.
+    pub fn resync_request(&mut self) -> Option<ClientMessage> {
+        std::mem::take(&mut self.needs_resync).then_some(ClientMessage::Resync)
+    }
+
+    /// Apply a frame received from the server.
+    ///
+    /// This must be called during a pass of `ctx`, since it also runs the
+    /// [`OutputCommand`]s of the server, e.g. copying text.
+    /// The scale of `ctx` is set to the `pixels_per_point` of the server from the next pass on,
+    /// so the meshes are painted the way they were tessellated.
+    pub fn apply(&mut self, ctx: &egui::Context, frame: FrameDelta) {
+        let FrameDelta {
+            pixels_per_point,
+            textures_delta,
+            primitives,
+            cursor_icon,
+            commands,
+        } = frame;
+
+        {
+            let tex_manager = ctx.tex_manager();
+            let mut tex_manager = tex_manager.write();
+            for (remote_id, delta) in textures_delta.set {
+                if let Some(local_id) = self.textures.get(&remote_id) {
+                    tex_manager.set(*local_id, delta);
+                } else if delta.pos.is_none() {
+                    let local_id = tex_manager.alloc(
+                        format!("remote {remote_id:?}"),
+                        delta.image,
+                        delta.options,
+                    );
+                    self.textures.insert(remote_id, local_id);
+                } else {
+                    log::warn!("Partial update of unknown remote texture {remote_id:?}");
+                    self.needs_resync = true;
+                }
+            }
+            for remote_id in textures_delta.free {
+                if let Some(local_id) = self.textures.remove(&remote_id) {
+                    tex_manager.free(local_id);
+                }
+            }
+        }
+
+        if ctx.pixels_per_point() != pixels_per_point {
+            ctx.set_pixels_per_point(pixels_per_point);
+        }
+
+        let last_primitives = std::mem::take(&mut self.primitives);
+        self.primitives = primitives
+            .into_iter()
+            .map(|primitive| {
+                self.resolve(&last_primitives, primitive)
+                    .unwrap_or_else(|| {
+                        self.needs_resync = true;
+                        (Rect::NOTHING, Arc::new(Mesh::default()))
+                    })
+            })
+            .collect();
+
+        self.cursor_icon = cursor_icon;
+
+        for command in commands {
+            match command {
+                OutputCommand::CopyText(text) => ctx.copy_text(text),
+                OutputCommand::CopyImage(image) => ctx.copy_image(image),
+                OutputCommand::OpenUrl(open_url) => ctx.open_url(open_url),
+            }
+        }
+    }
+
+    /// The mesh described by the server, using our texture ids.
+    ///
+    /// Returns `None` if it refers to a texture or primitive we don't have.
+    fn resolve(
+        &self,
+        last_primitives: &[(Rect, Arc<Mesh>)],
+        primitive: PrimitiveDelta,
+    ) -> Option<(Rect, Arc<Mesh>)> {
+        match primitive {
+            PrimitiveDelta::Unchanged(index) => {
+                let primitive = last_primitives.get(index as usize).cloned();
+                if primitive.is_none() {
+                    log::warn!("The server refers to an unknown primitive {index}");
+                }
+                primitive
+            }
+            PrimitiveDelta::New(remote_mesh) => {
+                let mut mesh = remote_mesh.mesh;
+                let Some(local_id) = self.textures.get(&mesh.texture_id) else {
+                    log::warn!(
+                        "The server refers to an unknown texture {:?}",
+                        mesh.texture_id
+                    );
+                    return None;
+                };
+                mesh.texture_id = *local_id;
+                Some((remote_mesh.clip_rect, Arc::new(mesh)))
+            }
+            PrimitiveDelta::Patch {
+                base,
+                clip_rect,
+                keep_vertices,
+                vertices,
+                keep_indices,
+                indices,
+            } => {
+                let Some((_, base_mesh)) = last_primitives.get(base as usize) else {
+                    log::warn!("The server refers to an unknown primitive {base}");
+                    return None;
+                };
+                let (Some(kept_vertices), Some(kept_indices)) = (
+                    base_mesh.vertices.get(..keep_vertices as usize),
+                    base_mesh.indices.get(..keep_indices as usize),
+                ) else {
+                    log::warn!("Invalid patch of primitive {base}");
+                    return None;
+                };
+                let mesh = Mesh {
+                    vertices: [kept_vertices, &vertices].concat(),
+                    indices: [kept_indices, &indices].concat(),
+                    texture_id: base_mesh.texture_id,
+                };
+                Some((clip_rect, Arc::new(mesh)))
+            }
+        }
+    }
+
+    /// Paint the last frame in the background of `ctx`, and show the cursor of the server.
+    pub fn paint(&self, ctx: &egui::Context) {
+        let painter = ctx.layer_painter(LayerId::background());
+        for (clip_rect, mesh) in &self.primitives {
+            painter
+                .with_clip_rect(*clip_rect)
+                .add(Shape::Mesh(mesh.clone()));
+        }
+        ctx.set_cursor_icon(self.cursor_icon);
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::hash::{Hash as _, Hasher as _};
+
+use egui::{
+    ColorImage, CursorIcon, FullOutput, ImageData, Mesh, Rect, TextureId,
+    epaint::{ImageDelta, Primitive, textures::TextureOptions, textures::TexturesDelta},
+};
+
+use crate::{FrameDelta, PrimitiveDelta, RemoteMesh};
+
+/// Turns the output of egui into [`FrameDelta`]s, sending only what changed.
+///
+/// * Meshes that were already sent in the previous frame are sent as [`PrimitiveDelta::Unchanged`].
+/// * Meshes that start like the mesh at the same position in the previous frame
+///   are sent as a [`PrimitiveDelta::Patch`].
+/// * When a whole texture is set again, only the region of texels that changed is sent.
+/// * Frames where nothing changed are not sent at all.
+///
+/// The encoder keeps a copy of all textures, so it can send them to a client that
+/// connects later (see [`Self::reset_client`]).
+pub struct FrameEncoder {
+    /// The primitives of the last frame that was sent.
+    last_primitives: Vec<RemoteMesh>,
+
+    /// Maps the hash of a primitive to its index in [`Self::last_primitives`].
+    last_primitive_indices: HashMap<u64, u32>,
+
+    last_pixels_per_point: f32,
+    last_cursor_icon: CursorIcon,
+
+    /// The current content of all textures.
+    textures: HashMap<TextureId, (ColorImage, TextureOptions)>,
+
+    /// Set when the client has none of the textures yet.
+    resend_textures: bool,
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameEncoder {
+    pub fn new() -> Self {
+        Self {
+            last_primitives: Vec::new(),
+            last_primitive_indices: HashMap::new(),
+            last_pixels_per_point: 0.0,
+            last_cursor_icon: CursorIcon::Default,
+            textures: HashMap::new(),
+            resend_textures: false,
+        }
+    }
+
+    /// Call this when a new client connects.
+    ///
+    /// The next frame is then sent in full, including all textures.
+    pub fn reset_client(&mut self) {
+        self.last_primitives.clear();
+        self.last_primitive_indices.clear();
+        self.last_pixels_per_point = 0.0;
+        self.last_cursor_icon = CursorIcon::Default;
+        self.resend_textures = true;
+    }
+
+    /// Tessellate the output of a pass and encode what changed since the last frame.
+    ///
+    /// Returns `None` if nothing changed, so there is nothing to send.
+    pub fn encode(&mut self, ctx: &egui::Context, output: FullOutput) -> Option<FrameDelta> {
+        let FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            ..
+        } = output;
+        let primitives = ctx.tessellate(shapes, pixels_per_point);
+        self.encode_primitives(
+            pixels_per_point,
+            &primitives,
+            textures_delta,
+            platform_output.cursor_icon,
+            platform_output.commands,
+        )
+    }
+
+    /// Encode already tessellated primitives.
+    ///
+    /// Paint callbacks are skipped, since they can't be sent to the client.
+    ///
+    /// Returns `None` if nothing changed, so there is nothing to send.
+    pub fn encode_primitives(
+        &mut self,
+        pixels_per_point: f32,
+        primitives: &[egui::ClippedPrimitive],
+        textures_delta: TexturesDelta,
+        cursor_icon: CursorIcon,
+        commands: Vec<egui::OutputCommand>,
+    ) -> Option<FrameDelta> {
+        let textures_delta = self.encode_textures(textures_delta);
+
+        let mut new_primitives = Vec::with_capacity(primitives.len());
+        let mut new_indices = HashMap::with_capacity(primitives.len());
+        let mut deltas = Vec::with_capacity(primitives.len());
+        let mut unchanged = primitives.len() == self.last_primitives.len();
+        for primitive in primitives {
+            let Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue;
+            };
+            let hash = primitive_hash(primitive.clip_rect, mesh);
+            let index = new_primitives.len() as u32;
+
+            let last_index = self.last_primitive_indices.get(&hash).copied().filter(|i| {
+                let last = &self.last_primitives[*i as usize];
+                last.clip_rect == primitive.clip_rect && last.mesh == *mesh
+            });
+            if let Some(last_index) = last_index {
+                unchanged &= last_index == index;
+                deltas.push(PrimitiveDelta::Unchanged(last_index));
+                new_primitives.push(self.last_primitives[last_index as usize].clone());
+            } else {
+                unchanged = false;
+                let remote_mesh = RemoteMesh {
+                    clip_rect: primitive.clip_rect,
+                    mesh: mesh.clone(),
+                };
+                deltas.push(
+                    self.patch(index, &remote_mesh)
+                        .unwrap_or_else(|| PrimitiveDelta::New(remote_mesh.clone())),
+                );
+                new_primitives.push(remote_mesh);
+            }
+            new_indices.entry(hash).or_insert(index);
+        }
+        unchanged &= new_primitives.len() == self.last_primitives.len();
+
+        let unchanged = unchanged
+            && textures_delta.is_empty()
+            && commands.is_empty()
+            && cursor_icon == self.last_cursor_icon
+            && pixels_per_point == self.last_pixels_per_point;
+
+        self.last_primitives = new_primitives;
+        self.last_primitive_indices = new_indices;
+        self.last_cursor_icon = cursor_icon;
+        self.last_pixels_per_point = pixels_per_point;
+
+        (!unchanged).then_some(FrameDelta {
+            pixels_per_point,
+            textures_delta,
+            primitives: deltas,
+            cursor_icon,
+            commands,
+        })
+    }
+
+    /// Describe the mesh as a change of the mesh at the same position in the last frame,
+    /// if that is smaller than sending the whole mesh.
+    fn patch(&self, index: u32, new: &RemoteMesh) -> Option<PrimitiveDelta> {
+        let last = self.last_primitives.get(index as usize)?;
+        if last.clip_rect != new.clip_rect || last.mesh.texture_id != new.mesh.texture_id {
+            return None;
+        }
+
+        let keep_vertices = common_prefix_len(&last.mesh.vertices, &new.mesh.vertices);
+        let keep_indices = common_prefix_len(&last.mesh.indices, &new.mesh.indices);
+        let new_len = new.mesh.vertices.len() + new.mesh.indices.len();
+        if 2 * (keep_vertices + keep_indices) < new_len {
+            return None;
+        }
+
+        Some(PrimitiveDelta::Patch {
+            base: index,
+            clip_rect: new.clip_rect,
+            keep_vertices: keep_vertices as u32,
+            vertices: new.mesh.vertices[keep_vertices..].to_vec(),
+            keep_indices: keep_indices as u32,
+            indices: new.mesh.indices[keep_indices..].to_vec(),
+        })
+    }
+
+    /// Update our copy of the textures, and replace whole textures with the changed region.
+    fn encode_textures(&mut self, textures_delta: TexturesDelta) -> TexturesDelta {
+        let mut encoded = TexturesDelta::default();
+
+        if std::mem::take(&mut self.resend_textures) {
+            for (id, (image, options)) in &self.textures {
+                encoded
+                    .set
+                    .push((*id, ImageDelta::full(image.clone(), *options)));
+            }
+        }
+
+        for (id, delta) in textures_delta.set {
+            let ImageData::Color(image) = &delta.image;
+            let current = self.textures.get_mut(&id);
+            match (delta.pos, current) {
+                (None, Some((current, options)))
+                    if current.size == image.size && *options == delta.options =>
+                {
+                    if let Some([x, y, width, height]) = changed_region(current, image) {
+                        let patch = crop(image, [x, y], [width, height]);
+                        encoded
+                            .set
+                            .push((id, ImageDelta::partial([x, y], patch, delta.options)));
+                    }
+                    current.clone_from(image);
+                }
+                (None, _) => {
+                    self.textures.insert(id, ((**image).clone(), delta.options));
+                    encoded.set.push((id, delta));
+                }
+                (Some(pos), Some((current, _))) => {
+                    blit(current, pos, image);
+                    encoded.set.push((id, delta));
+                }
+                (Some(_), None) => {
+                    log::warn!("Partial update of unknown texture {id:?}");
+                    encoded.set.push((id, delta));
+                }
+            }
+        }
+
+        for id in textures_delta.free {
+            self.textures.remove(&id);
+            encoded.free.push(id);
+        }
+
+        encoded
+    }
+}
+
+fn primitive_hash(clip_rect: Rect, mesh: &Mesh) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for value in [
+        clip_rect.min.x,
+        clip_rect.min.y,
+        clip_rect.max.x,
+        clip_rect.max.y,
+    ] {
+        value.to_bits().hash(&mut hasher);
+    }
+    mesh.texture_id.hash(&mut hasher);
+    mesh.indices.hash(&mut hasher);
+    for vertex in &mesh.vertices {
+        for value in [vertex.pos.x, vertex.pos.y, vertex.uv.x, vertex.uv.y] {
+            value.to_bits().hash(&mut hasher);
+        }
+        vertex.color.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn common_prefix_len<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// The `[x, y, width, height]` of the smallest region containing all texels that differ.
+fn changed_region(old: &ColorImage, new: &ColorImage) -> Option<[usize; 4]> {
+    let width = new.size[0];
+    if width == 0 {
+        return None; // No texels, and `chunks(0)` would panic
+    }
+    let mut min = [usize::MAX; 2];
+    let mut max = [0; 2];
+    for (y, (old_row, new_row)) in old
+        .pixels
+        .chunks(width)
+        .zip(new.pixels.chunks(width))
+        .enumerate()
+    {
+        if old_row == new_row {
+            continue;
+        }
+        let first = old_row.iter().zip(new_row).position(|(a, b)| a != b)?;
+        let last = old_row.iter().zip(new_row).rposition(|(a, b)| a != b)?;
+        min = [min[0].min(first), min[1].min(y)];
+        max = [max[0].max(last), max[1].max(y)];
+    }
+    (min[1] != usize::MAX).then(|| [min[0], min[1], max[0] - min[0] + 1, max[1] - min[1] + 1])
+}
+
+fn crop(image: &ColorImage, [x, y]: [usize; 2], [width, height]: [usize; 2]) -> ColorImage {
+    let pixels = image
+        .pixels
+        .chunks(image.size[0])
+        .skip(y)
+        .take(height)
+        .flat_map(|row| &row[x..x + width])
+        .copied()
+        .collect();
+    ColorImage::new([width, height], pixels)
+}
+
+fn blit(target: &mut ColorImage, [x, y]: [usize; 2], patch: &ColorImage) {
+    let target_width = target.size[0];
+    let patch_width = patch.size[0];
+    if patch_width == 0 {
+        return;
+    }
+    for (row, patch_row) in patch.pixels.chunks(patch_width).enumerate() {
+        let start = (y + row) * target_width + x;
+        if let Some(target_row) = target.pixels.get_mut(start..start + patch_width) {
+            target_row.copy_from_slice(patch_row);
+        }
+    }
+}
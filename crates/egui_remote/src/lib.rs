@@ -0,0 +1,43 @@
+//! Show an [`egui`] UI running on another machine, e.g. a headless server, in a browser.
+//!
+//! The server runs the UI with a [`RemoteServer`], which tessellates the output and sends
+//! it to the client over WebSocket. The client sends its [`egui::RawInput`] back.
+//!
+//! Only what changed is sent: meshes that were already sent are referred to by index,
+//! and only the changed regions of textures are sent (see [`FrameEncoder`]).
+//!
+//! The client is an [`eframe`] app ([`RemoteApp`]), so on the web it reuses the input handling of `eframe`.
+//! Start it with [`start_web`].
+//!
+//! ## Feature flags
+#![cfg_attr(feature = "document-features", doc = document_features::document_features!())]
+//!
+
+mod client;
+mod encoder;
+mod protocol;
+
+pub use client::RemoteClient;
+pub use encoder::FrameEncoder;
+pub use protocol::{
+    ClientMessage, DecodeError, FrameDelta, PROTOCOL_VERSION, PrimitiveDelta, RemoteMesh,
+    ServerMessage,
+};
+
+#[cfg(feature = "client")]
+mod app;
+
+#[cfg(feature = "client")]
+pub use app::RemoteApp;
+
+#[cfg(all(feature = "client", target_arch = "wasm32"))]
+mod web;
+
+#[cfg(all(feature = "client", target_arch = "wasm32"))]
+pub use web::start_web;
+
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+mod server;
+
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub use server::{RemoteServer, ServerError};
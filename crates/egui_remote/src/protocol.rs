@@ -0,0 +1,143 @@
+use egui::{
+    CursorIcon, Mesh, OutputCommand, RawInput, Rect,
+    epaint::{Vertex, textures::TexturesDelta},
+};
+
+/// Bumped whenever the messages change in an incompatible way.
+///
+/// The server sends it in [`ServerMessage::Hello`], and the client refuses to talk to
+/// a server with another version.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// A message sent from the server to the client.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ServerMessage {
+    /// The first message of each connection.
+    Hello {
+        /// See [`PROTOCOL_VERSION`].
+        protocol_version: u32,
+    },
+
+    /// The result of running the UI.
+    Frame(FrameDelta),
+}
+
+/// A message sent from the client to the server.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ClientMessage {
+    /// The input for the next frame.
+    ///
+    /// The events of several inputs are all handled,
+    /// but only the rest of the last one is used (e.g. the screen size).
+    Input(RawInput),
+
+    /// The client lost track of the frames, so the next one should be sent in full.
+    ///
+    /// See [`crate::RemoteClient::resync_request`].
+    Resync,
+}
+
+/// What changed since the last frame sent to the client.
+///
+/// Created by [`crate::FrameEncoder`] and applied by [`crate::RemoteClient`].
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct FrameDelta {
+    /// The `pixels_per_point` the meshes were tessellated with.
+    pub pixels_per_point: f32,
+
+    /// Textures to set before painting, and to free after painting.
+    ///
+    /// A whole texture is only sent when it is new: changes are sent as
+    /// partial updates covering only the changed texels.
+    pub textures_delta: TexturesDelta,
+
+    /// The meshes to paint, back to front.
+    pub primitives: Vec<PrimitiveDelta>,
+
+    /// The cursor to show.
+    pub cursor_icon: CursorIcon,
+
+    /// Copy text, open urls, …
+    pub commands: Vec<OutputCommand>,
+}
+
+/// A mesh to paint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PrimitiveDelta {
+    /// The same as the primitive at this index in the previous frame.
+    Unchanged(u32),
+
+    /// A primitive that wasn't in the previous frame.
+    New(RemoteMesh),
+
+    /// The start of a primitive of the previous frame, followed by new vertices and indices.
+    ///
+    /// Since meshes with the same clip rectangle and texture are merged when tessellating,
+    /// a small change usually only affects the end of a mesh.
+    Patch {
+        /// The index of the primitive in the previous frame.
+        base: u32,
+
+        /// The area to clip the mesh to.
+        clip_rect: Rect,
+
+        /// How many vertices to keep from the start of the base mesh.
+        keep_vertices: u32,
+
+        /// The vertices following the kept ones.
+        vertices: Vec<Vertex>,
+
+        /// How many indices to keep from the start of the base mesh.
+        keep_indices: u32,
+
+        /// The indices following the kept ones.
+        indices: Vec<u32>,
+    },
+}
+
+/// A mesh within a clip rectangle, in points.
+///
+/// Like [`egui::ClippedPrimitive`], but without paint callbacks, which can't be sent to the client.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct RemoteMesh {
+    /// The area to clip the mesh to.
+    pub clip_rect: Rect,
+
+    /// The triangles, with the [`egui::TextureId`]s of the server.
+    pub mesh: Mesh,
+}
+
+/// Could not decode a message.
+#[derive(Debug)]
+pub struct DecodeError(bincode::Error);
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to decode egui_remote message: {}", self.0)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+macro_rules! impl_encoding {
+    ($message:ty) => {
+        impl $message {
+            /// Serialize the message, to send it as a binary WebSocket message.
+            pub fn encode(&self) -> Vec<u8> {
+                bincode::serialize(self).expect("Failed to serialize message")
+            }
+
+            /// Deserialize a message created by [`Self::encode`].
+            ///
+            /// # Errors
+            /// Fails if the bytes are not a valid message, e.g. because the other side
+            /// uses another [`PROTOCOL_VERSION`].
+            pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+                bincode::deserialize(bytes).map_err(DecodeError)
+            }
+        }
+    };
+}
+
+impl_encoding!(ServerMessage);
+impl_encoding!(ClientMessage);
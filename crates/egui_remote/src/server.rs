@@ -0,0 +1,288 @@
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use egui::{RawInput, mutex::Mutex};
+use tungstenite::{Message, WebSocket};
+
+use crate::{ClientMessage, DecodeError, FrameEncoder, PROTOCOL_VERSION, ServerMessage};
+
+/// How long to wait for input before checking for repaints requested from other threads.
+const MAX_IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// How long a client may take to complete the WebSocket handshake, or to receive a frame,
+/// before we give up on it and serve the next one.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something went wrong while serving a client.
+#[derive(Debug)]
+pub enum ServerError {
+    /// Accepting a connection failed.
+    Accept(std::io::Error),
+
+    /// Configuring the socket of a client failed.
+    Socket(std::io::Error),
+
+    /// The WebSocket handshake failed.
+    Handshake(String),
+
+    /// Sending or receiving over the WebSocket failed.
+    WebSocket(tungstenite::Error),
+
+    /// The client sent an invalid message.
+    Decode(DecodeError),
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accept(err) => write!(f, "Failed to accept a connection: {err}"),
+            Self::Socket(err) => write!(f, "Socket error: {err}"),
+            Self::Handshake(err) => write!(f, "WebSocket handshake failed: {err}"),
+            Self::WebSocket(err) => write!(f, "WebSocket error: {err}"),
+            Self::Decode(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+impl From<tungstenite::Error> for ServerError {
+    #[inline]
+    fn from(err: tungstenite::Error) -> Self {
+        Self::WebSocket(err)
+    }
+}
+
+impl From<DecodeError> for ServerError {
+    #[inline]
+    fn from(err: DecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Runs an egui UI for remote clients connecting over WebSocket, e.g. [`crate::RemoteApp`].
+///
+/// One client is served at a time.
+/// A client that doesn't complete the WebSocket handshake or stops receiving frames
+/// is disconnected after a few seconds.
+/// A frame is run whenever the client sends input, or when egui requests a repaint,
+/// and only what changed is sent back (see [`FrameEncoder`]).
+///
+/// ``` no_run
+/// let mut server = egui_remote::RemoteServer::bind("0.0.0.0:9000")?;
+/// server.run(|ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| {
+///         ui.heading("Hello from the server!");
+///     });
+/// })?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct RemoteServer {
+    listener: TcpListener,
+    ctx: egui::Context,
+    encoder: FrameEncoder,
+
+    /// When egui wants the next frame, as requested through [`egui::Context::request_repaint`].
+    repaint_at: Arc<Mutex<Option<Instant>>>,
+    start_time: Instant,
+}
+
+impl RemoteServer {
+    /// Listen for clients on the given address.
+    ///
+    /// # Errors
+    /// Fails if the address can't be bound.
+    pub fn bind(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+
+        let ctx = egui::Context::default();
+        let repaint_at: Arc<Mutex<Option<Instant>>> = Default::default();
+        ctx.set_request_repaint_callback({
+            let repaint_at = repaint_at.clone();
+            move |info| {
+                let at = Instant::now() + info.delay;
+                let mut repaint_at = repaint_at.lock();
+                *repaint_at = Some(repaint_at.map_or(at, |current| current.min(at)));
+            }
+        });
+
+        Ok(Self {
+            listener,
+            ctx,
+            encoder: FrameEncoder::new(),
+            repaint_at,
+            start_time: Instant::now(),
+        })
+    }
+
+    /// The address the server listens on, e.g. to find the port when binding to port 0.
+    ///
+    /// # Errors
+    /// See [`TcpListener::local_addr`].
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// The context running the UI.
+    pub fn ctx(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// Serve clients one after the other, forever.
+    ///
+    /// Errors of a single client are logged, and the next client is served.
+    ///
+    /// # Errors
+    /// Fails if accepting connections fails.
+    pub fn run(&mut self, mut run_ui: impl FnMut(&egui::Context)) -> std::io::Result<()> {
+        loop {
+            match self.serve_client(&mut run_ui) {
+                Ok(()) => {}
+                Err(ServerError::Accept(err)) => return Err(err),
+                Err(err) => log::warn!("{err}"),
+            }
+        }
+    }
+
+    /// Wait for the next client, and serve it until it disconnects.
+    ///
+    /// # Errors
+    /// Fails if the connection fails, or if the client sends an invalid message.
+    pub fn serve_client(
+        &mut self,
+        mut run_ui: impl FnMut(&egui::Context),
+    ) -> Result<(), ServerError> {
+        let (stream, addr) = self.listener.accept().map_err(ServerError::Accept)?;
+        log::info!("Serving {addr}");
+
+        // Since only one client is served at a time, don't let a stuck client block the others:
+        stream
+            .set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+            .map_err(ServerError::Socket)?;
+        let mut socket =
+            tungstenite::accept(stream).map_err(|err| ServerError::Handshake(err.to_string()))?;
+        socket.send(Message::binary(
+            ServerMessage::Hello {
+                protocol_version: PROTOCOL_VERSION,
+            }
+            .encode(),
+        ))?;
+        self.encoder.reset_client();
+
+        // The last input of the client, without events. `None` until the client sent some.
+        let mut input: Option<RawInput> = None;
+        let mut events = Vec::new();
+        loop {
+            let mut got_input = false;
+            let mut wait = true;
+            loop {
+                let message = match read(&mut socket, wait.then(|| self.wait_time()))? {
+                    Read::Message(message) => message,
+                    Read::Nothing => break,
+                    Read::Closed => {
+                        log::info!("{addr} disconnected");
+                        return Ok(());
+                    }
+                };
+                // Handle everything that has arrived before running a frame.
+                wait = false;
+
+                let Message::Binary(bytes) = message else {
+                    continue;
+                };
+                match ClientMessage::decode(&bytes)? {
+                    ClientMessage::Input(mut raw_input) => {
+                        events.append(&mut raw_input.events);
+                        for info in raw_input.viewports.values_mut() {
+                            info.events.clear(); // The client can't close the server
+                        }
+                        input = Some(raw_input);
+                        got_input = true;
+                    }
+                    ClientMessage::Resync => {
+                        log::debug!("{addr} asked for a resync");
+                        self.encoder.reset_client();
+                        got_input = true;
+                    }
+                }
+            }
+
+            let repaint_due = self
+                .repaint_at
+                .lock()
+                .is_some_and(|repaint_at| repaint_at <= Instant::now());
+            let Some(input) = &input else {
+                continue;
+            };
+            if got_input || repaint_due {
+                let mut raw_input = input.clone();
+                raw_input.events = std::mem::take(&mut events);
+                raw_input.time = Some(self.start_time.elapsed().as_secs_f64());
+                self.run_frame(&mut socket, raw_input, &mut run_ui)?;
+            }
+        }
+    }
+
+    /// How long to wait for input before the next repaint is due.
+    fn wait_time(&self) -> Duration {
+        let until_repaint = self.repaint_at.lock().map_or(MAX_IDLE_WAIT, |repaint_at| {
+            repaint_at.saturating_duration_since(Instant::now())
+        });
+        until_repaint.min(MAX_IDLE_WAIT)
+    }
+
+    fn run_frame(
+        &mut self,
+        socket: &mut WebSocket<TcpStream>,
+        raw_input: RawInput,
+        run_ui: &mut impl FnMut(&egui::Context),
+    ) -> Result<(), ServerError> {
+        *self.repaint_at.lock() = None;
+        let output = self.ctx.run(raw_input, |ctx| run_ui(ctx));
+        if let Some(frame) = self.encoder.encode(&self.ctx, output) {
+            socket
+                .get_mut()
+                .set_nonblocking(false)
+                .map_err(ServerError::Socket)?;
+            socket.send(Message::binary(ServerMessage::Frame(frame).encode()))?;
+        }
+        Ok(())
+    }
+}
+
+enum Read {
+    Message(Message),
+    Nothing,
+    Closed,
+}
+
+/// Read a message, waiting at most `wait`, or not at all if `None`.
+fn read(socket: &mut WebSocket<TcpStream>, wait: Option<Duration>) -> Result<Read, ServerError> {
+    let stream = socket.get_mut();
+    match wait {
+        Some(wait) if !wait.is_zero() => {
+            stream
+                .set_nonblocking(false)
+                .and_then(|()| stream.set_read_timeout(Some(wait)))
+                .map_err(ServerError::Socket)?;
+        }
+        _ => stream.set_nonblocking(true).map_err(ServerError::Socket)?,
+    }
+
+    match socket.read() {
+        Ok(Message::Close(_))
+        | Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Ok(Read::Closed)
+        }
+        Ok(message) => Ok(Read::Message(message)),
+        Err(tungstenite::Error::Io(err))
+            if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
+            Ok(Read::Nothing)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+use wasm_bindgen::{JsCast as _, JsValue, closure::Closure};
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+use crate::{RemoteApp, ServerMessage};
+
+/// Connect to a server over WebSocket and show its UI in the canvas.
+///
+/// `url` is the address of the server, e.g. `ws://localhost:9000`.
+/// Input is collected by `eframe` like for any other web app, and sent to the server.
+///
+/// # Errors
+/// Fails if the url is invalid or if `eframe` fails to start.
+pub async fn start_web(canvas: web_sys::HtmlCanvasElement, url: &str) -> Result<(), JsValue> {
+    let socket = WebSocket::new(url)?;
+    socket.set_binary_type(BinaryType::Arraybuffer);
+
+    let runner = eframe::WebRunner::new();
+    runner
+        .start(
+            canvas,
+            eframe::WebOptions::default(),
+            Box::new(move |cc| {
+                let (sender, receiver) = std::sync::mpsc::channel();
+
+                // Dropped when the connection closes, so the app knows.
+                let sender: Rc<RefCell<Option<Sender<ServerMessage>>>> =
+                    Rc::new(RefCell::new(Some(sender)));
+
+                let ctx = cc.egui_ctx.clone();
+                let on_message = Closure::<dyn FnMut(_)>::new({
+                    let sender = sender.clone();
+                    move |event: MessageEvent| {
+                        let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() else {
+                            log::warn!("Ignoring non-binary WebSocket message");
+                            return;
+                        };
+                        let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                        match ServerMessage::decode(&bytes) {
+                            Ok(message) => {
+                                if let Some(sender) = &*sender.borrow() {
+                                    sender.send(message).ok();
+                                }
+                                ctx.request_repaint();
+                            }
+                            Err(err) => log::error!("{err}"),
+                        }
+                    }
+                });
+                socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+                on_message.forget();
+
+                let ctx = cc.egui_ctx.clone();
+                let on_close = Closure::<dyn FnMut()>::new(move || {
+                    sender.borrow_mut().take();
+                    ctx.request_repaint();
+                });
+                socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+                on_close.forget();
+
+                let send = move |message: crate::ClientMessage| {
+                    if socket.ready_state() != WebSocket::OPEN {
+                        return false;
+                    }
+                    if let Err(err) = socket.send_with_u8_array(&message.encode()) {
+                        log::warn!("Failed to send input to the server: {err:?}");
+                        return false;
+                    }
+                    true
+                };
+                Ok(Box::new(RemoteApp::new(send, receiver)))
+            }),
+        )
+        .await
+}
@@ -0,0 +1,382 @@
+use egui::{
+    Color32, ColorImage, CursorIcon, ImageData, RawInput, TextureId, TextureOptions,
+    epaint::{ImageDelta, textures::TexturesDelta},
+};
+use egui_remote::{FrameDelta, FrameEncoder, PrimitiveDelta, RemoteClient, ServerMessage};
+
+fn input() -> RawInput {
+    RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(400.0, 300.0),
+        )),
+        ..Default::default()
+    }
+}
+
+fn ui(ctx: &egui::Context, text: &str) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.label("Static");
+        ui.label(text);
+    });
+}
+
+fn texture(color: Color32) -> ColorImage {
+    ColorImage::new([4, 4], vec![color; 16])
+}
+
+fn encode_textures(encoder: &mut FrameEncoder, set: Vec<(TextureId, ImageDelta)>) -> TexturesDelta {
+    encoder
+        .encode_primitives(
+            1.0,
+            &[],
+            TexturesDelta {
+                set,
+                free: Vec::new(),
+            },
+            CursorIcon::Default,
+            Vec::new(),
+        )
+        .map(|frame| frame.textures_delta)
+        .unwrap_or_default()
+}
+
+#[test]
+fn unchanged_frames_are_not_sent() {
+    let ctx = egui::Context::default();
+    let mut encoder = FrameEncoder::new();
+
+    let output = ctx.run(input(), |ctx| ui(ctx, "Hello"));
+    let frame = encoder.encode(&ctx, output).unwrap();
+    assert!(!frame.textures_delta.is_empty(), "The font texture");
+    assert!(
+        frame
+            .primitives
+            .iter()
+            .all(|primitive| matches!(primitive, PrimitiveDelta::New(_)))
+    );
+
+    // Run a few frames, since the first ones may still change (e.g. because of the sizing pass).
+    for _ in 0..3 {
+        let output = ctx.run(input(), |ctx| ui(ctx, "Hello"));
+        encoder.encode(&ctx, output);
+    }
+    let output = ctx.run(input(), |ctx| ui(ctx, "Hello"));
+    assert_eq!(encoder.encode(&ctx, output), None);
+
+    let output = ctx.run(input(), |ctx| ui(ctx, "World"));
+    let frame = encoder.encode(&ctx, output).unwrap();
+    assert!(
+        frame.primitives.iter().all(|primitive| matches!(
+            primitive,
+            PrimitiveDelta::Unchanged(_) | PrimitiveDelta::Patch { .. }
+        )),
+        "The static parts are reused"
+    );
+}
+
+#[test]
+fn frames_survive_the_round_trip() {
+    let server_ctx = egui::Context::default();
+    let client_ctx = egui::Context::default();
+    let mut encoder = FrameEncoder::new();
+    let mut client = RemoteClient::new();
+
+    for text in ["Hello", "Hello", "World"] {
+        let output = server_ctx.run(input(), |ctx| ui(ctx, text));
+        let num_primitives = server_ctx
+            .tessellate(output.shapes.clone(), output.pixels_per_point)
+            .len();
+        let Some(frame) = encoder.encode(&server_ctx, output) else {
+            continue;
+        };
+
+        let bytes = ServerMessage::Frame(frame.clone()).encode();
+        let decoded = ServerMessage::decode(&bytes).unwrap();
+        assert_eq!(decoded, ServerMessage::Frame(frame.clone()));
+
+        let mut frame = Some(frame);
+        let _ = client_ctx.run(RawInput::default(), |ctx| {
+            if let Some(frame) = frame.take() {
+                client.apply(ctx, frame);
+            }
+            client.paint(ctx);
+        });
+        assert_eq!(client.num_primitives(), num_primitives);
+    }
+
+    assert!(ServerMessage::decode(&[0xff; 3]).is_err());
+}
+
+#[test]
+fn only_changed_texels_are_sent() {
+    let id = TextureId::Managed(1);
+    let options = TextureOptions::NEAREST;
+    let mut encoder = FrameEncoder::new();
+
+    let delta = encode_textures(
+        &mut encoder,
+        vec![(id, ImageDelta::full(texture(Color32::RED), options))],
+    );
+    assert_eq!(delta.set.len(), 1);
+    assert_eq!(delta.set[0].1.pos, None);
+
+    // Setting the same image again sends nothing:
+    let delta = encode_textures(
+        &mut encoder,
+        vec![(id, ImageDelta::full(texture(Color32::RED), options))],
+    );
+    assert!(delta.is_empty());
+
+    // Changing two texels sends the region containing them:
+    let mut image = texture(Color32::RED);
+    image[(1, 1)] = Color32::BLUE;
+    image[(2, 3)] = Color32::GREEN;
+    let delta = encode_textures(&mut encoder, vec![(id, ImageDelta::full(image, options))]);
+    let [(delta_id, image_delta)] = &delta.set[..] else {
+        panic!("Expected a single delta, got {}", delta.set.len());
+    };
+    assert_eq!(*delta_id, id);
+    assert_eq!(image_delta.pos, Some([1, 1]));
+    let ImageData::Color(patch) = &image_delta.image;
+    assert_eq!(patch.size, [2, 3]);
+    assert_eq!(patch[(0, 0)], Color32::BLUE);
+    assert_eq!(patch[(1, 2)], Color32::GREEN);
+
+    // A new client gets the whole texture, as it is now:
+    encoder.reset_client();
+    let delta = encode_textures(&mut encoder, Vec::new());
+    let [(_, image_delta)] = &delta.set[..] else {
+        panic!("Expected a single delta, got {}", delta.set.len());
+    };
+    assert_eq!(image_delta.pos, None);
+    let ImageData::Color(image) = &image_delta.image;
+    assert_eq!(image[(2, 3)], Color32::GREEN);
+}
+
+#[test]
+fn empty_textures() {
+    let id = TextureId::Managed(1);
+    let options = TextureOptions::NEAREST;
+    let mut encoder = FrameEncoder::new();
+
+    for size in [[0, 4], [4, 0], [0, 0]] {
+        let empty = || ColorImage::new(size, Vec::new());
+        let delta = encode_textures(&mut encoder, vec![(id, ImageDelta::full(empty(), options))]);
+        assert_eq!(delta.set.len(), 1);
+
+        let delta = encode_textures(&mut encoder, vec![(id, ImageDelta::full(empty(), options))]);
+        assert!(delta.is_empty());
+
+        let delta = encode_textures(
+            &mut encoder,
+            vec![(id, ImageDelta::partial([0, 0], empty(), options))],
+        );
+        assert_eq!(delta.set.len(), 1);
+    }
+}
+
+#[test]
+fn client_maps_texture_ids() {
+    let ctx = egui::Context::default();
+    let mut client = RemoteClient::new();
+    let remote_id = TextureId::Managed(0);
+
+    let mut textures_delta = TexturesDelta::default();
+    textures_delta.set.push((
+        remote_id,
+        ImageDelta::full(texture(Color32::RED), TextureOptions::default()),
+    ));
+    let frame = FrameDelta {
+        pixels_per_point: 1.0,
+        textures_delta,
+        primitives: Vec::new(),
+        cursor_icon: CursorIcon::Text,
+        commands: vec![egui::OutputCommand::CopyText("copied".to_owned())],
+    };
+
+    let output = ctx.run(RawInput::default(), |ctx| {
+        client.apply(ctx, frame.clone());
+        client.paint(ctx);
+    });
+    assert_eq!(output.platform_output.cursor_icon, CursorIcon::Text);
+    assert_eq!(
+        output.platform_output.commands,
+        [egui::OutputCommand::CopyText("copied".to_owned())]
+    );
+    let new_texture = output
+        .textures_delta
+        .set
+        .iter()
+        .find(|(_, delta)| delta.image.size() == [4, 4])
+        .map(|(id, _)| *id)
+        .unwrap();
+    assert_ne!(
+        new_texture, remote_id,
+        "The font texture of the client is kept"
+    );
+}
+
+#[test]
+fn client_keeps_indices_of_unresolved_primitives() {
+    fn apply(ctx: &egui::Context, client: &mut RemoteClient, primitives: Vec<PrimitiveDelta>) {
+        let mut frame = Some(FrameDelta {
+            pixels_per_point: 2.0,
+            textures_delta: TexturesDelta::default(),
+            primitives,
+            cursor_icon: CursorIcon::Default,
+            commands: Vec::new(),
+        });
+        let _ = ctx.run(RawInput::default(), |ctx| {
+            if let Some(frame) = frame.take() {
+                client.apply(ctx, frame);
+            }
+        });
+    }
+
+    let ctx = egui::Context::default();
+    let mut client = RemoteClient::new();
+
+    let unknown_texture = egui::Mesh::with_texture(TextureId::Managed(42));
+    apply(
+        &ctx,
+        &mut client,
+        vec![
+            PrimitiveDelta::Unchanged(7),
+            PrimitiveDelta::New(egui_remote::RemoteMesh {
+                clip_rect: egui::Rect::EVERYTHING,
+                mesh: unknown_texture,
+            }),
+        ],
+    );
+    assert_eq!(client.num_primitives(), 2, "Placeholders keep the indices");
+    assert_eq!(
+        client.resync_request(),
+        Some(egui_remote::ClientMessage::Resync)
+    );
+    assert_eq!(client.resync_request(), None, "Only requested once");
+
+    apply(
+        &ctx,
+        &mut client,
+        vec![PrimitiveDelta::Unchanged(1), PrimitiveDelta::Unchanged(0)],
+    );
+    assert_eq!(client.num_primitives(), 2);
+    assert_eq!(client.resync_request(), None, "The placeholders are known");
+
+    let _ = ctx.run(RawInput::default(), |_| {});
+    assert_eq!(ctx.pixels_per_point(), 2.0, "The scale of the server");
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn serve_over_websocket() {
+    use egui_remote::{ClientMessage, PROTOCOL_VERSION, RemoteServer};
+    use tungstenite::Message;
+
+    let mut server = RemoteServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let server_thread = std::thread::Builder::new()
+        .name("egui_remote server".to_owned())
+        .spawn(move || server.serve_client(|ctx| ui(ctx, "Remote")))
+        .unwrap();
+
+    let (mut socket, _) = tungstenite::connect(format!("ws://{addr}")).unwrap();
+    let mut read = || {
+        let Message::Binary(bytes) = socket.read().unwrap() else {
+            panic!("Expected a binary message");
+        };
+        ServerMessage::decode(&bytes).unwrap()
+    };
+    assert_eq!(
+        read(),
+        ServerMessage::Hello {
+            protocol_version: PROTOCOL_VERSION
+        }
+    );
+
+    socket
+        .send(Message::binary(ClientMessage::Input(input()).encode()))
+        .unwrap();
+    let Message::Binary(bytes) = socket.read().unwrap() else {
+        panic!("Expected a binary message");
+    };
+    let ServerMessage::Frame(frame) = ServerMessage::decode(&bytes).unwrap() else {
+        panic!("Expected a frame");
+    };
+    assert!(!frame.primitives.is_empty());
+    assert!(!frame.textures_delta.is_empty());
+
+    socket.close(None).unwrap();
+    while socket.read().is_ok() {}
+    server_thread.join().unwrap().unwrap();
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn remote_app_forwards_input() {
+    use std::sync::mpsc;
+
+    use egui_remote::{ClientMessage, PROTOCOL_VERSION, RemoteApp};
+
+    let (server_sender, server_messages) = mpsc::channel();
+    let (input_sender, inputs) = mpsc::channel();
+    let app = RemoteApp::new(
+        move |message: ClientMessage| input_sender.send(message).is_ok(),
+        server_messages,
+    );
+    let mut runner = eframe::HeadlessRunner::new(
+        eframe::HeadlessOptions::default(),
+        None,
+        Box::new(|_cc| Ok(Box::new(app))),
+    )
+    .unwrap();
+
+    runner.step();
+    assert_eq!(
+        inputs.try_iter().count(),
+        1,
+        "The first input is always sent"
+    );
+
+    runner.step();
+    assert_eq!(inputs.try_iter().count(), 0, "Nothing happened");
+
+    // A new connection needs the input again:
+    server_sender
+        .send(ServerMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+        })
+        .unwrap();
+    runner.run_frames(2);
+    assert_eq!(inputs.try_iter().count(), 1);
+
+    runner.push_event(egui::Event::PointerMoved(egui::pos2(10.0, 10.0)));
+    runner.step();
+    let Ok(ClientMessage::Input(input)) = inputs.try_recv() else {
+        panic!("Expected an input");
+    };
+    assert_eq!(
+        input.events,
+        [egui::Event::PointerMoved(egui::pos2(10.0, 10.0))]
+    );
+
+    // Render a frame of the server:
+    let server_ctx = egui::Context::default();
+    let mut encoder = FrameEncoder::new();
+    let output = server_ctx.run(input, |ctx| ui(ctx, "Remote"));
+    let frame = encoder.encode(&server_ctx, output).unwrap();
+    server_sender.send(ServerMessage::Frame(frame)).unwrap();
+    runner.step();
+    assert!(!runner.output().shapes.is_empty());
+
+    drop(server_sender);
+    runner.step();
+    let shows_error = runner.output().shapes.iter().any(|clipped| {
+        matches!(
+            &clipped.shape,
+            egui::Shape::Text(text) if text.galley.text() == "Disconnected from the server"
+        )
+    });
+    assert!(shows_error);
+}
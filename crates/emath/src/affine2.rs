@@ -0,0 +1,347 @@
+use crate::{Pos2, Rect, Rot2, TSTransform, Vec2, vec2};
+
+/// A general 2D affine transform: a linear map (rotation, scale, skew, reflection) followed by a translation.
+///
+/// This is a superset of [`TSTransform`], which only supports translation and uniform scaling.
+///
+/// The linear part is stored as the two basis vectors, i.e. where the X and Y axes end up:
+///
+/// ```text
+/// | x_axis.x  y_axis.x  translation.x |
+/// | x_axis.y  y_axis.y  translation.y |
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+pub struct Affine2 {
+    /// Where the X axis ends up (the first column of the linear part).
+    pub x_axis: Vec2,
+
+    /// Where the Y axis ends up (the second column of the linear part).
+    pub y_axis: Vec2,
+
+    /// Translation amount, applied after the linear part.
+    pub translation: Vec2,
+}
+
+impl Eq for Affine2 {}
+
+impl Default for Affine2 {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Affine2 {
+    pub const IDENTITY: Self = Self {
+        x_axis: Vec2::X,
+        y_axis: Vec2::Y,
+        translation: Vec2::ZERO,
+    };
+
+    /// Create a transform from the images of the X and Y axes, and a translation.
+    #[inline]
+    pub const fn new(x_axis: Vec2, y_axis: Vec2, translation: Vec2) -> Self {
+        Self {
+            x_axis,
+            y_axis,
+            translation,
+        }
+    }
+
+    #[inline]
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self::new(Vec2::X, Vec2::Y, translation)
+    }
+
+    /// Uniform scaling around `(0, 0)`.
+    #[inline]
+    pub fn from_scaling(scaling: f32) -> Self {
+        Self::from_scale(Vec2::splat(scaling))
+    }
+
+    /// Non-uniform scaling around `(0, 0)`.
+    #[inline]
+    pub fn from_scale(scale: Vec2) -> Self {
+        Self::new(vec2(scale.x, 0.0), vec2(0.0, scale.y), Vec2::ZERO)
+    }
+
+    /// Rotation around `(0, 0)`.
+    #[inline]
+    pub fn from_rotation(rot: Rot2) -> Self {
+        Self::new(rot * Vec2::X, rot * Vec2::Y, Vec2::ZERO)
+    }
+
+    /// Rotation by `angle` radians clockwise around `(0, 0)`.
+    #[inline]
+    pub fn from_angle(angle: f32) -> Self {
+        Self::from_rotation(Rot2::from_angle(angle))
+    }
+
+    /// Skew (shear) around `(0, 0)`.
+    ///
+    /// `skew.x` is the angle (in radians) the Y axis is tilted towards the X axis,
+    /// and `skew.y` is the angle the X axis is tilted towards the Y axis.
+    #[inline]
+    pub fn from_skew(skew: Vec2) -> Self {
+        Self::new(vec2(1.0, skew.y.tan()), vec2(skew.x.tan(), 1.0), Vec2::ZERO)
+    }
+
+    /// Apply `transform` around the given `pivot` point instead of around `(0, 0)`.
+    ///
+    /// ```
+    /// # use emath::{pos2, Affine2};
+    /// let rot = Affine2::from_angle(std::f32::consts::TAU / 4.0).around(pos2(10.0, 10.0));
+    /// assert!((rot * pos2(10.0, 10.0) - pos2(10.0, 10.0)).length() < 1e-5);
+    /// assert!((rot * pos2(20.0, 10.0) - pos2(10.0, 20.0)).length() < 1e-5);
+    /// ```
+    #[inline]
+    pub fn around(self, pivot: Pos2) -> Self {
+        Self::from_translation(pivot.to_vec2()) * self * Self::from_translation(-pivot.to_vec2())
+    }
+
+    /// The determinant of the linear part.
+    ///
+    /// Negative if the transform flips the orientation.
+    #[inline]
+    pub fn determinant(&self) -> f32 {
+        self.x_axis.x * self.y_axis.y - self.y_axis.x * self.x_axis.y
+    }
+
+    /// Is this a valid, invertible transform?
+    pub fn is_valid(&self) -> bool {
+        self.x_axis.is_finite()
+            && self.y_axis.is_finite()
+            && self.translation.is_finite()
+            && self.determinant() != 0.0
+    }
+
+    /// The average factor by which lengths are scaled: `sqrt(|determinant|)`.
+    ///
+    /// This is exact for rotations and uniform scaling,
+    /// and a reasonable approximation for skews and non-uniform scaling.
+    /// Used for things like stroke widths.
+    #[inline]
+    pub fn scale_factor(&self) -> f32 {
+        self.determinant().abs().sqrt()
+    }
+
+    /// The angle (in radians, clockwise) that the X axis is rotated by.
+    #[inline]
+    pub fn rotation_angle(&self) -> f32 {
+        self.x_axis.angle()
+    }
+
+    /// Is this only a translation and a (positive) uniform scaling?
+    ///
+    /// If so, it can be converted to a [`TSTransform`] with [`Self::as_ts_transform`].
+    #[inline]
+    pub fn is_translate_scale(&self) -> bool {
+        self.x_axis.y == 0.0
+            && self.y_axis.x == 0.0
+            && self.x_axis.x == self.y_axis.y
+            && 0.0 < self.x_axis.x
+    }
+
+    /// Is this a rotation, a uniform scaling and a translation, with no skew or reflection?
+    ///
+    /// Such transforms preserve angles and circles.
+    #[inline]
+    pub fn is_similarity(&self) -> bool {
+        let epsilon = 1e-5 * self.x_axis.length().max(self.y_axis.length());
+        (self.x_axis.rot90() + self.y_axis).length() <= epsilon
+    }
+
+    /// Convert to a [`TSTransform`], if this transform is only a translation and uniform scaling.
+    ///
+    /// ```
+    /// # use emath::{vec2, Affine2, TSTransform};
+    /// let ts = TSTransform::new(vec2(2.0, 3.0), 2.0);
+    /// assert_eq!(Affine2::from(ts).as_ts_transform(), Some(ts));
+    /// assert_eq!(Affine2::from_angle(1.0).as_ts_transform(), None);
+    /// ```
+    #[inline]
+    pub fn as_ts_transform(&self) -> Option<TSTransform> {
+        self.is_translate_scale()
+            .then(|| TSTransform::new(self.translation, self.x_axis.x))
+    }
+
+    /// Inverts the transform.
+    ///
+    /// ```
+    /// # use emath::{pos2, vec2, Affine2, Rot2};
+    /// let t = Affine2::from_translation(vec2(5.0, 2.0))
+    ///     * Affine2::from_rotation(Rot2::from_angle(0.5))
+    ///     * Affine2::from_skew(vec2(0.3, 0.0));
+    /// let p = pos2(7.0, -3.0);
+    /// assert!((t.inverse() * (t * p) - p).length() < 1e-5);
+    /// ```
+    #[inline]
+    pub fn inverse(&self) -> Self {
+        let inv_det = 1.0 / self.determinant();
+        let x_axis = inv_det * vec2(self.y_axis.y, -self.x_axis.y);
+        let y_axis = inv_det * vec2(-self.y_axis.x, self.x_axis.x);
+        let translation = -(x_axis * self.translation.x + y_axis * self.translation.y);
+        Self {
+            x_axis,
+            y_axis,
+            translation,
+        }
+    }
+
+    /// Transforms the given vector, ignoring the translation.
+    #[inline]
+    pub fn mul_vec(&self, vec: Vec2) -> Vec2 {
+        self.x_axis * vec.x + self.y_axis * vec.y
+    }
+
+    /// Transforms the given coordinate.
+    ///
+    /// ```
+    /// # use emath::{pos2, vec2, Affine2};
+    /// let t = Affine2::from_translation(vec2(1.0, 2.0)) * Affine2::from_scale(vec2(2.0, 3.0));
+    /// assert_eq!(t.mul_pos(pos2(1.0, 1.0)), pos2(3.0, 5.0));
+    /// ```
+    #[inline]
+    pub fn mul_pos(&self, pos: Pos2) -> Pos2 {
+        (self.mul_vec(pos.to_vec2()) + self.translation).to_pos2()
+    }
+
+    /// The four corners of the transformed rectangle,
+    /// in the order left-top, right-top, right-bottom, left-bottom.
+    #[inline]
+    pub fn rect_corners(&self, rect: Rect) -> [Pos2; 4] {
+        [
+            self.mul_pos(rect.left_top()),
+            self.mul_pos(rect.right_top()),
+            self.mul_pos(rect.right_bottom()),
+            self.mul_pos(rect.left_bottom()),
+        ]
+    }
+
+    /// Transforms the given rectangle, returning the axis-aligned bounding box of the result.
+    ///
+    /// For transforms with rotation or skew the result is larger than the transformed rectangle.
+    /// Use [`Self::rect_corners`] if you need the exact shape.
+    ///
+    /// ```
+    /// # use emath::{pos2, Affine2, Rect};
+    /// let rect = Rect::from_min_max(pos2(-1.0, -2.0), pos2(1.0, 2.0));
+    /// let bb = Affine2::from_angle(std::f32::consts::TAU / 4.0).mul_rect(rect);
+    /// assert!((bb.min - pos2(-2.0, -1.0)).length() < 1e-5);
+    /// assert!((bb.max - pos2(2.0, 1.0)).length() < 1e-5);
+    /// ```
+    #[inline]
+    pub fn mul_rect(&self, rect: Rect) -> Rect {
+        Rect::from_points(&self.rect_corners(rect))
+    }
+}
+
+impl From<TSTransform> for Affine2 {
+    #[inline]
+    fn from(ts: TSTransform) -> Self {
+        Self::new(vec2(ts.scaling, 0.0), vec2(0.0, ts.scaling), ts.translation)
+    }
+}
+
+impl From<Rot2> for Affine2 {
+    #[inline]
+    fn from(rot: Rot2) -> Self {
+        Self::from_rotation(rot)
+    }
+}
+
+/// Transforms the position.
+impl std::ops::Mul<Pos2> for Affine2 {
+    type Output = Pos2;
+
+    #[inline]
+    fn mul(self, pos: Pos2) -> Pos2 {
+        self.mul_pos(pos)
+    }
+}
+
+/// Transforms the rectangle, returning its bounding box.
+impl std::ops::Mul<Rect> for Affine2 {
+    type Output = Rect;
+
+    #[inline]
+    fn mul(self, rect: Rect) -> Rect {
+        self.mul_rect(rect)
+    }
+}
+
+impl std::ops::Mul<Self> for Affine2 {
+    type Output = Self;
+
+    /// Applies the right hand side transform, then the left hand side.
+    ///
+    /// ```
+    /// # use emath::{pos2, vec2, Affine2};
+    /// let a = Affine2::from_translation(vec2(1.0, 0.0));
+    /// let b = Affine2::from_scale(vec2(2.0, 3.0));
+    /// assert_eq!((a * b) * pos2(1.0, 1.0), a * (b * pos2(1.0, 1.0)));
+    /// ```
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            x_axis: self.mul_vec(rhs.x_axis),
+            y_axis: self.mul_vec(rhs.y_axis),
+            translation: self.mul_pos(rhs.translation.to_pos2()).to_vec2(),
+        }
+    }
+}
+
+impl std::ops::Mul<TSTransform> for Affine2 {
+    type Output = Self;
+
+    /// Applies the right hand side transform, then the left hand side.
+    #[inline]
+    fn mul(self, rhs: TSTransform) -> Self::Output {
+        self * Self::from(rhs)
+    }
+}
+
+impl std::ops::Mul<Affine2> for TSTransform {
+    type Output = Affine2;
+
+    /// Applies the right hand side transform, then the left hand side.
+    #[inline]
+    fn mul(self, rhs: Affine2) -> Self::Output {
+        Affine2::from(self) * rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos2;
+
+    #[test]
+    fn similarity() {
+        assert!(Affine2::IDENTITY.is_similarity());
+        assert!(Affine2::from_angle(0.7).is_similarity());
+        assert!((Affine2::from_scaling(3.0) * Affine2::from_angle(-2.0)).is_similarity());
+        assert!(!Affine2::from_scale(vec2(1.0, 2.0)).is_similarity());
+        assert!(!Affine2::from_skew(vec2(0.3, 0.0)).is_similarity());
+        assert!(!Affine2::from_scale(vec2(1.0, -1.0)).is_similarity());
+    }
+
+    #[test]
+    fn scale_factor() {
+        let t = Affine2::from_scaling(2.0) * Affine2::from_angle(1.2);
+        assert!((t.scale_factor() - 2.0).abs() < 1e-5);
+        assert!((t.rotation_angle() - 1.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ts_round_trip() {
+        let ts = TSTransform::new(vec2(4.0, -1.0), 0.5);
+        let affine = Affine2::from(ts);
+        let p = pos2(3.0, 9.0);
+        assert_eq!(affine * p, ts * p);
+        assert_eq!(affine.inverse().as_ts_transform(), Some(ts.inverse()));
+    }
+}
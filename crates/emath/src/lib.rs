@@ -25,6 +25,7 @@ use std::ops::{Add, Div, Mul, RangeInclusive, Sub};
 
 // ----------------------------------------------------------------------------
 
+mod affine2;
 pub mod align;
 pub mod easing;
 mod gui_rounding;
@@ -43,6 +44,7 @@ mod vec2;
 mod vec2b;
 
 pub use self::{
+    affine2::Affine2,
     align::{Align, Align2},
     gui_rounding::{GUI_ROUNDING, GuiRounding},
     history::History,
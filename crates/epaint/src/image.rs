@@ -392,7 +392,7 @@ impl AlphaFromCoverage {
                     coverage = 1.0
                 }
                 2.0 * coverage - coverage * coverage
-            },
+            }
         }
     }
 
@@ -56,11 +56,12 @@ pub use self::{
     mesh::{Mesh, Mesh16, Vertex},
     shadow::Shadow,
     shapes::{
-        CircleShape, CubicBezierShape, EllipseShape, PaintCallback, PaintCallbackInfo, PathShape,
-        QuadraticBezierShape, RectShape, Shape, TextShape,
+        CircleShape, CubicBezierShape, EllipseShape, FillRule, PaintCallback, PaintCallbackInfo,
+        PathShape, PolygonShape, QuadraticBezierShape, RectShape, Shape, StyledPathShape,
+        TextShape,
     },
     stats::PaintStats,
-    stroke::{PathStroke, Stroke, StrokeKind},
+    stroke::{DashPattern, LineCap, LineJoin, PathStroke, Stroke, StrokeKind, StrokeStyle},
     tessellator::{TessellationOptions, Tessellator},
     text::{FontFamily, FontId, Fonts, Galley},
     texture_atlas::TextureAtlas,
@@ -121,10 +122,95 @@ impl Default for TextureId {
 pub struct ClippedShape {
     /// Clip / scissor rectangle.
     /// Only show the part of the [`Shape`] that falls within this.
+    ///
+    /// In the same coordinate system as [`Self::shape`], so local if [`Self::transform`] is set.
     pub clip_rect: emath::Rect,
 
     /// The shape
     pub shape: Shape,
+
+    /// See [`Self::transform`].
+    transform: Option<emath::Affine2>,
+
+    /// Clip rectangle in the global coordinate system, applied on top of [`Self::clip_rect`].
+    ///
+    /// Only used together with [`Self::transform`].
+    global_clip_rect: emath::Rect,
+}
+
+impl ClippedShape {
+    /// A shape in the global coordinate system.
+    #[inline]
+    pub fn new(clip_rect: emath::Rect, shape: Shape) -> Self {
+        Self {
+            clip_rect,
+            shape,
+            transform: None,
+            global_clip_rect: emath::Rect::EVERYTHING,
+        }
+    }
+
+    /// If set, both [`Self::clip_rect`] and [`Self::shape`] are in a local coordinate system,
+    /// and this transforms them into the global (screen) coordinate system.
+    /// Use [`Self::global_clip_rect`] for the clip rectangle on screen.
+    ///
+    /// Unlike [`Shape::transform`], this supports rotation and skew exactly:
+    /// the shape is tessellated in local space, clipped against the (local) clip rectangle,
+    /// and only then are the resulting vertices transformed.
+    ///
+    /// Set by [`Self::transform_to_global`].
+    #[inline]
+    pub fn transform(&self) -> Option<emath::Affine2> {
+        self.transform
+    }
+
+    /// The clip rectangle in global coordinates.
+    ///
+    /// If [`Self::transform`] has rotation or skew, this is the bounding box of the transformed clip rectangle,
+    /// limited to the rectangle given to [`Self::clip_to_global_rect`].
+    #[inline]
+    pub fn global_clip_rect(&self) -> emath::Rect {
+        match self.transform {
+            Some(transform) => (transform * self.clip_rect).intersect(self.global_clip_rect),
+            None => self.clip_rect,
+        }
+    }
+
+    /// Transform the shape and clip rectangle into the global coordinate system.
+    ///
+    /// Translations and uniform scaling are applied directly to the shape and clip rectangle,
+    /// while other transforms are stored in [`Self::transform`] to be applied during tessellation.
+    pub fn transform_to_global(&mut self, to_global: emath::Affine2) {
+        if self.global_clip_rect.is_finite() {
+            self.global_clip_rect = to_global * self.global_clip_rect;
+        }
+
+        let to_global = match self.transform {
+            Some(transform) => to_global * transform,
+            None => to_global,
+        };
+
+        if let Some(ts) = to_global.as_ts_transform() {
+            self.clip_rect = (ts * self.clip_rect).intersect(self.global_clip_rect);
+            self.shape.transform(ts);
+            self.transform = None;
+            self.global_clip_rect = emath::Rect::EVERYTHING;
+        } else {
+            self.transform = Some(to_global);
+        }
+    }
+
+    /// Clip the shape to a rectangle in the global coordinate system.
+    ///
+    /// Unlike [`Self::clip_rect`], this is not rotated or skewed by [`Self::transform`],
+    /// so it can be used to keep a rotated shape inside e.g. the clip rectangle of a parent.
+    pub fn clip_to_global_rect(&mut self, global_clip_rect: emath::Rect) {
+        if self.transform.is_some() {
+            self.global_clip_rect = self.global_clip_rect.intersect(global_clip_rect);
+        } else {
+            self.clip_rect = self.clip_rect.intersect(global_clip_rect);
+        }
+    }
 }
 
 /// A [`Mesh`] or [`PaintCallback`] within a clip rectangle.
@@ -1,5 +1,5 @@
 use crate::{Color32, TextureId, WHITE_UV, emath};
-use emath::{Pos2, Rect, Rot2, TSTransform, Vec2};
+use emath::{Affine2, Pos2, Rect, Rot2, TSTransform, Vec2};
 
 /// The 2D vertex type.
 ///
@@ -306,6 +306,66 @@ impl Mesh {
         }
     }
 
+    /// Transform the mesh in-place with the given affine transform.
+    pub fn transform_affine(&mut self, transform: Affine2) {
+        for v in &mut self.vertices {
+            v.pos = transform * v.pos;
+        }
+    }
+
+    /// Cut away all parts of the triangles that fall outside the given rectangle, in-place.
+    ///
+    /// Triangles that cross the edge of the rectangle are split,
+    /// with the uv coordinates and colors of the new vertices interpolated.
+    ///
+    /// This is used when a clip rectangle can't be expressed as a scissor rectangle,
+    /// e.g. because it has been rotated.
+    pub fn clip_to_rect(&mut self, rect: Rect) {
+        profiling::function_scope!();
+
+        if rect.contains_rect(self.calc_bounds()) {
+            return;
+        }
+
+        let mut indices = Vec::with_capacity(self.indices.len());
+        let mut polygon = Vec::with_capacity(8);
+        let mut scratch = Vec::with_capacity(8);
+
+        for i in 0..self.indices.len() / 3 {
+            let tri = [
+                self.indices[3 * i],
+                self.indices[3 * i + 1],
+                self.indices[3 * i + 2],
+            ];
+            let verts = tri.map(|idx| self.vertices[idx as usize]);
+
+            if verts.iter().all(|v| rect.contains(v.pos)) {
+                indices.extend_from_slice(&tri);
+                continue;
+            }
+
+            polygon.clear();
+            polygon.extend_from_slice(&verts);
+            clip_polygon(&mut polygon, &mut scratch, |p| p.x - rect.min.x);
+            clip_polygon(&mut polygon, &mut scratch, |p| rect.max.x - p.x);
+            clip_polygon(&mut polygon, &mut scratch, |p| p.y - rect.min.y);
+            clip_polygon(&mut polygon, &mut scratch, |p| rect.max.y - p.y);
+
+            if polygon.len() < 3 {
+                continue;
+            }
+
+            // Triangle fan of the (convex) clipped polygon:
+            let first = self.vertices.len() as u32;
+            self.vertices.extend_from_slice(&polygon);
+            for j in 1..polygon.len() as u32 - 1 {
+                indices.extend_from_slice(&[first, first + j, first + j + 1]);
+            }
+        }
+
+        self.indices = indices;
+    }
+
     /// Rotate by some angle about an origin, in-place.
     ///
     /// Origin is a position in screen space.
@@ -316,6 +376,38 @@ impl Mesh {
     }
 }
 
+/// One step of Sutherland–Hodgman: keep the part of the convex `polygon`
+/// where `signed_dist` is non-negative.
+fn clip_polygon(
+    polygon: &mut Vec<Vertex>,
+    scratch: &mut Vec<Vertex>,
+    signed_dist: impl Fn(Pos2) -> f32,
+) {
+    if polygon.is_empty() {
+        return;
+    }
+
+    scratch.clear();
+    for i in 0..polygon.len() {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+        let (da, db) = (signed_dist(a.pos), signed_dist(b.pos));
+
+        if 0.0 <= da {
+            scratch.push(a);
+        }
+        if (0.0 <= da) != (0.0 <= db) {
+            let t = da / (da - db);
+            scratch.push(Vertex {
+                pos: a.pos.lerp(b.pos, t),
+                uv: a.uv.lerp(b.uv, t),
+                color: a.color.lerp_to_gamma(b.color, t),
+            });
+        }
+    }
+    std::mem::swap(polygon, scratch);
+}
+
 // ----------------------------------------------------------------------------
 
 /// A version of [`Mesh`] that uses 16-bit indices.
@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
 use crate::{
-    CircleShape, Color32, ColorMode, CubicBezierShape, EllipseShape, Mesh, PathShape,
-    QuadraticBezierShape, RectShape, Shape, TextShape, color,
+    CircleShape, Color32, ColorMode, CubicBezierShape, EllipseShape, Mesh, PathShape, PolygonShape,
+    QuadraticBezierShape, RectShape, Shape, StyledPathShape, TextShape, color,
 };
 
 /// Remember to handle [`Color32::PLACEHOLDER`] specially!
@@ -46,6 +46,29 @@ pub fn adjust_colors(
             adjust_color_mode(&mut stroke.color, adjust_color);
         }
 
+        Shape::Polygon(polygon_shape) => {
+            let PolygonShape {
+                contours: _,
+                fill_rule: _,
+                fill,
+                stroke,
+            } = Arc::make_mut(polygon_shape);
+            adjust_color(fill);
+            adjust_color_mode(&mut stroke.color, adjust_color);
+        }
+
+        Shape::StyledPath(path_shape) => {
+            let StyledPathShape {
+                points: _,
+                widths: _,
+                closed: _,
+                fill,
+                stroke,
+            } = Arc::make_mut(path_shape);
+            adjust_color(fill);
+            adjust_color_mode(&mut stroke.color, adjust_color);
+        }
+
         Shape::Circle(CircleShape {
             center: _,
             radius: _,
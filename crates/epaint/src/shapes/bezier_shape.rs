@@ -3,7 +3,7 @@
 
 use std::ops::Range;
 
-use crate::{Color32, PathShape, PathStroke, Shape};
+use crate::{Color32, PathShape, PathStroke, Shape, StyledPathShape};
 use emath::{Pos2, Rect, RectTransform};
 
 // ----------------------------------------------------------------------------
@@ -76,12 +76,45 @@ impl CubicBezierShape {
         pathshapes
     }
 
+    /// Convert the cubic Bézier curve to a [`StyledPathShape`] with a variable-width stroke.
+    ///
+    /// `widths` are the stroke widths at each of the [`Self::points`].
+    /// The width along the curve is interpolated the same way as the curve itself,
+    /// so the width at the start and end of the curve is the first and last of these.
+    /// The `tolerance` will be used to control the max distance between the curve and the base line.
+    pub fn to_variable_width_path(
+        &self,
+        widths: [f32; 4],
+        tolerance: Option<f32>,
+    ) -> StyledPathShape {
+        let [w0, w1, w2, w3] = widths;
+        let width_at = |t: f32| {
+            let h = 1.0 - t;
+            w3 * t * t * t + w2 * 3.0 * t * t * h + w1 * 3.0 * t * h * h + w0 * h * h * h
+        };
+
+        let tolerance = tolerance.unwrap_or((self.points[0].x - self.points[3].x).abs() * 0.001);
+        let mut points = vec![self.points[0]];
+        let mut stroke_widths = vec![w0];
+        self.for_each_flattened_with_t(tolerance, &mut |p, t| {
+            points.push(p);
+            stroke_widths.push(width_at(t));
+        });
+
+        StyledPathShape {
+            closed: self.closed,
+            fill: self.fill,
+            ..StyledPathShape::variable_width_line(points, stroke_widths, self.stroke.clone())
+        }
+    }
+
     /// The visual bounding rectangle (includes stroke width)
     pub fn visual_bounding_rect(&self) -> Rect {
         if self.fill == Color32::TRANSPARENT && self.stroke.is_empty() {
             Rect::NOTHING
         } else {
-            self.logical_bounding_rect().expand(self.stroke.width / 2.0)
+            self.logical_bounding_rect()
+                .expand(self.stroke.width * self.stroke.max_extent_factor())
         }
     }
 
@@ -437,12 +470,45 @@ impl QuadraticBezierShape {
         }
     }
 
+    /// Convert the quadratic Bézier curve to a [`StyledPathShape`] with a variable-width stroke.
+    ///
+    /// `widths` are the stroke widths at each of the [`Self::points`].
+    /// The width along the curve is interpolated the same way as the curve itself,
+    /// so the width at the start and end of the curve is the first and last of these.
+    /// The `tolerance` will be used to control the max distance between the curve and the base line.
+    pub fn to_variable_width_path(
+        &self,
+        widths: [f32; 3],
+        tolerance: Option<f32>,
+    ) -> StyledPathShape {
+        let [w0, w1, w2] = widths;
+        let width_at = |t: f32| {
+            let h = 1.0 - t;
+            w2 * t * t + w1 * 2.0 * t * h + w0 * h * h
+        };
+
+        let tolerance = tolerance.unwrap_or((self.points[0].x - self.points[2].x).abs() * 0.001);
+        let mut points = vec![self.points[0]];
+        let mut stroke_widths = vec![w0];
+        self.for_each_flattened_with_t(tolerance, &mut |p, t| {
+            points.push(p);
+            stroke_widths.push(width_at(t));
+        });
+
+        StyledPathShape {
+            closed: self.closed,
+            fill: self.fill,
+            ..StyledPathShape::variable_width_line(points, stroke_widths, self.stroke.clone())
+        }
+    }
+
     /// The visual bounding rectangle (includes stroke width)
     pub fn visual_bounding_rect(&self) -> Rect {
         if self.fill == Color32::TRANSPARENT && self.stroke.is_empty() {
             Rect::NOTHING
         } else {
-            self.logical_bounding_rect().expand(self.stroke.width / 2.0)
+            self.logical_bounding_rect()
+                .expand(self.stroke.width * self.stroke.max_extent_factor())
         }
     }
 
@@ -1130,4 +1196,28 @@ mod tests {
 
         assert_eq!(result.len(), 223);
     }
+
+    #[test]
+    fn test_cubic_variable_width_path() {
+        let curve = CubicBezierShape::from_points_stroke(
+            [
+                pos2(10.0, 10.0),
+                pos2(110.0, 170.0),
+                pos2(180.0, 30.0),
+                pos2(270.0, 210.0),
+            ],
+            false,
+            Color32::TRANSPARENT,
+            PathStroke::new(1.0, Color32::WHITE),
+        );
+
+        let path = curve.to_variable_width_path([1.0, 8.0, 8.0, 4.0], Some(0.1));
+        let widths = &path.widths;
+        assert_eq!(path.points.len(), widths.len());
+        assert_eq!(path.points.first(), Some(&curve.points[0]));
+        assert_eq!(path.points.last(), Some(&curve.points[3]));
+        assert_eq!(widths.first(), Some(&1.0));
+        assert!((widths.last().unwrap() - 4.0).abs() < 1e-4);
+        assert!(widths.iter().all(|&w| (1.0..=8.0).contains(&w)));
+    }
 }
@@ -3,8 +3,10 @@ mod circle_shape;
 mod ellipse_shape;
 mod paint_callback;
 mod path_shape;
+mod polygon_shape;
 mod rect_shape;
 mod shape;
+mod styled_path_shape;
 mod text_shape;
 
 pub use self::{
@@ -13,7 +15,9 @@ pub use self::{
     ellipse_shape::EllipseShape,
     paint_callback::{PaintCallback, PaintCallbackInfo},
     path_shape::PathShape,
+    polygon_shape::{FillRule, PolygonShape},
     rect_shape::RectShape,
     shape::Shape,
+    styled_path_shape::StyledPathShape,
     text_shape::TextShape,
 };
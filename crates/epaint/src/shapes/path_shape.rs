@@ -62,13 +62,65 @@ impl PathShape {
         }
     }
 
+    /// The outline of a rounded rectangle, e.g. for a dashed selection rectangle.
+    ///
+    /// Use this instead of a [`RectShape`] for a stroke with a [`PathStroke::style`].
+    pub fn rect(
+        rect: Rect,
+        corner_radius: impl Into<CornerRadius>,
+        fill: impl Into<Color32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        let mut points = vec![];
+        crate::tessellator::path::rounded_rectangle(&mut points, rect, corner_radius.into().into());
+        Self::convex_polygon(points, fill, stroke)
+    }
+
+    /// The outline of a circle.
+    ///
+    /// Use this instead of a [`CircleShape`] for a stroke with a [`PathStroke::style`].
+    #[inline]
+    pub fn circle(
+        center: Pos2,
+        radius: f32,
+        fill: impl Into<Color32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        Self::ellipse(center, Vec2::splat(radius), fill, stroke)
+    }
+
+    /// The outline of an ellipse.
+    ///
+    /// Use this instead of an [`EllipseShape`] for a stroke with a [`PathStroke::style`].
+    pub fn ellipse(
+        center: Pos2,
+        radius: Vec2,
+        fill: impl Into<Color32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        let points = super::shape::ellipse_points(center, radius);
+        Self::convex_polygon(points, fill, stroke)
+    }
+
+    /// Give the stroke a different width at each point, e.g. for a pen stroke or a tapered line.
+    ///
+    /// See [`StyledPathShape::variable_width_line`].
+    #[inline]
+    pub fn with_widths(self, widths: Vec<f32>) -> StyledPathShape {
+        StyledPathShape {
+            widths,
+            ..self.into()
+        }
+    }
+
     /// The visual bounding rectangle (includes stroke width)
     #[inline]
     pub fn visual_bounding_rect(&self) -> Rect {
         if self.fill == Color32::TRANSPARENT && self.stroke.is_empty() {
             Rect::NOTHING
         } else {
-            Rect::from_points(&self.points).expand(self.stroke.width / 2.0)
+            Rect::from_points(&self.points)
+                .expand(self.stroke.width * self.stroke.max_extent_factor())
         }
     }
 }
@@ -0,0 +1,565 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// Decides which parts of a [`PolygonShape`] are inside,
+/// when contours overlap, intersect themselves, or lie inside each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum FillRule {
+    /// A point is inside if the contours wind around it a non-zero number of times.
+    ///
+    /// To cut a hole, the contour of the hole must wind the opposite way of the contour around it.
+    #[default]
+    NonZero,
+
+    /// A point is inside if a ray from it crosses the contours an odd number of times.
+    ///
+    /// Any contour inside another one is a hole, regardless of its winding order.
+    EvenOdd,
+}
+
+impl FillRule {
+    #[inline]
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            Self::NonZero => winding != 0,
+            Self::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// A polygon made up of any number of closed contours.
+///
+/// Unlike [`PathShape`], the polygon can be concave, self-intersecting, and have holes.
+/// Which parts are filled is decided by the [`FillRule`].
+///
+/// Polygons can be combined with [`Self::union`], [`Self::intersection`] and [`Self::difference`].
+/// Only the contours are combined: the result is painted with the style of the first polygon.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PolygonShape {
+    /// Each contour is closed, i.e. the last point is connected to the first one.
+    pub contours: Vec<Vec<Pos2>>,
+
+    /// How to decide which parts of the polygon are filled.
+    pub fill_rule: FillRule,
+
+    /// The color of the filled parts, see [`Self::fill_rule`].
+    pub fill: Color32,
+
+    /// The stroke is painted along all the contours.
+    pub stroke: PathStroke,
+}
+
+impl PolygonShape {
+    #[inline]
+    pub fn new(
+        contours: Vec<Vec<Pos2>>,
+        fill: impl Into<Color32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        Self {
+            contours,
+            fill_rule: FillRule::default(),
+            fill: fill.into(),
+            stroke: stroke.into(),
+        }
+    }
+
+    #[inline]
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    /// The visual bounding rectangle (includes stroke width)
+    pub fn visual_bounding_rect(&self) -> Rect {
+        if self.fill == Color32::TRANSPARENT && self.stroke.is_empty() {
+            return Rect::NOTHING;
+        }
+        let mut rect = Rect::NOTHING;
+        for contour in &self.contours {
+            for &point in contour {
+                rect.extend_with(point);
+            }
+        }
+        rect.expand(self.stroke.width * self.stroke.max_extent_factor())
+    }
+
+    /// Is the given point inside the filled area of the polygon?
+    pub fn contains(&self, pos: Pos2) -> bool {
+        let mut winding = 0;
+        for contour in &self.contours {
+            for (i, &a) in contour.iter().enumerate() {
+                let b = contour[(i + 1) % contour.len()];
+                if let Some((x, direction)) = crossing([a, b], pos.y, Axis::X) {
+                    if x < pos.x {
+                        winding += direction;
+                    }
+                }
+            }
+        }
+        self.fill_rule.is_inside(winding)
+    }
+
+    /// The area covered by either polygon.
+    ///
+    /// See [`Self::filled_contours`] for the shape of the result.
+    /// The result keeps the fill and stroke of `self`; those of `other` are ignored.
+    pub fn union(&self, other: &Self) -> Self {
+        self.boolean_op(other, |a, b| a || b)
+    }
+
+    /// The area covered by both polygons.
+    ///
+    /// See [`Self::filled_contours`] for the shape of the result.
+    /// The result keeps the fill and stroke of `self`; those of `other` are ignored.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.boolean_op(other, |a, b| a && b)
+    }
+
+    /// The area covered by `self`, but not by `other`.
+    ///
+    /// See [`Self::filled_contours`] for the shape of the result.
+    /// The result keeps the fill and stroke of `self`; those of `other` are ignored.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.boolean_op(other, |a, b| a && !b)
+    }
+
+    /// The outlines of the filled area.
+    ///
+    /// The contours do not cross each other or themselves.
+    /// Outer contours are clockwise and holes are counter-clockwise,
+    /// so the result can be filled with [`FillRule::NonZero`].
+    pub fn filled_contours(&self) -> Vec<Vec<Pos2>> {
+        let mut edges = Vec::new();
+        add_edges(&mut edges, &self.contours, 0);
+        let edges = split_at_intersections(&edges);
+        let fill_rule = self.fill_rule;
+        chain_contours(&boundary_edges(&edges, |winding| {
+            fill_rule.is_inside(winding[0])
+        }))
+    }
+
+    fn boolean_op(&self, other: &Self, op: impl Fn(bool, bool) -> bool) -> Self {
+        let mut edges = Vec::new();
+        add_edges(&mut edges, &self.contours, 0);
+        add_edges(&mut edges, &other.contours, 1);
+        let edges = split_at_intersections(&edges);
+        let contours = chain_contours(&boundary_edges(&edges, |winding| {
+            op(
+                self.fill_rule.is_inside(winding[0]),
+                other.fill_rule.is_inside(winding[1]),
+            )
+        }));
+        Self {
+            contours,
+            fill_rule: FillRule::NonZero,
+            fill: self.fill,
+            stroke: self.stroke.clone(),
+        }
+    }
+}
+
+impl From<PolygonShape> for Shape {
+    #[inline(always)]
+    fn from(shape: PolygonShape) -> Self {
+        Self::Polygon(Arc::new(shape))
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+/// A straight edge of one of the (at most two) polygons taking part in an operation.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    a: Pos2,
+    b: Pos2,
+
+    /// Which polygon the edge belongs to.
+    operand: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    X,
+    Y,
+}
+
+/// Where the edge crosses the line `y = at` (or `x = at` for [`Axis::Y`]),
+/// and in which direction it winds.
+///
+/// The start of the edge is inclusive and the end is exclusive,
+/// so that a ray through a vertex crosses exactly one of its edges.
+#[inline]
+fn crossing([a, b]: [Pos2; 2], at: f32, axis: Axis) -> Option<(f32, i32)> {
+    let (a, b) = match axis {
+        Axis::X => (a, b),
+        Axis::Y => (pos2(a.y, -a.x), pos2(b.y, -b.x)),
+    };
+    if (a.y <= at) == (b.y <= at) {
+        return None;
+    }
+    let x = a.x + (at - a.y) * (b.x - a.x) / (b.y - a.y);
+    let direction = if a.y < b.y { 1 } else { -1 };
+    Some((x, direction))
+}
+
+fn add_edges(edges: &mut Vec<Edge>, contours: &[Vec<Pos2>], operand: usize) {
+    for contour in contours {
+        if contour.len() < 2 {
+            continue;
+        }
+        for (i, &a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            if a != b && a.is_finite() && b.is_finite() {
+                edges.push(Edge { a, b, operand });
+            }
+        }
+    }
+}
+
+/// Split the edges where they cross or touch each other,
+/// so that the resulting edges only meet at their end points.
+fn split_at_intersections(edges: &[Edge]) -> Vec<Edge> {
+    const EPS: f32 = 1e-5;
+
+    // Where to split each edge, as `(t, point)`:
+    let mut splits: Vec<Vec<(f32, Pos2)>> = vec![vec![]; edges.len()];
+
+    let min_x = |e: &Edge| e.a.x.min(e.b.x);
+    let max_x = |e: &Edge| e.a.x.max(e.b.x);
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_by(|&i, &j| min_x(&edges[i]).total_cmp(&min_x(&edges[j])));
+
+    for (k, &i) in order.iter().enumerate() {
+        let e = &edges[i];
+        let (e_min_y, e_max_y) = (e.a.y.min(e.b.y), e.a.y.max(e.b.y));
+        for &j in &order[k + 1..] {
+            let f = &edges[j];
+            if max_x(e) < min_x(f) {
+                break;
+            }
+            if f.a.y.max(f.b.y) < e_min_y || e_max_y < f.a.y.min(f.b.y) {
+                continue;
+            }
+
+            let r = e.b - e.a;
+            let s = f.b - f.a;
+            let denom = r.x * s.y - r.y * s.x;
+            let qp = f.a - e.a;
+
+            if denom.abs() <= EPS * r.length() * s.length() {
+                // Parallel. If they are on the same line, split each at the ends of the other:
+                if (qp.x * r.y - qp.y * r.x).abs() <= EPS * r.length() * (1.0 + qp.length()) {
+                    for (index, edge, other) in [(i, e, f), (j, f, e)] {
+                        let dir = edge.b - edge.a;
+                        for point in [other.a, other.b] {
+                            let t = (point - edge.a).dot(dir) / dir.length_sq();
+                            if EPS < t && t < 1.0 - EPS {
+                                splits[index].push((t, point));
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let t = (qp.x * s.y - qp.y * s.x) / denom;
+            let u = (qp.x * r.y - qp.y * r.x) / denom;
+            if !(-EPS..=1.0 + EPS).contains(&t) || !(-EPS..=1.0 + EPS).contains(&u) {
+                continue;
+            }
+
+            // Prefer the exact end points, so that the split edges connect exactly:
+            let point = if u <= EPS {
+                f.a
+            } else if 1.0 - EPS <= u {
+                f.b
+            } else if t <= EPS {
+                e.a
+            } else if 1.0 - EPS <= t {
+                e.b
+            } else {
+                e.a + t * r
+            };
+            if EPS < t && t < 1.0 - EPS {
+                splits[i].push((t, point));
+            }
+            if EPS < u && u < 1.0 - EPS {
+                splits[j].push((u, point));
+            }
+        }
+    }
+
+    let mut result = Vec::with_capacity(edges.len());
+    for (edge, mut splits) in edges.iter().zip(splits) {
+        splits.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut a = edge.a;
+        for b in splits.into_iter().map(|(_, point)| point).chain([edge.b]) {
+            if a != b {
+                result.push(Edge { a, b, ..*edge });
+                a = b;
+            }
+        }
+    }
+    result
+}
+
+/// Finds the edges that may cross a line of constant `y` (or `x` for [`Axis::Y`]) quickly,
+/// by sorting them into bands.
+struct BandIndex {
+    axis: Axis,
+    min: f32,
+    bands_per_unit: f32,
+    bands: Vec<Vec<usize>>,
+}
+
+impl BandIndex {
+    fn new(edges: &[Edge], axis: Axis) -> Self {
+        let coord = |p: Pos2| match axis {
+            Axis::X => p.y,
+            Axis::Y => -p.x,
+        };
+        let (min, max) = edges
+            .iter()
+            .flat_map(|e| [coord(e.a), coord(e.b)])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| {
+                (min.min(c), max.max(c))
+            });
+        let num_bands = (edges.len() as f32).sqrt().ceil().max(1.0);
+        let bands_per_unit = if min < max {
+            num_bands / (max - min)
+        } else {
+            0.0
+        };
+        let mut index = Self {
+            axis,
+            min,
+            bands_per_unit,
+            bands: vec![vec![]; num_bands as usize],
+        };
+        for (i, e) in edges.iter().enumerate() {
+            let (lo, hi) = (coord(e.a).min(coord(e.b)), coord(e.a).max(coord(e.b)));
+            for band in index.band(lo)..=index.band(hi) {
+                index.bands[band].push(i);
+            }
+        }
+        index
+    }
+
+    fn band(&self, coord: f32) -> usize {
+        let band = ((coord - self.min) * self.bands_per_unit).floor();
+        (band.max(0.0) as usize).min(self.bands.len() - 1)
+    }
+
+    /// All edges that may cross the line.
+    fn edges_at(&self, at: f32) -> &[usize] {
+        &self.bands[self.band(at)]
+    }
+}
+
+/// The edges that separate the inside from the outside,
+/// oriented clockwise around the inside (i.e. with the inside to the right in screen space).
+///
+/// `is_inside` is given the winding number of each operand.
+fn boundary_edges(edges: &[Edge], is_inside: impl Fn(&[i32; 2]) -> bool) -> Vec<[Pos2; 2]> {
+    if edges.is_empty() {
+        return vec![];
+    }
+
+    let index_x = BandIndex::new(edges, Axis::X);
+    let index_y = BandIndex::new(edges, Axis::Y);
+
+    let mut result: Vec<[Pos2; 2]> = Vec::new();
+    for edge in edges {
+        let dir = edge.b - edge.a;
+        let mid = edge.a.lerp(edge.b, 0.5);
+
+        // Cast a ray across the edge, along whichever axis is most perpendicular to it:
+        let (index, along, at, after) = if dir.x.abs() <= dir.y.abs() {
+            (&index_x, mid.x, mid.y, Vec2::X)
+        } else {
+            (&index_y, mid.y, -mid.x, Vec2::Y)
+        };
+        let tolerance = 1e-4 * (1.0 + along.abs());
+
+        // The winding numbers just before and just after the edge:
+        let mut before = [0; 2];
+        let mut after_winding = [0; 2];
+        for &i in index.edges_at(at) {
+            let other = &edges[i];
+            if let Some((x, direction)) = crossing([other.a, other.b], at, index.axis) {
+                if x < along - tolerance {
+                    before[other.operand] += direction;
+                } else if x <= along + tolerance {
+                    // This edge, or one lying on top of it
+                    after_winding[other.operand] += direction;
+                }
+            }
+        }
+        after_winding[0] += before[0];
+        after_winding[1] += before[1];
+
+        let inside_after = is_inside(&after_winding);
+        if is_inside(&before) == inside_after {
+            continue;
+        }
+
+        let inside_dir = if inside_after { after } else { -after };
+        // `rot90` points outwards for clockwise contours:
+        let boundary = if dir.rot90().dot(inside_dir) < 0.0 {
+            [edge.a, edge.b]
+        } else {
+            [edge.b, edge.a]
+        };
+        result.push(boundary);
+    }
+
+    // Edges lying on top of each other give the same boundary edge:
+    let key = |[a, b]: &[Pos2; 2]| [a.x, a.y, b.x, b.y].map(|c| (c + 0.0).to_bits());
+    result.sort_by_key(key);
+    result.dedup_by_key(|edge| key(edge));
+    result
+}
+
+/// Connect edges into closed contours.
+fn chain_contours(edges: &[[Pos2; 2]]) -> Vec<Vec<Pos2>> {
+    let key = |p: Pos2| [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits()];
+
+    let mut outgoing: ahash::HashMap<[u32; 2], Vec<usize>> = Default::default();
+    for (i, [a, _]) in edges.iter().enumerate() {
+        outgoing.entry(key(*a)).or_default().push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+
+        let [first, mut end] = edges[start];
+        let mut contour = vec![first];
+        while end != first {
+            let Some(next) = outgoing
+                .get_mut(&key(end))
+                .and_then(|candidates| candidates.iter().copied().find(|&i| !used[i]))
+            else {
+                break;
+            };
+            used[next] = true;
+            contour.push(end);
+            end = edges[next][1];
+        }
+
+        if 3 <= contour.len() {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f32, max: f32) -> Vec<Pos2> {
+        vec![
+            pos2(min, min),
+            pos2(max, min),
+            pos2(max, max),
+            pos2(min, max),
+        ]
+    }
+
+    fn area(contours: &[Vec<Pos2>]) -> f32 {
+        // Positive for clockwise contours (in screen space):
+        let mut area = 0.0;
+        for contour in contours {
+            for (i, &a) in contour.iter().enumerate() {
+                let b = contour[(i + 1) % contour.len()];
+                area += a.x * b.y - b.x * a.y;
+            }
+        }
+        0.5 * area
+    }
+
+    fn polygon(contours: Vec<Vec<Pos2>>) -> PolygonShape {
+        PolygonShape::new(contours, Color32::WHITE, Stroke::NONE)
+    }
+
+    #[test]
+    fn fill_rules() {
+        // Two squares on top of each other, the inner one with the same winding:
+        let shape = polygon(vec![square(0.0, 10.0), square(2.0, 8.0)]);
+        assert!(shape.contains(pos2(5.0, 5.0)));
+        assert!(shape.contains(pos2(1.0, 5.0)));
+        assert_eq!(area(&shape.filled_contours()), 100.0);
+
+        let shape = shape.with_fill_rule(FillRule::EvenOdd);
+        assert!(!shape.contains(pos2(5.0, 5.0)));
+        assert!(shape.contains(pos2(1.0, 5.0)));
+        assert_eq!(area(&shape.filled_contours()), 64.0);
+
+        // A hole winding the other way:
+        let mut hole = square(2.0, 8.0);
+        hole.reverse();
+        let shape = polygon(vec![square(0.0, 10.0), hole]);
+        assert!(!shape.contains(pos2(5.0, 5.0)));
+        assert_eq!(area(&shape.filled_contours()), 64.0);
+    }
+
+    #[test]
+    fn self_intersecting() {
+        // A bow tie, crossing itself at (5, 5):
+        let bow_tie = polygon(vec![vec![
+            pos2(0.0, 0.0),
+            pos2(10.0, 10.0),
+            pos2(10.0, 0.0),
+            pos2(0.0, 10.0),
+        ]]);
+        let contours = bow_tie.filled_contours();
+        assert_eq!(contours.len(), 2);
+        assert!((area(&contours) - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn boolean_ops() {
+        let a = polygon(vec![square(0.0, 10.0)]);
+        let b = polygon(vec![square(5.0, 15.0)]);
+
+        let union = a.union(&b);
+        assert_eq!(union.contours.len(), 1);
+        assert_eq!(area(&union.contours), 175.0);
+
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.contours.len(), 1);
+        assert_eq!(area(&intersection.contours), 25.0);
+
+        let difference = a.difference(&b);
+        assert_eq!(difference.contours.len(), 1);
+        assert_eq!(area(&difference.contours), 75.0);
+
+        // Cutting a hole:
+        let inner = polygon(vec![square(2.0, 8.0)]);
+        let ring = a.difference(&inner);
+        assert_eq!(ring.contours.len(), 2);
+        assert_eq!(area(&ring.contours), 64.0);
+        assert!(ring.contains(pos2(1.0, 1.0)));
+        assert!(!ring.contains(pos2(5.0, 5.0)));
+
+        // Shared edges:
+        let c = polygon(vec![vec![
+            pos2(10.0, 0.0),
+            pos2(20.0, 0.0),
+            pos2(20.0, 10.0),
+            pos2(10.0, 10.0),
+        ]]);
+        let union = a.union(&c);
+        assert_eq!(area(&union.contours), 200.0);
+        assert!(a.intersection(&c).contours.is_empty());
+    }
+}
@@ -2,17 +2,17 @@
 
 use std::sync::Arc;
 
-use emath::{Align2, Pos2, Rangef, Rect, TSTransform, Vec2, pos2};
+use emath::{Affine2, Align2, Pos2, Rangef, Rect, TSTransform, Vec2, pos2};
 
 use crate::{
-    Color32, CornerRadius, Mesh, Stroke, StrokeKind, TextureId,
+    Color32, CornerRadius, DashPattern, Mesh, Stroke, StrokeKind, TextureId,
     stroke::PathStroke,
     text::{FontId, Fonts, Galley},
 };
 
 use super::{
-    CircleShape, CubicBezierShape, EllipseShape, PaintCallback, PathShape, QuadraticBezierShape,
-    RectShape, TextShape,
+    CircleShape, CubicBezierShape, EllipseShape, PaintCallback, PathShape, PolygonShape,
+    QuadraticBezierShape, RectShape, StyledPathShape, TextShape,
 };
 
 /// A paint primitive such as a circle or a piece of text.
@@ -45,6 +45,17 @@ pub enum Shape {
     /// The path can have a stroke and/or fill (if closed).
     Path(PathShape),
 
+    /// A series of lines between points, with a different stroke width at each point.
+    ///
+    /// Wrapped in an [`Arc`] to minimize the size of [`Shape`].
+    StyledPath(Arc<StyledPathShape>),
+
+    /// A polygon with any number of contours, which can be concave,
+    /// self-intersecting and have holes.
+    ///
+    /// Wrapped in an [`Arc`] to minimize the size of [`Shape`].
+    Polygon(Arc<PolygonShape>),
+
     /// Rectangle with optional outline and fill.
     Rect(RectShape),
 
@@ -74,11 +85,11 @@ pub enum Shape {
 fn shape_size() {
     assert_eq!(
         std::mem::size_of::<Shape>(),
-        64,
+        72,
         "Shape changed size! If it shrank - good! Update this test. If it grew - bad! Try to find a way to avoid it."
     );
     assert!(
-        std::mem::size_of::<Shape>() <= 64,
+        std::mem::size_of::<Shape>() <= 72,
         "Shape is getting way too big!"
     );
 }
@@ -148,6 +159,18 @@ impl Shape {
         Self::Path(PathShape::line(points, stroke))
     }
 
+    /// A line through many points, with a different width at each point.
+    ///
+    /// See [`StyledPathShape::variable_width_line`].
+    #[inline]
+    pub fn variable_width_line(
+        points: Vec<Pos2>,
+        widths: Vec<f32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        StyledPathShape::variable_width_line(points, widths, stroke).into()
+    }
+
     /// A line that closes back to the start point again.
     #[inline]
     pub fn closed_line(points: Vec<Pos2>, stroke: impl Into<PathStroke>) -> Self {
@@ -167,6 +190,9 @@ impl Shape {
     }
 
     /// Turn a line into dashes.
+    ///
+    /// Each dash is a separate line segment, so dashes that go around corners are not joined.
+    /// Use [`Self::dashed_path`] for dashes that follow the line around corners.
     pub fn dashed_line(
         path: &[Pos2],
         stroke: impl Into<Stroke>,
@@ -185,7 +211,25 @@ impl Shape {
         shapes
     }
 
+    /// A dashed line through many points, as a single [`PathShape`].
+    ///
+    /// Unlike [`Self::dashed_line`], the dashes follow the line around corners,
+    /// and the `dash` pattern scales with the stroke when the shape is transformed.
+    #[inline]
+    pub fn dashed_path(
+        points: Vec<Pos2>,
+        stroke: impl Into<PathStroke>,
+        dash: DashPattern,
+    ) -> Self {
+        let stroke = stroke.into();
+        let style = stroke.style.as_deref().copied().unwrap_or_default();
+        Self::line(points, stroke.with_style(style.with_dash(dash)))
+    }
+
     /// Turn a line into dashes with different dash/gap lengths and a start offset.
+    ///
+    /// Each dash is a separate line segment, so dashes that go around corners are not joined.
+    /// Use [`Self::dashed_path`] for dashes that follow the line around corners.
     pub fn dashed_line_with_offset(
         path: &[Pos2],
         stroke: impl Into<Stroke>,
@@ -244,9 +288,23 @@ impl Shape {
         );
     }
 
+    /// A polygon with a fill and optional stroke,
+    /// which can be concave and have holes.
+    ///
+    /// See [`PolygonShape`].
+    #[inline]
+    pub fn polygon(
+        contours: Vec<Vec<Pos2>>,
+        fill: impl Into<Color32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        PolygonShape::new(contours, fill, stroke).into()
+    }
+
     /// A convex polygon with a fill and optional stroke.
     ///
     /// The most performant winding order is clockwise.
+    /// For concave polygons, use [`Self::polygon`].
     #[inline]
     pub fn convex_polygon(
         points: Vec<Pos2>,
@@ -377,6 +435,8 @@ impl Shape {
                 }
             }
             Self::Path(path_shape) => path_shape.visual_bounding_rect(),
+            Self::StyledPath(path_shape) => path_shape.visual_bounding_rect(),
+            Self::Polygon(polygon_shape) => polygon_shape.visual_bounding_rect(),
             Self::Rect(rect_shape) => rect_shape.visual_bounding_rect(),
             Self::Text(text_shape) => text_shape.visual_bounding_rect(),
             Self::Mesh(mesh) => mesh.calc_bounds(),
@@ -448,7 +508,21 @@ impl Shape {
                 for p in &mut path_shape.points {
                     *p = transform * *p;
                 }
-                path_shape.stroke.width *= transform.scaling;
+                path_shape.stroke.scale(transform.scaling);
+            }
+            Self::StyledPath(path_shape) => {
+                let path_shape = Arc::make_mut(path_shape);
+                for p in &mut path_shape.points {
+                    *p = transform * *p;
+                }
+                path_shape.scale_widths(transform.scaling);
+            }
+            Self::Polygon(polygon_shape) => {
+                let polygon_shape = Arc::make_mut(polygon_shape);
+                for p in polygon_shape.contours.iter_mut().flatten() {
+                    *p = transform * *p;
+                }
+                polygon_shape.stroke.scale(transform.scaling);
             }
             Self::Rect(rect_shape) => {
                 rect_shape.rect = transform * rect_shape.rect;
@@ -466,19 +540,176 @@ impl Shape {
                 for p in &mut bezier.points {
                     *p = transform * *p;
                 }
-                bezier.stroke.width *= transform.scaling;
+                bezier.stroke.scale(transform.scaling);
             }
             Self::CubicBezier(bezier) => {
                 for p in &mut bezier.points {
                     *p = transform * *p;
                 }
-                bezier.stroke.width *= transform.scaling;
+                bezier.stroke.scale(transform.scaling);
             }
             Self::Callback(shape) => {
                 shape.rect = transform * shape.rect;
             }
         }
     }
+
+    /// Transform the shape with a general affine transform (rotation, skew, …), in-place.
+    ///
+    /// If the transform is only a translation and uniform scaling, this is the same as [`Self::transform`].
+    ///
+    /// Otherwise rectangles, circles and ellipses are converted into [`Self::Path`]s
+    /// (or a [`Self::Mesh`] for textured rectangles), and stroke widths are scaled by
+    /// [`Affine2::scale_factor`].
+    /// Blur is lost, and text can only be rotated and uniformly scaled,
+    /// so any skew or non-uniform scaling of text is approximated.
+    ///
+    /// For exact results, give the [`crate::ClippedShape`] a transform instead
+    /// (see [`crate::ClippedShape::transform_to_global`]), which transforms the tessellated triangles.
+    pub fn transform_affine(&mut self, transform: Affine2) {
+        if let Some(ts) = transform.as_ts_transform() {
+            self.transform(ts);
+            return;
+        }
+
+        let scale_factor = transform.scale_factor();
+
+        match self {
+            Self::Noop => {}
+            Self::Vec(shapes) => {
+                for shape in shapes {
+                    shape.transform_affine(transform);
+                }
+            }
+            Self::Circle(circle_shape) => {
+                let CircleShape {
+                    center,
+                    radius,
+                    fill,
+                    stroke,
+                } = *circle_shape;
+                let points = ellipse_points(center, Vec2::splat(radius));
+                *self = Self::convex_polygon(points, fill, stroke);
+                self.transform_affine(transform);
+            }
+            Self::Ellipse(ellipse_shape) => {
+                let EllipseShape {
+                    center,
+                    radius,
+                    fill,
+                    stroke,
+                } = *ellipse_shape;
+                let points = ellipse_points(center, radius);
+                *self = Self::convex_polygon(points, fill, stroke);
+                self.transform_affine(transform);
+            }
+            Self::LineSegment { points, stroke } => {
+                for p in points {
+                    *p = transform * *p;
+                }
+                stroke.width *= scale_factor;
+            }
+            Self::Path(path_shape) => {
+                for p in &mut path_shape.points {
+                    *p = transform * *p;
+                }
+                path_shape.stroke.scale(scale_factor);
+            }
+            Self::StyledPath(path_shape) => {
+                let path_shape = Arc::make_mut(path_shape);
+                for p in &mut path_shape.points {
+                    *p = transform * *p;
+                }
+                path_shape.scale_widths(scale_factor);
+            }
+            Self::Polygon(polygon_shape) => {
+                let polygon_shape = Arc::make_mut(polygon_shape);
+                for p in polygon_shape.contours.iter_mut().flatten() {
+                    *p = transform * *p;
+                }
+                polygon_shape.stroke.scale(scale_factor);
+            }
+            Self::Rect(rect_shape) => {
+                let mut shapes = vec![];
+
+                let texture = rect_shape.brush.as_deref();
+                if let Some(brush) = texture {
+                    let mut mesh = Mesh::with_texture(brush.fill_texture_id);
+                    mesh.add_rect_with_uv(rect_shape.rect, brush.uv, rect_shape.fill);
+                    shapes.push(Self::mesh(mesh));
+                } else {
+                    let mut points = vec![];
+                    crate::tessellator::path::rounded_rectangle(
+                        &mut points,
+                        rect_shape.rect,
+                        rect_shape.corner_radius.into(),
+                    );
+                    shapes.push(Self::Path(PathShape {
+                        points,
+                        closed: true,
+                        fill: rect_shape.fill,
+                        stroke: PathStroke {
+                            kind: rect_shape.stroke_kind,
+                            ..rect_shape.stroke.into()
+                        },
+                    }));
+                }
+
+                if texture.is_some() && !rect_shape.stroke.is_empty() {
+                    let mut points = vec![];
+                    crate::tessellator::path::rounded_rectangle(
+                        &mut points,
+                        rect_shape.rect,
+                        rect_shape.corner_radius.into(),
+                    );
+                    shapes.push(Self::Path(PathShape {
+                        points,
+                        closed: true,
+                        fill: Color32::TRANSPARENT,
+                        stroke: PathStroke {
+                            kind: rect_shape.stroke_kind,
+                            ..rect_shape.stroke.into()
+                        },
+                    }));
+                }
+
+                *self = Self::Vec(shapes);
+                self.transform_affine(transform);
+            }
+            Self::Text(text_shape) => {
+                text_shape.transform_affine(transform);
+            }
+            Self::Mesh(mesh) => {
+                Arc::make_mut(mesh).transform_affine(transform);
+            }
+            Self::QuadraticBezier(bezier) => {
+                for p in &mut bezier.points {
+                    *p = transform * *p;
+                }
+                bezier.stroke.scale(scale_factor);
+            }
+            Self::CubicBezier(bezier) => {
+                for p in &mut bezier.points {
+                    *p = transform * *p;
+                }
+                bezier.stroke.scale(scale_factor);
+            }
+            Self::Callback(shape) => {
+                shape.rect = transform * shape.rect;
+            }
+        }
+    }
+}
+
+/// The outline of an ellipse, with enough points to look smooth after moderate magnification.
+pub(super) fn ellipse_points(center: Pos2, radius: Vec2) -> Vec<Pos2> {
+    let num_points = ((radius.max_elem() * 2.0).ceil() as usize).clamp(16, 256);
+    (0..num_points)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / num_points as f32;
+            center + radius * Vec2::angled(angle)
+        })
+        .collect()
 }
 
 // ----------------------------------------------------------------------------
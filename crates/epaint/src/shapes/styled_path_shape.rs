@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// A path with a different stroke width at each point.
+///
+/// Use this for pen strokes and tapered lines.
+/// The caps, joins and dashes come from the [`PathStroke::style`] of [`Self::stroke`].
+/// Use [`PathShape::with_widths`] or [`From<PathShape>`] to turn a [`PathShape`] into one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct StyledPathShape {
+    /// Filled paths should prefer clockwise order.
+    pub points: Vec<Pos2>,
+
+    /// The width of the stroke at each of the [`Self::points`].
+    ///
+    /// The width is interpolated linearly between the points.
+    /// Points without a width (e.g. all of them, if this is empty) use the width of [`Self::stroke`].
+    pub widths: Vec<f32>,
+
+    /// If true, connect the first and last of the points together.
+    /// This is required if `fill != TRANSPARENT`.
+    pub closed: bool,
+
+    /// Fill is only supported for convex polygons.
+    pub fill: Color32,
+
+    /// Color, thickness and style of the line.
+    pub stroke: PathStroke,
+}
+
+impl StyledPathShape {
+    /// A line through many points.
+    #[inline]
+    pub fn line(points: Vec<Pos2>, stroke: impl Into<PathStroke>) -> Self {
+        Self {
+            points,
+            widths: Vec::new(),
+            closed: false,
+            fill: Default::default(),
+            stroke: stroke.into(),
+        }
+    }
+
+    /// A line that closes back to the start point again.
+    #[inline]
+    pub fn closed_line(points: Vec<Pos2>, stroke: impl Into<PathStroke>) -> Self {
+        Self {
+            closed: true,
+            ..Self::line(points, stroke)
+        }
+    }
+
+    /// A line through many points, with a different width at each point.
+    ///
+    /// Useful for pen strokes (e.g. with the width proportional to the pen pressure) and tapered lines.
+    /// The width of the `stroke` is only used for points missing from `widths`.
+    ///
+    /// `widths` should have the same length as `points`.
+    #[inline]
+    pub fn variable_width_line(
+        points: Vec<Pos2>,
+        widths: Vec<f32>,
+        stroke: impl Into<PathStroke>,
+    ) -> Self {
+        Self {
+            widths,
+            ..Self::line(points, stroke)
+        }
+    }
+
+    /// The widest part of the stroke.
+    pub fn max_stroke_width(&self) -> f32 {
+        let fallback = if self.widths.len() < self.points.len() {
+            self.stroke.width
+        } else {
+            0.0
+        };
+        self.widths.iter().copied().fold(fallback, f32::max)
+    }
+
+    /// Scale all the stroke widths and dashes by `factor`.
+    pub(crate) fn scale_widths(&mut self, factor: f32) {
+        self.stroke.scale(factor);
+        for width in &mut self.widths {
+            *width *= factor;
+        }
+    }
+
+    /// The visual bounding rectangle (includes stroke width)
+    #[inline]
+    pub fn visual_bounding_rect(&self) -> Rect {
+        if self.fill == Color32::TRANSPARENT
+            && (self.stroke.color == ColorMode::TRANSPARENT || self.max_stroke_width() <= 0.0)
+        {
+            Rect::NOTHING
+        } else {
+            Rect::from_points(&self.points)
+                .expand(self.max_stroke_width() * self.stroke.max_extent_factor())
+        }
+    }
+}
+
+impl From<PathShape> for StyledPathShape {
+    #[inline]
+    fn from(shape: PathShape) -> Self {
+        let PathShape {
+            points,
+            closed,
+            fill,
+            stroke,
+        } = shape;
+        Self {
+            points,
+            widths: Vec::new(),
+            closed,
+            fill,
+            stroke,
+        }
+    }
+}
+
+impl From<StyledPathShape> for Shape {
+    #[inline(always)]
+    fn from(shape: StyledPathShape) -> Self {
+        Self::StyledPath(Arc::new(shape))
+    }
+}
@@ -164,6 +164,18 @@ impl TextShape {
             }
         }
     }
+
+    /// Transform the shape with a general affine transform, in-place.
+    ///
+    /// Text can only be rotated and uniformly scaled, so the transform is approximated
+    /// by a rotation of [`emath::Affine2::rotation_angle`] and a scaling of [`emath::Affine2::scale_factor`].
+    /// This is exact for transforms without skew or non-uniform scaling.
+    pub fn transform_affine(&mut self, transform: emath::Affine2) {
+        let pos = transform * self.pos;
+        self.transform(emath::TSTransform::from_scaling(transform.scale_factor()));
+        self.pos = pos;
+        self.angle += transform.rotation_angle();
+    }
 }
 
 impl From<TextShape> for Shape {
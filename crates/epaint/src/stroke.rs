@@ -8,6 +8,10 @@ use super::{Color32, ColorMode, Pos2, Rect, emath};
 
 /// Describes the width and color of a line.
 ///
+/// The line is solid, with butt caps and mitered corners.
+/// For other caps, joins and dashes, use a [`PathStroke`] with a [`PathStroke::style`],
+/// e.g. with [`crate::PathShape::rect`] instead of a [`crate::RectShape`].
+///
 /// The default stroke is the same as [`Stroke::NONE`].
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -111,8 +115,197 @@ pub enum StrokeKind {
     Outside,
 }
 
+/// How the ends of an open stroked path are painted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LineCap {
+    /// The stroke ends exactly at the end point.
+    #[default]
+    Butt,
+
+    /// The stroke ends with a half-circle around the end point.
+    Round,
+
+    /// The stroke ends with a square around the end point,
+    /// i.e. it is extended by half the stroke width.
+    Square,
+}
+
+/// How the corners of a stroked path are painted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum LineJoin {
+    /// The outer edges are extended until they meet in a sharp corner.
+    ///
+    /// Corners that would stick out too far are beveled instead,
+    /// see [`StrokeStyle::miter_limit`].
+    #[default]
+    Miter,
+
+    /// The outer corner is rounded off with a circular arc.
+    Round,
+
+    /// The outer corner is cut off with a straight line.
+    Bevel,
+}
+
+/// How the ends, corners and dashes of a stroke are painted.
+///
+/// See [`PathStroke::style`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct StrokeStyle {
+    /// How the ends of open paths are painted.
+    pub cap: LineCap,
+
+    /// How the corners of the path are painted.
+    pub join: LineJoin,
+
+    /// The maximum ratio between the length of a [`LineJoin::Miter`] corner and the stroke width.
+    ///
+    /// Sharper corners, which would stick out further than this, are beveled instead.
+    /// The default is √2, which bevels corners sharper than a right angle,
+    /// like the corners of a [`crate::PathShape`].
+    pub miter_limit: f32,
+
+    /// Paint the path as dashes. The default is a solid line.
+    pub dash: DashPattern,
+}
+
+impl Default for StrokeStyle {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: std::f32::consts::SQRT_2,
+            dash: DashPattern::SOLID,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// How the ends of open paths are painted.
+    #[inline]
+    pub fn with_cap(self, cap: LineCap) -> Self {
+        Self { cap, ..self }
+    }
+
+    /// How the corners of the path are painted.
+    #[inline]
+    pub fn with_join(self, join: LineJoin) -> Self {
+        Self { join, ..self }
+    }
+
+    /// See [`Self::miter_limit`].
+    #[inline]
+    pub fn with_miter_limit(self, miter_limit: f32) -> Self {
+        Self {
+            miter_limit,
+            ..self
+        }
+    }
+
+    /// Paint the path as dashes.
+    #[inline]
+    pub fn with_dash(self, dash: DashPattern) -> Self {
+        Self { dash, ..self }
+    }
+
+    /// Paint the path as dashes of the given length, separated by gaps of the given length.
+    #[inline]
+    pub fn dashed(self, dash_length: f32, gap_length: f32) -> Self {
+        self.with_dash(DashPattern::new(dash_length, gap_length))
+    }
+
+    /// How far outside the path the stroke may reach, in units of the stroke width.
+    ///
+    /// This accounts for square line caps and for mitered corners,
+    /// which reach up to half the [`Self::miter_limit`] times the width from their point.
+    #[inline]
+    pub(crate) fn max_extent_factor(&self) -> f32 {
+        let cap = match self.cap {
+            LineCap::Butt | LineCap::Round => 0.5,
+            LineCap::Square => 0.5 * std::f32::consts::SQRT_2,
+        };
+        match self.join {
+            LineJoin::Miter => cap.max(0.5 * self.miter_limit),
+            LineJoin::Round | LineJoin::Bevel => cap,
+        }
+    }
+}
+
+/// Splits a stroke into dashes separated by gaps.
+///
+/// The pattern follows the line around corners and along curves.
+/// Each dash is painted with the [`StrokeStyle::cap`] of the stroke.
+///
+/// The default is [`Self::SOLID`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DashPattern {
+    /// The length of each dash.
+    pub dash_length: f32,
+
+    /// The length of the gap between each dash.
+    ///
+    /// If this is zero or negative, the line is solid.
+    pub gap_length: f32,
+
+    /// How far into the pattern the line starts.
+    pub offset: f32,
+}
+
+impl DashPattern {
+    /// A solid line, without any gaps.
+    pub const SOLID: Self = Self {
+        dash_length: 0.0,
+        gap_length: 0.0,
+        offset: 0.0,
+    };
+
+    /// Dashes of the given length, separated by gaps of the given length,
+    /// starting with a dash at the start of the line.
+    #[inline]
+    pub fn new(dash_length: f32, gap_length: f32) -> Self {
+        Self {
+            dash_length,
+            gap_length,
+            offset: 0.0,
+        }
+    }
+
+    /// How far into the pattern the line starts.
+    ///
+    /// Animating this makes the dashes crawl along the line.
+    #[inline]
+    pub fn with_offset(self, offset: f32) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// Is this a solid line without gaps?
+    ///
+    /// An infinite gap is not solid: it paints a single dash at the start of the line.
+    #[inline]
+    pub fn is_solid(&self) -> bool {
+        self.gap_length.is_nan() || self.gap_length <= 0.0
+    }
+}
+
+impl std::ops::MulAssign<f32> for DashPattern {
+    #[inline]
+    fn mul_assign(&mut self, factor: f32) {
+        self.dash_length *= factor;
+        self.gap_length *= factor;
+        self.offset *= factor;
+    }
+}
+
 /// Describes the width and color of paths. The color can either be solid or provided by a callback. For more information, see [`ColorMode`]
 ///
+/// The caps, joins and dashes are controlled by [`Self::style`].
+///
 /// The default stroke is the same as [`Stroke::NONE`].
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -120,6 +313,14 @@ pub struct PathStroke {
     pub width: f32,
     pub color: ColorMode,
     pub kind: StrokeKind,
+
+    /// The caps, joins and dashes of the stroke.
+    ///
+    /// If `None`, the stroke is solid, with butt caps and mitered corners.
+    ///
+    /// Most strokes have no style, so this is optional and in an `Arc`,
+    /// so that the shapes holding a [`PathStroke`] keep [`crate::Shape`] small.
+    pub style: Option<Arc<StrokeStyle>>,
 }
 
 impl Default for PathStroke {
@@ -135,6 +336,7 @@ impl PathStroke {
         width: 0.0,
         color: ColorMode::TRANSPARENT,
         kind: StrokeKind::Middle,
+        style: None,
     };
 
     #[inline]
@@ -143,6 +345,7 @@ impl PathStroke {
             width: width.into(),
             color: ColorMode::Solid(color.into()),
             kind: StrokeKind::Middle,
+            style: None,
         }
     }
 
@@ -158,6 +361,7 @@ impl PathStroke {
             width: width.into(),
             color: ColorMode::UV(Arc::new(callback)),
             kind: StrokeKind::Middle,
+            style: None,
         }
     }
 
@@ -193,11 +397,40 @@ impl PathStroke {
         }
     }
 
+    /// Set the caps, joins and dashes of the stroke.
+    #[inline]
+    pub fn with_style(self, style: StrokeStyle) -> Self {
+        Self {
+            style: Some(Arc::new(style)),
+            ..self
+        }
+    }
+
     /// True if width is zero or color is solid and transparent
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.width <= 0.0 || self.color == ColorMode::TRANSPARENT
     }
+
+    /// How far outside the path the stroke may reach, in units of the stroke width.
+    ///
+    /// See [`StrokeStyle::max_extent_factor`].
+    #[inline]
+    pub(crate) fn max_extent_factor(&self) -> f32 {
+        self.style
+            .as_ref()
+            .map_or(0.5, |style| style.max_extent_factor())
+    }
+
+    /// Scale the width and the dashes of the stroke, e.g. when the shape is transformed.
+    pub(crate) fn scale(&mut self, factor: f32) {
+        self.width *= factor;
+        if let Some(style) = &mut self.style {
+            if !style.dash.is_solid() {
+                Arc::make_mut(style).dash *= factor;
+            }
+        }
+    }
 }
 
 impl<Color> From<(f32, Color)> for PathStroke
@@ -220,6 +453,7 @@ impl From<Stroke> for PathStroke {
                 width: value.width,
                 color: ColorMode::Solid(value.color),
                 kind: StrokeKind::Middle,
+                style: None,
             }
         }
     }
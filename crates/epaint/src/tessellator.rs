@@ -9,9 +9,10 @@ use emath::{GuiRounding as _, NumExt as _, Pos2, Rect, Rot2, Vec2, pos2, remap,
 
 use crate::{
     CircleShape, ClippedPrimitive, ClippedShape, Color32, CornerRadiusF32, CubicBezierShape,
-    EllipseShape, Mesh, PathShape, Primitive, QuadraticBezierShape, RectShape, Shape, Stroke,
-    StrokeKind, TextShape, TextureId, Vertex, WHITE_UV, color::ColorMode, emath,
-    stroke::PathStroke, texture_atlas::PreparedDisc,
+    EllipseShape, LineCap, LineJoin, Mesh, PathShape, PolygonShape, Primitive,
+    QuadraticBezierShape, RectShape, Shape, Stroke, StrokeKind, StrokeStyle, StyledPathShape,
+    TextShape, TextureId, Vertex, WHITE_UV, color::ColorMode, emath, stroke::PathStroke,
+    texture_atlas::PreparedDisc,
 };
 
 // ----------------------------------------------------------------------------
@@ -707,6 +708,8 @@ pub struct TessellationOptions {
     pub debug_ignore_clip_rects: bool,
 
     /// The maximum distance between the original curve and the flattened curve.
+    ///
+    /// This is also used for rounded line caps and joins.
     pub bezier_tolerance: f32,
 
     /// The default value will be 1.0e-5, it will be used during float compare.
@@ -903,6 +906,171 @@ fn fill_closed_path_with_uv(
     }
 }
 
+/// Fill the area inside contours that do not cross each other,
+/// such as the ones returned by [`PolygonShape::filled_contours`].
+///
+/// Unlike [`fill_closed_path`], this works for concave shapes and shapes with holes.
+/// The area is split into horizontal bands, and filled with one trapezoid
+/// per band and pair of edges.
+fn fill_contours(feathering: f32, contours: &[Vec<Pos2>], color: Color32, out: &mut Mesh) {
+    // Like in `fill_closed_path`, the feathering is centered on the edge:
+    // the fill is inset by half the feathering, and fades out to half the feathering outside.
+    let outward_normals: Vec<Vec<Vec2>> = contours
+        .iter()
+        .map(|contour| {
+            let n = contour.len();
+            (0..n)
+                .map(|i| {
+                    let prev = contour[(i + n - 1) % n];
+                    let (pos, next) = (contour[i], contour[(i + 1) % n]);
+                    let normal =
+                        0.5 * ((pos - prev).normalized() + (next - pos).normalized()).rot90();
+                    normal / normal.length_sq().max(0.25)
+                })
+                .collect()
+        })
+        .collect();
+    let inset_contours: Vec<Vec<Pos2>>;
+    let contours = if 0.0 < feathering {
+        inset_contours = contours
+            .iter()
+            .zip(&outward_normals)
+            .map(|(contour, normals)| {
+                contour
+                    .iter()
+                    .zip(normals)
+                    .map(|(&pos, &normal)| pos - 0.5 * feathering * normal)
+                    .collect()
+            })
+            .collect();
+        &inset_contours
+    } else {
+        contours
+    };
+
+    /// A non-horizontal edge, from top to bottom.
+    struct SweepEdge {
+        top: Pos2,
+        bottom: Pos2,
+
+        /// +1 for edges going down, -1 for edges going up.
+        winding: i32,
+    }
+
+    impl SweepEdge {
+        fn x_at(&self, y: f32) -> f32 {
+            let t = (y - self.top.y) / (self.bottom.y - self.top.y);
+            emath::lerp(self.top.x..=self.bottom.x, t)
+        }
+    }
+
+    let mut edges = vec![];
+    let mut ys = vec![];
+    for contour in contours {
+        for (i, &a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            ys.push(a.y);
+            if a.y < b.y {
+                edges.push(SweepEdge {
+                    top: a,
+                    bottom: b,
+                    winding: 1,
+                });
+            } else if b.y < a.y {
+                edges.push(SweepEdge {
+                    top: b,
+                    bottom: a,
+                    winding: -1,
+                });
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.top.y.total_cmp(&b.top.y));
+    ys.sort_by(f32::total_cmp);
+    ys.dedup();
+
+    let mut add_trapezoid = |left: &SweepEdge, right: &SweepEdge, y0: f32, y1: f32| {
+        let idx = out.vertices.len() as u32;
+        out.colored_vertex(pos2(left.x_at(y0), y0), color);
+        out.colored_vertex(pos2(right.x_at(y0), y0), color);
+        out.colored_vertex(pos2(right.x_at(y1), y1), color);
+        out.colored_vertex(pos2(left.x_at(y1), y1), color);
+        out.add_triangle(idx, idx + 1, idx + 2);
+        out.add_triangle(idx, idx + 2, idx + 3);
+    };
+
+    let mut active: Vec<usize> = vec![];
+    let mut next_edge = 0;
+    let mut spans: Vec<(usize, usize)> = vec![];
+
+    // Trapezoids which may continue into the next band, as `(left, right, top_y)`:
+    let mut open: Vec<(usize, usize, f32)> = vec![];
+
+    for band in ys.windows(2) {
+        let (y0, y1) = (band[0], band[1]);
+
+        active.retain(|&i| y0 < edges[i].bottom.y);
+        while next_edge < edges.len() && edges[next_edge].top.y <= y0 {
+            if y0 < edges[next_edge].bottom.y {
+                active.push(next_edge);
+            }
+            next_edge += 1;
+        }
+
+        // No edges cross each other within a band, so we can sort them by any `y` in it:
+        let y_mid = 0.5 * (y0 + y1);
+        active.sort_by(|&a, &b| edges[a].x_at(y_mid).total_cmp(&edges[b].x_at(y_mid)));
+
+        spans.clear();
+        let mut winding = 0;
+        let mut left = 0;
+        for &i in &active {
+            let before = winding;
+            winding += edges[i].winding;
+            if before == 0 {
+                left = i;
+            } else if winding == 0 {
+                spans.push((left, i));
+            }
+        }
+
+        open.retain(|&(left, right, top_y)| {
+            let continues = spans.contains(&(left, right));
+            if !continues {
+                add_trapezoid(&edges[left], &edges[right], top_y, y0);
+            }
+            continues
+        });
+        for &(left, right) in &spans {
+            if !open.iter().any(|&(l, r, _)| (l, r) == (left, right)) {
+                open.push((left, right, y0));
+            }
+        }
+    }
+
+    if let Some(&last_y) = ys.last() {
+        for (left, right, top_y) in open {
+            add_trapezoid(&edges[left], &edges[right], top_y, last_y);
+        }
+    }
+
+    if 0.0 < feathering {
+        // Fade out the edges, outwards from the (inset) filled area:
+        for (contour, normals) in contours.iter().zip(&outward_normals) {
+            let n = contour.len();
+            let idx = out.vertices.len() as u32;
+            for (i, (&pos, &normal)) in contour.iter().zip(normals).enumerate() {
+                out.colored_vertex(pos, color);
+                out.colored_vertex(pos + feathering * normal, Color32::TRANSPARENT);
+
+                let (i0, i1) = (2 * i as u32, 2 * ((i + 1) % n) as u32);
+                out.add_triangle(idx + i0, idx + i0 + 1, idx + i1);
+                out.add_triangle(idx + i0 + 1, idx + i1 + 1, idx + i1);
+            }
+        }
+    }
+}
+
 /// Tessellate the given path as a stroke with thickness.
 fn stroke_path(
     feathering: f32,
@@ -1295,6 +1463,461 @@ fn mul_color(color: Color32, factor: f32) -> Color32 {
     color.gamma_multiply(factor)
 }
 
+/// One cross-section of a stroke tessellated by [`stroke_path_with_caps_and_joins`].
+///
+/// The edges of the stroke are at `pos + left_extent * left_dir` and `pos - right_extent * right_dir`.
+/// The directions are unit normals of the path, or longer for mitered corners.
+#[derive(Clone, Copy, Debug)]
+struct StrokeSection {
+    pos: Pos2,
+    left_dir: Vec2,
+    right_dir: Vec2,
+    left_extent: f32,
+    right_extent: f32,
+
+    /// Thin strokes are painted one feathering wide, but with less opacity.
+    opacity: f32,
+}
+
+impl StrokeSection {
+    #[inline]
+    fn left(&self) -> Pos2 {
+        self.pos + self.left_extent * self.left_dir
+    }
+
+    #[inline]
+    fn right(&self) -> Pos2 {
+        self.pos - self.right_extent * self.right_dir
+    }
+}
+
+/// How many segments to use for a circular arc, so that it deviates at most `tolerance` from the true arc.
+fn arc_segments(radius: f32, angle: f32, tolerance: f32) -> usize {
+    if radius <= tolerance {
+        return 2;
+    }
+    let max_step = 2.0 * (1.0 - tolerance / radius).acos();
+    ((angle / max_step).ceil() as usize).clamp(2, 64)
+}
+
+/// Tessellate a stroke with the caps, joins and dashes of `style`,
+/// optionally with a different width at each point.
+///
+/// Points without a width in `widths` use [`PathStroke::width`].
+#[expect(clippy::too_many_arguments)]
+fn stroke_path_with_caps_and_joins(
+    feathering: f32,
+    tolerance: f32,
+    points: &[Pos2],
+    widths: &[f32],
+    path_type: PathType,
+    stroke: &PathStroke,
+    style: &StrokeStyle,
+    out: &mut Mesh,
+) {
+    let mut path: Vec<(Pos2, f32)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, &pos)| (pos, widths.get(i).copied().unwrap_or(stroke.width)))
+        .collect();
+    let path_type = dedup_stroke_points(&mut path, path_type);
+    if path.len() < 2 {
+        return;
+    }
+
+    let max_width = path.iter().map(|&(_, width)| width).fold(0.0, f32::max);
+    let uv_bbox = if matches!(stroke.color, ColorMode::UV(_)) {
+        Rect::from_points(&path.iter().map(|&(pos, _)| pos).collect::<Vec<Pos2>>())
+            .expand(max_width * style.max_extent_factor() + feathering)
+    } else {
+        Rect::NAN
+    };
+    let get_color = |pos: Pos2, opacity: f32| {
+        let color = match &stroke.color {
+            ColorMode::Solid(color) => *color,
+            ColorMode::UV(fun) => fun(uv_bbox, pos),
+        };
+        if opacity < 1.0 {
+            mul_color(color, opacity)
+        } else {
+            color
+        }
+    };
+
+    let dashes = if style.dash.is_solid() {
+        None
+    } else {
+        split_into_dashes(&path, path_type, &style.dash)
+    };
+
+    if let Some(dashes) = dashes {
+        for mut dash in dashes {
+            dedup_stroke_points(&mut dash, PathType::Open);
+            if 2 <= dash.len() {
+                stroke_polyline(
+                    feathering,
+                    tolerance,
+                    &dash,
+                    PathType::Open,
+                    stroke,
+                    style,
+                    &get_color,
+                    out,
+                );
+            }
+        }
+    } else {
+        stroke_polyline(
+            feathering, tolerance, &path, path_type, stroke, style, &get_color, out,
+        );
+    }
+}
+
+/// Remove repeated points, since they have no direction.
+///
+/// Returns the path type to use, since a closed path needs at least three points.
+fn dedup_stroke_points(path: &mut Vec<(Pos2, f32)>, path_type: PathType) -> PathType {
+    path.dedup_by(|(b, _), (a, _)| (*b - *a).length_sq() <= 1e-6);
+    if path_type == PathType::Closed {
+        if 1 < path.len() && (path[0].0 - path[path.len() - 1].0).length_sq() <= 1e-6 {
+            path.pop();
+        }
+        if path.len() < 3 {
+            return PathType::Open;
+        }
+    }
+    path_type
+}
+
+/// The most dashes we split a path into.
+///
+/// Beyond this the dashes are too small to see, and the path is painted solid instead.
+/// This also keeps the dashes long enough to make progress along the path in `f32`.
+const MAX_DASHES: f32 = 10_000.0;
+
+/// Split a path into the dashes of a [`crate::DashPattern`], interpolating the widths.
+///
+/// A dash that goes around the start of a closed path is kept in one piece.
+///
+/// Returns `None` if the path should be painted solid,
+/// because it would be split into more than [`MAX_DASHES`] dashes.
+fn split_into_dashes(
+    path: &[(Pos2, f32)],
+    path_type: PathType,
+    dash: &crate::DashPattern,
+) -> Option<Vec<Vec<(Pos2, f32)>>> {
+    let mut dashes: Vec<Vec<(Pos2, f32)>> = vec![];
+    if dash.dash_length.is_nan() || dash.dash_length <= 0.0 {
+        return Some(dashes);
+    }
+
+    let n = path.len();
+    let num_segments = if path_type == PathType::Closed {
+        n
+    } else {
+        n - 1
+    };
+    let segment_length = |i: usize| (path[(i + 1) % n].0 - path[i].0).length();
+
+    let period = dash.dash_length + dash.gap_length;
+    let total_length: f32 = (0..num_segments).map(segment_length).sum();
+    if MAX_DASHES * period < total_length {
+        return None;
+    }
+
+    let mut phase = dash.offset.rem_euclid(period);
+    let starts_in_dash = phase < dash.dash_length;
+    let mut current = vec![];
+    if starts_in_dash {
+        current.push(path[0]);
+    }
+
+    for i in 0..num_segments {
+        let (a, width_a) = path[i];
+        let (b, width_b) = path[(i + 1) % n];
+        let length = segment_length(i);
+        let mut t = 0.0;
+        while t < length {
+            let in_dash = phase < dash.dash_length;
+            let until_toggle = if in_dash {
+                dash.dash_length - phase
+            } else {
+                period - phase
+            };
+            let toggles = until_toggle <= length - t;
+            if toggles {
+                t += until_toggle;
+            } else {
+                phase += length - t;
+                t = length;
+            }
+            let f = t / length;
+            let point = (a.lerp(b, f), emath::lerp(width_a..=width_b, f));
+
+            if in_dash {
+                current.push(point);
+            }
+            if toggles {
+                if in_dash {
+                    dashes.push(std::mem::take(&mut current));
+                    phase = dash.dash_length;
+                } else {
+                    current.push(point);
+                    phase = 0.0;
+                }
+            }
+        }
+    }
+
+    if path_type == PathType::Closed && starts_in_dash && !current.is_empty() && !dashes.is_empty()
+    {
+        // The last dash continues into the first one:
+        current.extend(dashes[0].iter().skip(1));
+        dashes[0] = current;
+    } else if 1 < current.len() {
+        dashes.push(current);
+    }
+
+    Some(dashes)
+}
+
+/// Stroke a path without repeated points, using the caps and joins of `style`.
+#[expect(clippy::too_many_arguments)]
+fn stroke_polyline(
+    feathering: f32,
+    tolerance: f32,
+    path: &[(Pos2, f32)],
+    path_type: PathType,
+    stroke: &PathStroke,
+    style: &StrokeStyle,
+    get_color: &impl Fn(Pos2, f32) -> Color32,
+    out: &mut Mesh,
+) {
+    let n = path.len();
+    let num_segments = if path_type == PathType::Closed {
+        n
+    } else {
+        n - 1
+    };
+    let segments: Vec<(Vec2, f32)> = (0..num_segments)
+        .map(|i| {
+            let delta = path[(i + 1) % n].0 - path[i].0;
+            (delta.normalized(), delta.length())
+        })
+        .collect();
+
+    let mut sections: Vec<StrokeSection> = Vec::with_capacity(n + 2);
+
+    for (i, &(pos, width)) in path.iter().enumerate() {
+        // If the stroke is thinner than a pixel we paint it a pixel wide, but more transparent.
+        let opacity = if width < feathering {
+            (width / feathering).max(0.0)
+        } else {
+            1.0
+        };
+        let half_width = 0.5 * width.max(feathering);
+        let shift = match stroke.kind {
+            StrokeKind::Inside => -half_width,
+            StrokeKind::Middle => 0.0,
+            StrokeKind::Outside => half_width,
+        };
+        let section = |left_dir: Vec2, right_dir: Vec2| StrokeSection {
+            pos,
+            left_dir,
+            right_dir,
+            left_extent: half_width + shift,
+            right_extent: half_width - shift,
+            opacity,
+        };
+
+        let is_first = i == 0;
+        let is_last = i + 1 == n;
+        if path_type == PathType::Open && (is_first || is_last) {
+            let normal = segments[if is_first { 0 } else { n - 2 }].0.rot90();
+            sections.push(section(normal, normal));
+            continue;
+        }
+
+        let (dir_a, len_a) = segments[(i + num_segments - 1) % num_segments];
+        let (dir_b, len_b) = segments[i];
+        let (normal_a, normal_b) = (dir_a.rot90(), dir_b.rot90());
+        let cos = normal_a.dot(normal_b).clamp(-1.0, 1.0);
+
+        if 0.9999 < cos {
+            // Straight enough to not need a join.
+            let normal = (normal_a + normal_b).normalized();
+            sections.push(section(normal, normal));
+            continue;
+        }
+
+        // The corner on the outside of the turn gets the join,
+        // while the edges on the inside meet at a single point.
+        let left_is_outer = dir_b.dot(normal_a) < 0.0;
+        let (outer_extent, inner_extent) = if left_is_outer {
+            (half_width + shift, half_width - shift)
+        } else {
+            (half_width - shift, half_width + shift)
+        };
+
+        let miter = if -0.9999 < cos {
+            (normal_a + normal_b) / (1.0 + cos)
+        } else {
+            Vec2::ZERO // The path turns back on itself
+        };
+
+        // Don't let the inner corner reach beyond the neighboring segments:
+        let mut inner = miter;
+        let max_inner_length = (inner_extent * inner_extent + len_a.min(len_b).powi(2)).sqrt();
+        if max_inner_length < miter.length() * inner_extent {
+            inner = miter * (max_inner_length / (miter.length() * inner_extent));
+        }
+
+        let mut push_outer = |outer: Vec2| {
+            sections.push(if left_is_outer {
+                section(outer, inner)
+            } else {
+                section(inner, outer)
+            });
+        };
+
+        match style.join {
+            LineJoin::Miter if miter != Vec2::ZERO && miter.length() <= style.miter_limit => {
+                push_outer(miter);
+            }
+            LineJoin::Miter | LineJoin::Bevel => {
+                push_outer(normal_a);
+                push_outer(normal_b);
+            }
+            LineJoin::Round => {
+                // Rotate around the outside of the corner, which is forwards from `normal_a`:
+                let forward = if left_is_outer { dir_a } else { -dir_a };
+                let sign = normal_a.x * forward.y - normal_a.y * forward.x;
+                let angle = cos.acos();
+                let steps = arc_segments(outer_extent, angle, tolerance);
+                for step in 0..=steps {
+                    let rot = Rot2::from_angle(sign.signum() * angle * step as f32 / steps as f32);
+                    push_outer(rot * normal_a);
+                }
+            }
+        }
+    }
+
+    if path_type == PathType::Open && style.cap == LineCap::Square {
+        let first = &mut sections[0];
+        first.pos -= 0.5 * (first.left_extent + first.right_extent) * segments[0].0;
+        let last = sections.last_mut().expect("We have at least two sections");
+        last.pos += 0.5 * (last.left_extent + last.right_extent) * segments[n - 2].0;
+    }
+
+    // Paint the sections:
+    let f = 0.5 * feathering;
+    let idx = out.vertices.len() as u32;
+    let num_sections = sections.len() as u32;
+    out.reserve_vertices(4 * sections.len());
+    out.reserve_triangles(6 * sections.len());
+    for (i, section) in sections.iter().enumerate() {
+        let (left, right) = (section.left(), section.right());
+        let (left_inner, right_inner) =
+            (left - f * section.left_dir, right + f * section.right_dir);
+        out.colored_vertex(left + f * section.left_dir, Color32::TRANSPARENT);
+        out.colored_vertex(left_inner, get_color(left_inner, section.opacity));
+        out.colored_vertex(right_inner, get_color(right_inner, section.opacity));
+        out.colored_vertex(right - f * section.right_dir, Color32::TRANSPARENT);
+
+        let i1 = i as u32;
+        let connect_with_previous = 0 < i1 || path_type == PathType::Closed;
+        if connect_with_previous {
+            let i0 = (i1 + num_sections - 1) % num_sections;
+            out.add_triangle(idx + 4 * i0 + 0, idx + 4 * i0 + 1, idx + 4 * i1 + 0);
+            out.add_triangle(idx + 4 * i0 + 1, idx + 4 * i1 + 0, idx + 4 * i1 + 1);
+
+            out.add_triangle(idx + 4 * i0 + 1, idx + 4 * i0 + 2, idx + 4 * i1 + 1);
+            out.add_triangle(idx + 4 * i0 + 2, idx + 4 * i1 + 1, idx + 4 * i1 + 2);
+
+            out.add_triangle(idx + 4 * i0 + 2, idx + 4 * i0 + 3, idx + 4 * i1 + 2);
+            out.add_triangle(idx + 4 * i0 + 3, idx + 4 * i1 + 2, idx + 4 * i1 + 3);
+        }
+    }
+
+    if path_type == PathType::Open {
+        let first = (sections[0], idx, -segments[0].0);
+        let last = (
+            sections[sections.len() - 1],
+            idx + 4 * (num_sections - 1),
+            segments[n - 2].0,
+        );
+        for (section, section_idx, outwards) in [first, last] {
+            match style.cap {
+                LineCap::Butt | LineCap::Square => {
+                    add_butt_cap_feathering(feathering, section_idx, outwards, out);
+                }
+                LineCap::Round => {
+                    add_round_cap(feathering, tolerance, &section, outwards, &get_color, out);
+                }
+            }
+        }
+    }
+}
+
+/// Fade out the end of a stroke, which ends with the vertices at `idx..idx + 4`.
+fn add_butt_cap_feathering(feathering: f32, idx: u32, outwards: Vec2, out: &mut Mesh) {
+    if feathering <= 0.0 {
+        return;
+    }
+
+    let extrude = feathering * outwards;
+    let new_idx = out.vertices.len() as u32;
+    for i in 0..4 {
+        let pos = out.vertices[(idx + i) as usize].pos + extrude;
+        out.colored_vertex(pos, Color32::TRANSPARENT);
+    }
+
+    for i in 0..3 {
+        out.add_triangle(idx + i, idx + i + 1, new_idx + i);
+        out.add_triangle(idx + i + 1, new_idx + i + 1, new_idx + i);
+    }
+}
+
+/// Add a half-circle to the end of a stroke.
+fn add_round_cap(
+    feathering: f32,
+    tolerance: f32,
+    section: &StrokeSection,
+    outwards: Vec2,
+    get_color: &impl Fn(Pos2, f32) -> Color32,
+    out: &mut Mesh,
+) {
+    let f = 0.5 * feathering;
+    let center = section.left().lerp(section.right(), 0.5);
+    let radius = 0.5 * (section.left_extent + section.right_extent);
+    let inner_radius = (radius - f).max(0.0);
+    let normal = section.left_dir;
+
+    // Rotate from the left edge, around the end, to the right edge:
+    let sign = (normal.x * outwards.y - normal.y * outwards.x).signum();
+    let steps = arc_segments(radius, std::f32::consts::PI, tolerance);
+
+    let idx = out.vertices.len() as u32;
+    out.reserve_vertices(2 * steps + 3);
+    out.reserve_triangles(3 * steps);
+    out.colored_vertex(center, get_color(center, section.opacity));
+    for step in 0..=steps {
+        let rot = Rot2::from_angle(sign * std::f32::consts::PI * step as f32 / steps as f32);
+        let dir = rot * normal;
+        let inner = center + inner_radius * dir;
+        out.colored_vertex(inner, get_color(inner, section.opacity));
+        out.colored_vertex(center + (radius + f) * dir, Color32::TRANSPARENT);
+
+        if 0 < step {
+            let i0 = idx + 1 + 2 * (step as u32 - 1);
+            let i1 = i0 + 2;
+            out.add_triangle(idx, i0, i1);
+            out.add_triangle(i0, i0 + 1, i1 + 1);
+            out.add_triangle(i0, i1 + 1, i1);
+        }
+    }
+}
+
 // ----------------------------------------------------------------------------
 
 /// Converts [`Shape`]s into triangles ([`Mesh`]).
@@ -1362,7 +1985,12 @@ impl Tessellator {
         clipped_shape: ClippedShape,
         out_primitives: &mut Vec<ClippedPrimitive>,
     ) {
-        let ClippedShape { clip_rect, shape } = clipped_shape;
+        let ClippedShape {
+            clip_rect,
+            shape,
+            transform,
+            global_clip_rect,
+        } = clipped_shape;
 
         if !clip_rect.is_positive() {
             return; // skip empty clip rectangles
@@ -1370,11 +1998,30 @@ impl Tessellator {
 
         if let Shape::Vec(shapes) = shape {
             for shape in shapes {
-                self.tessellate_clipped_shape(ClippedShape { clip_rect, shape }, out_primitives);
+                self.tessellate_clipped_shape(
+                    ClippedShape {
+                        clip_rect,
+                        shape,
+                        transform,
+                        global_clip_rect,
+                    },
+                    out_primitives,
+                );
             }
             return;
         }
 
+        if let Some(transform) = transform {
+            self.tessellate_transformed_shape(
+                clip_rect,
+                global_clip_rect,
+                shape,
+                transform,
+                out_primitives,
+            );
+            return;
+        }
+
         if let Shape::Callback(callback) = shape {
             out_primitives.push(ClippedPrimitive {
                 clip_rect,
@@ -1413,6 +2060,73 @@ impl Tessellator {
         }
     }
 
+    /// Tessellate a shape given in a local coordinate system,
+    /// clip it to the local `clip_rect`, transform it into global coordinates,
+    /// and then clip it to the `global_clip_rect`.
+    fn tessellate_transformed_shape(
+        &mut self,
+        clip_rect: Rect,
+        global_clip_rect: Rect,
+        shape: Shape,
+        transform: emath::Affine2,
+        out_primitives: &mut Vec<ClippedPrimitive>,
+    ) {
+        // The backends can only clip to axis-aligned rectangles,
+        // so we clip the geometry ourselves and only use the bounding box as a scissor rectangle.
+        let scissor_rect = (transform * clip_rect).intersect(global_clip_rect);
+        if !scissor_rect.is_positive() {
+            return;
+        }
+
+        if let Shape::Callback(mut callback) = shape {
+            // A callback paints whatever it wants, so we can only give it the bounding box.
+            callback.rect = transform * callback.rect;
+            out_primitives.push(ClippedPrimitive {
+                clip_rect: scissor_rect,
+                primitive: Primitive::Callback(callback),
+            });
+            return;
+        }
+
+        let mut mesh = Mesh::with_texture(shape.texture_id());
+
+        // Feathering is specified in physical pixels, so it must shrink when the transform magnifies:
+        let scale_factor = transform.scale_factor();
+        let global_feathering = self.feathering;
+        if scale_factor > 0.0 {
+            self.feathering /= scale_factor;
+        }
+        self.clip_rect = clip_rect;
+        self.tessellate_shape(shape, &mut mesh);
+        self.feathering = global_feathering;
+
+        if mesh.is_empty() {
+            return;
+        }
+
+        mesh.clip_to_rect(clip_rect);
+        mesh.transform_affine(transform);
+        mesh.clip_to_rect(global_clip_rect);
+        if mesh.is_empty() {
+            return;
+        }
+
+        match out_primitives.last_mut() {
+            Some(ClippedPrimitive {
+                clip_rect,
+                primitive: Primitive::Mesh(out_mesh),
+            }) if *clip_rect == scissor_rect && out_mesh.texture_id == mesh.texture_id => {
+                out_mesh.append(mesh);
+            }
+            _ => {
+                out_primitives.push(ClippedPrimitive {
+                    clip_rect: scissor_rect,
+                    primitive: Primitive::Mesh(mesh),
+                });
+            }
+        }
+    }
+
     /// Tessellate a single [`Shape`] into a [`Mesh`].
     ///
     /// This call can panic the given shape is of [`Shape::Vec`] or [`Shape::Callback`].
@@ -1456,6 +2170,12 @@ impl Tessellator {
             Shape::Path(path_shape) => {
                 self.tessellate_path(&path_shape, out);
             }
+            Shape::StyledPath(path_shape) => {
+                self.tessellate_styled_path(&path_shape, out);
+            }
+            Shape::Polygon(polygon_shape) => {
+                self.tessellate_polygon(&polygon_shape, out);
+            }
             Shape::Rect(rect_shape) => {
                 self.tessellate_rect(&rect_shape, out);
             }
@@ -1531,8 +2251,7 @@ impl Tessellator {
         let path_stroke = PathStroke::from(stroke).outside();
         self.scratchpad_path.clear();
         self.scratchpad_path.add_circle(center, radius);
-        self.scratchpad_path
-            .fill_and_stroke(self.feathering, fill, &path_stroke, out);
+        self.fill_and_stroke_scratchpad_path(PathType::Closed, fill, &path_stroke, out);
     }
 
     /// Tessellate a single [`EllipseShape`] into a [`Mesh`].
@@ -1598,8 +2317,7 @@ impl Tessellator {
         let path_stroke = PathStroke::from(stroke).outside();
         self.scratchpad_path.clear();
         self.scratchpad_path.add_line_loop(&points);
-        self.scratchpad_path
-            .fill_and_stroke(self.feathering, fill, &path_stroke, out);
+        self.fill_and_stroke_scratchpad_path(PathType::Closed, fill, &path_stroke, out);
     }
 
     /// Tessellate a single [`Mesh`] into a [`Mesh`].
@@ -1735,8 +2453,7 @@ impl Tessellator {
         if *closed {
             self.scratchpad_path.add_line_loop(points);
 
-            self.scratchpad_path
-                .fill_and_stroke(self.feathering, *fill, stroke, out);
+            self.fill_and_stroke_scratchpad_path(PathType::Closed, *fill, stroke, out);
         } else {
             debug_assert_eq!(
                 *fill,
@@ -1746,8 +2463,102 @@ impl Tessellator {
 
             self.scratchpad_path.add_open_points(points);
 
-            self.scratchpad_path
-                .stroke(self.feathering, PathType::Open, stroke, out);
+            self.fill_and_stroke_scratchpad_path(PathType::Open, *fill, stroke, out);
+        }
+    }
+
+    /// Tessellate a single [`StyledPathShape`] into a [`Mesh`].
+    ///
+    /// * `path_shape`: the path to tessellate.
+    /// * `out`: triangles are appended to this.
+    pub fn tessellate_styled_path(&mut self, path_shape: &StyledPathShape, out: &mut Mesh) {
+        if path_shape.points.len() < 2 {
+            return;
+        }
+
+        if self.options.coarse_tessellation_culling
+            && !path_shape.visual_bounding_rect().intersects(self.clip_rect)
+        {
+            return;
+        }
+
+        profiling::function_scope!();
+
+        let StyledPathShape {
+            points,
+            widths,
+            closed,
+            fill,
+            stroke,
+        } = path_shape;
+
+        if *closed && *fill != Color32::TRANSPARENT {
+            self.scratchpad_path.clear();
+            self.scratchpad_path.add_line_loop(points);
+            self.scratchpad_path.fill(self.feathering, *fill, out);
+        }
+
+        if path_shape.max_stroke_width() <= 0.0 || stroke.color == ColorMode::TRANSPARENT {
+            return;
+        }
+
+        let style = stroke.style.as_deref().copied().unwrap_or_default();
+
+        let path_type = if *closed {
+            PathType::Closed
+        } else {
+            PathType::Open
+        };
+        stroke_path_with_caps_and_joins(
+            self.feathering,
+            self.options.bezier_tolerance,
+            points,
+            widths,
+            path_type,
+            stroke,
+            &style,
+            out,
+        );
+    }
+
+    /// Tessellate a single [`PolygonShape`] into a [`Mesh`].
+    ///
+    /// * `polygon_shape`: the polygon to tessellate.
+    /// * `out`: triangles are appended to this.
+    pub fn tessellate_polygon(&mut self, polygon_shape: &PolygonShape, out: &mut Mesh) {
+        if self.options.coarse_tessellation_culling
+            && !polygon_shape
+                .visual_bounding_rect()
+                .intersects(self.clip_rect)
+        {
+            return;
+        }
+
+        profiling::function_scope!();
+
+        let PolygonShape {
+            contours,
+            fill_rule: _,
+            fill,
+            stroke,
+        } = polygon_shape;
+
+        if *fill != Color32::TRANSPARENT {
+            let filled_contours = polygon_shape.filled_contours();
+            fill_contours(self.feathering, &filled_contours, *fill, out);
+        }
+
+        if !stroke.is_empty() {
+            for contour in contours.iter().filter(|contour| 2 <= contour.len()) {
+                self.scratchpad_path.clear();
+                self.scratchpad_path.add_line_loop(contour);
+                self.fill_and_stroke_scratchpad_path(
+                    PathType::Closed,
+                    Color32::TRANSPARENT,
+                    stroke,
+                    out,
+                );
+            }
         }
     }
 
@@ -1967,11 +2778,16 @@ impl Tessellator {
             }
 
             if !stroke.is_empty() {
-                path.stroke_closed(self.feathering, &path_stroke, out);
+                self.fill_and_stroke_scratchpad_path(
+                    PathType::Closed,
+                    Color32::TRANSPARENT,
+                    &path_stroke,
+                    out,
+                );
             }
         } else {
             // Stroke and maybe fill
-            path.fill_and_stroke(self.feathering, fill, &path_stroke, out);
+            self.fill_and_stroke_scratchpad_path(PathType::Closed, fill, &path_stroke, out);
         }
 
         self.feathering = old_feathering; // restore
@@ -2175,8 +2991,7 @@ impl Tessellator {
         if closed {
             self.scratchpad_path.add_line_loop(points);
 
-            self.scratchpad_path
-                .fill_and_stroke(self.feathering, fill, stroke, out);
+            self.fill_and_stroke_scratchpad_path(PathType::Closed, fill, stroke, out);
         } else {
             debug_assert_eq!(
                 fill,
@@ -2186,8 +3001,52 @@ impl Tessellator {
 
             self.scratchpad_path.add_open_points(points);
 
-            self.scratchpad_path
-                .stroke(self.feathering, PathType::Open, stroke, out);
+            self.fill_and_stroke_scratchpad_path(PathType::Open, fill, stroke, out);
+        }
+    }
+
+    /// Fill (if closed) and stroke [`Self::scratchpad_path`],
+    /// with the caps, joins and dashes of the [`PathStroke::style`], if any.
+    fn fill_and_stroke_scratchpad_path(
+        &mut self,
+        path_type: PathType,
+        fill: Color32,
+        stroke: &PathStroke,
+        out: &mut Mesh,
+    ) {
+        let Some(style) = &stroke.style else {
+            match path_type {
+                PathType::Open => self
+                    .scratchpad_path
+                    .stroke_open(self.feathering, stroke, out),
+                PathType::Closed => {
+                    self.scratchpad_path
+                        .fill_and_stroke(self.feathering, fill, stroke, out);
+                }
+            }
+            return;
+        };
+
+        // Filling may reverse the path, so take the points first:
+        self.scratchpad_points.clear();
+        self.scratchpad_points
+            .extend(self.scratchpad_path.0.iter().map(|p| p.pos));
+
+        if path_type == PathType::Closed && fill != Color32::TRANSPARENT {
+            self.scratchpad_path.fill(self.feathering, fill, out);
+        }
+
+        if !stroke.is_empty() {
+            stroke_path_with_caps_and_joins(
+                self.feathering,
+                self.options.bezier_tolerance,
+                &self.scratchpad_points,
+                &[],
+                path_type,
+                stroke,
+                style,
+                out,
+            );
         }
     }
 }
@@ -2270,6 +3129,12 @@ impl Tessellator {
 
                 Shape::Path(path_shape) => 32 < path_shape.points.len(),
 
+                Shape::StyledPath(path_shape) => 32 < path_shape.points.len(),
+
+                Shape::Polygon(polygon_shape) => {
+                    32 < polygon_shape.contours.iter().map(Vec::len).sum::<usize>()
+                }
+
                 Shape::QuadraticBezier(_) | Shape::CubicBezier(_) | Shape::Ellipse(_) => true,
 
                 Shape::Noop
@@ -2353,10 +3218,7 @@ fn test_tessellator() {
     shapes.push(Shape::mesh(mesh));
 
     let shape = Shape::Vec(shapes);
-    let clipped_shapes = vec![ClippedShape {
-        clip_rect: rect,
-        shape,
-    }];
+    let clipped_shapes = vec![ClippedShape::new(rect, shape)];
 
     let font_tex_size = [1024, 1024]; // unused
     let prepared_discs = vec![]; // unused
@@ -2367,6 +3229,36 @@ fn test_tessellator() {
     assert_eq!(primitives.len(), 2);
 }
 
+#[test]
+fn rotated_shape_is_clipped_to_global_rect() {
+    use crate::*;
+
+    let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 100.0));
+    let global_clip_rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(50.0, 50.0));
+
+    let mut clipped_shape = ClippedShape::new(rect, Shape::rect_filled(rect, 0.0, Color32::WHITE));
+    clipped_shape.transform_to_global(emath::Affine2::from_angle(0.3).around(rect.center()));
+    clipped_shape.clip_to_global_rect(global_clip_rect);
+    assert_eq!(clipped_shape.global_clip_rect(), global_clip_rect);
+
+    let primitives = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![])
+        .tessellate_shapes(vec![clipped_shape]);
+
+    assert_eq!(primitives.len(), 1);
+    assert_eq!(primitives[0].clip_rect, global_clip_rect);
+    let Primitive::Mesh(mesh) = &primitives[0].primitive else {
+        panic!("Expected a mesh");
+    };
+    assert!(!mesh.is_empty());
+    for &index in &mesh.indices {
+        let pos = mesh.vertices[index as usize].pos;
+        assert!(
+            global_clip_rect.expand(0.01).contains(pos),
+            "The mesh is clipped to the global clip rect: {pos:?}"
+        );
+    }
+}
+
 #[test]
 fn path_bounding_box() {
     use crate::*;
@@ -2411,3 +3303,431 @@ fn path_bounding_box() {
         );
     }
 }
+
+#[test]
+fn variable_width_stroke() {
+    use crate::*;
+
+    let mut tessellator = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![]);
+
+    let style = StrokeStyle::default()
+        .with_cap(LineCap::Round)
+        .with_join(LineJoin::Round);
+    let path = StyledPathShape::variable_width_line(
+        vec![pos2(0.0, 0.0), pos2(50.0, 0.0), pos2(50.0, 50.0)],
+        vec![2.0, 20.0, 2.0],
+        PathStroke::new(0.0, Color32::WHITE).with_style(style),
+    );
+
+    let mut mesh = Mesh::default();
+    tessellator.tessellate_styled_path(&path, &mut mesh);
+    assert!(mesh.is_valid());
+
+    let bounds = mesh.calc_bounds();
+    let expected = Rect::from_min_max(pos2(-1.5, -10.5), pos2(60.5, 51.5));
+    assert!(
+        (bounds.min - expected.min).length() < 0.1 && (bounds.max - expected.max).length() < 0.1,
+        "Expected {expected:?}, got {bounds:?}"
+    );
+    assert!(
+        bounds
+            .expand(0.1)
+            .contains_rect(bounds.intersect(path.visual_bounding_rect()))
+    );
+}
+
+#[test]
+fn miter_limit() {
+    use crate::*;
+
+    let points = vec![pos2(0.0, 0.0), pos2(100.0, 0.0), pos2(100.0, 100.0)];
+
+    // How far the stroke reaches outside the corner:
+    let tip_distance = |miter_limit: f32, join: LineJoin| {
+        let mut tessellator = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![]);
+        let style = StrokeStyle::default()
+            .with_join(join)
+            .with_miter_limit(miter_limit);
+        let path = PathShape::line(
+            points.clone(),
+            PathStroke::new(10.0, Color32::WHITE).with_style(style),
+        );
+        let mut mesh = Mesh::default();
+        tessellator.tessellate_path(&path, &mut mesh);
+        assert!(mesh.is_valid());
+        // The feathering may reach a little outside the visual bounds:
+        assert!(
+            path.visual_bounding_rect()
+                .expand(1.0)
+                .contains_rect(mesh.calc_bounds())
+        );
+        mesh.vertices
+            .iter()
+            .filter(|v| v.color != Color32::TRANSPARENT)
+            .map(|v| (v.pos - pos2(100.0, 0.0)).dot(vec2(1.0, -1.0).normalized()))
+            .fold(0.0, f32::max)
+    };
+
+    // A right angle has a miter ratio of √2:
+    let sharp = tip_distance(1.5, LineJoin::Miter);
+    assert!(
+        (sharp - 5.0 * std::f32::consts::SQRT_2).abs() < 1.0,
+        "{sharp}"
+    );
+
+    let beveled = tip_distance(1.3, LineJoin::Miter);
+    assert!(
+        beveled < sharp - 1.0,
+        "{beveled} should be less than {sharp}"
+    );
+
+    let round = tip_distance(1.5, LineJoin::Round);
+    assert!((round - 5.0).abs() < 1.0, "{round}");
+}
+
+#[test]
+fn dashed_stroke() {
+    use crate::*;
+
+    let mut tessellator = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![]);
+
+    // The covered parts of the x axis:
+    let mut dash_ranges = |style: StrokeStyle| {
+        let path = PathShape::line(
+            vec![pos2(0.0, 0.0), pos2(100.0, 0.0)],
+            PathStroke::new(2.0, Color32::WHITE).with_style(style),
+        );
+        let mut mesh = Mesh::default();
+        tessellator.tessellate_path(&path, &mut mesh);
+        assert!(mesh.is_valid());
+        let mut xs: Vec<f32> = mesh
+            .vertices
+            .iter()
+            .filter(|v| v.color != Color32::TRANSPARENT)
+            .map(|v| (100.0 * v.pos.x).round() / 100.0)
+            .collect();
+        xs.sort_by(f32::total_cmp);
+        xs.dedup();
+        xs
+    };
+
+    let style = StrokeStyle::default().dashed(10.0, 15.0);
+    assert_eq!(
+        dash_ranges(style),
+        [0.0, 10.0, 25.0, 35.0, 50.0, 60.0, 75.0, 85.0]
+    );
+
+    let offset = style.with_dash(style.dash.with_offset(5.0));
+    assert_eq!(
+        dash_ranges(offset),
+        [0.0, 5.0, 20.0, 30.0, 45.0, 55.0, 70.0, 80.0, 95.0, 100.0]
+    );
+
+    let square = style.with_cap(LineCap::Square);
+    assert_eq!(
+        dash_ranges(square),
+        [-1.0, 11.0, 24.0, 36.0, 49.0, 61.0, 74.0, 86.0]
+    );
+
+    // An infinite gap leaves a single dash:
+    let single = StrokeStyle::default().dashed(10.0, f32::INFINITY);
+    assert_eq!(dash_ranges(single), [0.0, 10.0]);
+
+    // Too many dashes to see are painted solid, instead of taking forever:
+    let tiny = StrokeStyle::default().dashed(1e-6, 1e-6);
+    assert_eq!(dash_ranges(tiny), [0.0, 100.0]);
+    let long_line = [(pos2(0.0, 0.0), 1.0), (pos2(1e9, 0.0), 1.0)];
+    let tiny = DashPattern::new(1e-3, 1e-3);
+    assert_eq!(split_into_dashes(&long_line, PathType::Open, &tiny), None);
+
+    // A dash going around the start of a closed path is not split in two:
+    let square = [
+        (pos2(0.0, 0.0), 1.0),
+        (pos2(10.0, 0.0), 1.0),
+        (pos2(10.0, 10.0), 1.0),
+        (pos2(0.0, 10.0), 1.0),
+    ];
+    let dashes = split_into_dashes(
+        &square,
+        PathType::Closed,
+        &DashPattern::new(10.0, 10.0).with_offset(5.0),
+    );
+    assert_eq!(
+        dashes.unwrap(),
+        [
+            vec![
+                (pos2(0.0, 5.0), 1.0),
+                (pos2(0.0, 0.0), 1.0),
+                (pos2(5.0, 0.0), 1.0)
+            ],
+            vec![
+                (pos2(10.0, 5.0), 1.0),
+                (pos2(10.0, 10.0), 1.0),
+                (pos2(5.0, 10.0), 1.0)
+            ],
+        ]
+    );
+}
+
+#[test]
+fn dashed_outlines() {
+    use crate::*;
+
+    let mut tessellator = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![]);
+
+    // The area covered by the stroke, ignoring the feathering:
+    let mut covered_area = |shape: Shape| {
+        let mut mesh = Mesh::default();
+        tessellator.tessellate_shape(shape, &mut mesh);
+        assert!(mesh.is_valid());
+        let area: f32 = mesh
+            .indices
+            .chunks(3)
+            .map(|triangle| [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]))
+            .filter(|vertices| vertices.iter().all(|v| v.color != Color32::TRANSPARENT))
+            .map(|[a, b, c]| {
+                let (ab, ac) = (b.pos - a.pos, c.pos - a.pos);
+                0.5 * (ab.x * ac.y - ab.y * ac.x).abs()
+            })
+            .sum();
+        (mesh, area)
+    };
+
+    let stroke = Stroke::new(4.0, Color32::WHITE);
+    let style = StrokeStyle::default().dashed(6.0, 6.0);
+    let path_stroke = PathStroke::from(stroke);
+    let dashed_path_stroke = path_stroke.clone().with_style(style);
+    let center = pos2(100.0, 100.0);
+    let rect = Rect::from_center_size(center, vec2(80.0, 60.0));
+    let corners = vec![
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ];
+    let quadratic = [pos2(0.0, 0.0), pos2(100.0, 200.0), pos2(200.0, 0.0)];
+    let cubic = [
+        pos2(0.0, 0.0),
+        pos2(50.0, 200.0),
+        pos2(150.0, -100.0),
+        pos2(200.0, 100.0),
+    ];
+
+    let shapes: [(Shape, Shape); 7] = [
+        (
+            CircleShape::stroke(center, 40.0, stroke).into(),
+            PathShape::circle(
+                center,
+                40.0,
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone().outside(),
+            )
+            .into(),
+        ),
+        (
+            EllipseShape::stroke(center, vec2(60.0, 30.0), stroke).into(),
+            PathShape::ellipse(
+                center,
+                vec2(60.0, 30.0),
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone().outside(),
+            )
+            .into(),
+        ),
+        (
+            RectShape::stroke(rect, 0.0, stroke, StrokeKind::Inside).into(),
+            PathShape::rect(
+                rect,
+                0.0,
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone().inside(),
+            )
+            .into(),
+        ),
+        (
+            Shape::closed_line(corners.clone(), path_stroke.clone()),
+            Shape::closed_line(corners.clone(), dashed_path_stroke.clone()),
+        ),
+        (
+            Shape::polygon(
+                vec![corners.clone()],
+                Color32::TRANSPARENT,
+                path_stroke.clone(),
+            ),
+            Shape::polygon(
+                vec![corners.clone()],
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone(),
+            ),
+        ),
+        (
+            QuadraticBezierShape::from_points_stroke(
+                quadratic,
+                false,
+                Color32::TRANSPARENT,
+                path_stroke.clone(),
+            )
+            .into(),
+            QuadraticBezierShape::from_points_stroke(
+                quadratic,
+                false,
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone(),
+            )
+            .into(),
+        ),
+        (
+            CubicBezierShape::from_points_stroke(
+                cubic,
+                false,
+                Color32::TRANSPARENT,
+                path_stroke.clone(),
+            )
+            .into(),
+            CubicBezierShape::from_points_stroke(
+                cubic,
+                false,
+                Color32::TRANSPARENT,
+                dashed_path_stroke.clone(),
+            )
+            .into(),
+        ),
+    ];
+
+    for (solid, dashed) in shapes {
+        let (solid_mesh, solid_area) = covered_area(solid.clone());
+        let (dashed_mesh, dashed_area) = covered_area(dashed);
+        let ratio = dashed_area / solid_area;
+        assert!(
+            (0.4..0.6).contains(&ratio),
+            "Dashes cover {ratio} of the solid stroke of {solid:?}"
+        );
+        assert!(
+            solid_mesh
+                .calc_bounds()
+                .expand(0.1)
+                .contains_rect(dashed_mesh.calc_bounds()),
+            "{solid:?}"
+        );
+    }
+
+    // The dashes stay on the same side of the edge as the solid stroke:
+    let (mesh, _) = covered_area(
+        PathShape::rect(
+            rect,
+            0.0,
+            Color32::TRANSPARENT,
+            dashed_path_stroke.clone().inside(),
+        )
+        .into(),
+    );
+    assert!(rect.expand(0.6).contains_rect(mesh.calc_bounds()));
+    let (mesh, _) = covered_area(
+        PathShape::circle(
+            center,
+            40.0,
+            Color32::TRANSPARENT,
+            dashed_path_stroke.clone().outside(),
+        )
+        .into(),
+    );
+    assert!(
+        mesh.vertices
+            .iter()
+            .filter(|v| v.color != Color32::TRANSPARENT)
+            .all(|v| 39.9 < v.pos.distance(center))
+    );
+}
+
+#[test]
+fn fill_concave_polygon() {
+    use crate::*;
+
+    let options = TessellationOptions {
+        feathering: false,
+        ..Default::default()
+    };
+    let mut tessellator = Tessellator::new(1.0, options, [1024, 1024], vec![]);
+
+    let mut filled_area = |polygon: PolygonShape| {
+        let mut mesh = Mesh::default();
+        tessellator.tessellate_polygon(&polygon, &mut mesh);
+        assert!(mesh.is_valid());
+        mesh.indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].pos);
+                let (ab, ac) = (b - a, c - a);
+                0.5 * (ab.x * ac.y - ab.y * ac.x).abs()
+            })
+            .sum::<f32>()
+    };
+
+    // An L-shape:
+    let l_shape = vec![
+        pos2(0.0, 0.0),
+        pos2(10.0, 0.0),
+        pos2(10.0, 4.0),
+        pos2(4.0, 4.0),
+        pos2(4.0, 10.0),
+        pos2(0.0, 10.0),
+    ];
+    let area = filled_area(PolygonShape::new(
+        vec![l_shape],
+        Color32::WHITE,
+        Stroke::NONE,
+    ));
+    assert!((area - 64.0).abs() < 1e-3, "{area}");
+
+    // A square with a square hole:
+    let outer = vec![
+        pos2(0.0, 0.0),
+        pos2(10.0, 0.0),
+        pos2(10.0, 10.0),
+        pos2(0.0, 10.0),
+    ];
+    let inner = vec![
+        pos2(2.0, 2.0),
+        pos2(8.0, 2.0),
+        pos2(8.0, 8.0),
+        pos2(2.0, 8.0),
+    ];
+    let polygon = PolygonShape::new(vec![outer, inner], Color32::WHITE, Stroke::NONE);
+    let area = filled_area(polygon.clone());
+    assert!((area - 100.0).abs() < 1e-3, "{area}");
+    let area = filled_area(polygon.with_fill_rule(FillRule::EvenOdd));
+    assert!((area - 64.0).abs() < 1e-3, "{area}");
+
+    // Like other filled shapes, the feathering is centered on the edge:
+    let mut tessellator = Tessellator::new(1.0, Default::default(), [1024, 1024], vec![]);
+    let square = vec![
+        pos2(0.0, 0.0),
+        pos2(10.0, 0.0),
+        pos2(10.0, 10.0),
+        pos2(0.0, 10.0),
+    ];
+    for contour in [square.clone(), square.into_iter().rev().collect()] {
+        let mut mesh = Mesh::default();
+        let polygon = PolygonShape::new(vec![contour], Color32::WHITE, Stroke::NONE);
+        tessellator.tessellate_polygon(&polygon, &mut mesh);
+        assert!(mesh.is_valid());
+        let bounds = |opaque: bool| {
+            Rect::from_points(
+                &mesh
+                    .vertices
+                    .iter()
+                    .filter(|v| (v.color == Color32::WHITE) == opaque)
+                    .map(|v| v.pos)
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            bounds(true),
+            Rect::from_min_max(pos2(0.5, 0.5), pos2(9.5, 9.5))
+        );
+        assert_eq!(
+            bounds(false),
+            Rect::from_min_max(pos2(-0.5, -0.5), pos2(10.5, 10.5))
+        );
+    }
+}
@@ -579,6 +579,11 @@ impl Fonts {
         self.lock().galley_cache.num_galleys_in_cache()
     }
 
+    /// How many text layouts were found in the [`Galley`] cache since [`Self::begin_pass`].
+    pub fn galley_cache_stats(&self) -> GalleyCacheStats {
+        self.lock().galley_cache.stats
+    }
+
     /// How full is the font atlas?
     ///
     /// This increases as new fonts and/or glyphs are used,
@@ -758,11 +763,24 @@ struct CachedGalley {
     galley: Arc<Galley>,
 }
 
+/// How the [`Galley`] cache was used during a pass. See [`Fonts::galley_cache_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GalleyCacheStats {
+    /// Layouts that were found in the cache.
+    pub hits: usize,
+
+    /// Layouts that had to be computed.
+    pub misses: usize,
+}
+
 #[derive(Default)]
 struct GalleyCache {
     /// Frame counter used to do garbage collection on the cache
     generation: u32,
     cache: nohash_hasher::IntMap<u64, CachedGalley>,
+
+    /// Reset by [`Self::flush_cache`].
+    stats: GalleyCacheStats,
 }
 
 impl GalleyCache {
@@ -802,6 +820,7 @@ impl GalleyCache {
         let galley = match self.cache.entry(hash) {
             std::collections::hash_map::Entry::Occupied(entry) => {
                 // The job was found in cache - no need to re-layout.
+                self.stats.hits += 1;
                 let cached = entry.into_mut();
                 cached.last_used = self.generation;
 
@@ -822,6 +841,7 @@ impl GalleyCache {
                 galley
             }
             std::collections::hash_map::Entry::Vacant(entry) => {
+                self.stats.misses += 1;
                 let job = Arc::new(job);
                 if allow_split_paragraphs && should_cache_each_paragraph_individually(&job) {
                     let (child_galleys, child_hashes) =
@@ -903,6 +923,8 @@ impl GalleyCache {
                 break_on_newline: job.break_on_newline,
                 halign: job.halign,
                 justify: job.justify,
+                justify_last_row: job.justify_last_row,
+                tab_stops: job.tab_stops.clone(),
                 first_row_min_height: if is_first_paragraph {
                     job.first_row_min_height
                 } else {
@@ -983,6 +1005,7 @@ impl GalleyCache {
             cached.last_used == current_generation // only keep those that were used this frame
         });
         self.generation = self.generation.wrapping_add(1);
+        self.stats = Default::default();
     }
 }
 
@@ -1073,7 +1096,7 @@ mod tests {
     use core::f32;
 
     use super::*;
-    use crate::text::{TextWrapping, layout};
+    use crate::text::{TabStop, TextWrapping, layout};
     use crate::{Stroke, text::TextFormat};
     use ecolor::Color32;
     use emath::Align;
@@ -1127,6 +1150,17 @@ mod tests {
                 Color32::WHITE,
                 50.0,
             ),
+            {
+                let mut job = LayoutJob::simple(
+                    "Item\tQty\tPrice\nApples\t12\t1.50\nPears\t3\t12.25 each, which is a bit much"
+                        .to_owned(),
+                    FontId::new(16.0, FontFamily::Proportional),
+                    Color32::WHITE,
+                    200.0,
+                );
+                job.tab_stops = vec![TabStop::right(90.0), TabStop::decimal(130.0)];
+                job
+            },
             {
                 let mut job = LayoutJob {
                     first_row_min_height: 20.0,
@@ -4,7 +4,10 @@ use emath::{Align, GuiRounding as _, NumExt as _, Pos2, Rect, Vec2, pos2, vec2};
 
 use crate::{Color32, Mesh, Stroke, Vertex, stroke::PathStroke, text::font::Font};
 
-use super::{FontsImpl, Galley, Glyph, LayoutJob, LayoutSection, PlacedRow, Row, RowVisuals};
+use super::{
+    FontsImpl, Galley, Glyph, LayoutJob, LayoutSection, PlacedRow, Row, RowVisuals, TabAlign,
+    TabStop,
+};
 
 // ----------------------------------------------------------------------------
 
@@ -51,6 +54,11 @@ struct Paragraph {
 
     /// In case of an empty paragraph ("\n"), use this as height.
     pub empty_paragraph_height: f32,
+
+    /// The index of each `\t` glyph, and its width when there is no tab stop after it.
+    ///
+    /// Only used if the job has [`LayoutJob::tab_stops`].
+    pub tabs: Vec<(usize, f32)>,
 }
 
 impl Paragraph {
@@ -60,10 +68,77 @@ impl Paragraph {
             section_index_at_start,
             glyphs: vec![],
             empty_paragraph_height: 0.0,
+            tabs: vec![],
+        }
+    }
+
+    /// Set the width of the tabs from glyph `start_idx` onwards,
+    /// so that the text after them lines up with the tab stops.
+    ///
+    /// The tab stops are measured from `row_start_x`, the start of the row the glyph is on.
+    /// Called for the whole paragraph, and again for the rest of it each time it is wrapped onto a new row.
+    fn layout_tabs(
+        &mut self,
+        job: &LayoutJob,
+        point_scale: PointScale,
+        start_idx: usize,
+        row_start_x: f32,
+    ) {
+        let first_tab = self.tabs.partition_point(|&(idx, _)| idx < start_idx);
+        for tab in first_tab..self.tabs.len() {
+            let (tab_idx, default_width) = self.tabs[tab];
+            let tab_x = self.glyphs[tab_idx].pos.x;
+            let text_start_x = self
+                .glyphs
+                .get(tab_idx + 1)
+                .map_or_else(|| self.glyphs[tab_idx].max_x(), |glyph| glyph.pos.x);
+
+            let tab_width = if let Some(tab_stop) = next_tab_stop(job, tab_x - row_start_x) {
+                // The text that is aligned to the tab stop, up to the next tab:
+                let text_end_idx = self
+                    .tabs
+                    .get(tab + 1)
+                    .map_or(self.glyphs.len(), |&(idx, _)| idx);
+                let text_after_tab = &self.glyphs[tab_idx + 1..text_end_idx];
+                let text_width = text_after_tab
+                    .last()
+                    .map_or(0.0, |glyph| glyph.max_x() - text_start_x);
+                let anchor_x = match tab_stop.align {
+                    TabAlign::Left => 0.0,
+                    TabAlign::Center => text_width / 2.0,
+                    TabAlign::Right => text_width,
+                    TabAlign::Decimal(separator) => text_after_tab
+                        .iter()
+                        .find(|glyph| glyph.chr == separator)
+                        .map_or(text_width, |glyph| glyph.pos.x - text_start_x),
+                };
+                point_scale.round_to_pixel(
+                    (tab_stop.position - (tab_x - row_start_x) - anchor_x).at_least(0.0),
+                )
+            } else {
+                default_width
+            };
+
+            // Like in `layout_section`, the text after the tab starts on a whole pixel:
+            let delta = point_scale.round_to_pixel(tab_x + tab_width)
+                - point_scale.round_to_pixel(tab_x + self.glyphs[tab_idx].advance_width);
+            self.glyphs[tab_idx].advance_width = tab_width;
+            for glyph in &mut self.glyphs[tab_idx + 1..] {
+                glyph.pos.x += delta;
+            }
         }
     }
 }
 
+/// The first tab stop after `x`, if any.
+fn next_tab_stop(job: &LayoutJob, x: f32) -> Option<TabStop> {
+    // Ignore tab stops we are already (almost) at, so that a `\t` always moves the text:
+    job.tab_stops
+        .iter()
+        .find(|tab_stop| x + 0.5 < tab_stop.position)
+        .copied()
+}
+
 /// Layout text into a [`Galley`].
 ///
 /// In most cases you should use [`crate::Fonts::layout_job`] instead
@@ -95,10 +170,16 @@ pub fn layout(fonts: &mut FontsImpl, job: Arc<LayoutJob>) -> Galley {
 
     let point_scale = PointScale::new(fonts.pixels_per_point());
 
+    if !job.tab_stops.is_empty() {
+        for paragraph in &mut paragraphs {
+            paragraph.layout_tabs(&job, point_scale, 0, 0.0);
+        }
+    }
+
     let intrinsic_size = calculate_intrinsic_size(point_scale, &job, &paragraphs);
 
     let mut elided = false;
-    let mut rows = rows_from_paragraphs(paragraphs, &job, &mut elided);
+    let mut rows = rows_from_paragraphs(point_scale, paragraphs, &job, &mut elided);
     if elided {
         if let Some(last_placed) = rows.last_mut() {
             let last_row = Arc::make_mut(&mut last_placed.row);
@@ -115,14 +196,9 @@ pub fn layout(fonts: &mut FontsImpl, job: Arc<LayoutJob>) -> Galley {
         let num_rows = rows.len();
         for (i, placed_row) in rows.iter_mut().enumerate() {
             let is_last_row = i + 1 == num_rows;
-            let justify_row = justify && !placed_row.ends_with_newline && !is_last_row;
-            halign_and_justify_row(
-                point_scale,
-                placed_row,
-                job.halign,
-                job.wrap.max_width,
-                justify_row,
-            );
+            let is_last_row_of_paragraph = placed_row.ends_with_newline || is_last_row;
+            let justify_row = justify && (job.justify_last_row || !is_last_row_of_paragraph);
+            halign_and_justify_row(point_scale, placed_row, &job, justify_row);
         }
     }
 
@@ -173,10 +249,17 @@ fn layout_section(
                 }
             }
 
+            let advance_width = glyph_info.advance_width;
+            if chr == '\t' && !job.tab_stops.is_empty() {
+                // The width depends on the tab stops, which depend on where the row starts.
+                // This is set later, in `Paragraph::layout_tabs`.
+                paragraph.tabs.push((paragraph.glyphs.len(), advance_width));
+            }
+
             paragraph.glyphs.push(Glyph {
                 chr,
                 pos: pos2(paragraph.cursor_x, f32::NAN),
-                advance_width: glyph_info.advance_width,
+                advance_width,
                 line_height,
                 font_impl_height: font_impl.map_or(0.0, |f| f.row_height()),
                 font_impl_ascent: font_impl.map_or(0.0, |f| f.ascent()),
@@ -186,7 +269,7 @@ fn layout_section(
                 section_index,
             });
 
-            paragraph.cursor_x += glyph_info.advance_width;
+            paragraph.cursor_x += advance_width;
             paragraph.cursor_x = font.round_to_pixel(paragraph.cursor_x);
             last_glyph_id = Some(glyph_info.id);
         }
@@ -227,6 +310,7 @@ fn calculate_intrinsic_size(
 
 // Ignores the Y coordinate.
 fn rows_from_paragraphs(
+    point_scale: PointScale,
     paragraphs: Vec<Paragraph>,
     job: &LayoutJob,
     elided: &mut bool,
@@ -235,7 +319,7 @@ fn rows_from_paragraphs(
 
     let mut rows = vec![];
 
-    for (i, paragraph) in paragraphs.into_iter().enumerate() {
+    for (i, mut paragraph) in paragraphs.into_iter().enumerate() {
         if job.wrap.max_rows <= rows.len() {
             *elided = true;
             break;
@@ -269,7 +353,7 @@ fn rows_from_paragraphs(
                     }),
                 });
             } else {
-                line_break(&paragraph, job, &mut rows, elided);
+                line_break(point_scale, &mut paragraph, job, &mut rows, elided);
                 let placed_row = rows.last_mut().unwrap();
                 let row = Arc::make_mut(&mut placed_row.row);
                 row.ends_with_newline = !is_last_paragraph;
@@ -281,7 +365,8 @@ fn rows_from_paragraphs(
 }
 
 fn line_break(
-    paragraph: &Paragraph,
+    point_scale: PointScale,
+    paragraph: &mut Paragraph,
     job: &LayoutJob,
     out_rows: &mut Vec<PlacedRow>,
     elided: &mut bool,
@@ -323,6 +408,7 @@ fn line_break(
                 });
                 row_start_x += first_row_indentation;
                 first_row_indentation = 0.0;
+                paragraph.layout_tabs(job, point_scale, row_start_idx, row_start_x);
             } else if let Some(last_kept_index) = row_break_candidates.get(job.wrap.break_anywhere)
             {
                 let glyphs: Vec<Glyph> = paragraph.glyphs[row_start_idx..=last_kept_index]
@@ -352,6 +438,7 @@ fn line_break(
                 row_start_idx = last_kept_index + 1;
                 row_start_x = paragraph.glyphs[row_start_idx].pos.x;
                 row_break_candidates.forget_before_idx(row_start_idx);
+                paragraph.layout_tabs(job, point_scale, row_start_idx, row_start_x);
             } else {
                 // Found no place to break, so we have to overrun wrap_width.
             }
@@ -550,21 +637,33 @@ fn replace_last_glyph_with_overflow_character(
 fn halign_and_justify_row(
     point_scale: PointScale,
     placed_row: &mut PlacedRow,
-    halign: Align,
-    wrap_width: f32,
+    job: &LayoutJob,
     justify: bool,
 ) {
+    let halign = job.halign;
+    let wrap_width = job.wrap.max_width;
     let row = Arc::make_mut(&mut placed_row.row);
 
     if row.glyphs.is_empty() {
         return;
     }
 
-    let num_leading_spaces = row
-        .glyphs
-        .iter()
-        .take_while(|glyph| glyph.chr.is_whitespace())
-        .count();
+    // Text aligned to tab stops must stay where it is,
+    // so we only stretch the text after the last tab.
+    let last_tab = if job.tab_stops.is_empty() {
+        None
+    } else {
+        row.glyphs.iter().rposition(|glyph| glyph.chr == '\t')
+    };
+
+    let num_leading_spaces = if last_tab.is_some() {
+        0 // Leading whitespace (including the tabs) is part of the layout
+    } else {
+        row.glyphs
+            .iter()
+            .take_while(|glyph| glyph.chr.is_whitespace())
+            .count()
+    };
 
     let glyph_range = if num_leading_spaces == row.glyphs.len() {
         // There is only whitespace
@@ -586,7 +685,12 @@ fn halign_and_justify_row(
     let original_max_x = row.glyphs[glyph_range.1 - 1].logical_rect().max.x;
     let original_width = original_max_x - original_min_x;
 
-    let target_width = if justify && num_glyphs_in_range > 1 {
+    // The glyphs that we spread out to justify the row:
+    let first_stretched = last_tab.map_or(0, |last_tab| last_tab + 1);
+    let stretch_range = (glyph_range.0.max(first_stretched), glyph_range.1);
+    let anything_to_stretch = stretch_range.0 < stretch_range.1;
+
+    let target_width = if justify && num_glyphs_in_range > 1 && anything_to_stretch {
         wrap_width
     } else {
         original_width
@@ -598,6 +702,20 @@ fn halign_and_justify_row(
         Align::RIGHT => (-target_width, 0.0),
     };
 
+    if !anything_to_stretch {
+        // Nothing after the last tab to stretch:
+        placed_row.pos.x = point_scale.round_to_pixel(target_min_x);
+        for glyph in &mut row.glyphs {
+            glyph.pos.x = point_scale.round_to_pixel(glyph.pos.x - original_min_x);
+        }
+        row.size.x = target_max_x - target_min_x;
+        return;
+    }
+    let num_glyphs_in_range = stretch_range.1 - stretch_range.0;
+    let fixed_width = row.glyphs[stretch_range.0].logical_rect().min.x - original_min_x;
+    let (original_width, target_width) = (original_width - fixed_width, target_width - fixed_width);
+    let glyph_range = stretch_range;
+
     let num_spaces_in_range = row.glyphs[glyph_range.0..glyph_range.1]
         .iter()
         .filter(|glyph| glyph.chr.is_whitespace())
@@ -624,14 +742,17 @@ fn halign_and_justify_row(
     }
 
     placed_row.pos.x = point_scale.round_to_pixel(target_min_x);
-    let mut translate_x = -original_min_x - extra_x_per_glyph * glyph_range.0 as f32;
+    let mut translate_x =
+        -original_min_x - extra_x_per_glyph * (glyph_range.0 - first_stretched) as f32;
 
-    for glyph in &mut row.glyphs {
+    for (i, glyph) in row.glyphs.iter_mut().enumerate() {
         glyph.pos.x += translate_x;
         glyph.pos.x = point_scale.round_to_pixel(glyph.pos.x);
-        translate_x += extra_x_per_glyph;
-        if glyph.chr.is_whitespace() {
-            translate_x += extra_x_per_space;
+        if first_stretched <= i {
+            translate_x += extra_x_per_glyph;
+            if glyph.chr.is_whitespace() {
+                translate_x += extra_x_per_space;
+            }
         }
     }
 
@@ -729,6 +850,7 @@ fn galley_from_rows(
 struct FormatSummary {
     any_background: bool,
     any_underline: bool,
+    any_wavy_underline: bool,
     any_strikethrough: bool,
 }
 
@@ -737,6 +859,7 @@ fn format_summary(job: &LayoutJob) -> FormatSummary {
     for section in &job.sections {
         format_summary.any_background |= section.format.background != Color32::TRANSPARENT;
         format_summary.any_underline |= section.format.underline != Stroke::NONE;
+        format_summary.any_wavy_underline |= section.format.wavy_underline != Stroke::NONE;
         format_summary.any_strikethrough |= section.format.strikethrough != Stroke::NONE;
     }
     format_summary
@@ -767,7 +890,7 @@ fn tessellate_row(
     let glyph_vertex_end = mesh.vertices.len();
 
     if format_summary.any_underline {
-        add_row_hline(point_scale, row, &mut mesh, |glyph| {
+        add_row_hline(point_scale, row, &mut mesh, false, |glyph| {
             let format = &job.sections[glyph.section_index as usize].format;
             let stroke = format.underline;
             let y = glyph.logical_rect().bottom();
@@ -775,8 +898,17 @@ fn tessellate_row(
         });
     }
 
+    if format_summary.any_wavy_underline {
+        add_row_hline(point_scale, row, &mut mesh, true, |glyph| {
+            let format = &job.sections[glyph.section_index as usize].format;
+            let stroke = format.wavy_underline;
+            let y = glyph.logical_rect().bottom() - wave_amplitude(stroke);
+            (stroke, y)
+        });
+    }
+
     if format_summary.any_strikethrough {
-        add_row_hline(point_scale, row, &mut mesh, |glyph| {
+        add_row_hline(point_scale, row, &mut mesh, false, |glyph| {
             let format = &job.sections[glyph.section_index as usize].format;
             let stroke = format.strikethrough;
             let y = glyph.logical_rect().center().y;
@@ -893,20 +1025,49 @@ fn tessellate_glyphs(point_scale: PointScale, job: &LayoutJob, row: &Row, mesh:
     }
 }
 
-/// Add a horizontal line over a row of glyphs with a stroke and y decided by a callback.
+/// How far a wavy line goes above and below its center.
+fn wave_amplitude(stroke: Stroke) -> f32 {
+    stroke.width.max(1.0)
+}
+
+/// Add horizontal lines below, above or through the glyphs of a row.
+///
+/// If `wavy` is set, the lines zig-zag up and down around `y`.
 fn add_row_hline(
     point_scale: PointScale,
     row: &Row,
     mesh: &mut Mesh,
+    wavy: bool,
     stroke_and_y: impl Fn(&Glyph) -> (Stroke, f32),
 ) {
     let mut path = crate::tessellator::Path::default(); // reusing path to avoid re-allocations.
+    let mut points = Vec::new();
 
     let mut end_line = |start: Option<(Stroke, Pos2)>, stop_x: f32| {
         if let Some((stroke, start)) = start {
             let stop = pos2(stop_x, start.y);
             path.clear();
-            path.add_line_segment([start, stop]);
+            if wavy {
+                let amplitude = wave_amplitude(stroke);
+                let half_period = 2.0 * amplitude;
+                points.clear();
+                let mut x = start.x;
+                let mut up = true;
+                while x < stop.x {
+                    let y = if up {
+                        start.y - amplitude
+                    } else {
+                        start.y + amplitude
+                    };
+                    points.push(pos2(x, y));
+                    x += half_period;
+                    up = !up;
+                }
+                points.push(pos2(stop.x, start.y));
+                path.add_open_points(&points);
+            } else {
+                path.add_line_segment([start, stop]);
+            }
             let feathering = 1.0 / point_scale.pixels_per_point();
             path.stroke_open(feathering, &PathStroke::from(stroke), mesh);
         }
@@ -1088,6 +1249,137 @@ mod tests {
         assert_eq!(galley.rows.len(), 1);
     }
 
+    #[test]
+    fn test_tab_stops() {
+        let mut fonts = FontsImpl::new(
+            1.0,
+            1024,
+            AlphaFromCoverage::default(),
+            FontDefinitions::default(),
+        );
+        let mut job = LayoutJob::single_section(
+            "a\tleft\tright\t1.5\tc\nbb\tl\tr\t100.25\tcenter".into(),
+            TextFormat::default(),
+        );
+        job.tab_stops = vec![
+            TabStop::left(50.0),
+            TabStop::right(150.0),
+            TabStop::decimal(200.0),
+            TabStop::center(300.0),
+        ];
+        let galley = layout(&mut fonts, job.into());
+        assert_eq!(galley.rows.len(), 2);
+
+        for row in &galley.rows {
+            let glyphs = &row.glyphs;
+            let tabs: Vec<usize> = (0..glyphs.len())
+                .filter(|&i| glyphs[i].chr == '\t')
+                .collect();
+            let [left, right, decimal, center] = tabs[..] else {
+                panic!("Expected four tabs");
+            };
+
+            // Within a pixel:
+            let assert_at = |x: f32, expected: f32| {
+                assert!((x - expected).abs() <= 1.0, "Expected {expected}, got {x}");
+            };
+            assert_at(glyphs[left + 1].pos.x, 50.0);
+            assert_at(glyphs[decimal - 1].max_x(), 150.0);
+            let separator = glyphs.iter().position(|g| g.chr == '.').unwrap();
+            assert_at(glyphs[separator].pos.x, 200.0);
+            let center_x = (glyphs[center + 1].pos.x + glyphs.last().unwrap().max_x()) / 2.0;
+            assert_at(center_x, 300.0);
+            assert!(glyphs[right].advance_width > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_tab_stops_on_wrapped_row() {
+        let mut fonts = FontsImpl::new(
+            1.0,
+            1024,
+            AlphaFromCoverage::default(),
+            FontDefinitions::default(),
+        );
+        let text = "a few words that wrap onto the next row:\tx";
+        let mut job = LayoutJob::simple(text.into(), FontId::default(), Color32::WHITE, 150.0);
+        job.tab_stops = vec![TabStop::left(80.0), TabStop::right(140.0)];
+
+        let galley = layout(&mut fonts, job.into());
+        assert_eq!(galley.rows.len(), 2);
+
+        // The tab is far enough into the paragraph to be past all tab stops,
+        // but the tab stops are measured from the start of its row:
+        let glyphs = &galley.rows[1].glyphs;
+        assert_eq!(glyphs[0].chr, 'o', "The tab is on the wrapped row");
+        let tab = glyphs.iter().position(|g| g.chr == '\t').unwrap();
+        assert!(glyphs[tab].pos.x < 140.0);
+        let x = glyphs.last().unwrap();
+        assert!(
+            (x.max_x() - 140.0).abs() <= 1.0,
+            "The text after the tab is right-aligned to the tab stop: {x:?}"
+        );
+        assert_eq!(x.pos.x, x.pos.x.round(), "Rounded to pixels");
+    }
+
+    #[test]
+    fn test_justify_after_tab() {
+        let mut fonts = FontsImpl::new(
+            1.0,
+            1024,
+            AlphaFromCoverage::default(),
+            FontDefinitions::default(),
+        );
+        let text = "Name:\tsome words that will be justified and wrapped\nLast row";
+        let mut job = LayoutJob::simple(text.into(), FontId::default(), Color32::WHITE, 300.0);
+        job.tab_stops = vec![TabStop::left(60.0)];
+        job.justify = true;
+
+        let galley = layout(&mut fonts, job.clone().into());
+        let first_row = &galley.rows[0];
+        assert!(2 < galley.rows.len());
+        let tab = first_row.glyphs.iter().position(|g| g.chr == '\t').unwrap();
+        assert_eq!(
+            first_row.glyphs[tab + 1].pos.x,
+            60.0,
+            "The tab stop is kept"
+        );
+        assert_eq!(first_row.size.x, 300.0);
+
+        // The last row is not justified, unless we ask for it:
+        let last_row_width = |galley: &Galley| galley.rows.last().unwrap().size.x;
+        assert!(last_row_width(&galley) < 100.0);
+        job.justify_last_row = true;
+        let galley = layout(&mut fonts, job.into());
+        assert_eq!(last_row_width(&galley), 300.0);
+
+        // Nothing follows the last tab, so nothing is stretched:
+        let mut job = LayoutJob::simple(
+            "Name:\tValue\t".into(),
+            FontId::default(),
+            Color32::WHITE,
+            300.0,
+        );
+        job.tab_stops = vec![TabStop::left(60.0)];
+        job.justify = true;
+        job.justify_last_row = true;
+        let galley = layout(&mut fonts, job.into());
+        let row = &galley.rows[0];
+        let value_end = row
+            .glyphs
+            .iter()
+            .rfind(|g| g.chr == 'e')
+            .unwrap()
+            .logical_rect()
+            .max
+            .x;
+        assert!(
+            (row.size.x - value_end).abs() < 1.0,
+            "The row keeps its natural width: {} vs {value_end}",
+            row.size.x
+        );
+    }
+
     #[test]
     fn test_truncate_with_newline() {
         // No matter where we wrap, we should be appending the newline character.
@@ -76,8 +76,22 @@ pub struct LayoutJob {
     pub halign: Align,
 
     /// Justify text so that word-wrapped rows fill the whole [`TextWrapping::max_width`].
+    ///
+    /// The last row of each paragraph is not justified, unless [`Self::justify_last_row`] is set.
+    /// Only the text after the last `\t` of a row is stretched, so that [`Self::tab_stops`] stay aligned.
     pub justify: bool,
 
+    /// With [`Self::justify`], also justify the last row of each paragraph.
+    ///
+    /// Default: `false`.
+    pub justify_last_row: bool,
+
+    /// Where `\t` characters move the text to, sorted by position.
+    ///
+    /// If empty (the default), or after the last tab stop,
+    /// a `\t` is as wide as [`crate::text::TAB_SIZE`] spaces.
+    pub tab_stops: Vec<TabStop>,
+
     /// Round output sizes using [`emath::GuiRounding`], to avoid rounding errors in layout code.
     pub round_output_to_gui: bool,
 }
@@ -93,6 +107,8 @@ impl Default for LayoutJob {
             break_on_newline: true,
             halign: Align::LEFT,
             justify: false,
+            justify_last_row: false,
+            tab_stops: Vec::new(),
             round_output_to_gui: true,
         }
     }
@@ -216,6 +232,8 @@ impl std::hash::Hash for LayoutJob {
             break_on_newline,
             halign,
             justify,
+            justify_last_row,
+            tab_stops,
             round_output_to_gui,
         } = self;
 
@@ -226,6 +244,8 @@ impl std::hash::Hash for LayoutJob {
         break_on_newline.hash(state);
         halign.hash(state);
         justify.hash(state);
+        justify_last_row.hash(state);
+        tab_stops.hash(state);
         round_output_to_gui.hash(state);
     }
 }
@@ -296,6 +316,11 @@ pub struct TextFormat {
 
     pub underline: Stroke,
 
+    /// A wavy underline, e.g. to mark misspelled words.
+    ///
+    /// Painted in addition to [`Self::underline`].
+    pub wavy_underline: Stroke,
+
     pub strikethrough: Stroke,
 
     /// If you use a small font and [`Align::TOP`] you
@@ -322,6 +347,7 @@ impl Default for TextFormat {
             expand_bg: 1.0,
             italics: false,
             underline: Stroke::NONE,
+            wavy_underline: Stroke::NONE,
             strikethrough: Stroke::NONE,
             valign: Align::BOTTOM,
         }
@@ -340,6 +366,7 @@ impl std::hash::Hash for TextFormat {
             expand_bg,
             italics,
             underline,
+            wavy_underline,
             strikethrough,
             valign,
         } = self;
@@ -353,6 +380,7 @@ impl std::hash::Hash for TextFormat {
         emath::OrderedFloat(*expand_bg).hash(state);
         italics.hash(state);
         underline.hash(state);
+        wavy_underline.hash(state);
         strikethrough.hash(state);
         valign.hash(state);
     }
@@ -502,6 +530,78 @@ impl TextWrapping {
 
 // ----------------------------------------------------------------------------
 
+/// How the text after a `\t` is aligned to a [`TabStop`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TabAlign {
+    /// The text starts at the tab stop.
+    #[default]
+    Left,
+
+    /// The text is centered on the tab stop.
+    Center,
+
+    /// The text ends at the tab stop.
+    Right,
+
+    /// The first occurrence of this character (e.g. `'.'`) is placed at the tab stop,
+    /// so that columns of numbers line up on their decimal separator.
+    ///
+    /// Text without the character ends at the tab stop, like [`Self::Right`].
+    Decimal(char),
+}
+
+/// A position that a `\t` moves the text to, see [`LayoutJob::tab_stops`].
+///
+/// The text after the tab runs until the next `\t` or newline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TabStop {
+    /// Distance from the start of the row, in points.
+    pub position: f32,
+
+    /// How the text after the tab is placed relative to [`Self::position`].
+    pub align: TabAlign,
+}
+
+impl TabStop {
+    /// A tab stop `position` points from the start of the row.
+    pub fn new(position: f32, align: TabAlign) -> Self {
+        Self { position, align }
+    }
+
+    /// The text starts at the tab stop, see [`TabAlign::Left`].
+    pub fn left(position: f32) -> Self {
+        Self::new(position, TabAlign::Left)
+    }
+
+    /// The text is centered on the tab stop, see [`TabAlign::Center`].
+    pub fn center(position: f32) -> Self {
+        Self::new(position, TabAlign::Center)
+    }
+
+    /// The text ends at the tab stop, see [`TabAlign::Right`].
+    pub fn right(position: f32) -> Self {
+        Self::new(position, TabAlign::Right)
+    }
+
+    /// Aligns on the first `'.'`, see [`TabAlign::Decimal`].
+    pub fn decimal(position: f32) -> Self {
+        Self::new(position, TabAlign::Decimal('.'))
+    }
+}
+
+impl std::hash::Hash for TabStop {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let Self { position, align } = self;
+        OrderedFloat(*position).hash(state);
+        align.hash(state);
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 /// Text that has been laid out, ready for painting.
 ///
 /// You can create a [`Galley`] using [`crate::Fonts::layout_job`];
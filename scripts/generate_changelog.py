@@ -269,6 +269,7 @@ def main() -> None:
         "egui_extras",
         "egui_glow",
         "egui_kittest",
+        "egui_remote",
         "egui-wgpu",
         "egui-winit",
         "egui",
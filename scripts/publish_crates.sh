@@ -10,5 +10,6 @@
 (cd crates/egui-wgpu            && cargo publish --quiet)  &&  echo "✅ egui-wgpu"
 (cd crates/eframe               && cargo publish --quiet)  &&  echo "✅ eframe"
 (cd crates/egui_kittest         && cargo publish --quiet)  &&  echo "✅ egui_kittest"
+(cd crates/egui_remote          && cargo publish --quiet)  &&  echo "✅ egui_remote"
 (cd crates/egui_extras          && cargo publish --quiet)  &&  echo "✅ egui_extras"
 (cd crates/egui_demo_lib        && cargo publish --quiet)  &&  echo "✅ egui_demo_lib"
@@ -0,0 +1,116 @@
+use egui::{Modifiers, ScrollArea, Vec2};
+use egui_kittest::Harness;
+use egui_kittest::kittest::Queryable as _;
+
+#[test]
+fn find_in_page() {
+    let mut harness = Harness::builder()
+        .with_size(Vec2::new(300.0, 200.0))
+        .build_ui(|ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                for i in 0..50 {
+                    ui.label(format!("Row {i}: needle"));
+                }
+                ui.add(egui::TextEdit::multiline(
+                    &mut "Needle in a read-only haystack",
+                ));
+                ui.add(egui::TextEdit::singleline(&mut "needle".to_owned()));
+            });
+        });
+
+    harness
+        .ctx
+        .options_mut(|o| o.find_in_page_with_keyboard = true);
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::F);
+    harness.run();
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert!(state.is_open());
+
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text("NEEDLE".to_owned()));
+    harness.run();
+
+    // Every label and the read-only text edit, but not the editable one:
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert_eq!(state.num_matches(), 51);
+    assert_eq!(state.current_match(), Some(0));
+
+    harness.key_press_modifiers(Modifiers::SHIFT, egui::Key::Enter);
+    harness.run();
+
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert_eq!(state.current_match(), Some(50));
+    assert!(
+        harness
+            .get_by_role(egui::accesskit::Role::MultilineTextInput)
+            .rect()
+            .max
+            .y
+            <= 200.0,
+        "The last match should have been scrolled into view"
+    );
+
+    harness.key_press(egui::Key::Escape);
+    harness.run();
+    assert!(!egui::text_selection::FindInPageState::load(&harness.ctx).is_open());
+}
+
+#[test]
+fn find_in_page_virtualized() {
+    use std::fmt::Write as _;
+
+    let mut text = String::new();
+    for i in 0..100_000 {
+        if i % 40_000 == 39_999 {
+            writeln!(text, "line {i}: needle").unwrap();
+        } else {
+            writeln!(text, "line {i}").unwrap();
+        }
+    }
+    let mut harness = Harness::builder()
+        .with_size(Vec2::new(300.0, 200.0))
+        .build_ui_state(
+            |ui, (text, visible_text): &mut (egui::LineIndexedString, String)| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    let output = egui::TextEdit::virtualized(text).show(ui);
+                    *visible_text = output.galley.text().to_owned();
+                });
+            },
+            (text.into(), String::new()),
+        );
+
+    harness
+        .ctx
+        .options_mut(|o| o.find_in_page_with_keyboard = true);
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::F);
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text("needle".to_owned()));
+    harness.run();
+
+    // Neither match is laid out to begin with:
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert_eq!(state.num_matches(), 2);
+    assert_eq!(state.current_match(), Some(0));
+    assert!(harness.state().1.contains("line 39999: needle"));
+
+    harness.key_press(egui::Key::Enter);
+    harness.run();
+
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert_eq!(state.current_match(), Some(1));
+    assert!(
+        harness.state().1.contains("line 79999: needle"),
+        "The match should have been scrolled into view"
+    );
+
+    // The cached matches are updated when the text changes:
+    harness.state_mut().0.push_str("one more needle\n");
+    harness.run();
+    let state = egui::text_selection::FindInPageState::load(&harness.ctx);
+    assert_eq!(state.num_matches(), 3);
+}
@@ -0,0 +1,99 @@
+use egui::Vec2;
+use egui_kittest::Harness;
+
+#[test]
+fn scene_rotation() {
+    struct State {
+        scene_rect: egui::Rect,
+        to_global: Option<egui::emath::Affine2>,
+        button_rect: egui::Rect,
+        clicked: bool,
+    }
+    let mut harness = Harness::builder()
+        .with_size(Vec2::new(200.0, 200.0))
+        .build_ui_state(
+            |ui, state: &mut State| {
+                egui::Scene::new()
+                    .rotation(std::f32::consts::TAU / 4.0)
+                    .show(ui, &mut state.scene_rect, |ui| {
+                        state.to_global = ui.ctx().layer_affine_to_global(ui.layer_id());
+                        let response = ui.button("Rotated");
+                        state.button_rect = response.rect;
+                        if response.clicked() {
+                            state.clicked = true;
+                        }
+                    });
+            },
+            State {
+                scene_rect: egui::Rect::ZERO,
+                to_global: None,
+                button_rect: egui::Rect::NOTHING,
+                clicked: false,
+            },
+        );
+
+    let to_global = harness
+        .state()
+        .to_global
+        .expect("The scene layer has a transform");
+    assert!(
+        (to_global.rotation_angle() - std::f32::consts::TAU / 4.0).abs() < 1e-4,
+        "The scene is rotated a quarter turn: {to_global:?}"
+    );
+
+    // The button is rotated around the center of the scene, so it no longer is where its rect says:
+    let pos = to_global * harness.state().button_rect.center();
+    assert!(
+        !harness.state().button_rect.contains(pos),
+        "The button moved on screen"
+    );
+    egui_kittest::FuzzAction::Click {
+        pos,
+        button: egui::PointerButton::Primary,
+    }
+    .apply(&mut harness);
+    harness.run();
+    assert!(harness.state().clicked, "Clicks hit the rotated button");
+}
+
+#[test]
+fn scene_register_pan_and_zoom_with_rotation() {
+    let rotation = std::f32::consts::TAU / 8.0;
+    let mut harness = Harness::builder()
+        .with_size(Vec2::new(200.0, 200.0))
+        .build_ui_state(
+            |ui, state: &mut (egui::emath::TSTransform, egui::Rect)| {
+                let (rect, mut response) =
+                    ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
+                egui::Scene::new().rotation(rotation).register_pan_and_zoom(
+                    ui,
+                    &mut response,
+                    &mut state.0,
+                );
+                state.1 = rect;
+            },
+            (egui::emath::TSTransform::IDENTITY, egui::Rect::NOTHING),
+        );
+
+    let to_global = |harness: &Harness<'_, (egui::emath::TSTransform, egui::Rect)>| {
+        let (to_global, rect) = *harness.state();
+        egui::emath::Affine2::from_angle(rotation).around(rect.center()) * to_global
+    };
+    let pointer = egui::pos2(150.0, 60.0);
+    let point_in_scene = to_global(&harness).inverse() * pointer;
+
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::PointerMoved(pointer));
+    harness.run();
+    harness.input_mut().events.push(egui::Event::Zoom(0.5));
+    harness.run();
+
+    assert_eq!(harness.state().0.scaling, 0.5);
+    let pos = to_global(&harness) * point_in_scene;
+    assert!(
+        pos.distance(pointer) < 0.01,
+        "Zooming keeps the rotated scene under the pointer: {pos:?}"
+    );
+}
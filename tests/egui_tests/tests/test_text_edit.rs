@@ -0,0 +1,515 @@
+use egui::{Modifiers, ScrollArea, Vec2};
+use egui_kittest::Harness;
+use egui_kittest::kittest::Queryable as _;
+
+#[test]
+fn virtualized_text_edit() {
+    use std::fmt::Write as _;
+
+    struct State {
+        text: egui::LineIndexedString,
+        visible_text: String,
+        cursor: Option<std::ops::Range<usize>>,
+    }
+
+    let mut text = String::new();
+    for i in 0..100_000 {
+        writeln!(text, "line {i}").unwrap();
+    }
+    let num_chars = text.chars().count();
+    let mut harness = Harness::builder()
+        .with_size(Vec2::new(300.0, 200.0))
+        .build_ui_state(
+            |ui, state: &mut State| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    let output = egui::TextEdit::virtualized(&mut state.text).show(ui);
+                    state.visible_text = output.galley.text().to_owned();
+                    state.cursor = output.cursor_range.map(|c| c.as_sorted_char_range());
+                });
+            },
+            State {
+                text: text.into(),
+                visible_text: String::new(),
+                cursor: None,
+            },
+        );
+
+    assert!(harness.state().visible_text.starts_with("line 0\nline 1\n"));
+    assert!(harness.state().visible_text.lines().count() < 20);
+
+    harness
+        .get_by_role(egui::accesskit::Role::MultilineTextInput)
+        .focus();
+    harness.run();
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::ArrowDown);
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text("x".to_owned()));
+    harness.run();
+
+    let state = harness.state();
+    assert!(state.text.as_str().ends_with("line 99999\nx"));
+    assert_eq!(state.cursor, Some(num_chars + 1..num_chars + 1));
+    assert!(state.visible_text.ends_with("line 99999\nx"));
+    assert!(state.visible_text.lines().count() < 20);
+
+    harness.key_press_modifiers(Modifiers::COMMAND | Modifiers::SHIFT, egui::Key::ArrowUp);
+    harness.run();
+    harness.key_press(egui::Key::Backspace);
+    harness.run();
+
+    let state = harness.state();
+    assert_eq!(state.text.as_str(), "");
+    assert_eq!(state.cursor, Some(0..0));
+}
+
+#[test]
+fn virtualized_text_edit_keys() {
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut egui::LineIndexedString| {
+            ui.add(egui::TextEdit::virtualized(text).lock_focus(true));
+        },
+        egui::LineIndexedString::new("one two\nthree"),
+    );
+
+    harness
+        .get_by_role(egui::accesskit::Role::MultilineTextInput)
+        .focus();
+    harness.run();
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::ArrowDown);
+    harness.run();
+
+    // Deleting words stays in the line, and then deletes the newline:
+    harness.key_press_modifiers(Modifiers::CTRL, egui::Key::Backspace);
+    harness.run();
+    assert_eq!(harness.state().as_str(), "one two\n");
+    harness.key_press_modifiers(Modifiers::CTRL, egui::Key::Backspace);
+    harness.run();
+    assert_eq!(harness.state().as_str(), "one two");
+
+    harness.key_press_modifiers(Modifiers::CTRL, egui::Key::ArrowLeft);
+    harness.key_press(egui::Key::Tab);
+    harness.run();
+    assert_eq!(harness.state().as_str(), "one \ttwo");
+
+    harness.key_press(egui::Key::Home);
+    harness.key_press(egui::Key::Tab);
+    harness.run();
+    assert_eq!(harness.state().as_str(), "\tone \ttwo");
+    harness.key_press_modifiers(Modifiers::SHIFT, egui::Key::Tab);
+    harness.run();
+    assert_eq!(harness.state().as_str(), "one \ttwo");
+}
+
+#[test]
+fn spell_checker_context_menu() {
+    struct OnlyHello;
+
+    impl egui::SpellChecker for OnlyHello {
+        fn misspelled(&self, text: &str) -> Vec<std::ops::Range<usize>> {
+            text.match_indices("helo").map(|(i, _)| i..i + 4).collect()
+        }
+
+        fn suggestions(&self, _word: &str) -> Vec<String> {
+            vec!["hello".to_owned()]
+        }
+    }
+
+    fn right_click_first_word<State>(harness: &mut Harness<'_, State>) {
+        let text_edit = harness.get_by_role(egui::accesskit::Role::TextInput);
+        let pos = text_edit.rect().min + egui::vec2(10.0, 8.0);
+        for pressed in [true, false] {
+            harness.input_mut().events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Secondary,
+                pressed,
+                modifiers: Modifiers::NONE,
+            });
+        }
+        harness.run();
+    }
+
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut String| {
+            ui.add(egui::TextEdit::singleline(text).spell_checker(&OnlyHello));
+        },
+        "helo world".to_owned(),
+    );
+    right_click_first_word(&mut harness);
+    harness.get_by_label("hello").click();
+    harness.run();
+    assert_eq!(harness.state(), "hello world");
+
+    // The replacement respects the char limit:
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut String| {
+            ui.add(
+                egui::TextEdit::singleline(text)
+                    .char_limit(10)
+                    .spell_checker(&OnlyHello),
+            );
+        },
+        "helo world".to_owned(),
+    );
+    right_click_first_word(&mut harness);
+    harness.get_by_label("hello").click();
+    harness.run();
+    assert_eq!(harness.state(), "hell world");
+
+    // The menu closes if the text changes under it:
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut String| {
+            ui.add(egui::TextEdit::singleline(text).spell_checker(&OnlyHello));
+        },
+        "helo world".to_owned(),
+    );
+    right_click_first_word(&mut harness);
+    assert!(harness.query_by_label("hello").is_some());
+    harness.state_mut().insert(0, 'x');
+    harness.run();
+    assert!(harness.query_by_label("hello").is_none());
+    assert_eq!(harness.state(), "xhelo world");
+}
+
+#[test]
+fn masked_and_validated_text_edits() {
+    #[derive(Default)]
+    struct State {
+        phone: String,
+        amount: String,
+        phone_error: Option<String>,
+        digits: egui::LineIndexedString,
+        digits_error: Option<String>,
+    }
+
+    let is_amount_char = |c: char| c.is_ascii_digit() || c == '.';
+    let is_short = |text: &str| -> Result<(), String> {
+        if text.len() <= 3 {
+            Ok(())
+        } else {
+            Err("Too long".to_owned())
+        }
+    };
+    let mut harness = Harness::new_ui_state(
+        |ui, state: &mut State| {
+            let mask = egui::InputMask::new("(999) 999-9999");
+            let output = egui::TextEdit::singleline(&mut state.phone)
+                .id_salt("phone")
+                .mask(mask.clone())
+                .validator(&mask)
+                .show(ui);
+            state.phone_error = output.validation_error;
+            ui.add(
+                egui::TextEdit::singleline(&mut state.amount)
+                    .id_salt("amount")
+                    .char_filter(&is_amount_char),
+            );
+            let output = egui::TextEdit::virtualized(&mut state.digits)
+                .id_salt("digits")
+                .char_filter(&is_amount_char)
+                .validator(&is_short)
+                .show(ui);
+            state.digits_error = output.validation_error;
+        },
+        State::default(),
+    );
+
+    harness
+        .get_all_by_role(egui::accesskit::Role::TextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+    for text in ["5", "5", "x", "5", "1", "2"] {
+        harness
+            .input_mut()
+            .events
+            .push(egui::Event::Text(text.to_owned()));
+        harness.run();
+    }
+    assert_eq!(harness.state().phone, "(555) 12");
+    assert_eq!(
+        harness.state().phone_error.as_deref(),
+        Some("Expected (___) ___-____")
+    );
+    harness.get_by_label("Expected (___) ___-____");
+
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Paste("34567890".to_owned()));
+    harness.run();
+    assert_eq!(harness.state().phone, "(555) 123-4567");
+    assert_eq!(harness.state().phone_error, None);
+
+    harness
+        .get_all_by_role(egui::accesskit::Role::TextInput)
+        .nth(1)
+        .unwrap()
+        .focus();
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Paste("$1,234.50".to_owned()));
+    harness.run();
+    assert_eq!(harness.state().amount, "1234.50");
+
+    // Virtualized text edits filter and validate too:
+    harness
+        .get_all_by_role(egui::accesskit::Role::MultilineTextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Paste("a1b2c3d4".to_owned()));
+    harness.run();
+    assert_eq!(harness.state().digits.as_str(), "1234");
+    assert_eq!(harness.state().digits_error.as_deref(), Some("Too long"));
+    harness.get_by_label("Too long");
+
+    // Newlines and IME text are filtered too:
+    harness.key_press(egui::Key::Enter);
+    for ime_event in [
+        egui::ImeEvent::Enabled,
+        egui::ImeEvent::Preedit("x9".to_owned()),
+        egui::ImeEvent::Commit("x9".to_owned()),
+    ] {
+        harness.input_mut().events.push(egui::Event::Ime(ime_event));
+        harness.run();
+    }
+    assert_eq!(harness.state().digits.as_str(), "12349");
+}
+
+#[test]
+fn mask_waits_for_ime_commit() {
+    let mut harness = Harness::new_ui_state(
+        |ui, phone: &mut String| {
+            ui.add(egui::TextEdit::singleline(phone).mask("(999) 999-9999"));
+        },
+        String::new(),
+    );
+    harness
+        .get_by_role(egui::accesskit::Role::TextInput)
+        .focus();
+    harness.run();
+
+    let ime = |harness: &mut Harness<'_, String>, ime_event| {
+        harness.input_mut().events.push(egui::Event::Ime(ime_event));
+        harness.run();
+    };
+    ime(&mut harness, egui::ImeEvent::Enabled);
+    ime(&mut harness, egui::ImeEvent::Preedit("5".to_owned()));
+    assert_eq!(harness.state(), "5", "The preedit is not formatted");
+    ime(&mut harness, egui::ImeEvent::Preedit("55".to_owned()));
+    assert_eq!(harness.state(), "55");
+    ime(&mut harness, egui::ImeEvent::Commit("555".to_owned()));
+    assert_eq!(harness.state(), "(555", "The committed text is formatted");
+}
+
+#[test]
+fn mask_is_ignored_when_virtualized() {
+    let mut harness = Harness::new_ui_state(
+        |ui, text: &mut egui::LineIndexedString| {
+            ui.add(egui::TextEdit::virtualized(text).mask("999"));
+        },
+        egui::LineIndexedString::default(),
+    );
+    harness
+        .get_by_role(egui::accesskit::Role::MultilineTextInput)
+        .focus();
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Paste("a1b2".to_owned()));
+    harness.run();
+    assert_eq!(harness.state().as_str(), "a1b2");
+}
+
+#[test]
+fn autocomplete() {
+    #[derive(Default)]
+    struct State {
+        city: String,
+        message: String,
+    }
+
+    let cities = vec!["Berlin", "Stockholm", "Stuttgart"];
+    let users = vec!["alice", "bob"];
+    let mut harness = Harness::new_ui_state(
+        |ui, state: &mut State| {
+            egui::Autocomplete::new("city", &cities)
+                .show(ui, &mut state.city, |text| egui::TextEdit::singleline(text));
+            egui::Autocomplete::new("message", &users)
+                .trigger('@')
+                .show(ui, &mut state.message, |text| {
+                    egui::TextEdit::multiline(text)
+                });
+        },
+        State::default(),
+    );
+
+    let type_text = |harness: &mut Harness<'_, State>, text: &str| {
+        harness
+            .input_mut()
+            .events
+            .push(egui::Event::Text(text.to_owned()));
+        harness.run();
+    };
+
+    harness
+        .get_all_by_role(egui::accesskit::Role::TextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+    type_text(&mut harness, "st");
+    harness.get_by_label("Stockholm");
+    harness.get_by_label("Stuttgart");
+    assert!(harness.query_by_label("Berlin").is_none());
+
+    harness.key_press(egui::Key::ArrowDown);
+    harness.key_press(egui::Key::Enter);
+    harness.run();
+    assert_eq!(harness.state().city, "Stuttgart");
+    assert!(harness.query_by_label("Stockholm").is_none());
+
+    harness
+        .get_all_by_role(egui::accesskit::Role::MultilineTextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+    type_text(&mut harness, "Hi ");
+    assert!(harness.query_by_label("alice").is_none());
+    type_text(&mut harness, "@b");
+    harness.get_by_label("bob").click();
+    harness.run();
+    assert_eq!(harness.state().message, "Hi @bob");
+
+    // The text edit keeps focus, so we can keep typing:
+    type_text(&mut harness, "!");
+    assert_eq!(harness.state().message, "Hi @bob!");
+
+    // Escape closes the popup without losing focus:
+    type_text(&mut harness, " @");
+    harness.get_by_label("alice");
+    harness.key_press(egui::Key::Escape);
+    harness.run();
+    assert!(harness.query_by_label("alice").is_none());
+    type_text(&mut harness, "a");
+    assert_eq!(harness.state().message, "Hi @bob! @a");
+}
+
+#[test]
+fn autocomplete_while_loading() {
+    let loading = |_: &str| -> Option<Vec<String>> { None };
+    let mut harness = Harness::new_ui_state(
+        |ui, message: &mut String| {
+            egui::Autocomplete::new("message", &loading)
+                .trigger('@')
+                .show(ui, message, |text| egui::TextEdit::multiline(text));
+        },
+        String::new(),
+    );
+
+    harness
+        .get_by_role(egui::accesskit::Role::MultilineTextInput)
+        .focus();
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text("@a".to_owned()));
+    // The spinner keeps repainting:
+    harness.step();
+    harness.step();
+
+    // There is nothing to accept yet, so Enter goes to the text edit:
+    harness.key_press(egui::Key::Enter);
+    harness.step();
+    assert_eq!(harness.state(), "@a\n");
+}
+
+#[test]
+fn rich_text_editing() {
+    let mut harness = Harness::new_ui_state(
+        |ui, documents: &mut [egui::RichTextDocument; 2]| {
+            for document in documents {
+                ui.add(egui::TextEdit::multiline(document));
+            }
+        },
+        [
+            egui::RichTextDocument::new("Hello world"),
+            egui::RichTextDocument::default(),
+        ],
+    );
+
+    harness
+        .get_all_by_role(egui::accesskit::Role::MultilineTextInput)
+        .next()
+        .unwrap()
+        .focus();
+    harness.run();
+
+    // Select "world" and make it bold:
+    harness.key_press(egui::Key::End);
+    for _ in 0..5 {
+        harness.key_press_modifiers(Modifiers::SHIFT, egui::Key::ArrowLeft);
+    }
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::B);
+    harness.run();
+    let bold: Vec<_> = harness.state()[0]
+        .spans()
+        .filter(|(_, attributes)| attributes.bold)
+        .map(|(range, _)| range)
+        .collect();
+    assert_eq!(bold, vec![6..11]);
+
+    // Formatting can be undone and redone:
+    let is_bold = |harness: &Harness<'_, [egui::RichTextDocument; 2]>| {
+        harness.state()[0]
+            .attributes_at(6)
+            .is_some_and(|attributes| attributes.bold)
+    };
+    harness.key_press_modifiers(Modifiers::COMMAND, egui::Key::Z);
+    harness.run();
+    assert!(!is_bold(&harness));
+    harness.key_press_modifiers(Modifiers::COMMAND | Modifiers::SHIFT, egui::Key::Z);
+    harness.run();
+    assert!(is_bold(&harness));
+
+    // Copy it, and paste it into the other document:
+    harness.input_mut().events.push(egui::Event::Copy);
+    harness.run();
+    harness
+        .get_all_by_role(egui::accesskit::Role::MultilineTextInput)
+        .nth(1)
+        .unwrap()
+        .focus();
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Paste("world".to_owned()));
+    harness.run();
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text("!".to_owned()));
+    harness.run();
+
+    let pasted = &harness.state()[1];
+    assert_eq!(pasted.text(), "world!");
+    assert!(
+        pasted
+            .attributes_at(5)
+            .is_some_and(|attributes| attributes.bold)
+    );
+}
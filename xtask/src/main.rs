@@ -5,6 +5,7 @@
 #![allow(clippy::exit)]
 
 mod deny;
+mod snapshots;
 pub(crate) mod utils;
 
 type DynError = Box<dyn std::error::Error>;
@@ -23,6 +24,7 @@ fn try_main() -> Result<(), DynError> {
     match args.as_slice() {
         &[] | &["-h"] | &["--help"] => print_help(),
         &["deny", ..] => deny::deny(&args[1..])?,
+        &["snapshots", ..] => snapshots::snapshots(&args[1..])?,
         c => Err(format!("Invalid arguments {c:?}"))?,
     }
     Ok(())
@@ -34,6 +36,9 @@ fn print_help() {
 
     Subcommands
     deny: Run cargo-deny for all targets
+    snapshots report: Write an HTML report of all failing egui_kittest snapshots to target/snapshot_report
+    snapshots accept <NAME>...: Accept the new images of the given failing snapshots
+    snapshots accept --all: Accept the new images of all failing snapshots
 
     Options
     -h, --help: print help and exit
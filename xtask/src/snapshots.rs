@@ -0,0 +1,316 @@
+//! Review failing `egui_kittest` snapshots.
+//!
+//! `egui_kittest` writes `{name}.new.png` next to every snapshot `{name}.png` it checks,
+//! and `{name}.diff.png` if they differ by more than the test allows. This collects those into an HTML report
+//! and accepts the new images as the references.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::DynError;
+
+const REPORT_DIR: &str = "target/snapshot_report";
+
+pub fn snapshots(args: &[&str]) -> Result<(), DynError> {
+    let root = workspace_root();
+    match args {
+        ["report"] => report(&root),
+        ["accept", "--all"] => {
+            let failures = find_failures(&root)?;
+            accept(&root, &failures)
+        }
+        ["accept", names @ ..] if !names.is_empty() => {
+            let failures = find_failures(&root)?;
+            let mut selected = Vec::new();
+            for name in names {
+                let name = name.trim_end_matches(".png");
+                let failure = failures
+                    .iter()
+                    .find(|failure| failure.name == name)
+                    .ok_or_else(|| format!("No failing snapshot named {name:?}"))?;
+                selected.push(failure.clone());
+            }
+            accept(&root, &selected)
+        }
+        _ => Err(format!("Invalid arguments: {args:?}").into()),
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask is in the workspace root")
+        .to_owned()
+}
+
+/// A snapshot whose last test run produced a different image.
+#[derive(Clone, Debug)]
+struct Failure {
+    /// The path of the snapshot relative to the workspace root, without the `.png` extension.
+    name: String,
+    reason: Reason,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Reason {
+    /// There is no reference image yet.
+    Missing,
+
+    /// The reference image can't be read, e.g. because git-lfs isn't set up.
+    Unreadable,
+
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+
+    /// The images differ, see the `.diff.png`.
+    Diff,
+}
+
+impl Reason {
+    fn description(self) -> String {
+        match self {
+            Self::Missing => "New snapshot".to_owned(),
+            Self::Unreadable => "Reference image is unreadable".to_owned(),
+            Self::SizeMismatch { expected, actual } => {
+                format!(
+                    "Size changed from {}x{} to {}x{}",
+                    expected.0, expected.1, actual.0, actual.1
+                )
+            }
+            Self::Diff => "Images differ".to_owned(),
+        }
+    }
+}
+
+impl Failure {
+    fn path(&self, root: &Path, suffix: &str) -> PathBuf {
+        root.join(format!("{}{suffix}", self.name))
+    }
+}
+
+/// Find all snapshots with a `.new.png` that doesn't match the reference.
+fn find_failures(root: &Path) -> Result<Vec<Failure>, DynError> {
+    let mut new_images = Vec::new();
+    find_new_images(root, &mut new_images)?;
+    new_images.sort();
+
+    let mut failures = Vec::new();
+    for new_path in new_images {
+        let relative = new_path
+            .strip_prefix(root)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let name = relative.trim_end_matches(".new.png").to_owned();
+        let failure = Failure {
+            name,
+            reason: Reason::Diff,
+        };
+
+        let reference = fs::read(failure.path(root, ".png")).ok();
+        let reason = match reference {
+            None => Reason::Missing,
+            Some(reference) => match (png_size(&reference), png_size(&fs::read(&new_path)?)) {
+                (None, _) => Reason::Unreadable,
+                (Some(expected), Some(actual)) if expected != actual => {
+                    Reason::SizeMismatch { expected, actual }
+                }
+                // Snapshots that pass don't get a diff, even if they differ a little:
+                _ if failure.path(root, ".diff.png").exists() => Reason::Diff,
+                _ => continue, // The last run matched the reference
+            },
+        };
+        failures.push(Failure { reason, ..failure });
+    }
+    Ok(failures)
+}
+
+fn find_new_images(dir: &Path, images: &mut Vec<PathBuf>) -> Result<(), DynError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if !file_name.starts_with('.') && file_name != "target" {
+                find_new_images(&path, images)?;
+            }
+        } else if file_name.ends_with(".new.png") {
+            images.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Read the size from the header of a png file.
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !png.starts_with(SIGNATURE) || png.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(png.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(png.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+/// Replace the reference images with the new ones.
+fn accept(root: &Path, failures: &[Failure]) -> Result<(), DynError> {
+    for failure in failures {
+        fs::rename(failure.path(root, ".new.png"), failure.path(root, ".png"))?;
+        fs::remove_file(failure.path(root, ".diff.png")).ok();
+        println!("Accepted {}", failure.name);
+    }
+    if failures.is_empty() {
+        println!("No failing snapshots");
+    }
+    Ok(())
+}
+
+/// Copy the images of all failures to [`REPORT_DIR`] and write an `index.html` showing them.
+fn report(root: &Path) -> Result<(), DynError> {
+    let failures = find_failures(root)?;
+
+    let report_dir = root.join(REPORT_DIR);
+    fs::remove_dir_all(&report_dir).ok();
+    fs::create_dir_all(report_dir.join("images"))?;
+
+    let mut entries = String::new();
+    for (index, failure) in failures.iter().enumerate() {
+        let mut images = Vec::new();
+        for (suffix, kind) in [(".png", "old"), (".new.png", "new"), (".diff.png", "diff")] {
+            let source = failure.path(root, suffix);
+            let target = format!("images/{index}_{kind}.png");
+            if fs::copy(&source, report_dir.join(&target)).is_ok() {
+                images.push((kind, target));
+            }
+        }
+        write_entry(&mut entries, failure, &images)?;
+    }
+
+    let html = TEMPLATE
+        .replace("$COUNT", &failures.len().to_string())
+        .replace("$ENTRIES", &entries);
+    let index_path = report_dir.join("index.html");
+    fs::write(&index_path, html)?;
+
+    println!(
+        "Found {} failing snapshot(s). Report written to {}",
+        failures.len(),
+        index_path.display()
+    );
+    Ok(())
+}
+
+fn write_entry(
+    html: &mut String,
+    failure: &Failure,
+    images: &[(&str, String)],
+) -> Result<(), DynError> {
+    let name = escape_html(&failure.name);
+    let image = |kind: &str| {
+        images
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, path)| format!(r#"<img src="{path}" alt="{kind}">"#))
+            .unwrap_or_else(|| format!(r#"<div class="missing">No {kind} image</div>"#))
+    };
+
+    writeln!(
+        html,
+        r#"<section class="snapshot" data-name="{name}">
+  <h2><label><input type="checkbox" class="select"> {name}</label></h2>
+  <p class="reason">{reason}</p>
+  <div class="views">
+    <button data-view="side-by-side" class="active">Side by side</button>
+    <button data-view="onion-skin">Onion skin</button>
+    <button data-view="diff">Diff</button>
+  </div>
+  <div class="view side-by-side">
+    <figure>{old}<figcaption>Reference</figcaption></figure>
+    <figure>{new}<figcaption>New</figcaption></figure>
+  </div>
+  <div class="view onion-skin hidden">
+    <div class="onion">{old}<div class="top">{new}</div></div>
+    <input type="range" min="0" max="100" value="50">
+  </div>
+  <div class="view diff hidden">{diff}</div>
+</section>"#,
+        reason = escape_html(&failure.reason.description()),
+        old = image("old"),
+        new = image("new"),
+        diff = image("diff"),
+    )?;
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Snapshot report</title>
+<style>
+  body { font-family: sans-serif; background: #1b1b1b; color: #ddd; margin: 2em; }
+  section { border-top: 1px solid #444; padding: 1em 0; }
+  h2 { font-size: 1em; font-family: monospace; }
+  .reason { color: #e8a33d; }
+  .views button { background: #333; color: #ddd; border: 1px solid #555; padding: 0.3em 0.8em; }
+  .views button.active { background: #555; }
+  .view { margin-top: 1em; }
+  .hidden { display: none; }
+  .side-by-side { display: flex; gap: 1em; align-items: flex-start; }
+  figure { margin: 0; }
+  img { max-width: 100%; image-rendering: pixelated; background: repeating-conic-gradient(#666 0% 25%, #888 0% 50%) 50% / 16px 16px; }
+  .onion { position: relative; display: inline-block; }
+  .onion .top { position: absolute; top: 0; left: 0; opacity: 0.5; }
+  .missing { color: #999; font-style: italic; padding: 1em; border: 1px dashed #555; }
+  #accept { position: sticky; top: 0; background: #1b1b1b; padding: 1em 0; }
+  #accept textarea { width: 100%; font-family: monospace; }
+</style>
+</head>
+<body>
+<h1>$COUNT failing snapshot(s)</h1>
+<div id="accept">
+  <label><input type="checkbox" id="select-all"> Select all</label>
+  <p>Run this to accept the selected snapshots:</p>
+  <textarea id="command" rows="3" readonly></textarea>
+</div>
+$ENTRIES
+<script>
+  const command = document.getElementById("command");
+  const checkboxes = [...document.querySelectorAll("input.select")];
+  function updateCommand() {
+    const names = checkboxes
+      .filter((checkbox) => checkbox.checked)
+      .map((checkbox) => checkbox.closest("section").dataset.name);
+    command.value = names.length ? "cargo xtask snapshots accept " + names.join(" ") : "";
+  }
+  checkboxes.forEach((checkbox) => checkbox.addEventListener("change", updateCommand));
+  document.getElementById("select-all").addEventListener("change", (event) => {
+    checkboxes.forEach((checkbox) => (checkbox.checked = event.target.checked));
+    updateCommand();
+  });
+  document.querySelectorAll("section.snapshot").forEach((section) => {
+    section.querySelectorAll(".views button").forEach((button) => {
+      button.addEventListener("click", () => {
+        section.querySelectorAll(".views button").forEach((b) => b.classList.remove("active"));
+        button.classList.add("active");
+        section.querySelectorAll(".view").forEach((view) => {
+          view.classList.toggle("hidden", !view.classList.contains(button.dataset.view));
+        });
+      });
+    });
+    const slider = section.querySelector(".onion-skin input");
+    const top = section.querySelector(".onion .top");
+    slider.addEventListener("input", () => (top.style.opacity = slider.value / 100));
+  });
+</script>
+</body>
+</html>
+"#;